@@ -2,112 +2,227 @@ package com.arula.terminal;
 
 import android.content.Context;
 import android.util.Log;
-import java.util.concurrent.ConcurrentLinkedQueue;
-import java.util.concurrent.atomic.AtomicBoolean;
+import com.arula.terminal.core.ArulaConfig;
+import com.arula.terminal.core.ArulaCore;
+import com.arula.terminal.core.BackgroundListener;
+import com.arula.terminal.core.ConversationInfo;
+import com.arula.terminal.core.ConversationMessage;
+import com.arula.terminal.core.StreamEvent;
+import com.arula.terminal.core.StreamListener;
+import java.util.Collections;
+import java.util.List;
 
 /**
- * JNI bridge to Rust core library
+ * Bridge to the Rust core through the UniFFI-generated bindings
+ * (package com.arula.terminal.core, generated by build_native.sh)
  */
 public class ArulaNative {
     private static final String TAG = "ArulaNative";
-    private static boolean initialized = false;
+    private static ArulaCore core;
     private static ArulaCallback callback;
-    private static final ConcurrentLinkedQueue<String> messageQueue = new ConcurrentLinkedQueue<>();
-    private static final AtomicBoolean processing = new AtomicBoolean(false);
+    private static final StringBuilder reply = new StringBuilder();
 
-    // Load native library
-    static {
+    private static final StreamListener LISTENER = ArulaNative::dispatch;
+
+    /**
+     * Initialize the Arula core with configuration
+     */
+    public static synchronized boolean initialize(Context context, ArulaConfig config) {
+        if (core != null) {
+            return true;
+        }
         try {
-            System.loadLibrary("arula_android");
-            Log.i(TAG, "Native library loaded successfully");
-        } catch (UnsatisfiedLinkError e) {
-            Log.e(TAG, "Failed to load native library", e);
+            core = new ArulaCore(config, context.getFilesDir().getAbsolutePath());
+            core.setListener(LISTENER);
+            Log.i(TAG, "Arula core initialized successfully");
+            return true;
+        } catch (Exception e) {
+            Log.e(TAG, "Failed to initialize Arula core", e);
+            return false;
         }
     }
 
     /**
-     * Initialize the Arula core with configuration
+     * Send a message to the AI; returns the session id or null on failure
      */
-    public static native boolean initialize(String configJson);
+    public static String sendMessage(String message) {
+        if (core == null) {
+            Log.e(TAG, "sendMessage called before initialize");
+            return null;
+        }
+        try {
+            return core.sendMessage(message);
+        } catch (Exception e) {
+            dispatchError(e.getMessage());
+            return null;
+        }
+    }
 
     /**
-     * Send a message to the AI
+     * Whether a reply is currently streaming
      */
-    public static native void sendMessage(String message);
+    public static boolean isStreaming() {
+        return core != null && core.isStreaming();
+    }
 
     /**
-     * Set configuration provider
+     * Route progress/completion of the running stream to a foreground service; null clears it
      */
-    public static native void setConfig(String configJson);
+    public static void setBackgroundListener(BackgroundListener listener) {
+        if (core != null) {
+            core.setBackgroundListener(listener);
+        }
+    }
 
     /**
-     * Get current configuration
+     * Update configuration
      */
-    public static native String getConfig();
+    public static void setConfig(ArulaConfig config) {
+        if (core == null) {
+            return;
+        }
+        try {
+            core.setConfig(config);
+        } catch (Exception e) {
+            Log.e(TAG, "Failed to update config", e);
+        }
+    }
 
     /**
-     * Cleanup resources
+     * Get current configuration, or null before initialization
      */
-    public static native void cleanup();
+    public static ArulaConfig getConfig() {
+        return core != null ? core.config() : null;
+    }
 
     /**
-     * Check if core is initialized
+     * Stop the running response
      */
-    public static boolean isInitialized() {
-        return initialized;
+    public static void cancel() {
+        if (core != null) {
+            core.cancel();
+        }
     }
 
     /**
-     * Set callback for receiving messages from Rust
+     * Saved conversations, most recent first
      */
-    public static void setCallback(ArulaCallback cb) {
-        callback = cb;
+    public static List<ConversationInfo> listConversations() {
+        if (core == null) {
+            return Collections.emptyList();
+        }
+        try {
+            return core.listConversations();
+        } catch (Exception e) {
+            Log.e(TAG, "Failed to list conversations", e);
+            return Collections.emptyList();
+        }
     }
 
     /**
-     * Called from Rust to deliver messages
+     * Load a saved conversation and make it current; returns its messages
      */
-    private static void onMessageReceived(String message) {
-        Log.d(TAG, "Message received from Rust: " + message);
-        if (callback != null) {
-            callback.onMessage(message);
+    public static List<ConversationMessage> loadConversation(String id) {
+        if (core == null) {
+            return Collections.emptyList();
+        }
+        try {
+            return core.loadConversation(id);
+        } catch (Exception e) {
+            Log.e(TAG, "Failed to load conversation " + id, e);
+            return Collections.emptyList();
         }
     }
 
     /**
-     * Called from Rust to deliver stream chunks
+     * Delete a saved conversation
      */
-    private static void onStreamChunk(String chunk) {
-        Log.d(TAG, "Stream chunk received from Rust: " + chunk);
-        if (callback != null) {
-            callback.onStreamChunk(chunk);
+    public static boolean deleteConversation(String id) {
+        if (core == null) {
+            return false;
+        }
+        try {
+            core.deleteConversation(id);
+            return true;
+        } catch (Exception e) {
+            Log.e(TAG, "Failed to delete conversation " + id, e);
+            return false;
         }
     }
 
     /**
-     * Called from Rust when a tool execution starts
+     * Start a new conversation
      */
-    private static void onToolStart(String toolName, String toolId) {
-        Log.d(TAG, "Tool started: " + toolName + " (" + toolId + ")");
-        if (callback != null) {
-            callback.onToolStart(toolName, toolId);
+    public static void newConversation() {
+        if (core != null) {
+            core.newConversation();
         }
     }
 
     /**
-     * Called from Rust when a tool execution completes
+     * Save a message sent or received outside the core into the current conversation
      */
-    private static void onToolComplete(String toolId, String result) {
-        Log.d(TAG, "Tool completed: " + toolId);
-        if (callback != null) {
-            callback.onToolComplete(toolId, result);
+    public static void recordMessage(String role, String content) {
+        if (core != null) {
+            core.recordMessage(role, content);
         }
     }
 
     /**
-     * Called from Rust for errors
+     * Cleanup resources
+     */
+    public static synchronized void cleanup() {
+        if (core != null) {
+            core.cancel();
+            core.close();
+            core = null;
+        }
+    }
+
+    /**
+     * Check if core is initialized
      */
-    private static void onError(String error) {
+    public static boolean isInitialized() {
+        return core != null;
+    }
+
+    /**
+     * Set callback for receiving messages from Rust
+     */
+    public static void setCallback(ArulaCallback cb) {
+        callback = cb;
+    }
+
+    /**
+     * Called from the core's event thread
+     */
+    private static void dispatch(StreamEvent event) {
+        ArulaCallback cb = callback;
+        if (cb == null) {
+            return;
+        }
+        cb.onEvent(event);
+
+        if (event instanceof StreamEvent.Started) {
+            reply.setLength(0);
+        } else if (event instanceof StreamEvent.Token) {
+            String text = ((StreamEvent.Token) event).getText();
+            reply.append(text);
+            cb.onStreamChunk(text);
+        } else if (event instanceof StreamEvent.ToolCallStart) {
+            StreamEvent.ToolCallStart start = (StreamEvent.ToolCallStart) event;
+            cb.onToolStart(start.getName(), start.getToolCallId());
+        } else if (event instanceof StreamEvent.ToolCallResult) {
+            StreamEvent.ToolCallResult result = (StreamEvent.ToolCallResult) event;
+            cb.onToolComplete(result.getName(), result.getSummary());
+        } else if (event instanceof StreamEvent.Finished) {
+            cb.onMessage(reply.toString());
+        } else if (event instanceof StreamEvent.Error) {
+            cb.onError(((StreamEvent.Error) event).getMessage());
+        }
+    }
+
+    private static void dispatchError(String error) {
         Log.e(TAG, "Error from Rust: " + error);
         if (callback != null) {
             callback.onError(error);
@@ -123,28 +238,10 @@ public class ArulaNative {
         void onToolStart(String toolName, String toolId);
         void onToolComplete(String toolId, String result);
         void onError(String error);
-    }
-
-    /**
-     * Initialize with Android context
-     */
-    public static boolean initializeWithContext(Context context, String configJson) {
-        if (!initialized) {
-            // Set up Android-specific paths and configuration
-            String androidConfig = enhanceConfigForAndroid(context, configJson);
-            initialized = initialize(androidConfig);
-            if (initialized) {
-                Log.i(TAG, "Arula core initialized successfully");
-            }
-        }
-        return initialized;
-    }
 
-    /**
-     * Enhance configuration with Android-specific settings
-     */
-    private static String enhanceConfigForAndroid(Context context, String configJson) {
-        // Add Android paths, Termux integration, etc.
-        return configJson; // TODO: Implement Android-specific config
+        /**
+         * Typed event stream; override to handle events without a dedicated callback
+         */
+        default void onEvent(StreamEvent event) {}
     }
-}
\ No newline at end of file
+}
@@ -3,8 +3,11 @@ package com.arula.terminal;
 import android.app.Notification;
 import android.app.NotificationChannel;
 import android.app.NotificationManager;
+import android.app.PendingIntent;
 import android.app.Service;
+import android.content.Context;
 import android.content.Intent;
+import android.content.pm.ServiceInfo;
 import android.os.Build;
 import android.os.Handler;
 import android.os.IBinder;
@@ -12,14 +15,32 @@ import android.os.Looper;
 import android.util.Log;
 import androidx.annotation.Nullable;
 import androidx.core.app.NotificationCompat;
+import androidx.core.content.ContextCompat;
+import com.arula.terminal.core.BackgroundListener;
 
 /**
- * Background service for AI processing
+ * Background service for AI processing.
+ *
+ * Also keeps a running stream alive as a foreground service while the app is
+ * backgrounded (ACTION_CONTINUE_STREAM), with progress and completion
+ * notifications driven by the core's BackgroundListener callbacks.
  */
-public class ArulaService extends Service implements ArulaNative.ArulaCallback {
+public class ArulaService extends Service implements ArulaNative.ArulaCallback, BackgroundListener {
     private static final String TAG = "ArulaService";
     private static final String CHANNEL_ID = "ArulaServiceChannel";
     private static final int NOTIFICATION_ID = 1;
+    private static final int COMPLETION_NOTIFICATION_ID = 2;
+
+    public static final String ACTION_CONTINUE_STREAM = "CONTINUE_STREAM";
+
+    /**
+     * Keep the running stream alive while the app is in the background
+     */
+    public static void continueInBackground(Context context) {
+        Intent intent = new Intent(context, ArulaService.class);
+        intent.setAction(ACTION_CONTINUE_STREAM);
+        ContextCompat.startForegroundService(context, intent);
+    }
 
     private Handler mainHandler;
     private boolean isInitialized = false;
@@ -44,6 +65,9 @@ public class ArulaService extends Service implements ArulaNative.ArulaCallback {
                 case "STOP_AI":
                     stopAiProcessing();
                     break;
+                case ACTION_CONTINUE_STREAM:
+                    continueStream();
+                    break;
                 case "SEND_MESSAGE":
                     String message = intent.getStringExtra("message");
                     if (message != null) {
@@ -78,12 +102,15 @@ public class ArulaService extends Service implements ArulaNative.ArulaCallback {
 
     private void initializeArula() {
         try {
-            // Load configuration
+            // Reuse the core when the activity already started it
+            if (ArulaNative.isInitialized()) {
+                isInitialized = true;
+                return;
+            }
             ArulaNative.setCallback(this);
 
-            // Default configuration for service
-            String config = createServiceConfig();
-            isInitialized = ArulaNative.initializeWithContext(this, config);
+            // Configuration comes from the app settings
+            isInitialized = ArulaNative.initialize(this, new SettingsManager(this).toNativeConfig());
 
             if (isInitialized) {
                 Log.i(TAG, "Arula service initialized successfully");
@@ -95,23 +122,28 @@ public class ArulaService extends Service implements ArulaNative.ArulaCallback {
         }
     }
 
-    private String createServiceConfig() {
-        return "{" +
-            "\"active_provider\": \"openai\"," +
-            "\"providers\": {" +
-            "\"openai\": {" +
-            "\"api_key\": \"" + System.getenv("OPENAI_API_KEY") + "\"," +
-            "\"api_url\": \"https://api.openai.com/v1\"," +
-            "\"model\": \"gpt-4\"," +
-            "\"max_tokens\": 4096," +
-            "\"temperature\": 0.7" +
-            "}" +
-            "}" +
-        "}";
+    private void continueStream() {
+        startInForeground("Finishing response...");
+        if (!ArulaNative.isStreaming()) {
+            // Finished between the activity stopping and the service starting
+            stopAiProcessing();
+            return;
+        }
+        ArulaNative.setBackgroundListener(this);
+        Log.i(TAG, "Continuing stream in background");
+    }
+
+    private void startInForeground(String text) {
+        Notification notification = createServiceNotification(text);
+        if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.Q) {
+            startForeground(NOTIFICATION_ID, notification, ServiceInfo.FOREGROUND_SERVICE_TYPE_DATA_SYNC);
+        } else {
+            startForeground(NOTIFICATION_ID, notification);
+        }
     }
 
     private void startAiProcessing() {
-        startForeground(NOTIFICATION_ID, createServiceNotification("Arula AI Ready"));
+        startInForeground("Arula AI Ready");
         Log.i(TAG, "AI processing started");
     }
 
@@ -135,10 +167,53 @@ public class ArulaService extends Service implements ArulaNative.ArulaCallback {
             .setContentTitle("Arula Terminal")
             .setContentText(text)
             .setSmallIcon(R.drawable.ic_terminal)
+            .setContentIntent(openAppIntent())
+            .setOnlyAlertOnce(true)
             .setOngoing(true)
             .build();
     }
 
+    private PendingIntent openAppIntent() {
+        Intent intent = new Intent(this, MainActivity.class);
+        intent.setFlags(Intent.FLAG_ACTIVITY_SINGLE_TOP | Intent.FLAG_ACTIVITY_CLEAR_TOP);
+        return PendingIntent.getActivity(this, 0, intent, PendingIntent.FLAG_IMMUTABLE);
+    }
+
+    /**
+     * Replaces the ongoing notification with a dismissable result and stops the service
+     */
+    private void finishBackgroundStream(String title, String text) {
+        ArulaNative.setBackgroundListener(null);
+        Notification notification = new NotificationCompat.Builder(this, CHANNEL_ID)
+            .setContentTitle(title)
+            .setContentText(text)
+            .setStyle(new NotificationCompat.BigTextStyle().bigText(text))
+            .setSmallIcon(R.drawable.ic_terminal)
+            .setContentIntent(openAppIntent())
+            .setAutoCancel(true)
+            .build();
+        NotificationManager manager = getSystemService(NotificationManager.class);
+        manager.notify(COMPLETION_NOTIFICATION_ID, notification);
+        stopAiProcessing();
+    }
+
+    // BackgroundListener implementations (called from the core's event thread)
+
+    @Override
+    public void onProgress(String sessionId, String status) {
+        mainHandler.post(() -> updateNotification(status));
+    }
+
+    @Override
+    public void onCompleted(String sessionId, String preview) {
+        mainHandler.post(() -> finishBackgroundStream("Response ready", preview));
+    }
+
+    @Override
+    public void onFailed(String sessionId, String message) {
+        mainHandler.post(() -> finishBackgroundStream("Response failed", message));
+    }
+
     private void updateNotification(String text) {
         Notification notification = createServiceNotification(text);
         NotificationManager manager = getSystemService(NotificationManager.class);
@@ -193,9 +268,8 @@ public class ArulaService extends Service implements ArulaNative.ArulaCallback {
     @Override
     public void onDestroy() {
         super.onDestroy();
-        if (isInitialized) {
-            ArulaNative.cleanup();
-        }
+        // The core is shared with the activity; only detach from it
+        ArulaNative.setBackgroundListener(null);
         Log.i(TAG, "Arula service destroyed");
     }
 }
\ No newline at end of file
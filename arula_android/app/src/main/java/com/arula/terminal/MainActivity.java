@@ -26,9 +26,14 @@ import androidx.lifecycle.ViewModelProvider;
 import androidx.recyclerview.widget.LinearLayoutManager;
 import androidx.recyclerview.widget.RecyclerView;
 import com.arula.terminal.databinding.ActivityMainBinding;
+import com.arula.terminal.core.ConversationInfo;
+import com.arula.terminal.core.ConversationMessage;
+import com.arula.terminal.core.StreamEvent;
 import com.google.android.material.snackbar.Snackbar;
+import androidx.appcompat.app.AlertDialog;
 import org.json.JSONException;
 import org.json.JSONObject;
+import java.util.ArrayList;
 import java.util.List;
 
 /**
@@ -148,8 +153,11 @@ public class MainActivity extends AppCompatActivity implements ArulaNative.Arula
             messageAdapter.setMessages(history);
         }
 
-        // Keep native callback for potential future use
+        // Native core keeps conversation history in the app data dir
         ArulaNative.setCallback(this);
+        if (settingsManager != null) {
+            ArulaNative.initialize(this, settingsManager.toNativeConfig());
+        }
     }
 
     private void updateApiClientFromSettings() {
@@ -181,6 +189,7 @@ public class MainActivity extends AppCompatActivity implements ArulaNative.Arula
         Message userMessage = new Message(message, Message.Type.USER);
         messageAdapter.addMessage(userMessage);
         viewModel.addMessage(userMessage);
+        ArulaNative.recordMessage("user", message);
 
         // Show typing indicator
         showTypingIndicator(true);
@@ -215,6 +224,7 @@ public class MainActivity extends AppCompatActivity implements ArulaNative.Arula
                     addedToAdapter[0] = true;
                 }
                 viewModel.addMessage(aiMessage);
+                ArulaNative.recordMessage("assistant", response);
                 scrollToBottom();
             }
 
@@ -252,6 +262,7 @@ public class MainActivity extends AppCompatActivity implements ArulaNative.Arula
                         messageAdapter.updateLastMessage();
                     }
                     viewModel.addMessage(aiMessage);
+                    ArulaNative.recordMessage("assistant", aiMessage.getText());
                     scrollToBottom();
                     return;
                 }
@@ -262,6 +273,7 @@ public class MainActivity extends AppCompatActivity implements ArulaNative.Arula
                     messageAdapter.addMessage(aiMessage);
                     addedToAdapter[0] = true;
                     viewModel.addMessage(aiMessage);
+                    ArulaNative.recordMessage("assistant", fullMessage);
                     scrollToBottom();
                 }
             }
@@ -325,6 +337,16 @@ public class MainActivity extends AppCompatActivity implements ArulaNative.Arula
         });
     }
 
+    @Override
+    public void onEvent(StreamEvent event) {
+        if (event instanceof StreamEvent.Retrying) {
+            // Transient: the stream continues after the retry
+            String status = ((StreamEvent.Retrying) event).getMessage();
+            mainHandler.post(() ->
+                    Snackbar.make(binding.coordinator, status, Snackbar.LENGTH_SHORT).show());
+        }
+    }
+
     @Override
     public void onError(String error) {
         mainHandler.post(() -> {
@@ -438,6 +460,9 @@ public class MainActivity extends AppCompatActivity implements ArulaNative.Arula
         if (id == R.id.action_settings) {
             openSettings();
             return true;
+        } else if (id == R.id.action_conversations) {
+            showConversations();
+            return true;
         } else if (id == R.id.action_clear) {
             clearConversation();
             return true;
@@ -457,6 +482,56 @@ public class MainActivity extends AppCompatActivity implements ArulaNative.Arula
     private void clearConversation() {
         viewModel.clearMessages();
         messageAdapter.clearMessages();
+        ArulaNative.newConversation();
+    }
+
+    /**
+     * Shows saved conversations; tap to open, long-press to delete
+     */
+    private void showConversations() {
+        List<ConversationInfo> conversations = ArulaNative.listConversations();
+        if (conversations.isEmpty()) {
+            showError("No saved conversations");
+            return;
+        }
+
+        String[] titles = new String[conversations.size()];
+        for (int i = 0; i < conversations.size(); i++) {
+            ConversationInfo info = conversations.get(i);
+            titles[i] = info.getTitle() + " (" + info.getMessageCount() + ")";
+        }
+
+        AlertDialog dialog = new AlertDialog.Builder(this)
+                .setTitle(R.string.action_conversations)
+                .setItems(titles, (d, which) -> openConversation(conversations.get(which)))
+                .setNegativeButton(android.R.string.cancel, null)
+                .create();
+        dialog.setOnShowListener(d -> dialog.getListView().setOnItemLongClickListener((parent, view, position, itemId) -> {
+            ConversationInfo info = conversations.get(position);
+            new AlertDialog.Builder(this)
+                    .setMessage("Delete \"" + info.getTitle() + "\"?")
+                    .setPositiveButton("Delete", (confirm, w) -> {
+                        if (ArulaNative.deleteConversation(info.getId())) {
+                            dialog.dismiss();
+                            showConversations();
+                        }
+                    })
+                    .setNegativeButton(android.R.string.cancel, null)
+                    .show();
+            return true;
+        }));
+        dialog.show();
+    }
+
+    private void openConversation(ConversationInfo info) {
+        List<Message> loaded = new ArrayList<>();
+        for (ConversationMessage m : ArulaNative.loadConversation(info.getId())) {
+            Message.Type type = "user".equals(m.getRole()) ? Message.Type.USER : Message.Type.ASSISTANT;
+            loaded.add(new Message(m.getContent(), type));
+        }
+        viewModel.replaceMessages(loaded);
+        messageAdapter.setMessages(loaded);
+        scrollToBottom();
     }
 
     private void exportConversation() {
@@ -505,10 +580,22 @@ public class MainActivity extends AppCompatActivity implements ArulaNative.Arula
         }
     }
 
+    @Override
+    protected void onStop() {
+        super.onStop();
+        // Hand a running reply to the foreground service so it survives backgrounding
+        if (ArulaNative.isStreaming() && !isChangingConfigurations()) {
+            ArulaService.continueInBackground(this);
+        }
+    }
+
     @Override
     protected void onDestroy() {
         super.onDestroy();
-        ArulaNative.cleanup();
+        // A backgrounded stream still needs the core; the service finishes it
+        if (!ArulaNative.isStreaming()) {
+            ArulaNative.cleanup();
+        }
         binding = null;
     }
 }
\ No newline at end of file
@@ -49,6 +49,16 @@ public class MainViewModel extends AndroidViewModel {
         saveMessages();
     }
 
+    /**
+     * Replaces the visible history, e.g. after opening a saved conversation
+     */
+    public void replaceMessages(List<Message> newMessages) {
+        messageList.clear();
+        messageList.addAll(newMessages);
+        messages.setValue(new ArrayList<>(messageList));
+        saveMessages();
+    }
+
     public void clearMessages() {
         messageList.clear();
         messages.setValue(new ArrayList<>(messageList));
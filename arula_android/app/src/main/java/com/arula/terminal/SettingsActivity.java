@@ -12,8 +12,6 @@ import androidx.preference.Preference;
 import androidx.preference.PreferenceFragmentCompat;
 import androidx.preference.PreferenceManager;
 import androidx.preference.SwitchPreferenceCompat;
-import org.json.JSONException;
-import org.json.JSONObject;
 
 /**
  * Settings activity for Arula configuration
@@ -241,32 +239,10 @@ public class SettingsActivity extends AppCompatActivity {
         }
 
         private void testConnection() {
-            // Build configuration JSON
-            JSONObject config = new JSONObject();
-            try {
-                config.put("active_provider", prefs.getString("active_provider", "openai"));
-                config.put("openai_api_key", prefs.getString("openai_api_key", ""));
-                config.put("openai_api_url", prefs.getString("openai_api_url", "https://api.openai.com/v1"));
-                config.put("openai_model", prefs.getString("openai_model", "gpt-4"));
-
-                config.put("anthropic_api_key", prefs.getString("anthropic_api_key", ""));
-                config.put("anthropic_api_url", prefs.getString("anthropic_api_url", "https://api.anthropic.com"));
-                config.put("anthropic_model", prefs.getString("anthropic_model", "claude-3-opus-20240229"));
-
-                config.put("zai_api_key", prefs.getString("zai_api_key", ""));
-                config.put("zai_api_url", prefs.getString("zai_api_url", "https://z.ai/api"));
-                config.put("zai_model", prefs.getString("zai_model", "glm-4"));
-
-                // Test with ArulaNative
-                ArulaNative.setConfig(config.toString());
-
-                // Show test message
-                // In a real implementation, this would test the actual connection
-                Log.i(TAG, "Configuration updated: " + config.toString());
-
-            } catch (JSONException e) {
-                Log.e(TAG, "Failed to build configuration", e);
-            }
+            // Push the current settings to the core; the next message uses them
+            SettingsManager settings = new SettingsManager(requireContext());
+            settings.syncToNative();
+            Log.i(TAG, "Configuration updated for provider: " + settings.getActiveProvider());
         }
 
         @Override
@@ -3,8 +3,7 @@ package com.arula.terminal;
 import android.content.Context;
 import android.content.SharedPreferences;
 import androidx.preference.PreferenceManager;
-import org.json.JSONException;
-import org.json.JSONObject;
+import com.arula.terminal.core.ArulaConfig;
 
 /**
  * Manages application settings and synchronizes with native core
@@ -190,59 +189,45 @@ public class SettingsManager {
         syncToNative();
     }
 
+    /**
+     * Builds the typed core configuration from the current settings
+     */
+    public ArulaConfig toNativeConfig() {
+        String apiUrl = getApiUrl();
+        return new ArulaConfig(
+            getActiveProvider(),
+            getModel(),
+            apiUrl.isEmpty() ? null : apiUrl,
+            getApiKey(),
+            getSystemPrompt(),
+            getMaxTokens(),
+            getTemperature()
+        );
+    }
+
     /**
      * Synchronizes all settings to the native core
      */
     public void syncToNative() {
-        try {
-            JSONObject config = new JSONObject();
-            config.put("active_provider", getActiveProvider());
-            config.put("api_key", getApiKey());
-            config.put("api_url", getApiUrl());
-            config.put("model", getModel());
-            config.put("system_prompt", getSystemPrompt());
-            config.put("temperature", getTemperature());
-            config.put("max_tokens", getMaxTokens());
-            config.put("streaming", isStreamingEnabled());
-            config.put("thinking_enabled", isThinkingEnabled());
-
-            ArulaNative.setConfig(config.toString());
-        } catch (JSONException e) {
-            android.util.Log.e(TAG, "Failed to sync config to native", e);
-        }
+        ArulaNative.setConfig(toNativeConfig());
     }
 
     /**
      * Loads settings from native core (if available)
      */
     public void loadFromNative() {
-        try {
-            String configJson = ArulaNative.getConfig();
-            if (configJson != null && !configJson.isEmpty()) {
-                JSONObject config = new JSONObject(configJson);
-
-                if (config.has("active_provider")) {
-                    setActiveProvider(config.getString("active_provider"));
-                }
-                if (config.has("api_key")) {
-                    setApiKey(config.getString("api_key"));
-                }
-                if (config.has("api_url")) {
-                    setApiUrl(config.getString("api_url"));
-                }
-                if (config.has("model")) {
-                    setModel(config.getString("model"));
-                }
-                if (config.has("temperature")) {
-                    setTemperature((float) config.getDouble("temperature"));
-                }
-                if (config.has("max_tokens")) {
-                    setMaxTokens(config.getInt("max_tokens"));
-                }
-            }
-        } catch (JSONException e) {
-            android.util.Log.e(TAG, "Failed to load config from native", e);
+        ArulaConfig config = ArulaNative.getConfig();
+        if (config == null) {
+            return;
+        }
+        setActiveProvider(config.getProvider());
+        setApiKey(config.getApiKey());
+        if (config.getApiUrl() != null) {
+            setApiUrl(config.getApiUrl());
         }
+        setModel(config.getModel());
+        setTemperature(config.getTemperature());
+        setMaxTokens(config.getMaxTokens());
     }
 
     /**
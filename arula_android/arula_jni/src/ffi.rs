@@ -0,0 +1,614 @@
+//! UniFFI interface for the Android (and iOS) apps
+//!
+//! Exposes a typed API over `arula_core`: `ArulaConfig` for configuration,
+//! `StreamEvent` for streaming updates and `ConversationInfo`/`ConversationMessage`
+//! for saved conversations. Kotlin and Swift bindings are generated from this
+//! module with `uniffi-bindgen` (see `build_native.sh`), replacing the old
+//! hand-written `extern "C"` JNI exports.
+
+use arula_core::api::api::ChatMessage;
+use arula_core::utils::config::Config;
+use arula_core::{ConversationManager, SessionConfig, SessionManager, UiEvent};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Length of the reply preview passed to `BackgroundListener::on_completed`
+const REPLY_PREVIEW_CHARS: usize = 120;
+
+/// Default system prompt used when the app does not provide one
+const DEFAULT_SYSTEM_PROMPT: &str = "You are ARULA, an AI coding assistant running on Android.";
+
+/// Errors surfaced to the app
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum ArulaError {
+    #[error("Invalid configuration: {message}")]
+    Config { message: String },
+    #[error("Session error: {message}")]
+    Session { message: String },
+    #[error("Storage error: {message}")]
+    Storage { message: String },
+}
+
+/// Provider configuration passed from the app
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ArulaConfig {
+    pub provider: String,
+    pub model: String,
+    /// Custom endpoint; `None` uses the provider default
+    pub api_url: Option<String>,
+    pub api_key: String,
+    pub system_prompt: Option<String>,
+    /// Signed so the Java side can use plain `int`
+    pub max_tokens: i32,
+    pub temperature: f32,
+}
+
+impl ArulaConfig {
+    fn to_core(&self) -> Result<Config, ArulaError> {
+        let mut config = Config::default();
+        config
+            .switch_provider(&self.provider)
+            .map_err(|e| ArulaError::Config { message: e.to_string() })?;
+        config.set_model(&self.model);
+        config.set_api_key(&self.api_key);
+        if let Some(url) = self.api_url.as_deref().filter(|u| !u.trim().is_empty()) {
+            config.set_api_url(url);
+        }
+        Ok(config)
+    }
+
+    fn session_config(&self) -> SessionConfig {
+        SessionConfig {
+            system_prompt: self
+                .system_prompt
+                .clone()
+                .filter(|p| !p.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string()),
+            model: self.model.clone(),
+            max_tokens: self.max_tokens.max(1) as u32,
+            temperature: self.temperature,
+            sampling: Default::default(),
+            ignore_budget: false,
+            budget_usage: None,
+        }
+    }
+}
+
+/// Streaming update delivered to `StreamListener::on_event`
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum StreamEvent {
+    Started {
+        session_id: String,
+    },
+    Token {
+        session_id: String,
+        text: String,
+        is_final: bool,
+    },
+    Thinking {
+        session_id: String,
+        text: String,
+    },
+    ToolCallStart {
+        session_id: String,
+        tool_call_id: String,
+        name: String,
+        display: String,
+    },
+    ToolCallResult {
+        session_id: String,
+        name: String,
+        success: bool,
+        summary: String,
+    },
+    BashOutput {
+        session_id: String,
+        tool_call_id: String,
+        line: String,
+        is_stderr: bool,
+    },
+    Question {
+        session_id: String,
+        tool_call_id: String,
+        question: String,
+        options: Vec<String>,
+    },
+    Usage {
+        session_id: String,
+        prompt_tokens: u32,
+        completion_tokens: u32,
+    },
+    /// Why the model stopped; `notice` is set when the answer is incomplete
+    FinishReason {
+        session_id: String,
+        reason: String,
+        notice: Option<String>,
+    },
+    BudgetExceeded {
+        session_id: String,
+        message: String,
+    },
+    Finished {
+        session_id: String,
+    },
+    Error {
+        session_id: String,
+        message: String,
+    },
+    /// A request failed and is retried after a delay; the stream goes on
+    Retrying {
+        session_id: String,
+        message: String,
+    },
+    ConversationTitle {
+        title: String,
+    },
+    ConversationStarters {
+        starters: Vec<String>,
+    },
+}
+
+impl StreamEvent {
+    /// Maps a core UI event; user/AI message records, file snapshots, web
+    /// sources and tool rounds have no streaming counterpart.
+    fn from_ui_event(event: UiEvent) -> Option<Self> {
+        Some(match event {
+            UiEvent::UserMessage { .. } | UiEvent::AiMessage { .. } => return None,
+            UiEvent::FileSnapshot(..) | UiEvent::Sources(..) | UiEvent::ToolRound(..) => return None,
+            UiEvent::StreamStarted(id) => StreamEvent::Started {
+                session_id: id.to_string(),
+            },
+            UiEvent::Token(id, text, is_final) => StreamEvent::Token {
+                session_id: id.to_string(),
+                text,
+                is_final,
+            },
+            UiEvent::Thinking(id, text) => StreamEvent::Thinking {
+                session_id: id.to_string(),
+                text,
+            },
+            UiEvent::ToolCallStart(id, tool_call_id, name, display) => StreamEvent::ToolCallStart {
+                session_id: id.to_string(),
+                tool_call_id,
+                name,
+                display,
+            },
+            UiEvent::ToolCallResult(id, name, success, summary) => StreamEvent::ToolCallResult {
+                session_id: id.to_string(),
+                name,
+                success,
+                summary,
+            },
+            UiEvent::BashOutputLine(id, tool_call_id, line, is_stderr) => StreamEvent::BashOutput {
+                session_id: id.to_string(),
+                tool_call_id,
+                line,
+                is_stderr,
+            },
+            UiEvent::AskQuestion {
+                session_id,
+                tool_call_id,
+                question,
+                options,
+            } => StreamEvent::Question {
+                session_id: session_id.to_string(),
+                tool_call_id,
+                question,
+                options: options.unwrap_or_default(),
+            },
+            UiEvent::Usage(id, usage) => StreamEvent::Usage {
+                session_id: id.to_string(),
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+            },
+            UiEvent::FinishReason(id, reason) => StreamEvent::FinishReason {
+                session_id: id.to_string(),
+                reason: reason.label().to_string(),
+                notice: reason.notice(),
+            },
+            UiEvent::BudgetExceeded(id, message) => StreamEvent::BudgetExceeded {
+                session_id: id.to_string(),
+                message,
+            },
+            UiEvent::StreamFinished(id) => StreamEvent::Finished {
+                session_id: id.to_string(),
+            },
+            UiEvent::StreamErrored(id, message) => StreamEvent::Error {
+                session_id: id.to_string(),
+                message,
+            },
+            UiEvent::StreamRetrying(id, message) => StreamEvent::Retrying {
+                session_id: id.to_string(),
+                message,
+            },
+            UiEvent::ConversationTitle(title) => StreamEvent::ConversationTitle { title },
+            UiEvent::ConversationStarters(starters) => {
+                StreamEvent::ConversationStarters { starters }
+            }
+        })
+    }
+}
+
+/// Implemented by the app to receive streaming updates.
+///
+/// Called from a background thread; implementations must post to the UI thread themselves.
+#[uniffi::export(with_foreign)]
+pub trait StreamListener: Send + Sync {
+    fn on_event(&self, event: StreamEvent);
+}
+
+/// Implemented by the foreground service that keeps a stream alive while the app
+/// is in the background; receives coarse progress suitable for notifications.
+#[uniffi::export(with_foreign)]
+pub trait BackgroundListener: Send + Sync {
+    /// Status line for the ongoing notification (e.g. "Running: Shell")
+    fn on_progress(&self, session_id: String, status: String);
+    /// The reply finished; `preview` is the start of the response
+    fn on_completed(&self, session_id: String, preview: String);
+    fn on_failed(&self, session_id: String, message: String);
+}
+
+/// Summary of a saved conversation
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ConversationInfo {
+    pub id: String,
+    pub title: String,
+    /// RFC 3339 timestamp
+    pub updated_at: String,
+    pub message_count: i32,
+    pub model: String,
+}
+
+/// A user or assistant message of a saved conversation
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ConversationMessage {
+    /// "user" or "assistant"
+    pub role: String,
+    pub content: String,
+    pub timestamp: String,
+}
+
+/// The current conversation as core UI events, in the format `ConversationManager` stores
+struct Transcript {
+    id: Uuid,
+    events: Vec<UiEvent>,
+}
+
+impl Transcript {
+    fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            events: Vec::new(),
+        }
+    }
+
+    fn push_message(&mut self, role: &str, content: String) {
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        self.events.push(if role == "user" {
+            UiEvent::UserMessage { content, timestamp }
+        } else {
+            UiEvent::AiMessage { content, timestamp }
+        });
+    }
+
+    fn set_title(&mut self, title: String) {
+        self.events
+            .retain(|e| !matches!(e, UiEvent::ConversationTitle(_)));
+        self.events.insert(0, UiEvent::ConversationTitle(title));
+    }
+
+    fn messages(&self) -> Vec<ConversationMessage> {
+        self.events
+            .iter()
+            .filter_map(|event| match event {
+                UiEvent::UserMessage { content, timestamp } => Some(ConversationMessage {
+                    role: "user".to_string(),
+                    content: content.clone(),
+                    timestamp: timestamp.clone(),
+                }),
+                UiEvent::AiMessage { content, timestamp } => Some(ConversationMessage {
+                    role: "assistant".to_string(),
+                    content: content.clone(),
+                    timestamp: timestamp.clone(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Chat history sent with the next prompt
+    fn history(&self) -> Vec<ChatMessage> {
+        self.messages()
+            .into_iter()
+            .map(|m| ChatMessage {
+                role: m.role,
+                content: Some(m.content),
+                tool_calls: None,
+                tool_call_id: None,
+                tool_name: None,
+                images: Vec::new(),
+            })
+            .collect()
+    }
+
+    fn save(&self, conversations: &ConversationManager, model: String) {
+        if let Err(e) = conversations.save_conversation(self.id, &self.events, model) {
+            log::error!("Failed to save conversation {}: {}", self.id, e);
+        }
+    }
+}
+
+/// Entry point for the apps: owns the session manager and conversation storage.
+///
+/// Conversations are saved after every completed reply, in the same format as
+/// the desktop app, so history can be listed, reopened and deleted.
+#[derive(uniffi::Object)]
+pub struct ArulaCore {
+    manager: Mutex<SessionManager>,
+    config: Arc<Mutex<ArulaConfig>>,
+    conversations: Arc<ConversationManager>,
+    transcript: Arc<Mutex<Transcript>>,
+    listener: Arc<Mutex<Option<Arc<dyn StreamListener>>>>,
+    background_listener: Arc<Mutex<Option<Arc<dyn BackgroundListener>>>>,
+    current_session: Mutex<Option<Uuid>>,
+    streaming: Arc<AtomicBool>,
+}
+
+#[uniffi::export]
+impl ArulaCore {
+    /// Create the core; conversations are stored under `data_dir/conversations`.
+    #[uniffi::constructor]
+    pub fn new(config: ArulaConfig, data_dir: String) -> Result<Arc<Self>, ArulaError> {
+        #[cfg(target_os = "android")]
+        android_logger::init_once(
+            android_logger::Config::default()
+                .with_max_level(log::LevelFilter::Info)
+                .with_tag("ArulaCore"),
+        );
+
+        let manager = SessionManager::new(&config.to_core()?)
+            .map_err(|e| ArulaError::Session { message: e.to_string() })?;
+        let conversations =
+            ConversationManager::with_storage_dir(std::path::Path::new(&data_dir).join("conversations"))
+                .map_err(|e| ArulaError::Storage { message: e.to_string() })?;
+
+        let core = Arc::new(Self {
+            manager: Mutex::new(manager),
+            config: Arc::new(Mutex::new(config)),
+            conversations: Arc::new(conversations),
+            transcript: Arc::new(Mutex::new(Transcript::new())),
+            listener: Arc::new(Mutex::new(None)),
+            background_listener: Arc::new(Mutex::new(None)),
+            current_session: Mutex::new(None),
+            streaming: Arc::new(AtomicBool::new(false)),
+        });
+        core.spawn_event_forwarder();
+        log::info!("Arula core initialized");
+        Ok(core)
+    }
+
+    /// Register the listener that receives `StreamEvent`s
+    pub fn set_listener(&self, listener: Arc<dyn StreamListener>) {
+        *self.listener.lock().unwrap() = Some(listener);
+    }
+
+    /// Register (or clear) the listener used while the app is backgrounded
+    pub fn set_background_listener(&self, listener: Option<Arc<dyn BackgroundListener>>) {
+        *self.background_listener.lock().unwrap() = listener;
+    }
+
+    /// Whether a reply is currently streaming
+    pub fn is_streaming(&self) -> bool {
+        self.streaming.load(Ordering::SeqCst)
+    }
+
+    /// Replace the configuration and rebuild the backend
+    pub fn set_config(&self, config: ArulaConfig) -> Result<(), ArulaError> {
+        self.manager
+            .lock()
+            .unwrap()
+            .update_backend(&config.to_core()?)
+            .map_err(|e| ArulaError::Session { message: e.to_string() })?;
+        *self.config.lock().unwrap() = config;
+        Ok(())
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> ArulaConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Send a prompt in the current conversation; returns the session id
+    pub fn send_message(&self, message: String) -> Result<String, ArulaError> {
+        let session_id = Uuid::new_v4();
+        let session_config = self.config.lock().unwrap().session_config();
+
+        let history = {
+            let mut transcript = self.transcript.lock().unwrap();
+            let previous = transcript.history();
+            transcript.push_message("user", message.clone());
+            previous
+        };
+
+        self.manager
+            .lock()
+            .unwrap()
+            .start_stream(session_id, message, Some(history), session_config)
+            .map_err(|e| ArulaError::Session { message: e.to_string() })?;
+        *self.current_session.lock().unwrap() = Some(session_id);
+        self.streaming.store(true, Ordering::SeqCst);
+        Ok(session_id.to_string())
+    }
+
+    /// Append a message produced outside the core (e.g. by the app's own HTTP
+    /// client) to the current conversation and save it
+    pub fn record_message(&self, role: String, content: String) {
+        let mut transcript = self.transcript.lock().unwrap();
+        transcript.push_message(&role, content);
+        transcript.save(&self.conversations, self.config.lock().unwrap().model.clone());
+    }
+
+    /// Stop the running stream, if any
+    pub fn cancel(&self) {
+        if let Some(session_id) = self.current_session.lock().unwrap().take() {
+            self.manager.lock().unwrap().stop_stream(session_id);
+        }
+        self.streaming.store(false, Ordering::SeqCst);
+    }
+
+    /// Start a fresh conversation
+    pub fn new_conversation(&self) {
+        self.cancel();
+        *self.transcript.lock().unwrap() = Transcript::new();
+    }
+
+    /// Id of the conversation new messages are saved to
+    pub fn current_conversation_id(&self) -> String {
+        self.transcript.lock().unwrap().id.to_string()
+    }
+
+    /// Saved conversations, most recent first
+    pub fn list_conversations(&self) -> Result<Vec<ConversationInfo>, ArulaError> {
+        let conversations = self
+            .conversations
+            .list_conversations()
+            .map_err(|e| ArulaError::Storage { message: e.to_string() })?;
+        Ok(conversations
+            .into_iter()
+            .map(|c| ConversationInfo {
+                id: c.id.to_string(),
+                title: c.title,
+                updated_at: c.updated_at.to_rfc3339(),
+                message_count: c.message_count as i32,
+                model: c.model,
+            })
+            .collect())
+    }
+
+    /// Load a saved conversation and make it the current one
+    pub fn load_conversation(&self, id: String) -> Result<Vec<ConversationMessage>, ArulaError> {
+        let id = parse_id(&id)?;
+        let saved = self
+            .conversations
+            .load_conversation(id)
+            .map_err(|e| ArulaError::Storage { message: e.to_string() })?;
+
+        self.cancel();
+        let transcript = Transcript {
+            id,
+            events: saved.events,
+        };
+        let messages = transcript.messages();
+        *self.transcript.lock().unwrap() = transcript;
+        Ok(messages)
+    }
+
+    /// Delete a saved conversation; deleting the current one starts a fresh conversation
+    pub fn delete_conversation(&self, id: String) -> Result<(), ArulaError> {
+        let id = parse_id(&id)?;
+        self.conversations
+            .delete_conversation(id)
+            .map_err(|e| ArulaError::Storage { message: e.to_string() })?;
+        if self.transcript.lock().unwrap().id == id {
+            self.new_conversation();
+        }
+        Ok(())
+    }
+}
+
+impl ArulaCore {
+    /// Forward core UI events to the listeners and save completed replies
+    fn spawn_event_forwarder(&self) {
+        let mut rx = self.manager.lock().unwrap().subscribe();
+        let listener = self.listener.clone();
+        let background_listener = self.background_listener.clone();
+        let streaming = self.streaming.clone();
+        let transcript = self.transcript.clone();
+        let conversations = self.conversations.clone();
+        let config = self.config.clone();
+
+        std::thread::spawn(move || {
+            let mut reply = String::new();
+            loop {
+                let event = match rx.blocking_recv() {
+                    Ok(event) => event,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("Dropped {} stream events", skipped);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+
+                let background = background_listener.lock().unwrap().clone();
+                match &event {
+                    UiEvent::StreamStarted(id) => {
+                        reply.clear();
+                        if let Some(background) = &background {
+                            background.on_progress(id.to_string(), "Thinking…".to_string());
+                        }
+                    }
+                    UiEvent::Token(id, text, _) => {
+                        // Only report the switch to writing, not every token
+                        if reply.is_empty() {
+                            if let Some(background) = &background {
+                                background.on_progress(id.to_string(), "Writing response…".to_string());
+                            }
+                        }
+                        reply.push_str(text);
+                    }
+                    UiEvent::ToolCallStart(id, _, name, _) => {
+                        if let Some(background) = &background {
+                            background.on_progress(id.to_string(), format!("Running: {}", name));
+                        }
+                    }
+                    UiEvent::StreamFinished(id) => {
+                        streaming.store(false, Ordering::SeqCst);
+                        if let Some(background) = &background {
+                            background.on_completed(
+                                id.to_string(),
+                                reply.chars().take(REPLY_PREVIEW_CHARS).collect(),
+                            );
+                        }
+                        let mut transcript = transcript.lock().unwrap();
+                        if !reply.is_empty() {
+                            transcript.push_message("assistant", std::mem::take(&mut reply));
+                        }
+                        transcript.save(&conversations, config.lock().unwrap().model.clone());
+                    }
+                    UiEvent::StreamErrored(id, message) => {
+                        streaming.store(false, Ordering::SeqCst);
+                        if let Some(background) = &background {
+                            background.on_failed(id.to_string(), message.clone());
+                        }
+                    }
+                    UiEvent::StreamRetrying(id, message) => {
+                        if let Some(background) = &background {
+                            background.on_progress(id.to_string(), message.clone());
+                        }
+                    }
+                    UiEvent::ConversationTitle(title) => {
+                        let mut transcript = transcript.lock().unwrap();
+                        transcript.set_title(title.clone());
+                        transcript.save(&conversations, config.lock().unwrap().model.clone());
+                    }
+                    _ => {}
+                }
+
+                let Some(event) = StreamEvent::from_ui_event(event) else {
+                    continue;
+                };
+                let listener = listener.lock().unwrap().clone();
+                if let Some(listener) = listener {
+                    listener.on_event(event);
+                }
+            }
+        });
+    }
+}
+
+fn parse_id(id: &str) -> Result<Uuid, ArulaError> {
+    Uuid::parse_str(id).map_err(|e| ArulaError::Storage {
+        message: format!("Invalid conversation id {}: {}", id, e),
+    })
+}
@@ -1,12 +1,10 @@
 #![allow(dead_code)]
 #![allow(private_interfaces)]
 
-use jni::JNIEnv;
-use jni::objects::{JClass, JString};
+uniffi::setup_scaffolding!();
 
+pub mod ffi;
 pub mod platform;
 
-// Export for JNI
+pub use ffi::*;
 pub use platform::android::*;
-
-
@@ -1,7 +1,6 @@
 //! Android-specific platform implementations
 
-use anyhow::Result;
-use jni::{JNIEnv, objects::{JClass, JString, JObject}, sys::jobject};
+use jni::sys::jobject;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -91,93 +90,7 @@ impl AndroidPlatform {
     }
 }
 
-/// JNI exports for Android integration
-#[no_mangle]
-pub extern "C" fn Java_com_arula_terminal_ArulaNative_initialize<'local>(
-    mut env: JNIEnv<'local>,
-    _class: JClass<'local>,
-    config_json: JString<'local>,
-) -> bool {
-    let config_str: String = match env.get_string(&config_json) {
-        Ok(s) => s.into(),
-        Err(e) => {
-            log::error!("Failed to get config string: {:?}", e);
-            return false;
-        }
-    };
-
-    // Initialize android logger
-    #[cfg(target_os = "android")]
-    android_logger::init_once(
-        android_logger::Config::default()
-            .with_max_level(log::LevelFilter::Info)
-            .with_tag("ArulaCore"),
-    );
-
-    log::info!("Arula Android Core initialized with config: {}", config_str);
-    true
-}
-
-#[no_mangle]
-pub extern "C" fn Java_com_arula_terminal_ArulaNative_sendMessage<'local>(
-    mut env: JNIEnv<'local>,
-    _class: JClass<'local>,
-    message: JString<'local>,
-) {
-    // Send message to AI
-    match env.get_string(&message) {
-        Ok(msg) => {
-            let msg_str: String = msg.into();
-            log::info!("Sending message: {}", msg_str);
-        }
-        Err(e) => {
-            log::error!("Failed to get message string: {:?}", e);
-        }
-    }
-}
-
-#[no_mangle]
-pub extern "C" fn Java_com_arula_terminal_ArulaNative_setConfig<'local>(
-    _env: JNIEnv<'local>,
-    _class: JClass<'local>,
-    _config_json: JString<'local>,
-) {
-    // Update configuration
-}
-
-#[no_mangle]
-pub extern "C" fn Java_com_arula_terminal_ArulaNative_getConfig<'local>(
-    mut env: JNIEnv<'local>,
-    _class: JClass<'local>,
-) -> JString<'local> {
-    // Return current configuration
-    let config = "{}";
-    match env.new_string(config) {
-        Ok(s) => s,
-        Err(_) => JString::default(),
-    }
-}
-
-#[no_mangle]
-pub extern "C" fn Java_com_arula_terminal_ArulaNative_cleanup<'local>(
-    _env: JNIEnv<'local>,
-    _class: JClass<'local>,
-) {
-    // Cleanup resources
-    log::info!("Android Arula cleanup");
-}
-
-#[no_mangle]
-pub extern "C" fn Java_com_arula_terminal_ArulaNative_setCallback<'local>(
-    _env: JNIEnv<'local>,
-    _class: JClass<'local>,
-    _callback: JObject<'local>,
-) {
-    // Store callback for later use
-    log::info!("Setting Android callback");
-}
-
-/// Callback functions from Rust to Java
+/// Platform notifications; forwarded to the log until they get a `StreamEvent` counterpart
 pub mod callbacks {
     pub fn on_message(message: &str) {
         // Call Java callback
@@ -87,6 +87,23 @@ build_for_target "armv7-linux-androideabi" "armeabi-v7a"
 build_for_target "i686-linux-android" "x86"
 build_for_target "x86_64-linux-android" "x86_64"
 
+# Generate Kotlin bindings (com.arula.terminal.core) from the built library
+echo "Generating Kotlin bindings..."
+cargo run --bin uniffi-bindgen -- generate \
+    --library "target/aarch64-linux-android/release/libarula_android.so" \
+    --language kotlin \
+    --out-dir "$SCRIPT_DIR/app/src/main/java"
+echo "✓ Kotlin bindings generated"
+
+# Swift bindings for an iOS build: pass --swift
+if [ "$1" = "--swift" ]; then
+    cargo run --bin uniffi-bindgen -- generate \
+        --library "target/aarch64-linux-android/release/libarula_android.so" \
+        --language swift \
+        --out-dir "$SCRIPT_DIR/bindings/swift"
+    echo "✓ Swift bindings generated in bindings/swift"
+fi
+
 echo ""
 echo "✓ All builds complete!"
 echo "Native libraries installed to: $JNI_LIBS_DIR"
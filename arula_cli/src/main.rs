@@ -2,8 +2,8 @@
 #![allow(unreachable_code)]
 #![allow(private_interfaces)]
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(name = "arula")]
@@ -20,14 +20,324 @@ struct Cli {
     /// Enable debug mode
     #[arg(short, long)]
     debug: bool,
+
+    /// Log level or filter for ~/.arula/logs (error, warn, info, debug, trace)
+    #[arg(long, value_name = "LEVEL")]
+    log_level: Option<String>,
+
+    /// Record raw provider requests and responses (redacted) to ~/.arula/captures
+    #[arg(long)]
+    capture_http: bool,
+
+    /// Let file tools access a path outside the working directory (repeatable)
+    #[arg(long = "allow-path", value_name = "PATH")]
+    allow_paths: Vec<PathBuf>,
+
+    /// Persona (system prompt preset) to chat as; built-in or from `personas` in the config
+    #[arg(long, value_name = "NAME")]
+    persona: Option<String>,
+
+    /// Answer PROMPT with the agent and its tools, print the answer and exit
+    #[arg(short = 'p', long = "print", value_name = "PROMPT")]
+    print: Option<String>,
+
+    /// Let `-p` run commands and write files; without it they are refused
+    #[arg(long, requires = "print")]
+    yes: bool,
+
+    /// Print without colors (also set by the NO_COLOR environment variable)
+    #[arg(long)]
+    no_color: bool,
+
+    /// `json` prints command results as JSON and progress as one JSON object
+    /// per line on stderr
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Restore files changed by the AI during the last turn
+    Rollback {
+        /// Turn to roll back instead of the most recent one
+        #[arg(long, value_name = "ID")]
+        turn: Option<String>,
+    },
+    /// Serve an OpenAI-compatible API backed by Arula and its tools
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to bind; anything other than localhost exposes your tools
+        #[arg(long, default_value = "127.0.0.1")]
+        host: std::net::IpAddr,
+
+        /// Require clients to send this bearer token
+        #[arg(long, value_name = "KEY")]
+        api_key: Option<String>,
+
+        /// Also serve the WebSocket event API on this port (token: --api-key)
+        #[arg(long, value_name = "PORT")]
+        ws_port: Option<u16>,
+    },
+    /// Run as an Agent Client Protocol agent over stdio (for editor agent panels)
+    Acp,
+    /// Inspect or clear the local response cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+    /// Compare time-to-first-token, speed and cost across configured providers
+    Bench {
+        /// Prompt to send instead of the standard one
+        #[arg(long)]
+        prompt: Option<String>,
+
+        /// Only benchmark these providers (repeatable)
+        #[arg(long = "provider", value_name = "NAME")]
+        providers: Vec<String>,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write a Conventional Commits message for the staged changes and commit
+    Commit {
+        /// Commit without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Only print the message (e.g. for `git commit -m "$(arula commit --print)"`)
+        #[arg(long, conflicts_with = "yes")]
+        print: bool,
+    },
+    /// Review a diff and report findings by file, line and severity
+    Review {
+        /// Branch or ref to compare against (changes since the merge base), or
+        /// a range like main..feature; defaults to uncommitted changes
+        #[arg(value_name = "REF", conflicts_with = "staged")]
+        reference: Option<String>,
+
+        /// Review staged changes only
+        #[arg(long)]
+        staged: bool,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run a prompt over every file matching a glob, several files at a time
+    Map {
+        /// Files to process, relative to the working directory
+        #[arg(long, value_name = "PATTERN")]
+        glob: String,
+
+        /// Instruction applied to each file
+        #[arg(long)]
+        prompt: String,
+
+        /// Directory the answers are written to, mirroring the file paths
+        #[arg(long, value_name = "DIR", required_unless_present = "in_place")]
+        out: Option<PathBuf>,
+
+        /// Replace each file with the model's answer
+        #[arg(long, conflicts_with = "out")]
+        in_place: bool,
+
+        /// Requests in flight at once
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+
+        /// Start at most this many requests per minute
+        #[arg(long, value_name = "N")]
+        rpm: Option<u32>,
+
+        /// Process every file again instead of resuming the last run
+        #[arg(long)]
+        restart: bool,
+    },
+    /// Re-run a prompt whenever a file or directory changes and print how the answer changed
+    Watch {
+        /// File or directory to watch (ignored files are skipped)
+        path: PathBuf,
+
+        /// Prompt sent along with the watched files' contents
+        #[arg(long)]
+        prompt: String,
+
+        /// Wait until nothing changed for this long before re-running
+        #[arg(long, value_name = "MS", default_value_t = 1000)]
+        debounce: u64,
+
+        /// Also write each answer to this file (changes to it don't trigger a run)
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+    /// Run eval suites (YAML test cases) and report which cases regressed
+    Eval {
+        /// Suite files to run
+        #[arg(required = true, value_name = "SUITE")]
+        suites: Vec<PathBuf>,
+
+        /// Replay this MockBackend fixture instead of calling the provider
+        #[arg(long, value_name = "FIXTURE")]
+        mock: Option<PathBuf>,
+
+        /// Only run cases whose name contains this text
+        #[arg(long, value_name = "TEXT")]
+        filter: Option<String>,
+
+        /// Record golden transcripts from this run instead of comparing
+        #[arg(long)]
+        update: bool,
+
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Send the requests of an HTTP capture (from --capture-http) again
+    ReplayHttp {
+        /// Capture file; defaults to the most recent one
+        file: Option<PathBuf>,
+
+        /// Only replay the request with this number (1-based)
+        #[arg(long, value_name = "N")]
+        index: Option<usize>,
+
+        /// Key to send instead of the configured one; required when the
+        /// request's host is not a configured provider
+        #[arg(long, value_name = "KEY")]
+        api_key: Option<String>,
+    },
+    /// List the models each configured provider offers
+    Models {
+        /// Only list models of this provider
+        #[arg(long, value_name = "NAME")]
+        provider: Option<String>,
+
+        /// Print the lists as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List registered tools and their JSON schemas
+    #[command(args_conflicts_with_subcommands = true)]
+    Tools {
+        #[command(subcommand)]
+        action: Option<ToolsCommand>,
+
+        /// Show the full schema of one tool
+        name: Option<String>,
+
+        /// Include tools from configured MCP servers (connects to them)
+        #[arg(long)]
+        mcp: bool,
+
+        /// Print the OpenAI-format tool definitions as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Invoke a registered tool directly, without a model
+    Tool {
+        #[command(subcommand)]
+        action: ToolCommand,
+    },
+    /// Download the latest release from GitHub, verify its SHA-256 checksum and
+    /// replace this executable
+    SelfUpdate {
+        /// Only report whether a newer release exists
+        #[arg(long)]
+        check: bool,
+
+        /// Install without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ToolsCommand {
+    /// Write every tool as an OpenAI function-calling JSON document, for
+    /// external agents and tests
+    Schema {
+        /// Include tools from configured MCP servers (connects to them)
+        #[arg(long)]
+        mcp: bool,
+
+        /// Write the document to this file instead of stdout
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ToolCommand {
+    /// Run a tool with JSON arguments under the usual sandbox and project
+    /// tool policy, and print its result
+    Run {
+        /// Tool name, as listed by `arula tools`
+        name: String,
+
+        /// Arguments as a JSON object, `@FILE` to read them from a file, or `-` for stdin
+        #[arg(long, default_value = "{}")]
+        args: String,
+
+        /// Also offer tools from configured MCP servers (connects to them)
+        #[arg(long)]
+        mcp: bool,
+
+        /// Print the whole result (success, data, error) as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// List cached answers, newest first
+    List,
+    /// Remove every cached answer
+    Clear,
 }
 
 use arula_cli::ui::output::OutputHandler;
+use arula_cli::ui::output::spinners::{
+    create_progress_bar, create_simple_spinner, set_progress_mode, ProgressMode,
+};
 use arula_cli::ui::tui_app::TuiApp;
+use arula_core::api::agent::{ToolRegistry, ToolSchema};
+use arula_core::api::stream::StreamEvent;
+use arula_core::api::batch_map::{self, MapOptions, MapOutput};
+use arula_core::api::bench;
+use arula_core::api::commit_message;
+use arula_core::api::review::{self, ReviewTarget, Severity};
+use arula_core::api::watch::{self, FileWatcher};
+use arula_core::api::models::{context_window, get_fetcher, ModelFetcher};
+use arula_core::eval::{self, CaseResult, EvalSuite};
 use arula_core::utils::changelog::{Changelog, ChangelogType};
+use arula_core::server::{serve_acp, serve_headless, serve_openai, ServeOptions, WebSocketOptions};
+use arula_core::utils::config::Config;
+use arula_core::utils::error::{exit_code, exit_code_for, ArulaError, ToolError};
+use arula_core::utils::http_capture;
+use arula_core::utils::personas;
+use arula_core::utils::project_config::ProjectConfig;
+use arula_core::utils::response_cache::ResponseCache;
+use arula_core::utils::snapshots::SnapshotStore;
+use arula_core::utils::updates;
+use arula_core::tools::tools::{create_basic_tool_registry, create_default_tool_registry_with_mcp};
 use arula_core::{detect_project, is_ai_enhanced};
-use arula_core::App;
+use arula_core::{App, MockBackend, SessionConfig, SessionManager};
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Print changelog from remote git or local file
 fn print_changelog() -> Result<()> {
@@ -66,6 +376,661 @@ fn print_changelog() -> Result<()> {
     Ok(())
 }
 
+/// Restore the snapshots taken during a turn (defaults to the latest)
+fn run_rollback(turn: Option<String>) -> Result<()> {
+    let store = SnapshotStore::default_store();
+    let Some(turn) = turn.or_else(|| store.last_turn()) else {
+        println!("{}", console::style("No AI file changes to roll back").dim());
+        return Ok(());
+    };
+
+    let restored = store.rollback_turn(&turn)?;
+    if restored.is_empty() {
+        println!(
+            "{} {}",
+            console::style("No snapshots found for turn").yellow(),
+            turn
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} {}",
+        console::style("↶ Rolled back turn").cyan().bold(),
+        console::style(&turn).dim()
+    );
+    for snapshot in &restored {
+        let action = if snapshot.hash.is_some() { "restored" } else { "removed" };
+        println!(
+            "   {} {}",
+            console::style(format!("{:>8}", action)).dim(),
+            snapshot.path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// List or clear the response cache
+fn run_cache(action: CacheCommand) -> Result<()> {
+    let cache = ResponseCache::default_cache();
+    match action {
+        CacheCommand::List => {
+            let entries = cache.entries();
+            if entries.is_empty() {
+                println!("{}", console::style("Response cache is empty").dim());
+            }
+            for entry in &entries {
+                println!(
+                    "{} {} {}",
+                    console::style(&entry.key[..12]).dim(),
+                    console::style(entry.created.format("%Y-%m-%d %H:%M")).dim(),
+                    console::style(&entry.model).cyan()
+                );
+                println!("   {}", entry.prompt_preview);
+            }
+            let enabled = Config::load_or_default()
+                .map(|c| c.get_response_cache_enabled())
+                .unwrap_or(false);
+            if !enabled {
+                println!(
+                    "{}",
+                    console::style("Caching is off; set \"response_cache_enabled\": true to use it in the desktop app, `arula serve` and `arula acp`").dim()
+                );
+            }
+        }
+        CacheCommand::Clear => {
+            let removed = cache.clear()?;
+            println!(
+                "{} {} cached answer(s)",
+                console::style("✓ Removed").green().bold(),
+                removed
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Benchmark the configured providers and print a comparison
+async fn run_bench(prompt: Option<String>, providers: Vec<String>, json: bool) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let prompt = prompt.unwrap_or_else(|| bench::STANDARD_PROMPT.to_string());
+    if !json {
+        println!(
+            "{}",
+            console::style("Benchmarking providers, one request each...").dim()
+        );
+    }
+
+    let results = bench::run_bench(&config, &prompt, &providers).await;
+    if results.is_empty() {
+        anyhow::bail!("No matching providers configured");
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print!("{}", bench::render_table(&results));
+    }
+    Ok(())
+}
+
+/// Check GitHub for a newer release and install it over this executable
+async fn run_self_update(check: bool, yes: bool) -> Result<()> {
+    println!(
+        "{}",
+        console::style(format!("Checking {} for releases...", updates::REPOSITORY)).dim()
+    );
+    // Always ask GitHub here; the daily cache is for background checks
+    let release = updates::latest_release().await?;
+    if !updates::is_newer(&release.version, updates::CURRENT_VERSION) {
+        println!(
+            "{} arula {} is the latest release",
+            console::style("✓").green().bold(),
+            updates::CURRENT_VERSION
+        );
+        return Ok(());
+    }
+    println!(
+        "Update available: {} → {}",
+        console::style(updates::CURRENT_VERSION).dim(),
+        console::style(&release.version).cyan().bold()
+    );
+    println!("  {}", console::style(&release.url).dim());
+    if check {
+        return Ok(());
+    }
+    if release.asset_for_platform().is_none() {
+        anyhow::bail!(
+            "No build of {} for this platform; download it from {}",
+            release.version,
+            release.url
+        );
+    }
+    if !yes {
+        print!("Install {} now? [y/N] ", release.version);
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            println!("{}", console::style("Not updated").dim());
+            return Ok(());
+        }
+    }
+
+    let path = updates::self_update(&release).await?;
+    println!(
+        "{} Installed {} at {}",
+        console::style("✓ Updated").green().bold(),
+        release.version,
+        path.display()
+    );
+    Ok(())
+}
+
+/// Generate a commit message for the staged changes, preview it and commit
+async fn run_commit(yes: bool, print: bool) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let cwd = std::env::current_dir()?;
+    if !print {
+        println!(
+            "{}",
+            console::style(format!("Summarizing staged changes with {}...", config.get_model())).dim()
+        );
+    }
+    let message = commit_message::generate(&config, &cwd).await?;
+    if print {
+        println!("{}", message);
+        return Ok(());
+    }
+
+    println!();
+    for line in message.lines() {
+        println!("  {}", console::style(line).cyan());
+    }
+    println!();
+    if !yes {
+        print!("Commit with this message? [y/N] ");
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            println!("{}", console::style("Not committed").dim());
+            return Ok(());
+        }
+    }
+
+    let summary = commit_message::commit(&cwd, &message)?;
+    println!("{} {}", console::style("✓ Committed").green().bold(), summary);
+    Ok(())
+}
+
+/// Review a diff chunk by chunk and print the findings
+async fn run_review(reference: Option<String>, staged: bool, json: bool) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let cwd = std::env::current_dir()?;
+    let target = match reference {
+        Some(reference) => ReviewTarget::Range(reference),
+        None if staged => ReviewTarget::Staged,
+        None => ReviewTarget::WorkingTree,
+    };
+
+    let report = review::review(&config, &cwd, &target, |done, total| {
+        if !json {
+            eprint!("\r{}", console::style(format!("Reviewing {}: chunk {}/{}", target, done, total)).dim());
+        }
+    })
+    .await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    eprintln!();
+
+    let mut current_file = None;
+    let mut findings: Vec<_> = report.findings.iter().collect();
+    findings.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    for finding in findings {
+        if current_file != Some(&finding.file) {
+            println!("\n{}", console::style(&finding.file).cyan().bold());
+            current_file = Some(&finding.file);
+        }
+        let severity = match finding.severity {
+            Severity::Error => console::style(finding.severity.as_str()).red().bold(),
+            Severity::Warning => console::style(finding.severity.as_str()).yellow(),
+            Severity::Info => console::style(finding.severity.as_str()).dim(),
+        };
+        let line = finding.line.map_or("-".to_string(), |l| l.to_string());
+        println!("  {:>5}  {:<7}  {}", console::style(line).dim(), severity, finding.message);
+        if let Some(suggestion) = &finding.suggestion {
+            println!("                  {} {}", console::style("→").dim(), suggestion);
+        }
+    }
+    println!(
+        "\n{} error(s), {} warning(s), {} info in {} chunk(s)",
+        report.count(Severity::Error),
+        report.count(Severity::Warning),
+        report.count(Severity::Info),
+        report.chunks
+    );
+    for failed in &report.failed_chunks {
+        println!("{} {}", console::style("Not reviewed:").yellow(), failed);
+    }
+    Ok(())
+}
+
+/// Run a prompt over many files with a progress bar
+async fn run_map(options: MapOptions) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let cwd = std::env::current_dir()?;
+    let progress = create_progress_bar(0, &format!("Mapping with {}", config.get_model()));
+    let report = batch_map::run(&config, &cwd, &options, |done, total, file| {
+        progress.set_length(total as u64);
+        progress.set_position(done as u64);
+        progress.set_message(file.to_string());
+    })
+    .await;
+    progress.finish_and_clear();
+    let report = report?;
+
+    println!(
+        "{} {} file(s)",
+        console::style("✓ Processed").green().bold(),
+        report.processed
+    );
+    if report.skipped > 0 {
+        println!(
+            "{}",
+            console::style(format!(
+                "  {} file(s) unchanged since the last run (--restart to redo them)",
+                report.skipped
+            ))
+            .dim()
+        );
+    }
+    for (file, error) in &report.failed {
+        println!("{} {}: {}", console::style("✗").red(), file, error);
+    }
+    if report.rollback_turn.is_some() && report.processed > 0 {
+        println!(
+            "{}",
+            console::style("  Undo the changes with `arula rollback`").dim()
+        );
+    }
+    if !report.failed.is_empty() {
+        anyhow::bail!("{} file(s) failed; run again to retry them", report.failed.len());
+    }
+    Ok(())
+}
+
+/// Run the prompt, then again after every settled change, until Ctrl+C
+async fn run_watch(path: PathBuf, prompt: String, debounce: Duration, out: Option<PathBuf>) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let mut watcher = FileWatcher::new(&path)?;
+    if let Some(out) = &out {
+        watcher = watcher.ignoring(out);
+    }
+    println!(
+        "{} {} ({} file(s)) with {}; Ctrl+C to stop",
+        console::style("👀 Watching").cyan().bold(),
+        path.display(),
+        watcher.files().count(),
+        config.get_model()
+    );
+
+    let mut previous: Option<String> = None;
+    loop {
+        let spinner = create_simple_spinner("Running the prompt");
+        let answer = tokio::select! {
+            answer = watcher.run(&config, &prompt) => answer,
+            _ = tokio::signal::ctrl_c() => {
+                spinner.finish_and_clear();
+                return Ok(());
+            }
+        };
+        spinner.finish_and_clear();
+        match answer {
+            Ok(answer) => {
+                match &previous {
+                    None => println!("\n{}", answer.trim_end()),
+                    Some(previous) => {
+                        let diff = watch::answer_diff(previous, &answer);
+                        if diff.is_empty() {
+                            println!("{}", console::style("  Answer unchanged").dim());
+                        }
+                        for line in diff {
+                            let styled = if line.starts_with('+') {
+                                console::style(line).green()
+                            } else {
+                                console::style(line).red()
+                            };
+                            println!("  {}", styled);
+                        }
+                    }
+                }
+                if let Some(out) = &out {
+                    std::fs::write(out, format!("{}\n", answer.trim_end()))
+                        .with_context(|| format!("Failed to write {}", out.display()))?;
+                }
+                previous = Some(answer);
+            }
+            Err(err) => println!("{} {:#}", console::style("✗").red(), err),
+        }
+
+        let changed = tokio::select! {
+            changed = watcher.next_change(debounce) => changed,
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        };
+        let names: Vec<String> = changed
+            .iter()
+            .map(|p| watcher.relative(p).display().to_string())
+            .collect();
+        println!(
+            "\n{} {} {}",
+            console::style(chrono::Local::now().format("%H:%M:%S")).dim(),
+            console::style("Changed:").yellow(),
+            names.join(", ")
+        );
+    }
+}
+
+/// Run eval suites against the agent (or a mock fixture) and print pass/fail
+async fn run_eval(
+    suites: Vec<PathBuf>,
+    mock: Option<PathBuf>,
+    filter: Option<String>,
+    update: bool,
+    json: bool,
+) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let session = SessionConfig::from_config(&config);
+    let mock = mock.map(MockBackend::from_fixture).transpose()?;
+    let print_case = |result: &CaseResult| {
+        if json {
+            return;
+        }
+        let status = if result.passed {
+            console::style("PASS").green().bold()
+        } else {
+            console::style("FAIL").red().bold()
+        };
+        println!(
+            "  {} {} {}",
+            status,
+            result.name,
+            console::style(format!("({} ms)", result.duration_ms)).dim()
+        );
+        for failure in &result.failures {
+            for (i, line) in failure.lines().enumerate() {
+                let indent = if i == 0 { "       → " } else { "         " };
+                println!("{}{}", console::style(indent).dim(), line);
+            }
+        }
+    };
+
+    let (mut passed, mut failed) = (0, 0);
+    let mut report = Vec::new();
+    for path in &suites {
+        let suite = EvalSuite::load(path)?;
+        if !json {
+            println!("{}", console::style(path.display()).cyan().bold());
+        }
+        let results = match &mock {
+            Some(backend) => {
+                eval::run_suite(backend, &suite, &session, filter.as_deref(), update, print_case).await?
+            }
+            None => {
+                let backend = eval::agent_backend(&config, &suite)?;
+                eval::run_suite(&backend, &suite, &session, filter.as_deref(), update, print_case).await?
+            }
+        };
+        let suite_failed = results.iter().filter(|r| !r.passed).count();
+        passed += results.len() - suite_failed;
+        failed += suite_failed;
+        report.push(serde_json::json!({ "suite": path, "cases": results }));
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("\n{} passed, {} failed", passed, failed);
+    }
+    if failed > 0 {
+        anyhow::bail!("{} eval case(s) failed", failed);
+    }
+    Ok(())
+}
+
+/// Re-send captured provider requests and print what comes back
+async fn run_replay_http(
+    file: Option<PathBuf>,
+    index: Option<usize>,
+    api_key: Option<String>,
+) -> Result<()> {
+    let file = match file {
+        Some(file) => file,
+        None => std::fs::read_dir(http_capture::captures_dir())
+            .ok()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+            .max()
+            .ok_or_else(|| anyhow::anyhow!("No captures yet; run with --capture-http first"))?,
+    };
+    let config = Config::load_or_default()?;
+    let exchanges = http_capture::load(&file)?;
+    if index.is_some_and(|i| i == 0 || i > exchanges.len()) {
+        anyhow::bail!("{} has {} request(s)", file.display(), exchanges.len());
+    }
+
+    for (i, exchange) in exchanges.iter().enumerate() {
+        if index.is_some_and(|n| n != i + 1) {
+            continue;
+        }
+        println!(
+            "{} {} {}",
+            console::style(format!("#{}", i + 1)).dim(),
+            console::style(&exchange.method).cyan().bold(),
+            exchange.url
+        );
+        let status = http_capture::replay(exchange, &config, api_key.as_deref(), |chunk| {
+            print!("{}", chunk);
+            let _ = std::io::Write::flush(&mut std::io::stdout());
+        })
+        .await?;
+        let recorded = exchange
+            .status
+            .map_or("none".to_string(), |s| s.to_string());
+        let summary = format!("status {} (captured: {})", status, recorded);
+        if exchange.status == Some(status) {
+            println!("\n{}", console::style(summary).dim());
+        } else {
+            println!("\n{}", console::style(summary).yellow());
+        }
+    }
+    Ok(())
+}
+
+/// List the models of each configured provider that has a model fetcher
+async fn run_models(provider: Option<String>, json: bool) -> Result<()> {
+    let config = Config::load_or_default()?;
+    let mut listings = Vec::new();
+    for name in config.get_provider_names() {
+        if provider.as_ref().is_some_and(|p| !p.eq_ignore_ascii_case(&name)) {
+            continue;
+        }
+        let Some(fetcher) = get_fetcher(&name) else {
+            continue;
+        };
+        let provider_config = &config.providers[&name];
+        let fetched = fetcher
+            .fetch_models(&provider_config.api_key, provider_config.api_url.as_deref())
+            .await;
+        // Fetchers report failures as a single "⚠️ ..." entry
+        let (errors, models): (Vec<String>, Vec<String>) =
+            fetched.into_iter().partition(|m| m.starts_with("⚠️"));
+        listings.push(serde_json::json!({
+            "provider": name,
+            "current": provider_config.model,
+            "models": models
+                .iter()
+                .map(|m| serde_json::json!({ "id": m, "context_window": context_window(m) }))
+                .collect::<Vec<_>>(),
+            "error": errors.first(),
+        }));
+    }
+    if listings.is_empty() {
+        anyhow::bail!("No configured provider supports listing models");
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&listings)?);
+        return Ok(());
+    }
+    for listing in &listings {
+        let provider = listing["provider"].as_str().unwrap_or_default();
+        println!("{}", console::style(provider).cyan().bold());
+        if let Some(error) = listing["error"].as_str() {
+            println!("  {}", console::style(error).red());
+        }
+        for model in listing["models"].as_array().into_iter().flatten() {
+            let id = model["id"].as_str().unwrap_or_default();
+            let marker = if listing["current"] == id { "*" } else { " " };
+            let context = model["context_window"]
+                .as_u64()
+                .map(|tokens| format!("{}k context", tokens / 1000))
+                .unwrap_or_default();
+            println!("{} {:<48} {}", marker, id, console::style(context).dim());
+        }
+    }
+    Ok(())
+}
+
+/// List registered tools, or the schema of one
+async fn run_tools(name: Option<String>, mcp: bool, json: bool) -> Result<()> {
+    let registry = tool_registry(mcp).await?;
+    let schemas: Vec<ToolSchema> = registry
+        .get_schemas()
+        .into_iter()
+        .filter(|schema| name.as_ref().is_none_or(|n| *n == schema.name))
+        .collect();
+    if let Some(name) = &name {
+        if schemas.is_empty() {
+            anyhow::bail!("No tool named {}", name);
+        }
+    }
+
+    if json || name.is_some() {
+        let tools: Vec<_> = schemas.iter().map(|s| s.to_openai_tool()).collect();
+        println!("{}", serde_json::to_string_pretty(&tools)?);
+        return Ok(());
+    }
+    for schema in &schemas {
+        println!("{}", console::style(&schema.name).cyan().bold());
+        println!("  {}", schema.description.lines().next().unwrap_or_default());
+        let mut params: Vec<_> = schema.parameters.iter().collect();
+        params.sort_by_key(|(param, _)| param.as_str());
+        for (param, param_schema) in params {
+            let required = if schema.required.contains(param) { "" } else { "?" };
+            println!(
+                "  {} {}{}: {}",
+                console::style("•").dim(),
+                param,
+                required,
+                console::style(&param_schema.param_type).dim()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The built-in tools, plus those of the configured MCP servers with `mcp`
+async fn tool_registry(mcp: bool) -> Result<ToolRegistry> {
+    if !mcp {
+        return Ok(create_basic_tool_registry());
+    }
+    let config = Config::load_or_default()?;
+    create_default_tool_registry_with_mcp(&config)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))
+}
+
+/// Write the tool registry as one OpenAI function-calling document
+async fn run_tools_schema(mcp: bool, out: Option<PathBuf>) -> Result<()> {
+    let registry = tool_registry(mcp).await?;
+    let document = serde_json::to_string_pretty(&registry.schema_document())?;
+    match out {
+        Some(path) => {
+            std::fs::write(&path, format!("{}\n", document))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "{} {} tool(s) to {}",
+                console::style("✓ Wrote").green().bold(),
+                registry.get_schemas().len(),
+                path.display()
+            );
+        }
+        None => println!("{}", document),
+    }
+    Ok(())
+}
+
+/// Run one tool as the agent would, but with arguments from the command line
+async fn run_tool(name: String, args: String, mcp: bool, json: bool) -> Result<()> {
+    let config = Config::load_or_default()?;
+    arula_core::tools::sandbox::configure(&config);
+    arula_core::tools::builtin::transcribe::configure(&config);
+    let registry = tool_registry(mcp).await?;
+    let Some(schema) = registry.get_schemas().into_iter().find(|s| s.name == name) else {
+        anyhow::bail!("No tool named {} (see `arula tools`)", name);
+    };
+    if let Some(project) = ProjectConfig::load_from_current_dir() {
+        if !project.is_tool_allowed(&name) {
+            anyhow::bail!("{} is not in allowed_tools of this project's .arula/config.toml", name);
+        }
+    }
+
+    let args = match args.trim() {
+        "-" => {
+            let mut text = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)?;
+            text
+        }
+        args => match args.strip_prefix('@') {
+            Some(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read arguments from {}", path))?,
+            None => args.to_string(),
+        },
+    };
+    let args: serde_json::Value =
+        serde_json::from_str(&args).context("--args is not valid JSON")?;
+    schema
+        .check_arguments(&args)
+        .map_err(|e| anyhow::anyhow!("{}: {}", name, e))?;
+
+    let result = registry
+        .execute_tool(&name, args)
+        .await
+        .with_context(|| format!("No tool named {}", name))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else if result.success {
+        match &result.data {
+            serde_json::Value::String(text) => println!("{}", text),
+            data => println!("{}", serde_json::to_string_pretty(data)?),
+        }
+    }
+    if !result.success {
+        // Exits with exit_code::TOOL
+        return Err(ToolError::ExecutionFailed {
+            tool_name: name,
+            message: result.error.unwrap_or_else(|| "no error message".to_string()),
+        }
+        .into());
+    }
+    Ok(())
+}
+
 /// Print project context information
 fn print_project_context() -> Result<()> {
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
@@ -94,6 +1059,13 @@ fn print_project_context() -> Result<()> {
             arula_core::ProjectType::Node => console::style(type_str).green(),
             arula_core::ProjectType::Python => console::style(type_str).blue(),
             arula_core::ProjectType::Go => console::style(type_str).cyan(),
+            arula_core::ProjectType::Java => console::style(type_str).yellow(),
+            arula_core::ProjectType::DotNet => console::style(type_str).magenta(),
+            arula_core::ProjectType::Cpp => console::style(type_str).blue(),
+            arula_core::ProjectType::Php => console::style(type_str).magenta(),
+            arula_core::ProjectType::Ruby => console::style(type_str).red(),
+            arula_core::ProjectType::Elixir => console::style(type_str).magenta(),
+            arula_core::ProjectType::Monorepo => console::style(type_str).white().bold(),
             arula_core::ProjectType::Unknown => console::style(type_str).dim(),
         };
 
@@ -111,6 +1083,14 @@ fn print_project_context() -> Result<()> {
             );
         }
 
+        if !project.subprojects.is_empty() {
+            println!(
+                "   {} {}",
+                console::style("Subprojects:").dim(),
+                console::style(project.subprojects.len().to_string()).white()
+            );
+        }
+
         let dep_count = project.dependencies.len();
         if dep_count > 0 {
             println!(
@@ -140,47 +1120,36 @@ fn print_project_context() -> Result<()> {
     Ok(())
 }
 
-/// Print conversation starter recommendations
-fn print_conversation_starters() -> Result<()> {
-    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
-
-    // Generate context-aware starters
-    let starters = if let Some(project) = detect_project(&cwd) {
-        match project.project_type {
-            arula_core::ProjectType::Rust => vec![
-                "Review and improve code quality",
-                "Run tests and fix any issues",
-                "Add new feature with proper error handling",
-            ],
-            arula_core::ProjectType::Node => vec![
-                "Review dependencies and update outdated packages",
-                "Add tests for critical functions",
-                "Improve error handling and logging",
-            ],
-            arula_core::ProjectType::Python => vec![
-                "Review code for PEP 8 compliance",
-                "Add type hints to improve code clarity",
-                "Write unit tests for core functionality",
-            ],
-            arula_core::ProjectType::Go => vec![
-                "Review code for idiomatic Go patterns",
-                "Add comprehensive error handling",
-                "Write benchmarks for performance",
-            ],
-            arula_core::ProjectType::Unknown => vec![
-                "Explain the project structure",
-                "Suggest improvements to code organization",
-                "Add documentation for key components",
-            ],
-        }
+/// Point at crash reports left by earlier runs, then stop mentioning them
+fn print_crash_reports() {
+    let reports = arula_core::utils::crash_report::unseen();
+    let Some(latest) = reports.last() else {
+        return;
+    };
+    let heading = if reports.len() == 1 {
+        "ARULA crashed last time.".to_string()
     } else {
-        // Default starters when no project detected
-        vec![
-            "Start a new conversation",
-            "Ask about my capabilities",
-            "Get help with a task",
-        ]
+        format!("ARULA crashed {} times since the last launch.", reports.len())
     };
+    println!("{} {}", console::style("⚠️ Crash:").yellow().bold(), heading);
+    println!("   Report: {}", console::style(latest.path.display()).cyan());
+    println!(
+        "   Its panic message and log may quote your work; share the location and backtrace at https://github.com/{}/issues if you'd like it fixed.",
+        arula_core::utils::updates::REPOSITORY
+    );
+    println!();
+    for report in &reports {
+        let _ = report.mark_seen();
+    }
+}
+
+/// Print conversation starter recommendations
+fn print_conversation_starters(config: &Config) -> Result<()> {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+    let starters = arula_core::utils::starters::offline(config, &cwd);
+    if starters.is_empty() {
+        return Ok(());
+    }
 
     println!(
         "{} {}",
@@ -201,9 +1170,147 @@ fn print_conversation_starters() -> Result<()> {
     Ok(())
 }
 
+/// Run the OpenAI-compatible server until interrupted
+async fn run_serve(
+    host: std::net::IpAddr,
+    port: u16,
+    api_key: Option<String>,
+    ws_port: Option<u16>,
+    allow_paths: &[PathBuf],
+) -> Result<()> {
+    if ws_port.is_some() && api_key.is_none() && !host.is_loopback() {
+        anyhow::bail!(
+            "--ws-port on {} needs --api-key; without a token anyone who can reach it could run prompts and tools",
+            host
+        );
+    }
+    let config = Config::load_or_default()?;
+    for path in allow_paths {
+        arula_core::tools::sandbox::grant(path);
+    }
+
+    let addr = std::net::SocketAddr::new(host, port);
+    println!(
+        "{} {}",
+        console::style("Serving OpenAI-compatible API on").cyan().bold(),
+        console::style(format!("http://{}/v1", addr)).underlined()
+    );
+    println!(
+        "   {} {}",
+        console::style("model").dim(),
+        config.get_model()
+    );
+    if api_key.is_none() && !host.is_loopback() {
+        println!(
+            "{}",
+            console::style("⚠️ No --api-key set; anyone who can reach this address can run tools")
+                .yellow()
+        );
+    }
+
+    if let Some(ws_port) = ws_port {
+        let options = WebSocketOptions {
+            addr: std::net::SocketAddr::new(host, ws_port),
+            token: api_key.clone(),
+        };
+        println!(
+            "{} {}",
+            console::style("Serving WebSocket event API on").cyan().bold(),
+            console::style(format!("ws://{}/ws", options.addr)).underlined()
+        );
+        // The headless session manager owns a runtime, so it gets its own thread
+        let ws_config = config.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = serve_headless(&ws_config, options) {
+                eprintln!("⚠️ WebSocket server failed: {}", e);
+            }
+        });
+    }
+
+    serve_openai(&config, ServeOptions { addr, api_key }).await
+}
+
+/// Name of the persona `name` refers to, or an error listing the available ones
+fn persona_name(config: &Config, name: &str) -> Result<String> {
+    let Some(persona) = personas::find(config, name) else {
+        let names: Vec<String> = personas::all(config).into_iter().map(|p| p.name).collect();
+        anyhow::bail!("Unknown persona '{}'; available: {}", name, names.join(", "));
+    };
+    Ok(persona.name)
+}
+
+/// Answer `prompt` without the chat UI. The answer goes to stdout and tool
+/// calls to stderr; a failed request or a reached budget ends with its exit code.
+async fn run_print(config: &Config, prompt: &str, yes: bool, json: bool) -> Result<()> {
+    // Nobody is there to review a write or command, so only --yes allows them
+    arula_core::tools::sandbox::set_confirm_actions(!yes);
+    let (backend, _, _) = SessionManager::build_backend(config)?;
+    let session = SessionConfig::from_config(config);
+    let mut client = backend.client().clone();
+    client.set_sampling(session.temperature, session.sampling);
+    client.set_max_tokens(session.max_tokens);
+
+    let mut answer = String::new();
+    let mut budget = None;
+    let response = client
+        .run(prompt, None, |event| match event {
+            StreamEvent::TextDelta(text) => {
+                if !json {
+                    print!("{}", text);
+                    let _ = std::io::Write::flush(&mut std::io::stdout());
+                }
+                answer.push_str(&text);
+            }
+            StreamEvent::ToolCallComplete(call) => {
+                eprintln!("{}", console::style(format!("→ {}", call.function.name)).dim());
+            }
+            StreamEvent::BudgetExceeded(exceeded) => budget = Some(exceeded),
+            _ => {}
+        })
+        .await?;
+    if !json && !answer.is_empty() && !answer.ends_with('\n') {
+        println!();
+    }
+
+    if let Some(exceeded) = budget {
+        return Err(ArulaError::BudgetExceeded(exceeded).into());
+    }
+    if !response.success {
+        anyhow::bail!(response.error.unwrap_or_else(|| "Request failed".to_string()));
+    }
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "answer": answer, "model": response.model, "usage": response.usage })
+        );
+    }
+    Ok(())
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+async fn main() {
+    // clap exits with 2 on bad arguments, which is taken by auth failures
+    let cli = Cli::try_parse().unwrap_or_else(|err| {
+        let _ = err.print();
+        std::process::exit(if err.use_stderr() { exit_code::USAGE } else { 0 });
+    });
+    let json_output = cli.output == OutputFormat::Json;
+    if let Err(err) = run(cli).await {
+        let code = exit_code_for(&err);
+        if json_output {
+            eprintln!(
+                "{}",
+                serde_json::json!({ "type": "error", "code": code, "message": format!("{:#}", err) })
+            );
+        } else {
+            eprintln!("Error: {:?}", err);
+        }
+        std::process::exit(code);
+    }
+}
+
+/// Start the chat UI or run a subcommand; `main` turns errors into exit codes
+async fn run(cli: Cli) -> Result<()> {
 
     // Set debug environment variable if debug flag is enabled
     if cli.debug {
@@ -212,13 +1319,157 @@ async fn main() -> Result<()> {
         }
     }
 
+    if cli.capture_http {
+        http_capture::enable();
+    }
+
+    if cli.no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+    // Otherwise spinners animate on a terminal and print plain lines elsewhere
+    let json_output = cli.output == OutputFormat::Json;
+    if json_output {
+        set_progress_mode(ProgressMode::Json);
+    }
+
+    // Panics leave a report in ~/.arula/crashes, mentioned on the next launch
+    arula_core::utils::crash_report::install("arula-cli");
+
     // Initialize global logger
     if let Err(e) = arula_core::utils::logger::init_global_logger() {
         eprintln!("⚠️ Failed to initialize logger: {}", e);
     }
 
+    // Structured logs go to ~/.arula/logs; keep the guard alive until exit
+    let telemetry = arula_core::utils::config::Config::load_or_default()
+        .ok()
+        .and_then(|config| config.telemetry);
+    let _log_guard = match arula_core::utils::logging::init_logging(
+        cli.log_level.as_deref(),
+        telemetry.as_ref(),
+    ) {
+        Ok(guard) => Some(guard),
+        Err(e) => {
+            eprintln!("⚠️ Failed to initialize logging: {}", e);
+            None
+        }
+    };
+
+    // One-shot subcommands run without starting the chat UI
+    if let Some(command) = cli.command {
+        return match command {
+            Command::Rollback { turn } => run_rollback(turn),
+            Command::Serve {
+                port,
+                host,
+                api_key,
+                ws_port,
+            } => run_serve(host, port, api_key, ws_port, &cli.allow_paths).await,
+            Command::Acp => {
+                for path in &cli.allow_paths {
+                    arula_core::tools::sandbox::grant(path);
+                }
+                serve_acp(Config::load_or_default()?).await
+            }
+            Command::Cache { action } => run_cache(action),
+            Command::Bench {
+                prompt,
+                providers,
+                json,
+            } => run_bench(prompt, providers, json || json_output).await,
+            Command::Commit { yes, print } => run_commit(yes, print).await,
+            Command::Review {
+                reference,
+                staged,
+                json,
+            } => run_review(reference, staged, json || json_output).await,
+            Command::Map {
+                glob,
+                prompt,
+                out,
+                in_place,
+                concurrency,
+                rpm,
+                restart,
+            } => {
+                let output = match out {
+                    Some(dir) if !in_place => MapOutput::Dir(dir),
+                    _ => MapOutput::InPlace,
+                };
+                run_map(MapOptions {
+                    glob,
+                    prompt,
+                    output,
+                    concurrency,
+                    requests_per_minute: rpm,
+                    restart,
+                })
+                .await
+            }
+            Command::Watch {
+                path,
+                prompt,
+                debounce,
+                out,
+            } => run_watch(path, prompt, Duration::from_millis(debounce), out).await,
+            Command::Eval {
+                suites,
+                mock,
+                filter,
+                update,
+                json,
+            } => run_eval(suites, mock, filter, update, json || json_output).await,
+            Command::ReplayHttp {
+                file,
+                index,
+                api_key,
+            } => run_replay_http(file, index, api_key).await,
+            Command::Models { provider, json } => run_models(provider, json || json_output).await,
+            Command::Tools {
+                action: Some(ToolsCommand::Schema { mcp, out }),
+                ..
+            } => run_tools_schema(mcp, out).await,
+            Command::Tools {
+                action: None,
+                name,
+                mcp,
+                json,
+            } => run_tools(name, mcp, json || json_output).await,
+            Command::Tool {
+                action:
+                    ToolCommand::Run {
+                        name,
+                        args,
+                        mcp,
+                        json,
+                    },
+            } => {
+                for path in &cli.allow_paths {
+                    arula_core::tools::sandbox::grant(path);
+                }
+                run_tool(name, args, mcp, json || json_output).await
+            }
+            Command::SelfUpdate { check, yes } => run_self_update(check, yes).await,
+        };
+    }
+
+    for path in &cli.allow_paths {
+        arula_core::tools::sandbox::grant(path);
+    }
+    if let Some(prompt) = cli.print {
+        let mut config = Config::load_or_default()?;
+        if let Some(name) = &cli.persona {
+            config.active_persona = Some(persona_name(&config, name)?);
+        }
+        return run_print(&config, &prompt, cli.yes, json_output).await;
+    }
+
     // Create app with debug flag
     let mut app = App::new()?.with_debug(cli.debug);
+    if let Some(name) = &cli.persona {
+        app.config.active_persona = Some(persona_name(&app.config, name)?);
+    }
 
     // Initialize app components
     let _ = app.initialize_git_state().await;
@@ -226,13 +1477,14 @@ async fn main() -> Result<()> {
     let _ = app.initialize_agent_client();
 
     // Print banner and changelog BEFORE entering TUI
-    let output = OutputHandler::new();
+    let output = OutputHandler::new().with_tool_verbosity(app.config.get_tool_verbosity());
     output.print_banner()?;
     println!();
+    print_crash_reports();
     print_changelog()?;
     print_project_context()?;
     println!();
-    print_conversation_starters()?;
+    print_conversation_starters(&app.config)?;
     println!();
 
     // Run TUI
@@ -358,7 +358,11 @@ impl ConfigMenu {
             format!(
                 "API Key: {}",
                 if config.get_api_key().is_empty() {
-                    "Not set"
+                    if arula_core::utils::config::AiConfig::is_local_provider(&config.active_provider) {
+                        "Not needed"
+                    } else {
+                        "Not set"
+                    }
                 } else {
                     "••••••••"
                 }
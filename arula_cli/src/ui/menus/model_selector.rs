@@ -1,6 +1,7 @@
 //! Model selection menu functionality for ARULA CLI
 //! Extracted from original overlay_menu.rs for modular architecture
 
+use crate::api::model_info;
 use crate::app::App;
 use crate::ui::menus::common::draw_modern_box;
 use crate::ui::menus::dialogs::Dialogs;
@@ -71,6 +72,12 @@ impl ModelSelector {
                 let (models, loading) = self.get_ollama_models(app, output)?;
                 (models, loading)
             }
+            "xai" | "deepseek" | "lm studio" | "llamafile" => {
+                // Clear cache to simulate first-run behavior
+                app.cache_provider_models(Vec::new());
+                let (models, loading) = self.get_provider_models(app, output)?;
+                (models, loading)
+            }
             "openrouter" => {
                 // For OpenRouter, fetch models dynamically with caching
                 // Force cache clear to simulate first-run behavior every time
@@ -197,6 +204,9 @@ impl ModelSelector {
                         "ollama" => app.get_cached_ollama_models(),
                         "z.ai coding plan" | "z.ai" | "zai" => app.get_cached_zai_models(),
                         "openrouter" => app.get_cached_openrouter_models(),
+                        "xai" | "deepseek" | "lm studio" | "llamafile" => {
+                            app.get_cached_provider_models()
+                        }
                         _ => None,
                     };
 
@@ -434,6 +444,9 @@ impl ModelSelector {
                                         "openrouter" => {
                                             app.cache_openrouter_models(Vec::new());
                                         }
+                                        "xai" | "deepseek" | "lm studio" | "llamafile" => {
+                                            app.cache_provider_models(Vec::new());
+                                        }
                                         _ => {}
                                     }
                                     let _ = output.print_system("🗑️ Cache cleared");
@@ -452,6 +465,9 @@ impl ModelSelector {
                                     "ollama" => app.fetch_ollama_models(),
                                     "z.ai coding plan" | "z.ai" | "zai" => app.fetch_zai_models(),
                                     "openrouter" => app.fetch_openrouter_models(),
+                                    "xai" | "deepseek" | "lm studio" | "llamafile" => {
+                                        app.fetch_provider_models()
+                                    }
                                     _ => {}
                                 }
                                 all_models = vec!["Fetching models...".to_string()];
@@ -544,6 +560,16 @@ impl ModelSelector {
         Ok((vec!["Fetching models...".to_string()], true))
     }
 
+    /// Get the models of a provider listed through its `ModelFetcher` with loading state
+    fn get_provider_models(
+        &self,
+        app: &App,
+        _output: &mut OutputHandler,
+    ) -> Result<(Vec<String>, bool)> {
+        app.fetch_provider_models();
+        Ok((vec!["Fetching models...".to_string()], true))
+    }
+
     // NOTE: draw_modern_box is now in common.rs
 
     /// Render model selector with search functionality
@@ -668,9 +694,17 @@ impl ModelSelector {
             {
                 let y = start_y + 3 + (idx - viewport_start) as u16;
 
+                // OpenRouter models carry context length, pricing and modalities
+                let details = model_details(model).unwrap_or_default();
+                let name_width = if details.is_empty() {
+                    max_text_width
+                } else {
+                    max_text_width.saturating_sub(details.chars().count() + 1)
+                };
+
                 // Truncate long model names to fit
-                let display_text = if model.len() > max_text_width {
-                    format!("{}...", &model[..max_text_width.saturating_sub(3)])
+                let display_text = if model.len() > name_width {
+                    format!("{}...", &model[..name_width.saturating_sub(3)])
                 } else {
                     model.clone()
                 };
@@ -680,7 +714,12 @@ impl ModelSelector {
 
                 // Pad with spaces to clear any previous content
                 let text_width = menu_width.saturating_sub(4) as usize;
-                let padded_text = format!("{:width$}", text, width = text_width);
+                let padded_text = format!(
+                    "{:name$}{}",
+                    text,
+                    details,
+                    name = text_width.saturating_sub(details.chars().count())
+                );
 
                 let color = if idx == selected_idx {
                     SetForegroundColor(crossterm::style::Color::AnsiValue(
@@ -738,3 +777,32 @@ impl Default for ModelSelector {
         Self::new()
     }
 }
+
+/// Compact context length, price per Mtok and extra input modalities of an
+/// OpenRouter model, e.g. `200K $3/$15 img`
+fn model_details(model: &str) -> Option<String> {
+    let info = model_info::get(model)?;
+    let mut parts = Vec::new();
+    if let Some(tokens) = info.context_length {
+        parts.push(model_info::format_tokens(tokens));
+    }
+    if info.is_free() {
+        parts.push("free".to_string());
+    } else if let (Some(input), Some(output)) = (info.prompt_price, info.completion_price) {
+        let price = |p: f64| {
+            if p.fract() == 0.0 {
+                format!("${}", p)
+            } else {
+                format!("${:.2}", p)
+            }
+        };
+        parts.push(format!("{}/{}", price(input), price(output)));
+    }
+    for badge in info.badges() {
+        parts.push(match badge {
+            "image" => "img".to_string(),
+            other => other.to_string(),
+        });
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
@@ -26,8 +26,12 @@ impl ProviderMenu {
                 "openai".to_string(),
                 "anthropic".to_string(),
                 "ollama".to_string(),
+                "lm studio".to_string(),
+                "llamafile".to_string(),
                 "z.ai coding plan".to_string(),
                 "openrouter".to_string(),
+                "xai".to_string(),
+                "deepseek".to_string(),
                 "custom".to_string(),
             ],
         }
@@ -8,6 +8,7 @@ use super::markdown::MarkdownStreamer;
 use super::spinners::{SpinnerManager, SpinnerStyle};
 use super::tool_display;
 use crate::api::api::Usage;
+use arula_core::utils::config::ToolVerbosity;
 use console::style;
 use crossterm::terminal;
 use std::io::{self, Write};
@@ -18,7 +19,8 @@ use std::io::{self, Write};
 /// - AI response streaming with markdown rendering
 /// - Code block syntax highlighting
 /// - Tool call/result display
-/// - Progress indicators and spinners
+/// - Progress indicators and spinners, as plain or JSON lines when stderr
+///   isn't a terminal or with `--output json` (see `spinners::progress_mode`)
 /// - Banner and status messages
 ///
 /// # Example
@@ -43,6 +45,8 @@ pub struct OutputHandler {
     streaming: bool,
     /// Current stream content buffer
     stream_buffer: String,
+    /// How much of each tool call is printed
+    tool_verbosity: ToolVerbosity,
 }
 
 impl OutputHandler {
@@ -55,6 +59,7 @@ impl OutputHandler {
             spinner_manager: SpinnerManager::new(),
             streaming: false,
             stream_buffer: String::new(),
+            tool_verbosity: ToolVerbosity::default(),
         }
     }
 
@@ -64,6 +69,12 @@ impl OutputHandler {
         self
     }
 
+    /// Builder method to set how much of each tool call is printed
+    pub fn with_tool_verbosity(mut self, verbosity: ToolVerbosity) -> Self {
+        self.tool_verbosity = verbosity;
+        self
+    }
+
     /// Get terminal width
     pub fn terminal_width(&self) -> usize {
         terminal::size()
@@ -282,6 +293,9 @@ impl OutputHandler {
 
     /// Print a tool call notification
     pub fn print_tool_call(&self, tool_name: &str, arguments: &str) -> io::Result<()> {
+        if self.tool_verbosity == ToolVerbosity::Hidden {
+            return Ok(());
+        }
         let stdout = io::stdout();
         let mut handle = stdout.lock();
 
@@ -298,10 +312,16 @@ impl OutputHandler {
         result: &serde_json::Value,
         success: bool,
     ) -> io::Result<()> {
+        let formatted = match self.tool_verbosity {
+            ToolVerbosity::Hidden => return Ok(()),
+            ToolVerbosity::Full => tool_display::format_detailed_result(tool_name, result, success),
+            ToolVerbosity::Summary => {
+                tool_display::format_tool_result_box(tool_name, result, success)
+            }
+        };
         let stdout = io::stdout();
         let mut handle = stdout.lock();
 
-        let formatted = tool_display::format_tool_result_box(tool_name, result, success);
         writeln!(handle, "{}", formatted)?;
         handle.flush()
     }
@@ -330,6 +350,12 @@ impl OutputHandler {
         let stdout = io::stdout();
         let mut handle = stdout.lock();
 
+        // Highlighting writes escapes directly, so honor --no-color/NO_COLOR here
+        if !console::colors_enabled() {
+            writeln!(handle, "{}", code)?;
+            return handle.flush();
+        }
+
         let highlighted = self.code_highlighter.highlight(code, language);
         writeln!(handle, "{}", highlighted)?;
         handle.flush()
@@ -337,6 +363,9 @@ impl OutputHandler {
 
     /// Print a code block with a box border
     pub fn print_code_box(&self, code: &str, language: &str) -> io::Result<()> {
+        if !console::colors_enabled() {
+            return self.print_code(code, language);
+        }
         let stdout = io::stdout();
         let mut handle = stdout.lock();
 
@@ -409,6 +438,14 @@ impl OutputHandler {
             style("•").dim(),
             style(usage.total_tokens).bold().cyan()
         )?;
+        if let Some(read) = usage.cache_read_input_tokens.filter(|n| *n > 0) {
+            writeln!(
+                handle,
+                "  {} Cached prompt tokens: {}",
+                style("•").dim(),
+                style(read).green()
+            )?;
+        }
         writeln!(handle, "{}", style("─".repeat(40)).dim())?;
 
         handle.flush()
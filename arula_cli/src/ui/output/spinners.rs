@@ -1,10 +1,58 @@
 //! Spinner and progress bar utilities using indicatif
 //!
 //! Provides pre-configured spinners and progress bars for various operations.
-
-use indicatif::{ProgressBar, ProgressStyle};
+//! They animate only when stderr is a terminal; in logs and pipes each new
+//! state is printed as one plain line instead, and with `--output json` as one
+//! JSON object per line (see `ProgressMode`).
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle, TermLike};
+use serde_json::{json, Value};
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
 use std::time::Duration;
 
+/// How spinners and progress bars are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// Animated in place (stderr is a terminal)
+    Animated,
+    /// One plain line per change, for logs and pipes
+    Plain,
+    /// One `{"type":"progress",...}` object per line on stderr (`--output json`)
+    Json,
+}
+
+/// Between the position, length and message of `Json` mode lines
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+/// 0 until `set_progress_mode` is called, then the mode's index + 1
+static PROGRESS_MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Override the detected progress mode
+pub fn set_progress_mode(mode: ProgressMode) {
+    let value = match mode {
+        ProgressMode::Animated => 1,
+        ProgressMode::Plain => 2,
+        ProgressMode::Json => 3,
+    };
+    PROGRESS_MODE.store(value, Ordering::Relaxed);
+}
+
+/// The mode set with `set_progress_mode`, otherwise `Animated` when stderr is
+/// a terminal that can move the cursor and `Plain` when it isn't
+pub fn progress_mode() -> ProgressMode {
+    match PROGRESS_MODE.load(Ordering::Relaxed) {
+        1 => ProgressMode::Animated,
+        2 => ProgressMode::Plain,
+        3 => ProgressMode::Json,
+        _ if io::stderr().is_terminal() && std::env::var("TERM").as_deref() != Ok("dumb") => {
+            ProgressMode::Animated
+        }
+        _ => ProgressMode::Plain,
+    }
+}
+
 /// Pre-defined spinner styles for different use cases
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SpinnerStyle {
@@ -98,6 +146,9 @@ impl SpinnerStyle {
 /// ```
 pub fn create_spinner(style: SpinnerStyle, message: &str) -> ProgressBar {
     let spinner = ProgressBar::new_spinner();
+    if print_lines(&spinner, false, message) {
+        return spinner;
+    }
 
     let template = format!("{{spinner:.cyan}} {} {{msg}}", style.icon());
 
@@ -133,6 +184,9 @@ pub fn create_simple_spinner(message: &str) -> ProgressBar {
 /// ```
 pub fn create_progress_bar(total: u64, message: &str) -> ProgressBar {
     let bar = ProgressBar::new(total);
+    if print_lines(&bar, true, message) {
+        return bar;
+    }
 
     bar.set_style(
         ProgressStyle::default_bar()
@@ -150,6 +204,9 @@ pub fn create_progress_bar(total: u64, message: &str) -> ProgressBar {
 /// Create a download-style progress bar with ETA
 pub fn create_download_bar(total: u64, message: &str) -> ProgressBar {
     let bar = ProgressBar::new(total);
+    if print_lines(&bar, true, message) {
+        return bar;
+    }
 
     bar.set_style(
         ProgressStyle::default_bar()
@@ -167,6 +224,9 @@ pub fn create_download_bar(total: u64, message: &str) -> ProgressBar {
 /// Create an indeterminate progress bar (for unknown length operations)
 pub fn create_indeterminate_bar(message: &str) -> ProgressBar {
     let bar = ProgressBar::new_spinner();
+    if print_lines(&bar, false, message) {
+        return bar;
+    }
 
     bar.set_style(
         ProgressStyle::default_spinner()
@@ -181,6 +241,105 @@ pub fn create_indeterminate_bar(message: &str) -> ProgressBar {
     bar
 }
 
+/// Set `bar` up to print lines instead of animating, unless the mode is
+/// `Animated`; returns whether it did. `counted` bars show their position
+/// and length.
+fn print_lines(bar: &ProgressBar, counted: bool, message: &str) -> bool {
+    let mode = progress_mode();
+    let template = match (mode, counted) {
+        (ProgressMode::Animated, _) => return false,
+        (ProgressMode::Plain, true) => "[{pos}/{len}] {msg}",
+        (ProgressMode::Plain, false) => "{msg}",
+        // Split up again by `progress_event`; indicatif expands tabs
+        (ProgressMode::Json, true) => "{pos}\u{1f}{len}\u{1f}{msg}",
+        (ProgressMode::Json, false) => "\u{1f}\u{1f}{msg}",
+    };
+    bar.set_style(ProgressStyle::with_template(template).expect("Invalid line template"));
+    bar.set_draw_target(ProgressDrawTarget::term_like(Box::new(LineTarget {
+        mode,
+        pending: Mutex::new(String::new()),
+        last: Mutex::new(String::new()),
+    })));
+    bar.set_message(message.to_string());
+    true
+}
+
+/// Draw target printing each new state of a bar as one line on stderr,
+/// ignoring the cursor movement indicatif uses to redraw in place
+#[derive(Debug)]
+struct LineTarget {
+    mode: ProgressMode,
+    /// Text drawn since the last flush
+    pending: Mutex<String>,
+    /// Last line printed, so redraws of an unchanged state print nothing
+    last: Mutex<String>,
+}
+
+impl TermLike for LineTarget {
+    fn width(&self) -> u16 {
+        512
+    }
+
+    fn move_cursor_up(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_down(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_right(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_left(&self, _: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_line(&self, s: &str) -> io::Result<()> {
+        self.write_str(s)
+    }
+
+    fn write_str(&self, s: &str) -> io::Result<()> {
+        self.pending.lock().unwrap().push_str(s);
+        Ok(())
+    }
+
+    fn clear_line(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        let drawn = std::mem::take(&mut *self.pending.lock().unwrap());
+        // Lines are padded to the width with spaces
+        let line = console::strip_ansi_codes(&drawn).trim_end().to_string();
+        let mut last = self.last.lock().unwrap();
+        if line.trim().is_empty() || *last == line {
+            return Ok(());
+        }
+        match self.mode {
+            ProgressMode::Json => eprintln!("{}", progress_event(&line)),
+            _ => eprintln!("{}", line),
+        }
+        *last = line;
+        Ok(())
+    }
+}
+
+/// The JSON event for a line drawn with a `Json` mode template
+fn progress_event(line: &str) -> Value {
+    let mut fields = line.splitn(3, FIELD_SEPARATOR);
+    let mut number = || fields.next().and_then(|f| f.trim().parse::<u64>().ok());
+    let (position, total) = (number(), number());
+    let message = fields.next().unwrap_or_default().trim();
+    let mut event = json!({ "type": "progress", "message": message });
+    if let (Some(position), Some(total)) = (position, total) {
+        event["position"] = json!(position);
+        event["total"] = json!(total);
+    }
+    event
+}
+
 /// Spinner manager for handling multiple spinners
 pub struct SpinnerManager {
     current: Option<ProgressBar>,
@@ -263,6 +422,18 @@ mod tests {
         spinner.finish_and_clear();
     }
 
+    #[test]
+    fn test_progress_event() {
+        assert_eq!(
+            progress_event("3\u{1f}40\u{1f}src/main.rs"),
+            json!({ "type": "progress", "message": "src/main.rs", "position": 3, "total": 40 })
+        );
+        assert_eq!(
+            progress_event("\u{1f}\u{1f}Running the prompt"),
+            json!({ "type": "progress", "message": "Running the prompt" })
+        );
+    }
+
     #[test]
     fn test_spinner_manager() {
         let mut manager = SpinnerManager::new();
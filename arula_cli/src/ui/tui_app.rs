@@ -17,10 +17,11 @@ use ratatui::{
 };
 use serde_json::Value;
 use std::io::{self, Stdout};
+use std::path::Path;
 use std::time::{Duration, Instant};
 
 use arula_core::app::AiResponse;
-use arula_core::prelude::detect_project;
+use arula_core::tools::edit_review::{self, Decision, ProposedChange};
 use arula_core::App;
 use regex::Regex;
 use std::sync::OnceLock;
@@ -31,6 +32,9 @@ use crate::ui::menus::main_menu::MainMenu;
 use crate::ui::output::OutputHandler;
 use crate::ui::scroll_history::{insert_history_lines, HistoryLine, HistorySpan};
 use arula_core::utils::chat::MessageType;
+use arula_core::utils::config::ToolVerbosity;
+use arula_core::utils::prompt_history::{self, Recall};
+use arula_core::utils::starters;
 
 /// Tool execution status
 #[derive(Clone)]
@@ -54,6 +58,12 @@ pub enum ToolState {
 /// The TUI viewport height (input + info line)
 const VIEWPORT_HEIGHT: u16 = 2;
 
+/// Diff lines printed for a change under review; `e` shows the whole file
+const MAX_REVIEW_LINES: usize = 200;
+
+/// Answers to the review prompt, shown in the input line
+const REVIEW_KEYS: &str = "[a]ccept  [r]eject  [e]dit";
+
 /// Application state (separate from terminal for borrow checker)
 struct AppState {
     input: String,
@@ -78,6 +88,18 @@ struct AppState {
     fetching_starters: bool,
     /// Currently selected starter index (for keyboard navigation)
     selected_starter: Option<usize>,
+    /// The last run stopped at a budget limit; replying "continue" lifts it once
+    budget_exceeded: bool,
+    /// Why and when a failed request is retried, until the response arrives
+    retry_status: Option<String>,
+    /// Prompts sent from any session, oldest first, for Up and Ctrl+R
+    prompt_history: Vec<String>,
+    /// Where Up/Down in the input currently are in the prompt history
+    recall: Recall,
+    /// Query of the open Ctrl+R search
+    history_search: Option<String>,
+    /// File change printed above, waiting for a/r/e
+    review: Option<ProposedChange>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -109,6 +131,77 @@ impl AppState {
             conversation_starters: Vec::new(),
             fetching_starters: false,
             selected_starter: None,
+            budget_exceeded: false,
+            retry_status: None,
+            prompt_history: prompt_history::load(),
+            recall: Recall::default(),
+            history_search: None,
+            review: None,
+        }
+    }
+
+    /// Entries Up walks through: this conversation's prompts first, then the
+    /// global history. The input is a single line, so newlines become spaces.
+    fn recall_entries(&self) -> Vec<String> {
+        let session: Vec<String> = self
+            .app
+            .get_message_history()
+            .iter()
+            .filter(|m| m.message_type == MessageType::User)
+            .map(|m| m.content.clone())
+            .collect();
+        prompt_history::merged(&self.prompt_history, &session)
+            .into_iter()
+            .map(|entry| entry.replace('\n', " "))
+            .collect()
+    }
+
+    fn set_input(&mut self, text: String) {
+        self.input = text;
+        self.input_cursor = self.input.chars().count();
+    }
+
+    /// Put an older or newer sent prompt into the input
+    fn recall_prompt(&mut self, older: bool) -> bool {
+        let entries = self.recall_entries();
+        match self.recall.step(&entries, older) {
+            Some(prompt) => {
+                self.set_input(prompt);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Best match of the Ctrl+R search
+    fn search_match(&self) -> Option<String> {
+        let query = self.history_search.as_deref()?;
+        let entries = self.recall_entries();
+        prompt_history::search(&entries, query)
+            .first()
+            .map(|entry| entry.to_string())
+    }
+
+    /// Keys while the Ctrl+R search is open: typing narrows it, Enter takes
+    /// the best match into the input, Esc leaves the input as it was
+    fn handle_search_key(&mut self, code: KeyCode) {
+        let Some(query) = self.history_search.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => query.push(c),
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Enter | KeyCode::Tab => {
+                if let Some(prompt) = self.search_match() {
+                    self.recall.reset();
+                    self.set_input(prompt);
+                }
+                self.history_search = None;
+            }
+            KeyCode::Esc => self.history_search = None,
+            _ => {}
         }
     }
 
@@ -171,6 +264,47 @@ impl AppState {
         );
     }
 
+    /// Print a proposed file change as a colored unified diff
+    fn show_review(&mut self, change: &ProposedChange) {
+        self.push_history(
+            HistoryKind::Tool,
+            HistoryLine::new(vec![
+                HistorySpan::new("✎ Review: ").fg(Color::Yellow).bold(),
+                HistorySpan::new(TuiApp::display_tool_name(&change.tool)).bold(),
+                HistorySpan::new(format!(" {} ", change.path)),
+                HistorySpan::new(format!("+{}", change.lines_added)).fg(Color::Green),
+                HistorySpan::new(" "),
+                HistorySpan::new(format!("−{}", change.lines_removed)).fg(Color::Red),
+            ]),
+        );
+        for line in change.diff.lines().take(MAX_REVIEW_LINES) {
+            let span = HistorySpan::new(format!("  {}", clean_text(line)));
+            let span = if line.starts_with("+++") || line.starts_with("---") {
+                span.bold().dim()
+            } else if line.starts_with("@@") {
+                span.fg(Color::Cyan)
+            } else if line.starts_with('+') {
+                span.fg(Color::Green)
+            } else if line.starts_with('-') {
+                span.fg(Color::Red)
+            } else {
+                span.dim()
+            };
+            self.push_history(HistoryKind::Tool, HistoryLine::new(vec![span]));
+        }
+        let hidden = change.diff.lines().count().saturating_sub(MAX_REVIEW_LINES);
+        if hidden > 0 {
+            self.push_history(
+                HistoryKind::Tool,
+                HistoryLine::new(vec![HistorySpan::new(format!(
+                    "  … {} more lines (e opens the whole file)",
+                    hidden
+                ))
+                .dim()]),
+            );
+        }
+    }
+
     fn push_history(&mut self, kind: HistoryKind, line: HistoryLine) {
         if let Some(last) = self.last_history_kind {
             if last != kind {
@@ -268,10 +402,41 @@ impl AppState {
             RColor::Cyan
         };
 
-        let input_text = Line::from(vec![
-            Span::styled("▶ ", Style::default().fg(prompt_color).add_modifier(Modifier::BOLD)),
-            Span::styled(&self.input, Style::default().fg(RColor::White)),
-        ]);
+        // A review prompt or the Ctrl+R search takes over the line
+        let (input_text, prompt_width, cursor_chars) = match (&self.review, self.history_search.as_deref()) {
+            (Some(change), _) => {
+                let question = format!("Apply this change to {}? ", change.path);
+                let width = (question.chars().count() + REVIEW_KEYS.len()) as u16;
+                (
+                    Line::from(vec![
+                        Span::styled(question, Style::default().fg(RColor::Yellow).add_modifier(Modifier::BOLD)),
+                        Span::styled(REVIEW_KEYS, Style::default().fg(RColor::White)),
+                    ]),
+                    width,
+                    0,
+                )
+            }
+            (None, Some(query)) => {
+                let found = self.search_match().unwrap_or_default();
+                (
+                    Line::from(vec![
+                        Span::styled(format!("(search) '{}': ", query), Style::default().fg(RColor::Magenta)),
+                        Span::styled(found, Style::default().fg(RColor::White)),
+                    ]),
+                    // The cursor stays at the end of the query
+                    "(search) '".len() as u16 + query.chars().count() as u16,
+                    0,
+                )
+            }
+            (None, None) => (
+                Line::from(vec![
+                    Span::styled("▶ ", Style::default().fg(prompt_color).add_modifier(Modifier::BOLD)),
+                    Span::styled(self.input.as_str(), Style::default().fg(RColor::White)),
+                ]),
+                2, // Width of "▶ "
+                self.input.chars().take(self.input_cursor).count() as u16,
+            ),
+        };
 
         let input = Paragraph::new(input_text)
             .style(Style::default().fg(RColor::White).bg(RColor::Rgb(12, 12, 16)))
@@ -283,13 +448,9 @@ impl AppState {
 
         f.render_widget(input, area);
 
-        // Calculate cursor X position with bounds checking
-        let prompt_width = 2; // Width of "▶ "
-        let input_char_count = self.input.chars().take(self.input_cursor).count() as u16;
-
         // Ensure cursor stays within the input area (minus border)
         let max_cursor_x = area.width.saturating_sub(1); // Leave 1 char for border
-        let cursor_offset = input_char_count.min(max_cursor_x.saturating_sub(prompt_width));
+        let cursor_offset = cursor_chars.min(max_cursor_x.saturating_sub(prompt_width));
         let cursor_x = area.x + prompt_width + cursor_offset;
 
         // Cursor Y is at the input line (accounting for top border)
@@ -472,6 +633,21 @@ impl AppState {
             lines.push(Line::from(spans));
         }
 
+        if let Some(retry) = self.retry_status.as_ref().filter(|_| self.is_waiting) {
+            let spinner = ["◐", "◓", "◑", "◒"][self.frame % 4];
+            lines.push(Line::from(vec![
+                Span::styled("┌", border),
+                Span::styled(
+                    format!(" {spinner} Retrying "),
+                    Style::default()
+                        .fg(RColor::Rgb(255, 220, 100))
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("┐ ", border),
+                Span::styled(retry.as_str(), Style::default().fg(RColor::Rgb(180, 180, 180))),
+            ]));
+        }
+
         if self.is_waiting && !self.thinking_content.is_empty() {
             let spinner = ["◐", "◓", "◑", "◒"][self.frame % 4];
 
@@ -630,6 +806,9 @@ impl TuiApp {
         let terminal = Terminal::with_options(backend, TerminalOptions { viewport })?;
         let viewport_height = VIEWPORT_HEIGHT;
 
+        // File writes wait here for a/r/e instead of happening straight away
+        edit_review::set_enabled(app.config.get_review_edits());
+
         Ok(Self {
             terminal,
             viewport_height,
@@ -637,56 +816,11 @@ impl TuiApp {
         })
     }
 
-    /// Generate conversation starters based on project context
+    /// Show the pinned and project starters (`starters::offline`)
     /// This is called when the conversation is empty
     fn generate_conversation_starters(&mut self) {
-        use std::path::PathBuf;
-
-        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
-
-        // Check if we have PROJECT.manifest
-        let manifest_path = cwd.join("PROJECT.manifest");
-        let _has_manifest = manifest_path.exists();
-
-        // Generate context-aware starters
-        let starters = if let Some(project) = detect_project(&cwd) {
-            match project.project_type {
-                arula_core::ProjectType::Rust => vec![
-                    "Review and improve code quality".to_string(),
-                    "Run tests and fix any issues".to_string(),
-                    "Add new feature with proper error handling".to_string(),
-                ],
-                arula_core::ProjectType::Node => vec![
-                    "Review dependencies and update outdated packages".to_string(),
-                    "Add tests for critical functions".to_string(),
-                    "Improve error handling and logging".to_string(),
-                ],
-                arula_core::ProjectType::Python => vec![
-                    "Review code for PEP 8 compliance".to_string(),
-                    "Add type hints to improve code clarity".to_string(),
-                    "Write unit tests for core functionality".to_string(),
-                ],
-                arula_core::ProjectType::Go => vec![
-                    "Review code for idiomatic Go patterns".to_string(),
-                    "Add comprehensive error handling".to_string(),
-                    "Write benchmarks for performance".to_string(),
-                ],
-                arula_core::ProjectType::Unknown => vec![
-                    "Explain the project structure".to_string(),
-                    "Suggest improvements to code organization".to_string(),
-                    "Add documentation for key components".to_string(),
-                ],
-            }
-        } else {
-            // Default starters when no project detected
-            vec![
-                "Start a new conversation".to_string(),
-                "Ask about my capabilities".to_string(),
-                "Get help with a task".to_string(),
-            ]
-        };
-
-        self.state.conversation_starters = starters;
+        let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("/"));
+        self.state.conversation_starters = starters::offline(&self.state.app.config, &cwd);
     }
 
     fn rebuild_terminal(&mut self, viewport_height: u16) -> Result<()> {
@@ -839,6 +973,30 @@ impl TuiApp {
         arguments.trim().to_string()
     }
 
+    /// Complete output of a tool result, for full tool verbosity
+    fn full_tool_output(result: &Value) -> String {
+        if let Some(obj) = result.as_object() {
+            for key in ["stdout", "output", "content", "diff", "result"] {
+                if let Some(text) = obj.get(key).and_then(|v| v.as_str()) {
+                    let mut output = strip_ansi_codes(text).to_string();
+                    if let Some(stderr) = obj
+                        .get("stderr")
+                        .and_then(|v| v.as_str())
+                        .filter(|s| !s.trim().is_empty())
+                    {
+                        output.push('\n');
+                        output.push_str(&strip_ansi_codes(stderr));
+                    }
+                    return output.trim_end().to_string();
+                }
+            }
+        }
+        match result.as_str() {
+            Some(text) => strip_ansi_codes(text).trim_end().to_string(),
+            None => serde_json::to_string_pretty(result).unwrap_or_default(),
+        }
+    }
+
     fn summarize_tool_result(result: &Value, success: bool) -> String {
         // Prefer structured fields
         if let Some(obj) = result.as_object() {
@@ -962,6 +1120,32 @@ impl TuiApp {
                 redraw = true;
             }
 
+            // Drop a review whose tool was cancelled while it was shown
+            if let Some(change) = &self.state.review {
+                if edit_review::pending().iter().all(|c| c.id != change.id) {
+                    self.state.push_history(
+                        HistoryKind::Tool,
+                        HistoryLine::new(vec![
+                            HistorySpan::new("✗ Withdrawn, nothing written: ")
+                                .fg(Color::Red)
+                                .bold(),
+                            HistorySpan::new(change.path.clone()),
+                        ]),
+                    );
+                    self.state.review = None;
+                    redraw = true;
+                }
+            }
+
+            // Show the next file change waiting for review
+            if self.state.review.is_none() {
+                if let Some(change) = edit_review::pending().into_iter().next() {
+                    self.state.show_review(&change);
+                    self.state.review = Some(change);
+                    redraw = true;
+                }
+            }
+
             // Handle events - only Press events (not Release or Repeat)
             if event::poll(Duration::from_millis(50))? {
                 match event::read()? {
@@ -974,6 +1158,29 @@ impl TuiApp {
                             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                                 return Ok(());
                             }
+                            _ if self.state.review.is_some() => {
+                                redraw = self.handle_review_key(key.code)?;
+                            }
+                            _ if self.state.history_search.is_some() => {
+                                self.state.handle_search_key(key.code);
+                                redraw = true;
+                            }
+                            // Ctrl+R searches the prompt history
+                            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                self.state.history_search = Some(String::new());
+                                redraw = true;
+                            }
+                            // Up in an empty input, and Up/Down while it still holds a
+                            // recalled prompt, walk the prompt history
+                            KeyCode::Up
+                                if self.state.input.is_empty()
+                                    || self.state.recall.is_showing(&self.state.input) =>
+                            {
+                                redraw = self.state.recall_prompt(true);
+                            }
+                            KeyCode::Down if self.state.recall.is_showing(&self.state.input) => {
+                                redraw = self.state.recall_prompt(false);
+                            }
                             // Ctrl+1/2/3: Send conversation starter messages
                             KeyCode::Char('1') | KeyCode::Char('2') | KeyCode::Char('3') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                                 if !self.state.conversation_starters.is_empty() {
@@ -1141,14 +1348,102 @@ impl TuiApp {
         }
     }
 
+    /// Answer the change under review: a/y/Enter accept, r/n/Esc reject, and
+    /// e opens the proposed content in `$VISUAL`/`$EDITOR` and writes the result
+    fn handle_review_key(&mut self, code: KeyCode) -> Result<bool> {
+        let Some(change) = self.state.review.clone() else {
+            return Ok(false);
+        };
+        let decision = match code {
+            KeyCode::Char('a') | KeyCode::Char('y') | KeyCode::Enter => Decision::Accept,
+            KeyCode::Char('r') | KeyCode::Char('n') | KeyCode::Esc => Decision::Reject,
+            KeyCode::Char('e') => match self.edit_in_editor(&change) {
+                Ok(content) => Decision::Edit(content),
+                Err(e) => {
+                    self.state.push_history(
+                        HistoryKind::Tool,
+                        HistoryLine::new(vec![
+                            HistorySpan::new("✗ ").fg(Color::Red).bold(),
+                            HistorySpan::new(format!("Couldn't edit the change: {}", e)),
+                        ]),
+                    );
+                    return Ok(true);
+                }
+            },
+            _ => return Ok(false),
+        };
+
+        let (label, color) = match &decision {
+            Decision::Accept => ("✓ Accepted ", Color::Green),
+            Decision::Reject => ("✗ Rejected ", Color::Red),
+            Decision::Edit(_) => ("✎ Applied your edit of ", Color::Yellow),
+        };
+        // The tool may have given up waiting (e.g. the request was cancelled)
+        let (label, color) = match edit_review::decide(change.id, decision) {
+            Ok(()) => (label, color),
+            Err(_) => ("✗ No longer waiting, nothing written: ", Color::Red),
+        };
+        self.state.push_history(
+            HistoryKind::Tool,
+            HistoryLine::new(vec![
+                HistorySpan::new(label).fg(color).bold(),
+                HistorySpan::new(change.path.clone()),
+            ]),
+        );
+        self.state.review = None;
+        Ok(true)
+    }
+
+    /// Let the user edit the proposed content in their editor and return it
+    fn edit_in_editor(&mut self, change: &ProposedChange) -> Result<String> {
+        let name = Path::new(&change.path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "change".to_string());
+        let file = std::env::temp_dir().join(format!("arula-review-{}-{}", change.id, name));
+        std::fs::write(&file, &change.content)?;
+
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+        // Allow editors with arguments, such as "code --wait"
+        let mut words = editor.split_whitespace();
+        let program = words.next().unwrap_or("vi");
+
+        disable_raw_mode()?;
+        let status = std::process::Command::new(program)
+            .args(words)
+            .arg(&file)
+            .status();
+        enable_raw_mode()?;
+        self.rebuild_terminal(self.required_viewport_height())?;
+
+        let edited = std::fs::read_to_string(&file);
+        let _ = std::fs::remove_file(&file);
+        let status = status.map_err(|e| anyhow::anyhow!("failed to start {}: {}", program, e))?;
+        if !status.success() {
+            anyhow::bail!("{} exited with {}", program, status);
+        }
+        Ok(edited?)
+    }
+
     async fn submit_message(&mut self) -> Result<()> {
         let message = self.state.input.clone();
         self.state.input.clear();
         self.state.input_cursor = 0;
+        self.state.recall.reset();
+        prompt_history::record(&message);
+        self.state.prompt_history.push(message.clone());
 
         self.state.add_user_message(&message);
         self.state.last_ai_message = None;
 
+        if std::mem::take(&mut self.state.budget_exceeded)
+            && message.trim().eq_ignore_ascii_case("continue")
+        {
+            self.state.app.ignore_budget_next = true;
+        }
+
         self.state.is_waiting = true;
         self.state.current_response.clear();
         self.state.thinking_content.clear();
@@ -1161,8 +1456,16 @@ impl TuiApp {
     fn poll_ai_response(&mut self) -> Result<bool> {
         let mut changed = false;
         while let Some(response) = self.state.app.check_ai_response_nonblocking() {
+            // Anything else means the retried request went through (or gave up)
+            if !matches!(response, AiResponse::AgentRetrying(_)) {
+                self.state.retry_status = None;
+            }
             match response {
                 AiResponse::AgentStreamStart => {}
+                AiResponse::AgentRetrying(message) => {
+                    self.state.retry_status = Some(message);
+                    changed = true;
+                }
                 AiResponse::AgentStreamText(text) => {
                     let clean = clean_text(&text);
                     self.state.current_response.push_str(&clean);
@@ -1197,7 +1500,10 @@ impl TuiApp {
                         .retain(|t| t.status == ToolState::Running || t.id == id);
 
                     // Log tool call to history so it scrolls up
-                    self.state.add_tool_message(&name, &arguments);
+                    let verbosity = self.state.app.config.get_tool_verbosity();
+                    if verbosity != ToolVerbosity::Hidden {
+                        self.state.add_tool_message(&name, &arguments);
+                    }
 
                     // Update existing entry or push new
                     if let Some(existing) = self.state.active_tools.iter_mut().find(|t| t.id == id)
@@ -1265,8 +1571,26 @@ impl TuiApp {
                                 .as_millis();
                             spans.push(HistorySpan::new(format!(" • {}ms", duration_ms)).dim());
                         }
-                        self.state
-                            .push_history(HistoryKind::Tool, HistoryLine::new(spans));
+                        match self.state.app.config.get_tool_verbosity() {
+                            ToolVerbosity::Hidden => {}
+                            ToolVerbosity::Summary => {
+                                self.state
+                                    .push_history(HistoryKind::Tool, HistoryLine::new(spans));
+                            }
+                            ToolVerbosity::Full => {
+                                self.state
+                                    .push_history(HistoryKind::Tool, HistoryLine::new(spans));
+                                for line in Self::full_tool_output(&result).lines() {
+                                    self.state.push_history(
+                                        HistoryKind::Tool,
+                                        HistoryLine::new(vec![
+                                            HistorySpan::new("  │ ").dim(),
+                                            HistorySpan::new(line.to_string()),
+                                        ]),
+                                    );
+                                }
+                            }
+                        }
 
                         // Keep only running tools visible in the status list to avoid duplication.
                         self.state
@@ -1275,6 +1599,30 @@ impl TuiApp {
                     }
                     changed = true;
                 }
+                AiResponse::AgentBudgetExceeded(message) => {
+                    self.state.push_history(
+                        HistoryKind::Tool,
+                        HistoryLine::new(vec![
+                            HistorySpan::new("⚠ ").fg(Color::Yellow).bold(),
+                            HistorySpan::new(message).fg(Color::Yellow),
+                            HistorySpan::new(" — reply \"continue\" to keep going anyway").dim(),
+                        ]),
+                    );
+                    self.state.budget_exceeded = true;
+                    changed = true;
+                }
+                AiResponse::AgentFinished(reason) => {
+                    if let Some(notice) = reason.notice() {
+                        self.state.push_history(
+                            HistoryKind::Tool,
+                            HistoryLine::new(vec![
+                                HistorySpan::new("⚠ ").fg(Color::Yellow).bold(),
+                                HistorySpan::new(notice).fg(Color::Yellow),
+                            ]),
+                        );
+                        changed = true;
+                    }
+                }
                 AiResponse::AgentStreamEnd => {
                     let remaining = self.state.stream_collector.finalize();
                     if !remaining.is_empty() {
@@ -3,6 +3,11 @@
 //! This module implements patterns inspired by open-agent-sdk but using
 //! our existing reqwest-based infrastructure to avoid OpenSSL dependencies.
 
+use crate::api::api::{FinishReason, ResponseMetadata, Usage};
+use crate::api::http_client::RetryNotice;
+use crate::utils::budget::{BudgetExceeded, BudgetUsage};
+use crate::utils::config::{BudgetLimits, SamplingParams};
+use crate::utils::tool_output::DEFAULT_TOOL_RESULT_MAX_TOKENS;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -127,6 +132,41 @@ pub struct ToolSchema {
 }
 
 impl ToolSchema {
+    /// Why `args` can't be passed to this tool: not a JSON object, a required
+    /// parameter missing, or a parameter the tool doesn't take
+    pub fn check_arguments(&self, args: &Value) -> Result<(), String> {
+        let Some(object) = args.as_object() else {
+            return Err("arguments must be a JSON object".to_string());
+        };
+        let missing: Vec<&str> = self
+            .required
+            .iter()
+            .filter(|name| !object.contains_key(*name))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "missing required argument(s): {}",
+                missing.join(", ")
+            ));
+        }
+        let unknown: Vec<&str> = object
+            .keys()
+            .filter(|name| !self.parameters.contains_key(*name))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            let mut expected: Vec<&str> = self.parameters.keys().map(String::as_str).collect();
+            expected.sort();
+            return Err(format!(
+                "unknown argument(s): {} (takes {})",
+                unknown.join(", "),
+                expected.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
     pub fn to_openai_schema(&self) -> Value {
         let mut properties = serde_json::Map::new();
 
@@ -232,10 +272,43 @@ impl ToolRegistry {
         self.tools.write().unwrap().insert(name, arc_tool);
     }
 
+    /// Removes every tool for which `keep` returns false
+    pub fn retain<F: Fn(&str) -> bool>(&mut self, keep: F) {
+        self.tools.write().unwrap().retain(|name, _| keep(name));
+    }
+
     pub fn get_tools(&self) -> Vec<String> {
         self.tools.read().unwrap().keys().cloned().collect()
     }
 
+    /// Schemas of every registered tool, sorted by name
+    pub fn get_schemas(&self) -> Vec<ToolSchema> {
+        let mut schemas: Vec<ToolSchema> = self
+            .tools
+            .read()
+            .unwrap()
+            .values()
+            .map(|tool| tool.schema())
+            .collect();
+        schemas.sort_by(|a, b| a.name.cmp(&b.name));
+        schemas
+    }
+
+    /// Every tool as an OpenAI function-calling definition, sorted by name,
+    /// in a document that names the Arula version it came from
+    pub fn schema_document(&self) -> Value {
+        let tools: Vec<Value> = self
+            .get_schemas()
+            .iter()
+            .map(ToolSchema::to_openai_tool)
+            .collect();
+        json!({
+            "generator": format!("arula {}", crate::utils::updates::CURRENT_VERSION),
+            "format": "openai-function-calling",
+            "tools": tools,
+        })
+    }
+
     pub fn get_openai_tools(&self) -> Vec<Value> {
         self.tools
             .read()
@@ -245,10 +318,15 @@ impl ToolRegistry {
             .collect()
     }
 
-    pub async fn execute_tool(&self, name: &str, params: Value) -> Option<ToolResult> {
+    pub async fn execute_tool(&self, name: &str, mut params: Value) -> Option<ToolResult> {
         let tool = { self.tools.read().unwrap().get(name).cloned() };
 
         if let Some(tool) = tool {
+            let schema = tool.schema();
+            if schema.parameters.contains_key("path") {
+                let optional = !schema.required.iter().any(|r| r == "path");
+                crate::tools::sandbox::anchor_path_argument(&mut params, optional);
+            }
             Some(tool.execute_with_result(params).await)
         } else {
             None
@@ -319,8 +397,11 @@ pub struct AgentOptionsBuilder {
     model: Option<String>,
     temperature: Option<f32>,
     max_tokens: Option<u32>,
+    sampling: SamplingParams,
     auto_execute_tools: bool,
     max_tool_iterations: u32,
+    budget: BudgetLimits,
+    tool_result_max_tokens: usize,
     debug: bool,
     streaming: bool,
 }
@@ -338,8 +419,11 @@ impl AgentOptionsBuilder {
             model: None,
             temperature: None,
             max_tokens: None,
+            sampling: SamplingParams::default(),
             auto_execute_tools: true,
             max_tool_iterations: 50,
+            budget: BudgetLimits::default(),
+            tool_result_max_tokens: DEFAULT_TOOL_RESULT_MAX_TOKENS,
             debug: false,
             streaming: true,
         }
@@ -365,6 +449,11 @@ impl AgentOptionsBuilder {
         self
     }
 
+    pub fn sampling(mut self, sampling: SamplingParams) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
     pub fn auto_execute_tools(mut self, auto_execute: bool) -> Self {
         self.auto_execute_tools = auto_execute;
         self
@@ -375,6 +464,16 @@ impl AgentOptionsBuilder {
         self
     }
 
+    pub fn budget(mut self, budget: BudgetLimits) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn tool_result_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.tool_result_max_tokens = max_tokens;
+        self
+    }
+
     pub fn debug(mut self, debug: bool) -> Self {
         self.debug = debug;
         self
@@ -392,9 +491,13 @@ impl AgentOptionsBuilder {
                 .unwrap_or_else(|| "You are a helpful AI assistant.".to_string()),
             model: self.model.unwrap_or_else(|| "gpt-3.5-turbo".to_string()),
             temperature: self.temperature.unwrap_or(0.7),
-            max_tokens: self.max_tokens.unwrap_or(2048),
+            max_tokens: self.max_tokens.unwrap_or(4096),
+            sampling: self.sampling,
             auto_execute_tools: self.auto_execute_tools,
             max_tool_iterations: self.max_tool_iterations,
+            budget: self.budget,
+            budget_usage: BudgetUsage::default(),
+            tool_result_max_tokens: self.tool_result_max_tokens,
             debug: self.debug,
             streaming: self.streaming,
         }
@@ -408,8 +511,15 @@ pub struct AgentOptions {
     pub model: String,
     pub temperature: f32,
     pub max_tokens: u32,
+    /// top_p, penalties, stop sequences and seed
+    pub sampling: SamplingParams,
     pub auto_execute_tools: bool,
     pub max_tool_iterations: u32,
+    pub budget: BudgetLimits,
+    /// What the conversation has used so far, counted against `budget`
+    pub budget_usage: BudgetUsage,
+    /// Tool results larger than this are head/tail-truncated in the history
+    pub tool_result_max_tokens: usize,
     pub debug: bool,
     pub streaming: bool,
 }
@@ -449,6 +559,30 @@ pub enum ContentBlock {
         question: String,
         options: Option<Vec<String>>,
     },
+    /// A budget limit ended the run; the frontend may offer to continue anyway
+    BudgetExceeded {
+        exceeded: BudgetExceeded,
+    },
+    /// A failed request is about to be retried; shown as a transient status
+    Retrying {
+        notice: RetryNotice,
+    },
+    /// The tool calls of the agent loop's round `round` (from 1) are about to run
+    ToolRound {
+        round: u32,
+    },
+    /// Token usage of one model request
+    Usage {
+        usage: Usage,
+    },
+    /// Provider details about the response
+    Metadata {
+        metadata: ResponseMetadata,
+    },
+    /// Why the model stopped; sent at the end of each model request
+    Finish {
+        reason: FinishReason,
+    },
     Error {
         error: String,
     },
@@ -486,3 +620,117 @@ impl ContentBlock {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit_schema() -> ToolSchema {
+        ToolSchemaBuilder::new("edit_file", "Edit a file")
+            .param("path", "string")
+            .required("path")
+            .param("content", "string")
+            .required("content")
+            .param("create", "boolean")
+            .build()
+    }
+
+    struct SchemaOnly(ToolSchema);
+
+    #[async_trait]
+    impl Tool for SchemaOnly {
+        type Params = Value;
+        type Result = Value;
+
+        fn name(&self) -> &str {
+            &self.0.name
+        }
+
+        fn description(&self) -> &str {
+            &self.0.description
+        }
+
+        fn schema(&self) -> ToolSchema {
+            self.0.clone()
+        }
+
+        async fn execute(&self, params: Value) -> Result<Value, String> {
+            Ok(params)
+        }
+    }
+
+    #[test]
+    fn test_check_arguments_accepts_known_arguments() {
+        let schema = edit_schema();
+        assert!(schema
+            .check_arguments(&json!({"path": "a.txt", "content": "hi"}))
+            .is_ok());
+        assert!(schema
+            .check_arguments(&json!({"path": "a.txt", "content": "hi", "create": true}))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_check_arguments_reports_missing() {
+        let error = edit_schema()
+            .check_arguments(&json!({"create": true}))
+            .unwrap_err();
+        assert_eq!(error, "missing required argument(s): path, content");
+    }
+
+    #[test]
+    fn test_check_arguments_reports_unknown() {
+        let error = edit_schema()
+            .check_arguments(&json!({"path": "a.txt", "content": "hi", "mode": "w"}))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "unknown argument(s): mode (takes content, create, path)"
+        );
+    }
+
+    #[test]
+    fn test_check_arguments_rejects_non_objects() {
+        let schema = edit_schema();
+        for args in [json!(null), json!("a.txt"), json!(["a.txt", "hi"])] {
+            assert_eq!(
+                schema.check_arguments(&args).unwrap_err(),
+                "arguments must be a JSON object"
+            );
+        }
+    }
+
+    #[test]
+    fn test_schema_document_lists_tools_by_name() {
+        let mut registry = ToolRegistry::new();
+        registry.register(SchemaOnly(edit_schema()));
+        registry.register(SchemaOnly(
+            ToolSchemaBuilder::new("bash", "Run a command")
+                .param("command", "string")
+                .required("command")
+                .build(),
+        ));
+
+        let document = registry.schema_document();
+        assert_eq!(document["format"], "openai-function-calling");
+        assert_eq!(
+            document["generator"],
+            format!("arula {}", crate::utils::updates::CURRENT_VERSION)
+        );
+
+        let tools = document["tools"].as_array().unwrap();
+        let names: Vec<&str> = tools
+            .iter()
+            .map(|tool| tool["function"]["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["bash", "edit_file"]);
+
+        let edit = &tools[1];
+        assert_eq!(edit["type"], "function");
+        assert_eq!(edit["function"]["description"], "Edit a file");
+        let parameters = &edit["function"]["parameters"];
+        assert_eq!(parameters["type"], "object");
+        assert_eq!(parameters["properties"]["create"]["type"], "boolean");
+        assert_eq!(parameters["required"], json!(["path", "content"]));
+    }
+}
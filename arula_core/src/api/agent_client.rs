@@ -4,17 +4,22 @@
 //! patterns while integrating with the existing reqwest-based API client.
 
 use crate::api::agent::{AgentOptions, ContentBlock, ToolRegistry};
-use crate::api::api::{ApiClient, ChatMessage};
+use crate::api::api::{ApiClient, ApiResponse, ChatMessage, FinishReason, ResponseMetadata};
+use crate::api::history;
 use crate::tools::tools::{create_basic_tool_registry, initialize_mcp_tools};
-use crate::utils::config::Config;
+use crate::tools::sandbox;
+use crate::utils::budget::{BudgetTracker, BudgetUsage};
+use crate::utils::config::{BudgetLimits, Config, SamplingParams};
 use crate::utils::debug::debug_print;
 use crate::utils::error_utils::{api_error, stream_error, ErrorContext};
+use crate::utils::tool_output::shrink_for_history;
 use anyhow::Result;
 use futures::Stream;
 use serde_json::json;
 use std::pin::Pin;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::Instrument;
 
 /// Modern AI Agent Client
 pub struct AgentClient {
@@ -60,7 +65,11 @@ impl AgentClient {
         options: AgentOptions,
         config: &crate::utils::config::Config,
     ) -> Self {
-        let api_client = ApiClient::new(provider, endpoint, api_key, model);
+        let provider_config = config.providers.get(&provider);
+        let api_client = ApiClient::new(provider, endpoint, api_key, model)
+            .with_sampling(options.temperature, options.sampling.clone())
+            .with_max_tokens(options.max_tokens)
+            .with_provider_config(provider_config);
         let tool_registry = create_basic_tool_registry();
 
         Self {
@@ -81,7 +90,11 @@ impl AgentClient {
         config: &crate::utils::config::Config,
         tool_registry: crate::api::agent::ToolRegistry,
     ) -> Self {
-        let api_client = ApiClient::new(provider, endpoint, api_key, model);
+        let provider_config = config.providers.get(&provider);
+        let api_client = ApiClient::new(provider, endpoint, api_key, model)
+            .with_sampling(options.temperature, options.sampling.clone())
+            .with_max_tokens(options.max_tokens)
+            .with_provider_config(provider_config);
 
         Self {
             api_client,
@@ -98,6 +111,44 @@ impl AgentClient {
         Self::new(provider, endpoint, api_key, model, options, &config)
     }
 
+    /// System prompt sent ahead of the conversation
+    pub fn system_prompt(&self) -> &str {
+        &self.options.system_prompt
+    }
+
+    /// Use a session's temperature and sampling parameters for the following requests
+    pub fn set_sampling(&mut self, temperature: f32, sampling: SamplingParams) {
+        self.options.temperature = temperature;
+        self.options.sampling = sampling.clone();
+        self.api_client = self.api_client.clone().with_sampling(temperature, sampling);
+    }
+
+    /// Cap the responses of the following requests at `max_tokens` tokens
+    pub fn set_max_tokens(&mut self, max_tokens: u32) {
+        self.options.max_tokens = max_tokens;
+        self.api_client = self.api_client.clone().with_max_tokens(max_tokens);
+    }
+
+    /// Replace the budget limits of the following runs
+    pub fn set_budget(&mut self, budget: BudgetLimits) {
+        self.options.budget = budget;
+    }
+
+    /// Count the following runs against `usage`, the session's running totals
+    pub fn set_budget_usage(&mut self, usage: BudgetUsage) {
+        self.options.budget_usage = usage;
+    }
+
+    /// The session totals the following runs count against
+    pub fn budget_usage(&self) -> &BudgetUsage {
+        &self.options.budget_usage
+    }
+
+    /// Budget check for a run: this client's limits over its session's usage
+    fn budget_tracker(&self) -> BudgetTracker {
+        BudgetTracker::with_usage(self.options.budget.clone(), self.options.budget_usage.clone())
+    }
+
     /// Check if streaming is enabled in the configuration
     pub fn is_streaming_enabled(&self) -> bool {
         self.config.get_streaming_enabled()
@@ -141,15 +192,18 @@ impl AgentClient {
         let api_client = self.api_client.clone();
         let auto_execute_tools = self.options.auto_execute_tools;
         let max_tool_iterations = self.options.max_tool_iterations;
+        let budget = self.budget_tracker();
+        let tool_result_max_tokens = self.options.tool_result_max_tokens;
         let config_clone = self.config.clone();
 
         // Get tools from registry
         let tools = self.tool_registry.get_openai_tools();
 
         // Build messages
+        let conversation_history = self.trim_history(conversation_history).await;
         let messages = self.build_api_messages(message, conversation_history)?;
 
-        tokio::spawn(async move {
+        tokio::spawn(sandbox::inherit_workdir(async move {
             // Re-initialize MCP tools if needed (though get_openai_tools above implies they are loaded)
             // But clone needs re-init if it creates a fresh registry? AgentClient::clone does basic registry.
             // We should use the registry we have.
@@ -222,6 +276,26 @@ impl AgentClient {
                             options,
                         });
                     }
+                    StreamEvent::BudgetExceeded(exceeded) => {
+                        let _ = tx_for_callback.send(ContentBlock::BudgetExceeded { exceeded });
+                    }
+                    StreamEvent::Retrying(notice) => {
+                        let _ = tx_for_callback.send(ContentBlock::Retrying { notice });
+                    }
+                    StreamEvent::ToolRound(round) => {
+                        let _ = tx_for_callback.send(ContentBlock::ToolRound { round });
+                    }
+                    StreamEvent::Metadata(metadata) => {
+                        let _ = tx_for_callback.send(ContentBlock::Metadata { metadata });
+                    }
+                    StreamEvent::Finish { reason, usage } => {
+                        if let Some(usage) = usage {
+                            let _ = tx_for_callback.send(ContentBlock::Usage { usage });
+                        }
+                        let _ = tx_for_callback.send(ContentBlock::Finish {
+                            reason: FinishReason::from_provider(&reason),
+                        });
+                    }
                     _ => {}
                 }
             };
@@ -236,6 +310,8 @@ impl AgentClient {
                 &execution_registry,
                 auto_execute_tools,
                 max_tool_iterations,
+                budget,
+                tool_result_max_tokens,
                 callback,
             )
             .await;
@@ -246,11 +322,49 @@ impl AgentClient {
                 let error_msg = stream_error(error_context);
                 let _ = tx.send(ContentBlock::error(error_msg));
             }
-        });
+        }.instrument(tracing::Span::current())));
 
         Ok(Box::pin(UnboundedReceiverStream::new(rx)))
     }
 
+    /// Run the agent loop on `message` until the model is done, passing every
+    /// event to `callback`.
+    ///
+    /// Unlike `query_streaming`, a failed request comes back as its typed error
+    /// (`ApiError`, `ZAIApiError`, ...) instead of a message, so one-shot
+    /// callers can tell an auth failure from a network one.
+    pub async fn run<F>(
+        &self,
+        message: &str,
+        conversation_history: Option<Vec<ChatMessage>>,
+        callback: F,
+    ) -> Result<ApiResponse>
+    where
+        F: FnMut(crate::api::stream::StreamEvent) + Send,
+    {
+        let tools = self.tool_registry.get_openai_tools();
+        let conversation_history = self.trim_history(conversation_history).await;
+        let messages = self.build_api_messages(message, conversation_history)?;
+
+        let mut execution_registry = create_basic_tool_registry();
+        if let Err(e) = initialize_mcp_tools(&mut execution_registry, &self.config).await {
+            debug_print(&format!("⚠️ Failed to initialize MCP tools: {}", e));
+        }
+
+        crate::api::stream::stream_with_tools(
+            &self.api_client,
+            messages,
+            &tools,
+            &execution_registry,
+            self.options.auto_execute_tools,
+            self.options.max_tool_iterations,
+            self.budget_tracker(),
+            self.options.tool_result_max_tokens,
+            callback,
+        )
+        .await
+    }
+
     /// Send a message with non-streaming mode (waits for complete response)
     ///
     /// This method waits for the complete API response before returning it.
@@ -274,6 +388,8 @@ impl AgentClient {
         let api_client = self.api_client.clone();
         let auto_execute_tools = self.options.auto_execute_tools;
         let max_tool_iterations = self.options.max_tool_iterations;
+        let budget = self.budget_tracker();
+        let tool_result_max_tokens = self.options.tool_result_max_tokens;
         let debug = self.options.debug;
         let config_clone = self.config.clone();
         let tx_clone = tx.clone();
@@ -282,9 +398,10 @@ impl AgentClient {
         let tools = self.tool_registry.get_openai_tools();
 
         // Build messages
+        let conversation_history = self.trim_history(conversation_history).await;
         let messages = self.build_api_messages(message, conversation_history)?;
 
-        tokio::spawn(async move {
+        tokio::spawn(sandbox::inherit_workdir(async move {
             // Create tool registry for execution
             let mut execution_registry = create_basic_tool_registry();
             if let Err(e) = initialize_mcp_tools(&mut execution_registry, &config_clone).await {
@@ -300,6 +417,8 @@ impl AgentClient {
                 tx,
                 auto_execute_tools,
                 max_tool_iterations,
+                budget,
+                tool_result_max_tokens,
                 debug,
                 &execution_registry,
             )
@@ -310,7 +429,7 @@ impl AgentClient {
                 let error_msg = api_error(error_context);
                 let _ = tx_clone.send(ContentBlock::error(error_msg));
             }
-        });
+        }.instrument(tracing::Span::current())));
 
         Ok(Box::pin(UnboundedReceiverStream::new(rx)))
     }
@@ -323,6 +442,8 @@ impl AgentClient {
         tx: mpsc::UnboundedSender<ContentBlock>,
         auto_execute_tools: bool,
         max_tool_iterations: u32,
+        mut budget: BudgetTracker,
+        tool_result_max_tokens: usize,
         debug: bool,
         tool_registry: &crate::api::agent::ToolRegistry,
     ) -> Result<()> {
@@ -335,6 +456,12 @@ impl AgentClient {
                 break;
             }
 
+            if let Some(exceeded) = budget.check() {
+                tracing::warn!(%exceeded, "Budget limit reached, stopping agent loop");
+                let _ = tx.send(ContentBlock::BudgetExceeded { exceeded });
+                break;
+            }
+
             if debug {
                 debug_print(&format!("Non-streaming iteration {}", iterations + 1));
             }
@@ -343,6 +470,20 @@ impl AgentClient {
             let response = api_client
                 .send_message_with_tools_sync(&current_messages, &tools)
                 .await?;
+            if let Some(usage) = &response.usage {
+                budget.add_usage(api_client.model(), usage);
+                let _ = tx.send(ContentBlock::Usage {
+                    usage: usage.clone(),
+                });
+            }
+            if response.model.is_some() {
+                let _ = tx.send(ContentBlock::Metadata {
+                    metadata: ResponseMetadata {
+                        model: response.model.clone(),
+                        ..Default::default()
+                    },
+                });
+            }
 
             // Send reasoning/thinking content if present
             if let Some(ref reasoning) = response.reasoning_content {
@@ -385,6 +526,7 @@ impl AgentClient {
                                     tool_calls: Some(vec![tool_call.clone()]),
                                     tool_call_id: None,
                                     tool_name: None,
+                                    images: Vec::new(),
                                 });
 
                                 // Execute the tool
@@ -425,14 +567,16 @@ impl AgentClient {
                                 // Add tool result to messages
                                 current_messages.push(crate::api::api::ChatMessage {
                                     role: "tool".to_string(),
-                                    content: Some(result_content),
+                                    content: Some(shrink_for_history(&result_content, tool_result_max_tokens).into_owned()),
                                     tool_calls: None,
                                     tool_call_id: Some(tool_call.id.clone()),
                                     tool_name: Some(tool_call.function.name.clone()),
+                                    images: Vec::new(),
                                 });
 
                                 // Continue the loop for another iteration
                                 iterations += 1;
+                                budget.add_tool_iteration();
                                 continue;
                             }
                         }
@@ -482,6 +626,7 @@ impl AgentClient {
                                 tool_calls: Some(vec![tool_call.clone()]),
                                 tool_call_id: None,
                                 tool_name: None,
+                                images: Vec::new(),
                             });
 
                             // Execute the tool
@@ -522,14 +667,16 @@ impl AgentClient {
                             // Add tool result to messages
                             current_messages.push(crate::api::api::ChatMessage {
                                 role: "tool".to_string(),
-                                content: Some(result_content),
+                                content: Some(shrink_for_history(&result_content, tool_result_max_tokens).into_owned()),
                                 tool_calls: None,
                                 tool_call_id: Some(tool_call.id.clone()),
                                 tool_name: Some(tool_call.function.name.clone()),
+                                images: Vec::new(),
                             });
 
                             // Continue the loop for another iteration
                             iterations += 1;
+                            budget.add_tool_iteration();
                             continue;
                         }
                     }
@@ -573,6 +720,7 @@ impl AgentClient {
                         tool_calls: Some(calls.clone()),
                         tool_call_id: None,
                         tool_name: None,
+                        images: Vec::new(),
                     });
 
                     // Execute each tool call
@@ -622,15 +770,17 @@ impl AgentClient {
                         // Add tool result to messages
                         current_messages.push(ChatMessage {
                             role: "tool".to_string(),
-                            content: Some(result_content),
+                            content: Some(shrink_for_history(&result_content, tool_result_max_tokens).into_owned()),
                             tool_calls: None,
                             tool_call_id: Some(tool_call.id.clone()),
                             tool_name: Some(tool_call.function.name.clone()),
+                            images: Vec::new(),
                         });
                     }
 
                     // Continue the loop for another iteration
                     iterations += 1;
+                    budget.add_tool_iteration();
                     continue;
                 } else {
                     // Tool calls present but auto-execute disabled
@@ -645,6 +795,11 @@ impl AgentClient {
             }
 
             // No more tool calls, we're done
+            let reason = match &response.tool_calls {
+                Some(calls) if !calls.is_empty() => FinishReason::ToolCalls,
+                _ => FinishReason::Stop,
+            };
+            let _ = tx.send(ContentBlock::Finish { reason });
             break;
         }
 
@@ -686,6 +841,15 @@ impl AgentClient {
         self.tool_registry.get_tools()
     }
 
+    /// Cut the history down with the configured strategy for this model
+    async fn trim_history(&self, history: Option<Vec<ChatMessage>>) -> Option<Vec<ChatMessage>> {
+        let strategy = self.config.get_history_strategy(self.api_client.model());
+        match history {
+            Some(history) => Some(history::apply(history, &strategy, &self.config).await),
+            None => None,
+        }
+    }
+
     /// Build API messages from user message and conversation history
     fn build_api_messages(
         &self,
@@ -707,6 +871,7 @@ impl AgentClient {
                     tool_calls: None,
                     tool_call_id: None,
                     tool_name: None,
+                    images: Vec::new(),
                 });
             }
 
@@ -730,6 +895,7 @@ impl AgentClient {
                     tool_calls: None,
                     tool_call_id: None,
                     tool_name: None,
+                    images: Vec::new(),
                 });
             }
         } else {
@@ -740,6 +906,7 @@ impl AgentClient {
                 tool_calls: None,
                 tool_call_id: None,
                 tool_name: None,
+                images: Vec::new(),
             });
 
             messages.push(ChatMessage {
@@ -748,6 +915,7 @@ impl AgentClient {
                 tool_calls: None,
                 tool_call_id: None,
                 tool_name: None,
+                images: Vec::new(),
             });
         }
 
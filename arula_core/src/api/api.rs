@@ -2,8 +2,15 @@ use anyhow::{anyhow, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use crate::api::http_client;
+use crate::utils::config::{ProviderConfig, SamplingParams};
+use crate::utils::error::ApiError;
+use crate::utils::http_capture;
+use crate::utils::inspector;
+
 // Z.AI specific error types
 #[derive(Debug, thiserror::Error)]
 pub enum ZAIApiError {
@@ -52,13 +59,9 @@ impl ZAIApiError {
     }
 }
 
-/// Debug print helper that checks ARULA_DEBUG environment variable
+/// Debug print helper; written to the log file at debug level
 fn debug_print(msg: &str) {
-    if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-        println!("🔧 DEBUG: {}", msg);
-        // Also log to file
-        crate::utils::logger::debug(msg);
-    }
+    tracing::debug!("{}", msg);
 }
 
 /// Log raw HTTP request details
@@ -119,7 +122,7 @@ fn log_http_response(response: &reqwest::Response) {
     crate::utils::logger::info(&log_msg);
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChatMessage {
     pub role: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -131,19 +134,26 @@ pub struct ChatMessage {
     /// Tool name for Ollama tool responses (Ollama uses tool_name instead of tool_call_id)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_name: Option<String>,
+    /// Images sent along with the text, as `data:` URLs (vision input)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ToolCall {
     pub id: String,
     pub r#type: String,
     pub function: ToolCallFunction,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ToolCallFunction {
     pub name: String,
     pub arguments: String,
+    /// The streamed arguments were cut off or garbled and only survive as a
+    /// repair, so the call is answered with an error instead of being run
+    #[serde(skip)]
+    pub truncated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -151,6 +161,171 @@ pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    /// Prompt tokens written to the provider's prompt cache (Anthropic)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_input_tokens: Option<u32>,
+    /// Prompt tokens served from the provider's prompt cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_input_tokens: Option<u32>,
+}
+
+/// Why the model stopped generating a response
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// Natural end of the answer or a stop sequence
+    #[default]
+    Stop,
+    /// Hit the max token limit; the answer is cut off
+    Length,
+    /// The model asked for tools
+    ToolCalls,
+    /// The provider filtered or refused the output
+    ContentFilter,
+    /// Any other provider-specific reason
+    Other(String),
+}
+
+impl FinishReason {
+    /// Map a provider's reason string (OpenAI, Anthropic, Ollama, Gemini spellings)
+    pub fn from_provider(reason: &str) -> Self {
+        match reason {
+            "" | "stop" | "end_turn" | "stop_sequence" | "STOP" => FinishReason::Stop,
+            "length" | "max_tokens" | "MAX_TOKENS" => FinishReason::Length,
+            // `ask_question` ends the loop to wait for the user's answer
+            "tool_calls" | "tool_use" | "function_call" | "ask_question" => FinishReason::ToolCalls,
+            "content_filter" | "refusal" | "SAFETY" => FinishReason::ContentFilter,
+            other => FinishReason::Other(other.to_string()),
+        }
+    }
+
+    /// Short label for display
+    pub fn label(&self) -> &str {
+        match self {
+            FinishReason::Stop => "stop",
+            FinishReason::Length => "length",
+            FinishReason::ToolCalls => "tool",
+            FinishReason::ContentFilter => "content filter",
+            FinishReason::Other(reason) => reason,
+        }
+    }
+
+    /// Explanation for reasons the user should know about (the answer is incomplete)
+    pub fn notice(&self) -> Option<String> {
+        match self {
+            FinishReason::Stop | FinishReason::ToolCalls => None,
+            FinishReason::Length => {
+                Some("Response cut off: the model hit its max token limit".to_string())
+            }
+            FinishReason::ContentFilter => {
+                Some("Response stopped by the provider's content filter".to_string())
+            }
+            FinishReason::Other(reason) => Some(format!("Response ended early ({})", reason)),
+        }
+    }
+}
+
+/// Provider details about a response, for the inspector and logs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseMetadata {
+    /// Provider's response id (e.g. `chatcmpl-...`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Model that actually answered, which may differ from the requested one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Upstream provider chosen by a router such as OpenRouter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// OpenAI backend configuration fingerprint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+}
+
+/// Request body layouts that take sampling parameters differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestFormat {
+    /// OpenAI chat completions and compatible APIs (OpenRouter, Z.AI, custom)
+    OpenAI,
+    /// Anthropic Messages API (no penalties or seed)
+    Anthropic,
+    /// Ollama's native `/api/chat`, which takes them under `options`
+    Ollama,
+}
+
+/// Add the sampling parameters that are set to a request body
+pub fn apply_sampling(request: &mut Value, sampling: &SamplingParams, format: RequestFormat) {
+    let target = match format {
+        RequestFormat::Ollama => {
+            if !request["options"].is_object() {
+                request["options"] = json!({});
+            }
+            &mut request["options"]
+        }
+        RequestFormat::OpenAI | RequestFormat::Anthropic => request,
+    };
+
+    if let Some(top_p) = sampling.top_p {
+        target["top_p"] = json!(top_p);
+    }
+    if !sampling.stop.is_empty() {
+        let key = match format {
+            RequestFormat::Anthropic => "stop_sequences",
+            RequestFormat::OpenAI | RequestFormat::Ollama => "stop",
+        };
+        target[key] = json!(sampling.stop);
+    }
+    if format == RequestFormat::Anthropic {
+        return;
+    }
+    if let Some(penalty) = sampling.frequency_penalty {
+        target["frequency_penalty"] = json!(penalty);
+    }
+    if let Some(penalty) = sampling.presence_penalty {
+        target["presence_penalty"] = json!(penalty);
+    }
+    if let Some(seed) = sampling.seed {
+        target["seed"] = json!(seed);
+    }
+}
+
+/// Mark the stable prefix of an Anthropic Messages request (system prompt,
+/// which carries the project manifest, and tool schemas) with `cache_control`
+/// so follow-up turns read it from Anthropic's prompt cache
+pub fn apply_prompt_caching(request: &mut Value) {
+    let cache_control = json!({"type": "ephemeral"});
+    if let Some(system) = request["system"].as_str().map(str::to_string) {
+        request["system"] = json!([{
+            "type": "text",
+            "text": system,
+            "cache_control": cache_control,
+        }]);
+    } else if let Some(last) = request["system"].as_array_mut().and_then(|b| b.last_mut()) {
+        last["cache_control"] = cache_control.clone();
+    }
+    // Tools come before the system prompt in Anthropic's cache order, so a
+    // breakpoint on the last tool covers all of them
+    if let Some(last) = request["tools"].as_array_mut().and_then(|t| t.last_mut()) {
+        last["cache_control"] = cache_control;
+    }
+}
+
+/// Merge a provider's `extra_body` into a request body: objects merge key by
+/// key, `null` removes the key and anything else replaces the value
+pub fn merge_json(request: &mut Value, patch: &Value) {
+    let (Some(target), Some(patch)) = (request.as_object_mut(), patch.as_object()) else {
+        *request = patch.clone();
+        return;
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else if value.is_object() && target.get(key).is_some_and(Value::is_object) {
+            merge_json(&mut target[key], value);
+        } else {
+            target.insert(key.clone(), value.clone());
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -200,6 +375,12 @@ pub enum AIProvider {
     Ollama,
     ZAiCoding,
     OpenRouter,
+    XAi,
+    DeepSeek,
+    /// LM Studio's OpenAI-compatible local server
+    LmStudio,
+    /// llamafile (llama.cpp server) OpenAI-compatible local server
+    Llamafile,
     Custom,
 }
 
@@ -210,6 +391,15 @@ pub struct ApiClient {
     pub endpoint: String,
     api_key: String,
     model: String,
+    temperature: f32,
+    /// Cap on the tokens generated per response
+    max_tokens: u32,
+    sampling: SamplingParams,
+    extra_headers: HashMap<String, String>,
+    extra_body: Option<Value>,
+    retry_policy: http_client::RetryPolicy,
+    /// Overall timeout of non-streaming requests
+    request_timeout: Option<Duration>,
 }
 
 impl ApiClient {
@@ -221,6 +411,10 @@ impl ApiClient {
             "ollama" => AIProvider::Ollama,
             "z.ai coding plan" | "z.ai" | "zai" => AIProvider::ZAiCoding,
             "openrouter" => AIProvider::OpenRouter,
+            "xai" | "x.ai" | "grok" => AIProvider::XAi,
+            "deepseek" => AIProvider::DeepSeek,
+            "lm studio" | "lmstudio" | "lm-studio" => AIProvider::LmStudio,
+            "llamafile" => AIProvider::Llamafile,
             _ => AIProvider::Custom,
         };
 
@@ -245,6 +439,14 @@ impl ApiClient {
                 .trim_end_matches("/chat/completions")
                 .to_string()
         };
+        // These OpenAI-compatible APIs only serve under /v1
+        let normalized_endpoint = match provider_type {
+            AIProvider::XAi
+            | AIProvider::DeepSeek
+            | AIProvider::LmStudio
+            | AIProvider::Llamafile => format!("{}/v1", normalized_endpoint),
+            _ => normalized_endpoint,
+        };
 
         if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
             debug_print(&format!(
@@ -259,16 +461,13 @@ impl ApiClient {
             debug_print(&format!("DEBUG: Model = {}", model));
         }
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(60))
-            .user_agent("arula-cli/1.0")
-            .http1_title_case_headers()
-            .tcp_nodelay(true)
-            .connection_verbose(std::env::var("ARULA_DEBUG").unwrap_or_default() == "1")
-            .pool_idle_timeout(Duration::from_secs(30))
-            .pool_max_idle_per_host(5)
-            .build()
-            .expect("Failed to create HTTP client");
+        // Share one pooled client across all providers so connections survive between turns
+        let client = if matches!(provider_type, AIProvider::ZAiCoding) {
+            // Z.AI only works over HTTP/1.1
+            http_client::get_http1_streaming_client().clone()
+        } else {
+            http_client::get_streaming_client().clone()
+        };
 
         // Initialize OpenAI client for streaming support
         Self {
@@ -277,7 +476,74 @@ impl ApiClient {
             endpoint: normalized_endpoint,
             api_key,
             model,
+            temperature: 0.7,
+            max_tokens: 4096,
+            sampling: SamplingParams::default(),
+            extra_headers: HashMap::new(),
+            extra_body: None,
+            retry_policy: http_client::RetryPolicy::default(),
+            request_timeout: None,
+        }
+    }
+
+    /// Use these sampling settings for every request
+    pub fn with_sampling(mut self, temperature: f32, sampling: SamplingParams) -> Self {
+        self.temperature = temperature;
+        self.sampling = sampling;
+        self
+    }
+
+    /// Cap every response at `max_tokens` generated tokens
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Apply the provider's HTTP settings: send its `extra_headers`, merge
+    /// its `extra_body` into every request and use its timeouts and retry policy
+    pub fn with_provider_config(mut self, provider: Option<&ProviderConfig>) -> Self {
+        let Some(provider) = provider else {
+            return self;
+        };
+        self.extra_headers = provider.extra_headers.clone();
+        self.extra_body = provider.extra_body.clone();
+
+        let connect_timeout = provider.connect_timeout_seconds.map(Duration::from_secs);
+        let read_timeout = provider.read_timeout_seconds.map(Duration::from_secs);
+        if connect_timeout.is_some() || read_timeout.is_some() {
+            self.client = http_client::get_streaming_client_with_timeouts(
+                connect_timeout,
+                read_timeout,
+                matches!(self.provider, AIProvider::ZAiCoding),
+            );
+        }
+        self.request_timeout = provider.timeout_seconds.map(Duration::from_secs);
+
+        let defaults = http_client::RetryPolicy::default();
+        self.retry_policy = http_client::RetryPolicy {
+            max_retries: provider.max_retries.unwrap_or(defaults.max_retries),
+            initial_delay: provider
+                .retry_delay_ms
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.initial_delay),
+            ..defaults
+        };
+        self
+    }
+
+    /// Add the configured extra headers and body fields to a request
+    fn apply_overrides(
+        &self,
+        mut request_builder: reqwest::RequestBuilder,
+        request_body: &mut Value,
+    ) -> reqwest::RequestBuilder {
+        for (name, value) in &self.extra_headers {
+            request_builder = request_builder.header(name, value);
         }
+        if let Some(extra_body) = &self.extra_body {
+            merge_json(request_body, extra_body);
+        }
+        request_builder
     }
 
     /// Get the current model name
@@ -285,17 +551,63 @@ impl ApiClient {
         &self.model
     }
 
+    /// Sampling temperature sent with requests
+    pub fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    /// Cap on the tokens generated per response
+    pub fn max_tokens(&self) -> u32 {
+        self.max_tokens
+    }
+
+    /// Sampling parameters beyond temperature
+    pub fn sampling(&self) -> &SamplingParams {
+        &self.sampling
+    }
+
+    /// How failed requests and dropped streams are retried
+    pub fn retry_policy(&self) -> &http_client::RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Body layout of this client's requests
+    pub fn request_format(&self) -> RequestFormat {
+        match self.provider {
+            AIProvider::Claude => RequestFormat::Anthropic,
+            AIProvider::Ollama => RequestFormat::Ollama,
+            _ if self.endpoint.contains("/api/anthropic") => RequestFormat::Anthropic,
+            _ => RequestFormat::OpenAI,
+        }
+    }
+
     /// Send a raw streaming request and return the HTTP response
     /// Used by the unified stream.rs module
     pub async fn make_streaming_request(
         &self,
         request_body: serde_json::Value,
+    ) -> Result<reqwest::Response> {
+        self.make_streaming_request_with_notices(request_body, |_| {})
+            .await
+    }
+
+    /// Like `make_streaming_request`, calling `on_retry` before each retry of
+    /// a failed attempt so the frontend can show it
+    pub async fn make_streaming_request_with_notices(
+        &self,
+        mut request_body: serde_json::Value,
+        on_retry: impl FnMut(&http_client::RetryNotice),
     ) -> Result<reqwest::Response> {
         // Align streaming endpoints with provider-specific REST paths
         let request_url = match self.provider {
             AIProvider::Ollama => format!("{}/api/chat", self.endpoint),
             AIProvider::Claude => format!("{}/v1/messages", self.endpoint),
-            AIProvider::OpenAI | AIProvider::OpenRouter => {
+            AIProvider::OpenAI
+            | AIProvider::OpenRouter
+            | AIProvider::XAi
+            | AIProvider::DeepSeek
+            | AIProvider::LmStudio
+            | AIProvider::Llamafile => {
                 format!("{}/chat/completions", self.endpoint)
             }
             AIProvider::ZAiCoding => {
@@ -320,7 +632,7 @@ impl ApiClient {
                     .header("x-api-key", &self.api_key)
                     .header("anthropic-version", "2023-06-01");
             }
-            AIProvider::OpenAI | AIProvider::OpenRouter => {
+            AIProvider::OpenAI | AIProvider::OpenRouter | AIProvider::XAi | AIProvider::DeepSeek => {
                 request_builder =
                     request_builder.header("Authorization", format!("Bearer {}", self.api_key));
             }
@@ -338,8 +650,8 @@ impl ApiClient {
                         .header("Accept-Language", "en-US,en");
                 }
             }
-            // Ollama usually doesn't need auth, but Custom might
-            AIProvider::Custom => {
+            // Ollama usually doesn't need auth, but Custom and local servers might
+            AIProvider::Custom | AIProvider::LmStudio | AIProvider::Llamafile => {
                 if !self.api_key.is_empty() {
                     request_builder =
                         request_builder.header("Authorization", format!("Bearer {}", self.api_key));
@@ -347,28 +659,32 @@ impl ApiClient {
             }
             _ => {}
         }
+        let request_builder = self.apply_overrides(request_builder, &mut request_body);
 
-        // Log the request if debug mode is enabled
-        if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-            let body_str = serde_json::to_string_pretty(&request_body).unwrap_or_default();
-            println!(
-                "🔧 DEBUG: Streaming request to {}: {}",
-                request_url, body_str
-            );
-        }
+        // Capture the request for the event inspector
+        inspector::record_request(&request_url, &request_body);
 
-        let response = request_builder.json(&request_body).send().await?;
+        let request = request_builder.json(&request_body).build()?;
+        http_capture::record_request(&request, &request_body, &self.extra_headers);
+        let response =
+            http_client::execute_with_retry(&self.client, request, &self.retry_policy, on_retry)
+                .await?;
+        http_capture::record_status(response.status());
 
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = http_client::retry_after(&response).map(|d| d.as_secs());
             let text = response.text().await.unwrap_or_default();
+            inspector::record_response(status, &text);
+            http_capture::record_response(&text);
 
             // Check for specific Z.AI errors
             if self.provider == AIProvider::ZAiCoding {
                 return Err(ZAIApiError::from_status_code(status.as_u16(), &text).into());
             }
 
-            return Err(anyhow!("API Error {}: {}", status, text));
+            let provider = format!("{:?}", self.provider);
+            return Err(ApiError::from_status(&provider, status, &text, retry_after).into());
         }
 
         Ok(response)
@@ -388,6 +704,7 @@ impl ApiClient {
             tool_calls: None,
             tool_call_id: None,
             tool_name: None,
+            images: Vec::new(),
         });
 
         // Add conversation history if provided
@@ -406,6 +723,7 @@ impl ApiClient {
             tool_calls: None,
             tool_call_id: None,
             tool_name: None,
+            images: Vec::new(),
         });
 
         // Use the unified send_request method without tools
@@ -423,18 +741,21 @@ impl ApiClient {
         let thinking_enabled = config.get_thinking_enabled().unwrap_or(false);
 
         // Debug: Check if thinking is enabled for Z.AI
-        if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-            eprintln!("🧠 DEBUG: thinking_enabled = {} for provider {:?}", thinking_enabled, self.provider);
-            eprintln!("🧠 DEBUG: endpoint = {}", self.endpoint);
-        }
+        tracing::debug!(
+            "thinking_enabled = {} for provider {:?}, endpoint = {}",
+            thinking_enabled,
+            self.provider,
+            self.endpoint
+        );
 
         // Build request body based on provider
-        let request_body = match self.provider {
+        let mut request_body = match self.provider {
             AIProvider::Claude => {
                 // Claude-specific request format
                 let mut request = json!({
                     "model": self.model,
-                    "max_tokens": 4096,
+                    "max_tokens": self.max_tokens,
+                    "temperature": self.temperature,
                     "messages": messages.iter().map(|msg| {
                         let mut msg_obj = json!({
                             "role": msg.role,
@@ -490,8 +811,8 @@ impl ApiClient {
 
                 // Add Ollama-specific options
                 request["options"] = json!({
-                    "temperature": 0.7,
-                    "num_predict": 4096
+                    "temperature": self.temperature,
+                    "num_predict": self.max_tokens
                 });
 
                 request
@@ -566,7 +887,7 @@ impl ApiClient {
 
                     let mut request = json!({
                         "model": self.model,
-                        "max_tokens": 4096,
+                        "max_tokens": self.max_tokens,
                         "messages": anthropic_messages,
                         "stream": false
                     });
@@ -577,14 +898,10 @@ impl ApiClient {
 
                     // Add thinking mode if enabled (for Z.AI Anthropic-compatible endpoint)
                     if thinking_enabled {
-                        if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                            eprintln!("🧠 DEBUG: Adding thinking block to Z.AI Anthropic-compatible request");
-                        }
+                        tracing::debug!("Adding thinking block to Z.AI Anthropic-compatible request");
                         request["thinking"] = serde_json::json!({
                             "type": "enabled"
                         });
-                    } else if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                        eprintln!("🧠 DEBUG: NOT adding thinking block - thinking_enabled is false");
                     }
 
                     // Convert tools to Anthropic format
@@ -691,7 +1008,13 @@ impl ApiClient {
                     request
                 }
             }
-            AIProvider::OpenAI | AIProvider::OpenRouter | AIProvider::Custom => {
+            AIProvider::OpenAI
+            | AIProvider::OpenRouter
+            | AIProvider::XAi
+            | AIProvider::DeepSeek
+            | AIProvider::LmStudio
+            | AIProvider::Llamafile
+            | AIProvider::Custom => {
                 // OpenAI-compatible request format
                 let mut request = json!({
                     "model": self.model,
@@ -716,8 +1039,8 @@ impl ApiClient {
 
                         msg_obj
                     }).collect::<Vec<_>>(),
-                    "temperature": 0.7,
-                    "max_tokens": 4096,
+                    "temperature": self.temperature,
+                    "max_tokens": self.max_tokens,
                     "stream": false
                 });
 
@@ -738,6 +1061,8 @@ impl ApiClient {
             }
         };
 
+        apply_sampling(&mut request_body, &self.sampling, self.request_format());
+
         // Determine the endpoint URL
         let endpoint_url = match self.provider {
             AIProvider::Ollama => format!("{}/api/chat", self.endpoint),
@@ -752,28 +1077,20 @@ impl ApiClient {
                     self.endpoint.clone()
                 }
             }
-            AIProvider::OpenAI | AIProvider::OpenRouter | AIProvider::Custom => {
+            AIProvider::OpenAI
+            | AIProvider::OpenRouter
+            | AIProvider::XAi
+            | AIProvider::DeepSeek
+            | AIProvider::LmStudio
+            | AIProvider::Llamafile
+            | AIProvider::Custom => {
                 format!("{}/chat/completions", self.endpoint)
             }
         };
 
-        // Create HTTP client
-        let client = if matches!(self.provider, AIProvider::ZAiCoding) {
-            // Create a new client specifically for Z.AI to force HTTP/1.1
-            Client::builder()
-                .timeout(Duration::from_secs(60))
-                .user_agent("arula-cli/1.0")
-                .http1_only() // Force HTTP/1.1 for Z.AI compatibility
-                .tcp_nodelay(true)
-                .connection_verbose(std::env::var("ARULA_DEBUG").unwrap_or_default() == "1")
-                .build()
-                .expect("Failed to create Z.AI HTTP client")
-        } else {
-            self.client.clone()
-        };
-
         // Build request with appropriate headers
-        let mut request_builder = client
+        let mut request_builder = self
+            .client
             .post(&endpoint_url)
             .header("Content-Type", "application/json");
 
@@ -784,7 +1101,7 @@ impl ApiClient {
                     .header("x-api-key", &self.api_key)
                     .header("anthropic-version", "2023-06-01");
             }
-            AIProvider::OpenAI | AIProvider::OpenRouter => {
+            AIProvider::OpenAI | AIProvider::OpenRouter | AIProvider::XAi | AIProvider::DeepSeek => {
                 if !self.api_key.is_empty() {
                     request_builder =
                         request_builder.header("Authorization", format!("Bearer {}", self.api_key));
@@ -801,7 +1118,7 @@ impl ApiClient {
                         request_builder.header("Authorization", format!("Bearer {}", self.api_key));
                 }
             }
-            AIProvider::Custom => {
+            AIProvider::Custom | AIProvider::LmStudio | AIProvider::Llamafile => {
                 if !self.api_key.is_empty() {
                     request_builder =
                         request_builder.header("Authorization", format!("Bearer {}", self.api_key));
@@ -809,34 +1126,34 @@ impl ApiClient {
             }
             _ => {} // Ollama usually doesn't need auth
         }
+        let request_builder = self.apply_overrides(request_builder, &mut request_body);
 
-        // Log the request if debug mode is enabled
-        if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-            let body_str = serde_json::to_string_pretty(&request_body).unwrap_or_default();
-            println!(
-                "🔧 DEBUG: Sending request to {}: {}",
-                endpoint_url, body_str
-            );
-        }
+        // Capture the request for the event inspector
+        inspector::record_request(&endpoint_url, &request_body);
+
+        // The shared client only bounds connecting and each read
+        let timeout = self.request_timeout.unwrap_or(http_client::REQUEST_TIMEOUT);
+        let request_builder = request_builder.timeout(timeout);
 
         // Send the request
-        let response = request_builder.json(&request_body).send().await?;
+        let request = request_builder.json(&request_body).build()?;
+        http_capture::record_request(&request, &request_body, &self.extra_headers);
+        let response =
+            http_client::execute_with_retry(&self.client, request, &self.retry_policy, |_| {})
+                .await?;
+        http_capture::record_status(response.status());
 
         // Handle the response
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = http_client::retry_after(&response).map(|d| d.as_secs());
             let text = response.text().await.unwrap_or_default();
 
-            // Log the response for debugging
-            if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                println!("🔧 DEBUG: API Response ({}): {}", status, text);
-            }
+            inspector::record_response(status, &text);
+            http_capture::record_response(&text);
 
-            return Err(anyhow::anyhow!(
-                "API request failed with status {}: {}",
-                status,
-                text
-            ));
+            let provider = format!("{:?}", self.provider);
+            return Err(ApiError::from_status(&provider, status, &text, retry_after).into());
         }
 
         // Parse response based on provider
@@ -845,9 +1162,8 @@ impl ApiClient {
                 let response_text = response.text().await?;
 
                 // Log the successful response if debug mode is enabled
-                if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                    println!("🔧 DEBUG: API Response (200 OK): {}", response_text);
-                }
+                inspector::record_response("200 OK", &response_text);
+                http_capture::record_response(&response_text);
 
                 let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
 
@@ -875,9 +1191,8 @@ impl ApiClient {
                 let response_text = response.text().await?;
 
                 // Log the successful response if debug mode is enabled
-                if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                    println!("🔧 DEBUG: API Response (200 OK): {}", response_text);
-                }
+                inspector::record_response("200 OK", &response_text);
+                http_capture::record_response(&response_text);
 
                 let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
 
@@ -903,9 +1218,8 @@ impl ApiClient {
                 let response_text = response.text().await?;
 
                 // Log the successful response if debug mode is enabled
-                if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                    println!("🔧 DEBUG: API Response (200 OK): {}", response_text);
-                }
+                inspector::record_response("200 OK", &response_text);
+                http_capture::record_response(&response_text);
 
                 let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
 
@@ -937,9 +1251,7 @@ impl ApiClient {
                                     // The thinking text is in the "thinking" field
                                     if let Some(thinking) = block.get("thinking").and_then(|t| t.as_str()) {
                                         thinking_content = Some(thinking.to_string());
-                                        if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                                            eprintln!("🧠 DEBUG: Found thinking block in Anthropic-format response");
-                                        }
+                                        tracing::debug!("Found thinking block in Anthropic-format response");
                                     }
                                 }
                                 "tool_use" => {
@@ -962,6 +1274,7 @@ impl ApiClient {
                                         function: ToolCallFunction {
                                             name,
                                             arguments: input.to_string(),
+                                            truncated: false,
                                         },
                                     });
                                 }
@@ -1025,14 +1338,19 @@ impl ApiClient {
                     })
                 }
             }
-            AIProvider::OpenAI | AIProvider::OpenRouter | AIProvider::Custom => {
+            AIProvider::OpenAI
+            | AIProvider::OpenRouter
+            | AIProvider::XAi
+            | AIProvider::DeepSeek
+            | AIProvider::LmStudio
+            | AIProvider::Llamafile
+            | AIProvider::Custom => {
                 // OpenAI-compatible response format
                 let response_text = response.text().await?;
 
                 // Log the successful response if debug mode is enabled
-                if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                    println!("🔧 DEBUG: API Response (200 OK): {}", response_text);
-                }
+                inspector::record_response("200 OK", &response_text);
+                http_capture::record_response(&response_text);
 
                 let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
 
@@ -1174,6 +1492,7 @@ impl ApiClient {
                                         .as_str()
                                         .unwrap_or_default()
                                         .to_string(),
+                                    truncated: false,
                                 },
                             })
                             .collect::<Vec<_>>()
@@ -1695,6 +2014,7 @@ impl ApiClient {
                                                         .as_str()
                                                         .unwrap_or_default()
                                                         .to_string(),
+                                                    truncated: false,
                                                 },
                                             })
                                             .collect::<Vec<_>>()
@@ -1705,6 +2025,7 @@ impl ApiClient {
                                     prompt_tokens: z_usage.prompt_tokens as u32,
                                     completion_tokens: z_usage.completion_tokens as u32,
                                     total_tokens: z_usage.total_tokens as u32,
+                                    ..Default::default()
                                 });
 
                                 return Ok(ApiResponse {
@@ -1739,8 +2060,8 @@ impl ApiClient {
 
                         // Log retry attempt
                         if attempt < max_retries {
-                            eprintln!(
-                                "🔄 Z.AI request failed (attempt {}/{}), retrying...: {}",
+                            tracing::warn!(
+                                "Z.AI request failed (attempt {}/{}), retrying...: {}",
                                 attempt + 1,
                                 max_retries + 1,
                                 api_error
@@ -1762,8 +2083,8 @@ impl ApiClient {
                 Err(e) => {
                     // Handle network errors
                     if attempt < max_retries {
-                        eprintln!(
-                            "🔄 Z.AI network error (attempt {}/{}) retrying...: {}",
+                        tracing::warn!(
+                            "Z.AI network error (attempt {}/{}) retrying...: {}",
                             attempt + 1,
                             max_retries + 1,
                             e
@@ -1874,6 +2195,7 @@ impl ApiClient {
                                         .as_str()
                                         .unwrap_or_default()
                                         .to_string(),
+                                    truncated: false,
                                 },
                             })
                             .collect::<Vec<_>>()
@@ -2112,6 +2434,7 @@ impl ApiClient {
                                         .as_str()
                                         .unwrap_or_default()
                                         .to_string(),
+                                    truncated: false,
                                 },
                             })
                             .collect::<Vec<_>>()
@@ -2122,6 +2445,7 @@ impl ApiClient {
                         completion_tokens: usage_info["completion_tokens"].as_u64().unwrap_or(0)
                             as u32,
                         total_tokens: usage_info["total_tokens"].as_u64().unwrap_or(0) as u32,
+                        ..Default::default()
                     });
 
                     return Ok(ApiResponse {
@@ -2215,6 +2539,7 @@ mod tests {
             tool_calls: None,
             tool_call_id: None,
             tool_name: None,
+            images: Vec::new(),
         }
     }
 
@@ -2225,6 +2550,7 @@ mod tests {
             function: ToolCallFunction {
                 name: "bash_tool".to_string(),
                 arguments: "{\"command\": \"echo hello\"}".to_string(),
+                truncated: false,
             },
         }
     }
@@ -2272,6 +2598,7 @@ mod tests {
             tool_calls: Some(vec![tool_call.clone()]),
             tool_call_id: None,
             tool_name: None,
+            images: Vec::new(),
         };
 
         // Test serialization
@@ -2314,6 +2641,7 @@ mod tests {
             prompt_tokens: 10,
             completion_tokens: 20,
             total_tokens: 30,
+            ..Default::default()
         };
 
         let json_str = serde_json::to_string(&usage).unwrap();
@@ -2333,6 +2661,7 @@ mod tests {
             prompt_tokens: 15,
             completion_tokens: 25,
             total_tokens: 40,
+            ..Default::default()
         };
 
         let response = ApiResponse {
@@ -2428,6 +2757,27 @@ mod tests {
         assert_eq!(client.provider, AIProvider::OpenAI);
     }
 
+    #[test]
+    fn test_local_server_endpoints() {
+        let client = ApiClient::new(
+            "LM Studio".into(),
+            "http://localhost:1234/v1/".into(),
+            String::new(),
+            "local-model".into(),
+        );
+        assert_eq!(client.provider, AIProvider::LmStudio);
+        assert_eq!(client.endpoint, "http://localhost:1234/v1");
+
+        let client = ApiClient::new(
+            "llamafile".into(),
+            "http://localhost:8080".into(),
+            String::new(),
+            "LLaMA_CPP".into(),
+        );
+        assert_eq!(client.provider, AIProvider::Llamafile);
+        assert_eq!(client.endpoint, "http://localhost:8080/v1");
+    }
+
     #[test]
     fn test_ai_provider_enum() {
         // Test all AIProvider variants can be created and compared
@@ -2454,6 +2804,7 @@ mod tests {
             tool_calls: None,
             tool_call_id: None,
             tool_name: None,
+            images: Vec::new(),
         };
 
         let json_str = serde_json::to_string(&empty_message).unwrap();
@@ -2468,6 +2819,7 @@ mod tests {
             tool_calls: Some(vec![create_test_tool_call()]),
             tool_call_id: None,
             tool_name: None,
+            images: Vec::new(),
         };
 
         let json_str = serde_json::to_string(&tool_only_message).unwrap();
@@ -2499,6 +2851,7 @@ mod tests {
             prompt_tokens: 5,
             completion_tokens: 10,
             total_tokens: 15,
+            ..Default::default()
         };
         let debug_str = format!("{:?}", usage);
         assert!(debug_str.contains("Usage"));
@@ -2514,6 +2867,7 @@ mod tests {
             tool_calls: None,
             tool_call_id: None,
             tool_name: None,
+            images: Vec::new(),
         };
 
         let json_str = serde_json::to_string(&special_message).unwrap();
@@ -2527,10 +2881,92 @@ mod tests {
             tool_calls: None,
             tool_call_id: None,
             tool_name: None,
+            images: Vec::new(),
         };
 
         let json_str = serde_json::to_string(&unicode_message).unwrap();
         let deserialized: ChatMessage = serde_json::from_str(&json_str).unwrap();
         assert!(deserialized.content.unwrap().contains("🚀"));
     }
+
+    #[test]
+    fn test_finish_reason_from_provider() {
+        assert_eq!(FinishReason::from_provider("end_turn"), FinishReason::Stop);
+        assert_eq!(FinishReason::from_provider("max_tokens"), FinishReason::Length);
+        assert_eq!(FinishReason::from_provider("tool_use"), FinishReason::ToolCalls);
+        assert_eq!(
+            FinishReason::from_provider("content_filter"),
+            FinishReason::ContentFilter
+        );
+        assert_eq!(
+            FinishReason::from_provider("recitation"),
+            FinishReason::Other("recitation".to_string())
+        );
+        assert!(FinishReason::Stop.notice().is_none());
+        assert!(FinishReason::Length.notice().is_some());
+    }
+
+    #[test]
+    fn test_apply_sampling() {
+        let sampling = SamplingParams {
+            top_p: Some(0.9),
+            frequency_penalty: Some(0.5),
+            stop: vec!["END".to_string()],
+            seed: Some(42),
+            ..Default::default()
+        };
+
+        let mut request = json!({ "model": "gpt-4o" });
+        apply_sampling(&mut request, &sampling, RequestFormat::OpenAI);
+        assert_eq!(request["stop"], json!(["END"]));
+        assert_eq!(request["seed"], 42);
+        assert!(request.get("presence_penalty").is_none());
+
+        let mut request = json!({ "model": "claude" });
+        apply_sampling(&mut request, &sampling, RequestFormat::Anthropic);
+        assert_eq!(request["stop_sequences"], json!(["END"]));
+        assert!(request.get("seed").is_none());
+
+        let mut request = json!({ "model": "llama3", "options": { "num_predict": 10 } });
+        apply_sampling(&mut request, &sampling, RequestFormat::Ollama);
+        assert_eq!(request["options"]["seed"], 42);
+        assert_eq!(request["options"]["num_predict"], 10);
+    }
+
+    #[test]
+    fn test_merge_json() {
+        let mut request = json!({
+            "model": "anthropic/claude-sonnet-4",
+            "stream": true,
+            "provider": { "sort": "price" },
+            "seed": 7
+        });
+        merge_json(
+            &mut request,
+            &json!({
+                "provider": { "order": ["anthropic", "bedrock"] },
+                "seed": null,
+                "user": "team-a"
+            }),
+        );
+        assert_eq!(request["provider"]["sort"], "price");
+        assert_eq!(request["provider"]["order"], json!(["anthropic", "bedrock"]));
+        assert!(request.get("seed").is_none());
+        assert_eq!(request["user"], "team-a");
+        assert_eq!(request["stream"], true);
+    }
+
+    #[test]
+    fn test_apply_prompt_caching() {
+        let mut request = json!({
+            "system": "You are Arula.",
+            "tools": [{ "name": "read_file" }, { "name": "write_file" }],
+            "messages": [],
+        });
+        apply_prompt_caching(&mut request);
+        assert_eq!(request["system"][0]["text"], "You are Arula.");
+        assert_eq!(request["system"][0]["cache_control"]["type"], "ephemeral");
+        assert!(request["tools"][0].get("cache_control").is_none());
+        assert_eq!(request["tools"][1]["cache_control"]["type"], "ephemeral");
+    }
 }
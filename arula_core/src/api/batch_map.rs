@@ -0,0 +1,378 @@
+//! Running one prompt over many files behind `arula map`
+//!
+//! Every file matching the glob is sent with the prompt in its own request,
+//! several at a time and optionally throttled to a number of requests per
+//! minute. Answers are written to an output directory mirroring the file
+//! tree, or back over the files themselves. Finished files are recorded in a
+//! state file under `.arula/map/`, so an interrupted run picks up where it
+//! stopped and files that changed since are processed again.
+//!
+//! In place, a file is only overwritten with a complete answer: files whose
+//! rewrite wouldn't fit in `MAP_MAX_TOKENS` are refused up front, an answer
+//! cut off at the limit fails the file, and every file is snapshotted first
+//! so `arula rollback` undoes the run.
+
+use crate::api::api::FinishReason;
+use crate::api::stream::complete_with_reason;
+use crate::utils::config::Config;
+use crate::utils::context_budget::estimate_tokens;
+use crate::utils::redact;
+use crate::utils::snapshots::{self, SnapshotStore};
+use anyhow::{bail, Context, Result};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Largest file sent to the model; bigger ones are reported as failed
+const MAX_FILE_BYTES: u64 = 256 * 1024;
+
+const MAP_MAX_TOKENS: u32 = 8192;
+
+const MAP_TIMEOUT: Duration = Duration::from_secs(300);
+
+const IN_PLACE_INSTRUCTIONS: &str = "Apply the instruction above to the file below. Reply with \
+the complete new contents of the file only: no explanation and no code fences.";
+
+const OUTPUT_INSTRUCTIONS: &str =
+    "Apply the instruction above to the file below. Reply with the result only.";
+
+/// Where the answers go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapOutput {
+    /// Overwrite each file with the model's answer
+    InPlace,
+    /// Write answers to the same relative paths under this directory
+    Dir(PathBuf),
+}
+
+/// What to run over which files
+#[derive(Debug, Clone)]
+pub struct MapOptions {
+    pub glob: String,
+    pub prompt: String,
+    pub output: MapOutput,
+    /// Requests in flight at once
+    pub concurrency: usize,
+    /// Requests started per minute, if limited
+    pub requests_per_minute: Option<u32>,
+    /// Forget the files a previous run finished
+    pub restart: bool,
+}
+
+/// Outcome of a whole run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MapReport {
+    pub processed: usize,
+    /// Files a previous run already finished
+    pub skipped: usize,
+    /// Relative paths with the error that stopped them
+    pub failed: Vec<(String, String)>,
+    /// Snapshot turn of an in-place run, for `arula rollback`
+    pub rollback_turn: Option<String>,
+}
+
+/// Finished files of a run, saved after every file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MapState {
+    glob: String,
+    prompt: String,
+    /// Relative path to the SHA-256 of the file as the run left it
+    done: BTreeMap<String, String>,
+}
+
+impl MapState {
+    fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn is_done(&self, relative: &str, contents: &[u8]) -> bool {
+        self.done.get(relative) == Some(&hash(contents))
+    }
+}
+
+fn hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// State file of a run, keyed by the glob, prompt and output
+fn state_path(root: &Path, options: &MapOptions) -> PathBuf {
+    let output = match &options.output {
+        MapOutput::InPlace => String::new(),
+        MapOutput::Dir(dir) => dir.to_string_lossy().into_owned(),
+    };
+    let key = hash(format!("{}\0{}\0{}", options.glob, options.prompt, output).as_bytes());
+    root.join(".arula")
+        .join("map")
+        .join(format!("{}.json", &key[..16]))
+}
+
+/// Files under `root` matching `pattern`, relative to `root` and sorted;
+/// ignored files (.gitignore and friends) are left out
+pub fn matching_files(root: &Path, pattern: &str) -> Result<Vec<String>> {
+    let matcher = globset::GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("Invalid glob pattern: {}", pattern))?
+        .compile_matcher();
+    let mut files: Vec<String> = ignore::WalkBuilder::new(root)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(root).ok()?;
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            matcher.is_match(&relative).then_some(relative)
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn build_prompt(instruction: &str, output: &MapOutput, relative: &str, contents: &str) -> String {
+    let (instructions, contents) = match output {
+        // Redacting would write the placeholders back into the file
+        MapOutput::InPlace => (IN_PLACE_INSTRUCTIONS, contents.to_string()),
+        MapOutput::Dir(_) => (OUTPUT_INSTRUCTIONS, redact::redact(contents).into_owned()),
+    };
+    format!(
+        "{}\n\n{}\n\nFile: {}\n```\n{}\n```",
+        instruction.trim(),
+        instructions,
+        relative,
+        contents.trim_end()
+    )
+}
+
+/// The answer without the code fence models wrap whole files in anyway
+fn strip_fence(answer: &str) -> String {
+    let trimmed = answer.trim();
+    let unfenced = trimmed
+        .strip_prefix("```")
+        .and_then(|inner| inner.split_once('\n'))
+        .and_then(|(_, rest)| rest.trim_end().strip_suffix("```"))
+        // A fence inside the answer means it isn't one wrapped block
+        .filter(|inner| !inner.contains("\n```"));
+    format!("{}\n", unfenced.unwrap_or(trimmed).trim_end())
+}
+
+/// Why `contents` can't be rewritten in place, if its new version wouldn't
+/// fit in one answer (with room for the model changing it a little)
+fn too_large_to_rewrite(contents: &str) -> Option<String> {
+    let tokens = estimate_tokens(contents);
+    let limit = MAP_MAX_TOKENS as usize * 3 / 4;
+    (tokens > limit).then(|| {
+        format!(
+            "too large to rewrite in place (~{} tokens, the answer limit is {})",
+            tokens, MAP_MAX_TOKENS
+        )
+    })
+}
+
+/// Send one file with the prompt and write the answer where it belongs;
+/// in place the file is snapshotted under `turn` first
+async fn process_file(
+    config: &Config,
+    root: &Path,
+    options: &MapOptions,
+    relative: &str,
+    contents: &str,
+    turn: Option<&str>,
+) -> Result<()> {
+    let prompt = build_prompt(&options.prompt, &options.output, relative, contents);
+    let (answer, reason) =
+        complete_with_reason(config, prompt, MAP_MAX_TOKENS, MAP_TIMEOUT).await?;
+    if reason == FinishReason::Length {
+        bail!(
+            "The answer was cut off at {} tokens; the file was left unchanged",
+            MAP_MAX_TOKENS
+        );
+    }
+    let answer = strip_fence(&answer);
+    if answer.trim().is_empty() {
+        bail!("The model returned an empty answer");
+    }
+    let target = match &options.output {
+        MapOutput::InPlace => root.join(relative),
+        MapOutput::Dir(dir) => root.join(dir).join(relative),
+    };
+    if let Some(turn) = turn {
+        SnapshotStore::default_store()
+            .take(turn, "map", &target)
+            .with_context(|| format!("Failed to snapshot {}", target.display()))?;
+    }
+    if let Some(dir) = target.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    tokio::fs::write(&target, answer)
+        .await
+        .with_context(|| format!("Failed to write {}", target.display()))
+}
+
+/// Run the prompt over every matching file under `root`, calling
+/// `on_file(done, total, path)` as files finish
+pub async fn run(
+    config: &Config,
+    root: &Path,
+    options: &MapOptions,
+    mut on_file: impl FnMut(usize, usize, &str),
+) -> Result<MapReport> {
+    let mut files = matching_files(root, &options.glob)?;
+    if let MapOutput::Dir(dir) = &options.output {
+        // Don't feed earlier answers back in when the output is inside the tree
+        let dir = dir.to_string_lossy().replace('\\', "/");
+        let prefix = format!("{}/", dir.trim_end_matches('/'));
+        files.retain(|file| !file.starts_with(&prefix));
+    }
+    if files.is_empty() {
+        bail!("No files match {}", options.glob);
+    }
+
+    let state_path = state_path(root, options);
+    let mut state = if options.restart {
+        MapState::default()
+    } else {
+        MapState::load(&state_path)
+    };
+    state.glob = options.glob.clone();
+    state.prompt = options.prompt.clone();
+
+    let total = files.len();
+    let mut report = MapReport::default();
+    let mut pending = Vec::new();
+    for relative in files {
+        let path = root.join(&relative);
+        let bytes = match std::fs::metadata(&path) {
+            Ok(meta) if meta.len() > MAX_FILE_BYTES => {
+                report
+                    .failed
+                    .push((relative, format!("too large ({} KB)", meta.len() / 1024)));
+                continue;
+            }
+            _ => std::fs::read(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?,
+        };
+        if state.is_done(&relative, &bytes) {
+            report.skipped += 1;
+            continue;
+        }
+        match String::from_utf8(bytes) {
+            Ok(contents) => {
+                let too_large = (options.output == MapOutput::InPlace)
+                    .then(|| too_large_to_rewrite(&contents))
+                    .flatten();
+                match too_large {
+                    Some(reason) => report.failed.push((relative, reason)),
+                    None => pending.push((relative, contents)),
+                }
+            }
+            Err(_) => report
+                .failed
+                .push((relative, "not a text file".to_string())),
+        }
+    }
+
+    let rollback_turn = (options.output == MapOutput::InPlace && !pending.is_empty())
+        .then(snapshots::begin_turn);
+    report.rollback_turn = rollback_turn.clone();
+    let turn = rollback_turn.as_deref();
+
+    // Requests are started at most once per interval when throttled
+    let throttle = options
+        .requests_per_minute
+        .filter(|&rpm| rpm > 0)
+        .map(|rpm| {
+            let mut interval = tokio::time::interval(Duration::from_secs(60) / rpm);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            tokio::sync::Mutex::new(interval)
+        });
+    let throttle = &throttle;
+    let mut results = futures::stream::iter(pending)
+        .map(|(relative, contents)| async move {
+            if let Some(interval) = throttle {
+                interval.lock().await.tick().await;
+            }
+            let result = process_file(config, root, options, &relative, &contents, turn).await;
+            (relative, contents, result)
+        })
+        .buffer_unordered(options.concurrency.max(1));
+
+    let mut done = report.skipped + report.failed.len();
+    while let Some((relative, contents, result)) = results.next().await {
+        match result {
+            Ok(()) => {
+                // In place the file changed, so a resumed run must compare
+                // against the new contents
+                let finished = match &options.output {
+                    MapOutput::InPlace => std::fs::read(root.join(&relative)).unwrap_or_default(),
+                    MapOutput::Dir(_) => contents.into_bytes(),
+                };
+                state.done.insert(relative.clone(), hash(&finished));
+                state.save(&state_path)?;
+                report.processed += 1;
+            }
+            Err(err) => report.failed.push((relative.clone(), err.to_string())),
+        }
+        done += 1;
+        on_file(done, total, &relative);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_files_and_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/api")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(root.join("src/api/mod.rs"), "").unwrap();
+        std::fs::write(root.join("src/notes.md"), "").unwrap();
+        std::fs::write(root.join("build.rs"), "").unwrap();
+
+        let files = matching_files(root, "src/**/*.rs").unwrap();
+        assert_eq!(files, ["src/api/mod.rs", "src/main.rs"]);
+        assert_eq!(matching_files(root, "*.rs").unwrap(), ["build.rs"]);
+
+        let mut state = MapState::default();
+        state
+            .done
+            .insert("src/main.rs".into(), hash(b"fn main() {}"));
+        let path = root.join(".arula/map/state.json");
+        state.save(&path).unwrap();
+        let state = MapState::load(&path);
+        assert!(state.is_done("src/main.rs", b"fn main() {}"));
+        assert!(!state.is_done("src/main.rs", b"fn main() { changed() }"));
+    }
+
+    #[test]
+    fn test_in_place_size_limit() {
+        assert!(too_large_to_rewrite("fn main() {}\n").is_none());
+        let large = "x".repeat(MAP_MAX_TOKENS as usize * 4);
+        assert!(too_large_to_rewrite(&large).unwrap().contains("too large"));
+    }
+
+    #[test]
+    fn test_strip_fence() {
+        assert_eq!(strip_fence("```rust\nfn a() {}\n```"), "fn a() {}\n");
+        assert_eq!(strip_fence("plain answer\n\n"), "plain answer\n");
+        let two = "```rust\na\n```\ntext\n```rust\nb\n```";
+        assert_eq!(strip_fence(two), format!("{}\n", two));
+    }
+}
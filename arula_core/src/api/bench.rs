@@ -0,0 +1,284 @@
+//! Cross-provider latency benchmark behind `arula bench`
+//!
+//! Sends the same prompt, without tools, to each configured provider in turn
+//! and measures time to first token, generation speed and estimated cost.
+//! Runs are real requests, so they are billed and show up in the usage stats.
+//!
+//! The desktop compare view uses the same measurements for two models
+//! answering one prompt side by side, keeping the answers.
+
+use crate::api::api::{ApiClient, ChatMessage};
+use crate::api::stream::{build_client_request, process_response, StreamEvent};
+use crate::utils::config::{AiConfig, Config};
+use crate::utils::usage_store;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+/// Prompt used when none is given: long enough to measure throughput, short
+/// enough to stay cheap
+pub const STANDARD_PROMPT: &str =
+    "Explain in about 200 words how HTTP caching works, covering Cache-Control, ETags and revalidation.";
+
+/// Longest a single provider may take
+const BENCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+const BENCH_MAX_TOKENS: u32 = 512;
+
+/// Room for a full answer when comparing models
+const COMPARE_MAX_TOKENS: u32 = 4096;
+
+/// Measurements for one provider
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BenchResult {
+    pub provider: String,
+    pub model: String,
+    /// Time to the first streamed text or reasoning token
+    pub ttft_ms: Option<u64>,
+    pub total_ms: u64,
+    pub completion_tokens: u32,
+    /// Completion tokens per second after the first token
+    pub tokens_per_sec: Option<f64>,
+    /// Estimated USD cost, when the model's price is known
+    pub cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BenchResult {
+    fn failed(provider: &str, model: &str, error: impl Into<String>) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            error: Some(error.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Benchmark `providers` (all configured providers when empty) one at a time
+pub async fn run_bench(config: &Config, prompt: &str, providers: &[String]) -> Vec<BenchResult> {
+    let names: Vec<String> = config
+        .get_provider_names()
+        .into_iter()
+        .filter(|name| {
+            providers.is_empty() || providers.iter().any(|p| p.eq_ignore_ascii_case(name))
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(names.len());
+    for name in names {
+        let mut provider_config = config.clone();
+        provider_config.active_provider = name.clone();
+        results.push(bench_provider(&provider_config, prompt).await);
+    }
+    results
+}
+
+/// Benchmark the config's active provider
+pub async fn bench_provider(config: &Config, prompt: &str) -> BenchResult {
+    measure(config, prompt, BENCH_MAX_TOKENS).await.0
+}
+
+/// One model's answer in a comparison, with its measurements
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Comparison {
+    pub result: BenchResult,
+    pub answer: String,
+}
+
+/// Send `prompt` to each `(provider, model)` at once and keep the answers
+pub async fn compare_models(
+    config: &Config,
+    prompt: &str,
+    targets: &[(String, String)],
+) -> Vec<Comparison> {
+    let runs = targets.iter().map(|(provider, model)| async move {
+        let Some(target) = target_config(config, provider, model) else {
+            return Comparison {
+                result: BenchResult::failed(provider, model, "Provider is not configured"),
+                answer: String::new(),
+            };
+        };
+        let (result, answer) = measure(&target, prompt, COMPARE_MAX_TOKENS).await;
+        Comparison { result, answer }
+    });
+    futures::future::join_all(runs).await
+}
+
+/// `config` switched to `model` of `provider`, if that provider is configured
+fn target_config(config: &Config, provider: &str, model: &str) -> Option<Config> {
+    let name = config
+        .get_provider_names()
+        .into_iter()
+        .find(|name| name.eq_ignore_ascii_case(provider))?;
+    let mut target = config.clone();
+    target.active_provider = name;
+    if !model.trim().is_empty() {
+        target.set_model(model.trim());
+    }
+    Some(target)
+}
+
+/// Run `prompt` on the config's active provider, returning the measurements
+/// and the streamed answer
+async fn measure(config: &Config, prompt: &str, max_tokens: u32) -> (BenchResult, String) {
+    let provider = config.active_provider.clone();
+    let model = config.get_model();
+    let api_key = config.get_api_key();
+    if api_key.is_empty() && !AiConfig::is_local_provider(&provider) {
+        let result = BenchResult::failed(&provider, &model, "No API key configured");
+        return (result, String::new());
+    }
+
+    let client = ApiClient::new(
+        provider.clone(),
+        config.get_api_url(),
+        api_key,
+        model.clone(),
+    )
+    .with_provider_config(config.get_active_provider_config());
+    let messages = [ChatMessage {
+        role: "user".to_string(),
+        content: Some(prompt.to_string()),
+        tool_calls: None,
+        tool_call_id: None,
+        tool_name: None,
+        images: Vec::new(),
+    }];
+    let request = build_client_request(&client, &messages, None, max_tokens);
+
+    let started = Instant::now();
+    let mut first_token: Option<Duration> = None;
+    let mut streamed_chars = 0;
+    let mut answer = String::new();
+    let run = async {
+        let response = client.make_streaming_request(request).await?;
+        process_response(response, |event| match event {
+            StreamEvent::TextDelta(text) => {
+                first_token.get_or_insert_with(|| started.elapsed());
+                streamed_chars += text.chars().count();
+                answer.push_str(&text);
+            }
+            StreamEvent::ThinkingDelta(text) => {
+                first_token.get_or_insert_with(|| started.elapsed());
+                streamed_chars += text.chars().count();
+            }
+            _ => {}
+        })
+        .await
+    };
+    let response = match tokio::time::timeout(BENCH_TIMEOUT, run).await {
+        Ok(Ok(response)) if response.success => response,
+        Ok(Ok(response)) => {
+            let error = response
+                .error
+                .unwrap_or_else(|| "Request failed".to_string());
+            return (BenchResult::failed(&provider, &model, error), answer);
+        }
+        Ok(Err(e)) => {
+            return (
+                BenchResult::failed(&provider, &model, e.to_string()),
+                answer,
+            )
+        }
+        Err(_) => return (BenchResult::failed(&provider, &model, "Timed out"), answer),
+    };
+    let total = started.elapsed();
+
+    // Providers that don't report usage get the usual 4-characters-per-token estimate
+    let (prompt_tokens, completion_tokens) = match &response.usage {
+        Some(usage) => (usage.prompt_tokens, usage.completion_tokens),
+        None => ((prompt.len() / 4) as u32, (streamed_chars / 4) as u32),
+    };
+
+    let result = BenchResult {
+        provider,
+        cost: usage_store::estimate_cost(&model, prompt_tokens as u64, completion_tokens as u64),
+        model,
+        ttft_ms: first_token.map(|d| d.as_millis() as u64),
+        total_ms: total.as_millis() as u64,
+        completion_tokens,
+        tokens_per_sec: tokens_per_sec(completion_tokens, first_token, total),
+        error: None,
+    };
+    (result, answer)
+}
+
+/// Generation speed between the first token and the end of the response
+fn tokens_per_sec(tokens: u32, first_token: Option<Duration>, total: Duration) -> Option<f64> {
+    let generating = total.checked_sub(first_token?)?.as_secs_f64();
+    (tokens > 0 && generating > 0.0).then(|| tokens as f64 / generating)
+}
+
+/// Plain-text comparison table, fastest first token at the top
+pub fn render_table(results: &[BenchResult]) -> String {
+    let mut sorted: Vec<&BenchResult> = results.iter().collect();
+    sorted.sort_by_key(|r| (r.error.is_some(), r.ttft_ms.unwrap_or(u64::MAX)));
+
+    let mut table = format!(
+        "{:<20} {:<28} {:>9} {:>9} {:>8} {:>9}\n",
+        "PROVIDER", "MODEL", "TTFT", "TOTAL", "TOK/S", "COST"
+    );
+    for r in sorted {
+        if let Some(error) = &r.error {
+            let _ = writeln!(table, "{:<20} {:<28} error: {}", r.provider, r.model, error);
+            continue;
+        }
+        let _ = writeln!(
+            table,
+            "{:<20} {:<28} {:>9} {:>9} {:>8} {:>9}",
+            r.provider,
+            r.model,
+            r.ttft_ms.map_or("-".to_string(), |ms| format!("{}ms", ms)),
+            format!("{}ms", r.total_ms),
+            r.tokens_per_sec
+                .map_or("-".to_string(), |t| format!("{:.1}", t)),
+            r.cost.map_or("-".to_string(), |c| format!("${:.4}", c)),
+        );
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_per_sec_and_table() {
+        let speed = tokens_per_sec(
+            100,
+            Some(Duration::from_millis(500)),
+            Duration::from_millis(2500),
+        );
+        assert_eq!(speed, Some(50.0));
+        assert_eq!(tokens_per_sec(100, None, Duration::from_secs(1)), None);
+
+        let results = [
+            BenchResult::failed("openai", "gpt-4o", "No API key configured"),
+            BenchResult {
+                provider: "ollama".into(),
+                model: "llama3".into(),
+                ttft_ms: Some(120),
+                total_ms: 900,
+                completion_tokens: 40,
+                tokens_per_sec: speed,
+                cost: None,
+                error: None,
+            },
+        ];
+        let table = render_table(&results);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[1].starts_with("ollama") && lines[1].contains("120ms"));
+        assert!(lines[2].contains("error: No API key configured"));
+    }
+
+    #[test]
+    fn test_target_config() {
+        let config = Config::new_for_test("openai", "gpt-4o", "https://api.openai.com/v1", "key");
+        let target = target_config(&config, "OpenAI", "gpt-4o-mini").unwrap();
+        assert_eq!(target.get_model(), "gpt-4o-mini");
+        assert_eq!(config.get_model(), "gpt-4o");
+        assert!(target_config(&config, "anthropic", "claude").is_none());
+    }
+}
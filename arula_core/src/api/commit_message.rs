@@ -0,0 +1,142 @@
+//! Commit message generation behind `arula commit` and the desktop git pill
+//!
+//! Diffs the staged changes, asks the active provider for a Conventional
+//! Commits message and, once the user accepted it, runs `git commit` with it.
+//! The diff is redacted and capped before it is sent.
+
+use crate::api::stream::complete;
+use crate::utils::config::Config;
+use crate::utils::redact;
+use anyhow::{bail, Context, Result};
+use std::io::Write as _;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Largest diff sent to the model; the stat summary always goes in full
+const MAX_DIFF_CHARS: usize = 24_000;
+
+const COMMIT_MAX_TOKENS: u32 = 400;
+
+const COMMIT_TIMEOUT: Duration = Duration::from_secs(90);
+
+const INSTRUCTIONS: &str = "Write a git commit message for the staged changes below, following \
+Conventional Commits: a `type(scope): summary` subject of at most 72 characters in the \
+imperative mood (types: feat, fix, docs, style, refactor, perf, test, build, ci, chore), \
+then, if the change needs explaining, a blank line and a short body wrapped at 72 columns \
+saying what changed and why. Reply with the message only, no code fences or commentary.";
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The prompt for the staged changes of the repository containing `dir`
+pub fn staged_prompt(dir: &Path) -> Result<String> {
+    let stat = git(dir, &["diff", "--cached", "--stat"])?;
+    if stat.trim().is_empty() {
+        bail!("No staged changes; stage files with `git add` first");
+    }
+    let diff = git(dir, &["diff", "--cached", "--no-color"])?;
+    Ok(build_prompt(&stat, &diff))
+}
+
+fn build_prompt(stat: &str, diff: &str) -> String {
+    let diff = match diff.char_indices().nth(MAX_DIFF_CHARS) {
+        Some((cut, _)) => format!("{}\n[diff truncated]", &diff[..cut]),
+        None => diff.to_string(),
+    };
+    let prompt = format!(
+        "{}\n\nFiles changed:\n```\n{}```\n\nDiff:\n```diff\n{}\n```",
+        INSTRUCTIONS,
+        stat,
+        diff.trim_end()
+    );
+    redact::redact(&prompt).into_owned()
+}
+
+/// Ask the active provider for a message describing the staged changes
+pub async fn generate(config: &Config, dir: &Path) -> Result<String> {
+    let prompt = staged_prompt(dir)?;
+    let answer = complete(config, prompt, COMMIT_MAX_TOKENS, COMMIT_TIMEOUT).await?;
+    let message = clean_message(&answer);
+    if message.is_empty() {
+        bail!("The model returned an empty message");
+    }
+    Ok(message)
+}
+
+/// Strip the code fences and quotes models tend to wrap messages in
+pub fn clean_message(raw: &str) -> String {
+    let mut text = raw.trim();
+    if let Some(inner) = text.strip_prefix("```") {
+        // Drop the language tag after the opening fence
+        let inner = inner.split_once('\n').map_or("", |(_, rest)| rest);
+        text = inner.trim_end().strip_suffix("```").unwrap_or(inner).trim();
+    }
+    let text = text.trim_matches(|c| c == '"' || c == '`').trim();
+    text.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Commit the staged changes with `message`, returning git's summary line
+pub fn commit(dir: &Path, message: &str) -> Result<String> {
+    let mut child = Command::new("git")
+        .args(["commit", "--file", "-"])
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run git")?;
+    child
+        .stdin
+        .take()
+        .context("Failed to open git stdin")?
+        .write_all(message.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        // "nothing to commit" and hook output go to stdout
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = match stderr.trim() {
+            "" => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            stderr => stderr.to_string(),
+        };
+        bail!("{}", detail);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_and_cleanup() {
+        let prompt = build_prompt(" src/lib.rs | 2 +-\n", "-old\n+new\n");
+        assert!(prompt.starts_with(INSTRUCTIONS));
+        assert!(prompt.contains("```diff\n-old\n+new\n```"));
+
+        let long = "+x\n".repeat(MAX_DIFF_CHARS);
+        assert!(build_prompt("", &long).contains("[diff truncated]"));
+
+        assert_eq!(
+            clean_message("```text\nfix(api): handle empty responses  \n\nDetails.\n```"),
+            "fix(api): handle empty responses\n\nDetails."
+        );
+        assert_eq!(clean_message("\"feat: add commit\"\n"), "feat: add commit");
+    }
+}
@@ -0,0 +1,177 @@
+//! Context-limit check before a request is sent
+//!
+//! Estimates the size of the request a prompt would produce (system prompt,
+//! history as the configured strategy trims it, the prompt itself and the
+//! room reserved for the answer) and compares it with the model's context
+//! window, taken from OpenRouter's metadata when it has been fetched and the
+//! built-in table otherwise. Frontends warn before sending, and can compact
+//! the history with `compact` when the request won't fit.
+
+use crate::api::api::ChatMessage;
+use crate::api::history::{self, message_tokens};
+use crate::api::{model_info, models};
+use crate::utils::config::{Config, HistoryStrategy};
+use crate::utils::context_budget::estimate_tokens;
+
+/// Requests above this share of the context window get a warning
+pub const NEAR_LIMIT_RATIO: f32 = 0.85;
+
+/// Turns kept verbatim when older ones are summarized
+pub const COMPACT_KEEP_TURNS: usize = 4;
+
+/// Share of the room left for history that trimming aims for, leaving space
+/// for the next few turns
+const TRIM_TARGET_RATIO: f32 = 0.6;
+
+/// How a request compares with the model's context window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextFit {
+    Fits,
+    NearLimit,
+    OverLimit,
+}
+
+/// Estimated size of a request against the model's context window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestSize {
+    /// System prompt, history and prompt tokens
+    pub input_tokens: usize,
+    /// Tokens reserved for the answer (`max_tokens`)
+    pub output_tokens: usize,
+    /// Tokens the history contributes, the part compaction can shrink
+    pub history_tokens: usize,
+    /// Context window of the model
+    pub limit: usize,
+}
+
+impl RequestSize {
+    pub fn total(&self) -> usize {
+        self.input_tokens + self.output_tokens
+    }
+
+    pub fn fit(&self) -> ContextFit {
+        let total = self.total();
+        if total > self.limit {
+            ContextFit::OverLimit
+        } else if total as f32 > self.limit as f32 * NEAR_LIMIT_RATIO {
+            ContextFit::NearLimit
+        } else {
+            ContextFit::Fits
+        }
+    }
+
+    /// History token budget that brings the request well under the limit
+    pub fn trim_budget(&self) -> usize {
+        let fixed = self.total() - self.history_tokens;
+        (self.limit.saturating_sub(fixed) as f32 * TRIM_TARGET_RATIO) as usize
+    }
+}
+
+/// Context window of `model` in tokens, if known
+pub fn context_limit(model: &str) -> Option<usize> {
+    model_info::get(model)
+        .and_then(|info| info.context_length)
+        .or_else(|| models::context_window(model))
+        .map(|tokens| tokens as usize)
+}
+
+/// Estimate the request `prompt` would produce after `history` with
+/// `system_tokens` of system prompt. `None` when the model's window is unknown.
+pub fn estimate(
+    model: &str,
+    system_tokens: usize,
+    history: &[ChatMessage],
+    strategy: &HistoryStrategy,
+    prompt: &str,
+    max_tokens: u32,
+) -> Option<RequestSize> {
+    let limit = context_limit(model)?;
+    let history_tokens = history::trim(history.to_vec(), strategy)
+        .kept
+        .iter()
+        .map(message_tokens)
+        .sum::<usize>();
+    Some(RequestSize {
+        input_tokens: system_tokens + history_tokens + estimate_tokens(prompt),
+        output_tokens: max_tokens as usize,
+        history_tokens,
+        limit,
+    })
+}
+
+/// How `compact` shrinks a history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compaction {
+    /// Replace all but the last `COMPACT_KEEP_TURNS` turns with a summary
+    Summarize,
+    /// Drop the oldest turns until the rest fits `max_tokens`
+    Trim { max_tokens: usize },
+}
+
+/// Shrink `history` so the next request fits. Summarizing asks the model,
+/// in pieces that fit its window; if that fails the error is returned and
+/// the caller keeps the history as it was.
+pub async fn compact(
+    history: Vec<ChatMessage>,
+    compaction: Compaction,
+    config: &Config,
+) -> Result<Vec<ChatMessage>, String> {
+    match compaction {
+        Compaction::Summarize => history::summarize_old(history, COMPACT_KEEP_TURNS, config).await,
+        Compaction::Trim { max_tokens } => {
+            Ok(history::trim(history, &HistoryStrategy::TokenBudget { max_tokens }).kept)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, chars: usize) -> ChatMessage {
+        ChatMessage {
+            role: role.to_string(),
+            content: Some("x".repeat(chars)),
+            tool_calls: None,
+            tool_call_id: None,
+            tool_name: None,
+            images: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_estimate_against_window() {
+        // llama3 has an 8K window in the built-in table
+        let history: Vec<ChatMessage> = (0..10)
+            .flat_map(|_| [message("user", 400), message("assistant", 2400)])
+            .collect();
+        let full = estimate(
+            "llama3:8b",
+            500,
+            &history,
+            &HistoryStrategy::Full,
+            "hi",
+            1024,
+        )
+        .unwrap();
+        assert_eq!(full.limit, 8_192);
+        assert_eq!(full.fit(), ContextFit::OverLimit);
+
+        // The configured strategy's trimming is taken into account
+        let window = HistoryStrategy::SlidingWindow { turns: 2 };
+        let size = estimate("llama3:8b", 500, &history, &window, "hi", 1024).unwrap();
+        assert_eq!(size.fit(), ContextFit::Fits);
+
+        // Trimming to the suggested budget keeps the history within it
+        let trimmed = history::trim(
+            history.clone(),
+            &HistoryStrategy::TokenBudget {
+                max_tokens: full.trim_budget(),
+            },
+        );
+        let tokens: usize = trimmed.kept.iter().map(message_tokens).sum();
+        assert!(tokens <= full.trim_budget());
+
+        assert!(estimate("my-finetune", 0, &history, &HistoryStrategy::Full, "", 0).is_none());
+    }
+}
@@ -0,0 +1,319 @@
+//! Conversation history trimming
+//!
+//! Long chats eventually outgrow the context window, which small local
+//! models hit after a few dozen turns. Before a request is built, the history
+//! is cut down according to the provider's (or model's) `HistoryStrategy`:
+//! everything, the last N turns, the newest turns that fit a token budget, or
+//! the last N turns plus a model-written summary of the older ones. A turn is
+//! a user message with everything up to the next one (answers, tool calls and
+//! tool results), so tool results are never separated from their calls.
+
+use crate::api::api::ChatMessage;
+use crate::api::{context_limit, stream};
+use crate::utils::config::{Config, HistoryStrategy};
+use crate::utils::context_budget::estimate_tokens;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Summaries of dropped turns, by hash of their content
+static SUMMARIES: Mutex<Option<HashMap<u64, String>>> = Mutex::new(None);
+
+const SUMMARY_MAX_TOKENS: u32 = 1024;
+const SUMMARY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Share of the model's context window one summary request may fill
+const SUMMARY_INPUT_RATIO: f32 = 0.5;
+/// Transcript tokens per summary request when the window is unknown
+const SUMMARY_DEFAULT_INPUT_TOKENS: usize = 6_000;
+const SUMMARY_MIN_INPUT_TOKENS: usize = 500;
+
+/// History split by a strategy
+#[derive(Debug, Clone, Default)]
+pub struct Trimmed {
+    /// Leading system messages and the turns that are sent
+    pub kept: Vec<ChatMessage>,
+    /// Older turns that are left out (or summarized)
+    pub dropped: Vec<ChatMessage>,
+}
+
+/// Leading system messages and the turns after them
+fn split_turns(history: Vec<ChatMessage>) -> (Vec<ChatMessage>, Vec<Vec<ChatMessage>>) {
+    let mut system = Vec::new();
+    let mut turns: Vec<Vec<ChatMessage>> = Vec::new();
+    for message in history {
+        if turns.is_empty() && message.role == "system" {
+            system.push(message);
+        } else if message.role == "user" || turns.is_empty() {
+            turns.push(vec![message]);
+        } else if let Some(turn) = turns.last_mut() {
+            turn.push(message);
+        }
+    }
+    (system, turns)
+}
+
+/// Estimated tokens of a message, including its tool calls
+pub fn message_tokens(message: &ChatMessage) -> usize {
+    let tool_calls: usize = message
+        .tool_calls
+        .iter()
+        .flatten()
+        .map(|call| {
+            estimate_tokens(&call.function.name) + estimate_tokens(&call.function.arguments)
+        })
+        .sum();
+    estimate_tokens(message.content.as_deref().unwrap_or_default()) + tool_calls
+}
+
+/// Split `history` into what `strategy` sends and what it leaves out. The
+/// latest turn is always kept.
+pub fn trim(history: Vec<ChatMessage>, strategy: &HistoryStrategy) -> Trimmed {
+    let (system, turns) = split_turns(history);
+    let keep = match *strategy {
+        HistoryStrategy::Full => turns.len(),
+        HistoryStrategy::SlidingWindow { turns: window } => window.max(1),
+        HistoryStrategy::TokenBudget { max_tokens } => {
+            let mut used: usize = system.iter().map(message_tokens).sum();
+            let mut keep = 0;
+            for turn in turns.iter().rev() {
+                used += turn.iter().map(message_tokens).sum::<usize>();
+                if keep > 0 && used > max_tokens {
+                    break;
+                }
+                keep += 1;
+            }
+            keep
+        }
+        // Drop whole blocks of `keep_turns` so the summary only changes
+        // every `keep_turns` turns instead of on every request
+        HistoryStrategy::SummarizeOld { keep_turns } => {
+            let keep_turns = keep_turns.max(1);
+            let old = turns.len().saturating_sub(keep_turns);
+            turns.len() - old / keep_turns * keep_turns
+        }
+    };
+
+    let split = turns.len().saturating_sub(keep);
+    let mut turns = turns.into_iter();
+    let dropped = turns.by_ref().take(split).flatten().collect();
+    let mut kept = system;
+    kept.extend(turns.flatten());
+    Trimmed { kept, dropped }
+}
+
+fn text_message(role: &str, content: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: Some(content),
+        tool_calls: None,
+        tool_call_id: None,
+        tool_name: None,
+        images: Vec::new(),
+    }
+}
+
+/// Transcript of dropped turns for the summary prompt, in pieces of at most
+/// `budget` tokens. A message too long for a piece of its own is cut.
+fn transcript_chunks(messages: &[ChatMessage], budget: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let (mut current, mut current_tokens) = (String::new(), 0);
+    for m in messages {
+        let Some(content) = m.content.as_deref().filter(|c| !c.trim().is_empty()) else {
+            continue;
+        };
+        let mut entry = format!("{}: {}", m.role, content);
+        if estimate_tokens(&entry) > budget {
+            entry = entry.chars().take(budget.saturating_sub(1) * 4).collect();
+            entry.push('…');
+        }
+        let tokens = estimate_tokens(&entry);
+        if !current.is_empty() && current_tokens + tokens > budget {
+            chunks.push(std::mem::take(&mut current));
+            current_tokens = 0;
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(&entry);
+        current_tokens += tokens;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Tokens of transcript one summary request may carry, so it fits the
+/// window of the model that is being summarized for
+fn summary_input_budget(model: &str) -> usize {
+    context_limit::context_limit(model)
+        .map(|limit| {
+            (limit.saturating_sub(SUMMARY_MAX_TOKENS as usize) as f32 * SUMMARY_INPUT_RATIO) as usize
+        })
+        .unwrap_or(SUMMARY_DEFAULT_INPUT_TOKENS)
+        .max(SUMMARY_MIN_INPUT_TOKENS)
+}
+
+/// Summary of `dropped`, one request per piece of the transcript that fits
+/// the model's window
+async fn summarize(dropped: &[ChatMessage], config: &Config) -> Result<String, String> {
+    let chunks = transcript_chunks(dropped, summary_input_budget(&config.get_model()));
+    let mut hasher = DefaultHasher::new();
+    chunks.hash(&mut hasher);
+    let key = hasher.finish();
+    if let Some(summary) = SUMMARIES
+        .lock()
+        .ok()
+        .and_then(|cache| cache.as_ref()?.get(&key).cloned())
+    {
+        return Ok(summary);
+    }
+
+    let mut parts = Vec::with_capacity(chunks.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        let part = if chunks.len() > 1 {
+            format!(" (part {} of {})", i + 1, chunks.len())
+        } else {
+            String::new()
+        };
+        let prompt = format!(
+            "Summarize this earlier part{} of a conversation between a user and a coding \
+             assistant. Keep decisions, file names, open tasks and anything the user asked \
+             to remember; leave out pleasantries. Reply with the summary only.\n\n{}",
+            part, chunk
+        );
+        let summary = stream::complete(config, prompt, SUMMARY_MAX_TOKENS, SUMMARY_TIMEOUT)
+            .await
+            .map_err(|err| format!("{:#}", err))?;
+        parts.push(summary);
+    }
+    let summary = parts.join("\n\n");
+    if let Ok(mut cache) = SUMMARIES.lock() {
+        cache
+            .get_or_insert_with(HashMap::new)
+            .insert(key, summary.clone());
+    }
+    Ok(summary)
+}
+
+/// Put a summary exchange right after the system messages of `kept`
+fn insert_summary(kept: &mut Vec<ChatMessage>, summary: String) {
+    let at = kept.iter().take_while(|m| m.role == "system").count();
+    kept.splice(
+        at..at,
+        [
+            text_message(
+                "user",
+                format!("Summary of our earlier conversation:\n\n{}", summary),
+            ),
+            text_message(
+                "assistant",
+                "Understood, continuing from there.".to_string(),
+            ),
+        ],
+    );
+}
+
+/// Replace all but the last `keep_turns` turns of `history` with a summary.
+/// Unlike `apply`, a failed summary is an error and nothing is dropped.
+pub async fn summarize_old(
+    history: Vec<ChatMessage>,
+    keep_turns: usize,
+    config: &Config,
+) -> Result<Vec<ChatMessage>, String> {
+    let Trimmed { mut kept, dropped } =
+        trim(history, &HistoryStrategy::SummarizeOld { keep_turns });
+    if !dropped.is_empty() {
+        insert_summary(&mut kept, summarize(&dropped, config).await?);
+    }
+    Ok(kept)
+}
+
+/// Apply `strategy` to `history`; with `SummarizeOld` the dropped turns are
+/// replaced by a summary exchange right after the system messages
+pub async fn apply(
+    history: Vec<ChatMessage>,
+    strategy: &HistoryStrategy,
+    config: &Config,
+) -> Vec<ChatMessage> {
+    let Trimmed { mut kept, dropped } = trim(history, strategy);
+    if dropped.is_empty() {
+        return kept;
+    }
+    tracing::debug!(
+        dropped = dropped.len(),
+        kept = kept.len(),
+        ?strategy,
+        "Trimmed conversation history"
+    );
+    if !matches!(strategy, HistoryStrategy::SummarizeOld { .. }) {
+        return kept;
+    }
+    match summarize(&dropped, config).await {
+        Ok(summary) => insert_summary(&mut kept, summary),
+        Err(err) => tracing::warn!(
+            "Failed to summarize old history, dropping it instead: {}",
+            err
+        ),
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turns(n: usize) -> Vec<ChatMessage> {
+        let mut history = vec![text_message("system", "You are Arula.".to_string())];
+        for i in 0..n {
+            history.push(text_message("user", format!("question {i}")));
+            history.push(text_message("assistant", "x".repeat(400)));
+        }
+        history
+    }
+
+    #[test]
+    fn test_trim_strategies() {
+        let full = trim(turns(6), &HistoryStrategy::Full);
+        assert_eq!(full.kept.len(), 13);
+        assert!(full.dropped.is_empty());
+
+        let window = trim(turns(6), &HistoryStrategy::SlidingWindow { turns: 2 });
+        assert_eq!(window.kept.len(), 5);
+        assert_eq!(window.kept[0].role, "system");
+        assert_eq!(window.kept[1].content.as_deref(), Some("question 4"));
+        assert_eq!(window.dropped.len(), 8);
+
+        // Each turn is ~103 tokens; the system prompt and two turns fit
+        let budget = trim(turns(6), &HistoryStrategy::TokenBudget { max_tokens: 250 });
+        assert_eq!(budget.kept.len(), 5);
+        // The latest turn is kept even when it alone is over budget
+        let tiny = trim(turns(6), &HistoryStrategy::TokenBudget { max_tokens: 10 });
+        assert_eq!(tiny.kept.len(), 3);
+
+        // 7 turns with blocks of 3: one block is old enough to summarize
+        let summarize = trim(turns(7), &HistoryStrategy::SummarizeOld { keep_turns: 3 });
+        assert_eq!(summarize.dropped.len(), 6);
+        assert_eq!(summarize.kept.len(), 9);
+        assert_eq!(summarize.kept[1].content.as_deref(), Some("question 3"));
+    }
+
+    #[test]
+    fn test_transcript_chunks_fit_budget() {
+        // Each turn is ~108 tokens, so two fit a piece
+        let history = turns(6);
+        let chunks = transcript_chunks(&history[1..], 250);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|c| estimate_tokens(c) <= 250));
+        assert!(chunks[0].starts_with("user: question 0\n\nassistant: xxx"));
+
+        // A message longer than the budget is cut to fit
+        let long = [text_message("user", "y".repeat(10_000))];
+        let chunks = transcript_chunks(&long, 500);
+        assert_eq!(chunks.len(), 1);
+        assert!(estimate_tokens(&chunks[0]) <= 500);
+        assert!(chunks[0].ends_with('…'));
+    }
+}
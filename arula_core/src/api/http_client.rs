@@ -13,9 +13,19 @@
 //! - Lazy initialization with `OnceLock` to avoid blocking startup
 //! - Connection pooling to reuse connections
 //! - HTTP/2 multiplexing when available
+//!
+//! Provider requests should go through the shared clients here rather than
+//! building their own, so TLS sessions and connections are reused across turns.
+//! Timeouts can be tuned with `ARULA_HTTP_CONNECT_TIMEOUT_SECS` and
+//! `ARULA_HTTP_READ_TIMEOUT_SECS`, or per provider in the config, which also
+//! sets how often failed requests are retried (see [`execute_with_retry`]).
+//! The shared clients have no overall timeout, since a stream may run for
+//! minutes; non-streaming requests get [`REQUEST_TIMEOUT`] per request.
 
-use reqwest::Client;
-use std::sync::OnceLock;
+use reqwest::{Client, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 use std::time::Duration;
 
 /// Lazy-initialized HTTP client for AI API requests
@@ -24,12 +34,34 @@ static AI_CLIENT: OnceLock<Client> = OnceLock::new();
 /// Lazy-initialized HTTP client for general requests
 static GENERAL_CLIENT: OnceLock<Client> = OnceLock::new();
 
+/// Lazy-initialized HTTP client for streaming provider requests
+static STREAMING_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Lazy-initialized HTTP/1.1-only streaming client (Z.AI rejects HTTP/2)
+static HTTP1_STREAMING_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Streaming clients with provider-specific timeouts, by (connect, read, HTTP/1.1 only)
+static CUSTOM_STREAMING_CLIENTS: Mutex<Option<HashMap<(Duration, Duration, bool), Client>>> =
+    Mutex::new(None);
+
+/// Environment variable overriding the connection timeout in seconds
+pub const CONNECT_TIMEOUT_ENV: &str = "ARULA_HTTP_CONNECT_TIMEOUT_SECS";
+
+/// Environment variable overriding the read (idle stream) timeout in seconds
+pub const READ_TIMEOUT_ENV: &str = "ARULA_HTTP_READ_TIMEOUT_SECS";
+
+/// Total deadline of a non-streaming request, unless the provider sets
+/// `timeout_seconds`; streaming requests are only bounded per chunk
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Configuration for the AI API client
 pub struct AiClientConfig {
     /// Overall request timeout (default: 5 minutes)
     pub timeout: Duration,
     /// Connection timeout (default: 30 seconds)
     pub connect_timeout: Duration,
+    /// Maximum gap between received bytes (default: 120 seconds)
+    pub read_timeout: Duration,
     /// Pool idle timeout (default: 90 seconds)
     pub pool_idle_timeout: Duration,
     /// Max idle connections per host (default: 10)
@@ -45,6 +77,7 @@ impl Default for AiClientConfig {
         Self {
             timeout: Duration::from_secs(300), // 5 minutes for AI responses
             connect_timeout: Duration::from_secs(30), // 30s to establish connection
+            read_timeout: Duration::from_secs(120), // Slow models can pause between chunks
             pool_idle_timeout: Duration::from_secs(90), // Keep connections alive
             pool_max_idle_per_host: 10,        // Multiple parallel requests
             http2_keep_alive_interval: Duration::from_secs(30),
@@ -53,6 +86,30 @@ impl Default for AiClientConfig {
     }
 }
 
+impl AiClientConfig {
+    /// Default configuration with timeouts overridden from the environment
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(secs) = env_secs(CONNECT_TIMEOUT_ENV) {
+            config.connect_timeout = secs;
+        }
+        if let Some(secs) = env_secs(READ_TIMEOUT_ENV) {
+            config.read_timeout = secs;
+        }
+        config
+    }
+}
+
+fn env_secs(name: &str) -> Option<Duration> {
+    std::env::var(name)
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
 /// Get the optimized HTTP client for AI API requests
 ///
 /// This client is configured for long-running AI requests with:
@@ -73,7 +130,24 @@ impl Default for AiClientConfig {
 /// ```
 pub fn get_ai_client() -> &'static Client {
     AI_CLIENT.get_or_init(|| {
-        create_ai_client(AiClientConfig::default()).expect("Failed to create AI HTTP client")
+        create_ai_client(AiClientConfig::from_env()).expect("Failed to create AI HTTP client")
+    })
+}
+
+/// Get the shared client for streaming provider requests
+///
+/// `Client` is reference-counted, so cloning the returned client keeps using
+/// the same connection pool.
+pub fn get_streaming_client() -> &'static Client {
+    STREAMING_CLIENT.get_or_init(|| {
+        create_streaming_client().expect("Failed to create streaming HTTP client")
+    })
+}
+
+/// Get the shared HTTP/1.1-only streaming client
+pub fn get_http1_streaming_client() -> &'static Client {
+    HTTP1_STREAMING_CLIENT.get_or_init(|| {
+        create_http1_streaming_client().expect("Failed to create HTTP/1.1 streaming client")
     })
 }
 
@@ -105,6 +179,10 @@ pub fn create_ai_client(config: AiClientConfig) -> Result<Client, reqwest::Error
         .pool_max_idle_per_host(config.pool_max_idle_per_host)
         // TCP keep-alive for network stability
         .tcp_keepalive(config.tcp_keepalive)
+        .tcp_nodelay(true)
+        // HTTP/2 keep-alive pings keep idle pooled connections warm
+        .http2_keep_alive_interval(config.http2_keep_alive_interval)
+        .http2_keep_alive_while_idle(true)
         // User agent
         .user_agent(format!("arula-cli/{}", env!("CARGO_PKG_VERSION")))
         // Build
@@ -125,16 +203,197 @@ pub fn create_general_client() -> Result<Client, reqwest::Error> {
 /// Create a client for streaming requests (no overall timeout)
 ///
 /// Streaming requests need special handling because the total
-/// response time is unpredictable.
+/// response time is unpredictable; only the gap between chunks is bounded.
 pub fn create_streaming_client() -> Result<Client, reqwest::Error> {
+    let config = AiClientConfig::from_env();
+    streaming_builder(&config)
+        .http2_keep_alive_interval(config.http2_keep_alive_interval)
+        .http2_keep_alive_while_idle(true)
+        .build()
+}
+
+/// Create a streaming client restricted to HTTP/1.1
+pub fn create_http1_streaming_client() -> Result<Client, reqwest::Error> {
+    streaming_builder(&AiClientConfig::from_env())
+        .http1_only()
+        .build()
+}
+
+fn streaming_builder(config: &AiClientConfig) -> reqwest::ClientBuilder {
     Client::builder()
         // No overall timeout - streaming can take any length
-        .connect_timeout(Duration::from_secs(30))
-        .pool_idle_timeout(Duration::from_secs(90))
-        .pool_max_idle_per_host(10)
-        .tcp_keepalive(Duration::from_secs(60))
+        .connect_timeout(config.connect_timeout)
+        .read_timeout(config.read_timeout)
+        .pool_idle_timeout(config.pool_idle_timeout)
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .tcp_keepalive(config.tcp_keepalive)
+        .tcp_nodelay(true)
+        .connection_verbose(std::env::var("ARULA_DEBUG").unwrap_or_default() == "1")
         .user_agent(format!("arula-cli/{}", env!("CARGO_PKG_VERSION")))
+}
+
+/// Get a shared streaming client with the given timeouts, falling back to
+/// the environment or defaults for unset ones
+pub fn get_streaming_client_with_timeouts(
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    http1_only: bool,
+) -> Client {
+    let mut config = AiClientConfig::from_env();
+    config.connect_timeout = connect_timeout.unwrap_or(config.connect_timeout);
+    config.read_timeout = read_timeout.unwrap_or(config.read_timeout);
+    let key = (config.connect_timeout, config.read_timeout, http1_only);
+
+    let mut clients = CUSTOM_STREAMING_CLIENTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let clients = clients.get_or_insert_with(HashMap::new);
+    if let Some(client) = clients.get(&key) {
+        return client.clone();
+    }
+    let builder = streaming_builder(&config);
+    let builder = if http1_only {
+        builder.http1_only()
+    } else {
+        builder
+            .http2_keep_alive_interval(config.http2_keep_alive_interval)
+            .http2_keep_alive_while_idle(true)
+    };
+    let client = builder
         .build()
+        .expect("Failed to create streaming HTTP client");
+    clients.insert(key, client.clone());
+    client
+}
+
+/// How often and how patiently a failed provider request is retried
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (default: 3)
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one (default: 1 second)
+    pub initial_delay: Duration,
+    /// Longest delay between attempts, including `Retry-After` (default: 30 seconds)
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff before retry number `retry` (1-based), with
+    /// jitter so parallel sessions don't retry in lockstep: a random delay
+    /// between half and all of the backoff
+    pub fn delay(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+        backoff / 2 + backoff.mul_f64(fastrand::f64() / 2.0)
+    }
+}
+
+/// A retry about to happen, shown by the frontends as a transient status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryNotice {
+    /// 1-based number of this retry
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub delay: Duration,
+    /// What failed, e.g. `429 Too Many Requests` or `connection failed`
+    pub reason: String,
+    /// The connection broke mid-response and the retry continues the answer
+    #[serde(default)]
+    pub resuming: bool,
+}
+
+impl std::fmt::Display for RetryNotice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = if self.resuming {
+            "reconnecting"
+        } else {
+            "retrying"
+        };
+        write!(
+            f,
+            "{}, {} in {:.1}s ({}/{})",
+            self.reason,
+            action,
+            self.delay.as_secs_f64(),
+            self.attempt,
+            self.max_retries
+        )
+    }
+}
+
+/// Statuses worth retrying: timeouts, rate limits and overloaded or
+/// unavailable servers
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 408 | 425 | 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+/// Delay a `Retry-After` header asks for, in seconds (HTTP dates are ignored)
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+/// Send `request`, retrying connection failures, timeouts and retryable
+/// statuses with backoff. `on_retry` is called before each wait. Once the
+/// retries are used up the last response or error is returned as is.
+pub async fn execute_with_retry(
+    client: &Client,
+    request: reqwest::Request,
+    policy: &RetryPolicy,
+    mut on_retry: impl FnMut(&RetryNotice),
+) -> reqwest::Result<Response> {
+    let mut attempt = 0;
+    loop {
+        // Streaming bodies can't be cloned, so those get a single attempt
+        let Some(retry_request) = request
+            .try_clone()
+            .filter(|_| attempt < policy.max_retries)
+        else {
+            return client.execute(request).await;
+        };
+        attempt += 1;
+
+        let (reason, delay) = match client.execute(retry_request).await {
+            Ok(response) if is_retryable_status(response.status()) => {
+                let delay = retry_after(&response).map(|d| d.min(policy.max_delay));
+                (response.status().to_string(), delay)
+            }
+            Ok(response) => return Ok(response),
+            Err(err) if err.is_timeout() => ("request timed out".to_string(), None),
+            Err(err) if err.is_connect() => ("connection failed".to_string(), None),
+            Err(err) => return Err(err),
+        };
+        let notice = RetryNotice {
+            attempt,
+            max_retries: policy.max_retries,
+            delay: delay.unwrap_or_else(|| policy.delay(attempt)),
+            reason,
+            resuming: false,
+        };
+        tracing::warn!(url = %request.url(), "{}", notice);
+        on_retry(&notice);
+        tokio::time::sleep(notice.delay).await;
+    }
 }
 
 /// Create a client with custom timeout
@@ -197,6 +456,44 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_get_streaming_clients() {
+        assert!(std::ptr::eq(get_streaming_client(), get_streaming_client()));
+        assert!(std::ptr::eq(
+            get_http1_streaming_client(),
+            get_http1_streaming_client()
+        ));
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(10),
+        };
+        for (retry, backoff) in [(1, 2), (2, 4), (3, 8), (4, 10), (9, 10)] {
+            let delay = policy.delay(retry);
+            let backoff = Duration::from_secs(backoff);
+            assert!(delay >= backoff / 2 && delay <= backoff, "{retry}: {delay:?}");
+        }
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+
+        let notice = RetryNotice {
+            attempt: 1,
+            max_retries: 3,
+            delay: Duration::from_millis(1500),
+            reason: StatusCode::TOO_MANY_REQUESTS.to_string(),
+            resuming: false,
+        };
+        assert_eq!(
+            notice.to_string(),
+            "429 Too Many Requests, retrying in 1.5s (1/3)"
+        );
+    }
+
     #[test]
     fn test_create_client_with_timeout() {
         let result = create_client_with_timeout(60);
@@ -7,16 +7,38 @@
 //! - `api` - Core API client for AI providers
 //! - `agent` - Modern AI agent framework with type-safe tools
 //! - `agent_client` - High-level agent client
+//! - `batch_map` - One prompt over many files (`arula map`)
+//! - `bench` - Cross-provider latency benchmark (`arula bench`)
+//! - `commit_message` - Conventional Commits messages for staged changes (`arula commit`)
+//! - `context_limit` - Request size against the model's context window, and history compaction
+//! - `history` - Conversation history trimming strategies
+//! - `model_info` - Context length, pricing and modalities from OpenRouter's model list
 //! - `models` - Unified model caching system
+//! - `ollama` - Installed model listing, pulls and deletion for Ollama
+//! - `provider_check` - Test request classifying wrong keys, URLs and models, and health pings
+//! - `review` - Chunked code review of a diff (`arula review`)
 //! - `http_client` - Optimized HTTP client with connection pooling
 //! - `stream` - Unified streaming logic with consolidated tool support
+//! - `tool_args` - Incremental assembly and repair of streamed tool arguments
+//! - `watch` - Re-running a prompt when watched files change (`arula watch`)
 
 pub mod agent;
 pub mod agent_client;
 pub mod api;
+pub mod batch_map;
+pub mod bench;
+pub mod commit_message;
+pub mod context_limit;
+pub mod history;
 pub mod http_client;
+pub mod model_info;
 pub mod models;
+pub mod ollama;
+pub mod provider_check;
+pub mod review;
 pub mod stream;
+pub mod tool_args;
+pub mod watch;
 pub mod xml_toolcall;
 
 // Note: Types are available via their modules:
@@ -0,0 +1,214 @@
+//! Model metadata from OpenRouter's model list
+//!
+//! OpenRouter's `/models` endpoint reports the context length, per-token
+//! prices and input modalities of every model it routes to. The fetcher
+//! stores them here (and in `~/.arula/cache/model_info.json`) so the model
+//! selectors can show them next to each model and the usage store can price
+//! OpenRouter responses with the actual rates instead of its built-in table.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// Metadata by model id, loaded from disk on first use
+static MODELS: RwLock<Option<HashMap<String, ModelInfo>>> = RwLock::new(None);
+
+/// What OpenRouter reports about one model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    pub context_length: Option<u32>,
+    /// USD per million prompt tokens
+    pub prompt_price: Option<f64>,
+    /// USD per million completion tokens
+    pub completion_price: Option<f64>,
+    /// Input modalities such as "text", "image", "file" or "audio"
+    #[serde(default)]
+    pub input_modalities: Vec<String>,
+}
+
+impl ModelInfo {
+    /// Whether the model only takes text
+    pub fn is_text_only(&self) -> bool {
+        self.input_modalities.iter().all(|m| m == "text")
+    }
+
+    /// Inputs beyond text, for badges next to the model name
+    pub fn badges(&self) -> Vec<&str> {
+        self.input_modalities
+            .iter()
+            .map(String::as_str)
+            .filter(|m| *m != "text")
+            .collect()
+    }
+
+    /// Whether both prices are zero (OpenRouter's `:free` variants)
+    pub fn is_free(&self) -> bool {
+        self.prompt_price == Some(0.0) && self.completion_price == Some(0.0)
+    }
+
+    /// One-line summary such as `200K ctx · $3.00 / $15.00 per Mtok`
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(tokens) = self.context_length {
+            parts.push(format!("{} ctx", format_tokens(tokens)));
+        }
+        if self.is_free() {
+            parts.push("free".to_string());
+        } else if let (Some(input), Some(output)) = (self.prompt_price, self.completion_price) {
+            parts.push(format!("${:.2} / ${:.2} per Mtok", input, output));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// Token count such as `128K` or `1M`
+pub fn format_tokens(tokens: u32) -> String {
+    if tokens >= 1_000_000 && tokens % 1_000_000 < 50_000 {
+        format!("{}M", tokens / 1_000_000)
+    } else if tokens >= 1_000_000 {
+        format!("{:.1}M", tokens as f64 / 1_000_000.0)
+    } else if tokens >= 1000 {
+        format!("{}K", tokens / 1000)
+    } else {
+        tokens.to_string()
+    }
+}
+
+/// Per-token price string as OpenRouter sends it, in USD per million tokens.
+/// Negative prices mark variable-priced routers and count as unknown.
+fn per_mtok(price: &Value) -> Option<f64> {
+    let price: f64 = match price {
+        Value::String(s) => s.parse().ok()?,
+        other => other.as_f64()?,
+    };
+    (price >= 0.0).then_some(price * 1_000_000.0)
+}
+
+/// Metadata of every model in an OpenRouter `/models` response
+pub fn parse_openrouter(json: &Value) -> Vec<ModelInfo> {
+    json["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|model| {
+            let id = model["id"].as_str()?.to_string();
+            let architecture = &model["architecture"];
+            let input_modalities = match architecture["input_modalities"].as_array() {
+                Some(modalities) => modalities
+                    .iter()
+                    .filter_map(|m| m.as_str().map(str::to_string))
+                    .collect(),
+                // Older responses only have "text+image->text"
+                None => architecture["modality"]
+                    .as_str()
+                    .and_then(|m| m.split("->").next())
+                    .map(|inputs| inputs.split('+').map(str::to_string).collect())
+                    .unwrap_or_else(|| vec!["text".to_string()]),
+            };
+            Some(ModelInfo {
+                id,
+                context_length: model["context_length"].as_u64().map(|n| n as u32),
+                prompt_price: per_mtok(&model["pricing"]["prompt"]),
+                completion_price: per_mtok(&model["pricing"]["completion"]),
+                input_modalities,
+            })
+        })
+        .collect()
+}
+
+fn cache_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".arula").join("cache").join("model_info.json"))
+}
+
+fn load_from_disk() -> HashMap<String, ModelInfo> {
+    cache_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Replace the stored metadata with a fresh model list and persist it
+pub fn store(models: Vec<ModelInfo>) {
+    if models.is_empty() {
+        return;
+    }
+    let models: HashMap<String, ModelInfo> =
+        models.into_iter().map(|m| (m.id.clone(), m)).collect();
+    if let Some(path) = cache_path() {
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, serde_json::to_string(&models)?));
+        if let Err(e) = written {
+            tracing::debug!("Failed to persist model metadata: {}", e);
+        }
+    }
+    if let Ok(mut stored) = MODELS.write() {
+        *stored = Some(models);
+    }
+}
+
+/// Metadata of `model` (an OpenRouter id such as `anthropic/claude-sonnet-4`),
+/// if it has been fetched
+pub fn get(model: &str) -> Option<ModelInfo> {
+    if let Ok(stored) = MODELS.read() {
+        if let Some(models) = stored.as_ref() {
+            return models.get(model).cloned();
+        }
+    }
+    let mut stored = MODELS.write().ok()?;
+    stored
+        .get_or_insert_with(load_from_disk)
+        .get(model)
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_openrouter_models() {
+        let json = serde_json::json!({
+            "data": [
+                {
+                    "id": "anthropic/claude-sonnet-4",
+                    "context_length": 200000,
+                    "architecture": {
+                        "modality": "text+image->text",
+                        "input_modalities": ["image", "text", "file"]
+                    },
+                    "pricing": {"prompt": "0.000003", "completion": "0.000015"}
+                },
+                {
+                    "id": "meta-llama/llama-3.3-70b-instruct:free",
+                    "context_length": 131072,
+                    "architecture": {"modality": "text->text"},
+                    "pricing": {"prompt": "0", "completion": "0"}
+                },
+                {
+                    "id": "openrouter/auto",
+                    "context_length": 2000000,
+                    "pricing": {"prompt": "-1", "completion": "-1"}
+                }
+            ]
+        });
+        let models = parse_openrouter(&json);
+        assert_eq!(models.len(), 3);
+
+        let sonnet = &models[0];
+        assert_eq!(sonnet.badges(), ["image", "file"]);
+        assert!((sonnet.prompt_price.unwrap() - 3.0).abs() < 1e-9);
+        assert_eq!(sonnet.summary(), "200K ctx · $3.00 / $15.00 per Mtok");
+
+        let llama = &models[1];
+        assert!(llama.is_text_only() && llama.is_free());
+        assert_eq!(llama.summary(), "131K ctx · free");
+
+        assert_eq!(models[2].prompt_price, None);
+        assert_eq!(models[2].summary(), "2M ctx");
+    }
+}
@@ -9,6 +9,8 @@
 //! - Caches are time-limited with configurable TTL
 //! - Background fetching support for responsive UI
 //! - Thread-safe using `std::sync::Mutex` (not async mutex, per Tokio best practices)
+//! - Optionally persisted to `~/.arula/cache/models.json`, so model lists from a
+//!   previous run are available immediately and refreshed once their TTL expires
 //!
 //! # Usage
 //!
@@ -21,13 +23,59 @@
 //! let models = cache.get_or_fetch_blocking(&OpenAIFetcher, "api_key", None);
 //! ```
 
+use crate::api::http_client;
+use crate::api::model_info;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
+/// Timeout for model list requests
+const MODEL_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Context window sizes in tokens by model-name prefix, most specific first
+const CONTEXT_WINDOWS: &[(&str, u32)] = &[
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4-mini", 200_000),
+    ("claude-", 200_000),
+    ("glm-4.6", 200_000),
+    ("glm-4", 128_000),
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini-", 1_048_576),
+    ("deepseek", 128_000),
+    ("grok-4", 256_000),
+    ("grok-code", 256_000),
+    ("grok-3", 131_072),
+    ("llama3.1", 128_000),
+    ("llama3.2", 128_000),
+    ("llama3", 8_192),
+    ("qwen2.5", 32_768),
+    ("mistral", 32_768),
+];
+
+/// Context window of `model` in tokens, if known. Router prefixes such as
+/// `anthropic/` and Ollama tags such as `:8b` are ignored.
+pub fn context_window(model: &str) -> Option<u32> {
+    let model = model.to_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    CONTEXT_WINDOWS
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, tokens)| *tokens)
+}
+
 /// Cached model list with expiration tracking
 #[derive(Clone, Debug)]
 pub struct CachedModels {
@@ -35,6 +83,8 @@ pub struct CachedModels {
     models: Vec<String>,
     /// When this cache entry was created
     cached_at: Instant,
+    /// Wall-clock time of the fetch, kept for persisting the entry
+    fetched_at: DateTime<Utc>,
     /// Time-to-live for this cache entry
     ttl: Duration,
 }
@@ -45,10 +95,28 @@ impl CachedModels {
         Self {
             models,
             cached_at: Instant::now(),
+            fetched_at: Utc::now(),
+            ttl,
+        }
+    }
+
+    /// Restore an entry that was fetched at `fetched_at`
+    fn restored(models: Vec<String>, fetched_at: DateTime<Utc>, ttl: Duration) -> Self {
+        let age = (Utc::now() - fetched_at).to_std().unwrap_or_default();
+        Self {
+            models,
+            // An age older than the monotonic clock allows means long expired anyway
+            cached_at: Instant::now().checked_sub(age).unwrap_or_else(|| Instant::now() - ttl),
+            fetched_at,
             ttl,
         }
     }
 
+    /// Whether this entry holds an error message or nothing instead of models
+    pub fn is_error(&self) -> bool {
+        self.models.iter().all(|m| m.starts_with("⚠️"))
+    }
+
     /// Check if this cache entry has expired
     pub fn is_expired(&self) -> bool {
         self.cached_at.elapsed() > self.ttl
@@ -85,6 +153,14 @@ pub trait ModelFetcher: Send + Sync {
     }
 }
 
+/// On-disk form of a cache entry
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedModels {
+    models: Vec<String>,
+    fetched_at: DateTime<Utc>,
+    ttl_secs: u64,
+}
+
 /// Unified model cache manager
 ///
 /// Manages model caches for all providers with automatic expiration.
@@ -93,6 +169,8 @@ pub struct ModelCacheManager {
     caches: Mutex<HashMap<String, CachedModels>>,
     /// Default TTL for cache entries
     default_ttl: Duration,
+    /// File the caches are persisted to, if any
+    disk_path: Option<PathBuf>,
     /// HTTP client for fetching models
     client: Client,
 }
@@ -103,18 +181,84 @@ impl ModelCacheManager {
         Self {
             caches: Mutex::new(HashMap::new()),
             default_ttl: Duration::from_secs(ttl_minutes * 60),
+            disk_path: None,
             client: Self::create_client(),
         }
     }
 
-    /// Create optimized HTTP client for model fetching
+    /// Create a cache manager persisted to `~/.arula/cache/models.json`
+    ///
+    /// Falls back to an in-memory cache when there is no home directory.
+    pub fn persistent(ttl_minutes: u64) -> Self {
+        match dirs::home_dir() {
+            Some(home) => {
+                Self::with_disk_path(ttl_minutes, home.join(".arula").join("cache").join("models.json"))
+            }
+            None => Self::new(ttl_minutes),
+        }
+    }
+
+    /// Create a cache manager persisted to `path`, loading any entries already there
+    pub fn with_disk_path(ttl_minutes: u64, path: impl Into<PathBuf>) -> Self {
+        let mut manager = Self::new(ttl_minutes);
+        let path = path.into();
+        if let Ok(mut caches) = manager.caches.lock() {
+            caches.extend(Self::load_from_disk(&path));
+        }
+        manager.disk_path = Some(path);
+        manager
+    }
+
+    fn load_from_disk(path: &Path) -> HashMap<String, CachedModels> {
+        let Ok(content) = fs::read_to_string(path) else {
+            return HashMap::new();
+        };
+        let persisted: HashMap<String, PersistedModels> =
+            serde_json::from_str(&content).unwrap_or_default();
+        persisted
+            .into_iter()
+            .map(|(provider, entry)| {
+                let ttl = Duration::from_secs(entry.ttl_secs);
+                (provider, CachedModels::restored(entry.models, entry.fetched_at, ttl))
+            })
+            .collect()
+    }
+
+    /// Write successful entries to disk; errors are only kept in memory
+    fn save_to_disk(&self, caches: &HashMap<String, CachedModels>) {
+        let Some(path) = &self.disk_path else {
+            return;
+        };
+        let persisted: HashMap<&String, PersistedModels> = caches
+            .iter()
+            .filter(|(_, cached)| !cached.is_error())
+            .map(|(provider, cached)| {
+                (
+                    provider,
+                    PersistedModels {
+                        models: cached.models.clone(),
+                        fetched_at: cached.fetched_at,
+                        ttl_secs: cached.ttl.as_secs(),
+                    },
+                )
+            })
+            .collect();
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json = serde_json::to_string_pretty(&persisted).unwrap_or_default();
+                fs::write(path, json)
+            });
+        if let Err(e) = result {
+            crate::debug_module!("CACHE", "Failed to persist model cache: {}", e);
+        }
+    }
+
+    /// Shared pooled HTTP client for model fetching
     fn create_client() -> Client {
-        Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("arula-cli/1.0")
-            .pool_idle_timeout(Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client")
+        http_client::get_general_client().clone()
     }
 
     /// Get cached models for a provider (if not expired)
@@ -129,25 +273,46 @@ impl ModelCacheManager {
         }
     }
 
+    /// Get cached models for a provider even if expired
+    ///
+    /// Lets the UI show the last known list while a refresh is in flight.
+    pub fn get_cached_or_stale(&self, provider: &str) -> Option<Vec<String>> {
+        let caches = self.caches.lock().ok()?;
+        caches.get(provider).map(|cached| cached.models().to_vec())
+    }
+
     /// Check if a provider has valid cached models
     pub fn has_valid_cache(&self, provider: &str) -> bool {
         self.get_cached(provider).is_some()
     }
 
+    /// Whether a provider has an unexpired, successful model list (no refresh needed)
+    pub fn is_fresh(&self, provider: &str) -> bool {
+        self.caches
+            .lock()
+            .ok()
+            .and_then(|caches| caches.get(provider).map(|c| !c.is_expired() && !c.is_error()))
+            .unwrap_or(false)
+    }
+
     /// Cache models for a provider
     pub fn cache(&self, provider: &str, models: Vec<String>) {
-        if let Ok(mut caches) = self.caches.lock() {
-            caches.insert(
-                provider.to_string(),
-                CachedModels::new(models, self.default_ttl),
-            );
-        }
+        self.cache_with_ttl(provider, models, self.default_ttl);
     }
 
     /// Cache models with custom TTL
+    ///
+    /// A failed fetch does not replace a previously fetched list.
     pub fn cache_with_ttl(&self, provider: &str, models: Vec<String>, ttl: Duration) {
         if let Ok(mut caches) = self.caches.lock() {
-            caches.insert(provider.to_string(), CachedModels::new(models, ttl));
+            let entry = CachedModels::new(models, ttl);
+            let keeps_previous = entry.is_error()
+                && caches.get(provider).is_some_and(|existing| !existing.is_error());
+            if keeps_previous {
+                return;
+            }
+            caches.insert(provider.to_string(), entry);
+            self.save_to_disk(&caches);
         }
     }
 
@@ -155,6 +320,7 @@ impl ModelCacheManager {
     pub fn invalidate(&self, provider: &str) {
         if let Ok(mut caches) = self.caches.lock() {
             caches.remove(provider);
+            self.save_to_disk(&caches);
         }
     }
 
@@ -162,6 +328,7 @@ impl ModelCacheManager {
     pub fn invalidate_all(&self) {
         if let Ok(mut caches) = self.caches.lock() {
             caches.clear();
+            self.save_to_disk(&caches);
         }
     }
 
@@ -262,17 +429,11 @@ pub struct OpenAIFetcher;
 #[async_trait]
 impl ModelFetcher for OpenAIFetcher {
     async fn fetch_models(&self, api_key: &str, _api_url: Option<&str>) -> Vec<String> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("arula-cli/1.0")
-            .build();
-
-        let client = match client {
-            Ok(c) => c,
-            Err(e) => return vec![format!("⚠️ Failed to create HTTP client: {}", e)],
-        };
+        let client = http_client::get_general_client();
 
-        let mut request = client.get("https://api.openai.com/v1/models");
+        let mut request = client
+            .get("https://api.openai.com/v1/models")
+            .timeout(MODEL_FETCH_TIMEOUT);
 
         if !api_key.is_empty() {
             request = request.header("Authorization", format!("Bearer {}", api_key));
@@ -356,18 +517,10 @@ impl ModelFetcher for OllamaFetcher {
             .trim_end_matches("/api/generate")
             .trim_end_matches("/api");
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("arula-cli/1.0")
-            .build();
-
-        let client = match client {
-            Ok(c) => c,
-            Err(e) => return vec![format!("⚠️ Failed to create HTTP client: {}", e)],
-        };
+        let client = http_client::get_general_client();
 
         let url = format!("{}/api/tags", base_url);
-        match client.get(&url).send().await {
+        match client.get(&url).timeout(MODEL_FETCH_TIMEOUT).send().await {
             Ok(response) => {
                 let status = response.status();
                 if status.is_success() {
@@ -424,17 +577,11 @@ pub struct OpenRouterFetcher;
 #[async_trait]
 impl ModelFetcher for OpenRouterFetcher {
     async fn fetch_models(&self, api_key: &str, _api_url: Option<&str>) -> Vec<String> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("arula-cli/1.0")
-            .build();
-
-        let client = match client {
-            Ok(c) => c,
-            Err(e) => return vec![format!("⚠️ Failed to create HTTP client: {}", e)],
-        };
+        let client = http_client::get_general_client();
 
-        let mut request = client.get("https://openrouter.ai/api/v1/models");
+        let mut request = client
+            .get("https://openrouter.ai/api/v1/models")
+            .timeout(MODEL_FETCH_TIMEOUT);
 
         if !api_key.is_empty() {
             request = request.header("Authorization", format!("Bearer {}", api_key));
@@ -446,6 +593,7 @@ impl ModelFetcher for OpenRouterFetcher {
                 if status.is_success() {
                     match response.json::<Value>().await {
                         Ok(json) => {
+                            model_info::store(model_info::parse_openrouter(&json));
                             let mut models = Vec::new();
                             if let Some(data) = json["data"].as_array() {
                                 for model_info in data {
@@ -492,24 +640,13 @@ pub struct ZaiFetcher;
 #[async_trait]
 impl ModelFetcher for ZaiFetcher {
     async fn fetch_models(&self, api_key: &str, _api_url: Option<&str>) -> Vec<String> {
-        use reqwest::Client;
-        use std::time::Duration;
-        
         let models_url = "https://api.z.ai/api/anthropic/v1/models";
-        
-        let client = match Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("arula/1.0")
-            .build()
-        {
-            Ok(client) => client,
-            Err(e) => {
-                return vec![format!("⚠️ Failed to create HTTP client: {}", e)];
-            }
-        };
+
+        let client = http_client::get_general_client();
         
         let request = client
             .get(models_url)
+            .timeout(MODEL_FETCH_TIMEOUT)
             .header("x-api-key", api_key);
         
         match request.send().await {
@@ -556,6 +693,165 @@ impl ModelFetcher for ZaiFetcher {
     }
 }
 
+/// xAI (Grok) model fetcher
+pub struct XaiFetcher;
+
+#[async_trait]
+impl ModelFetcher for XaiFetcher {
+    async fn fetch_models(&self, api_key: &str, _api_url: Option<&str>) -> Vec<String> {
+        fetch_model_ids("xAI", "https://api.x.ai/v1/models", api_key).await
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "xai"
+    }
+}
+
+/// DeepSeek model fetcher
+pub struct DeepSeekFetcher;
+
+#[async_trait]
+impl ModelFetcher for DeepSeekFetcher {
+    async fn fetch_models(&self, api_key: &str, _api_url: Option<&str>) -> Vec<String> {
+        fetch_model_ids("DeepSeek", "https://api.deepseek.com/models", api_key).await
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "deepseek"
+    }
+
+    fn default_ttl_minutes(&self) -> u64 {
+        60 // 1 hour - the list rarely changes
+    }
+}
+
+/// Ids listed by an OpenAI-style `/models` endpoint
+async fn fetch_model_ids(display_name: &str, url: &str, api_key: &str) -> Vec<String> {
+    if api_key.is_empty() {
+        return vec![format!("⚠️ Set a {} API key to list models", display_name)];
+    }
+    let request = http_client::get_general_client()
+        .get(url)
+        .timeout(MODEL_FETCH_TIMEOUT)
+        .header("Authorization", format!("Bearer {}", api_key));
+
+    match request.send().await {
+        Ok(response) if response.status() == 401 => vec!["⚠️ Invalid API key".to_string()],
+        Ok(response) if response.status().is_success() => {
+            match response.json::<Value>().await {
+                Ok(json) => {
+                    let mut models: Vec<String> = json["data"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|model| model["id"].as_str().map(str::to_string))
+                        .collect();
+                    models.sort();
+                    models
+                }
+                Err(e) => vec![format!("⚠️ Failed to parse {} response: {}", display_name, e)],
+            }
+        }
+        Ok(response) => vec![format!(
+            "⚠️ {} API error: Status {}",
+            display_name,
+            response.status()
+        )],
+        Err(e) => vec![format!("⚠️ Failed to fetch {} models: {}", display_name, e)],
+    }
+}
+
+/// Model fetcher for OpenAI-compatible servers running locally (LM Studio,
+/// llamafile), which list their models at `/v1/models` without a key
+pub struct LocalServerFetcher {
+    name: &'static str,
+    display_name: &'static str,
+    default_url: &'static str,
+}
+
+impl LocalServerFetcher {
+    pub const LM_STUDIO: Self = Self {
+        name: "lm studio",
+        display_name: "LM Studio",
+        default_url: "http://localhost:1234/v1",
+    };
+
+    pub const LLAMAFILE: Self = Self {
+        name: "llamafile",
+        display_name: "llamafile",
+        default_url: "http://localhost:8080/v1",
+    };
+}
+
+#[async_trait]
+impl ModelFetcher for LocalServerFetcher {
+    async fn fetch_models(&self, api_key: &str, api_url: Option<&str>) -> Vec<String> {
+        let base_url = api_url
+            .unwrap_or(self.default_url)
+            .trim_end_matches('/')
+            .trim_end_matches("/chat/completions")
+            .trim_end_matches("/models")
+            .trim_end_matches("/v1");
+
+        let mut request = http_client::get_general_client()
+            .get(format!("{}/v1/models", base_url))
+            .timeout(MODEL_FETCH_TIMEOUT);
+        if !api_key.is_empty() {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                match response.json::<Value>().await {
+                    Ok(json) => {
+                        let mut models = local_model_ids(&json);
+                        if models.is_empty() {
+                            models.push(format!("⚠️ No model is loaded in {}", self.display_name));
+                        }
+                        models
+                    }
+                    Err(e) => vec![format!(
+                        "⚠️ Failed to parse {} response: {}",
+                        self.display_name, e
+                    )],
+                }
+            }
+            Ok(response) => vec![format!(
+                "⚠️ {} API error: Status {}",
+                self.display_name,
+                response.status()
+            )],
+            Err(_) => vec![format!(
+                "⚠️ Cannot connect to {}. Is the server running at {}?",
+                self.display_name, base_url
+            )],
+        }
+    }
+
+    fn provider_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn default_ttl_minutes(&self) -> u64 {
+        5 // Loaded models change as often as Ollama's
+    }
+}
+
+/// Chat model ids of a `/v1/models` response; LM Studio lists its embedding
+/// models there too
+fn local_model_ids(json: &Value) -> Vec<String> {
+    let mut models: Vec<String> = json["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|model| model["id"].as_str())
+        .filter(|id| !id.contains("embed"))
+        .map(str::to_string)
+        .collect();
+    models.sort();
+    models
+}
+
 /// Get the appropriate fetcher for a provider name
 pub fn get_fetcher(provider: &str) -> Option<Box<dyn ModelFetcher>> {
     match provider.to_lowercase().as_str() {
@@ -564,6 +860,10 @@ pub fn get_fetcher(provider: &str) -> Option<Box<dyn ModelFetcher>> {
         "ollama" => Some(Box::new(OllamaFetcher)),
         "openrouter" => Some(Box::new(OpenRouterFetcher)),
         "zai" | "z.ai" | "z.ai coding plan" => Some(Box::new(ZaiFetcher)),
+        "xai" | "x.ai" | "grok" => Some(Box::new(XaiFetcher)),
+        "deepseek" => Some(Box::new(DeepSeekFetcher)),
+        "lm studio" | "lmstudio" | "lm-studio" => Some(Box::new(LocalServerFetcher::LM_STUDIO)),
+        "llamafile" => Some(Box::new(LocalServerFetcher::LLAMAFILE)),
         _ => None,
     }
 }
@@ -572,6 +872,14 @@ pub fn get_fetcher(provider: &str) -> Option<Box<dyn ModelFetcher>> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_context_window() {
+        assert_eq!(context_window("gpt-4o-mini"), Some(128_000));
+        assert_eq!(context_window("anthropic/claude-sonnet-4"), Some(200_000));
+        assert_eq!(context_window("llama3:8b"), Some(8_192));
+        assert_eq!(context_window("my-finetune"), None);
+    }
+
     #[test]
     fn test_cached_models() {
         let models = vec!["model1".to_string(), "model2".to_string()];
@@ -631,6 +939,43 @@ mod tests {
         assert!(!manager.has_valid_cache("provider2"));
     }
 
+    #[test]
+    fn test_cache_persists_to_disk() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("models.json");
+
+        let manager = ModelCacheManager::with_disk_path(30, &path);
+        manager.cache("openai", vec!["gpt-4o".to_string()]);
+        manager.cache("ollama", vec!["⚠️ Ollama is not running".to_string()]);
+        assert!(manager.is_fresh("openai"));
+        assert!(!manager.is_fresh("ollama"));
+
+        // A failed refresh keeps the previous list
+        manager.cache("openai", vec!["⚠️ Request failed".to_string()]);
+        assert_eq!(manager.get_cached("openai").unwrap(), vec!["gpt-4o".to_string()]);
+
+        let reloaded = ModelCacheManager::with_disk_path(30, &path);
+        assert_eq!(reloaded.get_cached("openai").unwrap(), vec!["gpt-4o".to_string()]);
+        assert!(reloaded.get_cached_or_stale("ollama").is_none());
+    }
+
+    #[test]
+    fn test_local_model_ids() {
+        let json = serde_json::json!({
+            "object": "list",
+            "data": [
+                {"id": "qwen2.5-coder-7b-instruct", "object": "model"},
+                {"id": "text-embedding-nomic-embed-text-v1.5", "object": "model"},
+                {"id": "llama-3.2-3b-instruct", "object": "model"}
+            ]
+        });
+        assert_eq!(
+            local_model_ids(&json),
+            ["llama-3.2-3b-instruct", "qwen2.5-coder-7b-instruct"]
+        );
+        assert!(local_model_ids(&serde_json::json!({})).is_empty());
+    }
+
     #[test]
     fn test_get_fetcher() {
         assert!(get_fetcher("openai").is_some());
@@ -639,6 +984,10 @@ mod tests {
         assert!(get_fetcher("openrouter").is_some());
         assert!(get_fetcher("zai").is_some());
         assert!(get_fetcher("z.ai").is_some());
+        assert!(get_fetcher("xai").is_some());
+        assert!(get_fetcher("deepseek").is_some());
+        assert!(get_fetcher("LM Studio").is_some());
+        assert!(get_fetcher("llamafile").is_some());
         assert!(get_fetcher("unknown_provider").is_none());
     }
 
@@ -0,0 +1,248 @@
+//! Local model management for Ollama
+//!
+//! Lists the installed models with their sizes, pulls new ones with
+//! streamed progress and deletes unused ones through Ollama's REST API
+//! (`/api/tags`, `/api/pull`, `/api/delete`). Used by the desktop Provider
+//! page when Ollama is selected.
+
+use crate::api::http_client;
+use anyhow::{anyhow, bail, Context, Result};
+use futures::{Stream, StreamExt};
+use serde::Deserialize;
+use std::pin::Pin;
+use std::time::Duration;
+
+const LIST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Ollama's base URL from a configured endpoint, which may include an API path
+pub fn base_url(api_url: Option<&str>) -> String {
+    api_url
+        .unwrap_or("http://localhost:11434")
+        .trim_end_matches('/')
+        .trim_end_matches("/api/chat")
+        .trim_end_matches("/api/tags")
+        .trim_end_matches("/api/generate")
+        .trim_end_matches("/api")
+        .trim_end_matches("/v1")
+        .to_string()
+}
+
+/// An installed model
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LocalModel {
+    pub name: String,
+    /// Bytes on disk
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub modified_at: Option<String>,
+    #[serde(default)]
+    pub details: ModelDetails,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct ModelDetails {
+    #[serde(default)]
+    pub parameter_size: Option<String>,
+    #[serde(default)]
+    pub quantization_level: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Tags {
+    #[serde(default)]
+    models: Vec<LocalModel>,
+}
+
+/// Installed models, largest first
+pub async fn list_models(base_url: &str) -> Result<Vec<LocalModel>> {
+    let response = http_client::get_general_client()
+        .get(format!("{}/api/tags", base_url))
+        .timeout(LIST_TIMEOUT)
+        .send()
+        .await
+        .with_context(|| format!("Cannot connect to Ollama at {}", base_url))?;
+    if !response.status().is_success() {
+        bail!("Ollama returned {}", response.status());
+    }
+    let mut models = response.json::<Tags>().await?.models;
+    models.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    Ok(models)
+}
+
+/// Remove an installed model
+pub async fn delete_model(base_url: &str, name: &str) -> Result<()> {
+    let response = http_client::get_general_client()
+        .delete(format!("{}/api/delete", base_url))
+        .json(&serde_json::json!({ "model": name }))
+        .timeout(LIST_TIMEOUT)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let text = response.text().await.unwrap_or_default();
+        bail!("Failed to delete {}: {}", name, error_message(&text));
+    }
+    Ok(())
+}
+
+/// Progress of a pull
+#[derive(Debug, Clone, PartialEq)]
+pub enum PullEvent {
+    /// Ollama's status line ("pulling manifest", "downloading", "verifying
+    /// sha256 digest", ...) and bytes of the current layer, when known
+    Progress {
+        status: String,
+        completed: u64,
+        total: u64,
+    },
+    Done,
+    Failed(String),
+}
+
+#[derive(Deserialize)]
+struct PullLine {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    completed: u64,
+    #[serde(default)]
+    total: u64,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// One NDJSON line of `/api/pull`
+fn parse_pull_line(line: &str) -> Option<PullEvent> {
+    let line: PullLine = serde_json::from_str(line).ok()?;
+    Some(match line {
+        PullLine {
+            error: Some(error), ..
+        } => PullEvent::Failed(error),
+        PullLine { status, .. } if status == "success" => PullEvent::Done,
+        PullLine {
+            status,
+            completed,
+            total,
+            ..
+        } => PullEvent::Progress {
+            status,
+            completed,
+            total,
+        },
+    })
+}
+
+/// Download `name`, streaming progress until it ends with `Done` or `Failed`
+pub fn pull_model(base_url: String, name: String) -> Pin<Box<dyn Stream<Item = PullEvent> + Send>> {
+    Box::pin(async_stream::stream! {
+        let response = http_client::get_streaming_client()
+            .post(format!("{}/api/pull", base_url))
+            .json(&serde_json::json!({ "model": name, "stream": true }))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Cannot connect to Ollama at {}: {}", base_url, e));
+        let response = match response {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                yield PullEvent::Failed(format!("Ollama returned {}: {}", status, error_message(&text)));
+                return;
+            }
+            Err(err) => {
+                yield PullEvent::Failed(err.to_string());
+                return;
+            }
+        };
+
+        let mut body = response.bytes_stream();
+        let mut buffer = String::new();
+        while let Some(chunk) = body.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    yield PullEvent::Failed(err.to_string());
+                    return;
+                }
+            };
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+            while let Some(pos) = buffer.find('\n') {
+                let line: String = buffer.drain(..=pos).collect();
+                let Some(event) = parse_pull_line(line.trim()) else {
+                    continue;
+                };
+                let finished = !matches!(event, PullEvent::Progress { .. });
+                yield event;
+                if finished {
+                    return;
+                }
+            }
+        }
+        yield PullEvent::Failed("Ollama closed the connection before the pull finished".to_string());
+    })
+}
+
+/// The `error` field of an Ollama error body, or the body itself
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["error"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+/// Human-readable size such as `4.7 GB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1000.0 {
+            break;
+        }
+        size /= 1000.0;
+        unit = next;
+    }
+    if unit == "B" {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pull_lines_and_sizes() {
+        assert_eq!(
+            parse_pull_line(
+                r#"{"status":"pulling 6a0746a1ec1a","digest":"sha256:6a07","total":4661211424,"completed":1000}"#
+            ),
+            Some(PullEvent::Progress {
+                status: "pulling 6a0746a1ec1a".into(),
+                completed: 1000,
+                total: 4661211424,
+            })
+        );
+        assert_eq!(
+            parse_pull_line(r#"{"status":"success"}"#),
+            Some(PullEvent::Done)
+        );
+        assert_eq!(
+            parse_pull_line(r#"{"error":"pull model manifest: file does not exist"}"#),
+            Some(PullEvent::Failed(
+                "pull model manifest: file does not exist".into()
+            ))
+        );
+        assert_eq!(parse_pull_line("not json"), None);
+
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(4_661_211_424), "4.7 GB");
+        assert_eq!(
+            base_url(Some("http://localhost:11434/api/chat/")),
+            "http://localhost:11434"
+        );
+        assert_eq!(base_url(Some("http://gpu:11434/v1")), "http://gpu:11434");
+    }
+}
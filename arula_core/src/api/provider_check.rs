@@ -0,0 +1,317 @@
+//! Connection check for provider settings
+//!
+//! Before provider settings are saved (and during first-launch setup), a
+//! one-token completion is sent with the entered key, URL and model. A failure
+//! is sorted into the usual misconfigurations, so the settings page can say
+//! what to fix instead of the first real prompt failing with a raw API error.
+//!
+//! `ping` is the cheap, periodic counterpart behind the health dot in the
+//! desktop top bar: it lists the provider's models instead of generating, and
+//! only reports whether the service answers, and how fast.
+
+use crate::api::api::ZAIApiError;
+use crate::api::http_client;
+use crate::api::stream;
+use crate::utils::config::Config;
+use crate::utils::error::ApiError;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+/// Answers slower than this count as degraded
+const PING_SLOW: Duration = Duration::from_secs(4);
+
+/// Why a provider check failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckFailure {
+    /// The key was rejected (401/403)
+    InvalidKey,
+    /// Nothing that looks like the provider's API answers at the URL
+    WrongUrl,
+    /// The API answered, but doesn't know the model
+    ModelNotFound(String),
+    /// The server couldn't be reached or didn't answer in time
+    Unreachable(String),
+    /// Any other error, as the provider reported it
+    Other(String),
+}
+
+impl fmt::Display for CheckFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckFailure::InvalidKey => f.write_str("Wrong API key: the provider rejected it"),
+            CheckFailure::WrongUrl => {
+                f.write_str("Wrong API URL: no compatible API answered there")
+            }
+            CheckFailure::ModelNotFound(model) => {
+                write!(f, "Model not found: the provider doesn't offer {}", model)
+            }
+            CheckFailure::Unreachable(reason) => {
+                write!(f, "Couldn't reach the provider: {}", reason)
+            }
+            CheckFailure::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Send a minimal request with the active provider settings of `config`
+pub async fn check(config: &Config) -> Result<(), CheckFailure> {
+    let model = config.get_model();
+    stream::complete(config, "Reply with OK.".to_string(), 1, CHECK_TIMEOUT)
+        .await
+        .map(|_| ())
+        .map_err(|err| classify_error(&err, &model))
+}
+
+fn classify_error(err: &anyhow::Error, model: &str) -> CheckFailure {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            if err.is_connect() || err.is_timeout() || err.is_builder() {
+                return CheckFailure::Unreachable(err.to_string());
+            }
+        }
+        if let Some(err) = cause.downcast_ref::<ZAIApiError>() {
+            match err {
+                ZAIApiError::AuthenticationError { message } => {
+                    return classify(401, message, model)
+                }
+                ZAIApiError::RequestError {
+                    message,
+                    status_code,
+                }
+                | ZAIApiError::StatusError {
+                    message,
+                    status_code,
+                } => return classify(*status_code, message, model),
+                _ => {}
+            }
+        }
+        match cause.downcast_ref::<ApiError>() {
+            Some(ApiError::AuthenticationFailed) => return CheckFailure::InvalidKey,
+            Some(err @ ApiError::Timeout { .. }) => return CheckFailure::Unreachable(err.to_string()),
+            Some(ApiError::ServerError {
+                status_code,
+                message,
+            }) => return classify(*status_code, message, model),
+            _ => {}
+        }
+    }
+
+    let message = format!("{:#}", err);
+    // `ApiClient` reports other failed responses as "API Error <status>: <body>"
+    let status = message
+        .find("API Error ")
+        .and_then(|at| message[at + 10..].split_whitespace().next())
+        .and_then(|code| code.trim_end_matches(':').parse::<u16>().ok());
+    match status {
+        Some(status) => classify(status, &message, model),
+        None => CheckFailure::Other(message),
+    }
+}
+
+/// Sort a failed response by its status and body
+fn classify(status: u16, body: &str, model: &str) -> CheckFailure {
+    let body_lower = body.to_lowercase();
+    let mentions_model = body_lower.contains("model")
+        && (body_lower.contains("not found")
+            || body_lower.contains("does not exist")
+            || body_lower.contains("not exist")
+            || body_lower.contains("invalid model")
+            || body_lower.contains("unknown model")
+            || body_lower.contains("not a valid model"));
+    match status {
+        401 | 403 => CheckFailure::InvalidKey,
+        _ if mentions_model => CheckFailure::ModelNotFound(model.to_string()),
+        404 | 405 => CheckFailure::WrongUrl,
+        _ => CheckFailure::Other(body.trim().to_string()),
+    }
+}
+
+/// How the active provider answered the last ping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    Ok,
+    /// Reachable, but slow, overloaded, erroring or rejecting the key
+    Degraded,
+    Unreachable,
+}
+
+/// Outcome of a `ping`
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    pub health: Health,
+    /// Time until the response headers arrived
+    pub latency: Option<Duration>,
+    /// Why the provider isn't `Ok`
+    pub error: Option<String>,
+}
+
+/// URL a ping lists models at, by provider
+fn ping_url(provider: &str, api_url: &str) -> String {
+    let base = api_url.trim_end_matches('/');
+    match provider.to_lowercase().as_str() {
+        "ollama" => format!("{}/api/tags", base.trim_end_matches("/api")),
+        "anthropic" => format!("{}/v1/models", base.trim_end_matches("/v1")),
+        _ => format!("{}/models", base),
+    }
+}
+
+/// Health of a provider that answered with `status` after `latency`
+fn health_for_status(status: u16, latency: Duration) -> (Health, Option<String>) {
+    match status {
+        401 | 403 => (
+            Health::Degraded,
+            Some("The API key was rejected; check Settings → Provider".to_string()),
+        ),
+        429 => (
+            Health::Degraded,
+            Some("Rate limited by the provider".to_string()),
+        ),
+        500.. => (
+            Health::Degraded,
+            Some(format!("The provider answered with status {}", status)),
+        ),
+        _ if latency > PING_SLOW => (
+            Health::Degraded,
+            Some(format!("Slow response: {:.1}s", latency.as_secs_f32())),
+        ),
+        // Any other answer, even a 404 from a server without a models
+        // listing, means the service is up
+        _ => (Health::Ok, None),
+    }
+}
+
+/// Phrases reqwest and the OS use when a request never reached the server
+const CONNECTIVITY_MARKERS: &[&str] = &[
+    "error sending request",
+    "connection refused",
+    "tcp connect error",
+    "dns error",
+    "failed to lookup address",
+    "network is unreachable",
+    "no route to host",
+    "connection reset",
+    "connection lost",
+];
+
+/// Whether a stream error message means the provider couldn't be reached,
+/// as opposed to the provider rejecting the request
+pub fn is_connectivity_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    CONNECTIVITY_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Check that the active provider answers, without spending tokens
+pub async fn ping(config: &Config) -> HealthReport {
+    let provider = config.active_provider.clone();
+    let api_key = config.get_api_key();
+    let mut request = http_client::get_general_client()
+        .get(ping_url(&provider, &config.get_api_url()))
+        .timeout(PING_TIMEOUT);
+    if !api_key.is_empty() {
+        request = if provider.eq_ignore_ascii_case("anthropic") {
+            request
+                .header("x-api-key", api_key)
+                .header("anthropic-version", "2023-06-01")
+        } else {
+            request.header("Authorization", format!("Bearer {}", api_key))
+        };
+    }
+
+    let started = Instant::now();
+    match request.send().await {
+        Ok(response) => {
+            let latency = started.elapsed();
+            let (health, error) = health_for_status(response.status().as_u16(), latency);
+            HealthReport {
+                health,
+                latency: Some(latency),
+                error,
+            }
+        }
+        Err(err) => HealthReport {
+            health: Health::Unreachable,
+            latency: None,
+            error: Some(if err.is_timeout() {
+                format!("No answer within {}s", PING_TIMEOUT.as_secs())
+            } else {
+                format!("{:#}", anyhow::Error::from(err))
+            }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_failures() {
+        let err = anyhow::anyhow!("API Error 401 Unauthorized: {{\"error\":\"bad key\"}}");
+        assert_eq!(classify_error(&err, "gpt-4o"), CheckFailure::InvalidKey);
+
+        let err = anyhow::anyhow!(
+            "API Error 404 Not Found: {{\"error\":{{\"message\":\"The model `gpt-9` does not exist\"}}}}"
+        );
+        assert_eq!(
+            classify_error(&err, "gpt-9"),
+            CheckFailure::ModelNotFound("gpt-9".to_string())
+        );
+
+        let err = anyhow::anyhow!("API Error 404 Not Found: <html>Not Found</html>");
+        assert_eq!(classify_error(&err, "gpt-4o"), CheckFailure::WrongUrl);
+
+        let err: anyhow::Error = ZAIApiError::from_status_code(401, "invalid token").into();
+        assert_eq!(classify_error(&err, "glm-4.6"), CheckFailure::InvalidKey);
+
+        let status = reqwest::StatusCode::UNAUTHORIZED;
+        let err: anyhow::Error = ApiError::from_status("OpenAI", status, "bad key", None).into();
+        assert_eq!(classify_error(&err, "gpt-4o"), CheckFailure::InvalidKey);
+
+        let status = reqwest::StatusCode::NOT_FOUND;
+        let err: anyhow::Error = ApiError::from_status("OpenAI", status, "<html>", None).into();
+        assert_eq!(classify_error(&err, "gpt-4o"), CheckFailure::WrongUrl);
+
+        let err: anyhow::Error = ApiError::Timeout { timeout_secs: 30 }.into();
+        assert!(matches!(
+            classify_error(&err, "llama3"),
+            CheckFailure::Unreachable(_)
+        ));
+    }
+
+    #[test]
+    fn test_ping_health() {
+        assert_eq!(
+            ping_url("ollama", "http://localhost:11434/"),
+            "http://localhost:11434/api/tags"
+        );
+        assert_eq!(
+            ping_url("anthropic", "https://api.anthropic.com"),
+            "https://api.anthropic.com/v1/models"
+        );
+        assert_eq!(
+            ping_url("openai", "https://api.openai.com/v1"),
+            "https://api.openai.com/v1/models"
+        );
+
+        let fast = Duration::from_millis(200);
+        assert_eq!(health_for_status(200, fast).0, Health::Ok);
+        assert_eq!(health_for_status(404, fast).0, Health::Ok);
+        assert_eq!(health_for_status(401, fast).0, Health::Degraded);
+        assert_eq!(health_for_status(503, fast).0, Health::Degraded);
+        assert_eq!(
+            health_for_status(200, Duration::from_secs(6)).0,
+            Health::Degraded
+        );
+
+        assert!(is_connectivity_error(
+            "error sending request for url (https://api.openai.com/v1/chat/completions)"
+        ));
+        assert!(!is_connectivity_error(
+            "API Error 401 Unauthorized: bad key"
+        ));
+    }
+}
@@ -0,0 +1,309 @@
+//! Code review of a diff behind `arula review` and the desktop review report
+//!
+//! The diff is split into chunks of whole files (or hunks, for large files)
+//! that each fit one request. Every chunk is reviewed separately and the
+//! model answers with a JSON array of findings, which are merged into one
+//! report ordered by severity. Chunks are redacted before they are sent.
+
+use crate::api::stream::complete;
+use crate::utils::config::Config;
+use crate::utils::redact;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+/// Largest piece of diff sent in one request
+const MAX_CHUNK_CHARS: usize = 16_000;
+
+const REVIEW_MAX_TOKENS: u32 = 2048;
+
+const REVIEW_TIMEOUT: Duration = Duration::from_secs(180);
+
+const INSTRUCTIONS: &str = "You are reviewing part of a code change. Look for bugs, security \
+problems, missing error handling, performance issues and unclear code in the added lines. \
+Answer with a JSON array only, one object per finding: {\"file\": path, \"line\": line number \
+in the new file, \"severity\": \"error\" | \"warning\" | \"info\", \"message\": what is wrong, \
+\"suggestion\": how to fix it}. Skip style nits a formatter would fix. Answer [] when nothing \
+is worth flagging.";
+
+/// Which changes to review
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewTarget {
+    /// Staged and unstaged changes against HEAD
+    WorkingTree,
+    /// Staged changes only
+    Staged,
+    /// A revision range like `main..feature`, or a ref whose changes since
+    /// the merge base with HEAD are reviewed, as in a pull request
+    Range(String),
+}
+
+impl ReviewTarget {
+    fn diff_args(&self) -> Vec<String> {
+        let mut args = vec!["diff".to_string(), "--no-color".to_string()];
+        match self {
+            Self::WorkingTree => args.push("HEAD".to_string()),
+            Self::Staged => args.push("--cached".to_string()),
+            Self::Range(range) if range.contains("..") => args.push(range.clone()),
+            Self::Range(base) => args.push(format!("{}...HEAD", base)),
+        }
+        args
+    }
+}
+
+impl fmt::Display for ReviewTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WorkingTree => f.write_str("working tree changes"),
+            Self::Staged => f.write_str("staged changes"),
+            Self::Range(range) => f.write_str(range),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    fn parse(text: &str) -> Self {
+        match text.to_ascii_lowercase().as_str() {
+            "error" | "critical" | "high" | "bug" => Self::Error,
+            "warning" | "medium" | "major" => Self::Warning,
+            _ => Self::Info,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+        }
+    }
+}
+
+/// One problem the model pointed out
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub file: String,
+    pub line: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+/// Findings for a whole diff
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReviewReport {
+    /// Ordered by severity, then file and line
+    pub findings: Vec<Finding>,
+    pub chunks: usize,
+    /// Errors of chunks that couldn't be reviewed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed_chunks: Vec<String>,
+}
+
+impl ReviewReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+}
+
+/// The diff of `target` in the repository containing `dir`
+pub fn diff(dir: &Path, target: &ReviewTarget) -> Result<String> {
+    let output = Command::new("git")
+        .args(target.diff_args())
+        .current_dir(dir)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Split a unified diff into pieces of at most `max_chars`, keeping files
+/// together where possible and repeating the file header for split hunks
+fn split_chunks(diff: &str, max_chars: usize) -> Vec<String> {
+    // Sections of one file, or one group of hunks of a large file
+    let mut sections: Vec<String> = Vec::new();
+    for file in split_before(diff, "diff --git ") {
+        if file.len() <= max_chars {
+            sections.push(file.to_string());
+            continue;
+        }
+        let mut hunks = split_before(file, "@@ ").into_iter();
+        let header = hunks.next().unwrap_or_default();
+        let mut section = header.to_string();
+        for hunk in hunks {
+            if section.len() > header.len() && section.len() + hunk.len() > max_chars {
+                sections.push(std::mem::replace(&mut section, header.to_string()));
+            }
+            section.push_str(hunk);
+        }
+        sections.push(section);
+    }
+
+    let mut chunks: Vec<String> = Vec::new();
+    for section in sections {
+        match chunks.last_mut() {
+            Some(chunk) if chunk.len() + section.len() <= max_chars => chunk.push_str(&section),
+            _ => chunks.push(section),
+        }
+    }
+    chunks.retain(|c| !c.trim().is_empty());
+    chunks
+}
+
+/// Split `text` before every line starting with `marker`
+fn split_before<'a>(text: &'a str, marker: &str) -> Vec<&'a str> {
+    let mut starts: Vec<usize> = text
+        .match_indices(marker)
+        .map(|(i, _)| i)
+        .filter(|&i| i == 0 || text.as_bytes()[i - 1] == b'\n')
+        .collect();
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| &text[start..starts.get(n + 1).copied().unwrap_or(text.len())])
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// Findings from the model's answer, tolerating prose or fences around the array
+fn parse_findings(answer: &str) -> Result<Vec<Finding>> {
+    let start = answer.find('[').context("No JSON array in the answer")?;
+    let end = answer
+        .rfind(']')
+        .filter(|&end| end > start)
+        .context("Unterminated JSON array")?;
+    let items: Vec<Value> =
+        serde_json::from_str(&answer[start..=end]).context("Invalid findings JSON")?;
+
+    let text = |item: &Value, key: &str| {
+        item.get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            Some(Finding {
+                file: text(item, "file").unwrap_or_default(),
+                line: item.get("line").and_then(|line| match line {
+                    Value::Number(n) => n.as_u64().map(|n| n as u32),
+                    Value::String(s) => s.parse().ok(),
+                    _ => None,
+                }),
+                severity: Severity::parse(&text(item, "severity").unwrap_or_default()),
+                message: text(item, "message")?,
+                suggestion: text(item, "suggestion"),
+            })
+        })
+        .collect())
+}
+
+/// Review the changes of `target`, calling `on_chunk(done, total)` as chunks finish
+pub async fn review(
+    config: &Config,
+    dir: &Path,
+    target: &ReviewTarget,
+    mut on_chunk: impl FnMut(usize, usize),
+) -> Result<ReviewReport> {
+    let diff = diff(dir, target)?;
+    let chunks = split_chunks(&diff, MAX_CHUNK_CHARS);
+    if chunks.is_empty() {
+        bail!("No changes to review ({})", target);
+    }
+
+    let mut report = ReviewReport {
+        chunks: chunks.len(),
+        ..Default::default()
+    };
+    for (i, chunk) in chunks.iter().enumerate() {
+        let prompt = format!(
+            "{}\n\n```diff\n{}\n```",
+            INSTRUCTIONS,
+            redact::redact(chunk.trim_end())
+        );
+        let findings = complete(config, prompt, REVIEW_MAX_TOKENS, REVIEW_TIMEOUT)
+            .await
+            .and_then(|answer| parse_findings(&answer));
+        match findings {
+            Ok(findings) => report.findings.extend(findings),
+            Err(err) => report
+                .failed_chunks
+                .push(format!("chunk {}: {}", i + 1, err)),
+        }
+        on_chunk(i + 1, chunks.len());
+    }
+    report
+        .findings
+        .sort_by(|a, b| (a.severity, &a.file, a.line).cmp(&(b.severity, &b.file, b.line)));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write as _;
+
+    #[test]
+    fn test_split_chunks() {
+        let file = |name: &str, hunks: usize| {
+            let mut text = format!("diff --git a/{0} b/{0}\n--- a/{0}\n+++ b/{0}\n", name);
+            for i in 0..hunks {
+                let _ = write!(text, "@@ -{0},1 +{0},1 @@\n-old\n+new line {0}\n", i + 1);
+            }
+            text
+        };
+        let diff = format!(
+            "{}{}{}",
+            file("a.rs", 1),
+            file("b.rs", 1),
+            file("big.rs", 12)
+        );
+
+        let chunks = split_chunks(&diff, 200);
+        assert!(chunks[0].contains("a/a.rs") && chunks[0].contains("a/b.rs"));
+        let big: Vec<&String> = chunks.iter().filter(|c| c.contains("big.rs")).collect();
+        assert!(big.len() > 1);
+        assert!(big.iter().all(|c| c.starts_with("diff --git a/big.rs")));
+        assert_eq!(chunks.concat().matches("+new line").count(), 14);
+    }
+
+    #[test]
+    fn test_parse_findings() {
+        let answer = r#"Here you go:
+```json
+[{"file": "src/lib.rs", "line": "12", "severity": "High", "message": "Unwrap on user input", "suggestion": "Return an error"},
+ {"file": "src/main.rs", "severity": "nit", "message": "Typo"},
+ {"file": "src/x.rs", "message": ""}]
+```"#;
+        let findings = parse_findings(answer).unwrap();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].line, Some(12));
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert_eq!(findings[1].severity, Severity::Info);
+        assert_eq!(findings[1].suggestion, None);
+        assert!(parse_findings("Looks good to me").is_err());
+    }
+}
@@ -8,17 +8,31 @@
 
 use crate::api::agent::ToolResult;
 use crate::api::api::{
-    AIProvider, ApiClient, ApiResponse, ChatMessage, ToolCall, ToolCallFunction, Usage,
+    apply_prompt_caching, apply_sampling, AIProvider, ApiClient, ApiResponse, ChatMessage,
+    FinishReason, RequestFormat, ResponseMetadata, ToolCall, ToolCallFunction, Usage,
 };
+use crate::api::http_client::RetryNotice;
+use crate::api::tool_args::ArgumentAssembler;
 use crate::api::xml_toolcall::extract_tool_call_from_xml;
 // Bash streaming is accessed via full path: crate::tools::builtin::bash::execute_bash_streaming_channel
+use crate::utils::budget::{BudgetExceeded, BudgetTracker};
+use crate::utils::config::Config;
+use crate::utils::error::ApiError;
 use crate::utils::error_utils::{stream_error, ErrorContext};
+use crate::utils::images;
+use crate::utils::http_capture;
+use crate::utils::inspector::{self, InspectorSource};
+use crate::utils::telemetry;
+use crate::utils::tool_output::shrink_for_history;
+use crate::utils::usage_store::{self, UsageKind};
 use anyhow::{anyhow, Result};
 use futures::StreamExt;
+use tracing::Instrument;
 use reqwest::Response;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::time::Duration;
 // std::sync no longer needed - using channels for callback
 
 // ============================================================================
@@ -32,9 +46,19 @@ pub struct StreamChunk {
     pub object: Option<String>,
     pub created: Option<u64>,
     pub model: Option<String>,
+    #[serde(default)]
     pub choices: Vec<StreamChoice>,
     #[serde(default)]
     pub usage: Option<StreamUsage>,
+    /// llama.cpp servers (llamafile) report token counts here instead of
+    /// `usage`
+    #[serde(default)]
+    pub timings: Option<LlamaTimings>,
+    /// Upstream provider picked by OpenRouter
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -70,11 +94,36 @@ pub struct StreamFunctionDelta {
     pub arguments: Option<String>,
 }
 
+/// Token counts; local servers leave some of the fields out
 #[derive(Debug, Clone, Deserialize)]
 pub struct StreamUsage {
+    #[serde(default)]
     pub prompt_tokens: u32,
+    #[serde(default)]
     pub completion_tokens: u32,
+    #[serde(default)]
     pub total_tokens: u32,
+    #[serde(default)]
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+    /// DeepSeek's count of prompt tokens served from its cache
+    #[serde(default)]
+    pub prompt_cache_hit_tokens: Option<u32>,
+}
+
+/// Generation timings of llama.cpp servers
+#[derive(Debug, Clone, Deserialize)]
+pub struct LlamaTimings {
+    #[serde(default)]
+    pub prompt_n: u32,
+    #[serde(default)]
+    pub predicted_n: u32,
+}
+
+/// Breakdown of prompt tokens; OpenAI reports automatic cache hits here
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptTokensDetails {
+    #[serde(default)]
+    pub cached_tokens: Option<u32>,
 }
 
 /// Events emitted during streaming
@@ -82,6 +131,8 @@ pub struct StreamUsage {
 pub enum StreamEvent {
     /// Start of stream
     Start { id: String, model: String },
+    /// Provider details about the response, sent once after `Start`
+    Metadata(ResponseMetadata),
     /// Text content chunk
     TextDelta(String),
     /// Thinking/reasoning content chunk
@@ -122,6 +173,12 @@ pub enum StreamEvent {
         reason: String,
         usage: Option<Usage>,
     },
+    /// A budget limit stopped the agent loop before the next request
+    BudgetExceeded(BudgetExceeded),
+    /// A request failed and is about to be retried
+    Retrying(RetryNotice),
+    /// The tool calls of the agent loop's round `n` (counting from 1) are about to run
+    ToolRound(u32),
     /// Error occurred
     Error(String),
 }
@@ -131,7 +188,7 @@ pub enum StreamEvent {
 struct ToolCallAccumulator {
     id: String,
     name: String,
-    arguments: String,
+    arguments: ArgumentAssembler,
 }
 
 impl ToolCallAccumulator {
@@ -141,7 +198,8 @@ impl ToolCallAccumulator {
             r#type: "function".to_string(),
             function: ToolCallFunction {
                 name: self.name.clone(),
-                arguments: self.arguments.clone(),
+                arguments: self.arguments.finish(),
+                truncated: self.arguments.is_truncated(),
             },
         }
     }
@@ -195,9 +253,7 @@ pub fn build_anthropic_request(
     };
 
     // Debug output
-    if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-        eprintln!("🧠 DEBUG build_anthropic_request: thinking_enabled = {}, model = {}", thinking_enabled, model);
-    }
+    tracing::debug!("build_anthropic_request: thinking_enabled = {}, model = {}", thinking_enabled, model);
     // Extract system message (first message with role "system")
     let system_content: Option<String> = messages
         .iter()
@@ -253,12 +309,10 @@ pub fn build_anthropic_request(
 
         // Add thinking mode if enabled (for Z.AI Anthropic-compatible endpoint)
         if thinking_enabled {
-            if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                eprintln!("🧠 DEBUG: Adding thinking block to Z.AI build_anthropic_request");
-            }
+            tracing::debug!("Adding thinking block to Z.AI build_anthropic_request");
             request["thinking"] = json!({"type": "enabled"});
-        } else if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-            eprintln!("🧠 DEBUG: NOT adding thinking block to Z.AI build_anthropic_request - thinking_enabled is false");
+        } else {
+            tracing::debug!("NOT adding thinking block to Z.AI build_anthropic_request - thinking_enabled is false");
         }
 
         // Add tools if provided (Z.AI supports tools)
@@ -277,6 +331,24 @@ pub fn build_anthropic_request(
         .filter(|msg| msg.role != "system") // System goes in separate param
         .filter_map(|msg| {
             match msg.role.as_str() {
+                "user" if !msg.images.is_empty() => {
+                    let mut content_blocks: Vec<Value> = msg
+                        .images
+                        .iter()
+                        .filter_map(|url| images::split_data_url(url))
+                        .map(|(media_type, data)| {
+                            json!({
+                                "type": "image",
+                                "source": { "type": "base64", "media_type": media_type, "data": data }
+                            })
+                        })
+                        .collect();
+                    content_blocks.push(json!({
+                        "type": "text",
+                        "text": msg.content.clone().unwrap_or_default()
+                    }));
+                    Some(json!({ "role": "user", "content": content_blocks }))
+                }
                 "user" => Some(json!({
                     "role": "user",
                     "content": msg.content.clone().unwrap_or_default()
@@ -347,12 +419,10 @@ pub fn build_anthropic_request(
 
     // Add thinking mode if enabled (for Anthropic or other compatible endpoints)
     if thinking_enabled {
-        if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-            eprintln!("🧠 DEBUG: Adding thinking block to non-Z.AI build_anthropic_request");
-        }
+        tracing::debug!("Adding thinking block to non-Z.AI build_anthropic_request");
         request["thinking"] = json!({"type": "enabled"});
-    } else if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-        eprintln!("🧠 DEBUG: NOT adding thinking block to non-Z.AI build_anthropic_request - thinking_enabled is false");
+    } else {
+        tracing::debug!("NOT adding thinking block to non-Z.AI build_anthropic_request - thinking_enabled is false");
     }
 
     // Convert and add tools if present
@@ -368,6 +438,96 @@ pub fn build_anthropic_request(
     request
 }
 
+/// Build the streaming request body for `client`: the Messages API format
+/// (with prompt caching on Claude) for Anthropic endpoints, the unified
+/// OpenAI-compatible format otherwise, plus the client's sampling parameters
+pub fn build_client_request(
+    client: &ApiClient,
+    messages: &[ChatMessage],
+    tools: Option<&[Value]>,
+    max_tokens: u32,
+) -> Value {
+    let mut request = if client.request_format() == RequestFormat::Anthropic {
+        let mut request = build_anthropic_request(client.model(), messages, tools, max_tokens);
+        if client.provider == AIProvider::Claude {
+            apply_prompt_caching(&mut request);
+        }
+        request
+    } else {
+        build_streaming_request(
+            &client.provider,
+            client.model(),
+            messages,
+            tools,
+            client.temperature(),
+            max_tokens,
+        )
+    };
+    apply_sampling(&mut request, client.sampling(), client.request_format());
+    request
+}
+
+/// Send a single tool-less `prompt` to the config's active provider and
+/// return the full answer, for helpers like commit messages and reviews
+pub async fn complete(
+    config: &Config,
+    prompt: String,
+    max_tokens: u32,
+    timeout: Duration,
+) -> Result<String> {
+    complete_with_reason(config, prompt, max_tokens, timeout)
+        .await
+        .map(|(answer, _)| answer)
+}
+
+/// `complete`, also returning why the model stopped, so callers that write
+/// the answer somewhere can refuse one cut off at `max_tokens`
+pub async fn complete_with_reason(
+    config: &Config,
+    prompt: String,
+    max_tokens: u32,
+    timeout: Duration,
+) -> Result<(String, FinishReason)> {
+    let client = ApiClient::new(
+        config.active_provider.clone(),
+        config.get_api_url(),
+        config.get_api_key(),
+        config.get_model(),
+    )
+    .with_provider_config(config.get_active_provider_config());
+    let messages = [ChatMessage {
+        role: "user".to_string(),
+        content: Some(prompt),
+        tool_calls: None,
+        tool_call_id: None,
+        tool_name: None,
+        images: Vec::new(),
+    }];
+    let request = build_client_request(&client, &messages, None, max_tokens);
+
+    let mut finish = String::new();
+    let run = async {
+        let response = client.make_streaming_request(request).await?;
+        process_response(response, |event| {
+            if let StreamEvent::Finish { reason, .. } = event {
+                finish = reason;
+            }
+        })
+        .await
+    };
+    let response = tokio::time::timeout(timeout, run)
+        .await
+        .map_err(|_| ApiError::Timeout {
+            timeout_secs: timeout.as_secs(),
+        })??;
+    if !response.success {
+        return Err(anyhow!(response
+            .error
+            .unwrap_or_else(|| "Request failed".to_string())));
+    }
+    Ok((response.response, FinishReason::from_provider(&finish)))
+}
+
 /// Build a unified request body for streaming, handling provider specifics
 pub fn build_streaming_request(
     provider: &AIProvider,
@@ -386,9 +546,7 @@ pub fn build_streaming_request(
     let is_zai = matches!(provider, AIProvider::ZAiCoding);
 
     // Debug output
-    if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-        eprintln!("🧠 DEBUG build_streaming_request: thinking_enabled = {}, provider = {:?}", thinking_enabled, provider);
-    }
+    tracing::debug!("build_streaming_request: thinking_enabled = {}, provider = {:?}", thinking_enabled, provider);
     let is_ollama = matches!(provider, AIProvider::Ollama);
 
     // 1. Process Messages
@@ -448,6 +606,28 @@ pub fn build_streaming_request(
                 }
             }
 
+            // Vision input: Ollama takes bare base64 next to the text, OpenAI
+            // compatible APIs a list of text and image_url parts
+            if !msg.images.is_empty() {
+                if is_ollama {
+                    let payloads: Vec<&str> = msg
+                        .images
+                        .iter()
+                        .filter_map(|url| images::split_data_url(url).map(|(_, data)| data))
+                        .collect();
+                    obj["images"] = json!(payloads);
+                } else {
+                    let mut parts = vec![json!({
+                        "type": "text",
+                        "text": msg.content.clone().unwrap_or_default()
+                    })];
+                    parts.extend(msg.images.iter().map(|url| {
+                        json!({ "type": "image_url", "image_url": { "url": url } })
+                    }));
+                    obj["content"] = json!(parts);
+                }
+            }
+
             Some(obj)
         })
         .collect();
@@ -487,21 +667,21 @@ pub fn build_streaming_request(
 
     // Add temperature separately to avoid type issues
     if is_zai {
-        request["temperature"] = json!("0.7");
+        request["temperature"] = json!(temperature.to_string());
         // Add thinking parameter for Z.AI if enabled
         if thinking_enabled {
-            if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                eprintln!("🧠 DEBUG: Adding thinking block to Z.AI streaming request");
-            }
+            tracing::debug!("Adding thinking block to Z.AI streaming request");
             request["thinking"] = json!({"type": "enabled"});
-        } else if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-            eprintln!("🧠 DEBUG: NOT adding thinking block to Z.AI streaming request - thinking_enabled is false");
+        } else {
+            tracing::debug!("NOT adding thinking block to Z.AI streaming request - thinking_enabled is false");
         }
     } else {
         request["temperature"] = json!(temperature);
         // Add reasoning_effort for other providers if thinking is enabled
         if thinking_enabled {
-            request["reasoning_effort"] = json!("medium");
+            if let Some(effort) = reasoning_effort(provider, model) {
+                request["reasoning_effort"] = json!(effort);
+            }
         }
     }
 
@@ -538,12 +718,123 @@ pub fn build_streaming_request(
     request
 }
 
+/// `reasoning_effort` to request when thinking is enabled. DeepSeek reasons
+/// by model (`deepseek-reasoner`) rather than by parameter, and of xAI's
+/// models only grok-3-mini takes the parameter, with "low" or "high".
+fn reasoning_effort(provider: &AIProvider, model: &str) -> Option<&'static str> {
+    match provider {
+        AIProvider::DeepSeek => None,
+        AIProvider::XAi => model.contains("grok-3-mini").then_some("high"),
+        _ => Some("medium"),
+    }
+}
+
 // ============================================================================
 //  Stream Processing
 // ============================================================================
 
+/// Splits the `<think>...</think>` block R1-style models (DeepSeek R1 and its
+/// distills on OpenAI-compatible hosts) write at the start of `content` off
+/// the answer, so it reaches the UI as thinking instead of text
+#[derive(Debug, Default)]
+struct ThinkTagSplitter {
+    in_think: bool,
+    /// Answer text has started, so later tags are left alone
+    seen_text: bool,
+    /// Held back because it may be the start of a tag
+    pending: String,
+}
+
+impl ThinkTagSplitter {
+    const OPEN: &'static str = "<think>";
+    const CLOSE: &'static str = "</think>";
+
+    /// Split a content delta into its thinking and text parts
+    fn push(&mut self, delta: &str) -> (String, String) {
+        let mut buf = std::mem::take(&mut self.pending);
+        buf.push_str(delta);
+        let (mut thinking, mut text) = (String::new(), String::new());
+        loop {
+            if self.in_think {
+                if let Some(end) = buf.find(Self::CLOSE) {
+                    thinking.push_str(&buf[..end]);
+                    buf.drain(..end + Self::CLOSE.len());
+                    self.in_think = false;
+                    continue;
+                }
+                let keep = partial_suffix(&buf, Self::CLOSE);
+                thinking.push_str(&buf[..buf.len() - keep]);
+                self.pending = buf[buf.len() - keep..].to_string();
+            } else if self.seen_text {
+                text.push_str(&buf);
+            } else {
+                let trimmed = buf.trim_start();
+                if let Some(rest) = trimmed.strip_prefix(Self::OPEN) {
+                    buf = rest.to_string();
+                    self.in_think = true;
+                    continue;
+                }
+                if Self::OPEN.starts_with(trimmed) {
+                    // Whitespace or part of an opening tag so far
+                    self.pending = buf;
+                } else {
+                    self.seen_text = true;
+                    text.push_str(trimmed);
+                }
+            }
+            break;
+        }
+        (thinking, text)
+    }
+
+    /// Whatever was held back when the stream ends
+    fn finish(&mut self) -> (String, String) {
+        let pending = std::mem::take(&mut self.pending);
+        if self.in_think {
+            (pending, String::new())
+        } else {
+            (String::new(), pending.trim_start().to_string())
+        }
+    }
+}
+
+/// Length of the longest suffix of `text` that is a proper prefix of `tag`
+fn partial_suffix(text: &str, tag: &str) -> usize {
+    (1..tag.len().min(text.len() + 1))
+        .rev()
+        .find(|&n| {
+            text.is_char_boundary(text.len() - n) && tag.starts_with(&text[text.len() - n..])
+        })
+        .unwrap_or(0)
+}
+
+/// How a streamed response ended
+enum StreamOutcome {
+    Complete(ApiResponse),
+    /// The connection broke after `partial` text had arrived
+    Interrupted { partial: String, error: String },
+}
+
 /// Process a raw HTTP response into a stream of events
-pub async fn process_response<F>(response: Response, callback: F) -> Result<ApiResponse>
+pub async fn process_response<F>(response: Response, mut callback: F) -> Result<ApiResponse>
+where
+    F: FnMut(StreamEvent),
+{
+    match read_response(response, &mut callback).await? {
+        StreamOutcome::Complete(response) => Ok(response),
+        StreamOutcome::Interrupted { partial, error } => {
+            callback(StreamEvent::Error(error.clone()));
+            Ok(ApiResponse {
+                response: partial,
+                success: false,
+                error: Some(error),
+                ..Default::default()
+            })
+        }
+    }
+}
+
+async fn read_response<F>(response: Response, callback: F) -> Result<StreamOutcome>
 where
     F: FnMut(StreamEvent),
 {
@@ -560,7 +851,44 @@ where
     }
 }
 
-async fn process_sse_stream<F>(response: Response, mut callback: F) -> Result<ApiResponse>
+/// Anthropic Messages streams tag every event with a `type` and carry no
+/// OpenAI-style `choices`
+fn is_anthropic_event(event: &Value) -> bool {
+    matches!(
+        event["type"].as_str(),
+        Some(
+            "message_start"
+                | "content_block_start"
+                | "content_block_delta"
+                | "content_block_stop"
+                | "message_delta"
+                | "message_stop"
+                | "ping"
+                | "error"
+        )
+    )
+}
+
+/// Usage from an Anthropic `message_start` or `message_delta` event, merged
+/// over what earlier events reported. Cached input counts as prompt tokens.
+fn anthropic_usage(value: &Value, previous: Option<&Usage>) -> Usage {
+    let count = |key: &str| value[key].as_u64().map(|n| n as u32);
+    let mut usage = previous.cloned().unwrap_or_default();
+    if let Some(input) = count("input_tokens") {
+        usage.cache_creation_input_tokens = count("cache_creation_input_tokens");
+        usage.cache_read_input_tokens = count("cache_read_input_tokens");
+        usage.prompt_tokens = input
+            + usage.cache_creation_input_tokens.unwrap_or_default()
+            + usage.cache_read_input_tokens.unwrap_or_default();
+    }
+    if let Some(output) = count("output_tokens") {
+        usage.completion_tokens = output;
+    }
+    usage.total_tokens = usage.prompt_tokens + usage.completion_tokens;
+    usage
+}
+
+async fn process_sse_stream<F>(response: Response, mut callback: F) -> Result<StreamOutcome>
 where
     F: FnMut(StreamEvent),
 {
@@ -574,6 +902,7 @@ where
     let mut model = String::new();
     let mut stream_id = String::new();
     let mut reasoning_buffer = String::new(); // For XML tool call extraction
+    let mut think_tags = ThinkTagSplitter::default();
 
     while let Some(res) = stream.next().await {
         match res {
@@ -583,9 +912,95 @@ where
                     break;
                 }
 
-                // Log streaming chunk if debug mode is enabled
-                if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                    crate::utils::debug::debug_print(&format!("Stream Chunk: {}", data));
+                inspector::record(InspectorSource::Chunk, "sse", data.as_str());
+                http_capture::record_chunk(&data);
+
+                if let Some(event) = serde_json::from_str::<Value>(&data)
+                    .ok()
+                    .filter(is_anthropic_event)
+                {
+                    match event["type"].as_str().unwrap_or_default() {
+                        "message_start" => {
+                            let message = &event["message"];
+                            stream_id = message["id"].as_str().unwrap_or_default().to_string();
+                            model = message["model"].as_str().unwrap_or_default().to_string();
+                            usage = Some(anthropic_usage(&message["usage"], None));
+                            callback(StreamEvent::Start {
+                                id: stream_id.clone(),
+                                model: model.clone(),
+                            });
+                            callback(StreamEvent::Metadata(ResponseMetadata {
+                                id: Some(stream_id.clone()),
+                                model: Some(model.clone()),
+                                ..Default::default()
+                            }));
+                        }
+                        "content_block_start" => {
+                            let block = &event["content_block"];
+                            if block["type"] == "tool_use" {
+                                let index = event["index"].as_u64().unwrap_or_default() as usize;
+                                let acc = tool_acc.entry(index).or_default();
+                                acc.id = block["id"].as_str().unwrap_or_default().to_string();
+                                acc.name = block["name"].as_str().unwrap_or_default().to_string();
+                                callback(StreamEvent::ToolCallStart {
+                                    index,
+                                    id: acc.id.clone(),
+                                    name: acc.name.clone(),
+                                });
+                            }
+                        }
+                        "content_block_delta" => {
+                            let delta = &event["delta"];
+                            match delta["type"].as_str().unwrap_or_default() {
+                                "text_delta" => {
+                                    let text = delta["text"].as_str().unwrap_or_default();
+                                    if !text.is_empty() {
+                                        accumulated.push_str(text);
+                                        callback(StreamEvent::TextDelta(text.to_string()));
+                                    }
+                                }
+                                "thinking_delta" => {
+                                    let think = delta["thinking"].as_str().unwrap_or_default();
+                                    if !think.is_empty() {
+                                        callback(StreamEvent::ThinkingDelta(think.to_string()));
+                                    }
+                                }
+                                "input_json_delta" => {
+                                    let index =
+                                        event["index"].as_u64().unwrap_or_default() as usize;
+                                    let partial = delta["partial_json"].as_str().unwrap_or_default();
+                                    tool_acc.entry(index).or_default().arguments.push(partial);
+                                    callback(StreamEvent::ToolCallDelta {
+                                        index,
+                                        arguments: partial.to_string(),
+                                    });
+                                }
+                                _ => {}
+                            }
+                        }
+                        "message_delta" => {
+                            if let Some(reason) = event["delta"]["stop_reason"].as_str() {
+                                finish_reason = reason.to_string();
+                            }
+                            usage = Some(anthropic_usage(&event["usage"], usage.as_ref()));
+                        }
+                        "error" => {
+                            let msg = event["error"]["message"]
+                                .as_str()
+                                .unwrap_or("Anthropic stream error")
+                                .to_string();
+                            callback(StreamEvent::Error(msg.clone()));
+                            return Ok(StreamOutcome::Complete(ApiResponse {
+                                response: accumulated,
+                                success: false,
+                                error: Some(msg),
+                                ..Default::default()
+                            }));
+                        }
+                        // ping, content_block_stop, message_stop
+                        _ => {}
+                    }
+                    continue;
                 }
 
                 if let Ok(chunk) = serde_json::from_str::<StreamChunk>(&data) {
@@ -601,13 +1016,35 @@ where
                                 id: stream_id.clone(),
                                 model: model.clone(),
                             });
+                            callback(StreamEvent::Metadata(ResponseMetadata {
+                                id: chunk.id.clone(),
+                                model: Some(model.clone()),
+                                provider: chunk.provider.clone(),
+                                system_fingerprint: chunk.system_fingerprint.clone(),
+                            }));
                         }
                     }
                     if let Some(u) = chunk.usage {
                         usage = Some(Usage {
                             prompt_tokens: u.prompt_tokens,
                             completion_tokens: u.completion_tokens,
-                            total_tokens: u.total_tokens,
+                            total_tokens: if u.total_tokens > 0 {
+                                u.total_tokens
+                            } else {
+                                u.prompt_tokens + u.completion_tokens
+                            },
+                            cache_read_input_tokens: u
+                                .prompt_tokens_details
+                                .and_then(|d| d.cached_tokens)
+                                .or(u.prompt_cache_hit_tokens),
+                            ..Default::default()
+                        });
+                    } else if let Some(t) = chunk.timings {
+                        usage = Some(Usage {
+                            prompt_tokens: t.prompt_n,
+                            completion_tokens: t.predicted_n,
+                            total_tokens: t.prompt_n + t.predicted_n,
+                            ..Default::default()
                         });
                     }
 
@@ -618,9 +1055,14 @@ where
                         let delta = choice.delta;
 
                         if let Some(c) = delta.content {
-                            if !c.is_empty() {
-                                accumulated.push_str(&c);
-                                callback(StreamEvent::TextDelta(c));
+                            let (think, text) = think_tags.push(&c);
+                            if !think.is_empty() {
+                                reasoning_buffer.push_str(&think);
+                                callback(StreamEvent::ThinkingDelta(think));
+                            }
+                            if !text.is_empty() {
+                                accumulated.push_str(&text);
+                                callback(StreamEvent::TextDelta(text));
                             }
                         }
 
@@ -650,7 +1092,7 @@ where
                                         });
                                     }
                                     if let Some(a) = func.arguments {
-                                        acc.arguments.push_str(&a);
+                                        acc.arguments.push(&a);
                                         callback(StreamEvent::ToolCallDelta {
                                             index: idx,
                                             arguments: a,
@@ -665,18 +1107,24 @@ where
             Err(e) => {
                 let error_context =
                     ErrorContext::new("Process SSE stream").with_underlying_error(&e);
-                let msg = stream_error(error_context);
-                callback(StreamEvent::Error(msg.clone()));
-                return Ok(ApiResponse {
-                    response: accumulated,
-                    success: false,
-                    error: Some(msg),
-                    ..Default::default()
+                return Ok(StreamOutcome::Interrupted {
+                    partial: accumulated,
+                    error: stream_error(error_context),
                 });
             }
         }
     }
 
+    let (think, text) = think_tags.finish();
+    if !think.is_empty() {
+        reasoning_buffer.push_str(&think);
+        callback(StreamEvent::ThinkingDelta(think));
+    }
+    if !text.is_empty() {
+        accumulated.push_str(&text);
+        callback(StreamEvent::TextDelta(text));
+    }
+
     // Before finalizing, check if reasoning_buffer contains XML tool calls
     // This handles GLM-4.6 style XML tool calls in reasoning content (Coding Plan endpoint only)
     // Note: Anthropic-compatible endpoint uses structured tool_use blocks, not XML
@@ -692,7 +1140,7 @@ where
                     ToolCallAccumulator {
                         id: tool_call.id.clone(),
                         name: tool_call.function.name.clone(),
-                        arguments: tool_call.function.arguments.clone(),
+                        arguments: ArgumentAssembler::from(tool_call.function.arguments.as_str()),
                     },
                 );
                 // Emit the tool call event
@@ -717,9 +1165,10 @@ where
         model,
         &mut callback,
     )
+    .map(StreamOutcome::Complete)
 }
 
-async fn process_ndjson_stream<F>(response: Response, mut callback: F) -> Result<ApiResponse>
+async fn process_ndjson_stream<F>(response: Response, mut callback: F) -> Result<StreamOutcome>
 where
     F: FnMut(StreamEvent),
 {
@@ -730,12 +1179,20 @@ where
     let mut finish_reason = "stop".to_string();
     let mut usage = None;
     let mut model = String::new();
+    let mut think_tags = ThinkTagSplitter::default();
 
     while let Some(item) = stream.next().await {
-        let bytes = item.map_err(|e| {
-            let error_context = ErrorContext::new("Read stream chunk").with_underlying_error(&e);
-            anyhow!("{}", stream_error(error_context))
-        })?;
+        let bytes = match item {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let error_context =
+                    ErrorContext::new("Read stream chunk").with_underlying_error(&e);
+                return Ok(StreamOutcome::Interrupted {
+                    partial: accumulated,
+                    error: stream_error(error_context),
+                });
+            }
+        };
         if let Ok(s) = std::str::from_utf8(&bytes) {
             buffer.push_str(s);
         }
@@ -747,10 +1204,8 @@ where
                 continue;
             }
 
-            // Log streaming chunk if debug mode is enabled
-            if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                crate::utils::debug::debug_print(&format!("Stream Chunk (NDJSON): {}", line));
-            }
+            inspector::record(InspectorSource::Chunk, "ndjson", line.as_str());
+            http_capture::record_chunk(&line);
 
             if let Ok(json) = serde_json::from_str::<Value>(&line) {
                 // Ollama 'done' check
@@ -772,9 +1227,13 @@ where
                     .and_then(|v| v.as_str());
 
                 if let Some(c) = content {
-                    if !c.is_empty() {
-                        accumulated.push_str(c);
-                        callback(StreamEvent::TextDelta(c.to_string()));
+                    let (think, text) = think_tags.push(c);
+                    if !think.is_empty() {
+                        callback(StreamEvent::ThinkingDelta(think));
+                    }
+                    if !text.is_empty() {
+                        accumulated.push_str(&text);
+                        callback(StreamEvent::TextDelta(text));
                     }
                 }
 
@@ -798,7 +1257,7 @@ where
 
                             let acc = tool_acc.entry(i).or_default();
                             acc.name = name.clone();
-                            acc.arguments = args.clone();
+                            acc.arguments = ArgumentAssembler::from(args.as_str());
                             acc.id = format!("call_{}", i);
 
                             callback(StreamEvent::ToolCallStart {
@@ -808,7 +1267,7 @@ where
                             });
                             callback(StreamEvent::ToolCallDelta {
                                 index: i,
-                                arguments: acc.arguments.clone(),
+                                arguments: args,
                             });
                             finish_reason = "tool_calls".to_string();
                         }
@@ -822,12 +1281,25 @@ where
                             id: "ndjson".into(),
                             model: model.clone(),
                         });
+                        callback(StreamEvent::Metadata(ResponseMetadata {
+                            model: Some(model.clone()),
+                            ..Default::default()
+                        }));
                     }
                 }
             }
         }
     }
 
+    let (think, text) = think_tags.finish();
+    if !think.is_empty() {
+        callback(StreamEvent::ThinkingDelta(think));
+    }
+    if !text.is_empty() {
+        accumulated.push_str(&text);
+        callback(StreamEvent::TextDelta(text));
+    }
+
     finalize(
         accumulated,
         tool_acc,
@@ -836,6 +1308,7 @@ where
         model,
         &mut callback,
     )
+    .map(StreamOutcome::Complete)
 }
 
 fn finalize<F>(
@@ -870,6 +1343,15 @@ where
         Some(cleaned)
     };
 
+    if let Some(usage) = &usage {
+        telemetry::record_tokens(&model, usage.prompt_tokens, usage.completion_tokens);
+        usage_store::record(UsageKind::Tokens {
+            model: model.clone(),
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        });
+    }
+
     callback(StreamEvent::Finish {
         reason: reason.clone(),
         usage: usage.clone(),
@@ -891,6 +1373,38 @@ where
 //  Main Streaming Loop
 // ============================================================================
 
+/// Messages that make the model continue an answer cut off after `partial`.
+/// APIs that accept a prefilled assistant turn continue it directly; the
+/// others get it as a finished turn and are asked to go on from there.
+fn resume_messages(client: &ApiClient, partial: &str) -> Vec<ChatMessage> {
+    let message = |role: &str, content: &str| ChatMessage {
+        role: role.to_string(),
+        content: Some(content.to_string()),
+        tool_calls: None,
+        tool_call_id: None,
+        tool_name: None,
+        images: Vec::new(),
+    };
+    // Anthropic rejects a prefill that ends in whitespace
+    let assistant = message("assistant", partial.trim_end());
+    let prefill = matches!(
+        client.request_format(),
+        RequestFormat::Anthropic | RequestFormat::Ollama
+    ) || client.provider == AIProvider::OpenRouter;
+    if prefill {
+        vec![assistant]
+    } else {
+        vec![
+            assistant,
+            message(
+                "user",
+                "Your last message was cut off by a network error. Continue exactly where it \
+                 stopped, without repeating anything.",
+            ),
+        ]
+    }
+}
+
 /// Execute a streaming conversation with automatic tool handling
 pub async fn stream_with_tools<F>(
     client: &ApiClient,
@@ -899,6 +1413,8 @@ pub async fn stream_with_tools<F>(
     tool_registry: &crate::api::agent::ToolRegistry,
     auto_execute_tools: bool,
     max_tool_iterations: u32,
+    mut budget: BudgetTracker,
+    tool_result_max_tokens: usize,
     mut callback: F,
 ) -> Result<ApiResponse>
 where
@@ -913,27 +1429,62 @@ where
             break;
         }
 
-        // Build request - check if we're using Anthropic-compatible endpoint
-        let request_body = if is_anthropic_compatible_endpoint(&client.endpoint) {
-            // Use Anthropic Messages API format
-            build_anthropic_request(client.model(), &current_messages, Some(tools), 4096)
-        } else {
-            // Use standard OpenAI-compatible format (for Coding Plan endpoint)
-            build_streaming_request(
-                &client.provider,
-                client.model(),
-                &current_messages,
-                Some(tools),
-                0.7,
-                4096,
-            )
-        };
-
-        // Send request
-        let response = client.make_streaming_request(request_body).await?;
+        if let Some(exceeded) = budget.check() {
+            tracing::warn!(%exceeded, "Budget limit reached, stopping agent loop");
+            let message = exceeded.to_string();
+            callback(StreamEvent::BudgetExceeded(exceeded));
+            return Ok(ApiResponse {
+                success: false,
+                error: Some(message),
+                ..Default::default()
+            });
+        }
 
-        // Process stream
-        let api_response = process_response(response, &mut callback).await?;
+        // Send the request, reconnecting and resuming from the text received
+        // so far when the connection drops mid-response
+        let mut partial = String::new();
+        let mut reconnects = 0;
+        let api_response = loop {
+            let mut messages = current_messages.clone();
+            if !partial.is_empty() {
+                messages.extend(resume_messages(client, &partial));
+            }
+            let request_body = build_client_request(client, &messages, Some(tools), client.max_tokens());
+            let response = client
+                .make_streaming_request_with_notices(request_body, |notice| {
+                    callback(StreamEvent::Retrying(notice.clone()))
+                })
+                .await?;
+
+            match read_response(response, &mut callback).await? {
+                StreamOutcome::Complete(mut api_response) => {
+                    api_response.response.insert_str(0, &partial);
+                    break api_response;
+                }
+                StreamOutcome::Interrupted { partial: received, error } => {
+                    partial.push_str(&received);
+                    let policy = client.retry_policy();
+                    if reconnects >= policy.max_retries {
+                        // The text so far already went out as deltas
+                        return Err(ApiError::ConnectionLost(error).into());
+                    }
+                    reconnects += 1;
+                    let notice = RetryNotice {
+                        attempt: reconnects,
+                        max_retries: policy.max_retries,
+                        delay: policy.delay(reconnects),
+                        reason: "connection lost".to_string(),
+                        resuming: true,
+                    };
+                    tracing::warn!(%error, "{}", notice);
+                    callback(StreamEvent::Retrying(notice.clone()));
+                    tokio::time::sleep(notice.delay).await;
+                }
+            }
+        };
+        if let Some(usage) = &api_response.usage {
+            budget.add_usage(client.model(), usage);
+        }
 
         // Check for tools
         if let Some(calls) = &api_response.tool_calls {
@@ -949,15 +1500,34 @@ where
                     tool_calls: Some(calls.clone()),
                     tool_call_id: None,
                     tool_name: None,
+                    images: Vec::new(),
                 });
 
                 // Execute tools
+                callback(StreamEvent::ToolRound(iterations + 1));
+                let mut tool_images = Vec::new();
                 for call in calls {
                     let args: Value =
                         serde_json::from_str(&call.function.arguments).unwrap_or(json!({}));
+                    let span = tracing::info_span!(
+                        "tool_call",
+                        id = %call.id,
+                        name = %call.function.name
+                    );
+                    let started = std::time::Instant::now();
+                    tracing::debug!(parent: &span, "Tool call started");
 
                     // Check if this is a bash command - use streaming execution
-                    let (result, content) = if call.function.name == "execute_bash" {
+                    let (result, mut content) = if call.function.truncated {
+                        // Running it with repaired arguments would e.g. write half a file
+                        let error = format!(
+                            "The arguments for {} were cut off before they were complete, so it was not run. \
+                             Send the call again with the complete arguments; split large content across several calls.",
+                            call.function.name
+                        );
+                        tracing::warn!(parent: &span, repaired = %call.function.arguments, "Not running a tool call with truncated arguments");
+                        (Some(ToolResult::error(error.clone())), format!("Error: {}", error))
+                    } else if call.function.name == "execute_bash" {
                         // Extract command from args
                         let command = args.get("command").and_then(|v| v.as_str()).unwrap_or("");
                         let timeout = args.get("timeout_seconds").and_then(|v| v.as_u64());
@@ -1020,7 +1590,7 @@ where
                             });
                         
                         // Emit AskQuestion event
-                        eprintln!("🔔 stream.rs: Emitting AskQuestion event - question='{}', options={:?}", question, options);
+                        tracing::debug!("Emitting AskQuestion event - question='{}', options={:?}", question, options);
                         callback(StreamEvent::AskQuestion {
                             tool_call_id: call.id.clone(),
                             question: question.clone(),
@@ -1028,7 +1598,10 @@ where
                         });
                         
                         // Execute the tool to get the result (for logging)
-                        let result = tool_registry.execute_tool(&call.function.name, args).await;
+                        let result = tool_registry
+                            .execute_tool(&call.function.name, args)
+                            .instrument(span.clone())
+                            .await;
                         
                         // Emit tool result
                         if let Some(res) = &result {
@@ -1045,6 +1618,7 @@ where
                             tool_calls: None,
                             tool_call_id: Some(call.id.clone()),
                             tool_name: Some(call.function.name.clone()),
+                            images: Vec::new(),
                         });
                         
                         // Return success with the question - the session manager will handle pausing
@@ -1061,7 +1635,10 @@ where
                         });
                     } else {
                         // Non-bash tools use standard execution
-                        let result = tool_registry.execute_tool(&call.function.name, args).await;
+                        let result = tool_registry
+                            .execute_tool(&call.function.name, args)
+                            .instrument(span.clone())
+                            .await;
                         let (content, _success) = match &result {
                             Some(res) => (
                                 if res.success {
@@ -1076,6 +1653,20 @@ where
                         (result, content)
                     };
 
+                    let success = result.as_ref().is_some_and(|r| r.success);
+                    tracing::info!(
+                        parent: &span,
+                        success,
+                        elapsed_ms = started.elapsed().as_millis() as u64,
+                        "Tool call finished"
+                    );
+                    telemetry::record_tool_call(&call.function.name, success, started.elapsed());
+                    usage_store::record(UsageKind::Tool {
+                        name: call.function.name.clone(),
+                        success,
+                        duration_ms: started.elapsed().as_millis() as u64,
+                    });
+
                     // Emit tool result event
                     match &result {
                         Some(res) => {
@@ -1095,17 +1686,42 @@ where
                         }
                     }
 
-                    // Add tool result to history
+                    if let Some(res) = result.as_ref().filter(|r| r.success) {
+                        match images::from_tool_result(&call.function.name, &res.data) {
+                            Some(Ok(url)) => tool_images.push(url),
+                            Some(Err(err)) => {
+                                tracing::warn!("Not attaching tool image: {:#}", err);
+                                content.push_str(&format!("\n\nThe image was not attached: {:#}", err));
+                            }
+                            None => {}
+                        }
+                    }
+
+                    // Add tool result to history, trimmed if oversized (the UI got the full result)
                     current_messages.push(ChatMessage {
                         role: "tool".to_string(),
-                        content: Some(content),
+                        content: Some(shrink_for_history(&content, tool_result_max_tokens).into_owned()),
                         tool_calls: None,
                         tool_call_id: Some(call.id.clone()),
                         tool_name: Some(call.function.name.clone()),
+                        images: Vec::new(),
+                    });
+                }
+
+                // Tool messages are text only, so screenshots follow as a user message
+                if !tool_images.is_empty() {
+                    current_messages.push(ChatMessage {
+                        role: "user".to_string(),
+                        content: Some("Screenshot attached by the tool call above.".to_string()),
+                        tool_calls: None,
+                        tool_call_id: None,
+                        tool_name: None,
+                        images: tool_images,
                     });
                 }
 
                 iterations += 1;
+                budget.add_tool_iteration();
                 continue; // Loop again with new history
             }
         }
@@ -1121,3 +1737,95 @@ where
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_complete_reports_typed_errors() {
+        use crate::utils::error::{exit_code, exit_code_for};
+        use std::io::{Read, Write};
+
+        // A provider that rejects every key
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let _ = stream.read(&mut [0u8; 8192]);
+                let body = r#"{"error":{"message":"Invalid API key"}}"#;
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 401 Unauthorized\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            }
+        });
+
+        let mut config = Config::default();
+        config.add_custom_provider("local", "test-model", &url, "bad-key").unwrap();
+        config.switch_provider("local").unwrap();
+        let err = complete(&config, "Hi".to_string(), 1, Duration::from_secs(10))
+            .await
+            .unwrap_err();
+        assert_eq!(exit_code_for(&err), exit_code::AUTH);
+    }
+
+    #[test]
+    fn test_think_tags_split_across_chunks() {
+        let mut splitter = ThinkTagSplitter::default();
+        let mut thinking = String::new();
+        let mut text = String::new();
+        let deltas = [
+            "<th",
+            "ink>\nThe user",
+            " wants 2+2.</thi",
+            "nk>\n\nIt is 4.",
+            " <think> stays",
+        ];
+        for delta in deltas {
+            let (t, x) = splitter.push(delta);
+            thinking.push_str(&t);
+            text.push_str(&x);
+        }
+        let (t, x) = splitter.finish();
+        thinking.push_str(&t);
+        text.push_str(&x);
+        assert_eq!(thinking, "\nThe user wants 2+2.");
+        assert_eq!(text, "It is 4. <think> stays");
+
+        let mut plain = ThinkTagSplitter::default();
+        assert_eq!(plain.push("<"), (String::new(), String::new()));
+        assert_eq!(plain.push("div>"), (String::new(), "<div>".to_string()));
+
+        assert_eq!(reasoning_effort(&AIProvider::XAi, "grok-3-mini"), Some("high"));
+        assert_eq!(reasoning_effort(&AIProvider::XAi, "grok-4"), None);
+        assert_eq!(
+            reasoning_effort(&AIProvider::DeepSeek, "deepseek-reasoner"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resume_messages() {
+        let client = |provider: &str| {
+            ApiClient::new(
+                provider.to_string(),
+                "http://localhost".to_string(),
+                String::new(),
+                "model".to_string(),
+            )
+        };
+
+        let messages = resume_messages(&client("anthropic"), "The answer is ");
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, "assistant");
+        assert_eq!(messages[0].content.as_deref(), Some("The answer is"));
+
+        let messages = resume_messages(&client("openai"), "The answer is ");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].role, "user");
+    }
+}
@@ -0,0 +1,295 @@
+//! Incremental assembly of streamed tool-call arguments
+//!
+//! Providers stream `function.arguments` as arbitrary fragments of a JSON
+//! object, and not all of them end up valid: streams get cut off mid-string,
+//! some models double-encode the object as a JSON string, others escape the
+//! quotes without the surrounding string, and a few send an empty `{}` before
+//! the real arguments. `ArgumentAssembler` tracks the JSON structure as
+//! fragments arrive and repairs the result on `finish()`, so the `ToolCall`
+//! kept in the history always carries a JSON object.
+//!
+//! Encoding quirks are harmless, but a value that had to be closed or
+//! replaced is missing whatever came after the cut. `is_truncated` reports
+//! that, and the tool loop answers such a call with an error asking the model
+//! to send it again rather than running it with partial arguments.
+
+use serde_json::Value;
+
+/// JSON structure seen so far, outside of string contents
+#[derive(Debug, Clone, Default)]
+struct Scanner {
+    /// Open `{` / `[` in order
+    stack: Vec<char>,
+    in_string: bool,
+    escaped: bool,
+    /// Byte offsets where top-level values started
+    value_starts: Vec<usize>,
+    /// Byte offsets of `,`, `{` and `[` outside strings
+    separators: Vec<usize>,
+}
+
+impl Scanner {
+    fn scan(text: &str) -> Self {
+        let mut scanner = Self::default();
+        scanner.feed(text, 0);
+        scanner
+    }
+
+    /// Advance over `fragment`, which starts at byte `offset` of the buffer
+    fn feed(&mut self, fragment: &str, offset: usize) {
+        for (i, c) in fragment.char_indices() {
+            if self.in_string {
+                match c {
+                    _ if self.escaped => self.escaped = false,
+                    '\\' => self.escaped = true,
+                    '"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match c {
+                '"' => self.in_string = true,
+                '{' | '[' => {
+                    if self.stack.is_empty() {
+                        self.value_starts.push(offset + i);
+                    }
+                    self.stack.push(c);
+                    self.separators.push(offset + i);
+                }
+                '}' | ']' => {
+                    self.stack.pop();
+                }
+                ',' => self.separators.push(offset + i),
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether the buffer holds at least one complete top-level value
+    fn is_balanced(&self) -> bool {
+        self.stack.is_empty() && !self.in_string
+    }
+}
+
+/// Collects argument fragments for one tool call
+#[derive(Debug, Clone, Default)]
+pub struct ArgumentAssembler {
+    buffer: String,
+    scanner: Scanner,
+}
+
+impl ArgumentAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a streamed fragment
+    pub fn push(&mut self, fragment: &str) {
+        let offset = self.buffer.len();
+        self.buffer.push_str(fragment);
+        self.scanner.feed(fragment, offset);
+    }
+
+    /// Whether the fragments so far form a complete JSON value
+    pub fn is_complete(&self) -> bool {
+        !self.buffer.trim().is_empty()
+            && self.scanner.is_balanced()
+            && serde_json::from_str::<Value>(self.latest_value()).is_ok()
+    }
+
+    /// The arguments as a JSON object string, repairing what the provider sent
+    /// where possible and falling back to `{}`
+    pub fn finish(&self) -> String {
+        let text = self.latest_value().trim();
+        if text.is_empty() {
+            return "{}".to_string();
+        }
+        if let Some(object) = parse_object(text) {
+            return object;
+        }
+        if let Some(object) = close_truncated(text) {
+            tracing::debug!(raw = %self.buffer, "Repaired truncated tool arguments");
+            return object;
+        }
+        tracing::warn!(raw = %self.buffer, "Unparseable tool arguments, using {{}}");
+        "{}".to_string()
+    }
+
+    /// Whether `finish` can only return a repair: the arguments were cut off
+    /// (closed early) or unparseable (replaced by `{}`)
+    pub fn is_truncated(&self) -> bool {
+        let text = self.latest_value().trim();
+        !text.is_empty() && parse_object(text).is_none()
+    }
+
+    /// The last non-empty top-level value, skipping e.g. an empty `{}` some
+    /// providers send before the real arguments
+    fn latest_value(&self) -> &str {
+        let starts = &self.scanner.value_starts;
+        if starts.len() < 2 {
+            return &self.buffer;
+        }
+        let values: Vec<&str> = starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(self.buffer.len());
+                &self.buffer[start..end]
+            })
+            .collect();
+        values
+            .iter()
+            .rev()
+            .find(|v| v.trim() != "{}")
+            .or(values.last())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl From<&str> for ArgumentAssembler {
+    fn from(text: &str) -> Self {
+        let mut assembler = Self::new();
+        assembler.push(text);
+        assembler
+    }
+}
+
+/// `text` as a JSON object string, unwrapping double encoding and bare
+/// escaped quotes
+fn parse_object(text: &str) -> Option<String> {
+    match serde_json::from_str::<Value>(text) {
+        Ok(Value::Object(_)) => Some(text.to_string()),
+        // Double-encoded: "{\"path\": \"a\"}"
+        Ok(Value::String(inner)) => parse_object(inner.trim()),
+        Ok(Value::Null) => Some("{}".to_string()),
+        Ok(_) => None,
+        // Escaped without the surrounding string: {\"path\": \"a\"}
+        Err(_) if text.contains("\\\"") => {
+            let unescaped: String = serde_json::from_str(&format!("\"{}\"", text)).ok()?;
+            parse_object(unescaped.trim())
+        }
+        Err(_) => None,
+    }
+}
+
+/// Close a value that was cut off, dropping a trailing incomplete member when
+/// closing alone doesn't yield valid JSON
+fn close_truncated(text: &str) -> Option<String> {
+    let mut text = text.to_string();
+    loop {
+        let scanner = Scanner::scan(&text);
+        if let Some(object) = parse_object(&close(&text, &scanner)) {
+            return Some(object);
+        }
+        // Cut back to the last separator: before a `,`, after a `{` or `[`
+        let cut = scanner.separators.iter().rev().find_map(|&pos| {
+            let end = if text[pos..].starts_with(',') {
+                pos
+            } else {
+                pos + 1
+            };
+            (end < text.len()).then_some(end)
+        })?;
+        text.truncate(cut);
+    }
+}
+
+/// Append whatever `text` needs to balance its strings and brackets
+fn close(text: &str, scanner: &Scanner) -> String {
+    let mut closed = text.to_string();
+    if scanner.in_string {
+        if scanner.escaped {
+            closed.pop();
+        } else if let Some(pos) = closed.rfind("\\u").filter(|&pos| closed.len() - pos < 6) {
+            // Partial \uXXXX escape
+            closed.truncate(pos);
+        }
+        closed.push('"');
+    }
+    let trimmed = closed.trim_end().trim_end_matches(',').len();
+    closed.truncate(trimmed);
+    for open in scanner.stack.iter().rev() {
+        closed.push(if *open == '{' { '}' } else { ']' });
+    }
+    closed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assemble(fragments: &[&str]) -> String {
+        let mut assembler = ArgumentAssembler::new();
+        for fragment in fragments {
+            assembler.push(fragment);
+        }
+        assembler.finish()
+    }
+
+    fn value(json: &str) -> Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_fragmented_arguments() {
+        let mut assembler = ArgumentAssembler::new();
+        for fragment in ["{\"pa", "th\": \"src/", "main.rs\", \"lines\"", ": [1, 2]}"] {
+            assert!(!assembler.is_complete());
+            assembler.push(fragment);
+        }
+        assert!(assembler.is_complete());
+        assert_eq!(
+            value(&assembler.finish()),
+            value(r#"{"path": "src/main.rs", "lines": [1, 2]}"#)
+        );
+        assert_eq!(assemble(&[]), "{}");
+    }
+
+    #[test]
+    fn test_truncation_is_reported() {
+        let complete: &[&str] = &[r#"{"path": "a.rs"}"#, r#""{\"path\": \"a.rs\"}""#, "null", ""];
+        for text in complete {
+            assert!(!ArgumentAssembler::from(*text).is_truncated(), "{}", text);
+        }
+        let cut: &[&str] = &[r#"{"path": "a.rs", "content": "fn main"#, "not json at all"];
+        for text in cut {
+            assert!(ArgumentAssembler::from(*text).is_truncated(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_malformed_provider_fixtures() {
+        let fixtures: &[(&[&str], &str)] = &[
+            // Stream cut off inside a string
+            (&[r#"{"command": "ls -la"#], r#"{"command": "ls -la"}"#),
+            // Cut off after an escape
+            (&[r#"{"content": "a\"#], r#"{"content": "a"}"#),
+            // Cut off inside a \u escape
+            (&[r#"{"content": "caf\u00"#], r#"{"content": "caf"}"#),
+            // Cut off after a key
+            (&[r#"{"path": "a.rs", "line"#], r#"{"path": "a.rs"}"#),
+            (
+                &[r#"{"path": "a.rs", "lines": [1, "#],
+                r#"{"path": "a.rs", "lines": [1]}"#,
+            ),
+            // Double-encoded
+            (&[r#""{\"path\": \"a.rs\"}""#], r#"{"path": "a.rs"}"#),
+            // Escaped quotes without the surrounding string
+            (&[r#"{\"path\": \"a.rs\"}"#], r#"{"path": "a.rs"}"#),
+            // Empty object before the real arguments
+            (&["{}", r#"{"path": "a.rs"}"#], r#"{"path": "a.rs"}"#),
+            (&[r#"{"path": "a.rs"}"#, "{}"], r#"{"path": "a.rs"}"#),
+            (&["null"], "{}"),
+            (&["not json at all"], "{}"),
+        ];
+        for (fragments, expected) in fixtures {
+            assert_eq!(
+                value(&assemble(fragments)),
+                value(expected),
+                "fragments: {:?}",
+                fragments
+            );
+        }
+    }
+}
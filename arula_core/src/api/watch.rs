@@ -0,0 +1,252 @@
+//! Re-running a prompt when files change behind `arula watch`
+//!
+//! The watched file, or every non-ignored file under a watched directory, is
+//! polled for size and modification time. Once changes have settled for the
+//! debounce delay the prompt is sent again together with the current contents
+//! of the watched files (redacted), and `answer_diff` tells the caller how the
+//! new answer differs from the previous one.
+
+use crate::api::stream::complete;
+use crate::tools::sandbox;
+use crate::utils::config::Config;
+use crate::utils::redact;
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How often the watched files are checked
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// File contents sent along with the prompt; later files are only named
+const MAX_CONTEXT_BYTES: usize = 96 * 1024;
+
+const WATCH_MAX_TOKENS: u32 = 4096;
+
+const WATCH_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Size and modification time of a watched file
+type Stamp = (u64, Option<SystemTime>);
+
+/// Polls a file or directory tree for changes
+#[derive(Debug)]
+pub struct FileWatcher {
+    root: PathBuf,
+    /// Files never reported, such as the file answers are written to
+    ignored: Vec<PathBuf>,
+    stamps: BTreeMap<PathBuf, Stamp>,
+}
+
+impl FileWatcher {
+    /// Start watching `root`, a file or a directory
+    pub fn new(root: &Path) -> Result<Self> {
+        if !root.exists() {
+            bail!("{} does not exist", root.display());
+        }
+        let mut watcher = Self {
+            root: root.to_path_buf(),
+            ignored: Vec::new(),
+            stamps: BTreeMap::new(),
+        };
+        watcher.stamps = watcher.scan();
+        Ok(watcher)
+    }
+
+    /// Never report changes to `path`, which need not exist yet
+    pub fn ignoring(mut self, path: &Path) -> Self {
+        self.ignored.push(sandbox::resolve(path));
+        self.stamps = self.scan();
+        self
+    }
+
+    /// Files currently watched, sorted
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.stamps.keys().map(PathBuf::as_path)
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        self.ignored.contains(&sandbox::resolve(path))
+    }
+
+    fn scan(&self) -> BTreeMap<PathBuf, Stamp> {
+        let stamp = |path: &Path| {
+            let meta = std::fs::metadata(path).ok()?;
+            Some((meta.len(), meta.modified().ok()))
+        };
+        let paths: Vec<PathBuf> = if self.root.is_file() {
+            vec![self.root.clone()]
+        } else {
+            ignore::WalkBuilder::new(&self.root)
+                .build()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+                .map(|entry| entry.into_path())
+                .collect()
+        };
+        paths
+            .into_iter()
+            .filter(|path| !self.is_ignored(path))
+            .filter_map(|path| stamp(&path).map(|s| (path, s)))
+            .collect()
+    }
+
+    /// Files added, removed or modified since the last call
+    pub fn changes(&mut self) -> Vec<PathBuf> {
+        let current = self.scan();
+        let mut changed: Vec<PathBuf> = current
+            .iter()
+            .filter(|(path, stamp)| self.stamps.get(*path) != Some(stamp))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.extend(
+            self.stamps
+                .keys()
+                .filter(|path| !current.contains_key(*path))
+                .cloned(),
+        );
+        self.stamps = current;
+        changed.sort();
+        changed
+    }
+
+    /// Wait for the next change and until nothing has changed for `debounce`,
+    /// then return every file changed in between
+    pub async fn next_change(&mut self, debounce: Duration) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        while changed.is_empty() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            changed = self.changes();
+        }
+        loop {
+            tokio::time::sleep(debounce.max(POLL_INTERVAL)).await;
+            let more = self.changes();
+            if more.is_empty() {
+                break;
+            }
+            changed.extend(more);
+        }
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+
+    /// `path` relative to the watched directory, for display
+    pub fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        if self.root.is_file() {
+            return path;
+        }
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    /// The prompt followed by the contents of the watched text files
+    pub fn prompt_with_files(&self, prompt: &str) -> String {
+        let mut text = format!(
+            "{}\n\nThe prompt above is re-run whenever these files change. Their current contents:",
+            prompt.trim()
+        );
+        let mut budget = MAX_CONTEXT_BYTES;
+        let mut left_out = Vec::new();
+        for path in self.files() {
+            let Ok(contents) = std::fs::read_to_string(path) else {
+                continue;
+            };
+            let name = self.relative(path).to_string_lossy().replace('\\', "/");
+            if contents.len() > budget {
+                left_out.push(name);
+                continue;
+            }
+            budget -= contents.len();
+            text.push_str(&format!(
+                "\n\nFile: {}\n```\n{}\n```",
+                name,
+                redact::redact(contents.trim_end())
+            ));
+        }
+        if !left_out.is_empty() {
+            text.push_str(&format!(
+                "\n\nLeft out for size: {}",
+                left_out.join(", ")
+            ));
+        }
+        text
+    }
+
+    /// Send the prompt with the watched files and return the answer
+    pub async fn run(&self, config: &Config, prompt: &str) -> Result<String> {
+        let answer = complete(
+            config,
+            self.prompt_with_files(prompt),
+            WATCH_MAX_TOKENS,
+            WATCH_TIMEOUT,
+        )
+        .await?;
+        if answer.trim().is_empty() {
+            bail!("The model returned an empty answer");
+        }
+        Ok(answer)
+    }
+}
+
+/// Lines removed from (`- `) and added to (`+ `) the previous answer
+pub fn answer_diff(previous: &str, answer: &str) -> Vec<String> {
+    diff::lines(previous.trim_end(), answer.trim_end())
+        .into_iter()
+        .filter_map(|line| match line {
+            diff::Result::Left(l) => Some(format!("- {}", l)),
+            diff::Result::Right(r) => Some(format!("+ {}", r)),
+            diff::Result::Both(..) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watcher_reports_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "first").unwrap();
+        std::fs::write(dir.path().join("SUMMARY.md"), "summary").unwrap();
+        let mut watcher = FileWatcher::new(dir.path())
+            .unwrap()
+            .ignoring(&dir.path().join("SUMMARY.md"));
+        assert_eq!(watcher.files().count(), 1);
+        assert!(watcher.changes().is_empty());
+
+        std::fs::write(dir.path().join("notes.md"), "first, edited").unwrap();
+        std::fs::write(dir.path().join("todo.md"), "new").unwrap();
+        std::fs::write(dir.path().join("SUMMARY.md"), "rewritten answer").unwrap();
+        let changed: Vec<PathBuf> = watcher
+            .changes()
+            .iter()
+            .map(|p| watcher.relative(p).to_path_buf())
+            .collect();
+        assert_eq!(changed, vec![PathBuf::from("notes.md"), PathBuf::from("todo.md")]);
+
+        std::fs::remove_file(dir.path().join("todo.md")).unwrap();
+        assert_eq!(watcher.changes().len(), 1);
+
+        let prompt = watcher.prompt_with_files("Summarize");
+        assert!(prompt.starts_with("Summarize"));
+        assert!(prompt.contains("File: notes.md\n```\nfirst, edited\n```"));
+        assert!(!prompt.contains("rewritten answer"));
+
+        assert_eq!(
+            answer_diff("a\nb\nc\n", "a\nB\nc"),
+            vec!["- b".to_string(), "+ B".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_out_file_created_after_start_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "first").unwrap();
+        let out = dir.path().join("out").join("..").join("SUMMARY.md");
+        let mut watcher = FileWatcher::new(dir.path()).unwrap().ignoring(&out);
+        assert!(watcher.changes().is_empty());
+
+        std::fs::write(dir.path().join("SUMMARY.md"), "answer").unwrap();
+        assert!(watcher.changes().is_empty());
+    }
+}
@@ -72,6 +72,14 @@ pub enum AiResponse {
         success: bool,
         result: serde_json::Value,
     },
+    /// A budget limit ended the run early (message describes the limit)
+    AgentBudgetExceeded(String),
+    /// A failed request is being retried (message describes why and when)
+    AgentRetrying(String),
+    /// Token usage of one model request
+    AgentUsage(crate::api::api::Usage),
+    /// Why a model request stopped; the last one in a run is the run's reason
+    AgentFinished(crate::api::api::FinishReason),
     AgentStreamEnd,
 }
 
@@ -117,6 +125,9 @@ pub struct App {
     pub anthropic_models: Arc<Mutex<Option<Vec<String>>>>,
     pub ollama_models: Arc<Mutex<Option<Vec<String>>>>,
     pub zai_models: Arc<Mutex<Option<Vec<String>>>>,
+    /// Models of the active provider when listed through `api::models::get_fetcher`
+    /// (xAI, DeepSeek, LM Studio, llamafile)
+    pub provider_models: Arc<Mutex<Option<Vec<String>>>>,
     // Conversation tracking
     pub current_conversation: Option<crate::utils::conversation::Conversation>,
     pub auto_save_conversations: bool,
@@ -126,11 +137,16 @@ pub struct App {
     pub shared_conversation: Arc<Mutex<Option<crate::utils::conversation::Conversation>>>,
     // Pending init message to be sent to AI
     pub pending_init_message: Option<String>,
+    /// Send the next request without budget limits ("continue anyway")
+    pub ignore_budget_next: bool,
 }
 
 impl App {
     pub fn new() -> Result<Self> {
         let config = Config::load_or_default()?;
+        crate::utils::redact::configure(&config);
+        crate::tools::sandbox::configure(&config);
+        crate::tools::builtin::transcribe::configure(&config);
 
         // Create persistent tracking channel
         let (tracking_tx, tracking_rx) = std::sync::mpsc::channel();
@@ -154,12 +170,14 @@ impl App {
             anthropic_models: Arc::new(Mutex::new(None)),
             ollama_models: Arc::new(Mutex::new(None)),
             zai_models: Arc::new(Mutex::new(None)),
+            provider_models: Arc::new(Mutex::new(None)),
             current_conversation: None,
             auto_save_conversations: true, // Default to auto-save
             tracking_rx: Some(tracking_rx),
             tracking_tx: Some(tracking_tx),
             shared_conversation: Arc::new(Mutex::new(None)),
             pending_init_message: None,
+            ignore_budget_next: false,
         })
     }
 
@@ -172,6 +190,9 @@ impl App {
     pub fn reload_config(&mut self) -> Result<()> {
         // Reload configuration from file
         self.config = Config::load_or_default()?;
+        crate::utils::redact::configure(&self.config);
+        crate::tools::sandbox::configure(&self.config);
+        crate::tools::builtin::transcribe::configure(&self.config);
 
         // Clear cached tool registry to force refresh with new config
         self.cached_tool_registry = None;
@@ -238,6 +259,15 @@ impl App {
             prompt_parts.push("# ARULA - Autonomous AI Interface\n\nYou are ARULA, an advanced AI coding assistant. You help users with coding, shell commands, and software development tasks. Be concise, helpful, and provide practical solutions.\n\n## Core Principles\n- Be concise and direct\n- Use tools for actions, don't output code as text\n- Read before editing - understand existing code first\n- Follow existing conventions and patterns\n- Verify your work - run tests/lint when available".to_string());
         }
 
+        // Instructions of the active persona
+        if let Some(section) = self
+            .config
+            .get_active_persona()
+            .and_then(|p| p.system_prompt_section())
+        {
+            prompt_parts.push(section);
+        }
+
         // 2. Add development mode warning if running from cargo
         if Self::is_running_from_cargo() {
             prompt_parts.push(r#"
@@ -423,26 +453,37 @@ You have access to these tools for file operations and shell commands:
     }
 
     pub fn initialize_agent_client(&mut self) -> Result<()> {
+        // The active persona may pick its own model and temperature
+        let persona = self.config.get_active_persona();
+        let config = persona
+            .as_ref()
+            .map(|p| p.overlay(&self.config))
+            .unwrap_or_else(|| self.config.clone());
+
         // Initialize modern agent client with default options
-        let agent_options = AgentOptionsBuilder::new()
+        let mut agent_options = AgentOptionsBuilder::new()
             .system_prompt(&self.build_system_prompt())
-            .model(&self.config.get_model())
+            .model(&config.get_model())
             .auto_execute_tools(true)
             .max_tool_iterations(1000)
-            .debug(self.debug)
-            .build();
+            .sampling(config.get_sampling())
+            .debug(self.debug);
+        if let Some(temperature) = persona.and_then(|p| p.temperature) {
+            agent_options = agent_options.temperature(temperature);
+        }
+        let agent_options = agent_options.build();
 
         // Create a new agent client with a basic tool registry
         // MCP tools are handled separately in the streaming response
         let basic_registry = crate::tools::tools::create_basic_tool_registry();
 
         self.agent_client = Some(AgentClient::new_with_registry(
-            self.config.active_provider.clone(),
-            self.config.get_api_url(),
-            self.config.get_api_key(),
-            self.config.get_model(),
+            config.active_provider.clone(),
+            config.get_api_url(),
+            config.get_api_key(),
+            config.get_model(),
             agent_options,
-            &self.config,
+            &config,
             basic_registry,
         ));
 
@@ -478,6 +519,14 @@ You have access to these tools for file operations and shell commands:
 
     pub fn clear_conversation(&mut self) {
         self.messages.clear();
+        self.reset_budget_usage();
+    }
+
+    /// Start the budget count of a new conversation
+    fn reset_budget_usage(&self) {
+        if let Some(client) = &self.agent_client {
+            client.budget_usage().reset();
+        }
     }
 
     pub fn get_message_history(&self) -> &Vec<ChatMessage> {
@@ -498,6 +547,8 @@ You have access to these tools for file operations and shell commands:
         // Add user message to history
         self.messages
             .push(ChatMessage::new(MessageType::User, message.to_string()));
+        crate::utils::snapshots::begin_turn();
+        crate::utils::http_capture::begin_turn();
 
         // Send message using the modern agent client
         self.send_to_ai_with_agent(message).await
@@ -511,12 +562,17 @@ You have access to these tools for file operations and shell commands:
         }
 
         // Get agent client
-        let agent_client = match &self.agent_client {
+        let mut agent_client = match &self.agent_client {
             Some(client) => client.clone(),
             None => {
                 return Err(anyhow::anyhow!("Agent client not initialized"));
             }
         };
+        if std::mem::take(&mut self.ignore_budget_next) {
+            // Continue past the limits, which then count from here
+            agent_client.set_budget(crate::utils::config::BudgetLimits::default());
+            agent_client.budget_usage().reset();
+        }
 
         // Create channel for streaming responses
         let (tx, rx) = mpsc::unbounded_channel();
@@ -582,6 +638,7 @@ You have access to these tools for file operations and shell commands:
                     tool_calls: None,
                     tool_call_id: None,
                     tool_name: None,
+                    images: Vec::new(),
                 }
             })
             .collect();
@@ -623,6 +680,15 @@ You have access to these tools for file operations and shell commands:
                     let _ = tx.send(AiResponse::AgentStreamEnd);
                 }
                 _result = async {
+                    // A pasted link is fetched up front and sent along with the prompt
+                    let mut msg = msg;
+                    let mut api_messages = api_messages;
+                    crate::tools::builtin::fetch_url::expand_pasted_link(
+                        &mut msg,
+                        Some(&mut api_messages),
+                    )
+                    .await;
+
                     // Choose streaming or non-streaming based on config
                     // query_streaming() uses true SSE streaming for real-time output
                     // query_non_streaming() waits for complete response before displaying
@@ -694,8 +760,9 @@ You have access to these tools for file operations and shell commands:
                                             }) => {
                                                 let _status = if result.success { "✓" } else { "✗" };
 
-                                                // Clone result data for all uses
-                                                let result_data = result.data.clone();
+                                                // Clone result data for all uses, with secrets redacted
+                                                let mut result_data = result.data.clone();
+                                                crate::utils::redact::redact_json(&mut result_data);
 
                                                 // Look up tool name from tracked tool calls
                                                 let tool_name = tool_calls_list.iter()
@@ -719,6 +786,16 @@ You have access to these tools for file operations and shell commands:
                                                 });
 
                                             }
+                                            Some(ContentBlock::BudgetExceeded { exceeded }) => {
+                                                let _ = tx.send(AiResponse::AgentBudgetExceeded(
+                                                    exceeded.to_string(),
+                                                ));
+                                            }
+                                            Some(ContentBlock::Retrying { notice }) => {
+                                                let _ = tx.send(AiResponse::AgentRetrying(
+                                                    notice.to_string(),
+                                                ));
+                                            }
                                             Some(ContentBlock::Error { error }) => {
                                                 // Convert error to AgentStreamText to maintain compatibility
                                                 let error_msg = format!("[Error] {}", error);
@@ -733,6 +810,18 @@ You have access to these tools for file operations and shell commands:
                                                 // Ask question is handled by SessionManager in desktop
                                                 // CLI doesn't show interactive question UI
                                             }
+                                            Some(ContentBlock::Usage { usage }) => {
+                                                let _ = tx.send(AiResponse::AgentUsage(usage));
+                                            }
+                                            Some(ContentBlock::Metadata { metadata }) => {
+                                                tracing::debug!(?metadata, "Response metadata");
+                                            }
+                                            Some(ContentBlock::ToolRound { .. }) => {
+                                                // The TUI shows each tool call as it runs
+                                            }
+                                            Some(ContentBlock::Finish { reason }) => {
+                                                let _ = tx.send(AiResponse::AgentFinished(reason));
+                                            }
                                             None => {
                                                 // Stream ended
                                                 break;
@@ -939,6 +1028,12 @@ You have access to these tools for file operations and shell commands:
                             // Note: Tool result tracking with proper name is handled via TrackingCommand
                             // This is a fallback that shouldn't normally be hit since we track via the async task
                         }
+                        AiResponse::AgentBudgetExceeded(_) | AiResponse::AgentRetrying(_) => {
+                            // Shown by the frontend
+                        }
+                        AiResponse::AgentUsage(_) | AiResponse::AgentFinished(_) => {
+                            // Shown by the frontend
+                        }
                         AiResponse::AgentStreamEnd => {
                             if let Some(full_message) = self.current_streaming_message.take() {
                                 self.messages.push(ChatMessage::new(
@@ -1104,23 +1199,15 @@ You have access to these tools for file operations and shell commands:
 
     /// Async function to fetch OpenRouter models
     async fn fetch_openrouter_models_async(api_key: &str) -> Vec<String> {
-        use reqwest::Client;
         use std::time::Duration;
 
         // Create HTTP client
-        let client = match Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("arula-cli/1.0")
-            .build()
-        {
-            Ok(client) => client,
-            Err(e) => {
-                return vec![format!("⚠️ Failed to create HTTP client: {}", e)];
-            }
-        };
+        let client = crate::api::http_client::get_general_client();
 
         // Build request
-        let mut request = client.get("https://openrouter.ai/api/v1/models");
+        let mut request = client
+            .get("https://openrouter.ai/api/v1/models")
+            .timeout(Duration::from_secs(10));
 
         // Add authorization header if API key is provided
         if !api_key.is_empty() {
@@ -1134,6 +1221,8 @@ You have access to these tools for file operations and shell commands:
                 if status.is_success() {
                     match response.json::<Value>().await {
                         Ok(json) => {
+                            use crate::api::model_info;
+                            model_info::store(model_info::parse_openrouter(&json));
                             let mut models = Vec::new();
 
                             // Parse the response
@@ -1228,21 +1317,13 @@ You have access to these tools for file operations and shell commands:
 
     /// Async function to fetch OpenAI models
     async fn fetch_openai_models_async(api_key: &str) -> Vec<String> {
-        use reqwest::Client;
         use std::time::Duration;
 
-        let client = match Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("arula-cli/1.0")
-            .build()
-        {
-            Ok(client) => client,
-            Err(e) => {
-                return vec![format!("⚠️ Failed to create HTTP client: {}", e)];
-            }
-        };
+        let client = crate::api::http_client::get_general_client();
 
-        let mut request = client.get("https://api.openai.com/v1/models");
+        let mut request = client
+            .get("https://api.openai.com/v1/models")
+            .timeout(Duration::from_secs(10));
 
         if !api_key.is_empty() {
             request = request.header("Authorization", format!("Bearer {}", api_key));
@@ -1472,25 +1553,16 @@ You have access to these tools for file operations and shell commands:
 
     /// Async function to fetch Z.AI models from the API
     async fn fetch_zai_models_async(api_key: &str) -> Vec<String> {
-        use reqwest::Client;
         use std::time::Duration;
         
         // Build the models endpoint URL for Z.AI Anthropic-compatible API
         let models_url = "https://api.z.ai/api/anthropic/v1/models";
         
-        let client = match Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("arula-cli/1.0")
-            .build()
-        {
-            Ok(client) => client,
-            Err(e) => {
-                return vec![format!("⚠️ Failed to create HTTP client: {}", e)];
-            }
-        };
+        let client = crate::api::http_client::get_general_client();
         
         let request = client
             .get(models_url)
+            .timeout(Duration::from_secs(10))
             .header("x-api-key", api_key);
         
         match request.send().await {
@@ -1528,9 +1600,53 @@ You have access to these tools for file operations and shell commands:
         }
     }
 
+    /// Get cached models of the active provider, returning None if not cached
+    pub fn get_cached_provider_models(&self) -> Option<Vec<String>> {
+        match self.provider_models.lock() {
+            Ok(models) => models.clone(),
+            Err(e) => {
+                eprintln!("Failed to lock provider models cache for reading: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Cache models of the active provider
+    pub fn cache_provider_models(&self, models: Vec<String>) {
+        match self.provider_models.lock() {
+            Ok(mut models_cache) => {
+                *models_cache = Some(models);
+            }
+            Err(e) => {
+                eprintln!("Failed to lock provider models cache for writing: {}", e);
+            }
+        }
+    }
+
+    /// Fetch the models of the active provider through its `ModelFetcher`
+    /// asynchronously (runs in background)
+    pub fn fetch_provider_models(&self) {
+        let models_cache = self.provider_models.clone();
+        let Some(fetcher) = crate::api::models::get_fetcher(&self.config.active_provider) else {
+            return;
+        };
+        let api_url = self.config.get_api_url();
+        let api_key = self.config.get_api_key();
+
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                let result = fetcher.fetch_models(&api_key, Some(&api_url)).await;
+                if let Ok(mut cache) = models_cache.lock() {
+                    *cache = Some(result);
+                }
+            });
+        } else if let Ok(mut cache) = models_cache.lock() {
+            *cache = Some(vec!["⚠️ No tokio runtime available".to_string()]);
+        }
+    }
+
     /// Async function to fetch Ollama models
     async fn fetch_ollama_models_async(api_url: &str) -> Vec<String> {
-        use reqwest::Client;
         use std::time::Duration;
 
         // Normalize the URL: remove trailing paths and slashes to get base URL
@@ -1542,18 +1658,11 @@ You have access to these tools for file operations and shell commands:
             .trim_end_matches("/api/generate")
             .trim_end_matches("/api");
 
-        let client = match Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("arula-cli/1.0")
-            .build()
-        {
-            Ok(client) => client,
-            Err(e) => {
-                return vec![format!("⚠️ Failed to create HTTP client: {}", e)];
-            }
-        };
+        let client = crate::api::http_client::get_general_client();
 
-        let request = client.get(format!("{}/api/tags", base_url));
+        let request = client
+            .get(format!("{}/api/tags", base_url))
+            .timeout(Duration::from_secs(10));
 
         match request.send().await {
             Ok(response) => {
@@ -1736,6 +1845,7 @@ You have access to these tools for file operations and shell commands:
 
         // Convert conversation messages to chat messages
         self.messages.clear();
+        self.reset_budget_usage();
         for msg in &conversation.messages {
             match msg.role.as_str() {
                 "user" => {
@@ -1917,6 +2027,7 @@ You have access to these tools for file operations and shell commands:
         let endpoint = provider_config.api_url.clone().unwrap_or_default();
 
         self.current_conversation = Some(Conversation::new(model, provider, endpoint));
+        self.reset_budget_usage();
     }
 
     /// Build built-in tools information for the AI
@@ -2008,6 +2119,7 @@ mod tests {
             anthropic_models: Arc::new(Mutex::new(None)),
             ollama_models: Arc::new(Mutex::new(None)),
             zai_models: Arc::new(Mutex::new(None)),
+            provider_models: Arc::new(Mutex::new(None)),
             current_conversation: None,
             auto_save_conversations: false,
             tracking_rx: Some(tracking_rx),
@@ -2015,6 +2127,7 @@ mod tests {
             shared_conversation: Arc::new(Mutex::new(None)),
             cached_tool_registry: None,
             git_state_tracker: GitStateTracker::new("."),
+            ignore_budget_next: false,
         }
     }
 
@@ -2094,6 +2207,7 @@ mod tests {
             anthropic_models: Arc::new(Mutex::new(None)),
             ollama_models: Arc::new(Mutex::new(None)),
             zai_models: Arc::new(Mutex::new(None)),
+            provider_models: Arc::new(Mutex::new(None)),
             current_conversation: None,
             auto_save_conversations: false,
             tracking_rx: Some(tracking_rx),
@@ -2101,6 +2215,7 @@ mod tests {
             shared_conversation: Arc::new(Mutex::new(None)),
             cached_tool_registry: None,
             git_state_tracker: GitStateTracker::new("."),
+            ignore_budget_next: false,
         };
 
         assert_eq!(app.config.get_model(), "test-model");
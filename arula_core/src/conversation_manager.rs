@@ -1,13 +1,21 @@
 //! Conversation management for saving and loading chat histories.
+//!
+//! Each conversation is a JSON file named by its ID. Pinned and archived
+//! conversations live in the `pinned/` and `archive/` subdirectories, so every
+//! sidebar section can be listed without opening the files of the others.
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::SystemTime;
 use uuid::Uuid;
 
 use crate::session_manager::UiEvent;
+use crate::utils::redact;
 
 /// Metadata for a saved conversation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +26,18 @@ pub struct ConversationMetadata {
     pub updated_at: DateTime<Utc>,
     pub message_count: usize,
     pub model: String,
+    /// Listed above all other conversations
+    #[serde(default)]
+    pub pinned: bool,
+    /// Hidden from the default list
+    #[serde(default)]
+    pub archived: bool,
+    /// Labels added by the user
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Saved while an answer was still streaming, and not saved since
+    #[serde(default)]
+    pub interrupted: bool,
 }
 
 impl ConversationMetadata {
@@ -66,6 +86,21 @@ impl ConversationMetadata {
             updated_at,
             message_count,
             model,
+            pinned: false,
+            archived: false,
+            tags: Vec::new(),
+            interrupted: false,
+        }
+    }
+
+    /// The list the conversation is shown in.
+    pub fn list(&self) -> ConversationList {
+        if self.archived {
+            ConversationList::Archived
+        } else if self.pinned {
+            ConversationList::Pinned
+        } else {
+            ConversationList::Recent
         }
     }
 
@@ -82,8 +117,52 @@ pub struct SavedConversation {
     pub events: Vec<UiEvent>,
 }
 
+/// Sidebar section of saved conversations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConversationList {
+    Pinned,
+    /// Neither pinned nor archived
+    Recent,
+    Archived,
+}
+
+impl ConversationList {
+    /// Subdirectory of the storage directory holding the list's files
+    fn subdir(&self) -> Option<&'static str> {
+        match self {
+            ConversationList::Pinned => Some("pinned"),
+            ConversationList::Recent => None,
+            ConversationList::Archived => Some("archive"),
+        }
+    }
+}
+
+/// A page of conversation metadata, most recently updated first.
+#[derive(Debug, Clone, Default)]
+pub struct ConversationPage {
+    pub conversations: Vec<ConversationMetadata>,
+    /// Total number of saved conversation files, readable or not
+    pub total: usize,
+    /// Offset of the next page; unreadable files make it run ahead of the
+    /// number of conversations loaded so far
+    pub next_offset: usize,
+}
+
+impl ConversationPage {
+    /// Whether more conversations exist after this page
+    pub fn has_more(&self) -> bool {
+        self.next_offset < self.total
+    }
+}
+
+/// Only the metadata of a saved conversation file; events are skipped while parsing.
+#[derive(Deserialize)]
+struct MetadataOnly {
+    metadata: ConversationMetadata,
+}
+
 /// Manager for conversation storage and retrieval.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ConversationManager {
     storage_dir: PathBuf,
 }
@@ -96,7 +175,14 @@ impl ConversationManager {
             .join(".arula")
             .join("conversations");
 
-        // Create storage directory if it doesn't exist
+        Self::with_storage_dir(storage_dir)
+    }
+
+    /// Creates a conversation manager that stores conversations in `storage_dir`.
+    ///
+    /// Used on platforms without a usable home directory (e.g. Android app storage).
+    pub fn with_storage_dir(storage_dir: impl Into<PathBuf>) -> Result<Self> {
+        let storage_dir = storage_dir.into();
         fs::create_dir_all(&storage_dir)
             .with_context(|| format!("Failed to create conversations directory: {:?}", storage_dir))?;
 
@@ -105,30 +191,102 @@ impl ConversationManager {
 
     /// Saves a conversation with the given ID and events.
     pub fn save_conversation(&self, id: Uuid, events: &[UiEvent], model: String) -> Result<()> {
+        self.save(id, events, model, false)
+    }
+
+    /// Saves a conversation whose answer is still streaming, marked as
+    /// interrupted until the next regular save, so an app that closes
+    /// mid-answer can offer to continue it.
+    pub fn save_interrupted_conversation(
+        &self,
+        id: Uuid,
+        events: &[UiEvent],
+        model: String,
+    ) -> Result<()> {
+        self.save(id, events, model, true)
+    }
+
+    /// Clears the interrupted mark of a saved conversation whose stream ended
+    /// without a regular save (it failed, or its tab was closed). Does nothing
+    /// when the conversation isn't marked.
+    pub fn clear_interrupted(&self, id: Uuid) -> Result<()> {
+        let Some(path) = self.find_file(id) else {
+            return Ok(());
+        };
+        if !Self::read_metadata(&path).is_some_and(|metadata| metadata.interrupted) {
+            return Ok(());
+        }
+        let mut conversation = self.load_conversation(id)?;
+        conversation.metadata.interrupted = false;
+        let json = serde_json::to_string_pretty(&conversation)
+            .context("Failed to serialize conversation")?;
+        fs::write(&path, json).context("Failed to write conversation file")
+    }
+
+    fn save(&self, id: Uuid, events: &[UiEvent], model: String, interrupted: bool) -> Result<()> {
         if events.is_empty() {
             return Ok(()); // Don't save empty conversations
         }
 
-        let metadata = ConversationMetadata::from_events(id, events, model);
+        let mut metadata = ConversationMetadata::from_events(id, events, model);
+        metadata.interrupted = interrupted;
+        // Pinned/archived state and tags survive re-saves
+        if let Some(previous) = self.find_file(id).and_then(|path| Self::read_metadata(&path)) {
+            metadata.pinned = previous.pinned;
+            metadata.archived = previous.archived;
+            metadata.tags = previous.tags;
+        }
         let conversation = SavedConversation { metadata, events: events.to_vec() };
 
-        let file_path = self.storage_dir.join(format!("{}.json", id));
-        let json = serde_json::to_string_pretty(&conversation)
+        // Secrets in tool output or messages never reach disk
+        let mut value = serde_json::to_value(&conversation)
             .context("Failed to serialize conversation")?;
+        redact::redact_json(&mut value);
+        let json = serde_json::to_string_pretty(&value)
+            .context("Failed to serialize conversation")?;
+
+        self.write_file(&conversation.metadata, json)
+    }
+
+    /// Directory holding the files of `list`
+    fn list_dir(&self, list: ConversationList) -> PathBuf {
+        match list.subdir() {
+            Some(subdir) => self.storage_dir.join(subdir),
+            None => self.storage_dir.clone(),
+        }
+    }
+
+    /// The file of conversation `id`, in whichever list it is.
+    fn find_file(&self, id: Uuid) -> Option<PathBuf> {
+        [ConversationList::Recent, ConversationList::Pinned, ConversationList::Archived]
+            .into_iter()
+            .map(|list| self.list_dir(list).join(format!("{}.json", id)))
+            .find(|path| path.exists())
+    }
 
-        fs::write(file_path, json)
+    /// Writes `json` to the file for `metadata`'s list, removing the file from
+    /// the list it was in before.
+    fn write_file(&self, metadata: &ConversationMetadata, json: String) -> Result<()> {
+        let dir = self.list_dir(metadata.list());
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create conversations directory: {:?}", dir))?;
+        let file_path = dir.join(format!("{}.json", metadata.id));
+        let previous = self.find_file(metadata.id);
+
+        fs::write(&file_path, json)
             .context("Failed to write conversation file")?;
 
+        if let Some(previous) = previous.filter(|previous| *previous != file_path) {
+            fs::remove_file(previous).context("Failed to remove the old conversation file")?;
+        }
         Ok(())
     }
 
     /// Loads a conversation by ID.
     pub fn load_conversation(&self, id: Uuid) -> Result<SavedConversation> {
-        let file_path = self.storage_dir.join(format!("{}.json", id));
-        
-        if !file_path.exists() {
+        let Some(file_path) = self.find_file(id) else {
             return Err(anyhow::anyhow!("Conversation not found: {}", id));
-        }
+        };
 
         let content = fs::read_to_string(&file_path)
             .context("Failed to read conversation file")?;
@@ -139,42 +297,97 @@ impl ConversationManager {
         Ok(conversation)
     }
 
-    /// Lists all saved conversations with their metadata.
+    /// Lists the saved conversations that aren't archived, pinned ones first.
     pub fn list_conversations(&self) -> Result<Vec<ConversationMetadata>> {
-        let mut conversations = Vec::new();
+        let mut conversations = self
+            .list_page(ConversationList::Pinned, 0, usize::MAX)?
+            .conversations;
+        conversations.extend(self.list_conversations_page(0, usize::MAX)?.conversations);
+        Ok(conversations)
+    }
 
-        if !self.storage_dir.exists() {
-            return Ok(conversations);
+    /// Lists up to `limit` conversations that are neither pinned nor archived,
+    /// starting at `offset`, most recent first.
+    pub fn list_conversations_page(&self, offset: usize, limit: usize) -> Result<ConversationPage> {
+        self.list_page(ConversationList::Recent, offset, limit)
+    }
+
+    /// Lists up to `limit` conversations of `list` starting at `offset`, most recent first.
+    ///
+    /// Files are ordered by modification time (every save rewrites the file), so
+    /// only the files in the requested page are opened, and only their metadata
+    /// is deserialized. Unreadable files are skipped.
+    pub fn list_page(
+        &self,
+        list: ConversationList,
+        offset: usize,
+        limit: usize,
+    ) -> Result<ConversationPage> {
+        let files = self.conversation_files(&self.list_dir(list))?;
+        let total = files.len();
+        let next_offset = offset.saturating_add(limit).min(total);
+
+        let mut conversations: Vec<ConversationMetadata> = files
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|(path, _)| Self::read_metadata(path))
+            .collect();
+
+        // Modification time is only a proxy; keep the page itself strictly ordered
+        conversations.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+
+        Ok(ConversationPage {
+            conversations,
+            total,
+            next_offset,
+        })
+    }
+
+    /// Conversation files in `dir` with their modification time, newest first.
+    fn conversation_files(&self, dir: &Path) -> Result<Vec<(PathBuf, SystemTime)>> {
+        let mut files = Vec::new();
+
+        if !dir.exists() {
+            return Ok(files);
         }
 
-        for entry in fs::read_dir(&self.storage_dir)
-            .context("Failed to read conversations directory")? 
+        for entry in fs::read_dir(dir)
+            .context("Failed to read conversations directory")?
         {
             let entry = entry.context("Failed to read directory entry")?;
             let path = entry.path();
 
             if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                let content = fs::read_to_string(&path)
-                    .context("Failed to read conversation file")?;
-                
-                let conversation: SavedConversation = serde_json::from_str(&content)
-                    .context("Failed to deserialize conversation")?;
-                
-                conversations.push(conversation.metadata);
+                let modified = entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push((path, modified));
             }
         }
 
-        // Sort by most recently updated first
-        conversations.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        files.sort_by(|a, b| b.1.cmp(&a.1));
 
-        Ok(conversations)
+        Ok(files)
+    }
+
+    /// Reads just the metadata of a conversation file.
+    fn read_metadata(path: &Path) -> Option<ConversationMetadata> {
+        let file = fs::File::open(path).ok()?;
+        let reader = std::io::BufReader::new(file);
+        match serde_json::from_reader::<_, MetadataOnly>(reader) {
+            Ok(parsed) => Some(parsed.metadata),
+            Err(e) => {
+                tracing::warn!("Skipping unreadable conversation {}: {}", path.display(), e);
+                None
+            }
+        }
     }
 
     /// Deletes a conversation by ID.
     pub fn delete_conversation(&self, id: Uuid) -> Result<()> {
-        let file_path = self.storage_dir.join(format!("{}.json", id));
-        
-        if file_path.exists() {
+        if let Some(file_path) = self.find_file(id) {
             fs::remove_file(&file_path)
                 .context("Failed to delete conversation file")?;
         }
@@ -187,15 +400,121 @@ impl ConversationManager {
         let mut conversation = self.load_conversation(metadata.id)?;
         conversation.metadata = metadata.clone();
 
-        let file_path = self.storage_dir.join(format!("{}.json", metadata.id));
         let json = serde_json::to_string_pretty(&conversation)
             .context("Failed to serialize conversation")?;
 
-        fs::write(file_path, json)
-            .context("Failed to write conversation file")?;
+        self.write_file(metadata, json)
+    }
 
+    /// Pins or unpins conversations. Pinning an archived conversation unarchives it.
+    pub fn set_pinned(&self, ids: &[Uuid], pinned: bool) -> Result<()> {
+        for &id in ids {
+            let mut metadata = self.load_conversation(id)?.metadata;
+            metadata.pinned = pinned;
+            if pinned {
+                metadata.archived = false;
+            }
+            self.update_conversation_metadata(&metadata)?;
+        }
         Ok(())
     }
+
+    /// Adds `tag` to conversations that don't have it yet.
+    pub fn add_tag(&self, ids: &[Uuid], tag: &str) -> Result<()> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Ok(());
+        }
+        for &id in ids {
+            let mut metadata = self.load_conversation(id)?.metadata;
+            if !metadata.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                metadata.tags.push(tag.to_string());
+                self.update_conversation_metadata(&metadata)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes conversations not saved for `days` days, archived ones
+    /// included; pinned conversations are kept. Returns how many were deleted.
+    pub fn delete_older_than(&self, days: u32) -> Result<usize> {
+        let cutoff = SystemTime::now() - std::time::Duration::from_secs(u64::from(days) * 86_400);
+        let mut deleted = 0;
+        for list in [ConversationList::Recent, ConversationList::Archived] {
+            for (path, modified) in self.conversation_files(&self.list_dir(list))? {
+                if modified < cutoff {
+                    fs::remove_file(&path)
+                        .with_context(|| format!("Failed to delete {}", path.display()))?;
+                    deleted += 1;
+                }
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Archives or restores conversations. Archiving a pinned conversation unpins it.
+    pub fn set_archived(&self, ids: &[Uuid], archived: bool) -> Result<()> {
+        for &id in ids {
+            let mut metadata = self.load_conversation(id)?.metadata;
+            metadata.archived = archived;
+            if archived {
+                metadata.pinned = false;
+            }
+            self.update_conversation_metadata(&metadata)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the interrupted saves of streaming conversations on a background
+/// thread, so serializing a long conversation every few seconds doesn't stall
+/// the UI
+#[derive(Debug, Clone)]
+pub struct PartialSaver {
+    jobs: mpsc::Sender<(Uuid, Vec<UiEvent>, String)>,
+    /// Conversations whose stream is still running; queued saves of any other
+    /// are dropped
+    streaming: Arc<Mutex<HashSet<Uuid>>>,
+}
+
+impl PartialSaver {
+    pub fn new(manager: ConversationManager) -> Self {
+        let (jobs, rx) = mpsc::channel::<(Uuid, Vec<UiEvent>, String)>();
+        let streaming = Arc::new(Mutex::new(HashSet::new()));
+        let active = Arc::clone(&streaming);
+        std::thread::spawn(move || {
+            for (id, events, model) in rx {
+                // Held while writing, so `finish` waits for a save in progress
+                let active = active.lock().unwrap_or_else(|e| e.into_inner());
+                if !active.contains(&id) {
+                    continue;
+                }
+                if let Err(err) = manager.save_interrupted_conversation(id, &events, model) {
+                    tracing::warn!("Failed to save streaming conversation: {}", err);
+                }
+            }
+        });
+        Self { jobs, streaming }
+    }
+
+    /// Queues a save of `events`, marked as interrupted
+    pub fn save(&self, id: Uuid, events: Vec<UiEvent>, model: String) {
+        self.streaming
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id);
+        let _ = self.jobs.send((id, events, model));
+    }
+
+    /// Stops saving `id` when its stream ended. Once this returns, no queued
+    /// save of it is written anymore; the result says whether one may already
+    /// be on disk, marked as interrupted.
+    pub fn finish(&self, id: Uuid) -> bool {
+        self.streaming
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&id)
+    }
 }
 
 #[cfg(test)]
@@ -225,6 +544,149 @@ mod tests {
         assert_eq!(metadata.title, "Hello, how are you?");
     }
 
+    #[test]
+    fn test_list_conversations_page() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = ConversationManager::with_storage_dir(temp_dir.path()).unwrap();
+        for i in 0..5 {
+            let events = vec![UiEvent::UserMessage {
+                content: format!("Message {}", i),
+                timestamp: Utc::now().to_rfc3339(),
+            }];
+            manager.save_conversation(Uuid::new_v4(), &events, "gpt-4".to_string()).unwrap();
+        }
+        fs::write(temp_dir.path().join("broken.json"), "{").unwrap();
+
+        // The unreadable file counts towards the total but is skipped in its page
+        let first = manager.list_conversations_page(0, 3).unwrap();
+        let second = manager.list_conversations_page(first.next_offset, 3).unwrap();
+        assert_eq!(first.total, 6);
+        assert_eq!(first.next_offset, 3);
+        assert!(first.has_more());
+        assert!(!second.has_more());
+        assert_eq!(first.conversations.len() + second.conversations.len(), 5);
+
+        let all = manager.list_conversations().unwrap();
+        assert_eq!(all.len(), 5);
+    }
+
+    #[test]
+    fn test_pin_and_archive() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = ConversationManager::with_storage_dir(temp_dir.path()).unwrap();
+        let events = vec![UiEvent::UserMessage {
+            content: "Hello".to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+        }];
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        for id in &ids {
+            manager.save_conversation(*id, &events, "gpt-4".to_string()).unwrap();
+        }
+
+        manager.set_pinned(&ids[..1], true).unwrap();
+        manager.set_archived(&ids[1..2], true).unwrap();
+        // Re-saving keeps the state
+        manager.save_conversation(ids[0], &events, "gpt-4".to_string()).unwrap();
+
+        let pinned = manager.list_page(ConversationList::Pinned, 0, 10).unwrap();
+        let recent = manager.list_conversations_page(0, 10).unwrap();
+        let archived = manager.list_page(ConversationList::Archived, 0, 10).unwrap();
+        assert_eq!(pinned.conversations[0].id, ids[0]);
+        assert!(pinned.conversations[0].pinned);
+        assert_eq!(recent.total, 1);
+        assert_eq!(recent.conversations[0].id, ids[2]);
+        assert_eq!(archived.conversations[0].id, ids[1]);
+        assert_eq!(manager.list_conversations().unwrap().len(), 2);
+
+        // Pinning an archived conversation restores it; deleting finds it anywhere
+        manager.set_pinned(&ids[1..2], true).unwrap();
+        assert_eq!(manager.list_page(ConversationList::Pinned, 0, 10).unwrap().total, 2);
+        assert_eq!(manager.list_page(ConversationList::Archived, 0, 10).unwrap().total, 0);
+        manager.delete_conversation(ids[1]).unwrap();
+        assert!(manager.load_conversation(ids[1]).is_err());
+    }
+
+    #[test]
+    fn test_tags_and_retention() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = ConversationManager::with_storage_dir(temp_dir.path()).unwrap();
+        let events = vec![UiEvent::UserMessage {
+            content: "Hello".to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+        }];
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        for id in &ids {
+            manager.save_conversation(*id, &events, "gpt-4".to_string()).unwrap();
+        }
+
+        manager.add_tag(&ids, "work").unwrap();
+        manager.add_tag(&ids[..1], "Work").unwrap();
+        manager.save_conversation(ids[0], &events, "gpt-4".to_string()).unwrap();
+        assert_eq!(manager.load_conversation(ids[0]).unwrap().metadata.tags, vec!["work"]);
+
+        // A streaming save is marked interrupted until the next regular save
+        manager.save_interrupted_conversation(ids[2], &events, "gpt-4".to_string()).unwrap();
+        assert!(manager.load_conversation(ids[2]).unwrap().metadata.interrupted);
+        manager.save_conversation(ids[2], &events, "gpt-4".to_string()).unwrap();
+        assert!(!manager.load_conversation(ids[2]).unwrap().metadata.interrupted);
+        // ...or until a stream that ended without one clears it
+        manager.save_interrupted_conversation(ids[2], &events, "gpt-4".to_string()).unwrap();
+        manager.clear_interrupted(ids[2]).unwrap();
+        assert!(!manager.load_conversation(ids[2]).unwrap().metadata.interrupted);
+        assert_eq!(manager.load_conversation(ids[2]).unwrap().events.len(), 1);
+
+        // Age every file; the pinned conversation is kept
+        manager.set_pinned(&ids[..1], true).unwrap();
+        manager.set_archived(&ids[1..2], true).unwrap();
+        let old = SystemTime::now() - std::time::Duration::from_secs(40 * 86_400);
+        let lists = [
+            ConversationList::Pinned,
+            ConversationList::Recent,
+            ConversationList::Archived,
+        ];
+        for list in lists {
+            for (path, _) in manager.conversation_files(&manager.list_dir(list)).unwrap() {
+                fs::File::options().write(true).open(path).unwrap().set_modified(old).unwrap();
+            }
+        }
+        assert_eq!(manager.delete_older_than(30).unwrap(), 2);
+        assert!(manager.load_conversation(ids[0]).is_ok());
+        assert_eq!(manager.delete_older_than(30).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_partial_saves_stop_when_finished() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = ConversationManager::with_storage_dir(temp_dir.path()).unwrap();
+        let saver = PartialSaver::new(manager.clone());
+        let events = vec![UiEvent::UserMessage {
+            content: "Hello".to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+        }];
+
+        let id = Uuid::new_v4();
+        saver.save(id, events.clone(), "gpt-4".to_string());
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while manager.load_conversation(id).is_err() {
+            assert!(std::time::Instant::now() < deadline, "the save never happened");
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(manager.load_conversation(id).unwrap().metadata.interrupted);
+
+        // Saves still queued when the stream ends are dropped
+        let other = Uuid::new_v4();
+        for _ in 0..20 {
+            saver.save(other, events.clone(), "gpt-4".to_string());
+        }
+        assert!(saver.finish(other));
+        assert!(!saver.finish(other));
+        manager.clear_interrupted(other).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        if let Ok(conversation) = manager.load_conversation(other) {
+            assert!(!conversation.metadata.interrupted);
+        }
+    }
+
     #[test]
     fn test_title_truncation() {
         let id = Uuid::new_v4();
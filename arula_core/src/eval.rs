@@ -0,0 +1,512 @@
+//! Evaluation harness behind `arula eval`
+//!
+//! A suite is a YAML file of test cases, each a prompt with expectations
+//! about the run: tools that must (or must not) be called and with which
+//! arguments, text the answer must contain or match, and optionally a golden
+//! transcript the run is compared against line by line. Suites run against
+//! any `Backend`, so a prompt or system prompt change can be checked against
+//! a real provider or replayed deterministically with `MockBackend`.
+//!
+//! Every case runs in a fresh temporary directory, a copy of the suite's
+//! `workspace` when it has one. The tools resolve paths against it and the
+//! file sandbox is rooted there, so `write_file` and `execute_bash` change the
+//! copy, never the directory `arula eval` was started in.
+//!
+//! ```yaml
+//! system_prompt: You are a concise assistant.   # optional
+//! workspace: fixtures/project                   # optional, relative to the suite file
+//! cases:
+//!   - name: reads the readme
+//!     prompt: What is this project?
+//!     expect:
+//!       tool_calls:
+//!         - name: read_file
+//!           args: { path: README.md }            # subset of the arguments
+//!       forbidden_tools: [write_file]
+//!       contains: [Arula]
+//!       matches: "(?i)terminal"
+//!       max_tool_calls: 3
+//!     golden: golden/readme.txt                  # relative to the suite file
+//! ```
+
+use crate::tools::sandbox;
+use crate::utils::config::Config;
+use crate::{AgentBackend, Backend, SessionConfig, SessionManager, StreamEvent};
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Longest a case may run unless it sets `timeout_secs`
+const DEFAULT_CASE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A YAML file of test cases
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalSuite {
+    /// Replaces the usual system prompt when set
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    pub cases: Vec<EvalCase>,
+    /// Directory copied for each case to run in
+    #[serde(default)]
+    pub workspace: Option<PathBuf>,
+    /// Directory golden and workspace paths are resolved against
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+impl EvalSuite {
+    /// Load a suite file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut suite: Self = serde_yaml::from_str(&text)
+            .with_context(|| format!("Invalid eval suite {}", path.display()))?;
+        suite.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(suite)
+    }
+}
+
+/// The agent a suite runs against unless a fixture replaces it: with the
+/// suite's own system prompt, or the usual one for the working directory.
+///
+/// Its tools really run, so this also turns the sandbox on whatever
+/// `fs_sandbox_enabled` says, keeping them inside each case's directory.
+pub fn agent_backend(config: &Config, suite: &EvalSuite) -> Result<AgentBackend> {
+    let backend = match &suite.system_prompt {
+        Some(system_prompt) => AgentBackend::new(config, system_prompt.clone()),
+        None => SessionManager::build_backend(config).map(|(backend, _, _)| backend),
+    }?;
+    sandbox::set_enabled(true);
+    Ok(backend)
+}
+
+/// One prompt and what its run must look like
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalCase {
+    pub name: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub expect: Expectations,
+    /// Transcript the run must reproduce exactly
+    #[serde(default)]
+    pub golden: Option<PathBuf>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Assertions about a run; all of them are optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Expectations {
+    /// Tools that must be called, in this order (other calls may come between)
+    pub tool_calls: Vec<ExpectedToolCall>,
+    /// Tools that must not be called
+    pub forbidden_tools: Vec<String>,
+    /// Text the answer must contain (case-sensitive)
+    pub contains: Vec<String>,
+    /// Text the answer must not contain
+    pub not_contains: Vec<String>,
+    /// Regex the answer must match
+    pub matches: Option<String>,
+    pub max_tool_calls: Option<usize>,
+}
+
+/// A tool call the run must make
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpectedToolCall {
+    pub name: String,
+    /// Arguments the call must have; others are ignored
+    #[serde(default)]
+    pub args: Option<Value>,
+}
+
+/// A tool call made during a run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordedToolCall {
+    pub name: String,
+    pub args: Value,
+}
+
+/// What a run did, as compared against the expectations
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Transcript {
+    /// Answer text of the whole run
+    pub text: String,
+    pub tool_calls: Vec<RecordedToolCall>,
+    pub error: Option<String>,
+    /// Tool calls and text in the order they happened, one `[tool]` line per call
+    pub rendered: String,
+}
+
+impl Transcript {
+    fn push_text(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.rendered.push_str(text);
+    }
+
+    fn push_tool_call(&mut self, name: &str, arguments: &str) {
+        let args: Value =
+            serde_json::from_str(arguments).unwrap_or(Value::String(arguments.into()));
+        if !self.rendered.is_empty() && !self.rendered.ends_with('\n') {
+            self.rendered.push('\n');
+        }
+        let _ = writeln!(self.rendered, "[tool] {} {}", name, args);
+        self.tool_calls.push(RecordedToolCall {
+            name: name.to_string(),
+            args,
+        });
+    }
+}
+
+/// Outcome of one case
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    pub name: String,
+    pub passed: bool,
+    /// One entry per failed expectation; golden mismatches include a diff
+    pub failures: Vec<String>,
+    pub duration_ms: u64,
+    pub transcript: Transcript,
+}
+
+/// Run one prompt on `backend` and record what happened
+pub async fn run_case<B: Backend>(
+    backend: &B,
+    case: &EvalCase,
+    session: SessionConfig,
+) -> Transcript {
+    let mut transcript = Transcript::default();
+    let mut stream = match backend.stream_session(case.prompt.clone(), None, session) {
+        Ok(stream) => stream,
+        Err(err) => {
+            transcript.error = Some(err.to_string());
+            return transcript;
+        }
+    };
+    let timeout = case
+        .timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_CASE_TIMEOUT);
+    let collect = async {
+        while let Some(event) = stream.next().await {
+            match event {
+                StreamEvent::Text { text } => transcript.push_text(&text),
+                StreamEvent::ToolCall {
+                    name, arguments, ..
+                } => transcript.push_tool_call(&name, &arguments),
+                StreamEvent::Error(err) => transcript.error = Some(err),
+                _ => {}
+            }
+        }
+    };
+    if tokio::time::timeout(timeout, collect).await.is_err() {
+        transcript.error = Some(format!("Timed out after {}s", timeout.as_secs()));
+    }
+    transcript
+}
+
+/// Failed expectations of a run; `golden` is the expected transcript, if any
+pub fn check(expect: &Expectations, transcript: &Transcript, golden: Option<&str>) -> Vec<String> {
+    let mut failures = Vec::new();
+    if let Some(error) = &transcript.error {
+        failures.push(format!("run failed: {}", error));
+    }
+
+    let mut calls = transcript.tool_calls.iter();
+    for expected in &expect.tool_calls {
+        let found = calls.any(|call| {
+            call.name == expected.name
+                && expected
+                    .args
+                    .as_ref()
+                    .is_none_or(|args| is_subset(args, &call.args))
+        });
+        if !found {
+            let args = expected
+                .args
+                .as_ref()
+                .map(|a| format!(" {}", a))
+                .unwrap_or_default();
+            failures.push(format!("expected a call to {}{}", expected.name, args));
+            break;
+        }
+    }
+    for tool in &expect.forbidden_tools {
+        if transcript.tool_calls.iter().any(|call| &call.name == tool) {
+            failures.push(format!("called forbidden tool {}", tool));
+        }
+    }
+    if let Some(max) = expect.max_tool_calls {
+        if transcript.tool_calls.len() > max {
+            failures.push(format!(
+                "made {} tool calls, at most {} expected",
+                transcript.tool_calls.len(),
+                max
+            ));
+        }
+    }
+
+    for needle in &expect.contains {
+        if !transcript.text.contains(needle.as_str()) {
+            failures.push(format!("answer does not contain {:?}", needle));
+        }
+    }
+    for needle in &expect.not_contains {
+        if transcript.text.contains(needle.as_str()) {
+            failures.push(format!("answer contains {:?}", needle));
+        }
+    }
+    if let Some(pattern) = &expect.matches {
+        match regex::Regex::new(pattern) {
+            Ok(re) if !re.is_match(&transcript.text) => {
+                failures.push(format!("answer does not match /{}/", pattern))
+            }
+            Ok(_) => {}
+            Err(err) => failures.push(format!("invalid regex /{}/: {}", pattern, err)),
+        }
+    }
+
+    if let Some(golden) = golden {
+        if golden.trim_end() != transcript.rendered.trim_end() {
+            failures.push(format!(
+                "transcript differs from golden:\n{}",
+                golden_diff(golden, &transcript.rendered)
+            ));
+        }
+    }
+    failures
+}
+
+/// Whether every field of `expected` is present in `actual` with the same value
+fn is_subset(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|a| is_subset(value, a))),
+        _ => expected == actual,
+    }
+}
+
+/// Changed lines between the golden and the actual transcript
+fn golden_diff(golden: &str, actual: &str) -> String {
+    diff::lines(golden.trim_end(), actual.trim_end())
+        .into_iter()
+        .filter_map(|line| match line {
+            diff::Result::Left(l) => Some(format!("- {}", l)),
+            diff::Result::Right(r) => Some(format!("+ {}", r)),
+            diff::Result::Both(..) => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Run every case of `suite` whose name contains `filter`, one after another,
+/// calling `on_case` as each finishes. With `update_golden`, golden files are
+/// rewritten from the runs instead of compared.
+pub async fn run_suite<B: Backend>(
+    backend: &B,
+    suite: &EvalSuite,
+    session: &SessionConfig,
+    filter: Option<&str>,
+    update_golden: bool,
+    mut on_case: impl FnMut(&CaseResult),
+) -> Result<Vec<CaseResult>> {
+    let mut results = Vec::new();
+    for case in &suite.cases {
+        if filter.is_some_and(|f| !case.name.contains(f)) {
+            continue;
+        }
+        let started = Instant::now();
+        let mut session = session.clone();
+        if let Some(system_prompt) = &suite.system_prompt {
+            session.system_prompt = system_prompt.clone();
+        }
+        let workdir = tempfile::tempdir().context("Failed to create a directory for the case")?;
+        if let Some(workspace) = &suite.workspace {
+            copy_dir(&suite.dir.join(workspace), workdir.path())?;
+        }
+        let transcript =
+            sandbox::with_workdir(workdir.path().to_path_buf(), run_case(backend, case, session))
+                .await;
+
+        let golden_path = case.golden.as_ref().map(|path| suite.dir.join(path));
+        let golden = match &golden_path {
+            Some(path) if update_golden && transcript.error.is_none() => {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                std::fs::write(path, &transcript.rendered)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                None
+            }
+            Some(path) => Some(std::fs::read_to_string(path).map_err(|_| path)),
+            None => None,
+        };
+        let mut failures = match &golden {
+            Some(Ok(golden)) => check(&case.expect, &transcript, Some(golden)),
+            _ => check(&case.expect, &transcript, None),
+        };
+        if let Some(Err(path)) = golden {
+            failures.push(format!(
+                "golden {} is missing; run with --update to record it",
+                path.display()
+            ));
+        }
+
+        let result = CaseResult {
+            name: case.name.clone(),
+            passed: failures.is_empty(),
+            failures,
+            duration_ms: started.elapsed().as_millis() as u64,
+            transcript,
+        };
+        on_case(&result);
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// Copy the contents of `from` into the existing directory `to`
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry.with_context(|| format!("Failed to read workspace {}", from.display()))?;
+        let target = to.join(entry.path().strip_prefix(from)?);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)
+                .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockBackend;
+
+    const SUITE: &str = r#"
+cases:
+  - name: reads the readme
+    prompt: What is this project?
+    expect:
+      tool_calls:
+        - name: read_file
+          args: { path: README.md }
+      forbidden_tools: [write_file]
+      contains: [Arula]
+      matches: "(?i)terminal ai"
+    golden: golden/readme.txt
+  - name: follow-up
+    prompt: Thanks!
+    expect:
+      tool_calls:
+        - name: bash
+      not_contains: [help]
+"#;
+
+    #[tokio::test]
+    async fn test_run_suite_against_mock() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut suite: EvalSuite = serde_yaml::from_str(SUITE).unwrap();
+        suite.dir = dir.path().to_path_buf();
+        let session = SessionConfig {
+            system_prompt: String::new(),
+            model: "mock".to_string(),
+            max_tokens: 100,
+            temperature: 0.0,
+            sampling: Default::default(),
+            ignore_budget: false,
+            budget_usage: None,
+        };
+        let mock = || MockBackend::parse(include_str!("../fixtures/mock_session.jsonl")).unwrap();
+
+        // The golden doesn't exist yet
+        let results = run_suite(&mock(), &suite, &session, None, false, |_| {})
+            .await
+            .unwrap();
+        assert!(results[0].failures[0].contains("--update"));
+        assert_eq!(results[1].failures.len(), 2);
+
+        run_suite(&mock(), &suite, &session, Some("readme"), true, |_| {})
+            .await
+            .unwrap();
+        let golden = std::fs::read_to_string(dir.path().join("golden/readme.txt")).unwrap();
+        assert!(golden.starts_with("[tool] read_file {\"path\":\"README.md\"}\nThis project is"));
+
+        let results = run_suite(&mock(), &suite, &session, Some("readme"), false, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].passed, "{:?}", results[0].failures);
+    }
+
+    /// Writes `out.txt` with the write_file tool and answers with its directory
+    #[derive(Clone)]
+    struct WritingBackend;
+
+    impl Backend for WritingBackend {
+        fn stream_session(
+            &self,
+            _prompt: String,
+            _history: Option<Vec<crate::api::api::ChatMessage>>,
+            _config: SessionConfig,
+        ) -> Result<std::pin::Pin<Box<dyn futures::Stream<Item = StreamEvent> + Send>>> {
+            Ok(Box::pin(futures::stream::once(async {
+                let mut registry = crate::api::agent::ToolRegistry::new();
+                registry.register(crate::tools::builtin::WriteFileTool::new());
+                let args = serde_json::json!({ "path": "out.txt", "content": "changed" });
+                registry.execute_tool("write_file", args).await;
+                StreamEvent::Text {
+                    text: sandbox::working_dir().display().to_string(),
+                }
+            })))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cases_run_in_a_copy_of_the_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("project")).unwrap();
+        std::fs::write(dir.path().join("project/out.txt"), "original").unwrap();
+        let mut suite: EvalSuite = serde_yaml::from_str(
+            "workspace: project\ncases:\n  - name: a\n    prompt: x\n  - name: b\n    prompt: y\n",
+        )
+        .unwrap();
+        suite.dir = dir.path().to_path_buf();
+
+        let session = SessionConfig::from_config(&Config::default());
+        let results = run_suite(&WritingBackend, &suite, &session, None, false, |_| {})
+            .await
+            .unwrap();
+        let workdirs: Vec<&str> = results.iter().map(|r| r.transcript.text.as_str()).collect();
+        assert_ne!(workdirs[0], workdirs[1]);
+        assert!(!workdirs[0].starts_with(&*dir.path().to_string_lossy()));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("project/out.txt")).unwrap(),
+            "original"
+        );
+    }
+
+    #[test]
+    fn test_golden_mismatch_diff() {
+        let transcript = Transcript {
+            rendered: "[tool] bash {}\nDone.\n".to_string(),
+            ..Default::default()
+        };
+        let failures = check(
+            &Expectations::default(),
+            &transcript,
+            Some("[tool] bash {}\nOK.\n"),
+        );
+        assert_eq!(
+            failures,
+            ["transcript differs from golden:\n- OK.\n+ Done."]
+        );
+    }
+}
@@ -5,9 +5,12 @@ pub mod api;
 pub mod app;
 pub mod async_optimizations;
 pub mod conversation_manager;
+pub mod eval;
 pub mod init;
+pub mod mock;
 pub mod prelude;
 pub mod profiling;
+pub mod server;
 pub mod session_manager;
 pub mod tools;
 pub mod utils;
@@ -19,9 +22,13 @@ use std::pin::Pin;
 pub use api::agent::{ContentBlock, ToolRegistry};
 pub use api::api::Usage;
 pub use app::App;
-pub use conversation_manager::{ConversationManager, ConversationMetadata, SavedConversation};
+pub use conversation_manager::{
+    ConversationList, ConversationManager, ConversationMetadata, ConversationPage, PartialSaver,
+    SavedConversation,
+};
+pub use mock::MockBackend;
 pub use prelude::*;
-pub use session_manager::{SessionManager, UiEvent};
+pub use session_manager::{PreparedBackend, SessionManager, UiEvent};
 pub use tools::*;
 pub use utils::*;
 
@@ -56,7 +63,22 @@ pub enum StreamEvent {
         question: String,
         options: Option<Vec<String>>,
     },
-    Finished,
+    /// A budget limit ended the run before the agent was done
+    BudgetExceeded(utils::budget::BudgetExceeded),
+    /// A failed request is about to be retried
+    Retrying(api::http_client::RetryNotice),
+    /// The tool calls of the agent loop's round `round` (from 1) are about to run
+    ToolRound {
+        round: u32,
+    },
+    /// Token usage of one model request (several per run when tools are used)
+    Usage(Usage),
+    /// Provider details about the response
+    Metadata(api::api::ResponseMetadata),
+    /// End of the run, with the reason the last model request stopped
+    Finished {
+        reason: api::api::FinishReason,
+    },
     Error(String),
 }
 
@@ -67,6 +89,39 @@ pub struct SessionConfig {
     pub model: String,
     pub max_tokens: u32,
     pub temperature: f32,
+    /// top_p, penalties, stop sequences and seed
+    #[serde(default)]
+    pub sampling: utils::config::SamplingParams,
+    /// Run without the configured budget limits ("continue anyway"); the
+    /// session's count starts over
+    #[serde(default)]
+    pub ignore_budget: bool,
+    /// Running totals of the session this run belongs to; without them the
+    /// limits apply to this run alone
+    #[serde(skip)]
+    pub budget_usage: Option<utils::budget::BudgetUsage>,
+}
+
+impl SessionConfig {
+    /// Defaults for frontends without their own generation settings, with
+    /// the active persona's model and temperature
+    pub fn from_config(config: &utils::config::Config) -> Self {
+        let persona = config.get_active_persona();
+        let model = persona
+            .as_ref()
+            .and_then(|p| p.model.clone())
+            .filter(|m| !m.trim().is_empty())
+            .unwrap_or_else(|| config.get_model());
+        Self {
+            system_prompt: String::new(),
+            model,
+            max_tokens: 4096,
+            temperature: persona.and_then(|p| p.temperature).unwrap_or(0.7),
+            sampling: config.get_sampling(),
+            ignore_budget: false,
+            budget_usage: None,
+        }
+    }
 }
 
 /// Backend trait for pluggable providers.
@@ -119,15 +174,36 @@ pub struct AgentBackend {
 
 impl AgentBackend {
     pub fn new(config: &utils::config::Config, system_prompt: String) -> anyhow::Result<Self> {
+        Self::new_with_project(config, system_prompt, None)
+    }
+
+    /// Create a backend with a per-project overlay applied (model and tool whitelist).
+    /// The active persona's model wins over the project's.
+    pub fn new_with_project(
+        config: &utils::config::Config,
+        system_prompt: String,
+        project: Option<&utils::project_config::ProjectConfig>,
+    ) -> anyhow::Result<Self> {
+        let effective_config = project.map(|p| p.overlay(config));
+        let config = effective_config.as_ref().unwrap_or(config);
+        let persona_config = config.get_active_persona().map(|p| p.overlay(config));
+        let config = persona_config.as_ref().unwrap_or(config);
+
         let agent_options = api::agent::AgentOptionsBuilder::new()
             .system_prompt(&system_prompt)
             .model(&config.get_model())
             .auto_execute_tools(true)
             .max_tool_iterations(1000)
+            .budget(config.get_budget())
+            .tool_result_max_tokens(config.get_tool_result_max_tokens())
+            .sampling(config.get_sampling())
             .debug(utils::debug::is_debug_enabled())
             .build();
 
-        let tool_registry = tools::tools::create_basic_tool_registry();
+        let mut tool_registry = tools::tools::create_basic_tool_registry();
+        if let Some(project) = project {
+            tool_registry.retain(|name| project.is_tool_allowed(name));
+        }
 
         let client = api::agent_client::AgentClient::new_with_registry(
             config.active_provider.clone(),
@@ -183,13 +259,53 @@ impl Backend for AgentBackend {
         history: Option<Vec<api::api::ChatMessage>>,
         config: SessionConfig,
     ) -> anyhow::Result<Pin<Box<dyn Stream<Item = StreamEvent> + Send>>> {
-        let client = self.client.clone();
+        let mut client = self.client.clone();
+        client.set_sampling(config.temperature, config.sampling.clone());
+        client.set_max_tokens(config.max_tokens);
+        let budget_usage = config.budget_usage.clone().unwrap_or_default();
+        if config.ignore_budget {
+            client.set_budget(utils::config::BudgetLimits::default());
+            budget_usage.reset();
+        }
+        client.set_budget_usage(budget_usage);
         let model = config.model.clone();
         let prompt = prompt.clone();
         let stream = async_stream::stream! {
             use futures::StreamExt;
             yield StreamEvent::Start { model: model.clone() };
 
+            // A pasted link is fetched up front and sent along with the prompt;
+            // the cache key covers the page as fetched
+            let mut prompt = prompt;
+            let mut history = history;
+            tools::builtin::fetch_url::expand_pasted_link(&mut prompt, history.as_mut()).await;
+
+            let cache_key = utils::response_cache::is_enabled().then(|| {
+                utils::response_cache::cache_key(
+                    &model,
+                    client.system_prompt(),
+                    config.temperature,
+                    &config.sampling,
+                    history.as_deref(),
+                    &prompt,
+                )
+            });
+            let cache = utils::response_cache::ResponseCache::default_cache();
+            if let Some(hit) = cache_key.as_deref().and_then(|key| cache.get(key)) {
+                tracing::info!(key = %hit.key, "Answered from response cache");
+                yield StreamEvent::Metadata(api::api::ResponseMetadata {
+                    provider: Some("cache".to_string()),
+                    model: Some(hit.model),
+                    ..Default::default()
+                });
+                yield StreamEvent::Text { text: hit.text };
+                yield StreamEvent::Finished { reason: api::api::FinishReason::Stop };
+                return;
+            }
+            // Only plain answers are cached: tool runs have side effects
+            let mut answer = cache_key.as_ref().map(|_| String::new());
+
+
             let result = if client.is_streaming_enabled() {
                 client.query_streaming(&prompt, history).await
             } else {
@@ -198,6 +314,7 @@ impl Backend for AgentBackend {
 
             match result {
                 Ok(mut s) => {
+                    let mut reason = api::api::FinishReason::default();
                     while let Some(block) = s.next().await {
                         let ev = match block {
                             ContentBlock::Text { text } => StreamEvent::Text { text },
@@ -206,11 +323,38 @@ impl Backend for AgentBackend {
                             ContentBlock::ToolResult { tool_call_id, result } => StreamEvent::ToolResult { tool_call_id, result },
                             ContentBlock::BashOutputLine { tool_call_id, line, is_stderr } => StreamEvent::BashOutputLine { tool_call_id, line, is_stderr },
                             ContentBlock::AskQuestion { tool_call_id, question, options } => StreamEvent::AskQuestion { tool_call_id, question, options },
+                            ContentBlock::BudgetExceeded { exceeded } => StreamEvent::BudgetExceeded(exceeded),
+                            ContentBlock::Retrying { notice } => StreamEvent::Retrying(notice),
+                            ContentBlock::ToolRound { round } => StreamEvent::ToolRound { round },
+                            ContentBlock::Usage { usage } => StreamEvent::Usage(usage),
+                            ContentBlock::Metadata { metadata } => StreamEvent::Metadata(metadata),
+                            // Each model request reports one; the run ends with the last
+                            ContentBlock::Finish { reason: last } => {
+                                reason = last;
+                                continue;
+                            }
                             ContentBlock::Error { error } => StreamEvent::Error(error),
                         };
+                        match &ev {
+                            StreamEvent::Text { text } => {
+                                if let Some(answer) = answer.as_mut() {
+                                    answer.push_str(text);
+                                }
+                            }
+                            StreamEvent::Reasoning { .. } | StreamEvent::Usage(_) | StreamEvent::Metadata(_) => {}
+                            _ => answer = None,
+                        }
                         yield ev;
                     }
-                    yield StreamEvent::Finished;
+                    if let (Some(key), Some(text)) = (cache_key, answer) {
+                        if reason == api::api::FinishReason::Stop && !text.is_empty() {
+                            let entry = utils::response_cache::CachedResponse::new(key, &model, &prompt, text);
+                            if let Err(e) = cache.put(&entry) {
+                                tracing::warn!("Failed to cache response: {:#}", e);
+                            }
+                        }
+                    }
+                    yield StreamEvent::Finished { reason };
                 }
                 Err(err) => {
                     yield StreamEvent::Error(err.to_string());
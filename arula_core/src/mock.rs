@@ -0,0 +1,201 @@
+//! Scripted backend for tests and demos
+//!
+//! `MockBackend` implements `Backend` by replaying `StreamEvent`s instead of
+//! calling a provider, so UI, dispatcher and agent-loop code can be exercised
+//! without network access or API keys. Each call to `stream_session` replays
+//! the next scripted turn, wrapping around after the last one.
+//!
+//! Fixture files hold one JSON-serialized `StreamEvent` per line; a blank line
+//! starts the next turn:
+//!
+//! ```text
+//! {"Start": {"model": "mock"}}
+//! {"Text": {"text": "Hello!"}}
+//! {"Finished": {"reason": "stop"}}
+//! ```
+//!
+//! `arula_core/fixtures/mock_session.jsonl` scripts a tool-using answer
+//! followed by a plain one. To drive a frontend with it, hand the backend to
+//! `SessionManager::set_mock_backend` (the desktop's `Dispatcher` forwards it).
+
+use crate::api::api::ChatMessage;
+use crate::{Backend, SessionConfig, StreamEvent};
+use anyhow::{Context, Result};
+use futures::Stream;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Backend that replays scripted turns
+#[derive(Clone, Default)]
+pub struct MockBackend {
+    turns: Arc<Vec<Vec<StreamEvent>>>,
+    next_turn: Arc<AtomicUsize>,
+    /// Pause before each event, to mimic streaming in demos
+    delay: Duration,
+    /// Prompts received, in order
+    prompts: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockBackend {
+    /// A backend that replays the same events on every turn
+    pub fn new(events: Vec<StreamEvent>) -> Self {
+        Self::with_turns(vec![events])
+    }
+
+    /// A backend that replays `turns` in order
+    pub fn with_turns(turns: Vec<Vec<StreamEvent>>) -> Self {
+        Self {
+            turns: Arc::new(turns),
+            ..Default::default()
+        }
+    }
+
+    /// Load turns from a fixture file
+    pub fn from_fixture(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read fixture {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid fixture {}", path.display()))
+    }
+
+    /// Parse fixture text
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut turns = Vec::new();
+        let mut current = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                if !current.is_empty() {
+                    turns.push(std::mem::take(&mut current));
+                }
+                continue;
+            }
+            let event: StreamEvent = serde_json::from_str(line)
+                .with_context(|| format!("Line {} is not a StreamEvent", number + 1))?;
+            current.push(event);
+        }
+        if !current.is_empty() {
+            turns.push(current);
+        }
+        Ok(Self::with_turns(turns))
+    }
+
+    /// Wait `delay` before each event
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Prompts the backend has been asked to answer
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+}
+
+impl Backend for MockBackend {
+    fn stream_session(
+        &self,
+        prompt: String,
+        _history: Option<Vec<ChatMessage>>,
+        _config: SessionConfig,
+    ) -> Result<Pin<Box<dyn Stream<Item = StreamEvent> + Send>>> {
+        if let Ok(mut prompts) = self.prompts.lock() {
+            prompts.push(prompt);
+        }
+        let events = match self.turns.len() {
+            0 => vec![StreamEvent::Error(
+                "MockBackend has no scripted turns".to_string(),
+            )],
+            len => self.turns[self.next_turn.fetch_add(1, Ordering::Relaxed) % len].clone(),
+        };
+        let delay = self.delay;
+        let stream = async_stream::stream! {
+            for event in events {
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+                yield event;
+            }
+        };
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SessionRunner;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_replays_fixture_turns() {
+        let backend = MockBackend::parse(include_str!("../fixtures/mock_session.jsonl")).unwrap();
+        let runner = SessionRunner::new(backend.clone());
+        let config = SessionConfig {
+            system_prompt: String::new(),
+            model: "mock".to_string(),
+            max_tokens: 100,
+            temperature: 0.0,
+            sampling: Default::default(),
+            ignore_budget: false,
+            budget_usage: None,
+        };
+
+        let first: Vec<StreamEvent> = runner
+            .stream_session("What is this project?".into(), None, config.clone())
+            .unwrap()
+            .collect()
+            .await;
+        assert!(first
+            .iter()
+            .any(|e| matches!(e, StreamEvent::ToolCall { name, .. } if name == "read_file")));
+
+        let second: Vec<StreamEvent> = runner
+            .stream_session("Thanks".into(), None, config.clone())
+            .unwrap()
+            .collect()
+            .await;
+        assert!(matches!(&second[1], StreamEvent::Text { text } if text.starts_with("Happy")));
+
+        // Wraps around to the first turn
+        let third = runner.stream_session("Again".into(), None, config).unwrap();
+        assert_eq!(third.collect::<Vec<_>>().await.len(), first.len());
+        assert_eq!(
+            backend.prompts(),
+            ["What is this project?", "Thanks", "Again"]
+        );
+    }
+
+    #[test]
+    fn test_session_manager_streams_from_mock() {
+        let app_config = crate::utils::config::Config::default();
+        let mut manager = crate::SessionManager::new(&app_config).unwrap();
+        let backend = MockBackend::new(vec![
+            StreamEvent::Text { text: "Scripted".into() },
+            StreamEvent::Finished { reason: Default::default() },
+        ]);
+        manager.set_mock_backend(Some(backend.clone()));
+        let mut events = manager.subscribe();
+
+        let session = uuid::Uuid::new_v4();
+        let config = SessionConfig::from_config(&app_config);
+        manager
+            .start_stream(session, "Hi there".into(), None, config)
+            .unwrap();
+
+        let mut text = String::new();
+        loop {
+            match events.blocking_recv().unwrap() {
+                crate::UiEvent::Token(id, token, _) if id == session => text.push_str(&token),
+                crate::UiEvent::StreamFinished(id) if id == session => break,
+                crate::UiEvent::StreamErrored(_, err) => panic!("{}", err),
+                _ => {}
+            }
+        }
+        assert_eq!(text, "Scripted");
+        assert_eq!(backend.prompts().len(), 1);
+    }
+}
@@ -31,8 +31,9 @@ pub use crate::api::agent::{ContentBlock, Tool, ToolResult, ToolSchema, ToolSche
 
 // Project context
 pub use crate::utils::project_context::{
-    detect_project, generate_auto_manifest, is_ai_enhanced, manifest_exists,
-    DetectedProject, ProjectType, MANIFEST_MARKER_AI, MANIFEST_MARKER_AUTO,
+    detect_project, generate_auto_manifest, is_ai_enhanced, manifest_exists, refresh_manifest,
+    DetectedProject, ManifestWatcher, ProjectType, MANIFEST_MARKER_AI, MANIFEST_MARKER_AUTO,
+    MANIFEST_MARKER_AUTO_END,
 };
 
 // Commonly used external crates
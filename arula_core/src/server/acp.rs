@@ -0,0 +1,525 @@
+//! Agent Client Protocol server (`arula acp`)
+//!
+//! Lets editors with an ACP agent panel (Zed, Neovim, VS Code) run Arula as
+//! their agent. The editor starts `arula acp` and exchanges newline-delimited
+//! JSON-RPC 2.0 messages over stdin/stdout:
+//!
+//! - `initialize`, `authenticate` (no auth methods are offered)
+//! - `session/new` - builds a backend for the given `cwd`, which the
+//!   session's tools and sandbox then use instead of the process directory
+//! - `session/prompt` - runs one turn, streaming `session/update`
+//!   notifications for text, reasoning and tool calls
+//! - `session/cancel` - stops the running turn
+//!
+//! Arula's permission prompts (the file sandbox and `ask_question`) are sent
+//! to the editor as `session/request_permission`, so the editor's approval UI
+//! decides instead of a terminal prompt. Bash commands and writes inside the
+//! working directory are confirmed the same way. Nothing else may be written to
+//! stdout while the server runs; logs go to `~/.arula/logs`.
+
+use crate::api::api::{ChatMessage, FinishReason};
+use crate::tools::builtin::question::{Answer, Question, QUESTION_HANDLER};
+use crate::tools::sandbox;
+use crate::utils::budget::BudgetKind;
+use crate::utils::config::Config;
+use crate::utils::snapshots;
+use crate::{AgentBackend, Backend, SessionConfig, SessionManager, StreamEvent};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+
+/// ACP version implemented here
+pub const ACP_PROTOCOL_VERSION: u64 = 1;
+
+/// How often pending permission prompts are checked while a turn runs
+const QUESTION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: message.into(),
+        }
+    }
+
+    fn internal(message: impl ToString) -> Self {
+        Self {
+            code: INTERNAL_ERROR,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Outgoing half of the JSON-RPC connection
+struct Connection {
+    out: mpsc::UnboundedSender<Value>,
+    next_id: AtomicU64,
+    /// Our requests to the editor, waiting for a response
+    pending: Mutex<HashMap<u64, oneshot::Sender<Value>>>,
+}
+
+impl Connection {
+    fn notify(&self, method: &str, params: Value) {
+        let _ = self.out.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn respond(&self, id: Value, result: Result<Value, RpcError>) {
+        let message = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": e.code, "message": e.message },
+            }),
+        };
+        let _ = self.out.send(message);
+    }
+
+    /// Send a request to the editor; `None` if it answered with an error or went away
+    async fn request(&self, method: &str, params: Value) -> Option<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().ok()?.insert(id, tx);
+        let _ = self.out.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        rx.await.ok()
+    }
+
+    fn resolve(&self, message: &Value) {
+        let Some(id) = message.get("id").and_then(Value::as_u64) else {
+            return;
+        };
+        let Some(tx) = self.pending.lock().ok().and_then(|mut p| p.remove(&id)) else {
+            return;
+        };
+        if let Some(result) = message.get("result") {
+            let _ = tx.send(result.clone());
+        }
+    }
+}
+
+struct AcpSession {
+    backend: AgentBackend,
+    history: Vec<ChatMessage>,
+    /// Working directory the editor opened the session in
+    cwd: PathBuf,
+}
+
+struct Agent {
+    config: Config,
+    conn: Connection,
+    sessions: Mutex<HashMap<String, Arc<tokio::sync::Mutex<AcpSession>>>>,
+    /// Cancellation for the turn running in each session
+    running: Mutex<HashMap<String, CancellationToken>>,
+    /// Session of the most recent turn, which permission prompts are shown for
+    active_session: Mutex<Option<String>>,
+}
+
+/// Serve ACP on stdin/stdout until the editor closes the connection
+pub async fn serve_acp(config: Config) -> anyhow::Result<()> {
+    sandbox::set_interactive(true);
+    sandbox::set_confirm_actions(true);
+
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<Value>();
+    tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(message) = out_rx.recv().await {
+            let line = format!("{}\n", message);
+            if stdout.write_all(line.as_bytes()).await.is_err() || stdout.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let agent = Arc::new(Agent {
+        config,
+        conn: Connection {
+            out: out_tx,
+            next_id: AtomicU64::new(0),
+            pending: Mutex::new(HashMap::new()),
+        },
+        sessions: Mutex::new(HashMap::new()),
+        running: Mutex::new(HashMap::new()),
+        active_session: Mutex::new(None),
+    });
+    tokio::spawn(forward_questions(agent.clone()));
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Value>(&line) {
+            Ok(message) => agent.clone().dispatch(message),
+            Err(e) => agent.conn.respond(
+                Value::Null,
+                Err(RpcError {
+                    code: -32700,
+                    message: format!("Parse error: {}", e),
+                }),
+            ),
+        }
+    }
+    Ok(())
+}
+
+impl Agent {
+    fn dispatch(self: Arc<Self>, message: Value) {
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            // A response to one of our requests
+            self.conn.resolve(&message);
+            return;
+        };
+        let id = message.get("id").cloned();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": ACP_PROTOCOL_VERSION,
+                "agentCapabilities": {
+                    "loadSession": false,
+                    "promptCapabilities": { "image": false, "audio": false, "embeddedContext": true },
+                },
+                "authMethods": [],
+            })),
+            "authenticate" => Ok(json!({})),
+            "session/new" => self.new_session(&params),
+            "session/prompt" => {
+                let Some(id) = id else {
+                    return;
+                };
+                tokio::spawn(async move {
+                    let result = self.prompt(&params).await;
+                    self.conn.respond(id, result);
+                });
+                return;
+            }
+            "session/cancel" => {
+                if let Some(session_id) = params.get("sessionId").and_then(Value::as_str) {
+                    if let Some(token) = self.running.lock().ok().and_then(|r| r.get(session_id).cloned()) {
+                        token.cancel();
+                    }
+                }
+                return;
+            }
+            other => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Method not found: {}", other),
+            }),
+        };
+
+        // Notifications get no response
+        if let Some(id) = id {
+            self.conn.respond(id, result);
+        }
+    }
+
+    fn new_session(&self, params: &Value) -> Result<Value, RpcError> {
+        let cwd = match params.get("cwd").and_then(Value::as_str) {
+            Some(cwd) => PathBuf::from(cwd)
+                .canonicalize()
+                .ok()
+                .filter(|dir| dir.is_dir())
+                .ok_or_else(|| RpcError::invalid_params(format!("Cannot use cwd '{}'", cwd)))?,
+            None => sandbox::working_dir(),
+        };
+        let (backend, _, _) = sandbox::with_workdir_sync(cwd.clone(), || {
+            SessionManager::build_backend(&self.config)
+        })
+        .map_err(RpcError::internal)?;
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.insert(
+                session_id.clone(),
+                Arc::new(tokio::sync::Mutex::new(AcpSession {
+                    backend,
+                    history: Vec::new(),
+                    cwd,
+                })),
+            );
+        }
+        Ok(json!({ "sessionId": session_id }))
+    }
+
+    async fn prompt(&self, params: &Value) -> Result<Value, RpcError> {
+        let session_id = params
+            .get("sessionId")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::invalid_params("sessionId is required"))?
+            .to_string();
+        let session = self
+            .sessions
+            .lock()
+            .ok()
+            .and_then(|s| s.get(&session_id).cloned())
+            .ok_or_else(|| RpcError::invalid_params(format!("Unknown session {}", session_id)))?;
+        let prompt = prompt_text(params.get("prompt").unwrap_or(&Value::Null));
+
+        // One turn per session at a time
+        let mut session = session.lock().await;
+        let cancel = CancellationToken::new();
+        if let Ok(mut running) = self.running.lock() {
+            running.insert(session_id.clone(), cancel.clone());
+        }
+        if let Ok(mut active) = self.active_session.lock() {
+            *active = Some(session_id.clone());
+        }
+        snapshots::begin_turn();
+
+        let history = (!session.history.is_empty()).then(|| session.history.clone());
+        let result = sandbox::with_workdir(
+            session.cwd.clone(),
+            self.run_turn(&session_id, &session.backend, prompt.clone(), history, &cancel),
+        )
+        .await;
+
+        if let Ok(mut running) = self.running.lock() {
+            running.remove(&session_id);
+        }
+        let (stop_reason, reply) = result?;
+        session.history.push(text_message("user", prompt));
+        if !reply.is_empty() {
+            session.history.push(text_message("assistant", reply));
+        }
+        Ok(json!({ "stopReason": stop_reason }))
+    }
+
+    /// Stream one turn to the editor, returning the stop reason and the reply text
+    async fn run_turn(
+        &self,
+        session_id: &str,
+        backend: &AgentBackend,
+        prompt: String,
+        history: Option<Vec<ChatMessage>>,
+        cancel: &CancellationToken,
+    ) -> Result<(&'static str, String), RpcError> {
+        let mut events = backend
+            .stream_session(prompt, history, SessionConfig::from_config(&self.config))
+            .map_err(RpcError::internal)?;
+
+        let mut reply = String::new();
+        let mut budget_stop = None;
+        loop {
+            let event = tokio::select! {
+                _ = cancel.cancelled() => return Ok(("cancelled", reply)),
+                event = events.next() => event,
+            };
+            let update = match event {
+                Some(StreamEvent::Text { text }) => {
+                    reply.push_str(&text);
+                    json!({ "sessionUpdate": "agent_message_chunk", "content": { "type": "text", "text": text } })
+                }
+                Some(StreamEvent::Reasoning { text }) => {
+                    json!({ "sessionUpdate": "agent_thought_chunk", "content": { "type": "text", "text": text } })
+                }
+                Some(StreamEvent::ToolCall { id, name, arguments }) => json!({
+                    "sessionUpdate": "tool_call",
+                    "toolCallId": id,
+                    "title": name,
+                    "kind": tool_kind(&name),
+                    "status": "in_progress",
+                    "rawInput": serde_json::from_str::<Value>(&arguments).unwrap_or(Value::String(arguments)),
+                }),
+                Some(StreamEvent::ToolResult { tool_call_id, result }) => json!({
+                    "sessionUpdate": "tool_call_update",
+                    "toolCallId": tool_call_id,
+                    "status": if result.success { "completed" } else { "failed" },
+                    "rawOutput": result.data,
+                }),
+                Some(StreamEvent::BudgetExceeded(exceeded)) => {
+                    budget_stop = Some(match exceeded.kind {
+                        BudgetKind::ToolIterations => "max_turn_requests",
+                        BudgetKind::Tokens | BudgetKind::Cost => "max_tokens",
+                    });
+                    continue;
+                }
+                Some(StreamEvent::Error(e)) => return Err(RpcError::internal(e)),
+                Some(StreamEvent::Finished { reason }) => {
+                    let stop = match reason {
+                        FinishReason::Length => "max_tokens",
+                        FinishReason::ContentFilter => "refusal",
+                        _ => "end_turn",
+                    };
+                    return Ok((budget_stop.unwrap_or(stop), reply));
+                }
+                None => return Ok((budget_stop.unwrap_or("end_turn"), reply)),
+                Some(_) => continue,
+            };
+            self.conn.notify(
+                "session/update",
+                json!({ "sessionId": session_id, "update": update }),
+            );
+        }
+    }
+}
+
+/// Relay pending sandbox prompts and `ask_question` batches to the editor
+async fn forward_questions(agent: Arc<Agent>) {
+    let mut forwarded = HashSet::new();
+    loop {
+        tokio::time::sleep(QUESTION_POLL_INTERVAL).await;
+        let pending = QUESTION_HANDLER.get_pending_questions();
+        // Answered batches never come back, so only the pending ones need remembering
+        forwarded.retain(|id| pending.iter().any(|(batch_id, _)| batch_id == id));
+        for (batch_id, questions) in pending {
+            if forwarded.insert(batch_id.clone()) {
+                let agent = agent.clone();
+                tokio::spawn(async move {
+                    let answers = ask_editor(&agent, &batch_id, questions).await;
+                    let _ = QUESTION_HANDLER.answer(&batch_id, answers);
+                });
+            }
+        }
+    }
+}
+
+/// Ask each question through `session/request_permission`
+async fn ask_editor(agent: &Agent, batch_id: &str, questions: Vec<Question>) -> Vec<Answer> {
+    let session_id = agent.active_session.lock().ok().and_then(|s| s.clone());
+    let mut answers = Vec::new();
+
+    for question in questions {
+        let choices = question.options.clone().unwrap_or_default();
+        let answer = match (&session_id, choices.is_empty()) {
+            (Some(session_id), false) => {
+                let response = agent
+                    .conn
+                    .request(
+                        "session/request_permission",
+                        json!({
+                            "sessionId": session_id,
+                            "toolCall": {
+                                "toolCallId": format!("{}-{}", batch_id, question.id),
+                                "title": question.question,
+                                "kind": "other",
+                                "status": "pending",
+                            },
+                            "options": permission_options(&choices),
+                        }),
+                    )
+                    .await;
+                response
+                    .as_ref()
+                    .and_then(|r| r.get("outcome"))
+                    .filter(|o| o.get("outcome").and_then(Value::as_str) == Some("selected"))
+                    .and_then(|o| o.get("optionId").and_then(Value::as_str))
+                    .map(str::to_string)
+                    .unwrap_or_default()
+            }
+            // ACP has no free-form questions; an empty answer denies sandbox access
+            _ => String::new(),
+        };
+        answers.push(Answer {
+            id: question.id,
+            answer,
+            answer_type: "selected".to_string(),
+        });
+    }
+    answers
+}
+
+/// Permission options for a question's choices, with ACP kinds for the sandbox prompt
+fn permission_options(choices: &[String]) -> Vec<Value> {
+    choices
+        .iter()
+        .map(|choice| {
+            let kind = match choice.as_str() {
+                sandbox::ALLOW_SESSION => "allow_always",
+                sandbox::DENY => "reject_once",
+                _ => "allow_once",
+            };
+            json!({ "optionId": choice, "name": choice, "kind": kind })
+        })
+        .collect()
+}
+
+/// ACP tool kind for an Arula tool name
+fn tool_kind(name: &str) -> &'static str {
+    match name {
+        "read_file" | "list_directory" | "code_outline" | "transcribe_audio" => "read",
+        "write_file" | "edit_file" => "edit",
+        "search_files" | "find_files" => "search",
+        "execute_bash" => "execute",
+        "web_search" | "fetch_url" => "fetch",
+        _ => "other",
+    }
+}
+
+/// Text of an ACP prompt: text blocks plus embedded resources and links
+fn prompt_text(prompt: &Value) -> String {
+    let Some(blocks) = prompt.as_array() else {
+        return prompt.as_str().unwrap_or_default().to_string();
+    };
+    blocks
+        .iter()
+        .filter_map(|block| match block.get("type").and_then(Value::as_str)? {
+            "text" => block.get("text").and_then(Value::as_str).map(str::to_string),
+            "resource" => {
+                let resource = block.get("resource")?;
+                let uri = resource.get("uri").and_then(Value::as_str).unwrap_or_default();
+                let text = resource.get("text").and_then(Value::as_str)?;
+                Some(format!("<context uri=\"{}\">\n{}\n</context>", uri, text))
+            }
+            "resource_link" => block
+                .get("uri")
+                .and_then(Value::as_str)
+                .map(|uri| format!("[{}]", uri)),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn text_message(role: &str, content: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: Some(content),
+        tool_calls: None,
+        tool_call_id: None,
+        tool_name: None,
+        images: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_text_and_permission_options() {
+        let prompt = json!([
+            { "type": "text", "text": "Explain this" },
+            { "type": "resource", "resource": { "uri": "file:///a.rs", "text": "fn a() {}" } },
+            { "type": "image", "data": "..." },
+        ]);
+        assert_eq!(
+            prompt_text(&prompt),
+            "Explain this\n\n<context uri=\"file:///a.rs\">\nfn a() {}\n</context>"
+        );
+
+        let options = permission_options(&[
+            sandbox::ALLOW_ONCE.to_string(),
+            sandbox::ALLOW_SESSION.to_string(),
+            sandbox::DENY.to_string(),
+        ]);
+        let kinds: Vec<_> = options.iter().map(|o| o["kind"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["allow_once", "allow_always", "reject_once"]);
+    }
+}
@@ -0,0 +1,151 @@
+//! D-Bus service for a running Arula instance (Linux)
+//!
+//! Claims `dev.arula.Arula` on the session bus and serves the
+//! `dev.arula.Arula1` interface at `/dev/arula/Arula`, so desktop automation,
+//! GNOME extensions and scripts can talk to the app:
+//!
+//! ```text
+//! busctl --user call dev.arula.Arula /dev/arula/Arula dev.arula.Arula1 SendPrompt s "Summarize README.md"
+//! busctl --user call dev.arula.Arula /dev/arula/Arula dev.arula.Arula1 LastAnswer
+//! ```
+//!
+//! Prompts are handed to the owner as `RemoteCommand`s, like WebSocket
+//! clients' prompts, so they appear in the UI. All D-Bus prompts go to one
+//! conversation until `NewSession` starts another.
+
+use crate::server::websocket::RemoteCommand;
+use crate::session_manager::UiEvent;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+/// Well-known bus name
+pub const DBUS_NAME: &str = "dev.arula.Arula";
+
+/// Object path of the service
+pub const DBUS_PATH: &str = "/dev/arula/Arula";
+
+#[derive(Debug, Default)]
+struct DbusState {
+    /// Conversation D-Bus prompts go to
+    session_id: Option<Uuid>,
+    /// Answers still streaming, by session
+    partial: HashMap<Uuid, String>,
+    last_answer: String,
+}
+
+impl DbusState {
+    fn apply(&mut self, event: &UiEvent) {
+        match event {
+            UiEvent::Token(id, text, false) => self.partial.entry(*id).or_default().push_str(text),
+            UiEvent::StreamFinished(id) => {
+                if let Some(answer) = self.partial.remove(id).filter(|a| !a.is_empty()) {
+                    self.last_answer = answer;
+                }
+            }
+            UiEvent::StreamErrored(id, _) => {
+                self.partial.remove(id);
+            }
+            _ => {}
+        }
+    }
+}
+
+struct ArulaService {
+    commands: mpsc::UnboundedSender<RemoteCommand>,
+    state: Arc<Mutex<DbusState>>,
+}
+
+#[zbus::interface(name = "dev.arula.Arula1")]
+impl ArulaService {
+    /// Start a new conversation for the following prompts; returns its id
+    fn new_session(&self) -> String {
+        let id = Uuid::new_v4();
+        if let Ok(mut state) = self.state.lock() {
+            state.session_id = Some(id);
+        }
+        id.to_string()
+    }
+
+    /// Send a prompt to the current D-Bus conversation; returns its id
+    fn send_prompt(&self, prompt: String) -> zbus::fdo::Result<String> {
+        let session_id = self
+            .state
+            .lock()
+            .map(|mut state| *state.session_id.get_or_insert_with(Uuid::new_v4))
+            .map_err(|_| zbus::fdo::Error::Failed("State unavailable".to_string()))?;
+        self.commands
+            .send(RemoteCommand::SendPrompt {
+                session_id,
+                prompt,
+                history: None,
+            })
+            .map_err(|_| zbus::fdo::Error::Failed("Arula is shutting down".to_string()))?;
+        Ok(session_id.to_string())
+    }
+
+    /// Text of the most recently finished answer in any conversation
+    fn last_answer(&self) -> String {
+        self.state
+            .lock()
+            .map(|state| state.last_answer.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Serve the interface until the command receiver is dropped
+pub(crate) async fn serve(
+    events: broadcast::Sender<UiEvent>,
+    commands: mpsc::UnboundedSender<RemoteCommand>,
+) -> anyhow::Result<()> {
+    let state = Arc::new(Mutex::new(DbusState::default()));
+    let closed = commands.clone();
+    let service = ArulaService {
+        commands,
+        state: state.clone(),
+    };
+    let _connection = zbus::connection::Builder::session()?
+        .name(DBUS_NAME)?
+        .serve_at(DBUS_PATH, service)?
+        .build()
+        .await?;
+    tracing::info!(name = DBUS_NAME, "D-Bus service registered");
+
+    let mut events = events.subscribe();
+    loop {
+        tokio::select! {
+            _ = closed.closed() => break,
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if let Ok(mut state) = state.lock() {
+                        state.apply(&event);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_answer_tracks_finished_streams() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut state = DbusState::default();
+        state.apply(&UiEvent::Token(a, "Hello ".to_string(), false));
+        state.apply(&UiEvent::Token(b, "Other".to_string(), false));
+        state.apply(&UiEvent::Token(a, "world".to_string(), false));
+        state.apply(&UiEvent::StreamFinished(a));
+        assert_eq!(state.last_answer, "Hello world");
+
+        state.apply(&UiEvent::StreamErrored(b, "boom".to_string()));
+        state.apply(&UiEvent::StreamFinished(b));
+        assert_eq!(state.last_answer, "Hello world");
+    }
+}
@@ -0,0 +1,16 @@
+//! Local servers that let other programs use Arula as their backend
+//!
+//! - `acp` - Agent Client Protocol over stdio for editor agent panels (`arula acp`)
+//! - `dbus` - `dev.arula.Arula1` session-bus interface (Linux)
+//! - `openai` - OpenAI-compatible `/v1/chat/completions` endpoint (`arula serve`)
+//! - `websocket` - `UiEvent` stream and prompt/stop commands for external frontends
+
+pub mod acp;
+#[cfg(target_os = "linux")]
+pub mod dbus;
+pub mod openai;
+pub mod websocket;
+
+pub use acp::serve_acp;
+pub use openai::{serve_openai, ServeOptions};
+pub use websocket::{serve_headless, RemoteCommand, WebSocketOptions};
@@ -0,0 +1,465 @@
+//! OpenAI-compatible chat completions server
+//!
+//! `arula serve` exposes `POST /v1/chat/completions` and `GET /v1/models` so
+//! editors and other tools that speak the OpenAI API can use Arula as a
+//! drop-in endpoint. Requests go to the configured provider with Arula's
+//! system prompt and tools; tool calls run on the server and only the final
+//! text is returned, either as one JSON response or as `chat.completion.chunk`
+//! server-sent events when `stream` is set.
+//!
+//! System messages from the client are appended to Arula's system prompt
+//! rather than replacing it. The server binds to localhost by default and can
+//! require a bearer token, since whoever reaches it can run Arula's tools.
+//! Requests must name localhost or the bound address in their `Host` header,
+//! so a web page can't reach a local server through DNS rebinding; only a
+//! server on all interfaces with a token accepts other host names.
+
+use crate::api::api::{ChatMessage, FinishReason, Usage};
+use crate::utils::config::{Config, SamplingParams};
+use crate::{AgentBackend, Backend, SessionConfig, SessionManager, StreamEvent};
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Settings for `serve_openai`
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    pub addr: SocketAddr,
+    /// Bearer token clients must send, if any
+    pub api_key: Option<String>,
+}
+
+struct ServerState {
+    backend: AgentBackend,
+    model: String,
+    /// Address the server is bound to
+    addr: SocketAddr,
+    api_key: Option<String>,
+    /// Configured sampling parameters, used where the request sets none
+    sampling: SamplingParams,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionRequest {
+    #[serde(default)]
+    model: Option<String>,
+    messages: Vec<RequestMessage>,
+    #[serde(default)]
+    stream: bool,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+    #[serde(default)]
+    top_p: Option<f32>,
+    #[serde(default)]
+    frequency_penalty: Option<f32>,
+    #[serde(default)]
+    presence_penalty: Option<f32>,
+    #[serde(default)]
+    stop: Option<StopSequences>,
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+/// `stop` may be a single string or a list
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StopSequences {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl ChatCompletionRequest {
+    /// The request's sampling parameters on top of the configured ones
+    fn sampling(&self, defaults: &SamplingParams) -> SamplingParams {
+        SamplingParams {
+            top_p: self.top_p.or(defaults.top_p),
+            frequency_penalty: self.frequency_penalty.or(defaults.frequency_penalty),
+            presence_penalty: self.presence_penalty.or(defaults.presence_penalty),
+            stop: match &self.stop {
+                Some(StopSequences::One(stop)) => vec![stop.clone()],
+                Some(StopSequences::Many(stop)) => stop.clone(),
+                None => defaults.stop.clone(),
+            },
+            seed: self.seed.or(defaults.seed),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestMessage {
+    role: String,
+    #[serde(default)]
+    content: Option<Value>,
+}
+
+/// Serve the OpenAI-compatible API until the process is stopped
+pub async fn serve_openai(config: &Config, options: ServeOptions) -> anyhow::Result<()> {
+    let (backend, _, _) = SessionManager::build_backend(config)?;
+    let state = Arc::new(ServerState {
+        backend,
+        model: config.get_model(),
+        addr: options.addr,
+        api_key: options.api_key,
+        sampling: config.get_sampling(),
+    });
+
+    let app = Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/models", get(list_models))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(options.addr).await?;
+    tracing::info!(addr = %options.addr, "OpenAI-compatible server listening");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn list_models(State(state): State<Arc<ServerState>>, headers: HeaderMap) -> Response {
+    if let Err(response) = authorize(&state, &headers) {
+        return response;
+    }
+    Json(json!({
+        "object": "list",
+        "data": [{
+            "id": state.model,
+            "object": "model",
+            "created": 0,
+            "owned_by": "arula",
+        }],
+    }))
+    .into_response()
+}
+
+async fn chat_completions(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Response {
+    if let Err(response) = authorize(&state, &headers) {
+        return response;
+    }
+
+    let system_prompt = state.backend.client().system_prompt();
+    let (prompt, history) = match split_messages(system_prompt, &request.messages) {
+        Ok(split) => split,
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, &message),
+    };
+
+    let sampling = request.sampling(&state.sampling);
+    // The configured model always answers; echo the requested name back
+    let model = request.model.unwrap_or_else(|| state.model.clone());
+    let session_config = SessionConfig {
+        system_prompt: system_prompt.to_string(),
+        model: state.model.clone(),
+        max_tokens: request.max_tokens.unwrap_or(4096),
+        temperature: request.temperature.unwrap_or(0.7),
+        sampling,
+        ignore_budget: false,
+        budget_usage: None,
+    };
+
+    let events = match state.backend.stream_session(prompt, history, session_config) {
+        Ok(events) => events,
+        Err(e) => return error_response(StatusCode::BAD_GATEWAY, &e.to_string()),
+    };
+
+    let id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+
+    if request.stream {
+        stream_response(events, id, created, model).into_response()
+    } else {
+        complete_response(events, id, created, model).await
+    }
+}
+
+/// Collect the whole run into one `chat.completion` object
+async fn complete_response(
+    mut events: impl futures::Stream<Item = StreamEvent> + Unpin,
+    id: String,
+    created: i64,
+    model: String,
+) -> Response {
+    let mut content = String::new();
+    let mut finish_reason = "stop";
+    let mut budget_exceeded = false;
+    let mut usage: Option<Usage> = None;
+    while let Some(event) = events.next().await {
+        match event {
+            StreamEvent::Text { text } => content.push_str(&text),
+            StreamEvent::BudgetExceeded(_) => budget_exceeded = true,
+            StreamEvent::Usage(u) => {
+                let total = usage.get_or_insert_with(Usage::default);
+                total.prompt_tokens += u.prompt_tokens;
+                total.completion_tokens += u.completion_tokens;
+                total.total_tokens += u.total_tokens;
+            }
+            StreamEvent::Error(e) => return error_response(StatusCode::BAD_GATEWAY, &e),
+            StreamEvent::Finished { reason } => {
+                finish_reason = openai_finish_reason(&reason, budget_exceeded);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    Json(json!({
+        "id": id,
+        "object": "chat.completion",
+        "created": created,
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": finish_reason,
+        }],
+        "usage": usage,
+    }))
+    .into_response()
+}
+
+/// Forward text as `chat.completion.chunk` events, ending with `[DONE]`
+fn stream_response(
+    events: impl futures::Stream<Item = StreamEvent> + Send + 'static,
+    id: String,
+    created: i64,
+    model: String,
+) -> Sse<impl futures::Stream<Item = Result<Event, Infallible>>> {
+    let chunk = move |delta: Value, finish_reason: Option<&str>| {
+        Event::default().data(
+            json!({
+                "id": id,
+                "object": "chat.completion.chunk",
+                "created": created,
+                "model": model,
+                "choices": [{
+                    "index": 0,
+                    "delta": delta,
+                    "finish_reason": finish_reason,
+                }],
+            })
+            .to_string(),
+        )
+    };
+
+    let stream = async_stream::stream! {
+        futures::pin_mut!(events);
+        yield Ok(chunk(json!({ "role": "assistant", "content": "" }), None));
+
+        let mut budget_exceeded = false;
+        while let Some(event) = events.next().await {
+            match event {
+                StreamEvent::Text { text } => {
+                    yield Ok(chunk(json!({ "content": text }), None));
+                }
+                StreamEvent::BudgetExceeded(_) => budget_exceeded = true,
+                StreamEvent::Error(e) => {
+                    yield Ok(Event::default().data(error_body(&e).to_string()));
+                    break;
+                }
+                StreamEvent::Finished { reason } => {
+                    let finish_reason = openai_finish_reason(&reason, budget_exceeded);
+                    yield Ok(chunk(json!({}), Some(finish_reason)));
+                    break;
+                }
+                _ => {}
+            }
+        }
+        yield Ok(Event::default().data("[DONE]"));
+    };
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// OpenAI's spelling of why a run ended. Tool calls run on the server, so the
+/// client only ever sees the final answer stop, get cut off or get filtered.
+fn openai_finish_reason(reason: &FinishReason, budget_exceeded: bool) -> &'static str {
+    match reason {
+        _ if budget_exceeded => "length",
+        FinishReason::Length => "length",
+        FinishReason::ContentFilter => "content_filter",
+        FinishReason::Stop | FinishReason::ToolCalls | FinishReason::Other(_) => "stop",
+    }
+}
+
+/// Turn OpenAI messages into Arula's prompt and history. The last message must
+/// come from the user; client system messages are added to `system_prompt`.
+fn split_messages(
+    system_prompt: &str,
+    messages: &[RequestMessage],
+) -> Result<(String, Option<Vec<ChatMessage>>), String> {
+    let Some((last, earlier)) = messages.split_last() else {
+        return Err("messages must not be empty".to_string());
+    };
+    if last.role != "user" {
+        return Err("the last message must have role \"user\"".to_string());
+    }
+
+    let client_system: Vec<String> = earlier
+        .iter()
+        .filter(|m| m.role == "system" || m.role == "developer")
+        .map(|m| message_text(m.content.as_ref()))
+        .collect();
+
+    let mut history = Vec::new();
+    if !client_system.is_empty() {
+        history.push(text_message(
+            "system",
+            format!("{}\n\n{}", system_prompt, client_system.join("\n\n")),
+        ));
+    }
+    // Client-side tool calls refer to tools Arula doesn't have, so only
+    // plain user and assistant turns are kept
+    history.extend(
+        earlier
+            .iter()
+            .filter(|m| m.role == "user" || m.role == "assistant")
+            .map(|m| text_message(&m.role, message_text(m.content.as_ref())))
+            .filter(|m| m.content.as_deref().is_some_and(|c| !c.is_empty())),
+    );
+
+    let prompt = message_text(last.content.as_ref());
+    Ok((prompt, (!history.is_empty()).then_some(history)))
+}
+
+/// Text of a message whose content is a string or a list of content parts
+fn message_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn text_message(role: &str, content: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: Some(content),
+        tool_calls: None,
+        tool_call_id: None,
+        tool_name: None,
+        images: Vec::new(),
+    }
+}
+
+fn authorize(state: &ServerState, headers: &HeaderMap) -> Result<(), Response> {
+    let host = headers.get(header::HOST).and_then(|v| v.to_str().ok());
+    if !host_allowed(host, state.addr, state.api_key.is_some()) {
+        return Err(error_response(StatusCode::FORBIDDEN, "Host not allowed"));
+    }
+    let Some(expected) = &state.api_key else {
+        return Ok(());
+    };
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if provided.is_some_and(|p| constant_time_eq(p.as_bytes(), expected.as_bytes())) {
+        Ok(())
+    } else {
+        Err(error_response(StatusCode::UNAUTHORIZED, "Invalid or missing API key"))
+    }
+}
+
+/// Whether a request with this `Host` header may reach a server bound to
+/// `addr`: localhost names and the bound address always, any name only when
+/// the server listens on all interfaces behind a token
+fn host_allowed(host: Option<&str>, addr: SocketAddr, has_token: bool) -> bool {
+    let Some(host) = host.and_then(|h| h.parse::<axum::http::uri::Authority>().ok()) else {
+        return false;
+    };
+    let name = host.host().trim_start_matches('[').trim_end_matches(']');
+    if name.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    match name.parse::<std::net::IpAddr>() {
+        Ok(ip) if ip.is_loopback() || ip == addr.ip() => true,
+        _ => addr.ip().is_unspecified() && has_token,
+    }
+}
+
+/// Compare without stopping at the first difference, so response times don't
+/// reveal how much of a guessed token was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn error_body(message: &str) -> Value {
+    json!({ "error": { "message": message, "type": "arula_error" } })
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(error_body(message))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: Value) -> RequestMessage {
+        RequestMessage {
+            role: role.to_string(),
+            content: Some(content),
+        }
+    }
+
+    #[test]
+    fn test_split_messages() {
+        let messages = vec![
+            message("system", json!("Answer in French.")),
+            message("user", json!("Hi")),
+            message("assistant", json!("Bonjour")),
+            message("user", json!([{ "type": "text", "text": "How are you?" }])),
+        ];
+        let (prompt, history) = split_messages("You are Arula.", &messages).unwrap();
+        let history = history.unwrap();
+        assert_eq!(prompt, "How are you?");
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].role, "system");
+        assert_eq!(
+            history[0].content.as_deref(),
+            Some("You are Arula.\n\nAnswer in French.")
+        );
+        assert_eq!(history[2].content.as_deref(), Some("Bonjour"));
+
+        assert!(split_messages("", &[message("assistant", json!("x"))]).is_err());
+        assert!(split_messages("", &[]).is_err());
+    }
+
+    #[test]
+    fn test_host_allowed() {
+        let local: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        assert!(host_allowed(Some("localhost:8080"), local, false));
+        assert!(host_allowed(Some("127.0.0.1:8080"), local, false));
+        assert!(host_allowed(Some("[::1]:8080"), local, false));
+        // DNS rebinding: the page's own name resolves to 127.0.0.1
+        assert!(!host_allowed(Some("attacker.example:8080"), local, false));
+        assert!(!host_allowed(None, local, false));
+
+        let lan: SocketAddr = "192.168.1.5:8080".parse().unwrap();
+        assert!(host_allowed(Some("192.168.1.5:8080"), lan, true));
+        assert!(!host_allowed(Some("arula.lan:8080"), lan, true));
+        let all: SocketAddr = "0.0.0.0:8080".parse().unwrap();
+        assert!(host_allowed(Some("arula.lan:8080"), all, true));
+        assert!(!host_allowed(Some("arula.lan:8080"), all, false));
+
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+    }
+}
@@ -0,0 +1,348 @@
+//! WebSocket event API for external frontends
+//!
+//! Serves a session manager's `UiEvent` stream on `ws://<addr>/ws` so web
+//! pages, terminal UIs or stream overlays can follow and drive the same
+//! sessions as the desktop app. Every frame is a JSON object tagged by `type`.
+//!
+//! Client to server:
+//!
+//! - `{"type": "subscribe", "session_id": null}` - receive events for every
+//!   session, or only for the given one (repeat to add more)
+//! - `{"type": "send_prompt", "prompt": "...", "session_id": null}` - start a
+//!   stream; a new session is created when `session_id` is omitted
+//! - `{"type": "stop_stream", "session_id": "..."}`
+//!
+//! Server to client: `accepted` (with the session id of a prompt), `event`
+//! (with the serialized `UiEvent`) and `error`.
+//!
+//! The server only listens on the address it is given (127.0.0.1 by default)
+//! and refuses to start on any other address without a token. Browsers let
+//! any page open a WebSocket to localhost, so without a token only
+//! connections from local origins are accepted.
+
+use crate::api::api::ChatMessage;
+use crate::session_manager::UiEvent;
+use crate::utils::config::Config;
+use crate::{SessionConfig, SessionManager};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+/// Port used when the config doesn't set one
+pub const DEFAULT_WEBSOCKET_PORT: u16 = 8765;
+
+/// Where to listen and which token to require
+#[derive(Debug, Clone)]
+pub struct WebSocketOptions {
+    pub addr: SocketAddr,
+    pub token: Option<String>,
+}
+
+impl WebSocketOptions {
+    /// Options from the `websocket` config section, if the server is enabled
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config.get_websocket().map(|ws| Self {
+            addr: SocketAddr::from(([127, 0, 0, 1], ws.port.unwrap_or(DEFAULT_WEBSOCKET_PORT))),
+            token: ws.token.clone(),
+        })
+    }
+}
+
+/// A request from a WebSocket or D-Bus client for the session manager's owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    SendPrompt {
+        session_id: Uuid,
+        prompt: String,
+        /// History supplied by the client; frontends with their own session
+        /// history may ignore it
+        history: Option<Vec<ChatMessage>>,
+    },
+    StopStream {
+        session_id: Uuid,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Subscribe {
+        #[serde(default)]
+        session_id: Option<Uuid>,
+    },
+    SendPrompt {
+        #[serde(default)]
+        session_id: Option<Uuid>,
+        prompt: String,
+        #[serde(default)]
+        history: Option<Vec<ChatMessage>>,
+    },
+    StopStream {
+        session_id: Uuid,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+    Accepted { session_id: Uuid },
+    Event { event: &'a UiEvent },
+    Error { message: String },
+}
+
+/// Which events a connection has asked for
+#[derive(Debug, Default)]
+enum Subscription {
+    #[default]
+    Nothing,
+    All,
+    Sessions(HashSet<Uuid>),
+}
+
+impl Subscription {
+    fn add(&mut self, session_id: Option<Uuid>) {
+        match (session_id, &mut *self) {
+            (None, _) => *self = Subscription::All,
+            (Some(_), Subscription::All) => {}
+            (Some(id), Subscription::Sessions(ids)) => {
+                ids.insert(id);
+            }
+            (Some(id), Subscription::Nothing) => *self = Subscription::Sessions(HashSet::from([id])),
+        }
+    }
+
+    fn matches(&self, event: &UiEvent) -> bool {
+        match self {
+            Subscription::Nothing => false,
+            Subscription::All => true,
+            Subscription::Sessions(ids) => event.session_id().is_some_and(|id| ids.contains(&id)),
+        }
+    }
+}
+
+struct ServerState {
+    token: Option<String>,
+    events: broadcast::Sender<UiEvent>,
+    commands: mpsc::UnboundedSender<RemoteCommand>,
+}
+
+/// Serve until the command receiver is dropped or the listener fails
+pub(crate) async fn serve(
+    listener: std::net::TcpListener,
+    options: WebSocketOptions,
+    events: broadcast::Sender<UiEvent>,
+    commands: mpsc::UnboundedSender<RemoteCommand>,
+) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    let closed = commands.clone();
+    let state = Arc::new(ServerState {
+        token: options.token,
+        events,
+        commands,
+    });
+    let app = Router::new().route("/ws", get(upgrade)).with_state(state);
+
+    tracing::info!(addr = %options.addr, "WebSocket event API listening");
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { closed.closed().await })
+        .await?;
+    Ok(())
+}
+
+/// Run the WebSocket API without a GUI: a dedicated session manager carries
+/// out client commands, using the history the client sends. Blocks until the
+/// server stops, so call it from a plain thread rather than an async task.
+pub fn serve_headless(config: &Config, options: WebSocketOptions) -> anyhow::Result<()> {
+    let mut manager = SessionManager::new(config)?;
+    let mut commands = manager.serve_websocket(options)?;
+
+    while let Some(command) = commands.blocking_recv() {
+        match command {
+            RemoteCommand::SendPrompt {
+                session_id,
+                prompt,
+                history,
+            } => {
+                let session_config = SessionConfig::from_config(config);
+                if let Err(e) = manager.start_stream(session_id, prompt, history, session_config) {
+                    tracing::error!(error = %e, %session_id, "Remote prompt failed to start");
+                }
+            }
+            RemoteCommand::StopStream { session_id } => manager.stop_stream(session_id),
+        }
+    }
+    Ok(())
+}
+
+async fn upgrade(
+    State(state): State<Arc<ServerState>>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let allowed = match &state.token {
+        Some(token) => query.get("token") == Some(token),
+        None => headers
+            .get(header::ORIGIN)
+            .and_then(|o| o.to_str().ok())
+            .is_none_or(is_local_origin),
+    };
+    if !allowed {
+        return (StatusCode::FORBIDDEN, "Missing token or non-local origin").into_response();
+    }
+    ws.on_upgrade(move |socket| handle_socket(socket, state))
+}
+
+/// Origins of pages served from this machine
+fn is_local_origin(origin: &str) -> bool {
+    if origin.starts_with("file://") {
+        return true;
+    }
+    let Some((_, rest)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match rest.strip_prefix("[::1]") {
+        Some(_) => "::1",
+        None => rest.split([':', '/']).next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+async fn handle_socket(socket: WebSocket, state: Arc<ServerState>) {
+    let (mut sink, mut stream) = socket.split();
+    let mut events = state.events.subscribe();
+    let mut subscription = Subscription::default();
+
+    loop {
+        let reply = tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    handle_client_message(&state, &mut subscription, text.as_str())
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => None,
+            },
+            event = events.recv() => match event {
+                Ok(event) if subscription.matches(&event) => {
+                    serde_json::to_string(&ServerMessage::Event { event: &event }).ok()
+                }
+                Ok(_) => None,
+                Err(RecvError::Lagged(missed)) => error_frame(format!("Missed {} events", missed)),
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        if let Some(reply) = reply {
+            if sink.send(Message::Text(reply.into())).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// Apply one client frame, returning the reply to send back
+fn handle_client_message(
+    state: &ServerState,
+    subscription: &mut Subscription,
+    text: &str,
+) -> Option<String> {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => return error_frame(format!("Invalid message: {}", e)),
+    };
+
+    let command = match message {
+        ClientMessage::Subscribe { session_id } => {
+            subscription.add(session_id);
+            return None;
+        }
+        ClientMessage::SendPrompt {
+            session_id,
+            prompt,
+            history,
+        } => {
+            let session_id = session_id.unwrap_or_else(Uuid::new_v4);
+            // Follow the session the client just started
+            subscription.add(Some(session_id));
+            RemoteCommand::SendPrompt {
+                session_id,
+                prompt,
+                history,
+            }
+        }
+        ClientMessage::StopStream { session_id } => RemoteCommand::StopStream { session_id },
+    };
+
+    let session_id = match &command {
+        RemoteCommand::SendPrompt { session_id, .. } | RemoteCommand::StopStream { session_id } => {
+            *session_id
+        }
+    };
+    if state.commands.send(command).is_err() {
+        return error_frame("Arula is shutting down".to_string());
+    }
+    serde_json::to_string(&ServerMessage::Accepted { session_id }).ok()
+}
+
+fn error_frame(message: String) -> Option<String> {
+    serde_json::to_string(&ServerMessage::Error { message }).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_messages() {
+        let (events, _) = broadcast::channel(4);
+        let (commands, mut received) = mpsc::unbounded_channel();
+        let state = ServerState {
+            token: None,
+            events,
+            commands,
+        };
+        let mut subscription = Subscription::default();
+
+        let reply = handle_client_message(
+            &state,
+            &mut subscription,
+            r#"{"type": "send_prompt", "prompt": "hi"}"#,
+        )
+        .unwrap();
+        let Some(RemoteCommand::SendPrompt { session_id, prompt, .. }) = received.try_recv().ok()
+        else {
+            panic!("expected a prompt");
+        };
+        assert_eq!(prompt, "hi");
+        assert!(reply.contains(&session_id.to_string()));
+        assert!(subscription.matches(&UiEvent::StreamStarted(session_id)));
+        assert!(!subscription.matches(&UiEvent::StreamStarted(Uuid::new_v4())));
+
+        handle_client_message(&state, &mut subscription, r#"{"type": "subscribe"}"#);
+        assert!(subscription.matches(&UiEvent::StreamStarted(Uuid::new_v4())));
+
+        let reply = handle_client_message(&state, &mut subscription, "{}").unwrap();
+        assert!(reply.contains("\"error\""));
+    }
+
+    #[test]
+    fn test_local_origins() {
+        assert!(is_local_origin("http://localhost:3000"));
+        assert!(is_local_origin("http://127.0.0.1"));
+        assert!(is_local_origin("file://"));
+        assert!(!is_local_origin("https://example.com"));
+        assert!(!is_local_origin("http://localhost.example.com"));
+    }
+}
@@ -3,19 +3,39 @@
 //! Handles AI streaming sessions, tool execution, and communication with UI layers.
 //! This module encapsulates all backend logic, keeping frontend layers pure.
 
-use crate::api::api::ChatMessage;
+use crate::api::api::{ChatMessage, FinishReason, Usage};
 use crate::api::models::{
-    AnthropicFetcher, ModelCacheManager, ModelFetcher, OllamaFetcher, OpenAIFetcher,
-    OpenRouterFetcher, ZaiFetcher,
+    AnthropicFetcher, DeepSeekFetcher, LocalServerFetcher, ModelCacheManager, ModelFetcher,
+    OllamaFetcher, OpenAIFetcher, OpenRouterFetcher, XaiFetcher, ZaiFetcher,
 };
 use crate::utils::config::Config;
-use crate::{AgentBackend, SessionConfig, SessionRunner, StreamEvent};
+use crate::utils::budget::BudgetUsage;
+use crate::utils::context_budget::{ContextBudget, ContextPriority, ContextReport, ContextSource};
+use crate::utils::git_state::{git_status_summary, recent_commits};
+use crate::utils::http_capture;
+use crate::utils::inspector::{self, InspectorSource};
+use crate::utils::knowledge::{self, KnowledgeBase};
+use crate::utils::project_config::ProjectConfig;
+use crate::utils::redact;
+use crate::utils::response_cache;
+use crate::utils::snapshots;
+use crate::utils::starters;
+use crate::utils::symbol_index;
+use crate::utils::telemetry;
+use crate::utils::usage_store::{self, UsageKind};
+use crate::utils::webhooks;
+use crate::utils::workspace::Workspace;
+use crate::server::websocket::{self, RemoteCommand, WebSocketOptions};
+use crate::tools::builtin::transcribe;
+use crate::tools::sandbox;
+use crate::{AgentBackend, Backend, MockBackend, SessionConfig, SessionRunner, StreamEvent};
 use futures::StreamExt;
+use tracing::Instrument;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use tokio::runtime::Runtime;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
@@ -49,10 +69,10 @@ fn read_base_system_prompt() -> Option<String> {
 
 /// Read PROJECT.manifest from current directory (project-specific context)
 fn read_project_manifest() -> Option<String> {
-    let manifest_path = std::path::Path::new("PROJECT.manifest");
+    let manifest_path = sandbox::absolute(std::path::Path::new("PROJECT.manifest"));
 
     if manifest_path.exists() {
-        std::fs::read_to_string(manifest_path).ok()
+        std::fs::read_to_string(&manifest_path).ok()
     } else {
         None
     }
@@ -93,27 +113,124 @@ You are ARULA, an advanced AI coding assistant designed for software engineering
 - Don't end responses with questions
 "#;
 
-/// Build system prompt with layered content
-/// Priority: Base System Prompt -> PROJECT.manifest
-fn build_system_prompt_with_manifest() -> String {
-    let mut prompt_parts = Vec::new();
+/// Maximum number of `git status` lines offered as context
+const GIT_STATUS_MAX_LINES: usize = 40;
+
+/// Number of recent commits offered as context
+const GIT_RECENT_COMMITS: usize = 5;
+
+/// Read the files pinned in `.arula/config.toml`, relative to the current directory
+fn read_pinned_files(project: Option<&ProjectConfig>) -> Vec<(String, String)> {
+    project
+        .and_then(|p| p.pinned_files.as_ref())
+        .map(|files| {
+            files
+                .iter()
+                .filter_map(|f| {
+                    let content = std::fs::read_to_string(sandbox::absolute(f.as_ref())).ok()?;
+                    Some((f.clone(), content))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Label of the budget share reserved for knowledge base excerpts
+const KNOWLEDGE_LABEL: &str = "Knowledge base excerpts";
+
+/// Build the system prompt from prioritized sources under the configured budget.
+///
+/// Order: Base System Prompt -> .arula/config.toml -> PROJECT.manifest -> pinned files
+/// -> git status -> knowledge base excerpts -> code outline. The base prompt and
+/// project instructions are always kept; everything else is trimmed lowest
+/// priority first. Excerpts are sent with each prompt, so only their share of
+/// the budget is reserved here, when `knowledge` has documents.
+fn build_system_prompt_with_context(
+    config: &Config,
+    knowledge: Option<&KnowledgeBase>,
+) -> (String, ContextReport) {
+    let project = ProjectConfig::load_from_current_dir();
+    let mut budget = ContextBudget::new(config.get_context_budget_tokens());
 
     // 1. Base system prompt (comprehensive or default)
-    if let Some(base_prompt) = read_base_system_prompt() {
-        prompt_parts.push(base_prompt);
-    } else {
-        prompt_parts.push(DEFAULT_BASE_PROMPT.to_string());
+    let base_prompt = read_base_system_prompt().unwrap_or_else(|| DEFAULT_BASE_PROMPT.to_string());
+    budget.add(ContextSource::new("System prompt", ContextPriority::Required, base_prompt));
+
+    // 1b. Instructions of the active persona
+    if let Some(section) = config.get_active_persona().and_then(|p| p.system_prompt_section()) {
+        budget.add(ContextSource::new("Persona", ContextPriority::Required, section));
     }
 
-    // 2. Add PROJECT.manifest from current directory (project context)
+    // 2. Project instructions from .arula/config.toml
+    if let Some(section) = project.as_ref().and_then(|p| p.system_prompt_section()) {
+        budget.add(ContextSource::new("Project instructions", ContextPriority::Required, section));
+    }
+
+    // 3. PROJECT.manifest from current directory (project context)
     if let Some(manifest) = read_project_manifest() {
-        prompt_parts.push(format!(
-            "\n====\n\n## PROJECT CONTEXT\n\nThe following PROJECT.manifest defines this project:\n\n{}", 
-            manifest
+        budget.add(ContextSource::new(
+            "PROJECT.manifest",
+            ContextPriority::High,
+            format!(
+                "\n====\n\n## PROJECT CONTEXT\n\nThe following PROJECT.manifest defines this project:\n\n{}",
+                manifest
+            ),
+        ));
+    }
+
+    // 4. Pinned files
+    for (path, content) in read_pinned_files(project.as_ref()) {
+        budget.add(ContextSource::new(
+            path.clone(),
+            ContextPriority::High,
+            format!("\n====\n\n## PINNED FILE: {}\n\n```\n{}\n```", path, content),
+        ));
+    }
+
+    // 5. Working tree status and recent commits
+    let cwd = Some(sandbox::working_dir()).filter(|_| config.get_git_context_enabled());
+    if let Some(cwd) = cwd {
+        if let Some(status) = git_status_summary(&cwd, GIT_STATUS_MAX_LINES) {
+            let commits = recent_commits(&cwd, GIT_RECENT_COMMITS)
+                .map(|log| format!("\n\nRecent commits:\n\n```\n{}\n```", log))
+                .unwrap_or_default();
+            budget.add(ContextSource::new(
+                "git status",
+                ContextPriority::Medium,
+                format!("\n====\n\n## GIT STATUS\n\n```\n{}\n```{}", status, commits),
+            ));
+        }
+    }
+
+    // 6. Room for the knowledge base excerpts picked for each prompt
+    if knowledge.is_some_and(KnowledgeBase::has_documents) {
+        let tokens = config
+            .get_knowledge()
+            .max_context_tokens
+            .unwrap_or(knowledge::DEFAULT_CONTEXT_TOKENS);
+        budget.add(ContextSource::reserved(KNOWLEDGE_LABEL, ContextPriority::Medium, tokens));
+    }
+
+    // 7. Symbol outline once the background index is ready
+    if let Some(outline) = symbol_index::current_index().and_then(|i| i.prompt_outline()) {
+        budget.add(ContextSource::new(
+            "Code outline",
+            ContextPriority::Low,
+            format!(
+                "\n====\n\n## CODE OUTLINE\n\nPublic symbols in this project (use code_outline for details):\n\n{}",
+                outline
+            ),
         ));
     }
 
-    prompt_parts.join("\n")
+    budget.assemble()
+}
+
+/// A web page a tool call read, cited under the answer it informed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    pub title: String,
+    pub url: String,
 }
 
 /// Events emitted by the session manager for UI updates.
@@ -136,6 +253,10 @@ pub enum UiEvent {
     ToolCallResult(Uuid, String, bool, String),  // session_id, name, success, summary
     /// Bash output line streamed during command execution
     BashOutputLine(Uuid, String, String, bool), // session_id, tool_call_id, line, is_stderr
+    /// A file was snapshotted before the last tool changed it; undo with `snapshots::undo`
+    FileSnapshot(Uuid, String), // session_id, snapshot_id
+    /// Web pages a web_search or fetch_url call returned, for citations
+    Sources(Uuid, Vec<Source>),
     /// Ask question - AI needs user input
     AskQuestion {
         session_id: Uuid,
@@ -143,14 +264,52 @@ pub enum UiEvent {
         question: String,
         options: Option<Vec<String>>,
     },
+    /// Token usage of one model request in the run
+    Usage(Uuid, Usage),
+    /// Why the model stopped; sent just before `StreamFinished`
+    FinishReason(Uuid, FinishReason),
     StreamFinished(Uuid),
     StreamErrored(Uuid, String),
+    /// A request failed and is retried after a delay; the run goes on
+    StreamRetrying(Uuid, String),
+    /// The tool calls of the agent loop's round n (from 1) are about to run
+    ToolRound(Uuid, u32),
+    /// A budget limit stopped the run; the frontend can offer "continue anyway"
+    BudgetExceeded(Uuid, String), // session_id, message
     /// Conversation starters generated
     ConversationStarters(Vec<String>),
     /// Generated title for the conversation
     ConversationTitle(String),
 }
 
+impl UiEvent {
+    /// Session the event belongs to, if it is tied to one
+    pub fn session_id(&self) -> Option<Uuid> {
+        match self {
+            UiEvent::StreamStarted(id)
+            | UiEvent::Token(id, ..)
+            | UiEvent::Thinking(id, _)
+            | UiEvent::ToolCallStart(id, ..)
+            | UiEvent::ToolCallResult(id, ..)
+            | UiEvent::BashOutputLine(id, ..)
+            | UiEvent::FileSnapshot(id, _)
+            | UiEvent::Sources(id, _)
+            | UiEvent::Usage(id, _)
+            | UiEvent::FinishReason(id, _)
+            | UiEvent::StreamFinished(id)
+            | UiEvent::StreamErrored(id, _)
+            | UiEvent::StreamRetrying(id, _)
+            | UiEvent::ToolRound(id, _)
+            | UiEvent::BudgetExceeded(id, _) => Some(*id),
+            UiEvent::AskQuestion { session_id, .. } => Some(*session_id),
+            UiEvent::UserMessage { .. }
+            | UiEvent::AiMessage { .. }
+            | UiEvent::ConversationStarters(_)
+            | UiEvent::ConversationTitle(_) => None,
+        }
+    }
+}
+
 /// Manages AI streaming sessions and communication with UI layers.
 ///
 /// This is the main backend orchestrator - frontend code should use this
@@ -164,38 +323,215 @@ pub struct SessionManager {
     model_cache: Arc<ModelCacheManager>,
     /// Active session cancellation tokens
     cancellation_tokens: Arc<Mutex<HashMap<Uuid, CancellationToken>>>,
+    /// Symbol index generation the current system prompt was built with
+    index_generation: u64,
+    /// What went into the current system prompt
+    context_report: ContextReport,
+    /// Documents of the active workspace, searched for every prompt
+    knowledge: Option<KnowledgeBase>,
+    /// Budget totals of each session, kept across its runs
+    budget_usage: HashMap<Uuid, BudgetUsage>,
+    /// Answers prompts instead of the provider when set (tests and demos)
+    mock: Option<MockBackend>,
+    /// Bumped whenever a backend is installed, so a stale `PreparedBackend`
+    /// can't replace a newer one
+    backend_generation: u64,
+}
+
+/// A backend built off the UI thread by `SessionManager::prepare_backend`
+#[derive(Clone)]
+pub struct PreparedBackend {
+    config: Config,
+    backend: AgentBackend,
+    index_generation: u64,
+    context_report: ContextReport,
+    /// `backend_generation` when the build started
+    replaces: u64,
+}
+
+impl std::fmt::Debug for PreparedBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreparedBackend")
+            .field("index_generation", &self.index_generation)
+            .field("replaces", &self.replaces)
+            .finish_non_exhaustive()
+    }
 }
 
 impl SessionManager {
     /// Creates a new session manager with the given configuration.
     pub fn new(config: &Config) -> anyhow::Result<Self> {
-        let backend = AgentBackend::new(config, build_system_prompt_with_manifest())?;
+        let knowledge = KnowledgeBase::for_workspace(&Workspace::default_workspace()).ok();
+        let (backend, index_generation, context_report) =
+            Self::build_backend_with_knowledge(config, knowledge.as_ref())?;
         let runtime = Runtime::new()?;
         let (events, _) = broadcast::channel(128);
+        runtime.spawn(webhooks::run(events.subscribe()));
         let runner = SessionRunner::new(backend);
         Ok(Self {
             runtime,
             events,
             runner,
             config: config.clone(),
-            model_cache: Arc::new(ModelCacheManager::new(30)), // 30 min TTL
+            model_cache: Arc::new(ModelCacheManager::persistent(30)), // 30 min TTL, kept across runs
             cancellation_tokens: Arc::new(Mutex::new(HashMap::new())),
+            index_generation,
+            context_report,
+            knowledge,
+            budget_usage: HashMap::new(),
+            mock: None,
+            backend_generation: 0,
         })
     }
 
+    /// Builds a backend for the current directory and returns it together with
+    /// the symbol index generation baked into its system prompt and the context report.
+    pub fn build_backend(
+        config: &Config,
+    ) -> anyhow::Result<(AgentBackend, u64, ContextReport)> {
+        Self::build_backend_with_knowledge(config, None)
+    }
+
+    /// `build_backend`, reserving context budget for the excerpts of `knowledge`
+    fn build_backend_with_knowledge(
+        config: &Config,
+        knowledge: Option<&KnowledgeBase>,
+    ) -> anyhow::Result<(AgentBackend, u64, ContextReport)> {
+        redact::configure(config);
+        response_cache::configure(config);
+        sandbox::configure(config);
+        transcribe::configure(config);
+        webhooks::configure(config);
+        symbol_index::index_in_background(sandbox::working_dir());
+        let index_generation = symbol_index::index_generation();
+        let project = ProjectConfig::load_from_current_dir();
+        let (system_prompt, context_report) = build_system_prompt_with_context(config, knowledge);
+        let backend = AgentBackend::new_with_project(config, system_prompt, project.as_ref())?;
+        Ok((backend, index_generation, context_report))
+    }
+
+    /// Stream every prompt from `backend` instead of the provider, or go back
+    /// to the provider with `None`
+    pub fn set_mock_backend(&mut self, backend: Option<MockBackend>) {
+        self.mock = backend;
+    }
+
     /// Get a clone of the backend (for use in async contexts like conversation starters)
     pub fn backend_clone(&self) -> AgentBackend {
         self.runner.backend_clone()
     }
 
     /// Updates the backend with new configuration.
+    ///
+    /// The project overlay is re-read from the current directory, so this should
+    /// also be called after the working directory changes.
     pub fn update_backend(&mut self, config: &Config) -> anyhow::Result<()> {
-        let backend = AgentBackend::new(config, build_system_prompt_with_manifest())?;
+        let (backend, index_generation, context_report) =
+            Self::build_backend_with_knowledge(config, self.knowledge.as_ref())?;
         self.runner = SessionRunner::new(backend);
         self.config = config.clone();
+        self.index_generation = index_generation;
+        self.context_report = context_report;
+        self.backend_generation += 1;
         Ok(())
     }
 
+    /// `update_backend` split for UIs: the returned future builds the backend
+    /// on a blocking thread (reading the project, its git state and manifest
+    /// takes too long for a UI thread) and `install_backend` swaps it in.
+    pub fn prepare_backend(
+        &self,
+        config: &Config,
+    ) -> impl std::future::Future<Output = anyhow::Result<PreparedBackend>> + Send + 'static {
+        let config = config.clone();
+        let knowledge = self.knowledge.clone();
+        let replaces = self.backend_generation;
+        async move {
+            tokio::task::spawn_blocking(move || {
+                let (backend, index_generation, context_report) =
+                    Self::build_backend_with_knowledge(&config, knowledge.as_ref())?;
+                Ok(PreparedBackend {
+                    config,
+                    backend,
+                    index_generation,
+                    context_report,
+                    replaces,
+                })
+            })
+            .await?
+        }
+    }
+
+    /// Use a backend from `prepare_backend`, unless another one was installed
+    /// since it started building. Returns whether it was installed.
+    pub fn install_backend(&mut self, prepared: PreparedBackend) -> bool {
+        if prepared.replaces != self.backend_generation {
+            return false;
+        }
+        self.runner = SessionRunner::new(prepared.backend);
+        self.config = prepared.config;
+        self.index_generation = prepared.index_generation;
+        self.context_report = prepared.context_report;
+        self.backend_generation += 1;
+        true
+    }
+
+    /// Breakdown of the current system prompt: which sources were included,
+    /// truncated or dropped to fit the context budget.
+    pub fn context_report(&self) -> &ContextReport {
+        &self.context_report
+    }
+
+    /// System prompt sent ahead of every conversation, as currently built.
+    pub fn system_prompt(&self) -> &str {
+        self.runner.backend().client().system_prompt()
+    }
+
+    /// Searches `workspace`'s knowledge base for the following prompts.
+    pub fn set_workspace(&mut self, workspace: &Workspace) {
+        self.knowledge = KnowledgeBase::for_workspace(workspace).ok();
+    }
+
+    /// Tokens the knowledge base excerpts of a prompt may take: the configured
+    /// share, cut to what the context budget kept for them (or, without a
+    /// reservation, to what the system prompt left over)
+    fn knowledge_tokens(&self) -> usize {
+        let configured = self
+            .config
+            .get_knowledge()
+            .max_context_tokens
+            .unwrap_or(knowledge::DEFAULT_CONTEXT_TOKENS);
+        let report = &self.context_report;
+        let room = report
+            .kept_tokens(KNOWLEDGE_LABEL)
+            .unwrap_or_else(|| report.budget_tokens.saturating_sub(report.used_tokens));
+        configured.min(room)
+    }
+
+    /// Adds the knowledge base excerpts relevant to `prompt`, up to
+    /// `max_tokens`, to it and to the matching last user message of `history`.
+    async fn with_knowledge(
+        knowledge: &KnowledgeBase,
+        config: &Config,
+        max_tokens: usize,
+        prompt: String,
+        mut history: Option<Vec<ChatMessage>>,
+    ) -> (String, Option<Vec<ChatMessage>>) {
+        let excerpts = knowledge.retrieve(&prompt, config, max_tokens).await;
+        if excerpts.is_empty() {
+            return (prompt, history);
+        }
+        tracing::info!(excerpts = excerpts.len(), "Adding knowledge base excerpts to prompt");
+        let expanded = knowledge::prompt_with_excerpts(&prompt, &excerpts);
+        // The UI's history already ends with the prompt as typed
+        if let Some(last) = history.as_mut().and_then(|h| h.last_mut()) {
+            if last.role == "user" && last.content.as_deref() == Some(prompt.as_str()) {
+                last.content = Some(expanded.clone());
+            }
+        }
+        (expanded, history)
+    }
+
     /// Signals that streaming should stop for the given session.
     /// This cancels the background task and sends a finished event.
     pub fn stop_stream(&self, session_id: Uuid) {
@@ -219,9 +555,13 @@ impl SessionManager {
             "list_directory" => "List".to_string(),
             "search_files" => "Search".to_string(),
             "web_search" => "Web".to_string(),
+            "fetch_url" => "Fetch".to_string(),
             "mcp_call" => "MCP".to_string(),
             "visioneer" => "Vision".to_string(),
             "ask_question" => "Question".to_string(),
+            "code_outline" => "Outline".to_string(),
+            "capture_screen" => "Screenshot".to_string(),
+            "transcribe_audio" => "Transcribe".to_string(),
             _ => name.to_string(),
         }
     }
@@ -275,15 +615,36 @@ impl SessionManager {
         }
     }
 
+    /// Pages a web_search or fetch_url result refers to
+    fn tool_sources(tool_name: &str, result: &serde_json::Value) -> Vec<Source> {
+        let data = result.get("Ok").unwrap_or(result);
+        let source = |item: &serde_json::Value| {
+            let url = item.get("url")?.as_str()?.to_string();
+            let title = item
+                .get("title")
+                .and_then(|t| t.as_str())
+                .filter(|t| !t.trim().is_empty())
+                .map_or_else(|| url.clone(), |t| t.trim().to_string());
+            Some(Source { title, url })
+        };
+        match tool_name {
+            "web_search" => data
+                .get("results")
+                .and_then(|r| r.as_array())
+                .map(|results| results.iter().filter_map(source).collect())
+                .unwrap_or_default(),
+            "fetch_url" => source(data).into_iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
     /// Helper function to summarize tool results
     fn summarize_tool_result(result: &serde_json::Value, success: bool) -> String {
         // Debug: log the actual result structure
-        if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-            eprintln!(
-                "🔧 TOOL RESULT DEBUG: {}",
-                serde_json::to_string(&result).unwrap_or_default()
-            );
-        }
+        tracing::trace!(
+            result = %serde_json::to_string(&result).unwrap_or_default(),
+            "Summarizing tool result"
+        );
 
         // Helper to extract first meaningful line
         fn first_line(s: &str, max_chars: usize) -> String {
@@ -381,6 +742,12 @@ impl SessionManager {
             }
         }
 
+        // Check for code_outline result - show symbol count
+        if let Some(symbols) = data.get("symbols").and_then(|s| s.as_array()) {
+            let total = data.get("total_found").and_then(|t| t.as_u64()).unwrap_or(symbols.len() as u64);
+            return format!("{} symbols", total);
+        }
+
         // Check for find_files result - show actual files found
         if let Some(files) = data.get("files").and_then(|f| f.as_array()) {
             let total_matches = data.get("total_matches").and_then(|m| m.as_u64()).unwrap_or(0) as usize;
@@ -634,14 +1001,26 @@ impl SessionManager {
 
     /// Starts a streaming session for the given prompt with conversation history.
     pub fn start_stream(
-        &self,
+        &mut self,
         session_id: Uuid,
         prompt: String,
         history: Option<Vec<ChatMessage>>,
-        session_config: SessionConfig,
+        mut session_config: SessionConfig,
     ) -> anyhow::Result<()> {
+        // Budget limits cover the whole session, not just this prompt
+        session_config
+            .budget_usage
+            .get_or_insert_with(|| self.budget_usage.entry(session_id).or_default().clone());
+
+        // Pick up the code outline if the symbol index finished since the last build
+        if symbol_index::index_generation() != self.index_generation {
+            let config = self.config.clone();
+            self.update_backend(&config)?;
+        }
+
         let tx = self.events.clone();
         let runner = self.runner.clone();
+        let mock = self.mock.clone();
 
         // Check if this is a new conversation (no history) for title generation
         let is_new_conversation = history.as_ref().map_or(true, |h| h.is_empty());
@@ -657,18 +1036,42 @@ impl SessionManager {
 
         let tokens_ref = self.cancellation_tokens.clone();
 
+        let provider = self.config.active_provider.clone();
+        let model = session_config.model.clone();
+        let started = std::time::Instant::now();
+        let knowledge_base = self.knowledge.clone();
+        let knowledge_tokens = self.knowledge_tokens();
+        let config = self.config.clone();
+
+        let span = tracing::info_span!("session", id = %session_id);
         self.runtime.spawn(async move {
+            inspector::begin_turn();
+            http_capture::begin_turn();
+            snapshots::begin_turn();
             let _ = tx.send(UiEvent::StreamStarted(session_id));
+            tracing::info!("Stream started");
 
             // If this is a new conversation, generate a title from the first user message
             if is_new_conversation {
                 Self::generate_conversation_title(tx.clone(), prompt.clone());
             }
 
-            match runner.stream_session(prompt, history, session_config) {
+            let (prompt, history) = match &knowledge_base {
+                Some(knowledge_base) => {
+                    Self::with_knowledge(knowledge_base, &config, knowledge_tokens, prompt, history).await
+                }
+                None => (prompt, history),
+            };
+
+            let stream = match &mock {
+                Some(mock) => mock.stream_session(prompt, history, session_config),
+                None => runner.stream_session(prompt, history, session_config),
+            };
+            match stream {
                 Ok(mut stream) => {
                     // Track tool call IDs to names
                     let mut tool_id_to_name: HashMap<String, String> = HashMap::new();
+                    let mut first_token: Option<std::time::Duration> = None;
 
                     loop {
                         tokio::select! {
@@ -679,9 +1082,13 @@ impl SessionManager {
                             }
                             // Process next stream event
                             event = stream.next() => {
+                                if let Some(event) = &event {
+                                    inspector::record_event(InspectorSource::StreamEvent, event);
+                                }
                                 match event {
                                     Some(StreamEvent::Start { .. }) => {}
                                     Some(StreamEvent::Text { text }) => {
+                                        first_token.get_or_insert_with(|| started.elapsed());
                                         let _ = tx.send(UiEvent::Token(session_id, text, false));
                                     }
                                     Some(StreamEvent::Reasoning { text }) => {
@@ -693,7 +1100,7 @@ impl SessionManager {
                                         tool_id_to_name.insert(id.clone(), name.clone());
 
                                         let display_name = Self::get_tool_display_name(&name);
-                                        let args_display = Self::format_tool_args(&arguments);
+                                        let args_display = Self::format_tool_args(&redact::redact(&arguments));
                                         let _ = tx.send(UiEvent::ToolCallStart(
                                             session_id,
                                             id.clone(),
@@ -708,20 +1115,34 @@ impl SessionManager {
                                             .cloned()
                                             .unwrap_or_else(|| "unknown".to_string());
 
-                                        let summary =
-                                            Self::summarize_tool_result(&result.data, result.success);
+                                        let mut data = result.data.clone();
+                                        redact::redact_json(&mut data);
+                                        let summary = Self::summarize_tool_result(&data, result.success);
+                                        let snapshot_id = data
+                                            .get("Ok")
+                                            .unwrap_or(&data)
+                                            .get("snapshot_id")
+                                            .and_then(|id| id.as_str())
+                                            .map(str::to_string);
+                                        let sources = Self::tool_sources(&tool_name, &data);
                                         let _ = tx.send(UiEvent::ToolCallResult(
                                             session_id,
                                             tool_name, // Send the actual tool name, not the ID
                                             result.success,
                                             summary,
                                         ));
+                                        if let Some(snapshot_id) = snapshot_id {
+                                            let _ = tx.send(UiEvent::FileSnapshot(session_id, snapshot_id));
+                                        }
+                                        if !sources.is_empty() {
+                                            let _ = tx.send(UiEvent::Sources(session_id, sources));
+                                        }
                                     }
                                     Some(StreamEvent::BashOutputLine { tool_call_id, line, is_stderr }) => {
                                         let _ = tx.send(UiEvent::BashOutputLine(
                                             session_id,
                                             tool_call_id,
-                                            line,
+                                            redact::redact(&line).into_owned(),
                                             is_stderr,
                                         ));
                                     }
@@ -733,12 +1154,37 @@ impl SessionManager {
                                             options,
                                         });
                                     }
-                                    Some(StreamEvent::Finished) => {
+                                    Some(StreamEvent::BudgetExceeded(exceeded)) => {
+                                        tracing::warn!(%exceeded, "Session run stopped by budget");
+                                        let _ = tx.send(UiEvent::BudgetExceeded(session_id, exceeded.to_string()));
+                                    }
+                                    Some(StreamEvent::Retrying(notice)) => {
+                                        let _ = tx.send(UiEvent::StreamRetrying(session_id, notice.to_string()));
+                                    }
+                                    Some(StreamEvent::ToolRound { round }) => {
+                                        let _ = tx.send(UiEvent::ToolRound(session_id, round));
+                                    }
+                                    Some(StreamEvent::Usage(usage)) => {
+                                        let _ = tx.send(UiEvent::Usage(session_id, usage));
+                                    }
+                                    Some(StreamEvent::Metadata(metadata)) => {
+                                        tracing::debug!(?metadata, "Response metadata");
+                                    }
+                                    Some(StreamEvent::Finished { reason }) => {
+                                        telemetry::record_turn(&provider, &model, started.elapsed());
+                                        Self::record_turn_usage(&provider, &model, first_token, started, true);
+                                        if reason != FinishReason::Stop {
+                                            tracing::info!(reason = reason.label(), "Stream finished early");
+                                        }
                                         let _ = tx.send(UiEvent::Token(session_id, String::new(), true));
+                                        let _ = tx.send(UiEvent::FinishReason(session_id, reason));
                                         let _ = tx.send(UiEvent::StreamFinished(session_id));
                                         break;
                                     }
                                     Some(StreamEvent::Error(err)) => {
+                                        tracing::error!(error = %err, "Stream errored");
+                                        telemetry::record_provider_error(&provider);
+                                        Self::record_turn_usage(&provider, &model, first_token, started, false);
                                         let _ = tx.send(UiEvent::StreamErrored(session_id, err));
                                         break;
                                     }
@@ -752,6 +1198,9 @@ impl SessionManager {
                     }
                 }
                 Err(err) => {
+                    tracing::error!(error = %err, "Failed to start stream");
+                    telemetry::record_provider_error(&provider);
+                    Self::record_turn_usage(&provider, &model, None, started, false);
                     let _ = tx.send(UiEvent::StreamErrored(session_id, err.to_string()));
                 }
             }
@@ -760,116 +1209,243 @@ impl SessionManager {
             if let Ok(mut tokens) = tokens_ref.lock() {
                 tokens.remove(&session_id);
             }
-        });
+            tracing::info!("Stream ended");
+        }.instrument(span));
 
         Ok(())
     }
 
+    /// Append a finished turn to the local usage store
+    fn record_turn_usage(
+        provider: &str,
+        model: &str,
+        first_token: Option<std::time::Duration>,
+        started: std::time::Instant,
+        success: bool,
+    ) {
+        usage_store::record(UsageKind::Turn {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            ttft_ms: first_token.map(|d| d.as_millis() as u64),
+            duration_ms: started.elapsed().as_millis() as u64,
+            success,
+        });
+    }
+
     /// Get a broadcast receiver for UI events
     pub fn subscribe(&self) -> broadcast::Receiver<UiEvent> {
         self.events.subscribe()
     }
 
+    /// Serve the UI event stream over WebSocket. Prompts and stop requests from
+    /// clients arrive on the returned receiver for the owner to act on, so they
+    /// go through the same path as the owner's own input.
+    pub fn serve_websocket(
+        &self,
+        options: WebSocketOptions,
+    ) -> anyhow::Result<mpsc::UnboundedReceiver<RemoteCommand>> {
+        // Origin checks only stop browsers; other clients need the token
+        if options.token.is_none() && !options.addr.ip().is_loopback() {
+            anyhow::bail!(
+                "Refusing to serve the WebSocket API on {} without a token; anyone who can reach it could run prompts and tools",
+                options.addr
+            );
+        }
+        // Bind here so a busy port is reported to the caller
+        let listener = std::net::TcpListener::bind(options.addr)?;
+        listener.set_nonblocking(true)?;
+
+        let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+        let events = self.events.clone();
+        self.runtime.spawn(async move {
+            if let Err(e) = websocket::serve(listener, options, events, commands_tx).await {
+                tracing::error!(error = %e, "WebSocket server stopped");
+            }
+        });
+        Ok(commands_rx)
+    }
+
+    /// Register the D-Bus service on the session bus in the background. Like
+    /// `serve_websocket`, prompts arrive on the returned receiver; failures
+    /// (no session bus, name taken by another instance) are only logged.
+    #[cfg(target_os = "linux")]
+    pub fn serve_dbus(&self) -> mpsc::UnboundedReceiver<RemoteCommand> {
+        let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+        let events = self.events.clone();
+        self.runtime.spawn(async move {
+            if let Err(e) = crate::server::dbus::serve(events, commands_tx).await {
+                tracing::warn!(error = %e, "D-Bus service unavailable");
+            }
+        });
+        commands_rx
+    }
+
     // ==================== Model Fetching ====================
 
-    /// Fetch OpenAI models asynchronously and cache them.
-    pub fn fetch_openai_models(&self) {
+    /// Refresh a provider's model list in the background unless the cached
+    /// list is still fresh. Stale lists stay readable while the fetch runs.
+    fn refresh_models<F: ModelFetcher + 'static>(
+        &self,
+        fetcher: F,
+        api_key: String,
+        api_url: Option<String>,
+    ) {
+        if self.model_cache.is_fresh(fetcher.provider_name()) {
+            return;
+        }
+        self.reload_models(fetcher, api_key, api_url);
+    }
+
+    /// Fetch a provider's model list in the background even if the cached one
+    /// is fresh, e.g. after models were installed or removed.
+    fn reload_models<F: ModelFetcher + 'static>(
+        &self,
+        fetcher: F,
+        api_key: String,
+        api_url: Option<String>,
+    ) {
         let cache = self.model_cache.clone();
-        let api_key = self.config.get_api_key();
         self.runtime.spawn(async move {
-            let fetcher = OpenAIFetcher;
-            let models = fetcher.fetch_models(&api_key, None).await;
-            cache.cache("openai", models);
+            let models = fetcher.fetch_models(&api_key, api_url.as_deref()).await;
+            let ttl = std::time::Duration::from_secs(fetcher.default_ttl_minutes() * 60);
+            cache.cache_with_ttl(fetcher.provider_name(), models, ttl);
         });
     }
 
+    /// Fetch OpenAI models asynchronously and cache them.
+    pub fn fetch_openai_models(&self) {
+        self.refresh_models(OpenAIFetcher, self.config.get_api_key(), None);
+    }
+
     /// Get cached OpenAI models.
     pub fn get_cached_openai_models(&self) -> Option<Vec<String>> {
-        self.model_cache.get_cached("openai")
+        self.model_cache.get_cached_or_stale("openai")
     }
 
     /// Fetch Anthropic models asynchronously and cache them.
     pub fn fetch_anthropic_models(&self) {
-        let cache = self.model_cache.clone();
-        let api_key = self.config.get_api_key();
-        self.runtime.spawn(async move {
-            let fetcher = AnthropicFetcher;
-            let models = fetcher.fetch_models(&api_key, None).await;
-            cache.cache("anthropic", models);
-        });
+        self.refresh_models(AnthropicFetcher, self.config.get_api_key(), None);
     }
 
     /// Get cached Anthropic models.
     pub fn get_cached_anthropic_models(&self) -> Option<Vec<String>> {
-        self.model_cache.get_cached("anthropic")
+        self.model_cache.get_cached_or_stale("anthropic")
     }
 
     /// Fetch Ollama models asynchronously and cache them.
     pub fn fetch_ollama_models(&self) {
-        let cache = self.model_cache.clone();
-        // Get Ollama-specific URL, not the active provider's URL
-        // This is important when the active provider is different (e.g., Z.AI)
-        let api_url = self.config.providers
+        self.refresh_models(OllamaFetcher, String::new(), Some(self.ollama_url()));
+    }
+
+    /// Re-fetch Ollama models after a model was pulled or deleted, even while
+    /// the cached list is fresh.
+    pub fn reload_ollama_models(&self) {
+        self.reload_models(OllamaFetcher, String::new(), Some(self.ollama_url()));
+    }
+
+    /// Ollama-specific URL, not the active provider's URL; this matters when
+    /// the active provider is different (e.g., Z.AI)
+    fn ollama_url(&self) -> String {
+        self.config
+            .providers
             .get("ollama")
             .and_then(|p| p.api_url.clone())
-            .unwrap_or_else(|| "http://localhost:11434".to_string());
-        self.runtime.spawn(async move {
-            let fetcher = OllamaFetcher;
-            let models = fetcher.fetch_models("", Some(&api_url)).await;
-            cache.cache("ollama", models);
-        });
+            .unwrap_or_else(|| "http://localhost:11434".to_string())
     }
 
     /// Get cached Ollama models.
     pub fn get_cached_ollama_models(&self) -> Option<Vec<String>> {
-        self.model_cache.get_cached("ollama")
+        self.model_cache.get_cached_or_stale("ollama")
+    }
+
+    /// Fetch xAI models asynchronously and cache them.
+    pub fn fetch_xai_models(&self) {
+        self.refresh_models(XaiFetcher, self.config.get_api_key(), None);
+    }
+
+    /// Get cached xAI models.
+    pub fn get_cached_xai_models(&self) -> Option<Vec<String>> {
+        self.model_cache.get_cached_or_stale("xai")
+    }
+
+    /// Fetch DeepSeek models asynchronously and cache them.
+    pub fn fetch_deepseek_models(&self) {
+        self.refresh_models(DeepSeekFetcher, self.config.get_api_key(), None);
+    }
+
+    /// Get cached DeepSeek models.
+    pub fn get_cached_deepseek_models(&self) -> Option<Vec<String>> {
+        self.model_cache.get_cached_or_stale("deepseek")
+    }
+
+    /// Fetch the models loaded in LM Studio asynchronously and cache them.
+    pub fn fetch_lm_studio_models(&self) {
+        self.refresh_local_server_models(LocalServerFetcher::LM_STUDIO);
+    }
+
+    /// Get cached LM Studio models.
+    pub fn get_cached_lm_studio_models(&self) -> Option<Vec<String>> {
+        self.model_cache.get_cached_or_stale("lm studio")
+    }
+
+    /// Fetch the model served by llamafile asynchronously and cache it.
+    pub fn fetch_llamafile_models(&self) {
+        self.refresh_local_server_models(LocalServerFetcher::LLAMAFILE);
+    }
+
+    /// Get cached llamafile models.
+    pub fn get_cached_llamafile_models(&self) -> Option<Vec<String>> {
+        self.model_cache.get_cached_or_stale("llamafile")
+    }
+
+    /// Like Ollama, use the server's own configured URL and key rather than
+    /// the active provider's
+    fn refresh_local_server_models(&self, fetcher: LocalServerFetcher) {
+        let provider = self.config.providers.get(fetcher.provider_name());
+        let api_url = provider.and_then(|p| p.api_url.clone());
+        let api_key = provider.map(|p| p.api_key.clone()).unwrap_or_default();
+        self.refresh_models(fetcher, api_key, api_url);
     }
 
     /// Fetch OpenRouter models asynchronously and cache them.
     pub fn fetch_openrouter_models(&self) {
-        let cache = self.model_cache.clone();
-        let api_key = self.config.get_api_key();
-        self.runtime.spawn(async move {
-            let fetcher = OpenRouterFetcher;
-            let models = fetcher.fetch_models(&api_key, None).await;
-            cache.cache("openrouter", models);
-        });
+        self.refresh_models(OpenRouterFetcher, self.config.get_api_key(), None);
     }
 
     /// Get cached OpenRouter models.
     pub fn get_cached_openrouter_models(&self) -> Option<Vec<String>> {
-        self.model_cache.get_cached("openrouter")
+        self.model_cache.get_cached_or_stale("openrouter")
     }
 
     /// Fetch Z.AI models asynchronously and cache them.
     pub fn fetch_zai_models(&self) {
-        let cache = self.model_cache.clone();
-        let config = self.config.clone();
-        
-        self.runtime.spawn(async move {
-            let fetcher = ZaiFetcher;
-            let api_key = config.get_api_key();
-            let models = fetcher.fetch_models(&api_key, None).await;
-            cache.cache("zai", models);
-        });
+        self.refresh_models(ZaiFetcher, self.config.get_api_key(), None);
     }
 
     /// Get cached Z.AI models.
     pub fn get_cached_zai_models(&self) -> Option<Vec<String>> {
-        self.model_cache.get_cached("zai")
+        self.model_cache.get_cached_or_stale("zai")
     }
 
     // ==================== Conversation Starters ====================
 
-    /// Generate 3 contextual conversation starter suggestions based on project context.
-    /// This makes a lightweight API call that doesn't get added to conversation history.
+    /// Produce the conversation starters for an empty session: the pinned ones,
+    /// then whatever `starter_mode` asks for. Generated starters come from a
+    /// lightweight API call that doesn't get added to conversation history.
     pub fn generate_conversation_starters(&self) {
         let backend = self.backend_clone();
         let config = self.config.clone();
         let events = self.events.clone();
-        
+
         self.runtime.spawn(async move {
-            let starters = fetch_starters_internal(backend, &config).await;
+            let count = starters::to_generate(&config);
+            let starters = if count == 0 {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                starters::offline(&config, &cwd)
+            } else {
+                let generated = fetch_starters_internal(backend, &config, count).await;
+                starters::with_pinned(&config.get_pinned_starters(), generated)
+            };
             let _ = events.send(UiEvent::ConversationStarters(starters));
         });
     }
@@ -879,34 +1455,31 @@ impl SessionManager {
 async fn fetch_starters_internal(
     backend: AgentBackend,
     config: &Config,
+    count: usize,
 ) -> Vec<String> {
     // Build system prompt with PROJECT.manifest context
-    let system_prompt = build_system_prompt_with_manifest();
+    let (system_prompt, _) = build_system_prompt_with_context(config, None);
     
-    let prompt = r#"Based on the PROJECT.manifest context, suggest exactly 3 short, actionable conversation starters 
+    let prompt = format!(r#"Based on the PROJECT.manifest context, suggest exactly {count} short, actionable conversation starters 
 that would be useful for a developer working on this project. Each starter should:
 - Be 5-10 words maximum
 - Start with a verb (e.g., "Add", "Fix", "Refactor", "Test")
 - Be specific and actionable
 - Relate to common development tasks for this codebase
 
-Return ONLY a JSON array of 3 strings, nothing else. Example format:
-["Add user authentication", "Fix memory leak in parser", "Add unit tests for API"]"#;
+Return ONLY a JSON array of {count} strings, nothing else. Example format:
+["Add user authentication", "Fix memory leak in parser", "Add unit tests for API"]"#);
 
     let client = match backend.create_client_with_prompt(config, system_prompt) {
         Ok(c) => c,
         Err(e) => {
-            eprintln!("Failed to create client for conversation starters: {e}");
-            return vec![
-                "Review recent changes".to_string(),
-                "Run tests and fix issues".to_string(),
-                "Add new feature".to_string(),
-            ];
+            tracing::warn!("Failed to create client for conversation starters: {e}");
+            return starters::fallback();
         }
     };
 
     // Make a single, non-streaming request to get starters
-    match client.query(prompt, None).await {
+    match client.query(&prompt, None).await {
         Ok(mut stream) => {
             let mut response = String::new();
             while let Some(block) = stream.next().await {
@@ -915,30 +1488,30 @@ Return ONLY a JSON array of 3 strings, nothing else. Example format:
                         response.push_str(&text);
                     }
                     crate::api::agent::ContentBlock::Reasoning { reasoning: _ } => {
-                        eprintln!("DEBUG: Ignoring reasoning block in starters");
+                        tracing::debug!("Ignoring reasoning block in starters");
                     }
                     _ => {
-                        eprintln!("DEBUG: Unexpected block type in starters");
+                        tracing::debug!("Unexpected block type in starters");
                     }
                 }
             }
             
-            eprintln!("DEBUG: Raw starters response: {response}");
-            eprintln!("DEBUG: Response length: {}", response.len());
+            tracing::debug!("Raw starters response: {response}");
+            tracing::debug!("Response length: {}", response.len());
             
             // Try to parse as JSON array directly
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(&response) {
-                eprintln!("DEBUG: Parsed as JSON");
+                tracing::debug!("Parsed as JSON");
                 if let Some(arr) = json.as_array() {
                     let starters: Vec<String> = arr.iter()
                         .filter_map(|v| v.as_str())
                         .map(|s| s.trim().to_string())
                         .filter(|s| !s.is_empty() && s.len() < 100)
-                        .take(3)
+                        .take(count)
                         .collect();
                     
                     if !starters.is_empty() {
-                        eprintln!("DEBUG: Parsed starters: {:?}", starters);
+                        tracing::debug!("Parsed starters: {:?}", starters);
                         return starters;
                     }
                 }
@@ -951,20 +1524,20 @@ Return ONLY a JSON array of 3 strings, nothing else. Example format:
                 .trim()
                 .to_string();
             
-            eprintln!("DEBUG: Cleaned response: {cleaned}");
+            tracing::debug!("Cleaned response: {cleaned}");
             
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(&cleaned) {
-                eprintln!("DEBUG: Parsed cleaned as JSON");
+                tracing::debug!("Parsed cleaned as JSON");
                 if let Some(arr) = json.as_array() {
                     let starters: Vec<String> = arr.iter()
                         .filter_map(|v| v.as_str())
                         .map(|s| s.trim().to_string())
                         .filter(|s| !s.is_empty() && s.len() < 100)
-                        .take(3)
+                        .take(count)
                         .collect();
                     
                     if !starters.is_empty() {
-                        eprintln!("DEBUG: Parsed starters from cleaned JSON: {:?}", starters);
+                        tracing::debug!("Parsed starters from cleaned JSON: {:?}", starters);
                         return starters;
                     }
                 }
@@ -1005,28 +1578,20 @@ Return ONLY a JSON array of 3 strings, nothing else. Example format:
                         None
                     }
                 })
-                .take(3)
+                .take(count)
                 .collect();
             
             if !extracted.is_empty() {
-                eprintln!("DEBUG: Extracted starters from lines: {:?}", extracted);
+                tracing::debug!("Extracted starters from lines: {:?}", extracted);
                 extracted
             } else {
-                eprintln!("DEBUG: No starters found, using defaults");
-                vec![
-                    "Review recent changes".to_string(),
-                    "Run tests and fix issues".to_string(),
-                    "Add new feature".to_string(),
-                ]
+                tracing::debug!("No starters found, using defaults");
+                starters::fallback()
             }
         }
         Err(e) => {
-            eprintln!("Failed to fetch conversation starters: {e}");
-            vec![
-                "Review recent changes".to_string(),
-                "Run tests and fix issues".to_string(),
-                "Add new feature".to_string(),
-            ]
+            tracing::warn!("Failed to fetch conversation starters: {e}");
+            starters::fallback()
         }
     }
 }
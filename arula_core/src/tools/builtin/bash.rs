@@ -14,6 +14,7 @@
 //! - Unix/Linux/macOS: Uses `sh -c`
 
 use crate::api::agent::{Tool, ToolSchema, ToolSchemaBuilder};
+use crate::tools::sandbox;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::process::Stdio;
@@ -87,6 +88,7 @@ impl Tool for BashTool {
     }
 
     async fn execute(&self, params: Self::Params) -> Result<Self::Result, String> {
+        sandbox::confirm(self.name(), &format!("run `{}`", params.command)).await?;
         execute_bash(&params.command, params.timeout_seconds).await
     }
 }
@@ -113,6 +115,7 @@ pub async fn execute_bash(
         c
     };
 
+    cmd.current_dir(sandbox::working_dir());
     cmd.stdin(Stdio::null());
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
@@ -159,9 +162,9 @@ pub fn execute_bash_streaming_channel(
 ) -> (mpsc::UnboundedReceiver<(String, bool)>, tokio::task::JoinHandle<Result<BashResult, String>>) {
     let (tx, rx) = mpsc::unbounded_channel();
     
-    let handle = tokio::spawn(async move {
+    let handle = tokio::spawn(sandbox::inherit_workdir(async move {
         execute_bash_streaming_inner(&command, timeout_seconds, tx).await
-    });
+    }));
     
     (rx, handle)
 }
@@ -177,6 +180,7 @@ async fn execute_bash_streaming_inner(
     if command.trim().is_empty() {
         return Err("Command cannot be empty".to_string());
     }
+    sandbox::confirm("execute_bash", &format!("run `{}`", command)).await?;
 
     let mut cmd = if cfg!(target_os = "windows") {
         let mut c = TokioCommand::new("cmd");
@@ -188,6 +192,7 @@ async fn execute_bash_streaming_inner(
         c
     };
 
+    cmd.current_dir(sandbox::working_dir());
     cmd.stdin(Stdio::null());
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
@@ -0,0 +1,138 @@
+//! Code outline tool
+//!
+//! Lists functions, types and methods of the current project using the
+//! tree-sitter symbol index, so the AI can navigate code without reading
+//! whole files.
+
+use crate::api::agent::{Tool, ToolSchema, ToolSchemaBuilder};
+use crate::utils::symbol_index::{current_index, Symbol, SymbolIndex};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Default number of symbols returned when no limit is given
+const DEFAULT_MAX_RESULTS: usize = 200;
+
+/// Parameters for the code outline tool
+#[derive(Debug, Deserialize)]
+pub struct CodeOutlineParams {
+    /// File or directory to outline (default: project root)
+    pub path: Option<String>,
+    /// Case-insensitive substring filter on symbol names
+    pub query: Option<String>,
+    /// Only include public/exported symbols (default: false)
+    pub exported_only: Option<bool>,
+    /// Maximum number of symbols to return (default: 200)
+    pub max_results: Option<usize>,
+}
+
+/// Result from the code outline tool
+#[derive(Debug, Serialize)]
+pub struct CodeOutlineResult {
+    /// Matching symbols in file order
+    pub symbols: Vec<Symbol>,
+    /// Total number of matches before truncation
+    pub total_found: usize,
+    /// Whether the result was truncated to `max_results`
+    pub limit_reached: bool,
+    /// Number of files covered by the index
+    pub files_indexed: usize,
+}
+
+/// Tool exposing the project symbol index
+pub struct CodeOutlineTool;
+
+impl CodeOutlineTool {
+    /// Create a new CodeOutlineTool instance
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for CodeOutlineTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for CodeOutlineTool {
+    type Params = CodeOutlineParams;
+    type Result = CodeOutlineResult;
+
+    fn name(&self) -> &str {
+        "code_outline"
+    }
+
+    fn description(&self) -> &str {
+        "List functions, types, traits/interfaces and methods with file and line numbers. \
+         Use this to find where something is defined before reading files."
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchemaBuilder::new("code_outline", "Outline the symbols of a file or directory")
+            .param("path", "string")
+            .description("path", "File or directory to outline (default: project root)")
+            .param("query", "string")
+            .description("query", "Only return symbols whose name contains this text")
+            .param("exported_only", "boolean")
+            .description("exported_only", "Only include public/exported symbols (default: false)")
+            .param("max_results", "integer")
+            .description("max_results", "Maximum number of symbols to return (default: 200)")
+            .build()
+    }
+
+    async fn execute(&self, params: Self::Params) -> Result<Self::Result, String> {
+        let cwd = crate::tools::sandbox::working_dir();
+        let target = match params.path.as_deref() {
+            Some(p) if !p.trim().is_empty() => {
+                let p = Path::new(p);
+                if p.is_absolute() { p.to_path_buf() } else { cwd.join(p) }
+            }
+            _ => cwd.clone(),
+        };
+        if !target.exists() {
+            return Err(format!("Path not found: {}", target.display()));
+        }
+
+        // Reuse the background index when it covers the target, otherwise
+        // index just the requested path
+        let index = match current_index() {
+            Some(index) if target.starts_with(&index.root) => index,
+            _ => {
+                let root: PathBuf = if target.is_dir() {
+                    target.clone()
+                } else {
+                    target.parent().map(Path::to_path_buf).unwrap_or_else(|| cwd.clone())
+                };
+                let index = tokio::task::spawn_blocking(move || SymbolIndex::build(&root))
+                    .await
+                    .map_err(|e| format!("Indexing failed: {}", e))?;
+                std::sync::Arc::new(index)
+            }
+        };
+
+        let query = params.query.map(|q| q.to_lowercase());
+        let exported_only = params.exported_only.unwrap_or(false);
+        let max_results = params.max_results.unwrap_or(DEFAULT_MAX_RESULTS).max(1);
+
+        let matches: Vec<&Symbol> = index
+            .symbols_under(&target)
+            .filter(|s| !exported_only || s.exported)
+            .filter(|s| {
+                query
+                    .as_ref()
+                    .map(|q| s.name.to_lowercase().contains(q))
+                    .unwrap_or(true)
+            })
+            .collect();
+
+        let total_found = matches.len();
+        Ok(CodeOutlineResult {
+            symbols: matches.into_iter().take(max_results).cloned().collect(),
+            total_found,
+            limit_reached: total_found > max_results,
+            files_indexed: index.files_indexed,
+        })
+    }
+}
@@ -0,0 +1,479 @@
+//! URL fetch tool
+//!
+//! Downloads a web page and reduces it to readable text: scripts, styles,
+//! navigation, headers, footers and forms are dropped, the `<article>` or
+//! `<main>` element is preferred over the whole body, and the rest is turned
+//! into paragraphs with Markdown-style headings and list items. Downloads and
+//! returned text are capped so a huge page can't flood the context.
+//!
+//! The same fetch runs when a user pastes a link into the chat (see
+//! [`pasted_url`]), so "https://… what changed here?" works without a tool call.
+//!
+//! Only public addresses are fetched: every host, including each redirect
+//! target, is resolved first and refused if any address is loopback, private,
+//! link-local (cloud metadata) or otherwise internal. The connection then goes
+//! to the checked addresses, so a second DNS answer can't point it elsewhere.
+
+use crate::api::agent::{Tool, ToolSchema, ToolSchemaBuilder};
+use crate::api::api::ChatMessage;
+use async_trait::async_trait;
+use futures::StreamExt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Largest response body read; the rest of the page is ignored
+const MAX_DOWNLOAD_BYTES: usize = 2 * 1024 * 1024;
+
+/// Characters of extracted text returned unless the caller asks for fewer or more
+pub const DEFAULT_MAX_CHARS: usize = 20_000;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Redirects followed before giving up
+const MAX_REDIRECTS: usize = 5;
+
+/// Longest prompt that is treated as a pasted link rather than a message
+/// that happens to contain one
+const MAX_PASTED_PROMPT_CHARS: usize = 300;
+
+/// Elements whose whole content is boilerplate
+const BOILERPLATE_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "iframe", "nav", "header", "footer", "aside",
+    "form", "button",
+];
+
+/// Parameters for the URL fetch tool
+#[derive(Debug, Deserialize)]
+pub struct FetchUrlParams {
+    /// http(s) URL to download
+    pub url: String,
+    /// Maximum characters of text to return (default: 20000)
+    pub max_chars: Option<usize>,
+}
+
+/// Result from the URL fetch tool
+#[derive(Debug, Serialize)]
+pub struct FetchUrlResult {
+    /// Final URL after redirects
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub content_type: String,
+    /// Readable text of the page
+    pub text: String,
+    /// Whether the download or the text was cut at a limit
+    pub truncated: bool,
+}
+
+/// Tool downloading web pages as readable text
+pub struct FetchUrlTool;
+
+impl FetchUrlTool {
+    /// Create a new FetchUrlTool instance
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for FetchUrlTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for FetchUrlTool {
+    type Params = FetchUrlParams;
+    type Result = FetchUrlResult;
+
+    fn name(&self) -> &str {
+        "fetch_url"
+    }
+
+    fn description(&self) -> &str {
+        "Download a web page and return its readable text without navigation, scripts and \
+         other boilerplate. Use it to read documentation, articles or search results."
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchemaBuilder::new("fetch_url", "Download a web page as readable text")
+            .param("url", "string")
+            .description("url", "The http(s) URL to fetch")
+            .required("url")
+            .param("max_chars", "integer")
+            .description(
+                "max_chars",
+                "Maximum characters of text to return (default: 20000)",
+            )
+            .build()
+    }
+
+    async fn execute(&self, params: Self::Params) -> Result<Self::Result, String> {
+        fetch(&params.url, params.max_chars.unwrap_or(DEFAULT_MAX_CHARS)).await
+    }
+}
+
+/// Download `url` and extract its readable text, keeping at most `max_chars`
+pub async fn fetch(url: &str, max_chars: usize) -> Result<FetchUrlResult, String> {
+    let mut url = reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    let mut redirects = 0;
+    let response = loop {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Only http(s) URLs can be fetched: {}", url));
+        }
+        let host = url.host_str().unwrap_or_default().to_string();
+        let addrs = public_addrs(&url).await?;
+        // Redirects are followed here so each target is checked too
+        let client = reqwest::Client::builder()
+            .user_agent("Mozilla/5.0 (compatible; ARULA-CLI/1.0)")
+            .timeout(FETCH_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve_to_addrs(&host, &addrs)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let response = client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+        if !response.status().is_redirection() {
+            break response;
+        }
+        if redirects == MAX_REDIRECTS {
+            return Err(format!("{} redirected more than {} times", url, MAX_REDIRECTS));
+        }
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| format!("{} redirected without a location", url))?;
+        url = url
+            .join(location)
+            .map_err(|e| format!("Invalid redirect from {}: {}", url, e))?;
+        redirects += 1;
+    };
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{} returned {}", url, status));
+    }
+    let final_url = response.url().to_string();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("text/html")
+        .to_string();
+    let is_html = content_type.contains("html");
+    if !is_html && !is_text(&content_type) {
+        return Err(format!("Not a text page ({})", content_type));
+    }
+
+    let mut body = Vec::new();
+    let mut truncated = false;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Download failed: {}", e))?;
+        let room = MAX_DOWNLOAD_BYTES - body.len();
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            truncated = true;
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let raw = String::from_utf8_lossy(&body);
+
+    let (title, text) = if is_html {
+        extract_readable(&raw)
+    } else {
+        (None, raw.trim().to_string())
+    };
+    let text = match text.char_indices().nth(max_chars) {
+        Some((cut, _)) => {
+            truncated = true;
+            format!("{}\n[truncated]", &text[..cut])
+        }
+        None => text,
+    };
+    Ok(FetchUrlResult {
+        url: final_url,
+        title,
+        content_type,
+        text,
+        truncated,
+    })
+}
+
+/// Addresses `url`'s host resolves to, or an error if any of them isn't public
+async fn public_addrs(url: &reqwest::Url) -> Result<Vec<SocketAddr>, String> {
+    let host = url.host_str().ok_or_else(|| format!("No host in {}", url))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let lookup = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((lookup, port))
+        .await
+        .map_err(|e| format!("Could not resolve {}: {}", host, e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("Could not resolve {}", host));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+        return Err(format!(
+            "Refusing to fetch {}: {} is a local or private address",
+            url,
+            addr.ip()
+        ));
+    }
+    Ok(addrs)
+}
+
+/// Whether `ip` is on the public internet: not loopback, private, link-local
+/// (where cloud metadata services live), shared, unspecified or broadcast
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || a == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                // Unique local (fc00::/7) and link-local (fe80::/10)
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+fn is_text(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || ["json", "xml", "markdown", "yaml", "javascript"]
+            .iter()
+            .any(|kind| content_type.contains(kind))
+}
+
+static TITLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+static COMMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<!--.*?-->").unwrap());
+static BOILERPLATE: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    BOILERPLATE_TAGS
+        .iter()
+        .map(|tag| Regex::new(&format!(r"(?is)<{0}\b[^>]*>.*?</{0}\s*>", tag)).unwrap())
+        .collect()
+});
+static HEADING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<h([1-6])\b[^>]*>").unwrap());
+static LIST_ITEM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<li\b[^>]*>").unwrap());
+static LINE_BREAK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<br\s*/?>").unwrap());
+static BLOCK_END: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)</(p|div|section|article|main|h[1-6]|ul|ol|tr|table|blockquote|pre|dd|dt)\s*>|<(p|hr|tr|pre|blockquote)\b[^>]*>").unwrap()
+});
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static NUMERIC_ENTITY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&#(x[0-9a-fA-F]+|[0-9]+);").unwrap());
+
+/// Title and readable text of an HTML page
+pub fn extract_readable(html: &str) -> (Option<String>, String) {
+    let title = TITLE
+        .captures(html)
+        .map(|c| collapse_spaces(&decode_entities(&c[1])))
+        .filter(|t| !t.is_empty());
+
+    let mut html = COMMENT.replace_all(html, "").into_owned();
+    for re in BOILERPLATE.iter() {
+        html = re.replace_all(&html, "").into_owned();
+    }
+    let content = ["article", "main", "body"]
+        .iter()
+        .find_map(|tag| inner(&html, tag))
+        .unwrap_or(&html);
+
+    let text = HEADING.replace_all(content, |c: &regex::Captures| {
+        let level: usize = c[1].parse().unwrap_or(1);
+        format!("\n\n{} ", "#".repeat(level))
+    });
+    let text = LIST_ITEM.replace_all(&text, "\n- ");
+    let text = LINE_BREAK.replace_all(&text, "\n");
+    let text = BLOCK_END.replace_all(&text, "\n\n");
+    let text = TAG.replace_all(&text, "");
+    let text = decode_entities(&text);
+
+    // One paragraph per non-empty line, without repeated blank lines
+    let mut out = String::new();
+    for line in text.lines().map(collapse_spaces) {
+        if line.is_empty() || line == "-" {
+            if !out.is_empty() && !out.ends_with("\n\n") {
+                out.push('\n');
+            }
+            continue;
+        }
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&line);
+    }
+    (title, out.trim().to_string())
+}
+
+/// Content between the first opening and the last closing `tag`
+fn inner<'a>(html: &'a str, tag: &str) -> Option<&'a str> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find(&format!("<{}", tag))?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = lower
+        .rfind(&format!("</{}", tag))
+        .filter(|&end| end >= start)?;
+    Some(&html[start..end])
+}
+
+fn collapse_spaces(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    let text = NUMERIC_ENTITY.replace_all(text, |c: &regex::Captures| {
+        let code = match c[1].strip_prefix('x') {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => c[1].parse().ok(),
+        };
+        code.and_then(char::from_u32)
+            .map(String::from)
+            .unwrap_or_default()
+    });
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&mdash;", "—")
+        .replace("&ndash;", "–")
+        .replace("&hellip;", "…")
+        .replace("&amp;", "&")
+}
+
+/// The link in a prompt that is just a pasted URL, optionally with a short
+/// question around it
+pub fn pasted_url(prompt: &str) -> Option<&str> {
+    let prompt = prompt.trim();
+    if prompt.len() > MAX_PASTED_PROMPT_CHARS || prompt.contains('\n') {
+        return None;
+    }
+    let mut urls = prompt
+        .split_whitespace()
+        .map(|token| token.trim_matches(|c| matches!(c, '<' | '>' | '(' | ')' | '"' | '\'')))
+        .filter(|token| token.starts_with("http://") || token.starts_with("https://"));
+    let url = urls.next()?;
+    // Several links are more likely a message about them than a paste
+    urls.next().is_none().then_some(url)
+}
+
+/// If `prompt` is a pasted link, fetch the page and send it along: `prompt`
+/// becomes the expanded prompt, and so does the last message of `history`
+/// when it is the prompt as typed. A failed fetch leaves both as they were.
+pub async fn expand_pasted_link(prompt: &mut String, history: Option<&mut Vec<ChatMessage>>) {
+    let Some(url) = pasted_url(prompt).map(str::to_string) else {
+        return;
+    };
+    match fetch(&url, DEFAULT_MAX_CHARS).await {
+        Ok(page) => {
+            let expanded = prompt_with_page(prompt, &page);
+            if let Some(last) = history.and_then(|h| h.last_mut()) {
+                if last.role == "user" && last.content.as_deref() == Some(prompt.as_str()) {
+                    last.content = Some(expanded.clone());
+                }
+            }
+            *prompt = expanded;
+        }
+        Err(err) => tracing::warn!("Could not fetch pasted link {}: {}", url, err),
+    }
+}
+
+/// The prompt sent in place of `prompt` once its pasted link was fetched
+pub fn prompt_with_page(prompt: &str, page: &FetchUrlResult) -> String {
+    let title = page
+        .title
+        .as_deref()
+        .map(|t| format!("Title: {}\n", t))
+        .unwrap_or_default();
+    format!(
+        "{}\n\n<fetched_page url=\"{}\">\n{}{}\n</fetched_page>",
+        prompt.trim(),
+        page.url,
+        title,
+        page.text
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_internal_addresses_are_refused() {
+        for ip in ["127.0.0.1", "10.0.0.8", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0"] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["::1", "fd00:ec2::254", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(is_public("93.184.216.34".parse().unwrap()));
+        assert!(is_public("2606:4700::1111".parse().unwrap()));
+
+        let err = fetch("http://169.254.169.254/latest/meta-data/", 100)
+            .await
+            .unwrap_err();
+        assert!(err.contains("local or private"), "{}", err);
+        let err = fetch("http://localhost:9/", 100).await.unwrap_err();
+        assert!(err.contains("local or private"), "{}", err);
+        assert!(fetch("file:///etc/passwd", 100).await.is_err());
+    }
+
+    #[test]
+    fn test_extract_readable() {
+        let html = r#"<html><head><title>Release notes &amp; more</title>
+            <style>body { color: red }</style><script>track();</script></head>
+            <body><nav><a href="/">Home</a> | <a href="/docs">Docs</a></nav>
+            <header>Site header</header>
+            <article><h1>Version 2.0</h1><p>It's   <b>faster</b>&nbsp;now.</p>
+            <!-- comment --><ul><li>New parser</li><li>Fewer bugs &#8212; really</li></ul>
+            <p>Line one<br>Line two</p></article>
+            <footer>© 2026</footer></body></html>"#;
+        let (title, text) = extract_readable(html);
+        assert_eq!(title.as_deref(), Some("Release notes & more"));
+        assert_eq!(
+            text,
+            "# Version 2.0\n\nIt's faster now.\n\n- New parser\n- Fewer bugs — really\n\nLine one\nLine two"
+        );
+    }
+
+    #[test]
+    fn test_pasted_url() {
+        assert_eq!(
+            pasted_url("https://example.com/post"),
+            Some("https://example.com/post")
+        );
+        assert_eq!(
+            pasted_url("summarize <https://example.com/a?b=1>"),
+            Some("https://example.com/a?b=1")
+        );
+        assert_eq!(pasted_url("compare https://a.com and https://b.com"), None);
+        assert_eq!(
+            pasted_url("why does this fail?\ncurl https://localhost"),
+            None
+        );
+        assert_eq!(pasted_url("no links here"), None);
+    }
+}
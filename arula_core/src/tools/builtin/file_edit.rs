@@ -10,6 +10,9 @@
 //! This module re-exports the types for organization.
 
 use crate::api::agent::{Tool, ToolSchema, ToolSchemaBuilder};
+use crate::tools::edit_review;
+use crate::tools::sandbox::{self, Access};
+use crate::utils::snapshots;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
@@ -52,6 +55,9 @@ pub struct FileEditResult {
     pub backup_path: Option<String>,
     /// Diff showing changes
     pub diff: Option<String>,
+    /// Snapshot of the file before the edit, for undo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<String>,
 }
 
 /// File editing tool
@@ -122,6 +128,7 @@ impl Tool for FileEditTool {
         if path.trim().is_empty() {
             return Err("File path cannot be empty".to_string());
         }
+        sandbox::check(self.name(), path, Access::Write).await?;
 
         let edit_type = params.edit_type.as_deref().unwrap_or("replace");
 
@@ -170,6 +177,14 @@ impl Tool for FileEditTool {
             }
             _ => return Err(format!("Unknown operation type: {}", edit_type)),
         };
+        let edited =
+            edit_review::review(self.name(), path, Some(&old_content), &new_content).await?;
+        let note = if edited.is_some() {
+            " with the user's changes; read it again before editing it further"
+        } else {
+            ""
+        };
+        let new_content = edited.unwrap_or(new_content);
 
         // Generate diff using the diff crate with 3 lines of context
         let diff_result = diff::lines(&old_content, &new_content);
@@ -232,17 +247,19 @@ impl Tool for FileEditTool {
         };
 
         // Write new content
+        let snapshot_id = snapshots::snapshot_before_write(self.name(), path);
         fs::write(path, &new_content)
             .map_err(|e| format!("Failed to write file '{}': {}", path, e))?;
 
         Ok(FileEditResult {
             success: true,
-            message: format!("Successfully edited '{}'", path),
+            message: format!("Successfully edited '{}'{}", path, note),
             lines_changed: Some(new_content.lines().count()),
             lines_added: if lines_added > 0 { Some(lines_added) } else { None },
             lines_removed: if lines_removed > 0 { Some(lines_removed) } else { None },
             backup_path: None,
             diff: diff_string,
+            snapshot_id,
         })
     }
 }
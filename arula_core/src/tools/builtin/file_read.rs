@@ -4,6 +4,7 @@
 //! Uses memory mapping for large files for better performance.
 
 use crate::api::agent::{Tool, ToolSchema, ToolSchemaBuilder};
+use crate::tools::sandbox::{self, Access};
 use async_trait::async_trait;
 use memmap2::MmapOptions;
 use serde::{Deserialize, Serialize};
@@ -112,6 +113,7 @@ impl Tool for FileReadTool {
         if path.trim().is_empty() {
             return Err("File path cannot be empty".to_string());
         }
+        sandbox::check(self.name(), &path, Access::Read).await?;
 
         let file =
             File::open(&path).map_err(|e| format!("Failed to open file '{}': {}", path, e))?;
@@ -3,6 +3,9 @@
 //! This tool creates or overwrites files with new content.
 
 use crate::api::agent::{Tool, ToolSchema, ToolSchemaBuilder};
+use crate::tools::edit_review;
+use crate::tools::sandbox::{self, Access};
+use crate::utils::snapshots;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -26,6 +29,9 @@ pub struct WriteFileResult {
     pub message: String,
     /// Number of bytes written
     pub bytes_written: usize,
+    /// Snapshot of the previous file state, for undo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<String>,
 }
 
 /// File writing tool
@@ -81,6 +87,17 @@ impl Tool for WriteFileTool {
         if path.trim().is_empty() {
             return Err("File path cannot be empty".to_string());
         }
+        sandbox::check(self.name(), &path, Access::Write).await?;
+        // A file that isn't UTF-8 is still replaced, so review it as a change
+        let current = match fs::read(&path) {
+            Ok(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("Failed to read file '{}': {}", path, e)),
+        };
+        let edited = edit_review::review(self.name(), &path, current.as_deref(), &content).await?;
+        let note = if edited.is_some() { " (edited by the user)" } else { "" };
+        let content = edited.unwrap_or(content);
+        let snapshot_id = snapshots::snapshot_before_write(self.name(), &path);
 
         // Create parent directories if they don't exist
         if let Some(parent) = Path::new(&path).parent() {
@@ -97,8 +114,12 @@ impl Tool for WriteFileTool {
 
         Ok(WriteFileResult {
             success: true,
-            message: format!("Successfully wrote {} bytes to '{}'", bytes_written, path),
+            message: format!(
+                "Successfully wrote {} bytes to '{}'{}",
+                bytes_written, path, note
+            ),
             bytes_written,
+            snapshot_id,
         })
     }
 }
@@ -4,6 +4,7 @@
 //! and recursive listing.
 
 use crate::api::agent::{Tool, ToolSchema, ToolSchemaBuilder};
+use crate::tools::sandbox::{self, Access};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
@@ -189,6 +190,8 @@ impl Tool for ListDirectoryTool {
             recursive,
         } = params;
 
+        sandbox::check(self.name(), &path, Access::Read).await?;
+
         let show_hidden = show_hidden.unwrap_or(false);
         let recursive = recursive.unwrap_or(false);
 
@@ -3,11 +3,15 @@
 //! This module contains all built-in tools that are available by default:
 //!
 //! - `bash` - Execute shell commands
+//! - `code_outline` - List project symbols from the tree-sitter index
+//! - `fetch_url` - Download web pages as readable text
 //! - `file_read` - Read file contents
 //! - `file_write` - Write/create files
 //! - `file_edit` - Edit existing files
 //! - `list_dir` - List directory contents
+//! - `screen_capture` - Take screenshots for vision input
 //! - `search` - Search files for patterns
+//! - `transcribe` - Transcribe audio files with Whisper
 //! - `web_search` - Search the web
 //! - `visioneer` - Vision/screenshot capabilities
 //! - `question` - Ask clarifying questions
@@ -27,13 +31,17 @@
 //! 4. Export from this module and add to `create_basic_tool_registry()`
 
 pub mod bash;
+pub mod code_outline;
+pub mod fetch_url;
 pub mod file_edit;
 pub mod file_read;
 pub mod file_write;
 pub mod find_files;
 pub mod list_dir;
 pub mod question;
+pub mod screen_capture;
 pub mod search;
+pub mod transcribe;
 pub mod web_search;
 
 // Re-export all tools for public API
@@ -41,6 +49,10 @@ pub mod web_search;
 #[allow(unused_imports)]
 pub use bash::{execute_bash_streaming, BashParams, BashResult, BashTool};
 #[allow(unused_imports)]
+pub use code_outline::{CodeOutlineParams, CodeOutlineResult, CodeOutlineTool};
+#[allow(unused_imports)]
+pub use fetch_url::{FetchUrlParams, FetchUrlResult, FetchUrlTool};
+#[allow(unused_imports)]
 pub use file_edit::{FileEditParams, FileEditResult, FileEditTool};
 #[allow(unused_imports)]
 pub use file_read::{FileReadParams, FileReadResult, FileReadTool};
@@ -53,6 +65,12 @@ pub use list_dir::{DirectoryEntry, ListDirParams, ListDirResult, ListDirectoryTo
 #[allow(unused_imports)]
 pub use question::{QuestionParams, QuestionResult, QuestionTool, QUESTION_HANDLER, QuestionHandler, Question, Answer};
 #[allow(unused_imports)]
+pub use screen_capture::{capture, CaptureScreenParams, CaptureScreenResult, CaptureScreenTool};
+#[allow(unused_imports)]
 pub use search::{FileMatch, SearchMatch, SearchParams, SearchResult, SearchTool};
 #[allow(unused_imports)]
+pub use transcribe::{
+    TranscribeAudioParams, TranscribeAudioResult, TranscribeAudioTool, TranscriptSegment,
+};
+#[allow(unused_imports)]
 pub use web_search::{WebSearchParams, WebSearchResult, WebSearchResultItem, WebSearchTool};
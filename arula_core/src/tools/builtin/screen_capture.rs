@@ -0,0 +1,326 @@
+//! Screenshot tool
+//!
+//! Captures the screen, a fixed region or a region the user selects with the
+//! platform's screenshot utility and saves it under `~/.arula/screenshots`.
+//! The result's `image_path` makes the agent loop attach the picture to the
+//! next request as vision input, so the model can look at what the user sees.
+//!
+//! Screenshots go to the provider without asking, so the tool is only
+//! registered once a frontend opts in with `set_enabled`: the desktop app does
+//! when `screen_capture` is set in the config.
+
+use crate::api::agent::{Tool, ToolSchema, ToolSchemaBuilder};
+use crate::tools::visioneer::CaptureRegion;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the model may take screenshots in this process
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether `capture_screen` is offered to the model
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Parameters for the screenshot tool
+#[derive(Debug, Deserialize)]
+pub struct CaptureScreenParams {
+    /// Part of the screen to capture, in pixels (default: whole screen)
+    pub region: Option<CaptureRegion>,
+    /// Let the user select the region with the mouse (default: false)
+    pub interactive: Option<bool>,
+}
+
+/// Result from the screenshot tool
+#[derive(Debug, Serialize)]
+pub struct CaptureScreenResult {
+    /// Saved PNG, attached to the next request
+    pub image_path: String,
+    pub bytes: u64,
+}
+
+/// Tool capturing the screen for vision input
+pub struct CaptureScreenTool;
+
+impl CaptureScreenTool {
+    /// Create a new CaptureScreenTool instance
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for CaptureScreenTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for CaptureScreenTool {
+    type Params = CaptureScreenParams;
+    type Result = CaptureScreenResult;
+
+    fn name(&self) -> &str {
+        "capture_screen"
+    }
+
+    fn description(&self) -> &str {
+        "Take a screenshot of the screen or a region of it. The image is attached to your \
+         next turn so you can see it; use it when the user asks about something on screen."
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchemaBuilder::new("capture_screen", "Take a screenshot and look at it")
+            .param("region", "object")
+            .description(
+                "region",
+                "Region to capture in pixels: {x, y, width, height} (default: whole screen)",
+            )
+            .param("interactive", "boolean")
+            .description(
+                "interactive",
+                "Let the user select the region with the mouse (default: false)",
+            )
+            .build()
+    }
+
+    async fn execute(&self, params: Self::Params) -> Result<Self::Result, String> {
+        let interactive = params.interactive.unwrap_or(false);
+        let path = tokio::task::spawn_blocking(move || capture(params.region, interactive))
+            .await
+            .map_err(|e| format!("Screenshot failed: {}", e))??;
+        let bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        Ok(CaptureScreenResult {
+            image_path: path.to_string_lossy().into_owned(),
+            bytes,
+        })
+    }
+}
+
+/// Capture the screen (or `region`, or a region the user selects when
+/// `interactive`) to a new PNG under `~/.arula/screenshots`
+pub fn capture(region: Option<CaptureRegion>, interactive: bool) -> Result<PathBuf, String> {
+    let dir = dirs::home_dir()
+        .ok_or("Could not find home directory")?
+        .join(".arula")
+        .join("screenshots");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!(
+        "{}.png",
+        chrono::Local::now().format("%Y%m%d-%H%M%S-%3f")
+    ));
+
+    let selection = match (interactive, region) {
+        (true, _) => Selection::Interactive,
+        (false, Some(region)) => Selection::Region(region),
+        (false, None) => Selection::Screen,
+    };
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let mut tried = Vec::new();
+    for (program, args) in commands(std::env::consts::OS, wayland, &selection, &path) {
+        // grim can't select a region itself; slurp prints the selected geometry
+        let args = match args.iter().position(|a| a == SLURP_GEOMETRY) {
+            Some(i) => match run(&mut Command::new("slurp")) {
+                Ok(geometry) => {
+                    let mut args = args.clone();
+                    args[i] = geometry.trim().to_string();
+                    args
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    tried.push("slurp".to_string());
+                    continue;
+                }
+                Err(e) => return Err(format!("Region selection failed: {}", e)),
+            },
+            None => args,
+        };
+        match run(Command::new(&program).args(&args)) {
+            Ok(_) if path.exists() => return Ok(path),
+            // Selection cancelled by the user
+            Ok(_) => return Err("No screenshot was taken".to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => tried.push(program),
+            Err(e) => return Err(format!("{} failed: {}", program, e)),
+        }
+    }
+    Err(format!(
+        "No screenshot utility found (tried {})",
+        tried.join(", ")
+    ))
+}
+
+/// What to capture
+enum Selection {
+    Screen,
+    Region(CaptureRegion),
+    Interactive,
+}
+
+/// Placeholder argument replaced by the region selected with `slurp`
+const SLURP_GEOMETRY: &str = "{slurp}";
+
+/// Screenshot commands to try in order, for the given platform
+fn commands(
+    os: &str,
+    wayland: bool,
+    selection: &Selection,
+    path: &Path,
+) -> Vec<(String, Vec<String>)> {
+    let out = path.to_string_lossy().into_owned();
+    let cmd = |program: &str, args: Vec<String>| (program.to_string(), args);
+    match os {
+        "macos" => {
+            let mut args = vec!["-x".to_string()];
+            match selection {
+                Selection::Screen => {}
+                Selection::Region(r) => {
+                    args.push(format!("-R{},{},{},{}", r.x, r.y, r.width, r.height))
+                }
+                Selection::Interactive => args.push("-i".to_string()),
+            }
+            args.push(out);
+            vec![cmd("screencapture", args)]
+        }
+        "windows" => {
+            // No selection UI without extra tools: interactive captures the whole screen
+            let bounds = match selection {
+                Selection::Region(r) => format!(
+                    "New-Object Drawing.Rectangle {},{},{},{}",
+                    r.x, r.y, r.width, r.height
+                ),
+                _ => "[Windows.Forms.SystemInformation]::VirtualScreen".to_string(),
+            };
+            let script = format!(
+                "Add-Type -AssemblyName System.Windows.Forms,System.Drawing; $b = {}; \
+                 $bmp = New-Object Drawing.Bitmap $b.Width, $b.Height; \
+                 $g = [Drawing.Graphics]::FromImage($bmp); \
+                 $g.CopyFromScreen($b.Location, [Drawing.Point]::Empty, $b.Size); \
+                 $bmp.Save('{}', [Drawing.Imaging.ImageFormat]::Png)",
+                bounds,
+                out.replace('\'', "''")
+            );
+            vec![cmd(
+                "powershell",
+                vec!["-NoProfile".into(), "-Command".into(), script],
+            )]
+        }
+        _ => {
+            let mut commands = Vec::new();
+            if wayland {
+                let mut args = Vec::new();
+                match selection {
+                    Selection::Screen => {}
+                    Selection::Region(r) => args.extend([
+                        "-g".to_string(),
+                        format!("{},{} {}x{}", r.x, r.y, r.width, r.height),
+                    ]),
+                    Selection::Interactive => {
+                        args.extend(["-g".to_string(), SLURP_GEOMETRY.to_string()])
+                    }
+                }
+                args.push(out.clone());
+                commands.push(cmd("grim", args));
+            }
+            match selection {
+                Selection::Screen => {
+                    commands.push(cmd("gnome-screenshot", vec!["-f".into(), out.clone()]));
+                    commands.push(cmd(
+                        "spectacle",
+                        vec![
+                            "-b".into(),
+                            "-n".into(),
+                            "-f".into(),
+                            "-o".into(),
+                            out.clone(),
+                        ],
+                    ));
+                    commands.push(cmd("scrot", vec!["-o".into(), out.clone()]));
+                    commands.push(cmd("import", vec!["-window".into(), "root".into(), out]));
+                }
+                Selection::Region(r) => {
+                    let geometry = format!("{},{},{},{}", r.x, r.y, r.width, r.height);
+                    commands.push(cmd(
+                        "scrot",
+                        vec!["-o".into(), "-a".into(), geometry, out.clone()],
+                    ));
+                    let crop = format!("{}x{}+{}+{}", r.width, r.height, r.x, r.y);
+                    commands.push(cmd(
+                        "import",
+                        vec!["-window".into(), "root".into(), "-crop".into(), crop, out],
+                    ));
+                }
+                Selection::Interactive => {
+                    commands.push(cmd(
+                        "gnome-screenshot",
+                        vec!["-a".into(), "-f".into(), out.clone()],
+                    ));
+                    commands.push(cmd(
+                        "spectacle",
+                        vec![
+                            "-b".into(),
+                            "-n".into(),
+                            "-r".into(),
+                            "-o".into(),
+                            out.clone(),
+                        ],
+                    ));
+                    commands.push(cmd("scrot", vec!["-o".into(), "-s".into(), out.clone()]));
+                    commands.push(cmd("import", vec![out]));
+                }
+            }
+            commands
+        }
+    }
+}
+
+/// Run a command to completion, returning its stdout
+fn run(command: &mut Command) -> std::io::Result<String> {
+    let output = command.output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(std::io::Error::other(match stderr.trim() {
+            "" => format!("exited with {}", output.status),
+            stderr => stderr.to_string(),
+        }));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands() {
+        let path = Path::new("/tmp/shot.png");
+        let region = Selection::Region(CaptureRegion {
+            x: 10,
+            y: 20,
+            width: 300,
+            height: 200,
+        });
+
+        let mac = commands("macos", false, &region, path);
+        assert_eq!(mac[0].1, ["-x", "-R10,20,300,200", "/tmp/shot.png"]);
+
+        let wayland = commands("linux", true, &region, path);
+        assert_eq!(wayland[0].0, "grim");
+        assert_eq!(wayland[0].1, ["-g", "10,20 300x200", "/tmp/shot.png"]);
+        assert!(wayland
+            .iter()
+            .any(|(p, a)| p == "import" && a.contains(&"300x200+10+20".to_string())));
+
+        let select = commands("linux", true, &Selection::Interactive, path);
+        assert_eq!(select[0].1[1], SLURP_GEOMETRY);
+        assert!(commands("linux", false, &Selection::Screen, path)
+            .iter()
+            .all(|(p, _)| p != "grim"));
+    }
+}
@@ -0,0 +1,427 @@
+//! Audio transcription tool
+//!
+//! Transcribes an audio file into timestamped text, with a local Whisper
+//! install (the `whisper` command of openai-whisper) when one is on the PATH
+//! and the active provider's OpenAI-compatible `/audio/transcriptions`
+//! endpoint otherwise. Both report segments with start and end times, which
+//! are returned as-is and as `[mm:ss] text` lines for meeting notes.
+//!
+//! The file must pass the sandbox like any other read. The endpoint comes from
+//! the config the frontend passed to `configure`.
+
+use crate::api::agent::{Tool, ToolSchema, ToolSchemaBuilder};
+use crate::tools::sandbox::{self, Access};
+use crate::utils::config::Config;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Largest file the transcription endpoints accept
+const MAX_UPLOAD_BYTES: u64 = 25 * 1024 * 1024;
+
+/// Model used for the provider's transcription endpoint
+const API_MODEL: &str = "whisper-1";
+
+/// Whisper model used locally unless `ARULA_WHISPER_MODEL` names another
+const DEFAULT_LOCAL_MODEL: &str = "base";
+
+const API_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Base URL and key of the active provider, set by `configure`
+static ENDPOINT: RwLock<Option<(String, String)>> = RwLock::new(None);
+
+/// Use the active provider of `config` for API transcriptions
+pub fn configure(config: &Config) {
+    if let Ok(mut endpoint) = ENDPOINT.write() {
+        *endpoint = Some((config.get_api_url(), config.get_api_key()));
+    }
+}
+
+/// Parameters for the transcription tool
+#[derive(Debug, Deserialize)]
+pub struct TranscribeAudioParams {
+    /// Audio file to transcribe
+    pub path: String,
+    /// "local", "api" or "auto" (default: local when Whisper is installed)
+    pub backend: Option<String>,
+    /// Spoken language as an ISO-639-1 code (default: detected)
+    pub language: Option<String>,
+}
+
+/// A stretch of speech with its position in the recording
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptSegment {
+    /// Seconds from the start of the recording
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// Result from the transcription tool
+#[derive(Debug, Serialize)]
+pub struct TranscribeAudioResult {
+    pub path: String,
+    /// "local" or "api"
+    pub backend: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub segments: Vec<TranscriptSegment>,
+    /// One `[mm:ss] text` line per segment
+    pub text: String,
+}
+
+/// Tool transcribing audio files with Whisper
+pub struct TranscribeAudioTool;
+
+impl TranscribeAudioTool {
+    /// Create a new TranscribeAudioTool instance
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for TranscribeAudioTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for TranscribeAudioTool {
+    type Params = TranscribeAudioParams;
+    type Result = TranscribeAudioResult;
+
+    fn name(&self) -> &str {
+        "transcribe_audio"
+    }
+
+    fn description(&self) -> &str {
+        "Transcribe an audio file (mp3, wav, m4a, ogg, webm, flac) into text with timestamps. \
+         Use it for recordings of meetings, calls or voice notes."
+    }
+
+    fn schema(&self) -> ToolSchema {
+        ToolSchemaBuilder::new(
+            "transcribe_audio",
+            "Transcribe an audio file with timestamps",
+        )
+        .param("path", "string")
+        .description("path", "Path to the audio file")
+        .required("path")
+        .param("backend", "string")
+        .description(
+            "backend",
+            "\"local\" for a local Whisper install, \"api\" for the provider's endpoint \
+                 (default: local when installed)",
+        )
+        .param("language", "string")
+        .description(
+            "language",
+            "Spoken language as an ISO-639-1 code like \"en\" (default: detected)",
+        )
+        .build()
+    }
+
+    async fn execute(&self, params: Self::Params) -> Result<Self::Result, String> {
+        sandbox::check(self.name(), &params.path, Access::Read).await?;
+        let path = Path::new(&params.path);
+        if !path.is_file() {
+            return Err(format!("Audio file not found: {}", params.path));
+        }
+        let backend = match params.backend.as_deref().unwrap_or("auto") {
+            "auto" if local_whisper_available() => "local",
+            "auto" => "api",
+            backend @ ("local" | "api") => backend,
+            other => return Err(format!("Unknown backend: {}", other)),
+        };
+
+        let transcript = if backend == "local" {
+            let path = path.to_path_buf();
+            let language = params.language.clone();
+            tokio::task::spawn_blocking(move || transcribe_local(&path, language.as_deref()))
+                .await
+                .map_err(|e| format!("Transcription failed: {}", e))??
+        } else {
+            transcribe_api(path, params.language.as_deref()).await?
+        };
+
+        let (language, segments) = parse_transcript(&transcript)?;
+        Ok(TranscribeAudioResult {
+            path: params.path,
+            backend: backend.to_string(),
+            language: language.or(params.language),
+            text: timestamped_text(&segments),
+            segments,
+        })
+    }
+}
+
+fn local_whisper_available() -> bool {
+    Command::new("whisper")
+        .arg("--help")
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// Run the local `whisper` command, returning its JSON output
+fn transcribe_local(path: &Path, language: Option<&str>) -> Result<Value, String> {
+    let out_dir = tempfile::tempdir().map_err(|e| format!("Failed to create temp dir: {}", e))?;
+    let model =
+        std::env::var("ARULA_WHISPER_MODEL").unwrap_or_else(|_| DEFAULT_LOCAL_MODEL.to_string());
+    let mut command = Command::new("whisper");
+    command
+        .arg(path)
+        .args([
+            "--model",
+            &model,
+            "--output_format",
+            "json",
+            "--verbose",
+            "False",
+        ])
+        .arg("--output_dir")
+        .arg(out_dir.path());
+    if let Some(language) = language {
+        command.args(["--language", language]);
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run whisper: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "whisper failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let json_path = out_dir.path().join(format!("{}.json", stem));
+    let json = std::fs::read_to_string(&json_path)
+        .map_err(|e| format!("No whisper output at {}: {}", json_path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Invalid whisper output: {}", e))
+}
+
+/// Upload the file to the active provider's transcription endpoint
+async fn transcribe_api(path: &Path, language: Option<&str>) -> Result<Value, String> {
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > MAX_UPLOAD_BYTES {
+        return Err(format!(
+            "{} is {} MB; the transcription endpoint accepts at most {} MB",
+            path.display(),
+            size / (1024 * 1024),
+            MAX_UPLOAD_BYTES / (1024 * 1024)
+        ));
+    }
+    let audio = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let (api_url, api_key) = ENDPOINT
+        .read()
+        .ok()
+        .and_then(|endpoint| endpoint.clone())
+        .ok_or("No provider is configured for API transcription")?;
+    let url = format!("{}/audio/transcriptions", api_url.trim_end_matches('/'));
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut fields = vec![
+        ("model", API_MODEL),
+        ("response_format", "verbose_json"),
+        ("timestamp_granularities[]", "segment"),
+    ];
+    if let Some(language) = language {
+        fields.push(("language", language));
+    }
+    let boundary = format!("arula-{}", uuid::Uuid::new_v4().simple());
+    let body = multipart_body(&boundary, &fields, &file_name, audio_mime(path), &audio);
+
+    let client = reqwest::Client::builder()
+        .timeout(API_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let response = client
+        .post(&url)
+        .bearer_auth(api_key)
+        .header(
+            "Content-Type",
+            format!("multipart/form-data; boundary={}", boundary),
+        )
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("Transcription request failed: {}", e))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    if !status.is_success() {
+        return Err(format!(
+            "Transcription endpoint returned {}: {}",
+            status,
+            text.trim()
+        ));
+    }
+    serde_json::from_str(&text).map_err(|e| format!("Invalid transcription response: {}", e))
+}
+
+fn audio_mime(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "mp3" | "mpga" | "mpeg" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "m4a" => "audio/mp4",
+        "mp4" => "video/mp4",
+        "ogg" | "oga" => "audio/ogg",
+        "webm" => "audio/webm",
+        "flac" => "audio/flac",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A `multipart/form-data` body with text `fields` and one `file` part
+fn multipart_body(
+    boundary: &str,
+    fields: &[(&str, &str)],
+    file_name: &str,
+    mime: &str,
+    file: &[u8],
+) -> Vec<u8> {
+    let mut body = Vec::with_capacity(file.len() + 1024);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+             Content-Type: {}\r\n\r\n",
+            boundary,
+            file_name.replace('"', "'"),
+            mime
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(file);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// Language and segments of a Whisper JSON transcript; local Whisper and
+/// `verbose_json` responses share this shape
+fn parse_transcript(json: &Value) -> Result<(Option<String>, Vec<TranscriptSegment>), String> {
+    let language = json
+        .get("language")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let segments: Vec<TranscriptSegment> = json
+        .get("segments")
+        .and_then(Value::as_array)
+        .map(|segments| {
+            segments
+                .iter()
+                .filter_map(|s| {
+                    Some(TranscriptSegment {
+                        start: s.get("start")?.as_f64()?,
+                        end: s.get("end")?.as_f64()?,
+                        text: s.get("text")?.as_str()?.trim().to_string(),
+                    })
+                })
+                .filter(|s| !s.text.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    if !segments.is_empty() {
+        return Ok((language, segments));
+    }
+    // Endpoints without segment timestamps still return the text
+    match json.get("text").and_then(Value::as_str).map(str::trim) {
+        Some(text) if !text.is_empty() => Ok((
+            language,
+            vec![TranscriptSegment {
+                start: 0.0,
+                end: 0.0,
+                text: text.to_string(),
+            }],
+        )),
+        _ => Err("The transcript is empty".to_string()),
+    }
+}
+
+/// `[mm:ss] text` per segment, with hours once the recording passes one
+fn timestamped_text(segments: &[TranscriptSegment]) -> String {
+    let long = segments.iter().any(|s| s.end >= 3600.0);
+    segments
+        .iter()
+        .map(|s| {
+            let secs = s.start as u64;
+            let stamp = if long {
+                format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+            } else {
+                format!("{:02}:{:02}", secs / 60, secs % 60)
+            };
+            format!("[{}] {}", stamp, s.text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_transcript() {
+        let json = json!({
+            "language": "en",
+            "text": "Hello everyone. Let's start.",
+            "segments": [
+                {"id": 0, "start": 0.0, "end": 2.5, "text": " Hello everyone."},
+                {"id": 1, "start": 65.2, "end": 67.0, "text": " Let's start."},
+                {"id": 2, "start": 67.0, "end": 68.0, "text": " "}
+            ]
+        });
+        let (language, segments) = parse_transcript(&json).unwrap();
+        assert_eq!(language.as_deref(), Some("en"));
+        assert_eq!(segments.len(), 2);
+        assert_eq!(
+            timestamped_text(&segments),
+            "[00:00] Hello everyone.\n[01:05] Let's start."
+        );
+
+        let (_, plain) = parse_transcript(&json!({"text": "Just text"})).unwrap();
+        assert_eq!(plain[0].text, "Just text");
+        assert!(parse_transcript(&json!({"text": ""})).is_err());
+    }
+
+    #[test]
+    fn test_multipart_body() {
+        let body = multipart_body(
+            "b",
+            &[("model", "whisper-1")],
+            "a.wav",
+            "audio/wav",
+            b"RIFF",
+        );
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.starts_with(
+            "--b\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\nwhisper-1\r\n"
+        ));
+        assert!(
+            body.contains("filename=\"a.wav\"\r\nContent-Type: audio/wav\r\n\r\nRIFF\r\n--b--\r\n")
+        );
+    }
+}
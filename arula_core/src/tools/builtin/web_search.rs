@@ -147,7 +147,7 @@ impl Tool for WebSearchTool {
 
             results.push(WebSearchResultItem {
                 title: decoded_title,
-                url,
+                url: result_url(&url),
                 description: String::new(),
             });
         }
@@ -162,3 +162,34 @@ impl Tool for WebSearchTool {
         })
     }
 }
+
+/// The target of a DuckDuckGo result link, which points at a redirect like
+/// `//duckduckgo.com/l/?uddg=<encoded url>&rut=...`
+fn result_url(href: &str) -> String {
+    let href = href.replace("&amp;", "&");
+    let target = href
+        .split_once("uddg=")
+        .map(|(_, rest)| rest.split('&').next().unwrap_or(rest))
+        .and_then(|encoded| urlencoding::decode(encoded).ok())
+        .map(|url| url.into_owned());
+    match target {
+        Some(url) => url,
+        None if href.starts_with("//") => format!("https:{}", href),
+        None => href,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_url() {
+        assert_eq!(
+            result_url("//duckduckgo.com/l/?uddg=https%3A%2F%2Fdocs.rs%2Fregex&amp;rut=abc"),
+            "https://docs.rs/regex"
+        );
+        assert_eq!(result_url("https://example.com/a"), "https://example.com/a");
+        assert_eq!(result_url("//example.com/a"), "https://example.com/a");
+    }
+}
@@ -0,0 +1,321 @@
+//! Applying code blocks from AI messages to files
+//!
+//! Models often answer with a whole file in a fenced block instead of
+//! calling a tool. When the fence names the file (```` ```rust src/main.rs ````,
+//! ```` ```rust:src/main.rs ```` or ```` ```rust path=src/main.rs ````), the
+//! block can be turned into an `edit_file` call (or `write_file` for a new
+//! file), previewed as a diff and applied through the tool, so the sandbox
+//! and undo snapshots apply as if the model had made the call itself.
+//!
+//! A block much shorter than the file it names is taken for an excerpt,
+//! unless the fence says `full`: it replaces only the lines between its first
+//! and last line as found in the file, and is refused when those can't be
+//! found exactly once.
+
+use crate::api::agent::Tool;
+use crate::tools::builtin::{FileEditTool, WriteFileTool};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Lines of unchanged context around each change in the preview diff
+const DIFF_CONTEXT: usize = 3;
+
+/// A block with fewer lines than this share of the file is an excerpt
+const EXCERPT_RATIO: f64 = 0.5;
+
+/// A fenced code block annotated with the file it belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileBlock {
+    pub path: String,
+    pub language: Option<String>,
+    pub code: String,
+    /// The fence marks the block as the whole file (```` ```rust src/main.rs full ````)
+    pub whole_file: bool,
+}
+
+/// Fenced code blocks of `markdown` that name a file
+pub fn file_blocks(markdown: &str) -> Vec<FileBlock> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(Option<(Option<String>, String)>, bool, Vec<&str>)> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        match &mut open {
+            None => {
+                if let Some(info) = trimmed.strip_prefix("```") {
+                    let whole_file = info.split_whitespace().any(|token| token == "full");
+                    open = Some((annotated_path(info), whole_file, Vec::new()));
+                }
+            }
+            Some((annotation, whole_file, lines)) => {
+                if trimmed.trim_end() == "```" {
+                    if let Some((language, path)) = annotation.take() {
+                        blocks.push(FileBlock {
+                            path,
+                            language,
+                            code: format!("{}\n", lines.join("\n")),
+                            whole_file: *whole_file,
+                        });
+                    }
+                    open = None;
+                } else {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+    blocks
+}
+
+/// Language and path from a fence info string
+fn annotated_path(info: &str) -> Option<(Option<String>, String)> {
+    let mut language = None;
+    for token in info.split_whitespace() {
+        let token = token.trim_matches(|c| c == '"' || c == '\'' || c == '`');
+        if let Some(path) = ["path=", "file=", "filename=", "title="]
+            .iter()
+            .find_map(|key| token.strip_prefix(key))
+        {
+            return Some((language, path.trim_matches('"').to_string()));
+        }
+        if let Some((lang, path)) = token.split_once(':').filter(|(_, p)| looks_like_path(p)) {
+            return Some((Some(lang.to_string()), path.to_string()));
+        }
+        if token != "full" && looks_like_path(token) {
+            return Some((language, token.to_string()));
+        }
+        language.get_or_insert_with(|| token.to_string());
+    }
+    None
+}
+
+/// `src/main.rs` or `Cargo.toml`, but not a bare language tag like `c++`
+fn looks_like_path(token: &str) -> bool {
+    let name = token.rsplit('/').next().unwrap_or(token);
+    !token.contains("://")
+        && (token.contains('/')
+            || name.rsplit_once('.').is_some_and(|(stem, ext)| {
+                !stem.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric())
+            }))
+}
+
+/// The tool call that would apply a block, with a preview of its effect
+#[derive(Debug, Clone)]
+pub struct ProposedEdit {
+    pub tool: &'static str,
+    pub args: Value,
+    /// Changed lines with context, prefixed with `+`, `-` or a space
+    pub diff: String,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+}
+
+/// Propose applying `block` to the file at `path` (the block's path resolved
+/// against the working directory). An excerpt that can't be placed in the
+/// file is refused with the reason.
+pub fn propose(block: &FileBlock, path: &Path) -> Result<ProposedEdit, String> {
+    let path_arg = path.to_string_lossy();
+    let Some(current) = std::fs::read_to_string(path).ok() else {
+        let (diff, lines_added, lines_removed) = line_diff("", &block.code);
+        return Ok(ProposedEdit {
+            tool: "write_file",
+            args: json!({ "path": path_arg, "content": block.code }),
+            diff,
+            lines_added,
+            lines_removed,
+        });
+    };
+
+    let block_lines = block.code.lines().count();
+    let file_lines = current.lines().count();
+    let (old_text, new_text) =
+        if block.whole_file || block_lines as f64 >= file_lines as f64 * EXCERPT_RATIO {
+            (current.clone(), block.code.clone())
+        } else {
+            excerpt_hunk(&current, &block.code).ok_or_else(|| {
+                format!(
+                    "The block for {} has {} of the file's {} lines and its first and last lines \
+                     don't mark one place in the file. Ask for the whole file, with `full` after \
+                     the path in the fence, or for an edit instead.",
+                    block.path, block_lines, file_lines
+                )
+            })?
+        };
+    let updated = current.replacen(&old_text, &new_text, 1);
+    let (diff, lines_added, lines_removed) = line_diff(&current, &updated);
+    Ok(ProposedEdit {
+        tool: "edit_file",
+        args: json!({ "path": path_arg, "type": "replace", "old_text": old_text, "new_text": new_text }),
+        diff,
+        lines_added,
+        lines_removed,
+    })
+}
+
+/// The file text an excerpt replaces and its replacement: from the line
+/// matching the excerpt's first non-blank line to the one matching its last,
+/// taken where the span is closest to the excerpt's length. `None` when the
+/// first line isn't in the file exactly once or the span isn't unique.
+fn excerpt_hunk(current: &str, excerpt: &str) -> Option<(String, String)> {
+    let excerpt_lines: Vec<&str> = excerpt.lines().collect();
+    let first = excerpt_lines.iter().position(|l| !l.trim().is_empty())?;
+    let last = excerpt_lines.iter().rposition(|l| !l.trim().is_empty())?;
+    let file_lines: Vec<&str> = current.lines().collect();
+    let matches = |file_line: &str, excerpt_line: &str| file_line.trim() == excerpt_line.trim();
+
+    let mut starts = file_lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| matches(l, excerpt_lines[first]))
+        .map(|(i, _)| i);
+    let start = starts.next()?;
+    if starts.next().is_some() {
+        return None;
+    }
+    let span = last - first;
+    let end = (start..file_lines.len())
+        .filter(|&i| matches(file_lines[i], excerpt_lines[last]))
+        .min_by_key(|&i| (i - start).abs_diff(span))?;
+
+    let old_text = file_lines[start..=end].join("\n");
+    let new_text = excerpt_lines[first..=last].join("\n");
+    (current.matches(&old_text).count() == 1).then_some((old_text, new_text))
+}
+
+/// Run the proposed tool call, returning the tool's message
+pub async fn apply(edit: &ProposedEdit) -> Result<String, String> {
+    let result = match edit.tool {
+        "edit_file" => {
+            FileEditTool::new()
+                .execute_with_result(edit.args.clone())
+                .await
+        }
+        _ => {
+            WriteFileTool::new()
+                .execute_with_result(edit.args.clone())
+                .await
+        }
+    };
+    if !result.success {
+        return Err(result.error.unwrap_or_else(|| "Edit failed".to_string()));
+    }
+    Ok(result
+        .data
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or("Applied")
+        .to_string())
+}
+
+fn line_diff(old: &str, new: &str) -> (String, usize, usize) {
+    // Without the final newline, which diff::lines reports as an empty line
+    let trim = |text: &str| text.strip_suffix('\n').unwrap_or(text).to_string();
+    let (old, new) = (trim(old), trim(new));
+    let items = diff::lines(&old, &new);
+    let changed: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| !matches!(item, diff::Result::Both(..)))
+        .map(|(i, _)| i)
+        .collect();
+    let near_change = |i: usize| changed.iter().any(|&c| c.abs_diff(i) <= DIFF_CONTEXT);
+
+    let (mut added, mut removed) = (0, 0);
+    let mut lines = Vec::new();
+    let mut skipped = false;
+    for (i, item) in items.iter().enumerate() {
+        if !near_change(i) {
+            skipped = true;
+            continue;
+        }
+        if std::mem::take(&mut skipped) && !lines.is_empty() {
+            lines.push("…".to_string());
+        }
+        match item {
+            diff::Result::Left(l) => {
+                removed += 1;
+                lines.push(format!("-{}", l));
+            }
+            diff::Result::Right(r) => {
+                added += 1;
+                lines.push(format!("+{}", r));
+            }
+            diff::Result::Both(l, _) => lines.push(format!(" {}", l)),
+        }
+    }
+    (lines.join("\n"), added, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_blocks() {
+        let markdown = "Change it like this:\n\n```rust src/main.rs\nfn main() {}\n```\n\n\
+                        ```toml:Cargo.toml\n[package]\n```\n\n```rust\nlet x = 1;\n```\n\n\
+                        ```python path=\"tools/run.py\"\nprint()\n```\n\n```c++\nint x;\n```\n";
+        let blocks = file_blocks(markdown);
+        let paths: Vec<&str> = blocks.iter().map(|b| b.path.as_str()).collect();
+        assert_eq!(paths, ["src/main.rs", "Cargo.toml", "tools/run.py"]);
+        assert_eq!(blocks[0].language.as_deref(), Some("rust"));
+        assert_eq!(blocks[0].code, "fn main() {}\n");
+        assert!(!blocks[0].whole_file);
+
+        let full = file_blocks("```rust src/lib.rs full\nmod a;\n```\n");
+        assert_eq!(full[0].path, "src/lib.rs");
+        assert!(full[0].whole_file);
+    }
+
+    #[test]
+    fn test_propose_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        std::fs::write(&path, "a\nb\nc\nd\ne\nf\ng\nh\ni\n").unwrap();
+        let block = FileBlock {
+            path: "lib.rs".into(),
+            language: None,
+            code: "a\nb\nc\nd\ne\nf\ng\nh\nI\n".into(),
+            whole_file: false,
+        };
+
+        let edit = propose(&block, &path).unwrap();
+        assert_eq!(edit.tool, "edit_file");
+        assert_eq!((edit.lines_added, edit.lines_removed), (1, 1));
+        assert_eq!(edit.diff, " f\n g\n h\n-i\n+I");
+
+        let new = propose(&block, &dir.path().join("new.rs")).unwrap();
+        assert_eq!(new.tool, "write_file");
+        assert_eq!(new.lines_added, 9);
+    }
+
+    #[test]
+    fn test_excerpts_replace_only_their_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        let file = "use std::fmt;\n\nfn one() {\n    1\n}\n\nfn two() {\n    2\n}\n\nfn three() {\n    3\n}\n";
+        std::fs::write(&path, file).unwrap();
+
+        let excerpt = FileBlock {
+            path: "lib.rs".into(),
+            language: None,
+            code: "fn two() {\n    22\n}\n".into(),
+            whole_file: false,
+        };
+        let edit = propose(&excerpt, &path).unwrap();
+        assert_eq!(edit.args["old_text"], "fn two() {\n    2\n}");
+        assert_eq!(edit.args["new_text"], "fn two() {\n    22\n}");
+        assert_eq!((edit.lines_added, edit.lines_removed), (1, 1));
+
+        let unplaced = FileBlock {
+            code: "fn four() {\n    4\n}\n".into(),
+            ..excerpt.clone()
+        };
+        assert!(propose(&unplaced, &path).is_err());
+
+        let whole = FileBlock {
+            whole_file: true,
+            ..unplaced
+        };
+        assert_eq!(propose(&whole, &path).unwrap().args["old_text"], file);
+    }
+}
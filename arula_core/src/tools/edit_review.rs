@@ -0,0 +1,281 @@
+//! Reviewing file changes before the file tools write them
+//!
+//! When a frontend calls `set_enabled(true)`, `write_file` and `edit_file`
+//! stop before writing and register the change here as a `ProposedChange`
+//! with a unified diff. The frontend shows the diff and `decide`s: accept it,
+//! reject it (the tool fails with a message telling the model so), or write
+//! content the user edited instead. Like the desktop's apply panel for code
+//! blocks, nothing touches the disk until the user has seen the diff.
+//!
+//! Frontends that never enable review (the desktop app, `arula serve`,
+//! `arula tool run`) write immediately, as before. `arula acp` has the editor
+//! approve each write through `sandbox::confirm` instead, and `arula -p`
+//! refuses writes and commands there unless run with `--yes`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+/// Unchanged lines shown around each change
+const CONTEXT: usize = 3;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+lazy_static::lazy_static! {
+    static ref PENDING: Mutex<HashMap<u64, (ProposedChange, oneshot::Sender<Decision>)>> =
+        Mutex::new(HashMap::new());
+}
+
+/// A write waiting for the user's decision
+#[derive(Debug, Clone)]
+pub struct ProposedChange {
+    pub id: u64,
+    /// Tool that proposed the change
+    pub tool: String,
+    pub path: String,
+    /// Unified diff against the current file (`/dev/null` for a new one)
+    pub diff: String,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    /// What the tool would write
+    pub content: String,
+}
+
+/// What the user decided about a `ProposedChange`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Accept,
+    Reject,
+    /// Write this content instead of the proposed one
+    Edit(String),
+}
+
+/// Whether the current frontend reviews writes before they happen
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Changes waiting for a decision, oldest first
+pub fn pending() -> Vec<ProposedChange> {
+    let pending = PENDING.lock().unwrap();
+    let mut changes: Vec<ProposedChange> = pending.values().map(|(c, _)| c.clone()).collect();
+    changes.sort_by_key(|c| c.id);
+    changes
+}
+
+/// Answer the change with `id`, letting its tool continue
+pub fn decide(id: u64, decision: Decision) -> Result<(), String> {
+    let entry = PENDING.lock().unwrap().remove(&id);
+    match entry {
+        Some((_, tx)) => tx
+            .send(decision)
+            .map_err(|_| "The tool is no longer waiting".to_string()),
+        None => Err(format!("No pending change with id {}", id)),
+    }
+}
+
+/// Called by the file tools before writing `content` to `path`, whose current
+/// contents are `current` (`None` for a new file).
+///
+/// Returns `Ok(None)` to write the content as proposed, `Ok(Some(edited))` to
+/// write the user's version instead, or an error for the model when the user
+/// rejected the change. Without review enabled this returns `Ok(None)` at once.
+pub async fn review(
+    tool: &str,
+    path: &str,
+    current: Option<&str>,
+    content: &str,
+) -> Result<Option<String>, String> {
+    if !ENABLED.load(Ordering::Relaxed) || current == Some(content) {
+        return Ok(None);
+    }
+    ask(tool, path, current, content).await
+}
+
+async fn ask(
+    tool: &str,
+    path: &str,
+    current: Option<&str>,
+    content: &str,
+) -> Result<Option<String>, String> {
+    let (diff, lines_added, lines_removed) = unified_diff(path, current, content);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let (tx, rx) = oneshot::channel();
+    let change = ProposedChange {
+        id,
+        tool: tool.to_string(),
+        path: path.to_string(),
+        diff,
+        lines_added,
+        lines_removed,
+        content: content.to_string(),
+    };
+    PENDING.lock().unwrap().insert(id, (change, tx));
+    // Withdraw the change if the tool is cancelled before the user decides
+    let _withdraw = Withdraw(id);
+
+    match rx.await {
+        Ok(Decision::Accept) => Ok(None),
+        Ok(Decision::Edit(edited)) => Ok(Some(edited)),
+        Ok(Decision::Reject) | Err(_) => Err(format!(
+            "The user rejected the change to '{}'. Ask what they want instead of retrying it.",
+            path
+        )),
+    }
+}
+
+/// Removes a change from `PENDING` when its tool stops waiting
+struct Withdraw(u64);
+
+impl Drop for Withdraw {
+    fn drop(&mut self) {
+        if let Ok(mut pending) = PENDING.lock() {
+            pending.remove(&self.0);
+        }
+    }
+}
+
+/// Unified diff of `old` (`None` when the file doesn't exist) to `new`, with
+/// the number of lines added and removed
+pub fn unified_diff(path: &str, old: Option<&str>, new: &str) -> (String, usize, usize) {
+    // Without the final newline, which diff::lines reports as an empty line
+    let trim = |text: &str| text.strip_suffix('\n').unwrap_or(text).to_string();
+    let (old_text, new_text) = (trim(old.unwrap_or("")), trim(new));
+    let items = diff::lines(&old_text, &new_text);
+
+    // Line in the old and new file at which each item starts
+    let mut positions = Vec::with_capacity(items.len());
+    let (mut old_line, mut new_line) = (1, 1);
+    for item in &items {
+        positions.push((old_line, new_line));
+        match item {
+            diff::Result::Left(_) => old_line += 1,
+            diff::Result::Right(_) => new_line += 1,
+            diff::Result::Both(..) => {
+                old_line += 1;
+                new_line += 1;
+            }
+        }
+    }
+    let changed: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| !matches!(item, diff::Result::Both(..)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut lines = vec![
+        match old {
+            Some(_) => format!("--- a/{}", path),
+            None => "--- /dev/null".to_string(),
+        },
+        format!("+++ b/{}", path),
+    ];
+    let (mut added, mut removed) = (0, 0);
+    let mut next = 0;
+    while next < changed.len() {
+        // Changes closer than twice the context share a hunk
+        let first = changed[next];
+        let mut last = first;
+        next += 1;
+        while next < changed.len() && changed[next] <= last + 2 * CONTEXT {
+            last = changed[next];
+            next += 1;
+        }
+        let hunk = &items[first.saturating_sub(CONTEXT)..(last + CONTEXT + 1).min(items.len())];
+        let (old_start, new_start) = positions[first.saturating_sub(CONTEXT)];
+        let old_count = hunk
+            .iter()
+            .filter(|item| !matches!(item, diff::Result::Right(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|item| !matches!(item, diff::Result::Left(_)))
+            .count();
+        lines.push(format!(
+            "@@ -{} +{} @@",
+            hunk_range(old_start, old_count),
+            hunk_range(new_start, new_count)
+        ));
+        for item in hunk {
+            match item {
+                diff::Result::Left(l) => {
+                    removed += 1;
+                    lines.push(format!("-{}", l));
+                }
+                diff::Result::Right(r) => {
+                    added += 1;
+                    lines.push(format!("+{}", r));
+                }
+                diff::Result::Both(l, _) => lines.push(format!(" {}", l)),
+            }
+        }
+    }
+    (lines.join("\n"), added, removed)
+}
+
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start - 1),
+        1 => start.to_string(),
+        _ => format!("{},{}", start, count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        let (diff, added, removed) = unified_diff("src/x.txt", Some(old), new);
+        assert_eq!((added, removed), (2, 1));
+        let lines: Vec<&str> = diff.lines().collect();
+        assert_eq!(lines[..4], ["--- a/src/x.txt", "+++ b/src/x.txt", "@@ -1,5 +1,5 @@", " a"]);
+        assert!(lines.contains(&"@@ -10,3 +10,4 @@"));
+        assert_eq!(lines.last(), Some(&"+m"));
+
+        let (diff, added, _) = unified_diff("new.txt", None, "one\ntwo\n");
+        assert_eq!(diff, "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+one\n+two");
+        assert_eq!(added, 2);
+    }
+
+    #[tokio::test]
+    async fn test_change_waits_for_decision() {
+        for (decision, expected) in [
+            (Decision::Edit("mine".to_string()), Ok(Some("mine".to_string()))),
+            (Decision::Accept, Ok(None)),
+        ] {
+            let task = tokio::spawn(ask("write_file", "review_test.txt", Some("old"), "new"));
+            let change = loop {
+                match pending().into_iter().find(|c| c.path == "review_test.txt") {
+                    Some(change) => break change,
+                    None => tokio::task::yield_now().await,
+                }
+            };
+            assert_eq!(change.diff.lines().last(), Some("+new"));
+            decide(change.id, decision).unwrap();
+            assert_eq!(task.await.unwrap(), expected);
+        }
+        assert!(decide(u64::MAX, Decision::Reject).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_change_is_withdrawn() {
+        let task = tokio::spawn(ask("write_file", "withdrawn_test.txt", None, "new"));
+        let change = loop {
+            match pending().into_iter().find(|c| c.path == "withdrawn_test.txt") {
+                Some(change) => break change,
+                None => tokio::task::yield_now().await,
+            }
+        };
+        task.abort();
+        let _ = task.await;
+        assert!(pending().iter().all(|c| c.id != change.id));
+        assert!(decide(change.id, Decision::Accept).is_err());
+    }
+}
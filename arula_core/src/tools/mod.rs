@@ -5,15 +5,21 @@
 //! # Module Structure
 //!
 //! - `builtin` - Organized built-in tools (new modular structure)
+//! - `code_blocks` - Applying file-annotated code blocks from AI messages
+//! - `edit_review` - Letting the user accept, reject or edit writes first
 //! - `tools` - Legacy tools file (being migrated to builtin/)
 //! - `visioneer` - Vision/screenshot capabilities
 //! - `mcp` - Model Context Protocol client
 //! - `mcp_dynamic` - Dynamic MCP tool loading
+//! - `sandbox` - Working-directory confinement for the file tools
 
 pub mod analyze_context;
 pub mod builtin;
+pub mod code_blocks;
+pub mod edit_review;
 pub mod mcp;
 pub mod mcp_dynamic;
+pub mod sandbox;
 pub mod tools;
 pub mod visioneer;
 
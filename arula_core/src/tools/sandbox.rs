@@ -0,0 +1,359 @@
+//! Filesystem sandbox for the file tools
+//!
+//! `read_file`, `write_file`, `edit_file` and `list_directory` may only touch
+//! paths under the current working directory. Anything outside it needs a
+//! grant:
+//!
+//! - `fs_allowed_paths` in `~/.arula/config.json` (or `--allow-path` in the CLI)
+//! - an interactive prompt, answered through `QUESTION_HANDLER`, when a
+//!   frontend that can show questions has called `set_interactive(true)`
+//!
+//! `arula acp` serves several editor sessions from one process, so each turn
+//! runs inside `with_workdir` and everything here resolves relative paths
+//! against that session's directory instead of the process one. It also turns
+//! on `set_confirm_actions`, so commands and writes inside the working
+//! directory are confirmed through the same prompt. `arula -p` turns it on
+//! without a prompt, so they are refused unless it was run with `--yes`.
+//!
+//! "Allow for this session" grants last until the process exits. Without an
+//! interactive frontend, outside paths are refused with an explanation the
+//! model can relay to the user. Set `fs_sandbox_enabled: false` to turn the
+//! sandbox off. Nothing is enforced until a frontend calls `configure`, so the
+//! tools stay usable as plain library calls.
+
+use crate::tools::builtin::question::{Question, QUESTION_HANDLER};
+use crate::utils::config::Config;
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Answers offered by the sandbox prompt
+pub const ALLOW_ONCE: &str = "Allow once";
+pub const ALLOW_SESSION: &str = "Allow for this session";
+pub const DENY: &str = "Deny";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static INTERACTIVE: AtomicBool = AtomicBool::new(false);
+static CONFIRM_ACTIONS: AtomicBool = AtomicBool::new(false);
+static GRANTS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+/// Tools the user allowed for the rest of the process with `confirm`
+static ALLOWED_TOOLS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+tokio::task_local! {
+    static WORKDIR: Option<PathBuf>;
+}
+
+/// Kind of access a tool needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+impl Access {
+    fn verb(self) -> &'static str {
+        match self {
+            Access::Read => "read",
+            Access::Write => "write to",
+        }
+    }
+}
+
+/// Apply sandbox settings from the config (enabled flag and allowed paths)
+pub fn configure(config: &Config) {
+    ENABLED.store(config.get_fs_sandbox_enabled(), Ordering::Relaxed);
+    for path in &config.fs_allowed_paths {
+        grant(path);
+    }
+}
+
+/// Turn the sandbox on or off regardless of `fs_sandbox_enabled`
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the current frontend answers sandbox prompts
+pub fn set_interactive(interactive: bool) {
+    INTERACTIVE.store(interactive, Ordering::Relaxed);
+}
+
+/// Whether bash commands and writes inside the working directory also need
+/// the user's approval
+pub fn set_confirm_actions(confirm: bool) {
+    CONFIRM_ACTIONS.store(confirm, Ordering::Relaxed);
+}
+
+/// Run `future` with `dir` as its working directory for the file tools, bash
+/// and `resolve`, leaving the process working directory alone
+pub async fn with_workdir<F: Future>(dir: PathBuf, future: F) -> F::Output {
+    WORKDIR.scope(Some(dir), future).await
+}
+
+/// Synchronous `with_workdir`, for setup code such as building a backend
+pub fn with_workdir_sync<R>(dir: PathBuf, f: impl FnOnce() -> R) -> R {
+    WORKDIR.sync_scope(Some(dir), f)
+}
+
+/// Carry the caller's `with_workdir` directory into `future`, which is about
+/// to be spawned as its own task
+pub fn inherit_workdir<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let dir = WORKDIR.try_with(Clone::clone).ok().flatten();
+    WORKDIR.scope(dir, future)
+}
+
+/// The working directory of the current task: its `with_workdir` directory,
+/// or the process one
+pub fn working_dir() -> PathBuf {
+    WORKDIR
+        .try_with(Clone::clone)
+        .ok()
+        .flatten()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// `path` joined onto `working_dir` when it is relative
+pub fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        working_dir().join(path)
+    }
+}
+
+/// Point a tool's relative `path` argument at the task's `with_workdir`
+/// directory, filling it in when it is missing and `fill_missing` is set.
+/// Outside `with_workdir` the arguments are left as they are.
+pub fn anchor_path_argument(args: &mut serde_json::Value, fill_missing: bool) {
+    let Some(dir) = WORKDIR.try_with(Clone::clone).ok().flatten() else {
+        return;
+    };
+    let Some(object) = args.as_object_mut() else {
+        return;
+    };
+    let path = match object.get("path").and_then(serde_json::Value::as_str) {
+        Some(path) if !path.trim().is_empty() => path.to_string(),
+        None if fill_missing => ".".to_string(),
+        _ => return,
+    };
+    if Path::new(&path).is_relative() {
+        let anchored = dir.join(path).to_string_lossy().to_string();
+        object.insert("path".to_string(), serde_json::Value::String(anchored));
+    }
+}
+
+/// Allow access to `path` and everything below it for the rest of the process
+pub fn grant(path: impl AsRef<Path>) {
+    let path = resolve(path.as_ref());
+    if let Ok(mut grants) = GRANTS.lock() {
+        if !grants.contains(&path) {
+            grants.push(path);
+        }
+    }
+}
+
+/// Paths granted so far
+pub fn granted_paths() -> Vec<PathBuf> {
+    GRANTS.lock().map(|g| g.clone()).unwrap_or_default()
+}
+
+/// Make `path` absolute and normalized the way the OS will resolve it.
+///
+/// Components are resolved one at a time: every existing prefix is
+/// canonicalized before the next `..` is applied, so `link/../secret` leaves
+/// from where the link points, not from the link itself. Parts that don't
+/// exist yet are kept lexically.
+pub fn resolve(path: &Path) -> PathBuf {
+    let absolute = absolute(path);
+
+    let mut resolved = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            other => {
+                resolved.push(other.as_os_str());
+                if let Ok(canonical) = resolved.canonicalize() {
+                    resolved = canonical;
+                }
+            }
+        }
+    }
+    resolved
+}
+
+/// Whether `path` is inside the working directory or a granted path
+pub fn is_allowed(path: &Path) -> bool {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return true;
+    }
+    let resolved = resolve(path);
+    let root = resolve(Path::new("."));
+    is_within(&resolved, &root, &granted_paths())
+}
+
+fn is_within(resolved: &Path, root: &Path, grants: &[PathBuf]) -> bool {
+    resolved.starts_with(root) || grants.iter().any(|g| resolved.starts_with(g))
+}
+
+/// Check that `tool` may access `path`, prompting the user if it is outside
+/// the sandbox. Returns an error message for the model when access is refused.
+pub async fn check(tool: &str, path: &str, access: Access) -> Result<(), String> {
+    let requested = Path::new(path);
+    if is_allowed(requested) {
+        return match access {
+            Access::Write => confirm(tool, &format!("write to '{}'", path)).await,
+            Access::Read => Ok(()),
+        };
+    }
+
+    let resolved = resolve(requested);
+    let root = resolve(Path::new("."));
+    if !INTERACTIVE.load(Ordering::Relaxed) {
+        return Err(format!(
+            "Access denied: '{}' is outside the working directory '{}'. \
+             Ask the user to allow it (fs_allowed_paths in config, or --allow-path).",
+            resolved.display(),
+            root.display()
+        ));
+    }
+
+    let answer = ask(format!(
+        "{} wants to {} '{}', which is outside the working directory '{}'. Allow?",
+        tool,
+        access.verb(),
+        resolved.display(),
+        root.display()
+    ))
+    .await;
+
+    match answer.as_str() {
+        ALLOW_ONCE => Ok(()),
+        ALLOW_SESSION => {
+            // Grant the directory so sibling files don't prompt again
+            let scope = if resolved.is_dir() {
+                resolved.clone()
+            } else {
+                resolved
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_else(|| resolved.clone())
+            };
+            grant(&scope);
+            Ok(())
+        }
+        _ => Err(format!(
+            "Access denied: the user did not allow access to '{}'",
+            resolved.display()
+        )),
+    }
+}
+
+/// Ask the user to approve `tool` doing `action` when `set_confirm_actions`
+/// is on. "Allow for this session" approves the tool for the rest of the
+/// process. Returns an error message for the model when the user says no, or
+/// at once when no interactive frontend could ask.
+pub async fn confirm(tool: &str, action: &str) -> Result<(), String> {
+    if !CONFIRM_ACTIONS.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    if ALLOWED_TOOLS.lock().map(|t| t.iter().any(|t| t == tool)).unwrap_or(false) {
+        return Ok(());
+    }
+    if !INTERACTIVE.load(Ordering::Relaxed) {
+        return Err(format!(
+            "{} may not {} without the user's approval. Ask the user to allow it \
+             (--yes in the CLI).",
+            tool, action
+        ));
+    }
+
+    match ask(format!("{} wants to {}. Allow?", tool, action)).await.as_str() {
+        ALLOW_ONCE => Ok(()),
+        ALLOW_SESSION => {
+            if let Ok(mut allowed) = ALLOWED_TOOLS.lock() {
+                allowed.push(tool.to_string());
+            }
+            Ok(())
+        }
+        _ => Err(format!("The user did not allow {} to {}", tool, action)),
+    }
+}
+
+/// Ask `question` with the allow/deny options, returning the answer
+async fn ask(question: String) -> String {
+    let batch_id = uuid::Uuid::new_v4().to_string();
+    let rx = QUESTION_HANDLER.ask(
+        batch_id,
+        vec![Question {
+            id: "q1".to_string(),
+            question,
+            options: Some(vec![
+                ALLOW_ONCE.to_string(),
+                ALLOW_SESSION.to_string(),
+                DENY.to_string(),
+            ]),
+        }],
+    );
+
+    rx.await
+        .ok()
+        .and_then(|answers| answers.into_iter().next())
+        .map(|a| a.answer)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_normalizes_parent_dirs() {
+        let cwd = resolve(Path::new("."));
+        assert_eq!(resolve(Path::new("a/../b/./c.txt")), cwd.join("b").join("c.txt"));
+    }
+
+    #[test]
+    fn test_outside_paths_need_grant() {
+        let cwd = resolve(Path::new("."));
+        let outside = resolve(Path::new("../granted-outside-arula"));
+        assert!(is_within(&resolve(Path::new("src/lib.rs")), &cwd, &[]));
+        assert!(!is_within(&outside.join("file.txt"), &cwd, &[]));
+        assert!(is_within(&outside.join("file.txt"), &cwd, &[outside.clone()]));
+        assert!(!is_within(&resolve(Path::new("../granted-outside-arula-2")), &cwd, &[outside]));
+    }
+
+    #[tokio::test]
+    async fn test_workdir_is_per_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let inside = with_workdir(dir.path().to_path_buf(), async {
+            let spawned = tokio::spawn(inherit_workdir(async { resolve(Path::new("a.txt")) }));
+            (resolve(Path::new("a.txt")), spawned.await.unwrap())
+        })
+        .await;
+        let expected = resolve(&dir.path().join("a.txt"));
+        assert_eq!(inside, (expected.clone(), expected));
+        assert_eq!(working_dir(), std::env::current_dir().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_then_parent_dir_cannot_escape() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        let outside = dir.path().join("outside");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(outside.join("sub")).unwrap();
+        std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(outside.join("sub"), root.join("link")).unwrap();
+
+        let root = resolve(&root);
+        let escaped = resolve(&root.join("link/../secret.txt"));
+        assert_eq!(escaped, resolve(&outside.join("secret.txt")));
+        assert!(!is_within(&escaped, &root, &[]));
+        assert!(!is_within(&resolve(&root.join("link/../new.txt")), &root, &[]));
+    }
+}
@@ -19,11 +19,14 @@
 // These are public API exports - not used internally but exposed for library consumers
 #[allow(unused_imports)]
 pub use crate::tools::builtin::{
-    BashParams, BashResult, BashTool, DirectoryEntry, FileEditParams, FileEditResult, FileEditTool,
+    BashParams, BashResult, BashTool, CaptureScreenParams, CaptureScreenResult, CaptureScreenTool,
+    CodeOutlineParams, CodeOutlineResult, CodeOutlineTool,
+    DirectoryEntry, FetchUrlParams, FetchUrlResult, FetchUrlTool, FileEditParams, FileEditResult, FileEditTool,
     FileReadParams, FileReadResult, FileReadTool, FindFilesParams, FindFilesResult, FindFilesTool,
     FoundFile, ListDirParams, ListDirResult, ListDirectoryTool, QuestionParams, QuestionResult,
     QuestionTool, QUESTION_HANDLER, QuestionHandler, SearchMatch, SearchParams, SearchResult, 
-    SearchTool, WebSearchParams, WebSearchResult, WebSearchResultItem, WebSearchTool, 
+    SearchTool, TranscribeAudioParams, TranscribeAudioResult, TranscribeAudioTool,
+    TranscriptSegment, WebSearchParams, WebSearchResult, WebSearchResultItem, WebSearchTool, 
     WriteFileParams, WriteFileResult, WriteFileTool,
 };
 
@@ -53,9 +56,15 @@ pub fn create_basic_tool_registry() -> crate::api::agent::ToolRegistry {
     registry.register(ListDirectoryTool::new());
     registry.register(SearchTool::new());
     registry.register(WebSearchTool::new());
+    registry.register(FetchUrlTool::new());
     registry.register(VisioneerTool::new());
     registry.register(QuestionTool::new());
     registry.register(AnalyzeContextTool::new());
+    registry.register(CodeOutlineTool::new());
+    if crate::tools::builtin::screen_capture::is_enabled() {
+        registry.register(CaptureScreenTool::new());
+    }
+    registry.register(TranscribeAudioTool::new());
 
     registry
 }
@@ -113,8 +122,12 @@ mod tests {
         assert!(tools.contains(&"list_directory".to_string()));
         assert!(tools.contains(&"search_files".to_string()));
         assert!(tools.contains(&"web_search".to_string()));
+        assert!(tools.contains(&"fetch_url".to_string()));
         assert!(tools.contains(&"visioneer".to_string()));
         assert!(tools.contains(&"ask_question".to_string()));
         assert!(tools.contains(&"analyze_context".to_string()));
+        // Screenshots are opt-in per frontend
+        assert!(!tools.contains(&"capture_screen".to_string()));
+        assert!(tools.contains(&"transcribe_audio".to_string()));
     }
 }
@@ -0,0 +1,205 @@
+//! Budget limits for agent runs
+//!
+//! `budget` in `~/.arula/config.json` caps a session (every prompt sent in it
+//! and the tool iterations they trigger) by total tokens, rounds of tool
+//! execution and estimated cost. What a session has used is kept in a
+//! `BudgetUsage` that outlives its runs: `SessionConfig::budget_usage` for
+//! session runs, `AgentClient::set_budget_usage` for a client kept for one
+//! conversation. The agent loop checks a `BudgetTracker` over it before each
+//! request; when a limit is hit the run ends with
+//! `StreamEvent::BudgetExceeded` rather than an error.
+//!
+//! The frontends' "continue anyway" action resumes one session without the
+//! limits and starts its count over: `SessionConfig::ignore_budget` for
+//! session runs, or `AgentClient::set_budget` on a client used for a single
+//! request.
+//!
+//! Not to be confused with `context_budget`, which sizes the system prompt.
+
+use crate::api::api::Usage;
+use crate::utils::config::BudgetLimits;
+use crate::utils::usage_store;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Which limit was hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BudgetKind {
+    Tokens,
+    ToolIterations,
+    Cost,
+}
+
+/// A limit that was reached, with the amount used so far
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetExceeded {
+    pub kind: BudgetKind,
+    pub used: f64,
+    pub limit: f64,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            BudgetKind::Tokens => write!(
+                f,
+                "Token budget reached: {} of {} tokens used",
+                self.used as u64, self.limit as u64
+            ),
+            BudgetKind::ToolIterations => write!(
+                f,
+                "Tool iteration limit reached: {} of {} rounds",
+                self.used as u64, self.limit as u64
+            ),
+            BudgetKind::Cost => write!(
+                f,
+                "Cost budget reached: ${:.2} of ${:.2} spent",
+                self.used, self.limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Running totals of one session, shared by its runs; clones count together
+#[derive(Debug, Clone, Default)]
+pub struct BudgetUsage(Arc<Mutex<Totals>>);
+
+#[derive(Debug, Default)]
+struct Totals {
+    tokens: u64,
+    cost: f64,
+    tool_iterations: u32,
+}
+
+impl BudgetUsage {
+    fn totals(&self) -> MutexGuard<'_, Totals> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Start counting from zero again
+    pub fn reset(&self) {
+        *self.totals() = Totals::default();
+    }
+}
+
+/// A session's usage checked against the limits of the current run
+#[derive(Debug, Clone, Default)]
+pub struct BudgetTracker {
+    limits: BudgetLimits,
+    usage: BudgetUsage,
+}
+
+impl BudgetTracker {
+    /// Track a run on its own against `limits`
+    pub fn new(limits: BudgetLimits) -> Self {
+        Self::with_usage(limits, BudgetUsage::default())
+    }
+
+    /// Track a run against `limits`, counting on from the session's `usage`
+    pub fn with_usage(limits: BudgetLimits, usage: BudgetUsage) -> Self {
+        Self { limits, usage }
+    }
+
+    /// Count the tokens (and estimated cost) of one response
+    pub fn add_usage(&mut self, model: &str, usage: &Usage) {
+        let prompt = u64::from(usage.prompt_tokens);
+        let completion = u64::from(usage.completion_tokens);
+        let mut totals = self.usage.totals();
+        totals.tokens += prompt + completion;
+        totals.cost += usage_store::estimate_cost(model, prompt, completion).unwrap_or(0.0);
+    }
+
+    /// Count one round of tool execution
+    pub fn add_tool_iteration(&mut self) {
+        self.usage.totals().tool_iterations += 1;
+    }
+
+    /// The first limit that has been reached, if any
+    pub fn check(&self) -> Option<BudgetExceeded> {
+        let BudgetLimits {
+            max_tokens,
+            max_tool_iterations,
+            max_cost,
+        } = &self.limits;
+        let totals = self.usage.totals();
+
+        if let Some(limit) = max_tokens.filter(|limit| totals.tokens >= *limit) {
+            return Some(BudgetExceeded {
+                kind: BudgetKind::Tokens,
+                used: totals.tokens as f64,
+                limit: limit as f64,
+            });
+        }
+        if let Some(limit) = max_tool_iterations.filter(|limit| totals.tool_iterations >= *limit) {
+            return Some(BudgetExceeded {
+                kind: BudgetKind::ToolIterations,
+                used: f64::from(totals.tool_iterations),
+                limit: f64::from(limit),
+            });
+        }
+        if let Some(limit) = max_cost.filter(|limit| totals.cost >= *limit) {
+            return Some(BudgetExceeded {
+                kind: BudgetKind::Cost,
+                used: totals.cost,
+                limit,
+            });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_are_checked_in_order() {
+        let mut tracker = BudgetTracker::new(BudgetLimits {
+            max_tokens: Some(1_000),
+            max_tool_iterations: Some(2),
+            max_cost: None,
+        });
+        assert_eq!(tracker.check(), None);
+
+        tracker.add_tool_iteration();
+        tracker.add_tool_iteration();
+        assert_eq!(tracker.check().unwrap().kind, BudgetKind::ToolIterations);
+
+        tracker.add_usage(
+            "gpt-4o",
+            &Usage {
+                prompt_tokens: 900,
+                completion_tokens: 200,
+                total_tokens: 1_100,
+                ..Default::default()
+            },
+        );
+        let exceeded = tracker.check().unwrap();
+        assert_eq!(exceeded.kind, BudgetKind::Tokens);
+        assert_eq!(exceeded.to_string(), "Token budget reached: 1100 of 1000 tokens used");
+    }
+
+    #[test]
+    fn test_usage_carries_over_between_runs() {
+        let limits = BudgetLimits {
+            max_tool_iterations: Some(3),
+            ..Default::default()
+        };
+        let session = BudgetUsage::default();
+
+        let mut first = BudgetTracker::with_usage(limits.clone(), session.clone());
+        first.add_tool_iteration();
+        first.add_tool_iteration();
+        assert_eq!(first.check(), None);
+
+        let mut second = BudgetTracker::with_usage(limits.clone(), session.clone());
+        second.add_tool_iteration();
+        assert_eq!(second.check().unwrap().kind, BudgetKind::ToolIterations);
+
+        session.reset();
+        assert_eq!(BudgetTracker::with_usage(limits, session).check(), None);
+    }
+}
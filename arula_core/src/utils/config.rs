@@ -4,7 +4,10 @@ use serde_json;
 use serde_yaml;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path; // Only for migration
+use std::path::{Path, PathBuf};
+
+use crate::utils::personas::Persona;
+use crate::utils::workspace::Workspace;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -23,11 +26,267 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub living_background_enabled: Option<bool>,
 
+    /// Token budget for the assembled system prompt (default: 12000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_budget_tokens: Option<usize>,
+
+    /// Largest tool result kept in the history before head/tail truncation (default: 4000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_result_max_tokens: Option<usize>,
+
+    /// Answer repeated identical runs from the local response cache (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_cache_enabled: Option<bool>,
+
+    /// Offer git status and recent commits as system prompt context (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_context_enabled: Option<bool>,
+
+    /// Look for a newer release on GitHub once a day (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_check_enabled: Option<bool>,
+
+    /// Delete saved conversations not updated for this many days; pinned ones
+    /// are kept (default: keep forever)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_retention_days: Option<u32>,
+
+    /// How much of each tool call the desktop app and CLI show (default: summary)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_verbosity: Option<ToolVerbosity>,
+
+    /// Where the suggestions shown in an empty conversation come from (default: generated)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starter_mode: Option<StarterMode>,
+
+    /// Starters always shown first in an empty conversation
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub pinned_starters: Vec<String>,
+
+    /// System prompt presets besides the built-in ones (see `utils::personas`)
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub personas: Vec<Persona>,
+
+    /// Name of the persona in use (none when absent)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_persona: Option<String>,
+
+    /// Mark misspelled words in the desktop chat input (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spellcheck_enabled: Option<bool>,
+
+    /// Hunspell dictionary the spellchecker uses (default: en_US)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spellcheck_language: Option<String>,
+
+    /// Restrict file tools to the working directory subtree (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fs_sandbox_enabled: Option<bool>,
+
+    /// Paths outside the working directory that file tools may always access
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub fs_allowed_paths: Vec<String>,
+
+    /// Show the diff of each file write in the interactive CLI and wait for
+    /// accept, reject or edit (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub review_edits: Option<bool>,
+
+    /// Let the model take screenshots with `capture_screen` in the desktop
+    /// app; they are sent to the provider unasked (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screen_capture: Option<bool>,
+
+    /// OpenTelemetry export settings (disabled when absent)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<TelemetryConfig>,
+
+    /// Secret redaction for transcripts and logs (enabled with built-in patterns when absent)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redaction: Option<RedactionConfig>,
+
+    /// Token, tool-iteration and cost ceilings for each session run (unlimited when absent)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetLimits>,
+
+    /// top_p, penalties, stop sequences and seed (provider defaults when absent)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<SamplingParams>,
+
+    /// Local WebSocket event API for external frontends (off when absent)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub websocket: Option<WebSocketConfig>,
+
+    /// URLs that receive a JSON POST on session events
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// Working directories picked in the desktop app, most recent first
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub recent_directories: Vec<String>,
+
+    /// Directories pinned in the desktop directory popup
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub bookmarked_directories: Vec<String>,
+
+    /// Root directories of the desktop workspaces, besides the default one
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub workspaces: Vec<String>,
+
+    /// Desktop color theme: "auto", "light", "dark" or "black"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+
+    /// Embedding model and context share for workspace documents (keyword search when absent)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub knowledge: Option<KnowledgeConfig>,
+
     /// Legacy field for backward compatibility (deprecated)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ai: Option<AiConfig>,
 }
 
+/// OpenTelemetry (OTLP/HTTP) export of agent traces and metrics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// Export traces and metrics
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// OTLP/HTTP collector base URL (default: `OTEL_EXPORTER_OTLP_ENDPOINT` or http://localhost:4318)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// `service.name` resource attribute (default: "arula")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_name: Option<String>,
+}
+
+/// Retrieval from the workspace knowledge base (see `utils::knowledge`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeConfig {
+    /// Provider whose `/embeddings` endpoint is used (default: the active one)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_provider: Option<String>,
+
+    /// Embedding model; without one, chunks are ranked by keyword overlap
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+
+    /// Tokens of document excerpts added to a prompt (default: 2000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_context_tokens: Option<usize>,
+}
+
+/// WebSocket event API served by the desktop app (see `server::websocket`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebSocketConfig {
+    /// Start the server
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Port on 127.0.0.1 (default: 8765)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+
+    /// Token clients must pass as `?token=`; without one only local origins may connect
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// One webhook endpoint (see `utils::webhooks`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+
+    /// Events to send; all of them when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<WebhookEvent>,
+
+    /// Extra request headers, e.g. an `Authorization` token for the receiver
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
+
+/// Session events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    StreamFinished,
+    ToolCallResult,
+    Error,
+}
+
+/// Redaction of API keys, tokens and secret env-var values before display or persistence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Redact at all
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Extra regular expressions; a named group `secret` limits redaction to that group
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+
+    /// Also redact values of environment variables whose names look secret (e.g. `*_API_KEY`)
+    #[serde(default = "default_true")]
+    pub env_values: bool,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: Vec::new(),
+            env_values: true,
+        }
+    }
+}
+
+/// Ceilings enforced by the agent loop; unset fields are unlimited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetLimits {
+    /// Total prompt + completion tokens across all requests of the run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+
+    /// Rounds of tool execution before the run stops
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tool_iterations: Option<u32>,
+
+    /// Estimated cost in USD (models without a known price count as free)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cost: Option<f64>,
+}
+
+/// Sampling parameters sent with every request; unset fields use the provider's default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SamplingParams {
+    /// Nucleus sampling: only consider tokens within this probability mass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+
+    /// Penalize tokens by how often they already appeared (-2.0 to 2.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+
+    /// Penalize tokens that appeared at all (-2.0 to 2.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+
+    /// Sequences that end the response when generated
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub stop: Vec<String>,
+
+    /// Seed for best-effort reproducible sampling
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
     pub model: String,
@@ -38,8 +297,11 @@ pub struct ProviderConfig {
     // Z.AI specific options
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thinking_enabled: Option<bool>,
+    /// Retries of a request that failed to connect, timed out or got a
+    /// rate-limit or server error (default: 3)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_retries: Option<u32>,
+    /// Overall timeout of non-streaming requests (default: 60)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout_seconds: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -57,6 +319,116 @@ pub struct ProviderConfig {
     /// Some Ollama models support tool calling, but it may cause issues with others
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools_enabled: Option<bool>,
+
+    /// Extra HTTP headers sent with every request to this provider
+    /// (organization IDs, gateway routing headers, ...)
+    #[serde(skip_serializing_if = "HashMap::is_empty", default = "HashMap::new")]
+    pub extra_headers: HashMap<String, String>,
+
+    /// JSON merged into every request body, e.g. `{"provider": {"order":
+    /// ["anthropic"]}}` for OpenRouter. Objects merge key by key and a
+    /// `null` value removes the key from the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_body: Option<serde_json::Value>,
+
+    /// Time allowed to establish a connection (default: 30)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_seconds: Option<u64>,
+
+    /// Longest pause between received bytes of a response (default: 120)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_timeout_seconds: Option<u64>,
+
+    /// Delay before the first retry, doubled (with jitter) for each further
+    /// one up to 30 seconds (default: 1000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_delay_ms: Option<u64>,
+
+    /// How much of a long conversation is sent with each request (default: full)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<HistoryStrategy>,
+
+    /// History strategies for specific models of this provider, overriding `history`
+    #[serde(skip_serializing_if = "HashMap::is_empty", default = "HashMap::new")]
+    pub model_history: HashMap<String, HistoryStrategy>,
+}
+
+/// How much of a tool call frontends show while an answer is worked out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolVerbosity {
+    /// Keep each call's output visible
+    Full,
+    /// One line per call; the output opens on demand
+    #[default]
+    Summary,
+    /// Only the final answer
+    Hidden,
+}
+
+impl ToolVerbosity {
+    pub const ALL: [ToolVerbosity; 3] = [
+        ToolVerbosity::Full,
+        ToolVerbosity::Summary,
+        ToolVerbosity::Hidden,
+    ];
+}
+
+impl std::fmt::Display for ToolVerbosity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ToolVerbosity::Full => "Full output",
+            ToolVerbosity::Summary => "Summaries",
+            ToolVerbosity::Hidden => "Hidden",
+        })
+    }
+}
+
+/// Where conversation starters come from, after the pinned ones (see `utils::starters`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StarterMode {
+    /// Ask the model for suggestions based on the project manifest
+    #[default]
+    Generated,
+    /// Fixed suggestions for the detected project type, without a model call
+    Project,
+    /// Only the pinned starters
+    Off,
+}
+
+impl StarterMode {
+    pub const ALL: [StarterMode; 3] = [
+        StarterMode::Generated,
+        StarterMode::Project,
+        StarterMode::Off,
+    ];
+}
+
+impl std::fmt::Display for StarterMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StarterMode::Generated => "Generated",
+            StarterMode::Project => "Project type",
+            StarterMode::Off => "Pinned only",
+        })
+    }
+}
+
+/// How the conversation history is trimmed before it is sent (see `api::history`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum HistoryStrategy {
+    /// Send every message
+    #[default]
+    Full,
+    /// Send only the last `turns` user turns with their answers and tool calls
+    SlidingWindow { turns: usize },
+    /// Send the newest turns that fit in `max_tokens` (estimated)
+    TokenBudget { max_tokens: usize },
+    /// Keep the last `keep_turns` turns and replace older ones with a summary
+    /// written by the model
+    SummarizeOld { keep_turns: usize },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -153,6 +525,32 @@ impl AiConfig {
                 api_url: "http://localhost:11434".to_string(),
                 api_key: std::env::var("OLLAMA_API_KEY").unwrap_or_default(),
             },
+            "xai" | "x.ai" | "grok" => AiConfig {
+                provider: "xai".to_string(),
+                model: "grok-4".to_string(),
+                api_url: "https://api.x.ai/v1".to_string(),
+                api_key: std::env::var("XAI_API_KEY").unwrap_or_default(),
+            },
+            "deepseek" => AiConfig {
+                provider: "deepseek".to_string(),
+                model: "deepseek-chat".to_string(),
+                api_url: "https://api.deepseek.com/v1".to_string(),
+                api_key: std::env::var("DEEPSEEK_API_KEY").unwrap_or_default(),
+            },
+            "lm studio" | "lmstudio" | "lm-studio" => AiConfig {
+                provider: "lm studio".to_string(),
+                // LM Studio answers with whichever model is loaded
+                model: "local-model".to_string(),
+                api_url: "http://localhost:1234/v1".to_string(),
+                api_key: std::env::var("LMSTUDIO_API_KEY").unwrap_or_default(),
+            },
+            "llamafile" => AiConfig {
+                provider: "llamafile".to_string(),
+                // llamafile serves a single model and ignores the name
+                model: "LLaMA_CPP".to_string(),
+                api_url: "http://localhost:8080/v1".to_string(),
+                api_key: std::env::var("LLAMAFILE_API_KEY").unwrap_or_default(),
+            },
             "openrouter" => AiConfig {
                 provider: "openrouter".to_string(),
                 model: "openai/gpt-4o".to_string(), // Popular default model
@@ -168,6 +566,14 @@ impl AiConfig {
         }
     }
 
+    /// Whether `provider` is a server on the user's machine, which needs no API key
+    pub fn is_local_provider(provider: &str) -> bool {
+        matches!(
+            provider.to_lowercase().as_str(),
+            "ollama" | "lm studio" | "lmstudio" | "lm-studio" | "llamafile"
+        )
+    }
+
     /// Apply provider defaults while preserving user customizations where appropriate
     pub fn apply_provider_defaults(&mut self, preserve_api_key: bool) {
         let defaults = Self::get_provider_defaults(&self.provider);
@@ -192,7 +598,8 @@ impl AiConfig {
     /// Check if a field is editable for the current provider
     pub fn is_field_editable(&self, field: ProviderField) -> bool {
         match self.provider.to_lowercase().as_str() {
-            "custom" | "ollama" => true, // All fields editable for custom and ollama
+            // All fields editable for custom and local servers
+            "custom" | "ollama" | "lm studio" | "llamafile" => true,
             _ => match field {
                 ProviderField::Model => true,   // Model always editable
                 ProviderField::ApiKey => true,  // API key always editable
@@ -239,6 +646,11 @@ impl Config {
         format!("{}/.arula/config.json", home)
     }
 
+    /// Whether a config file has been saved yet (false on first launch)
+    pub fn exists() -> bool {
+        Path::new(&Self::get_config_path()).exists()
+    }
+
     pub fn load_or_default() -> Result<Self> {
         let config_path = Self::get_config_path();
         let config_file = Path::new(&config_path);
@@ -302,6 +714,13 @@ impl Config {
                 web_search_enabled: None,
                 streaming: None,
                 tools_enabled: None,
+                extra_headers: HashMap::new(),
+                extra_body: None,
+                connect_timeout_seconds: None,
+                read_timeout_seconds: None,
+                retry_delay_ms: None,
+                history: None,
+                model_history: HashMap::new(),
             };
 
             self.providers
@@ -338,6 +757,13 @@ impl Config {
                     web_search_enabled: Some(false),
                     streaming: None,
                     tools_enabled: None,
+                    extra_headers: HashMap::new(),
+                    extra_body: None,
+                    connect_timeout_seconds: None,
+                    read_timeout_seconds: None,
+                    retry_delay_ms: None,
+                    history: None,
+                    model_history: HashMap::new(),
                 },
             );
         }
@@ -394,6 +820,247 @@ impl Config {
         self.save()
     }
 
+    /// Get the system prompt token budget
+    pub fn get_context_budget_tokens(&self) -> usize {
+        self.context_budget_tokens
+            .unwrap_or(crate::utils::context_budget::DEFAULT_CONTEXT_BUDGET_TOKENS)
+    }
+
+    /// Set the system prompt token budget
+    pub fn set_context_budget_tokens(&mut self, tokens: usize) -> Result<()> {
+        self.context_budget_tokens = Some(tokens);
+        self.save()
+    }
+
+    /// Get the token limit for a single tool result in the history
+    pub fn get_tool_result_max_tokens(&self) -> usize {
+        self.tool_result_max_tokens
+            .unwrap_or(crate::utils::tool_output::DEFAULT_TOOL_RESULT_MAX_TOKENS)
+    }
+
+    /// Set the token limit for a single tool result in the history
+    pub fn set_tool_result_max_tokens(&mut self, tokens: usize) -> Result<()> {
+        self.tool_result_max_tokens = Some(tokens);
+        self.save()
+    }
+
+    /// Whether repeated identical runs are answered from the response cache
+    pub fn get_response_cache_enabled(&self) -> bool {
+        self.response_cache_enabled.unwrap_or(false)
+    }
+
+    /// Set whether repeated identical runs are answered from the response cache
+    pub fn set_response_cache_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.response_cache_enabled = Some(enabled);
+        self.save()
+    }
+
+    /// Whether file tools are confined to the working directory
+    pub fn get_fs_sandbox_enabled(&self) -> bool {
+        self.fs_sandbox_enabled.unwrap_or(true)
+    }
+
+    /// Set whether file tools are confined to the working directory
+    pub fn set_fs_sandbox_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.fs_sandbox_enabled = Some(enabled);
+        self.save()
+    }
+
+    /// Whether the interactive CLI asks before file writes
+    pub fn get_review_edits(&self) -> bool {
+        self.review_edits.unwrap_or(true)
+    }
+
+    /// Whether the desktop app offers the screenshot tool
+    pub fn get_screen_capture(&self) -> bool {
+        self.screen_capture.unwrap_or(false)
+    }
+
+    /// Telemetry settings, if export is enabled
+    pub fn get_telemetry(&self) -> Option<&TelemetryConfig> {
+        self.telemetry.as_ref().filter(|t| t.enabled)
+    }
+
+    /// Redaction settings, falling back to the defaults when unset
+    pub fn get_redaction(&self) -> RedactionConfig {
+        self.redaction.clone().unwrap_or_default()
+    }
+
+    /// Budget limits for session runs, unlimited when unset
+    pub fn get_budget(&self) -> BudgetLimits {
+        self.budget.clone().unwrap_or_default()
+    }
+
+    /// Desktop color theme name, if one was picked
+    pub fn get_theme(&self) -> Option<&str> {
+        self.theme.as_deref()
+    }
+
+    /// Set the desktop color theme
+    pub fn set_theme(&mut self, theme: &str) -> Result<()> {
+        self.theme = Some(theme.to_string());
+        self.save()
+    }
+
+    /// Knowledge base retrieval settings, falling back to the defaults when unset
+    pub fn get_knowledge(&self) -> KnowledgeConfig {
+        self.knowledge.clone().unwrap_or_default()
+    }
+
+    /// History strategy of the active provider for `model`
+    pub fn get_history_strategy(&self, model: &str) -> HistoryStrategy {
+        self.get_active_provider_config()
+            .and_then(|p| p.model_history.get(model).or(p.history.as_ref()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether git status and recent commits are offered as system prompt context
+    pub fn get_git_context_enabled(&self) -> bool {
+        self.git_context_enabled.unwrap_or(true)
+    }
+
+    /// Set whether git status and recent commits are offered as system prompt context
+    pub fn set_git_context_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.git_context_enabled = Some(enabled);
+        self.save()
+    }
+
+    /// Whether to look for newer releases
+    pub fn get_update_check_enabled(&self) -> bool {
+        self.update_check_enabled.unwrap_or(true)
+    }
+
+    /// Set whether to look for newer releases
+    pub fn set_update_check_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.update_check_enabled = Some(enabled);
+        self.save()
+    }
+
+    /// Days after which saved conversations are deleted, if set
+    pub fn get_conversation_retention_days(&self) -> Option<u32> {
+        self.conversation_retention_days.filter(|days| *days > 0)
+    }
+
+    /// Set after how many days saved conversations are deleted (`None` keeps them)
+    pub fn set_conversation_retention_days(&mut self, days: Option<u32>) -> Result<()> {
+        self.conversation_retention_days = days;
+        self.save()
+    }
+
+    /// How much of each tool call to show
+    pub fn get_tool_verbosity(&self) -> ToolVerbosity {
+        self.tool_verbosity.unwrap_or_default()
+    }
+
+    /// Set how much of each tool call to show
+    pub fn set_tool_verbosity(&mut self, verbosity: ToolVerbosity) -> Result<()> {
+        self.tool_verbosity = Some(verbosity);
+        self.save()
+    }
+
+    /// Where conversation starters come from
+    pub fn get_starter_mode(&self) -> StarterMode {
+        self.starter_mode.unwrap_or_default()
+    }
+
+    /// Pinned conversation starters, without blank entries
+    pub fn get_pinned_starters(&self) -> Vec<String> {
+        self.pinned_starters
+            .iter()
+            .map(|starter| starter.trim().to_string())
+            .filter(|starter| !starter.is_empty())
+            .collect()
+    }
+
+    /// The persona in use, if it still exists
+    pub fn get_active_persona(&self) -> Option<Persona> {
+        let name = self.active_persona.as_deref()?;
+        crate::utils::personas::find(self, name)
+    }
+
+    /// Switch to the persona called `name`, or back to none
+    pub fn set_active_persona(&mut self, name: Option<&str>) -> Result<()> {
+        self.active_persona = name.map(str::to_string);
+        self.save()
+    }
+
+    /// Whether the chat input is spellchecked (default: false)
+    pub fn get_spellcheck_enabled(&self) -> bool {
+        self.spellcheck_enabled.unwrap_or(false)
+    }
+
+    pub fn set_spellcheck_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.spellcheck_enabled = Some(enabled);
+        self.save()
+    }
+
+    /// Dictionary the spellchecker loads, such as `en_US` or `de_DE`
+    pub fn get_spellcheck_language(&self) -> String {
+        self.spellcheck_language
+            .clone()
+            .unwrap_or_else(|| crate::utils::spellcheck::DEFAULT_LANGUAGE.to_string())
+    }
+
+    /// Recently used working directories, most recent first
+    pub fn get_recent_directories(&self) -> Vec<PathBuf> {
+        self.recent_directories.iter().map(PathBuf::from).collect()
+    }
+
+    /// Set the recently used working directories
+    pub fn set_recent_directories(&mut self, directories: &[PathBuf]) -> Result<()> {
+        self.recent_directories = directories
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+        self.save()
+    }
+
+    /// Bookmarked working directories, in the order they were pinned
+    pub fn get_bookmarked_directories(&self) -> Vec<PathBuf> {
+        self.bookmarked_directories.iter().map(PathBuf::from).collect()
+    }
+
+    /// Set the bookmarked working directories
+    pub fn set_bookmarked_directories(&mut self, directories: &[PathBuf]) -> Result<()> {
+        self.bookmarked_directories = directories
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+        self.save()
+    }
+
+    /// Workspaces added in the desktop app, without the default workspace
+    pub fn get_workspaces(&self) -> Vec<Workspace> {
+        self.workspaces.iter().map(Workspace::new).collect()
+    }
+
+    /// Set the workspaces added in the desktop app; the default workspace is skipped
+    pub fn set_workspaces(&mut self, workspaces: &[Workspace]) -> Result<()> {
+        self.workspaces = workspaces
+            .iter()
+            .filter_map(|w| w.root.as_ref())
+            .map(|root| root.to_string_lossy().into_owned())
+            .collect();
+        self.save()
+    }
+
+    /// Sampling parameters beyond temperature
+    pub fn get_sampling(&self) -> SamplingParams {
+        self.sampling.clone().unwrap_or_default()
+    }
+
+    /// Set the sampling parameters beyond temperature
+    pub fn set_sampling(&mut self, sampling: SamplingParams) -> Result<()> {
+        self.sampling = (sampling != SamplingParams::default()).then_some(sampling);
+        self.save()
+    }
+
+    /// WebSocket API settings, if the server is enabled
+    pub fn get_websocket(&self) -> Option<&WebSocketConfig> {
+        self.websocket.as_ref().filter(|w| w.enabled)
+    }
+
     /// Set Z.AI web search enabled
     pub fn set_zai_web_search_enabled(&mut self, enabled: bool) -> Result<()> {
         if let Some(config) = self.get_active_provider_config_mut() {
@@ -512,6 +1179,13 @@ impl Config {
                     .ok()
                     .and_then(|v| v.parse().ok()),
                 tools_enabled: None,
+                extra_headers: HashMap::new(),
+                extra_body: None,
+                connect_timeout_seconds: None,
+                read_timeout_seconds: None,
+                retry_delay_ms: None,
+                history: None,
+                model_history: HashMap::new(),
             },
         );
 
@@ -595,7 +1269,8 @@ impl Config {
     /// Check if a field is editable for the current provider
     pub fn is_field_editable(&self, field: ProviderField) -> bool {
         match self.active_provider.to_lowercase().as_str() {
-            "custom" | "ollama" => true, // All fields editable for custom and ollama
+            // All fields editable for custom and local servers
+            "custom" | "ollama" | "lm studio" | "llamafile" => true,
             _ => match field {
                 ProviderField::Model => true,   // Model always editable
                 ProviderField::ApiKey => true,  // API key always editable
@@ -632,6 +1307,13 @@ impl Config {
                 web_search_enabled: None,
                 streaming: None,
                 tools_enabled: None,
+                extra_headers: HashMap::new(),
+                extra_body: None,
+                connect_timeout_seconds: None,
+                read_timeout_seconds: None,
+                retry_delay_ms: None,
+                history: None,
+                model_history: HashMap::new(),
             },
         );
         Ok(())
@@ -655,6 +1337,13 @@ impl Config {
                 web_search_enabled: None,
                 streaming: None, // Defaults to true when not set
                 tools_enabled: None,
+                extra_headers: HashMap::new(),
+                extra_body: None,
+                connect_timeout_seconds: None,
+                read_timeout_seconds: None,
+                retry_delay_ms: None,
+                history: None,
+                model_history: HashMap::new(),
             },
         );
 
@@ -663,6 +1352,34 @@ impl Config {
             providers,
             mcp_servers: HashMap::new(),
             living_background_enabled: None,
+            context_budget_tokens: None,
+            tool_result_max_tokens: None,
+            response_cache_enabled: None,
+            git_context_enabled: None,
+            update_check_enabled: None,
+            conversation_retention_days: None,
+            tool_verbosity: None,
+            starter_mode: None,
+            pinned_starters: Vec::new(),
+            personas: Vec::new(),
+            active_persona: None,
+            spellcheck_enabled: None,
+            spellcheck_language: None,
+            fs_sandbox_enabled: None,
+            fs_allowed_paths: Vec::new(),
+            review_edits: None,
+            screen_capture: None,
+            telemetry: None,
+            redaction: None,
+            budget: None,
+            sampling: None,
+            websocket: None,
+            webhooks: Vec::new(),
+            recent_directories: Vec::new(),
+            bookmarked_directories: Vec::new(),
+            workspaces: Vec::new(),
+            theme: None,
+            knowledge: None,
             ai: None,
         }
     }
@@ -685,6 +1402,13 @@ impl Config {
                 web_search_enabled: None,
                 streaming: None, // Defaults to true when not set
                 tools_enabled: None,
+                extra_headers: HashMap::new(),
+                extra_body: None,
+                connect_timeout_seconds: None,
+                read_timeout_seconds: None,
+                retry_delay_ms: None,
+                history: None,
+                model_history: HashMap::new(),
             },
         );
 
@@ -693,6 +1417,34 @@ impl Config {
             providers,
             mcp_servers: HashMap::new(),
             living_background_enabled: None,
+            context_budget_tokens: None,
+            tool_result_max_tokens: None,
+            response_cache_enabled: None,
+            git_context_enabled: None,
+            update_check_enabled: None,
+            conversation_retention_days: None,
+            tool_verbosity: None,
+            starter_mode: None,
+            pinned_starters: Vec::new(),
+            personas: Vec::new(),
+            active_persona: None,
+            spellcheck_enabled: None,
+            spellcheck_language: None,
+            fs_sandbox_enabled: None,
+            fs_allowed_paths: Vec::new(),
+            review_edits: None,
+            screen_capture: None,
+            telemetry: None,
+            redaction: None,
+            budget: None,
+            sampling: None,
+            websocket: None,
+            webhooks: Vec::new(),
+            recent_directories: Vec::new(),
+            bookmarked_directories: Vec::new(),
+            workspaces: Vec::new(),
+            theme: None,
+            knowledge: None,
             ai: None,
         }
     }
@@ -713,6 +1465,13 @@ impl Config {
                 web_search_enabled: None,
                 streaming: None,
                 tools_enabled: None,
+                extra_headers: HashMap::new(),
+                extra_body: None,
+                connect_timeout_seconds: None,
+                read_timeout_seconds: None,
+                retry_delay_ms: None,
+                history: None,
+                model_history: HashMap::new(),
             },
         );
 
@@ -721,6 +1480,34 @@ impl Config {
             providers,
             mcp_servers: HashMap::new(),
             living_background_enabled: None,
+            context_budget_tokens: None,
+            tool_result_max_tokens: None,
+            response_cache_enabled: None,
+            git_context_enabled: None,
+            update_check_enabled: None,
+            conversation_retention_days: None,
+            tool_verbosity: None,
+            starter_mode: None,
+            pinned_starters: Vec::new(),
+            personas: Vec::new(),
+            active_persona: None,
+            spellcheck_enabled: None,
+            spellcheck_language: None,
+            fs_sandbox_enabled: None,
+            fs_allowed_paths: Vec::new(),
+            review_edits: None,
+            screen_capture: None,
+            telemetry: None,
+            redaction: None,
+            budget: None,
+            sampling: None,
+            websocket: None,
+            webhooks: Vec::new(),
+            recent_directories: Vec::new(),
+            bookmarked_directories: Vec::new(),
+            workspaces: Vec::new(),
+            theme: None,
+            knowledge: None,
             ai: None,
         }
     }
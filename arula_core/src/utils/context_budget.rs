@@ -0,0 +1,271 @@
+//! Context budget manager
+//!
+//! Assembles the system prompt from prioritized sources (base prompt, project
+//! instructions, PROJECT.manifest, pinned files, git status, code outline)
+//! under a token budget. Lower-priority sources are truncated or dropped first,
+//! and a `ContextReport` records what made it into the prompt so the UI can
+//! show it. Content sent with each prompt instead, like knowledge base
+//! excerpts, takes part through a reservation that holds its share of the
+//! budget without adding text.
+
+use serde::{Deserialize, Serialize};
+
+/// Default system prompt budget when none is configured
+pub const DEFAULT_CONTEXT_BUDGET_TOKENS: usize = 12_000;
+
+/// Sources that would keep fewer tokens than this are dropped instead of truncated
+const MIN_TRUNCATED_TOKENS: usize = 64;
+
+/// Note appended to truncated sources
+const TRUNCATION_NOTE: &str = "\n[... truncated to fit the context budget]";
+
+/// Priority of a context source; `Required` sources are never trimmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ContextPriority {
+    Required,
+    High,
+    Medium,
+    Low,
+}
+
+impl ContextPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContextPriority::Required => "Required",
+            ContextPriority::High => "High",
+            ContextPriority::Medium => "Medium",
+            ContextPriority::Low => "Low",
+        }
+    }
+}
+
+/// A candidate piece of system prompt content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextSource {
+    /// Short label shown in the UI (e.g. "PROJECT.manifest")
+    pub label: String,
+    pub priority: ContextPriority,
+    pub content: String,
+    /// Tokens held for content added later instead of `content`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reserved_tokens: Option<usize>,
+}
+
+impl ContextSource {
+    pub fn new(label: impl Into<String>, priority: ContextPriority, content: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            priority,
+            content: content.into(),
+            reserved_tokens: None,
+        }
+    }
+
+    /// Room for `tokens` of content that is sent with each prompt rather
+    /// than in the system prompt
+    pub fn reserved(label: impl Into<String>, priority: ContextPriority, tokens: usize) -> Self {
+        Self {
+            label: label.into(),
+            priority,
+            content: String::new(),
+            reserved_tokens: Some(tokens),
+        }
+    }
+}
+
+/// What happened to a source during assembly
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContextStatus {
+    Included,
+    Truncated { kept_tokens: usize },
+    Dropped,
+}
+
+/// Per-source entry of a `ContextReport`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextEntry {
+    pub label: String,
+    pub priority: ContextPriority,
+    /// Estimated size of the full source
+    pub tokens: usize,
+    pub status: ContextStatus,
+}
+
+/// Summary of an assembled system prompt
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContextReport {
+    pub budget_tokens: usize,
+    pub used_tokens: usize,
+    /// Entries in prompt order
+    pub entries: Vec<ContextEntry>,
+}
+
+impl ContextReport {
+    /// Number of sources that were truncated or dropped
+    pub fn trimmed_count(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.status != ContextStatus::Included)
+            .count()
+    }
+
+    /// Tokens the source labelled `label` kept, or `None` without such a source
+    pub fn kept_tokens(&self, label: &str) -> Option<usize> {
+        let entry = self.entries.iter().find(|e| e.label == label)?;
+        Some(match entry.status {
+            ContextStatus::Included => entry.tokens,
+            ContextStatus::Truncated { kept_tokens } => kept_tokens,
+            ContextStatus::Dropped => 0,
+        })
+    }
+}
+
+/// Rough token estimate (~4 characters per token)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Collects context sources and fits them into a token budget
+#[derive(Debug, Clone)]
+pub struct ContextBudget {
+    budget_tokens: usize,
+    sources: Vec<ContextSource>,
+}
+
+impl ContextBudget {
+    pub fn new(budget_tokens: usize) -> Self {
+        Self {
+            budget_tokens,
+            sources: Vec::new(),
+        }
+    }
+
+    /// Add a source; empty content is ignored unless it's a reservation
+    pub fn add(&mut self, source: ContextSource) -> &mut Self {
+        if source.reserved_tokens.is_some_and(|t| t > 0) || !source.content.trim().is_empty() {
+            self.sources.push(source);
+        }
+        self
+    }
+
+    /// Build the prompt and report.
+    ///
+    /// Budget is allocated in priority order (insertion order within a
+    /// priority), but sources keep their insertion order in the output.
+    pub fn assemble(self) -> (String, ContextReport) {
+        let sizes: Vec<usize> = self
+            .sources
+            .iter()
+            .map(|s| s.reserved_tokens.unwrap_or_else(|| estimate_tokens(&s.content)))
+            .collect();
+
+        let mut order: Vec<usize> = (0..self.sources.len()).collect();
+        order.sort_by_key(|&i| self.sources[i].priority);
+
+        let mut statuses = vec![ContextStatus::Dropped; self.sources.len()];
+        let mut remaining = self.budget_tokens;
+        for i in order {
+            let size = sizes[i];
+            if self.sources[i].priority == ContextPriority::Required || size <= remaining {
+                statuses[i] = ContextStatus::Included;
+                remaining = remaining.saturating_sub(size);
+            } else if remaining >= MIN_TRUNCATED_TOKENS {
+                statuses[i] = ContextStatus::Truncated { kept_tokens: remaining };
+                remaining = 0;
+            }
+        }
+
+        let mut parts = Vec::new();
+        let mut used_tokens = 0;
+        let mut entries = Vec::new();
+        for ((source, size), status) in self.sources.into_iter().zip(sizes).zip(statuses) {
+            match status {
+                ContextStatus::Included if source.reserved_tokens.is_some() => used_tokens += size,
+                ContextStatus::Truncated { kept_tokens } if source.reserved_tokens.is_some() => {
+                    used_tokens += kept_tokens
+                }
+                ContextStatus::Included => {
+                    used_tokens += size;
+                    parts.push(source.content);
+                }
+                ContextStatus::Truncated { kept_tokens } => {
+                    let text = truncate_to_tokens(&source.content, kept_tokens);
+                    used_tokens += estimate_tokens(&text);
+                    parts.push(text);
+                }
+                ContextStatus::Dropped => {}
+            }
+            entries.push(ContextEntry {
+                label: source.label,
+                priority: source.priority,
+                tokens: size,
+                status,
+            });
+        }
+
+        let report = ContextReport {
+            budget_tokens: self.budget_tokens,
+            used_tokens,
+            entries,
+        };
+        (parts.join("\n"), report)
+    }
+}
+
+/// Cut `text` to roughly `max_tokens`, preferring a line boundary
+fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    let note_tokens = estimate_tokens(TRUNCATION_NOTE);
+    let max_chars = max_tokens.saturating_sub(note_tokens) * 4;
+    let cut = text
+        .char_indices()
+        .nth(max_chars)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    let head = &text[..cut];
+    let head = match head.rfind('\n') {
+        Some(idx) if idx > cut / 2 => &head[..idx],
+        _ => head,
+    };
+    format!("{}{}", head, TRUNCATION_NOTE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_priority_sources_are_trimmed_first() {
+        let mut budget = ContextBudget::new(300);
+        budget
+            .add(ContextSource::new("base", ContextPriority::Required, "a".repeat(400)))
+            .add(ContextSource::new("outline", ContextPriority::Low, "c\n".repeat(400)))
+            .add(ContextSource::new("manifest", ContextPriority::High, "b".repeat(400)))
+            .add(ContextSource::new("empty", ContextPriority::High, "  "));
+
+        let (prompt, report) = budget.assemble();
+        let labels: Vec<&str> = report.entries.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, vec!["base", "outline", "manifest"]);
+        assert_eq!(report.entries[0].status, ContextStatus::Included);
+        assert_eq!(report.entries[2].status, ContextStatus::Included);
+        assert!(matches!(report.entries[1].status, ContextStatus::Truncated { .. }));
+        assert!(prompt.starts_with('a'));
+        assert!(prompt.contains(TRUNCATION_NOTE));
+        assert!(report.used_tokens <= 300);
+    }
+
+    #[test]
+    fn test_reservations_hold_budget_without_text() {
+        let mut budget = ContextBudget::new(300);
+        budget
+            .add(ContextSource::new("base", ContextPriority::Required, "a".repeat(400)))
+            .add(ContextSource::reserved("knowledge", ContextPriority::Medium, 150))
+            .add(ContextSource::new("outline", ContextPriority::Low, "c\n".repeat(400)));
+
+        let (prompt, report) = budget.assemble();
+        assert_eq!(report.kept_tokens("knowledge"), Some(150));
+        assert_eq!(report.kept_tokens("missing"), None);
+        assert!(matches!(report.entries[2].status, ContextStatus::Dropped));
+        assert_eq!(prompt, "a".repeat(400));
+        assert_eq!(report.used_tokens, 250);
+    }
+}
@@ -395,8 +395,10 @@ impl Conversation {
             std::fs::create_dir_all(parent)?;
         }
 
-        // Serialize and save
-        let json = serde_json::to_string_pretty(self)?;
+        // Serialize, redact secrets and save
+        let mut value = serde_json::to_value(self)?;
+        crate::utils::redact::redact_json(&mut value);
+        let json = serde_json::to_string_pretty(&value)?;
         std::fs::write(file_path, json)?;
 
         Ok(())
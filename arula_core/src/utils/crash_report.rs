@@ -0,0 +1,246 @@
+//! Crash reports written by a panic hook
+//!
+//! `install` chains a panic hook that writes `~/.arula/crashes/crash-*.txt`
+//! with the version, platform, panic message and location, a backtrace and
+//! the last log records (info and above, redacted and cut short). The panic
+//! message and log lines can quote prompts, answers or paths, so they stay in
+//! the local file. On the next launch frontends list the reports with
+//! `unseen`, offer to open them, and mark them seen; `issue_url` prefills a
+//! GitHub issue with only the version, platform, location and backtrace.
+//!
+//! Panics inside tokio tasks are not reported: the runtime catches them and
+//! the app keeps running.
+
+use crate::utils::logging;
+use crate::utils::redact;
+use crate::utils::updates::{CURRENT_VERSION, REPOSITORY};
+use chrono::{DateTime, Local, Utc};
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+
+/// Log records included in a report
+const LOG_TAIL: usize = 40;
+
+/// Longest log message kept in a report
+const LOG_MESSAGE_MAX_CHARS: usize = 200;
+
+/// Longest report text put into an issue URL
+const ISSUE_BODY_MAX_CHARS: usize = 6000;
+
+/// A crash report on disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrashReport {
+    pub path: PathBuf,
+    pub created: DateTime<Local>,
+}
+
+impl CrashReport {
+    /// Record that the user has been told about this report
+    pub fn mark_seen(&self) -> std::io::Result<()> {
+        std::fs::write(seen_marker(&self.path), "")
+    }
+}
+
+/// Where crash reports are written
+pub fn crash_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".arula").join("crashes"))
+}
+
+fn seen_marker(report: &Path) -> PathBuf {
+    report.with_extension("seen")
+}
+
+/// Install the panic hook; `app` names the frontend in the report. The
+/// previously installed hook still runs afterwards.
+pub fn install(app: &'static str) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // The runtime turns a task's panic into a JoinError; that's not a crash
+        let in_task = tokio::task::try_id().is_some();
+        if let Some(dir) = crash_dir().filter(|_| !in_task) {
+            match write_report(&dir, app, info) {
+                Ok(path) => eprintln!("Crash report written to {}", path.display()),
+                Err(e) => eprintln!("Failed to write crash report: {}", e),
+            }
+        }
+        previous(info);
+    }));
+}
+
+fn write_report(dir: &Path, app: &str, info: &PanicHookInfo<'_>) -> std::io::Result<PathBuf> {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(non-string panic payload)".to_string());
+    let location = info
+        .location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let thread = std::thread::current()
+        .name()
+        .unwrap_or("unnamed")
+        .to_string();
+    let backtrace = Backtrace::force_capture().to_string();
+    let log_tail = logging::recent_logs(LOG_TAIL)
+        .into_iter()
+        .filter(|record| matches!(record.level.as_str(), "INFO" | "WARN" | "ERROR"))
+        .map(|record| {
+            let message: String = record.message.chars().take(LOG_MESSAGE_MAX_CHARS).collect();
+            format!(
+                "{} {:5} {}: {}",
+                record.timestamp.format("%H:%M:%S%.3f"),
+                record.level,
+                record.target,
+                redact::redact(&message)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let text = render(app, &message, &location, &thread, &backtrace, &log_tail);
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "crash-{}-{}.txt",
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    ));
+    std::fs::write(&path, text)?;
+    Ok(path)
+}
+
+fn render(
+    app: &str,
+    message: &str,
+    location: &str,
+    thread: &str,
+    backtrace: &str,
+    log_tail: &[String],
+) -> String {
+    let mut text = String::new();
+    let _ = writeln!(text, "ARULA crash report");
+    let _ = writeln!(text, "App: {} {}", app, CURRENT_VERSION);
+    let _ = writeln!(
+        text,
+        "Platform: {}-{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(text, "Time: {}", Utc::now().to_rfc3339());
+    let _ = writeln!(text, "Thread: {}", thread);
+    let _ = writeln!(text, "Location: {}", location);
+    let _ = writeln!(text, "Message: {}", redact::redact(message));
+    let _ = writeln!(text, "\nBacktrace:\n{}", backtrace.trim_end());
+    let _ = writeln!(text, "\nRecent log:");
+    if log_tail.is_empty() {
+        let _ = writeln!(text, "(empty)");
+    }
+    for line in log_tail {
+        let _ = writeln!(text, "{}", line);
+    }
+    text
+}
+
+/// Reports the user hasn't been told about yet, oldest first
+pub fn unseen() -> Vec<CrashReport> {
+    crash_dir().map(|dir| unseen_in(&dir)).unwrap_or_default()
+}
+
+fn unseen_in(dir: &Path) -> Vec<CrashReport> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<CrashReport> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "txt") && !seen_marker(path).exists()
+        })
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some(CrashReport {
+                created: DateTime::<Local>::from(modified),
+                path,
+            })
+        })
+        .collect();
+    reports.sort_by_key(|report| report.created);
+    reports
+}
+
+/// The parts of a report that are safe to publish: the app, platform and
+/// location lines and the backtrace, without the panic message or log
+fn shareable(report: &str) -> String {
+    let mut text = String::new();
+    let mut in_backtrace = false;
+    for line in report.lines() {
+        if line == "Backtrace:" {
+            in_backtrace = true;
+        } else if line == "Recent log:" {
+            break;
+        }
+        let header = ["App: ", "Platform: ", "Location: "]
+            .iter()
+            .any(|prefix| line.starts_with(prefix));
+        if in_backtrace || header {
+            let _ = writeln!(text, "{}", line);
+        }
+    }
+    text.trim_end().to_string()
+}
+
+/// New-issue URL on GitHub whose body holds the shareable part of the report
+pub fn issue_url(report: &str) -> String {
+    let title = report
+        .lines()
+        .find_map(|line| line.strip_prefix("Location: "))
+        .map(|location| location.chars().take(80).collect::<String>())
+        .unwrap_or_else(|| "unknown location".to_string());
+    let shareable = shareable(report);
+    let mut body: String = shareable.chars().take(ISSUE_BODY_MAX_CHARS).collect();
+    if body.len() < shareable.len() {
+        body.push_str("\n[... truncated]");
+    }
+    format!(
+        "https://github.com/{}/issues/new?title={}&body={}",
+        REPOSITORY,
+        urlencoding::encode(&format!("Crash at {}", title)),
+        urlencoding::encode(&format!("```\n{}\n```", body))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_and_issue_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = render(
+            "arula-desktop",
+            "index out of bounds",
+            "src/main.rs:1:1",
+            "main",
+            "0: main",
+            &["12:00:00.000 INFO  arula: reading secrets.txt".to_string()],
+        );
+        assert!(text.contains("Message: index out of bounds"));
+
+        let path = dir.path().join("crash-20260101-000000-1.txt");
+        std::fs::write(&path, &text).unwrap();
+        let reports = unseen_in(dir.path());
+        assert_eq!(reports.len(), 1);
+        reports[0].mark_seen().unwrap();
+        assert!(unseen_in(dir.path()).is_empty());
+
+        let url = issue_url(&text);
+        assert!(url.starts_with(
+            "https://github.com/CriticalRange/arula-cli/issues/new?title=Crash%20at%20src%2Fmain.rs"
+        ));
+        assert!(url.contains("0%3A%20main"));
+        assert!(!url.contains("index%20out"));
+        assert!(!url.contains("secrets"));
+    }
+}
@@ -18,6 +18,9 @@
 //! # Environment Variables
 //!
 //! - `ARULA_DEBUG=1` - Enable debug output to console and log file
+//!
+//! Debug messages are also emitted as `tracing` events, so they show up in the
+//! rolling log and the desktop log viewer (see `utils::logging`).
 
 use std::sync::OnceLock;
 
@@ -63,6 +66,7 @@ pub fn debug_print(msg: &str) {
     if is_debug_enabled() {
         println!("🔧 DEBUG: {}", msg);
         crate::utils::logger::debug(msg);
+        trace_debug(None, msg);
     }
 }
 
@@ -75,6 +79,19 @@ pub fn debug_print_module(module: &str, msg: &str) {
     if is_debug_enabled() {
         println!("🔧 [{}] {}", module, msg);
         crate::utils::logger::debug(&format!("[{}] {}", module, msg));
+        trace_debug(Some(module), msg);
+    }
+}
+
+/// Forward a debug message to `tracing`
+///
+/// Used by the debug macros, which expand in other crates that may not depend
+/// on `tracing` themselves.
+#[doc(hidden)]
+pub fn trace_debug(module: Option<&str>, msg: &str) {
+    match module {
+        Some(module) => tracing::debug!(module, "{}", msg),
+        None => tracing::debug!("{}", msg),
     }
 }
 
@@ -234,6 +251,7 @@ macro_rules! debug {
             let msg = format!($($arg)*);
             println!("🔧 DEBUG: {}", msg);
             $crate::utils::logger::debug(&msg);
+            $crate::utils::debug::trace_debug(None, &msg);
         }
     };
 }
@@ -253,6 +271,7 @@ macro_rules! debug_module {
             let msg = format!($($arg)*);
             println!("🔧 [{}] {}", $module, msg);
             $crate::utils::logger::debug(&format!("[{}] {}", $module, msg));
+            $crate::utils::debug::trace_debug(Some($module), &msg);
         }
     };
 }
@@ -0,0 +1,112 @@
+//! Unsent prompt drafts
+//!
+//! What was typed into a session's input but not sent is kept in
+//! `~/.arula/drafts.json`, keyed by session id, so switching tabs or closing
+//! the app doesn't lose a half-written prompt. Saving an empty draft removes
+//! it; drafts older than `MAX_AGE_DAYS` are dropped whenever the file is written.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Drafts untouched for longer than this are forgotten
+pub const MAX_AGE_DAYS: i64 = 30;
+
+/// Serializes read-modify-write cycles of the file
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// An unsent prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Draft {
+    pub text: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Where drafts are kept
+pub fn drafts_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".arula")
+        .join("drafts.json")
+}
+
+/// All saved drafts by session id
+pub fn load() -> HashMap<Uuid, Draft> {
+    load_from(&drafts_path())
+}
+
+pub fn load_from(path: &Path) -> HashMap<Uuid, Draft> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// The most recently edited draft
+pub fn latest(drafts: &HashMap<Uuid, Draft>) -> Option<(Uuid, &Draft)> {
+    drafts
+        .iter()
+        .max_by_key(|(_, draft)| draft.updated_at)
+        .map(|(id, draft)| (*id, draft))
+}
+
+/// Keep `text` as the draft of session `id`, or forget it when blank
+pub fn save(id: Uuid, text: &str) {
+    if let Err(e) = save_to(&drafts_path(), id, text) {
+        tracing::warn!("Failed to save draft: {}", e);
+    }
+}
+
+fn save_to(path: &Path, id: Uuid, text: &str) -> anyhow::Result<()> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut drafts = load_from(path);
+    let now = Utc::now();
+    if text.trim().is_empty() {
+        if drafts.remove(&id).is_none() {
+            return Ok(());
+        }
+    } else {
+        drafts.insert(
+            id,
+            Draft {
+                text: text.to_string(),
+                updated_at: now,
+            },
+        );
+    }
+    drafts.retain(|_, draft| now - draft.updated_at < Duration::days(MAX_AGE_DAYS));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&drafts)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_clear_drafts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("drafts.json");
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+
+        save_to(&path, first, "half a prompt").unwrap();
+        save_to(&path, second, "another\nprompt").unwrap();
+        let drafts = load_from(&path);
+        assert_eq!(drafts.len(), 2);
+        assert_eq!(drafts[&first].text, "half a prompt");
+        assert_eq!(latest(&drafts).map(|(id, _)| id), Some(second));
+
+        // Sending (an empty input) forgets the draft
+        save_to(&path, second, "  ").unwrap();
+        let drafts = load_from(&path);
+        assert_eq!(drafts.len(), 1);
+        assert!(!drafts.contains_key(&second));
+    }
+}
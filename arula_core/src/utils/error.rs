@@ -15,9 +15,35 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! `exit_code_for` turns any of these, found anywhere in an error's chain,
+//! into the exit code of the `arula` process (see `exit_code`).
 
+use crate::api::api::ZAIApiError;
+use crate::utils::budget::BudgetExceeded;
 use thiserror::Error;
 
+/// Exit codes of the `arula` binary by kind of failure, so scripts can branch
+/// on them instead of parsing messages
+pub mod exit_code {
+    /// Any failure without a more specific code
+    pub const FAILURE: i32 = 1;
+    /// The provider rejected the API key
+    pub const AUTH: i32 = 2;
+    /// The provider was unreachable, timed out, rate limited or failed (5xx)
+    pub const NETWORK: i32 = 3;
+    /// A tool call failed
+    pub const TOOL: i32 = 4;
+    /// A token, cost or tool iteration budget was reached
+    pub const BUDGET: i32 = 5;
+    /// No provider configured, unknown model or an invalid config file
+    pub const CONFIG: i32 = 6;
+    /// Invalid command-line arguments
+    pub const USAGE: i32 = 64;
+    /// Cancelled, e.g. with Ctrl+C
+    pub const CANCELLED: i32 = 130;
+}
+
 /// Core errors that can occur in ARULA
 #[derive(Error, Debug)]
 pub enum ArulaError {
@@ -68,6 +94,29 @@ pub enum ArulaError {
     /// Conversation errors
     #[error("Conversation error: {0}")]
     Conversation(String),
+
+    /// A budget limit stopped the run
+    #[error("{0}")]
+    BudgetExceeded(#[from] BudgetExceeded),
+}
+
+impl ArulaError {
+    /// Exit code for this error (see `exit_code`)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ArulaError::Api(err) => err.exit_code(),
+            ArulaError::ToolExecution { .. } => exit_code::TOOL,
+            ArulaError::Config(_) | ArulaError::ProviderNotConfigured(_) => exit_code::CONFIG,
+            ArulaError::Network(err) => reqwest_exit_code(err),
+            ArulaError::Cancelled => exit_code::CANCELLED,
+            ArulaError::BudgetExceeded(_) => exit_code::BUDGET,
+            ArulaError::Io(_)
+            | ArulaError::Json(_)
+            | ArulaError::ChannelClosed
+            | ArulaError::GitState(_)
+            | ArulaError::Conversation(_) => exit_code::FAILURE,
+        }
+    }
 }
 
 /// API-specific errors with detailed information
@@ -105,11 +154,103 @@ pub enum ApiError {
     #[error("Streaming error: {0}")]
     StreamingError(String),
 
+    /// The connection dropped mid-response and reconnecting didn't help
+    #[error("Connection lost: {0}")]
+    ConnectionLost(String),
+
     /// Provider-specific error
     #[error("{provider} error: {message}")]
     ProviderError { provider: String, message: String },
 }
 
+impl ApiError {
+    /// Error for a failed response from `provider` with `status` and `body`;
+    /// `retry_after_secs` is the response's `Retry-After`, if it sent one
+    pub fn from_status(
+        provider: &str,
+        status: reqwest::StatusCode,
+        body: &str,
+        retry_after_secs: Option<u64>,
+    ) -> Self {
+        match status.as_u16() {
+            401 | 403 => ApiError::AuthenticationFailed,
+            429 => ApiError::RateLimited {
+                retry_after_secs: retry_after_secs.unwrap_or(0),
+            },
+            code @ 500..=599 => ApiError::ServerError {
+                status_code: code,
+                message: body.to_string(),
+            },
+            _ => ApiError::ProviderError {
+                provider: provider.to_string(),
+                message: format!("API Error {}: {}", status, body),
+            },
+        }
+    }
+
+    /// Exit code for this error (see `exit_code`)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ApiError::NotInitialized | ApiError::ModelNotFound(_) => exit_code::CONFIG,
+            ApiError::AuthenticationFailed => exit_code::AUTH,
+            ApiError::RateLimited { .. }
+            | ApiError::Timeout { .. }
+            | ApiError::ServerError { .. }
+            | ApiError::ConnectionLost(_) => exit_code::NETWORK,
+            ApiError::InvalidResponse(_)
+            | ApiError::StreamingError(_)
+            | ApiError::ProviderError { .. } => exit_code::FAILURE,
+        }
+    }
+}
+
+fn reqwest_exit_code(err: &reqwest::Error) -> i32 {
+    match err.status().map(|s| s.as_u16()) {
+        Some(401 | 403) => exit_code::AUTH,
+        Some(400..=499) => exit_code::FAILURE,
+        _ => exit_code::NETWORK,
+    }
+}
+
+/// Exit code for an error from anywhere in Arula: the outermost cause of a
+/// known kind decides, anything else is `exit_code::FAILURE`
+pub fn exit_code_for(err: &anyhow::Error) -> i32 {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<ArulaError>() {
+            return err.exit_code();
+        }
+        if let Some(err) = cause.downcast_ref::<ApiError>() {
+            return err.exit_code();
+        }
+        if cause.is::<ToolError>() {
+            return exit_code::TOOL;
+        }
+        if cause.is::<BudgetExceeded>() {
+            return exit_code::BUDGET;
+        }
+        if let Some(err) = cause.downcast_ref::<ZAIApiError>() {
+            return match err {
+                ZAIApiError::AuthenticationError { .. } => exit_code::AUTH,
+                ZAIApiError::RequestError { status_code, .. }
+                | ZAIApiError::StatusError { status_code, .. } => match status_code {
+                    401 | 403 => exit_code::AUTH,
+                    500..=599 => exit_code::NETWORK,
+                    _ => exit_code::FAILURE,
+                },
+                ZAIApiError::NetworkError(err) => reqwest_exit_code(err),
+                ZAIApiError::RateLimitError { .. }
+                | ZAIApiError::TimeoutError { .. }
+                | ZAIApiError::InternalError { .. }
+                | ZAIApiError::ServerFlowExceedError { .. } => exit_code::NETWORK,
+            };
+        }
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return reqwest_exit_code(err);
+        }
+    }
+    exit_code::FAILURE
+}
+
 /// Tool-specific errors
 #[derive(Error, Debug)]
 pub enum ToolError {
@@ -298,6 +439,33 @@ mod tests {
         assert_eq!(result.unwrap(), 42);
     }
 
+    #[test]
+    fn test_exit_codes() {
+        use crate::utils::budget::BudgetKind;
+        use anyhow::Context;
+
+        let auth: anyhow::Error = ZAIApiError::from_status_code(401, "invalid token").into();
+        assert_eq!(exit_code_for(&auth.context("Request failed")), exit_code::AUTH);
+
+        let tool: Result<(), ToolError> = Err(ToolError::NotFound("x".to_string()));
+        assert_eq!(exit_code_for(&tool.context("Running x").unwrap_err()), exit_code::TOOL);
+
+        let budget: anyhow::Error = ArulaError::from(BudgetExceeded {
+            kind: BudgetKind::Tokens,
+            used: 120.0,
+            limit: 100.0,
+        })
+        .into();
+        assert_eq!(exit_code_for(&budget), exit_code::BUDGET);
+
+        assert_eq!(exit_code_for(&ApiError::Timeout { timeout_secs: 5 }.into()), exit_code::NETWORK);
+        assert_eq!(
+            exit_code_for(&ApiError::ConnectionLost("reset by peer".into()).into()),
+            exit_code::NETWORK
+        );
+        assert_eq!(exit_code_for(&anyhow::anyhow!("something else")), exit_code::FAILURE);
+    }
+
     #[test]
     fn test_error_conversion() {
         let tool_err = ToolError::NotFound("my_tool".to_string());
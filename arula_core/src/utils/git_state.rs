@@ -5,7 +5,8 @@
 
 use anyhow::Result;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::process::Command as TokioCommand;
 
 /// Tracks git state for restoration
@@ -233,6 +234,131 @@ impl GitStateTracker {
     }
 }
 
+/// Stdout of `git <args>` in `dir`, or `None` outside a repository or without git
+fn git_output(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `git status --porcelain --branch` of `dir` (branch line plus changed files), capped at `max_lines`.
+///
+/// Returns `None` outside a git repository or when git is unavailable.
+pub fn git_status_summary(dir: &Path, max_lines: usize) -> Option<String> {
+    let stdout = git_output(dir, &["status", "--porcelain", "--branch"])?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    let mut summary = lines.iter().take(max_lines).copied().collect::<Vec<_>>().join("\n");
+    if lines.len() > max_lines {
+        summary.push_str(&format!("\n... and {} more changed files", lines.len() - max_lines));
+    }
+    Some(summary)
+}
+
+/// The last `count` commits of `dir` as `<hash> <subject>` lines
+pub fn recent_commits(dir: &Path, count: usize) -> Option<String> {
+    let log = git_output(dir, &["log", "--oneline", "-n", &count.to_string()])?;
+    let log = log.trim_end();
+    (!log.is_empty()).then(|| log.to_string())
+}
+
+/// Branch and working tree state, for the desktop top bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitInfo {
+    pub branch: String,
+    /// Changed, staged and untracked files
+    pub changed_files: usize,
+}
+
+impl GitInfo {
+    /// Current state of the repository containing `dir`
+    pub fn read(dir: &Path) -> Option<Self> {
+        Some(Self::parse(&git_output(dir, &["status", "--porcelain", "--branch"])?))
+    }
+
+    /// Parse `git status --porcelain --branch` output
+    fn parse(status: &str) -> Self {
+        let mut lines = status.lines();
+        let header = lines
+            .next()
+            .and_then(|l| l.strip_prefix("## "))
+            .unwrap_or_default();
+        let branch = header
+            .strip_prefix("No commits yet on ")
+            .unwrap_or(header)
+            .split("...")
+            .next()
+            .unwrap_or_default()
+            .split(' ')
+            .next()
+            .unwrap_or_default();
+        Self {
+            branch: if branch == "HEAD" { "detached".to_string() } else { branch.to_string() },
+            changed_files: lines.filter(|l| !l.trim().is_empty()).count(),
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.changed_files > 0
+    }
+}
+
+/// Polls a repository's HEAD and index and reports when the branch or
+/// working tree state may have changed.
+///
+/// Like `ManifestWatcher`, it is meant to be polled from the UI tick loop;
+/// checks are throttled to `interval`.
+#[derive(Debug)]
+pub struct GitWatcher {
+    git_dir: Option<PathBuf>,
+    interval: Duration,
+    last_check: Instant,
+    stamp: Option<(String, Option<SystemTime>)>,
+}
+
+impl GitWatcher {
+    pub fn new(dir: &Path, interval: Duration) -> Self {
+        let git_dir = git_output(dir, &["rev-parse", "--absolute-git-dir"])
+            .map(|out| PathBuf::from(out.trim()));
+        let mut watcher = Self {
+            git_dir,
+            interval,
+            last_check: Instant::now(),
+            stamp: None,
+        };
+        watcher.stamp = watcher.take_stamp();
+        watcher
+    }
+
+    /// Contents of HEAD (branch switches) and the index mtime (commits, staging)
+    fn take_stamp(&self) -> Option<(String, Option<SystemTime>)> {
+        let git_dir = self.git_dir.as_ref()?;
+        let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+        let index = fs::metadata(git_dir.join("index"))
+            .and_then(|m| m.modified())
+            .ok();
+        Some((head, index))
+    }
+
+    /// Returns true when HEAD or the index changed since the last poll
+    pub fn poll(&mut self) -> bool {
+        if self.git_dir.is_none() || self.last_check.elapsed() < self.interval {
+            return false;
+        }
+        self.last_check = Instant::now();
+
+        let stamp = self.take_stamp();
+        let changed = stamp != self.stamp;
+        self.stamp = stamp;
+        changed
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -249,4 +375,15 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_git_info() {
+        let info = GitInfo::parse("## main...origin/main [ahead 1]\n M src/lib.rs\n?? notes.txt\n");
+        assert_eq!(info.branch, "main");
+        assert_eq!(info.changed_files, 2);
+        assert!(info.is_dirty());
+
+        assert_eq!(GitInfo::parse("## No commits yet on dev\n").branch, "dev");
+        assert_eq!(GitInfo::parse("## HEAD (no branch)\n").branch, "detached");
+    }
 }
@@ -0,0 +1,382 @@
+//! Raw provider HTTP capture and replay for bug reports
+//!
+//! With `--capture-http` (or `ARULA_CAPTURE_HTTP=1`), every provider request
+//! of a turn is appended to `~/.arula/captures/<time>.jsonl` together with its
+//! status and the raw response body or stream chunks, one JSON record per
+//! line. Bodies pass through the redactor, and credential headers (including
+//! the provider's `extra_headers`) are stored as `[REDACTED]`.
+//! `arula replay-http <file>` sends the captured requests again, so a provider
+//! quirk can be reproduced from a file attached to an issue. Credentials are
+//! filled in from the config only for the configured provider the URL points
+//! at; any other URL needs an explicit `--api-key`, so replaying someone
+//! else's capture can't send your key to a host they picked.
+
+use crate::utils::config::{Config, ProviderConfig};
+use crate::utils::redact::{self, REDACTED};
+use anyhow::{bail, Context, Result};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// File of the current turn and when the turn started
+static CURRENT: Mutex<Option<(PathBuf, Instant)>> = Mutex::new(None);
+
+/// Headers holding credentials, never written to disk
+const SECRET_HEADERS: &[&str] = &["authorization", "x-api-key", "api-key", "cookie"];
+
+/// Capture requests from now on
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether requests are being captured
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+        || std::env::var("ARULA_CAPTURE_HTTP").is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// Directory the capture files are written to
+pub fn captures_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".arula")
+        .join("captures")
+}
+
+/// One line of a capture file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptureRecord {
+    Request {
+        at_ms: u64,
+        method: String,
+        url: String,
+        headers: BTreeMap<String, String>,
+        body: Value,
+    },
+    /// Status line of the response to the last request
+    Status { at_ms: u64, status: u16 },
+    /// One SSE event or NDJSON line of a streamed response
+    Chunk { at_ms: u64, data: String },
+    /// A complete (non-streamed or error) response body
+    Response { at_ms: u64, body: String },
+}
+
+/// Start a new capture file with the next request
+pub fn begin_turn() {
+    if let Ok(mut current) = CURRENT.lock() {
+        *current = None;
+    }
+}
+
+fn append(record: impl FnOnce(u64) -> CaptureRecord) {
+    if !is_enabled() {
+        return;
+    }
+    let Ok(mut current) = CURRENT.lock() else {
+        return;
+    };
+    let (path, started) = current.get_or_insert_with(|| {
+        let name = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f");
+        (
+            captures_dir().join(format!("{}.jsonl", name)),
+            Instant::now(),
+        )
+    });
+    let record = record(started.elapsed().as_millis() as u64);
+    let written = std::fs::create_dir_all(captures_dir()).and_then(|_| {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&*path)?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)
+    });
+    if let Err(err) = written {
+        tracing::warn!("Failed to write HTTP capture {}: {}", path.display(), err);
+    }
+}
+
+/// Record a request about to be sent; `extra_headers` are the provider's
+/// configured headers, which are treated as credentials
+pub fn record_request(
+    request: &reqwest::Request,
+    body: &Value,
+    extra_headers: &HashMap<String, String>,
+) {
+    if !is_enabled() {
+        return;
+    }
+    let headers = request
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            let secret = SECRET_HEADERS.contains(&name.as_str())
+                || extra_headers.keys().any(|h| h.eq_ignore_ascii_case(name.as_str()));
+            let value = if secret {
+                REDACTED.to_string()
+            } else {
+                redact::redact(value.to_str().unwrap_or_default()).into_owned()
+            };
+            (name.to_string(), value)
+        })
+        .collect();
+    let mut body = body.clone();
+    redact::redact_json(&mut body);
+    append(|at_ms| CaptureRecord::Request {
+        at_ms,
+        method: request.method().to_string(),
+        url: request.url().to_string(),
+        headers,
+        body,
+    });
+}
+
+/// Record the status of the response to the last request
+pub fn record_status(status: reqwest::StatusCode) {
+    append(|at_ms| CaptureRecord::Status {
+        at_ms,
+        status: status.as_u16(),
+    });
+}
+
+/// Record one chunk of a streamed response
+pub fn record_chunk(data: &str) {
+    append(|at_ms| CaptureRecord::Chunk {
+        at_ms,
+        data: redact::redact(data).into_owned(),
+    });
+}
+
+/// Record a complete response body
+pub fn record_response(body: &str) {
+    append(|at_ms| CaptureRecord::Response {
+        at_ms,
+        body: redact::redact(body).into_owned(),
+    });
+}
+
+/// A captured request with what came back
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exchange {
+    pub method: String,
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Value,
+    pub status: Option<u16>,
+    /// Response body, or the streamed chunks one per line
+    pub response: String,
+}
+
+/// Requests of a capture file, in the order they were sent
+pub fn load(path: &Path) -> Result<Vec<Exchange>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut exchanges: Vec<Exchange> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: CaptureRecord = serde_json::from_str(line)
+            .with_context(|| format!("Line {} is not a capture record", number + 1))?;
+        if let CaptureRecord::Request {
+            method,
+            url,
+            headers,
+            body,
+            ..
+        } = record
+        {
+            exchanges.push(Exchange {
+                method,
+                url,
+                headers,
+                body,
+                status: None,
+                response: String::new(),
+            });
+            continue;
+        }
+        let Some(exchange) = exchanges.last_mut() else {
+            bail!("Line {} comes before any request", number + 1);
+        };
+        match record {
+            CaptureRecord::Status { status, .. } => exchange.status = Some(status),
+            CaptureRecord::Chunk { data, .. } => {
+                exchange.response.push_str(&data);
+                exchange.response.push('\n');
+            }
+            CaptureRecord::Response { body, .. } => exchange.response.push_str(&body),
+            CaptureRecord::Request { .. } => unreachable!(),
+        }
+    }
+    Ok(exchanges)
+}
+
+/// Configured provider whose API URL has the same host and port as `url`
+fn provider_for<'a>(config: &'a Config, url: &str) -> Option<&'a ProviderConfig> {
+    let origin = |url: &str| {
+        let url = reqwest::Url::parse(url).ok()?;
+        Some((url.host_str()?.to_string(), url.port_or_known_default()))
+    };
+    let target = origin(url)?;
+    config
+        .providers
+        .iter()
+        .find(|(name, provider)| {
+            let api_url = provider.api_url.clone().unwrap_or_else(|| {
+                crate::utils::config::AiConfig::get_provider_defaults(name).api_url
+            });
+            origin(&api_url).as_ref() == Some(&target)
+        })
+        .map(|(_, provider)| provider)
+}
+
+/// Send a captured request again, calling `on_chunk` with each piece of the
+/// response body; returns the status.
+///
+/// Redacted credentials are filled in from the provider configured for the
+/// request's host, or with `api_key`. A URL that belongs to no configured
+/// provider is only sent credentials given explicitly.
+pub async fn replay(
+    exchange: &Exchange,
+    config: &Config,
+    api_key: Option<&str>,
+    mut on_chunk: impl FnMut(&str),
+) -> Result<u16> {
+    let provider = provider_for(config, &exchange.url);
+    let needs_key = exchange.headers.iter().any(|(name, value)| {
+        value == REDACTED && matches!(name.as_str(), "authorization" | "x-api-key" | "api-key")
+    });
+    let api_key = match (api_key, provider) {
+        (Some(key), _) => key.to_string(),
+        (None, Some(provider)) => provider.api_key.clone(),
+        (None, None) if needs_key => bail!(
+            "{} is not the URL of a configured provider; pass --api-key to send a key to it",
+            exchange.url
+        ),
+        (None, None) => String::new(),
+    };
+    let method = reqwest::Method::from_bytes(exchange.method.as_bytes())
+        .with_context(|| format!("Invalid method {}", exchange.method))?;
+    let client = crate::api::http_client::get_streaming_client();
+    let mut request = client.request(method, &exchange.url);
+    for (name, value) in &exchange.headers {
+        let value = match name.as_str() {
+            "content-length" => continue,
+            "authorization" if value == REDACTED => format!("Bearer {}", api_key),
+            "x-api-key" | "api-key" if value == REDACTED => api_key.clone(),
+            _ if value == REDACTED => {
+                let configured = provider.and_then(|p| {
+                    p.extra_headers
+                        .iter()
+                        .find(|(h, _)| h.eq_ignore_ascii_case(name))
+                        .map(|(_, v)| v.clone())
+                });
+                match configured {
+                    Some(value) => value,
+                    None => continue,
+                }
+            }
+            _ => value.clone(),
+        };
+        request = request.header(name, value);
+    }
+    let response = request
+        .body(serde_json::to_vec(&exchange.body)?)
+        .send()
+        .await?;
+    let status = response.status().as_u16();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        on_chunk(&String::from_utf8_lossy(&chunk?));
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_groups_records() {
+        let records = [
+            CaptureRecord::Request {
+                at_ms: 0,
+                method: "POST".into(),
+                url: "https://api.openai.com/v1/chat/completions".into(),
+                headers: BTreeMap::from([("authorization".into(), REDACTED.into())]),
+                body: serde_json::json!({"model": "gpt-4o", "stream": true}),
+            },
+            CaptureRecord::Status {
+                at_ms: 300,
+                status: 200,
+            },
+            CaptureRecord::Chunk {
+                at_ms: 310,
+                data: "{\"delta\":\"Hi\"}".into(),
+            },
+            CaptureRecord::Request {
+                at_ms: 900,
+                method: "POST".into(),
+                url: "https://api.openai.com/v1/chat/completions".into(),
+                headers: BTreeMap::new(),
+                body: Value::Null,
+            },
+            CaptureRecord::Response {
+                at_ms: 950,
+                body: "{\"error\":\"rate limited\"}".into(),
+            },
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.jsonl");
+        let lines: Vec<String> = records
+            .iter()
+            .map(|r| serde_json::to_string(r).unwrap())
+            .collect();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let exchanges = load(&path).unwrap();
+        assert_eq!(exchanges.len(), 2);
+        assert_eq!(exchanges[0].status, Some(200));
+        assert_eq!(exchanges[0].response, "{\"delta\":\"Hi\"}\n");
+        assert_eq!(exchanges[0].headers["authorization"], REDACTED);
+        assert_eq!(exchanges[1].status, None);
+        assert!(lines[1].starts_with("{\"kind\":\"status\""));
+    }
+
+    #[test]
+    fn test_keys_only_go_to_configured_hosts() {
+        let mut config = Config::default();
+        config
+            .add_custom_provider("local", "m", "http://127.0.0.1:8080/v1", "local-key")
+            .unwrap();
+        let provider = provider_for(&config, "http://127.0.0.1:8080/v1/chat/completions");
+        assert_eq!(provider.map(|p| p.api_key.as_str()), Some("local-key"));
+        assert!(provider_for(&config, "http://127.0.0.1:9999/v1/chat/completions").is_none());
+        assert!(provider_for(&config, "https://attacker.example/v1/chat/completions").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_refuses_unknown_host_without_key() {
+        let exchange = Exchange {
+            method: "POST".into(),
+            url: "https://attacker.example/v1/chat/completions".into(),
+            headers: BTreeMap::from([("authorization".into(), REDACTED.into())]),
+            body: Value::Null,
+            status: None,
+            response: String::new(),
+        };
+        let err = replay(&exchange, &Config::default(), None, |_| {})
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("--api-key"));
+    }
+}
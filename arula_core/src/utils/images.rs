@@ -0,0 +1,143 @@
+//! Images attached to messages as vision input
+//!
+//! Attachments travel in `ChatMessage::images` as `data:` URLs, which OpenAI
+//! compatible APIs accept as they are; Anthropic and Ollama get the MIME type
+//! and base64 payload split out of them by [`split_data_url`].
+
+use anyhow::{bail, Context, Result};
+use base64::Engine as _;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Largest image attached to a message; providers reject bigger ones anyway
+pub const MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// MIME type of an image file, from its extension
+pub fn mime_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// The image at `path` as a base64 `data:` URL
+pub fn data_url(path: &Path) -> Result<String> {
+    let mime =
+        mime_type(path).with_context(|| format!("Unsupported image type: {}", path.display()))?;
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .len();
+    if size > MAX_IMAGE_BYTES {
+        bail!(
+            "{} is too large to attach ({} KB, limit {} KB)",
+            path.display(),
+            size / 1024,
+            MAX_IMAGE_BYTES / 1024
+        );
+    }
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(encode(mime, &bytes))
+}
+
+/// `bytes` of the given MIME type as a base64 `data:` URL
+pub fn encode(mime: &str, bytes: &[u8]) -> String {
+    format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )
+}
+
+/// Tool whose results may point at an image to attach
+pub const IMAGE_TOOL: &str = "capture_screen";
+
+/// The screenshot a `capture_screen` result points at with its `image_path`,
+/// as a `data:` URL for the next request. Other tools' results, MCP servers
+/// included, never get files read and uploaded this way. An image that can't
+/// be attached (too large, unreadable) comes back as the error to report.
+pub fn from_tool_result(tool: &str, data: &Value) -> Option<Result<String>> {
+    if tool != IMAGE_TOOL {
+        return None;
+    }
+    let path = data.get("image_path")?.as_str()?;
+    Some(data_url(Path::new(path)))
+}
+
+/// Save raw RGBA pixels, as the clipboard hands them out, as a new PNG in
+/// `dir` so they can be attached like a screenshot
+pub fn save_rgba_png(dir: &Path, width: u32, height: u32, rgba: Vec<u8>) -> Result<PathBuf> {
+    let image = image::RgbaImage::from_raw(width, height, rgba)
+        .context("Image data doesn't match its size")?;
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!(
+        "{}.png",
+        chrono::Local::now().format("%Y%m%d-%H%M%S-%3f")
+    ));
+    image
+        .save_with_format(&path, image::ImageFormat::Png)
+        .with_context(|| format!("Failed to save {}", path.display()))?;
+    Ok(path)
+}
+
+/// Save a pasted clipboard image under `~/.arula/pasted`
+pub fn save_pasted(width: u32, height: u32, rgba: Vec<u8>) -> Result<PathBuf> {
+    let dir = dirs::home_dir()
+        .context("Could not find home directory")?
+        .join(".arula")
+        .join("pasted");
+    save_rgba_png(&dir, width, height, rgba)
+}
+
+/// MIME type and base64 payload of a `data:` URL
+pub fn split_data_url(url: &str) -> Option<(&str, &str)> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let mime = header.strip_suffix(";base64")?;
+    Some((mime, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_url_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shot.PNG");
+        std::fs::write(&path, b"\x89PNG").unwrap();
+
+        let url = data_url(&path).unwrap();
+        assert_eq!(url, "data:image/png;base64,iVBORw==");
+        assert_eq!(split_data_url(&url), Some(("image/png", "iVBORw==")));
+        assert_eq!(split_data_url("https://example.com/a.png"), None);
+        assert!(data_url(&dir.path().join("notes.txt")).is_err());
+    }
+
+    #[test]
+    fn test_only_screenshots_attach_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shot.png");
+        std::fs::write(&path, b"\x89PNG").unwrap();
+        let data = serde_json::json!({ "image_path": path.to_string_lossy() });
+        assert!(from_tool_result(IMAGE_TOOL, &data).unwrap().is_ok());
+        assert!(from_tool_result("mcp_files_read", &data).is_none());
+
+        let large = dir.path().join("large.png");
+        std::fs::File::create(&large).unwrap().set_len(MAX_IMAGE_BYTES + 1).unwrap();
+        let data = serde_json::json!({ "image_path": large.to_string_lossy() });
+        let err = from_tool_result(IMAGE_TOOL, &data).unwrap().unwrap_err();
+        assert!(err.to_string().contains("too large"));
+    }
+
+    #[test]
+    fn test_save_rgba_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = save_rgba_png(dir.path(), 2, 1, vec![255, 0, 0, 255, 0, 0, 255, 255]).unwrap();
+        assert!(data_url(&path).unwrap().starts_with("data:image/png;base64,"));
+        assert!(save_rgba_png(dir.path(), 2, 2, vec![0; 4]).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Event inspector for the desktop developer panel
+//!
+//! Captures what happened during the most recent turn: the raw provider
+//! request and response bodies, each streamed chunk, the `StreamEvent`s the
+//! backend produced and the `UiEvent`s the frontend received, all stamped with
+//! the time since the turn started. `begin_turn` clears the previous turn.
+//!
+//! This replaces printing request/response bodies to stdout under `ARULA_DEBUG`,
+//! which corrupted the CLI's TUI and was invisible in the desktop app.
+//!
+//! Nothing is recorded (or formatted) until a frontend opens its panel with
+//! `set_enabled(true)`, so closed inspectors cost nothing per chunk.
+
+use crate::utils::redact;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Entries kept per turn; older chunks are dropped first
+pub const INSPECTOR_CAPACITY: usize = 5000;
+
+/// Bodies longer than this are cut, so a huge history can't stall the UI
+pub const MAX_BODY_CHARS: usize = 200_000;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static INSPECTOR: Mutex<Option<Turn>> = Mutex::new(None);
+
+/// Where an inspector entry came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum InspectorSource {
+    /// Request body sent to the provider
+    Request,
+    /// Full (non-streaming or error) response body
+    Response,
+    /// One raw SSE/NDJSON chunk
+    Chunk,
+    /// Backend `StreamEvent`
+    StreamEvent,
+    /// Frontend `UiEvent`
+    UiEvent,
+}
+
+impl InspectorSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            InspectorSource::Request => "request",
+            InspectorSource::Response => "response",
+            InspectorSource::Chunk => "chunk",
+            InspectorSource::StreamEvent => "stream",
+            InspectorSource::UiEvent => "ui",
+        }
+    }
+}
+
+/// One captured item
+#[derive(Debug, Clone, Serialize)]
+pub struct InspectorEntry {
+    /// Milliseconds since the turn started
+    pub elapsed_ms: u64,
+    pub source: InspectorSource,
+    /// Short description such as the URL or event variant
+    pub title: String,
+    /// Raw payload (pretty JSON for requests/responses)
+    pub body: String,
+}
+
+/// Copy of the current turn for display
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InspectorSnapshot {
+    pub started_at: Option<DateTime<Utc>>,
+    pub entries: Vec<InspectorEntry>,
+    /// Entries dropped because the turn exceeded `INSPECTOR_CAPACITY`
+    pub dropped: usize,
+}
+
+impl InspectorSnapshot {
+    /// Milliseconds until the first chunk arrived
+    pub fn first_chunk_ms(&self) -> Option<u64> {
+        self.entries
+            .iter()
+            .find(|e| e.source == InspectorSource::Chunk)
+            .map(|e| e.elapsed_ms)
+    }
+
+    /// Milliseconds covered by the recorded entries
+    pub fn duration_ms(&self) -> u64 {
+        self.entries.last().map(|e| e.elapsed_ms).unwrap_or(0)
+    }
+}
+
+struct Turn {
+    started: Instant,
+    started_at: DateTime<Utc>,
+    entries: Vec<InspectorEntry>,
+    dropped: usize,
+}
+
+/// Whether entries are recorded, i.e. whether an inspector is open
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Start capturing a new turn, discarding the previous one
+pub fn begin_turn() {
+    if let Ok(mut inspector) = INSPECTOR.lock() {
+        *inspector = Some(Turn {
+            started: Instant::now(),
+            started_at: Utc::now(),
+            entries: Vec::new(),
+            dropped: 0,
+        });
+    }
+}
+
+/// Record an entry in the current turn (no-op while disabled or before the
+/// first `begin_turn`)
+pub fn record(source: InspectorSource, title: impl Into<String>, body: impl Into<String>) {
+    if !enabled() {
+        return;
+    }
+    let Ok(mut inspector) = INSPECTOR.lock() else {
+        return;
+    };
+    let Some(turn) = inspector.as_mut() else {
+        return;
+    };
+    if turn.entries.len() >= INSPECTOR_CAPACITY {
+        // Keep the request and the most recent events
+        if let Some(pos) = turn
+            .entries
+            .iter()
+            .position(|e| e.source != InspectorSource::Request)
+        {
+            turn.entries.remove(pos);
+            turn.dropped += 1;
+        }
+    }
+    let mut body = body.into();
+    if let Cow::Owned(redacted) = redact::redact(&body) {
+        body = redacted;
+    }
+    if body.len() > MAX_BODY_CHARS {
+        let cut = (0..=MAX_BODY_CHARS)
+            .rev()
+            .find(|i| body.is_char_boundary(*i))
+            .unwrap_or(0);
+        body.truncate(cut);
+        body.push_str("\n… (truncated)");
+    }
+    turn.entries.push(InspectorEntry {
+        elapsed_ms: turn.started.elapsed().as_millis() as u64,
+        source,
+        title: title.into(),
+        body,
+    });
+}
+
+/// Record a JSON request body sent to `url`
+pub fn record_request(url: &str, body: &serde_json::Value) {
+    if !enabled() {
+        return;
+    }
+    record(
+        InspectorSource::Request,
+        url,
+        serde_json::to_string_pretty(body).unwrap_or_default(),
+    );
+}
+
+/// Record a complete response body, pretty-printed when it is JSON
+pub fn record_response(status: impl std::fmt::Display, body: &str) {
+    if !enabled() {
+        return;
+    }
+    let pretty = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| serde_json::to_string_pretty(&v).ok())
+        .unwrap_or_else(|| body.to_string());
+    record(InspectorSource::Response, status.to_string(), pretty);
+}
+
+/// Record an event by its variant name and `Debug` form
+pub fn record_event(source: InspectorSource, event: &impl std::fmt::Debug) {
+    if !enabled() {
+        return;
+    }
+    let body = format!("{:?}", event);
+    let title = body
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    record(source, title, body);
+}
+
+/// Copy of everything captured for the current turn
+pub fn snapshot() -> InspectorSnapshot {
+    let Ok(inspector) = INSPECTOR.lock() else {
+        return InspectorSnapshot::default();
+    };
+    inspector
+        .as_ref()
+        .map(|turn| InspectorSnapshot {
+            started_at: Some(turn.started_at),
+            entries: turn.entries.clone(),
+            dropped: turn.dropped,
+        })
+        .unwrap_or_default()
+}
+
+/// Number of entries in the current turn, for cheap change detection
+pub fn entry_count() -> usize {
+    INSPECTOR
+        .lock()
+        .ok()
+        .and_then(|i| i.as_ref().map(|t| t.entries.len() + t.dropped))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_capture() {
+        set_enabled(true);
+        begin_turn();
+        record_request("http://localhost/v1", &serde_json::json!({"model": "m"}));
+        record(InspectorSource::Chunk, "sse", "{\"delta\":\"hi\"}");
+        record_event(InspectorSource::UiEvent, &Some(1));
+
+        let snapshot = snapshot();
+        assert_eq!(snapshot.entries.len(), 3);
+        assert_eq!(snapshot.entries[0].source, InspectorSource::Request);
+        assert!(snapshot.entries[0].body.contains("\"model\""));
+        assert_eq!(snapshot.entries[2].title, "Some");
+        assert!(snapshot.first_chunk_ms().is_some());
+    }
+}
@@ -0,0 +1,565 @@
+//! Workspace knowledge base
+//!
+//! Reference documents (PDF, Markdown, plain text) attached to a workspace are
+//! split into paragraph-aligned chunks and kept as one JSON file per document
+//! in `~/.arula/workspaces/<name>-<hash>/knowledge` (`~/.arula/knowledge` for
+//! the default workspace). With `knowledge.embedding_model` configured, chunks
+//! are embedded through the provider's OpenAI-compatible `/embeddings`
+//! endpoint and matched to prompts by cosine similarity; otherwise, and for
+//! documents added before a model was set, they are ranked by keyword overlap.
+//! The best chunks are sent along with each prompt, within the share of the
+//! context budget reserved for them. Documents are read once and read again
+//! only after the directory changes.
+//!
+//! PDFs are read with `pdftotext` from poppler-utils, which must be on the PATH.
+
+use crate::utils::config::{AiConfig, Config};
+use crate::utils::context_budget::estimate_tokens;
+use crate::utils::workspace::Workspace;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// File extensions that can be added
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["pdf", "md", "markdown", "txt"];
+
+/// Target size of one chunk
+const CHUNK_TOKENS: usize = 400;
+
+/// Excerpt tokens added to a prompt unless `knowledge.max_context_tokens` says otherwise
+pub const DEFAULT_CONTEXT_TOKENS: usize = 2000;
+
+/// Chunks embedded per request
+const EMBED_BATCH: usize = 64;
+
+const EMBED_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Lowest cosine similarity for an embedded chunk to count as relevant
+const MIN_SIMILARITY: f32 = 0.3;
+
+/// Lowest share of the prompt's keywords a chunk must contain
+const MIN_KEYWORD_SCORE: f32 = 0.5;
+
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "are", "but", "not", "you", "all", "can", "how", "what", "when", "where",
+    "which", "who", "why", "this", "that", "with", "from", "have", "has", "was", "were", "will",
+    "would", "should", "could", "does", "did", "into", "about", "there", "their", "them", "then",
+    "than", "your", "our", "its", "any", "some", "use", "using", "please", "just", "like", "make",
+];
+
+/// A piece of a document, with its embedding when one was computed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chunk {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embedding: Vec<f32>,
+}
+
+/// A document attached to a workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Document {
+    pub id: String,
+    /// File name shown in the UI
+    pub name: String,
+    /// Where the document was added from
+    pub source: PathBuf,
+    pub added_at: DateTime<Utc>,
+    /// Model the chunk embeddings were computed with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+    pub chunks: Vec<Chunk>,
+}
+
+/// A document as listed on the Knowledge page, without its chunks
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentInfo {
+    pub id: String,
+    pub name: String,
+    pub source: PathBuf,
+    pub added_at: DateTime<Utc>,
+    pub chunks: usize,
+    pub tokens: usize,
+    pub embedding_model: Option<String>,
+}
+
+impl From<&Document> for DocumentInfo {
+    fn from(doc: &Document) -> Self {
+        Self {
+            id: doc.id.clone(),
+            name: doc.name.clone(),
+            source: doc.source.clone(),
+            added_at: doc.added_at,
+            chunks: doc.chunks.len(),
+            tokens: doc.chunks.iter().map(|c| estimate_tokens(&c.text)).sum(),
+            embedding_model: doc.embedding_model.clone(),
+        }
+    }
+}
+
+/// A chunk picked for a prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct Excerpt {
+    /// Name of the document it came from
+    pub document: String,
+    pub text: String,
+    pub score: f32,
+}
+
+/// Documents as last read, with the directory's modification time then
+type DocumentCache = Arc<Mutex<Option<(SystemTime, Arc<Vec<Document>>)>>>;
+
+/// The documents of one workspace
+#[derive(Debug, Clone)]
+pub struct KnowledgeBase {
+    dir: PathBuf,
+    cache: DocumentCache,
+}
+
+impl KnowledgeBase {
+    /// A knowledge base stored in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            cache: Arc::default(),
+        }
+    }
+
+    /// The knowledge base of `workspace`
+    pub fn for_workspace(workspace: &Workspace) -> Result<Self> {
+        let arula_dir = dirs::home_dir()
+            .context("Could not find home directory")?
+            .join(".arula");
+        Ok(Self::new(workspace.knowledge_dir_in(&arula_dir)))
+    }
+
+    fn document_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn load_all(&self) -> Vec<Document> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut docs: Vec<Document> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let loaded = std::fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|json| Ok(serde_json::from_str::<Document>(&json)?));
+                match loaded {
+                    Ok(doc) => Some(doc),
+                    Err(e) => {
+                        tracing::warn!("Skipping knowledge file {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        docs.sort_by(|a, b| a.added_at.cmp(&b.added_at));
+        docs
+    }
+
+    /// Whether any document is attached, without reading them
+    pub fn has_documents(&self) -> bool {
+        std::fs::read_dir(&self.dir).is_ok_and(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        })
+    }
+
+    /// The documents, read off the async runtime when the directory changed
+    /// since they were last read
+    async fn documents(&self) -> Arc<Vec<Document>> {
+        let modified = std::fs::metadata(&self.dir).and_then(|m| m.modified()).ok();
+        let Some(modified) = modified else {
+            return Arc::default();
+        };
+        if let Some((at, docs)) = self.cache.lock().ok().and_then(|c| c.clone()) {
+            if at == modified {
+                return docs;
+            }
+        }
+        let this = self.clone();
+        let docs = Arc::new(
+            tokio::task::spawn_blocking(move || this.load_all())
+                .await
+                .unwrap_or_default(),
+        );
+        if let Ok(mut cache) = self.cache.lock() {
+            *cache = Some((modified, docs.clone()));
+        }
+        docs
+    }
+
+    /// Attached documents, oldest first
+    pub fn list(&self) -> Vec<DocumentInfo> {
+        self.load_all().iter().map(DocumentInfo::from).collect()
+    }
+
+    /// Read, chunk and (when configured) embed the file at `path`
+    pub async fn add(&self, path: &Path, config: &Config) -> Result<DocumentInfo> {
+        let text = extract_text(path)?;
+        let chunks = chunk_text(&text, CHUNK_TOKENS);
+        if chunks.is_empty() {
+            bail!("{} contains no text", path.display());
+        }
+
+        let mut doc = Document {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string()),
+            source: path.to_path_buf(),
+            added_at: Utc::now(),
+            embedding_model: None,
+            chunks: chunks
+                .into_iter()
+                .map(|text| Chunk {
+                    text,
+                    embedding: Vec::new(),
+                })
+                .collect(),
+        };
+        if let Some(model) = config.get_knowledge().embedding_model {
+            let texts: Vec<String> = doc.chunks.iter().map(|c| c.text.clone()).collect();
+            let embeddings = embed(&texts, config).await?;
+            for (chunk, embedding) in doc.chunks.iter_mut().zip(embeddings) {
+                chunk.embedding = embedding;
+            }
+            doc.embedding_model = Some(model);
+        }
+
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        std::fs::write(self.document_path(&doc.id), serde_json::to_string(&doc)?)
+            .context("Failed to save document")?;
+        tracing::info!(name = %doc.name, chunks = doc.chunks.len(), "Added document to knowledge base");
+        Ok(DocumentInfo::from(&doc))
+    }
+
+    /// Remove the document with `id`
+    pub fn remove(&self, id: &str) -> Result<()> {
+        std::fs::remove_file(self.document_path(id))
+            .with_context(|| format!("Failed to remove document {}", id))
+    }
+
+    /// The chunks most relevant to `query`, best first, within `max_tokens`
+    pub async fn retrieve(&self, query: &str, config: &Config, max_tokens: usize) -> Vec<Excerpt> {
+        let docs = self.documents().await;
+        if docs.is_empty() || query.trim().is_empty() {
+            return Vec::new();
+        }
+        let settings = config.get_knowledge();
+
+        let query_embedding = match &settings.embedding_model {
+            Some(model)
+                if docs
+                    .iter()
+                    .any(|d| d.embedding_model.as_deref() == Some(model.as_str())) =>
+            {
+                match embed(&[query.to_string()], config).await {
+                    Ok(mut embeddings) => embeddings.pop(),
+                    Err(e) => {
+                        tracing::warn!("Falling back to keyword search: {}", e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let keywords = keywords(query);
+        let mut scored: Vec<Excerpt> = Vec::new();
+        for doc in docs.iter() {
+            let same_model =
+                doc.embedding_model.is_some() && doc.embedding_model == settings.embedding_model;
+            for chunk in &doc.chunks {
+                let score = match &query_embedding {
+                    Some(q) if same_model && !chunk.embedding.is_empty() => {
+                        Some(cosine(q, &chunk.embedding)).filter(|s| *s >= MIN_SIMILARITY)
+                    }
+                    _ => Some(keyword_score(&keywords, &chunk.text))
+                        .filter(|s| *s >= MIN_KEYWORD_SCORE),
+                };
+                if let Some(score) = score {
+                    scored.push(Excerpt {
+                        document: doc.name.clone(),
+                        text: chunk.text.clone(),
+                        score,
+                    });
+                }
+            }
+        }
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        let mut used = 0;
+        scored
+            .into_iter()
+            .take_while(|excerpt| {
+                used += estimate_tokens(&excerpt.text);
+                used <= max_tokens
+            })
+            .collect()
+    }
+}
+
+/// Plain text of a supported document
+pub fn extract_text(path: &Path) -> Result<String> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "pdf" => {
+            let output = Command::new("pdftotext")
+                .args(["-layout", "-enc", "UTF-8"])
+                .arg(path)
+                .arg("-")
+                .output()
+                .context("Failed to run pdftotext; install poppler-utils to add PDFs")?;
+            if !output.status.success() {
+                bail!(
+                    "pdftotext failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        "md" | "markdown" | "txt" => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display())),
+        _ => bail!(
+            "Unsupported document type; expected one of: {}",
+            SUPPORTED_EXTENSIONS.join(", ")
+        ),
+    }
+}
+
+/// Split `text` into chunks of about `max_tokens`, at paragraph breaks where possible
+pub fn chunk_text(text: &str, max_tokens: usize) -> Vec<String> {
+    let max_chars = max_tokens * 4;
+    let mut pieces: Vec<String> = Vec::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if paragraph.chars().count() <= max_chars {
+            pieces.push(paragraph.to_string());
+            continue;
+        }
+        // Oversized paragraphs are cut at the last whitespace before the limit
+        let mut rest = paragraph;
+        while !rest.is_empty() {
+            let end = rest
+                .char_indices()
+                .nth(max_chars)
+                .map(|(i, _)| i)
+                .unwrap_or(rest.len());
+            let cut = if end < rest.len() {
+                rest[..end]
+                    .rfind(char::is_whitespace)
+                    .filter(|&i| i > 0)
+                    .unwrap_or(end)
+            } else {
+                end
+            };
+            pieces.push(rest[..cut].trim().to_string());
+            rest = rest[cut..].trim_start();
+        }
+    }
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for piece in pieces {
+        if !current.is_empty() && estimate_tokens(&current) + estimate_tokens(&piece) > max_tokens {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(&piece);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Distinct lowercase words of a prompt that are worth matching
+fn keywords(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .map(str::to_lowercase)
+        .filter(|w| w.chars().count() >= 3 && !STOP_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Share of `keywords` that occur in `text`
+fn keyword_score(keywords: &HashSet<String>, text: &str) -> f32 {
+    if keywords.is_empty() {
+        return 0.0;
+    }
+    let words = self::keywords(text);
+    keywords.iter().filter(|k| words.contains(*k)).count() as f32 / keywords.len() as f32
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
+
+/// Embed `texts` with the configured model, in request-sized batches
+async fn embed(texts: &[String], config: &Config) -> Result<Vec<Vec<f32>>> {
+    let settings = config.get_knowledge();
+    let model = settings
+        .embedding_model
+        .context("No embedding model configured")?;
+    let provider = settings
+        .embedding_provider
+        .unwrap_or_else(|| config.active_provider.clone());
+    let provider_config = config.providers.get(&provider);
+    let base_url = provider_config
+        .and_then(|p| p.api_url.clone())
+        .unwrap_or_else(|| AiConfig::get_provider_defaults(&provider).api_url);
+    let mut base_url = base_url.trim_end_matches('/').to_string();
+    // Ollama serves the OpenAI-compatible API under /v1
+    if provider.eq_ignore_ascii_case("ollama") && !base_url.ends_with("/v1") {
+        base_url.push_str("/v1");
+    }
+    let url = format!("{}/embeddings", base_url);
+    let api_key = provider_config
+        .map(|p| p.api_key.clone())
+        .unwrap_or_default();
+
+    let client = reqwest::Client::builder()
+        .timeout(EMBED_TIMEOUT)
+        .build()
+        .context("Failed to create HTTP client")?;
+    let mut embeddings = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(EMBED_BATCH) {
+        let mut request = client
+            .post(&url)
+            .json(&serde_json::json!({ "model": model, "input": batch }));
+        if !api_key.is_empty() {
+            request = request.bearer_auth(&api_key);
+        }
+        let response = request.send().await.context("Embedding request failed")?;
+        let status = response.status();
+        let body = response.text().await.context("Failed to read embeddings")?;
+        if !status.is_success() {
+            bail!("Embedding endpoint returned {}: {}", status, body.trim());
+        }
+
+        #[derive(Deserialize)]
+        struct Item {
+            index: usize,
+            embedding: Vec<f32>,
+        }
+        #[derive(Deserialize)]
+        struct Response {
+            data: Vec<Item>,
+        }
+        let mut data = serde_json::from_str::<Response>(&body)
+            .context("Invalid embeddings response")?
+            .data;
+        if data.len() != batch.len() {
+            bail!(
+                "Embedding endpoint returned {} vectors for {} inputs",
+                data.len(),
+                batch.len()
+            );
+        }
+        data.sort_by_key(|item| item.index);
+        embeddings.extend(data.into_iter().map(|item| item.embedding));
+    }
+    Ok(embeddings)
+}
+
+/// The prompt sent in place of `prompt` with the excerpts picked for it
+pub fn prompt_with_excerpts(prompt: &str, excerpts: &[Excerpt]) -> String {
+    let documents: Vec<String> = excerpts
+        .iter()
+        .map(|e| {
+            format!(
+                "<excerpt document=\"{}\">\n{}\n</excerpt>",
+                e.document, e.text
+            )
+        })
+        .collect();
+    format!(
+        "{}\n\n<knowledge_base>\n{}\n</knowledge_base>",
+        prompt.trim(),
+        documents.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_and_keyword_retrieval() {
+        let text = format!(
+            "# Deploying\n\nThe staging cluster is deployed with `make deploy-staging`.\n\n{}\n\n{}",
+            "Unrelated filler text. ".repeat(60),
+            "word ".repeat(500)
+        );
+        let chunks = chunk_text(&text, 100);
+        assert!(chunks.len() > 3, "{chunks:?}");
+        assert!(chunks.iter().all(|c| estimate_tokens(c) <= 100));
+        assert!(chunks[0].contains("make deploy-staging"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let kb = KnowledgeBase::new(dir.path());
+        let doc = Document {
+            id: "a".to_string(),
+            name: "ops.md".to_string(),
+            source: PathBuf::from("ops.md"),
+            added_at: Utc::now(),
+            embedding_model: None,
+            chunks: chunks
+                .into_iter()
+                .map(|text| Chunk {
+                    text,
+                    embedding: Vec::new(),
+                })
+                .collect(),
+        };
+        std::fs::write(kb.document_path("a"), serde_json::to_string(&doc).unwrap()).unwrap();
+        assert_eq!(kb.list()[0].name, "ops.md");
+
+        let config = Config::default();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let hits = runtime.block_on(kb.retrieve(
+            "How is the staging cluster deployed?",
+            &config,
+            DEFAULT_CONTEXT_TOKENS,
+        ));
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].text.contains("deploy-staging"));
+        assert!(runtime
+            .block_on(kb.retrieve("kubernetes helm charts", &config, DEFAULT_CONTEXT_TOKENS))
+            .is_empty());
+        assert!(runtime
+            .block_on(kb.retrieve("staging cluster deployed", &config, 0))
+            .is_empty());
+
+        assert!(kb.has_documents());
+        kb.remove("a").unwrap();
+        assert!(kb.list().is_empty());
+        assert!(!kb.has_documents());
+    }
+}
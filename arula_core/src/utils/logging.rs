@@ -0,0 +1,310 @@
+//! Structured logging built on `tracing`
+//!
+//! `init_logging` installs a subscriber with two layers:
+//! - a daily rolling file under `~/.arula/logs/arula.YYYY-MM-DD.log`
+//! - an in-memory ring buffer that the desktop log viewer reads via `recent_logs`
+//!
+//! Nothing is written to stdout/stderr so the CLI's TUI is never disturbed.
+//! Both layers pass records through `utils::redact` first.
+//! Agent runs are wrapped in `session` spans and tool executions in
+//! `tool_call` spans, so every record carries the session and tool it belongs to.
+//!
+//! # Level selection
+//!
+//! `--log-level` wins, then `ARULA_LOG` (any `EnvFilter` directive such as
+//! `arula_core=debug,warn`), then `debug` when `ARULA_DEBUG=1`, else `info`.
+//!
+//! # OpenTelemetry
+//!
+//! When a `TelemetryConfig` is passed and enabled, spans are also exported over
+//! OTLP (see `utils::telemetry`). A collector that can't be set up is logged as
+//! a warning instead of failing logging altogether.
+
+use crate::utils::config::TelemetryConfig;
+use crate::utils::redact;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::format::DefaultFields;
+use tracing_subscriber::fmt::FormattedFields;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Environment variable holding a log filter directive
+pub const LOG_LEVEL_ENV: &str = "ARULA_LOG";
+
+/// Number of records kept for the in-app log viewer
+pub const LOG_BUFFER_CAPACITY: usize = 2000;
+
+/// Rolled log files kept on disk
+const MAX_LOG_FILES: usize = 7;
+
+/// Levels offered by `--log-level`, most severe first
+pub const LOG_LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+static LOG_BUFFER: OnceLock<Mutex<VecDeque<LogRecord>>> = OnceLock::new();
+
+/// A log event captured for the in-app viewer
+#[derive(Debug, Clone, Serialize)]
+pub struct LogRecord {
+    pub timestamp: DateTime<Utc>,
+    /// Upper-case level name (e.g. "WARN")
+    pub level: String,
+    pub target: String,
+    /// Enclosing spans, outermost first (e.g. `session{id=..}:tool_call{name=..}`)
+    pub spans: String,
+    /// Message followed by any extra fields as `key=value`
+    pub message: String,
+}
+
+/// Keeps the background file writer (and OTLP exporters) alive; drop it only on shutdown
+pub struct LoggingGuard {
+    _file_guard: WorkerGuard,
+    #[cfg(feature = "otel")]
+    _telemetry: Option<crate::utils::telemetry::TelemetryGuard>,
+}
+
+/// Directory the rolling log files are written to
+pub fn log_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".arula")
+        .join("logs")
+}
+
+/// Install the global subscriber.
+///
+/// `level` is the value of `--log-level`, if given; `telemetry` is the
+/// `telemetry` section of the config. Fails if the level is not a valid
+/// filter, the log directory can't be created, or a subscriber is already
+/// installed.
+pub fn init_logging(
+    level: Option<&str>,
+    telemetry: Option<&TelemetryConfig>,
+) -> anyhow::Result<LoggingGuard> {
+    let directive = level
+        .map(str::to_string)
+        .or_else(|| std::env::var(LOG_LEVEL_ENV).ok())
+        .unwrap_or_else(|| {
+            if crate::utils::debug::is_debug_enabled() {
+                "debug".to_string()
+            } else {
+                "info".to_string()
+            }
+        });
+    let filter = EnvFilter::try_new(&directive)
+        .map_err(|e| anyhow::anyhow!("Invalid log level '{}': {}", directive, e))?;
+
+    let dir = log_dir();
+    std::fs::create_dir_all(&dir)?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("arula")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)?;
+    let (writer, file_guard) = tracing_appender::non_blocking(appender);
+
+    let telemetry = telemetry.filter(|t| t.enabled);
+    #[cfg(feature = "otel")]
+    let (telemetry_guard, telemetry_error) = match telemetry.map(crate::utils::telemetry::init) {
+        Some(Ok(guard)) => (Some(guard), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    #[cfg(feature = "otel")]
+    let otel_layer = telemetry_guard.as_ref().map(|guard| guard.trace_layer());
+    #[cfg(not(feature = "otel"))]
+    let otel_layer: Option<tracing_subscriber::layer::Identity> = None;
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(move || RedactingWriter(writer.clone()))
+                .with_ansi(false)
+                .with_target(true),
+        )
+        .with(BufferLayer)
+        .with(otel_layer)
+        .try_init()?;
+
+    #[cfg(feature = "otel")]
+    if let Some(e) = telemetry_error {
+        tracing::warn!("OpenTelemetry export disabled: {}", e);
+    }
+    #[cfg(not(feature = "otel"))]
+    if telemetry.is_some() {
+        tracing::warn!("Telemetry is enabled in config but this build lacks the `otel` feature");
+    }
+
+    Ok(LoggingGuard {
+        _file_guard: file_guard,
+        #[cfg(feature = "otel")]
+        _telemetry: telemetry_guard,
+    })
+}
+
+/// Extract `--log-level <level>` or `--log-level=<level>` from raw arguments
+///
+/// For binaries that don't use clap (the desktop app).
+pub fn log_level_from_args(args: impl IntoIterator<Item = String>) -> Option<String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--log-level" {
+            return args.next();
+        }
+        if let Some(level) = arg.strip_prefix("--log-level=") {
+            return Some(level.to_string());
+        }
+    }
+    None
+}
+
+/// The most recent `limit` records, oldest first
+pub fn recent_logs(limit: usize) -> Vec<LogRecord> {
+    let Some(buffer) = LOG_BUFFER.get() else {
+        return Vec::new();
+    };
+    let Ok(buffer) = buffer.lock() else {
+        return Vec::new();
+    };
+    let skip = buffer.len().saturating_sub(limit);
+    buffer.iter().skip(skip).cloned().collect()
+}
+
+/// Remove all records from the in-app buffer
+pub fn clear_logs() {
+    if let Some(Ok(mut buffer)) = LOG_BUFFER.get().map(|b| b.lock()) {
+        buffer.clear();
+    }
+}
+
+fn push_record(record: LogRecord) {
+    let buffer = LOG_BUFFER.get_or_init(|| Mutex::new(VecDeque::with_capacity(LOG_BUFFER_CAPACITY)));
+    if let Ok(mut buffer) = buffer.lock() {
+        if buffer.len() == LOG_BUFFER_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(record);
+    }
+}
+
+/// Writer that redacts secrets from each formatted log line before it hits disk
+struct RedactingWriter<W>(W);
+
+impl<W: std::io::Write> std::io::Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.0.write_all(redact::redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Layer feeding the in-app ring buffer
+struct BufferLayer;
+
+impl<S> Layer<S> for BufferLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        // Span fields are formatted by the fmt layer and stored in the span's extensions
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| {
+                        let extensions = span.extensions();
+                        match extensions.get::<FormattedFields<DefaultFields>>() {
+                            Some(fields) if !fields.is_empty() => {
+                                format!("{}{{{}}}", span.name(), fields)
+                            }
+                            _ => span.name().to_string(),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(":")
+            })
+            .unwrap_or_default();
+
+        let metadata = event.metadata();
+        push_record(LogRecord {
+            timestamp: Utc::now(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            spans,
+            message: redact::redact(&visitor.finish()).into_owned(),
+        });
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        if self.fields.is_empty() {
+            self.message
+        } else {
+            format!("{}{}", self.message, self.fields)
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_level_from_args() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            log_level_from_args(args(&["arula", "--log-level", "debug"])),
+            Some("debug".to_string())
+        );
+        assert_eq!(
+            log_level_from_args(args(&["arula", "--log-level=warn"])),
+            Some("warn".to_string())
+        );
+        assert_eq!(log_level_from_args(args(&["arula"])), None);
+    }
+}
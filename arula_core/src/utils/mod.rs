@@ -2,22 +2,69 @@
 //!
 //! Contains shared utilities, configuration management, data structures, and helper functions.
 
+pub mod budget;
 pub mod changelog;
 pub mod chat;
 pub mod colors;
 pub mod config;
+pub mod context_budget;
 pub mod conversation;
+pub mod crash_report;
 pub mod debug;
+pub mod drafts;
 pub mod error;
 pub mod error_utils;
 pub mod git_state;
+pub mod http_capture;
+pub mod images;
+pub mod inspector;
+pub mod knowledge;
 pub mod logger;
+pub mod logging;
+pub mod personas;
+pub mod project_config;
 pub mod project_context;
+pub mod prompt_history;
+pub mod redact;
+pub mod response_cache;
+pub mod snapshots;
+pub mod spellcheck;
+pub mod starters;
+pub mod symbol_index;
+pub mod telemetry;
 pub mod time;
 pub mod tool_call;
+pub mod tool_output;
+pub mod transcript;
+pub mod updates;
+pub mod usage_store;
+pub mod webhooks;
+pub mod workspace;
 
 // Available exports via submodules:
+// budget::{BudgetTracker, BudgetExceeded, BudgetKind}
+// context_budget::{ContextBudget, ContextSource, ContextPriority, ContextReport, estimate_tokens}
+// crash_report::{install, unseen, issue_url, crash_dir, CrashReport}
 // debug::{is_debug_enabled, debug_print, DebugTimer}
 // error::{ArulaError, ArulaResult, ApiError, ToolError, ResultExt, OptionExt}
 // error_utils::{ErrorContext, api_error, stream_error, network_error}
-// project_context::{detect_project, generate_auto_manifest, is_ai_enhanced, manifest_exists, DetectedProject, ProjectType}
+// http_capture::{enable, begin_turn, load, replay, CaptureRecord, Exchange}
+// images::{data_url, encode, split_data_url, mime_type, save_pasted, MAX_IMAGE_BYTES}
+// inspector::{begin_turn, record, snapshot, InspectorEntry, InspectorSnapshot, InspectorSource}
+// knowledge::{KnowledgeBase, Document, DocumentInfo, Excerpt, prompt_with_excerpts}
+// logging::{init_logging, recent_logs, LogRecord, LoggingGuard}
+// personas::{all, builtin, find, Persona}
+// project_config::{ProjectConfig, PROJECT_CONFIG_FILE}
+// project_context::{detect_project, generate_auto_manifest, refresh_manifest, is_ai_enhanced, manifest_exists, DetectedProject, ManifestWatcher, ProjectType}
+// redact::{configure, redact, redact_json, Redactor, REDACTED}
+// response_cache::{configure, cache_key, CachedResponse, ResponseCache}
+// snapshots::{begin_turn, snapshot_before_write, undo, Snapshot, SnapshotStore}
+// starters::{offline, to_generate, with_pinned, for_project, MAX_STARTERS}
+// symbol_index::{SymbolIndex, Symbol, SymbolKind, index_in_background, current_index}
+// telemetry::{record_turn, record_tokens, record_tool_call, record_provider_error}
+// tool_output::{shrink_for_history, DEFAULT_TOOL_RESULT_MAX_TOKENS}
+// transcript::{to_markdown, to_html}
+// updates::{check, latest_release, self_update, is_newer, Release, CURRENT_VERSION}
+// usage_store::{record, summarize, UsageKind, UsageSummary, estimate_cost}
+// webhooks::{configure, payload}
+// workspace::Workspace
@@ -0,0 +1,143 @@
+//! Personas: named presets of system prompt, temperature and model
+//!
+//! A few personas ship built in; `personas` in the config adds more or
+//! replaces a built-in one of the same name. The persona named by
+//! `active_persona` adds its instructions to the system prompt, and its model
+//! and temperature, when set, take precedence over the global ones.
+
+use crate::utils::config::Config;
+use serde::{Deserialize, Serialize};
+
+/// A system prompt preset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Persona {
+    pub name: String,
+    /// Instructions appended to the base system prompt
+    pub system_prompt: String,
+    /// Sampling temperature (frontend default when absent)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Model of the active provider to use instead of the configured one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl Persona {
+    fn new(name: &str, system_prompt: &str, temperature: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            system_prompt: system_prompt.to_string(),
+            temperature: Some(temperature),
+            model: None,
+        }
+    }
+
+    /// Persona instructions formatted as a system prompt section
+    pub fn system_prompt_section(&self) -> Option<String> {
+        let prompt = self.system_prompt.trim();
+        if prompt.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "\n====\n\n## PERSONA: {}\n\nFollow these instructions for the rest of the conversation:\n\n{}",
+            self.name, prompt
+        ))
+    }
+
+    /// Returns a copy of `config` using the persona's model.
+    ///
+    /// Like `ProjectConfig::overlay`, the result is only for building the
+    /// backend and must not be saved.
+    pub fn overlay(&self, config: &Config) -> Config {
+        let mut effective = config.clone();
+        if let Some(model) = self.model.as_deref().filter(|m| !m.trim().is_empty()) {
+            effective.set_model(model);
+        }
+        effective
+    }
+}
+
+/// Personas available without any configuration
+pub fn builtin() -> Vec<Persona> {
+    vec![
+        Persona::new(
+            "Code reviewer",
+            "Act as a careful senior code reviewer. Read the relevant code before commenting. \
+             Point out bugs, edge cases, security problems and unclear naming, most serious first, \
+             and cite file and line for each. Suggest concrete fixes, but don't edit files unless asked.",
+            0.2,
+        ),
+        Persona::new(
+            "Writing assistant",
+            "Act as a writing assistant for documentation, READMEs, commit messages and prose. \
+             Prefer plain words and short sentences, keep the author's voice, and explain \
+             substantial changes briefly. Don't run tools unless the text lives in a file you need to read.",
+            0.8,
+        ),
+        Persona::new(
+            "Teacher",
+            "Explain as a patient teacher. Start from what the user likely knows, introduce one \
+             idea at a time with small examples, and check understanding with a short question \
+             at the end instead of dumping everything at once.",
+            0.5,
+        ),
+    ]
+}
+
+/// Built-in personas followed by the configured ones; a configured persona
+/// replaces the built-in one with the same name
+pub fn all(config: &Config) -> Vec<Persona> {
+    let mut personas = builtin();
+    for persona in &config.personas {
+        if persona.name.trim().is_empty() {
+            continue;
+        }
+        match personas
+            .iter_mut()
+            .find(|p| p.name.eq_ignore_ascii_case(&persona.name))
+        {
+            Some(existing) => *existing = persona.clone(),
+            None => personas.push(persona.clone()),
+        }
+    }
+    personas
+}
+
+/// The persona called `name`, ignoring case
+pub fn find(config: &Config, name: &str) -> Option<Persona> {
+    all(config)
+        .into_iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_personas_override_builtin() {
+        let mut config = Config::default();
+        assert!(config.get_active_persona().is_none());
+
+        config.personas = vec![
+            Persona {
+                name: "code reviewer".to_string(),
+                system_prompt: "Only look for panics.".to_string(),
+                temperature: None,
+                model: Some("review-model".to_string()),
+            },
+            Persona::new("Rubber duck", "Ask questions, never answer.", 1.0),
+        ];
+        let names: Vec<String> = all(&config).into_iter().map(|p| p.name).collect();
+        assert_eq!(names.len(), builtin().len() + 1);
+        assert_eq!(names.last().map(String::as_str), Some("Rubber duck"));
+
+        config.active_persona = Some("Code Reviewer".to_string());
+        let active = config.get_active_persona().unwrap();
+        assert_eq!(active.temperature, None);
+        assert!(active.system_prompt_section().unwrap().contains("Only look for panics."));
+
+        config.active_persona = Some("missing".to_string());
+        assert!(config.get_active_persona().is_none());
+    }
+}
@@ -0,0 +1,136 @@
+//! Per-project configuration overlay
+//!
+//! Loads `.arula/config.toml` from a working directory and overlays it on top of
+//! the global `~/.arula/config.json`. Only a small set of keys can be overridden
+//! per project; everything else (providers, API keys, MCP servers) stays global.
+//!
+//! ```toml
+//! model = "gpt-4o"
+//! system_prompt = "This is a Rust workspace. Prefer cargo commands."
+//! allowed_tools = ["read_file", "list_directory", "search_files"]
+//! pinned_files = ["docs/ARCHITECTURE.md"]
+//! ```
+
+use crate::utils::config::Config;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Location of the project config, relative to the project root
+pub const PROJECT_CONFIG_FILE: &str = ".arula/config.toml";
+
+/// Project-level overrides read from `.arula/config.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    /// Model to use instead of the active provider's model
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Extra instructions appended to the system prompt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+
+    /// Whitelist of tool names; when set, all other tools are unavailable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+
+    /// Files (relative to the project root) always offered as context
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinned_files: Option<Vec<String>>,
+}
+
+impl ProjectConfig {
+    /// Path of the project config file inside `dir`
+    pub fn path_in(dir: &Path) -> PathBuf {
+        dir.join(PROJECT_CONFIG_FILE)
+    }
+
+    /// Load the project config from `dir`.
+    ///
+    /// Returns `None` when the file is missing or cannot be parsed. Parse errors
+    /// are reported in debug mode but never block the session.
+    pub fn load(dir: &Path) -> Option<Self> {
+        let path = Self::path_in(dir);
+        let content = fs::read_to_string(&path).ok()?;
+
+        match toml::from_str::<ProjectConfig>(&content) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
+                    eprintln!("⚠️ Failed to parse {}: {}", path.display(), e);
+                }
+                None
+            }
+        }
+    }
+
+    /// Load the project config from the current working directory
+    pub fn load_from_current_dir() -> Option<Self> {
+        Self::load(&crate::tools::sandbox::working_dir())
+    }
+
+    /// Returns a copy of `config` with the project overrides applied.
+    ///
+    /// The result is meant for building the backend only and should not be
+    /// saved, otherwise the project model would leak into the global config.
+    pub fn overlay(&self, config: &Config) -> Config {
+        let mut effective = config.clone();
+        if let Some(model) = self.model.as_deref().filter(|m| !m.trim().is_empty()) {
+            effective.set_model(model);
+        }
+        effective
+    }
+
+    /// Whether a tool may be offered to the model under this project config
+    pub fn is_tool_allowed(&self, name: &str) -> bool {
+        match &self.allowed_tools {
+            Some(allowed) => allowed.iter().any(|t| t == name),
+            None => true,
+        }
+    }
+
+    /// Project instructions formatted as a system prompt section
+    pub fn system_prompt_section(&self) -> Option<String> {
+        let prompt = self.system_prompt.as_deref()?.trim();
+        if prompt.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "\n====\n\n## PROJECT INSTRUCTIONS\n\nThe following instructions come from {}:\n\n{}",
+            PROJECT_CONFIG_FILE, prompt
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_missing_project_config() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(ProjectConfig::load(temp_dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_load_and_overlay_project_config() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join(".arula")).unwrap();
+        fs::write(
+            ProjectConfig::path_in(temp_dir.path()),
+            "model = \"project-model\"\nallowed_tools = [\"read_file\"]\n",
+        )
+        .unwrap();
+
+        let project = ProjectConfig::load(temp_dir.path()).unwrap();
+        assert!(project.is_tool_allowed("read_file"));
+        assert!(!project.is_tool_allowed("execute_bash"));
+        assert!(project.system_prompt_section().is_none());
+
+        let global = Config::new_for_test("openai", "gpt-4", "https://api.openai.com/v1", "key");
+        let effective = project.overlay(&global);
+        assert_eq!(effective.get_model(), "project-model");
+        assert_eq!(global.get_model(), "gpt-4");
+    }
+}
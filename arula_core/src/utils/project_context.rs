@@ -1,7 +1,13 @@
 //! Project context detection and manifest management
 //!
-//! Provides auto-detection of project type (Rust, Node, Python, Go, etc.)
-//! and automatic generation of PROJECT.manifest files.
+//! Provides auto-detection of project type (Rust, Node, Python, Go, Java, .NET,
+//! C/C++, PHP, Ruby, Elixir), monorepos with several manifests, and automatic
+//! generation of PROJECT.manifest files.
+//!
+//! A manifest has an auto-generated section, terminated by
+//! `MANIFEST_MARKER_AUTO_END`, followed by optional AI-enhanced content. The
+//! auto section is regenerated whenever key project files change (see
+//! `ManifestWatcher`), while everything below the end marker is preserved.
 
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -9,6 +15,27 @@ use std::path::{Path, PathBuf};
 /// Markers to distinguish manifest origin
 pub const MANIFEST_MARKER_AUTO: &str = "# AUTO-GENERATED by ARULA";
 pub const MANIFEST_MARKER_AI: &str = "# AI-ENHANCED by ARULA";
+/// Ends the auto-generated section; content below it is never rewritten
+pub const MANIFEST_MARKER_AUTO_END: &str = "# END AUTO-GENERATED by ARULA";
+
+/// Files whose changes trigger a manifest refresh
+const KEY_PROJECT_FILES: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "settings.gradle",
+    "settings.gradle.kts",
+    "CMakeLists.txt",
+    "composer.json",
+    "Gemfile",
+    "mix.exs",
+];
 
 /// Detected project type
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -17,6 +44,14 @@ pub enum ProjectType {
     Node,
     Python,
     Go,
+    Java,
+    DotNet,
+    Cpp,
+    Php,
+    Ruby,
+    Elixir,
+    /// Several projects in one repository (see `DetectedProject::subprojects`)
+    Monorepo,
     Unknown,
 }
 
@@ -27,6 +62,13 @@ impl ProjectType {
             ProjectType::Node => "Node.js",
             ProjectType::Python => "Python",
             ProjectType::Go => "Go",
+            ProjectType::Java => "Java",
+            ProjectType::DotNet => ".NET",
+            ProjectType::Cpp => "C/C++",
+            ProjectType::Php => "PHP",
+            ProjectType::Ruby => "Ruby",
+            ProjectType::Elixir => "Elixir",
+            ProjectType::Monorepo => "Monorepo",
             ProjectType::Unknown => "Unknown",
         }
     }
@@ -43,6 +85,19 @@ pub struct DetectedProject {
     pub test_command: Option<String>,
     pub run_command: Option<String>,
     pub framework: Option<String>,
+    /// Nested projects found in subdirectories (monorepos only)
+    #[serde(default)]
+    pub subprojects: Vec<Subproject>,
+}
+
+/// A project found in a subdirectory of a monorepo
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Subproject {
+    /// Path relative to the repository root
+    pub path: PathBuf,
+    pub project_type: ProjectType,
+    pub name: String,
+    pub framework: Option<String>,
 }
 
 impl Default for DetectedProject {
@@ -56,14 +111,19 @@ impl Default for DetectedProject {
             test_command: None,
             run_command: None,
             framework: None,
+            subprojects: Vec::new(),
         }
     }
 }
 
 /// Check if a manifest file was AI-enhanced
+///
+/// The AI marker is either the first line (legacy, fully AI-written manifest)
+/// or the first line after the auto-generated section.
 pub fn is_ai_enhanced(manifest_path: &Path) -> bool {
     if let Ok(content) = fs::read_to_string(manifest_path) {
         content.starts_with(MANIFEST_MARKER_AI)
+            || content.contains(&format!("\n{}", MANIFEST_MARKER_AI))
     } else {
         false
     }
@@ -74,23 +134,42 @@ pub fn manifest_exists(path: &Path) -> bool {
     path.join("PROJECT.manifest").exists()
 }
 
+/// Source file extensions used to recognize projects without a known manifest
+const SOURCE_EXTENSIONS: &[&str] = &[
+    ".rs", ".js", ".ts", ".py", ".go", ".java", ".kt", ".cs", ".c", ".cpp", ".h", ".hpp",
+    ".php", ".rb", ".ex", ".exs",
+];
+
+/// Directories that commonly hold the packages of a monorepo
+const MONOREPO_CONTAINERS: &[&str] = &["packages", "apps", "services", "crates", "libs", "modules"];
+
+/// Directories never scanned for subprojects
+const IGNORED_DIRS: &[&str] = &["node_modules", "target", "build", "dist", "vendor", "bin", "obj"];
+
 /// Detect project type and extract information from a directory
 pub fn detect_project(path: &Path) -> Option<DetectedProject> {
-    // Try each project type in order of specificity
-    if let Some(project) = parse_cargo_toml(path) {
-        return Some(project);
-    }
+    let root = detect_single_project(path);
+    let subprojects = detect_subprojects(path);
 
-    if let Some(project) = parse_package_json(path) {
-        return Some(project);
-    }
-
-    if let Some(project) = detect_python_project(path) {
-        return Some(project);
+    // Several manifests below the root make this a monorepo; keep whatever
+    // the root manifest told us (workspace commands, framework) as context
+    if subprojects.len() >= 2 {
+        let root = root.unwrap_or_default();
+        let name = if root.project_type == ProjectType::Unknown || root.name.is_empty() {
+            path.file_name()?.to_string_lossy().to_string()
+        } else {
+            root.name
+        };
+        return Some(DetectedProject {
+            project_type: ProjectType::Monorepo,
+            name,
+            subprojects,
+            ..root
+        });
     }
 
-    if let Some(project) = detect_go_project(path) {
-        return Some(project);
+    if root.is_some() {
+        return root;
     }
 
     // Return Unknown project if we can at least find some source files
@@ -98,8 +177,7 @@ pub fn detect_project(path: &Path) -> Option<DetectedProject> {
         || fs::read_dir(path).ok()?.any(|e| {
             e.ok().map(|e| {
                 let name = e.file_name().to_string_lossy().to_string();
-                name.ends_with(".rs") || name.ends_with(".js")
-                    || name.ends_with(".py") || name.ends_with(".go")
+                SOURCE_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
             }).unwrap_or(false)
         });
 
@@ -114,6 +192,64 @@ pub fn detect_project(path: &Path) -> Option<DetectedProject> {
     }
 }
 
+/// Detect a project from the manifests directly inside `path`
+fn detect_single_project(path: &Path) -> Option<DetectedProject> {
+    // Try each project type in order of specificity
+    parse_cargo_toml(path)
+        .or_else(|| parse_package_json(path))
+        .or_else(|| detect_python_project(path))
+        .or_else(|| detect_go_project(path))
+        .or_else(|| detect_java_project(path))
+        .or_else(|| detect_dotnet_project(path))
+        .or_else(|| detect_cmake_project(path))
+        .or_else(|| detect_php_project(path))
+        .or_else(|| detect_ruby_project(path))
+        .or_else(|| detect_elixir_project(path))
+}
+
+/// Find projects in direct subdirectories and in common monorepo containers
+fn detect_subprojects(path: &Path) -> Vec<Subproject> {
+    let mut candidates = child_dirs(path);
+    for container in MONOREPO_CONTAINERS {
+        let container_path = path.join(container);
+        if container_path.is_dir() {
+            candidates.extend(child_dirs(&container_path));
+        }
+    }
+
+    let mut subprojects: Vec<Subproject> = candidates
+        .into_iter()
+        .filter_map(|dir| {
+            let project = detect_single_project(&dir)?;
+            Some(Subproject {
+                path: dir.strip_prefix(path).unwrap_or(&dir).to_path_buf(),
+                project_type: project.project_type,
+                name: project.name,
+                framework: project.framework,
+            })
+        })
+        .collect();
+    subprojects.sort_by(|a, b| a.path.cmp(&b.path));
+    subprojects.dedup_by(|a, b| a.path == b.path);
+    subprojects
+}
+
+/// Non-hidden, non-build subdirectories of `path`
+fn child_dirs(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            !name.starts_with('.') && !IGNORED_DIRS.contains(&name.as_str())
+        })
+        .map(|e| e.path())
+        .collect()
+}
+
 /// Parse Cargo.toml for Rust projects
 fn parse_cargo_toml(path: &Path) -> Option<DetectedProject> {
     let cargo_path = path.join("Cargo.toml");
@@ -130,6 +266,8 @@ fn parse_cargo_toml(path: &Path) -> Option<DetectedProject> {
 
     let mut in_package = false;
     let mut in_dependencies = false;
+    let mut has_package = false;
+    let mut is_workspace = false;
 
     for line in content.lines() {
         let line = line.trim();
@@ -137,6 +275,11 @@ fn parse_cargo_toml(path: &Path) -> Option<DetectedProject> {
         if line.starts_with("[package]") {
             in_package = true;
             in_dependencies = false;
+            has_package = true;
+        } else if line.starts_with("[workspace") {
+            in_package = false;
+            in_dependencies = false;
+            is_workspace = true;
         } else if line.starts_with("[dependencies]") {
             in_package = false;
             in_dependencies = true;
@@ -176,15 +319,23 @@ fn parse_cargo_toml(path: &Path) -> Option<DetectedProject> {
         entry_points.push(PathBuf::from("src/lib.rs"));
     }
 
+    // A virtual workspace manifest has no package to select with -p
+    let target = if is_workspace && !has_package {
+        "--workspace".to_string()
+    } else {
+        format!("-p {}", name)
+    };
+
     Some(DetectedProject {
         project_type: ProjectType::Rust,
         name: name.clone(),
         dependencies,
         entry_points,
-        build_command: Some(format!("cargo build -p {}", name)),
-        test_command: Some(format!("cargo test -p {}", name)),
-        run_command: Some(format!("cargo run -p {}", name)),
+        build_command: Some(format!("cargo build {}", target)),
+        test_command: Some(format!("cargo test {}", target)),
+        run_command: has_package.then(|| format!("cargo run -p {}", name)),
         framework,
+        subprojects: Vec::new(),
     })
 }
 
@@ -249,6 +400,7 @@ fn parse_package_json(path: &Path) -> Option<DetectedProject> {
         test_command,
         run_command,
         framework,
+        subprojects: Vec::new(),
     })
 }
 
@@ -308,6 +460,7 @@ fn detect_python_project(path: &Path) -> Option<DetectedProject> {
         test_command: Some("pytest".to_string()),
         run_command: Some("python main.py".to_string()),
         framework,
+        subprojects: Vec::new(),
     })
 }
 
@@ -331,14 +484,28 @@ fn detect_go_project(path: &Path) -> Option<DetectedProject> {
                 name = last.to_string();
             }
         } else if line.starts_with("require ") || (!line.starts_with("//") && line.contains(" v")) {
+            let line = line.strip_prefix("require ").unwrap_or(line);
             if let Some(dep) = line.split_whitespace().next() {
-                if !dep.is_empty() && dep != "require" && dep != "(" && dep != ")" {
+                if !dep.is_empty() && dep != "(" && dep != ")" {
                     dependencies.push(dep.to_string());
                 }
             }
         }
     }
 
+    // Detect frameworks from module paths
+    let framework = dependencies.iter().find_map(|dep| {
+        let fw = match dep.as_str() {
+            "github.com/gin-gonic/gin" => "Gin",
+            "github.com/labstack/echo/v4" | "github.com/labstack/echo" => "Echo",
+            "github.com/gofiber/fiber/v2" | "github.com/gofiber/fiber" => "Fiber",
+            "github.com/go-chi/chi/v5" | "github.com/go-chi/chi" => "chi",
+            "github.com/spf13/cobra" => "Cobra",
+            _ => return None,
+        };
+        Some(fw.to_string())
+    });
+
     // Find entry points
     let mut entry_points = Vec::new();
     if path.join("main.go").exists() {
@@ -356,7 +523,411 @@ fn detect_go_project(path: &Path) -> Option<DetectedProject> {
         build_command: Some("go build".to_string()),
         test_command: Some("go test ./...".to_string()),
         run_command: Some("go run .".to_string()),
-        framework: None,
+        framework,
+        subprojects: Vec::new(),
+    })
+}
+
+/// Detect Java/Kotlin project (Maven or Gradle)
+fn detect_java_project(path: &Path) -> Option<DetectedProject> {
+    let pom = path.join("pom.xml");
+    let gradle = ["build.gradle.kts", "build.gradle"]
+        .iter()
+        .map(|f| path.join(f))
+        .find(|p| p.exists());
+
+    let (content, is_maven) = if pom.exists() {
+        (fs::read_to_string(&pom).ok()?, true)
+    } else {
+        (fs::read_to_string(gradle?).ok()?, false)
+    };
+
+    let mut name = path.file_name()?.to_string_lossy().to_string();
+    let mut dependencies = Vec::new();
+
+    if is_maven {
+        // First artifactId outside <parent> is the project itself
+        let without_parent = match (content.find("<parent>"), content.find("</parent>")) {
+            (Some(start), Some(end)) if end > start => {
+                format!("{}{}", &content[..start], &content[end..])
+            }
+            _ => content.clone(),
+        };
+        if let Some(artifact) = extract_xml_tag(&without_parent, "artifactId") {
+            name = artifact;
+        }
+        let mut rest = without_parent.as_str();
+        while let Some(idx) = rest.find("<dependency>") {
+            rest = &rest[idx + "<dependency>".len()..];
+            if let Some(artifact) = extract_xml_tag(rest, "artifactId") {
+                dependencies.push(artifact);
+            }
+        }
+    } else {
+        for line in content.lines() {
+            let line = line.trim();
+            let is_dependency = ["implementation", "api", "compileOnly", "runtimeOnly"]
+                .iter()
+                .any(|kw| line.starts_with(kw));
+            if is_dependency {
+                // implementation("group:artifact:version") or implementation 'group:artifact:version'
+                if let Some(coords) = line.split(['"', '\'']).nth(1) {
+                    if let Some(artifact) = coords.split(':').nth(1) {
+                        dependencies.push(artifact.to_string());
+                    }
+                }
+            }
+        }
+        let settings = ["settings.gradle.kts", "settings.gradle"]
+            .iter()
+            .find_map(|f| fs::read_to_string(path.join(f)).ok());
+        if let Some(root_name) = settings.as_deref().and_then(|s| {
+            s.lines()
+                .find(|l| l.trim().starts_with("rootProject.name"))
+                .and_then(extract_toml_string)
+        }) {
+            name = root_name;
+        }
+    }
+
+    let framework = if content.contains("spring-boot") || content.contains("org.springframework.boot") {
+        Some("Spring Boot".to_string())
+    } else if content.contains("com.android.application") || content.contains("com.android.library") {
+        Some("Android".to_string())
+    } else if content.contains("io.ktor") {
+        Some("Ktor".to_string())
+    } else if content.contains("io.quarkus") {
+        Some("Quarkus".to_string())
+    } else {
+        None
+    };
+
+    let mut entry_points = Vec::new();
+    for dir in ["src/main/java", "src/main/kotlin", "app/src/main"] {
+        if path.join(dir).exists() {
+            entry_points.push(PathBuf::from(dir));
+        }
+    }
+
+    let (build_command, test_command, run_command) = if is_maven {
+        ("mvn package".to_string(), "mvn test".to_string(), None)
+    } else {
+        let gradle = if path.join("gradlew").exists() { "./gradlew" } else { "gradle" };
+        (
+            format!("{} build", gradle),
+            format!("{} test", gradle),
+            Some(format!("{} run", gradle)),
+        )
+    };
+
+    Some(DetectedProject {
+        project_type: ProjectType::Java,
+        name,
+        dependencies,
+        entry_points,
+        build_command: Some(build_command),
+        test_command: Some(test_command),
+        run_command,
+        framework,
+        subprojects: Vec::new(),
+    })
+}
+
+/// Detect .NET project (*.sln / *.csproj / *.fsproj)
+fn detect_dotnet_project(path: &Path) -> Option<DetectedProject> {
+    let files: Vec<PathBuf> = fs::read_dir(path)
+        .ok()?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .collect();
+    let has_ext = |ext: &str| {
+        files
+            .iter()
+            .find(|p| p.extension().map(|e| e == ext).unwrap_or(false))
+            .cloned()
+    };
+
+    let project_file = has_ext("csproj").or_else(|| has_ext("fsproj"));
+    let solution = has_ext("sln");
+    let manifest = project_file.clone().or_else(|| solution.clone())?;
+
+    let name = manifest.file_stem()?.to_string_lossy().to_string();
+    let mut dependencies = Vec::new();
+    let mut framework = None;
+
+    if let Some(ref proj) = project_file {
+        let content = fs::read_to_string(proj).unwrap_or_default();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.starts_with("<PackageReference") {
+                if let Some(dep) = extract_xml_attr(line, "Include") {
+                    dependencies.push(dep);
+                }
+            }
+        }
+        framework = if content.contains("Microsoft.NET.Sdk.Web") {
+            Some("ASP.NET Core".to_string())
+        } else if content.contains("Microsoft.NET.Sdk.BlazorWebAssembly") {
+            Some("Blazor".to_string())
+        } else if content.contains("<UseMaui>true</UseMaui>") {
+            Some(".NET MAUI".to_string())
+        } else if content.contains("<UseWPF>true</UseWPF>") {
+            Some("WPF".to_string())
+        } else {
+            None
+        };
+    }
+
+    let entry_points = ["Program.cs", "Program.fs"]
+        .iter()
+        .filter(|f| path.join(f).exists())
+        .map(PathBuf::from)
+        .collect();
+
+    Some(DetectedProject {
+        project_type: ProjectType::DotNet,
+        name,
+        dependencies,
+        entry_points,
+        build_command: Some("dotnet build".to_string()),
+        test_command: Some("dotnet test".to_string()),
+        run_command: project_file.map(|_| "dotnet run".to_string()),
+        framework,
+        subprojects: Vec::new(),
+    })
+}
+
+/// Detect C/C++ project built with CMake
+fn detect_cmake_project(path: &Path) -> Option<DetectedProject> {
+    let cmake = path.join("CMakeLists.txt");
+    if !cmake.exists() {
+        return None;
+    }
+
+    let content = fs::read_to_string(&cmake).ok()?;
+    let mut name = path.file_name()?.to_string_lossy().to_string();
+    let mut dependencies = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        let lower = line.to_lowercase();
+        if lower.starts_with("project(") {
+            if let Some(project_name) = line[8..]
+                .split(|c: char| c.is_whitespace() || c == ')')
+                .next()
+                .filter(|n| !n.is_empty())
+            {
+                name = project_name.to_string();
+            }
+        } else if lower.starts_with("find_package(") {
+            if let Some(dep) = line[13..]
+                .split(|c: char| c.is_whitespace() || c == ')')
+                .next()
+                .filter(|n| !n.is_empty())
+            {
+                dependencies.push(dep.to_string());
+            }
+        }
+    }
+
+    let framework = dependencies.iter().find_map(|dep| {
+        let fw = match dep.as_str() {
+            "Qt5" | "Qt6" => "Qt",
+            "Boost" => "Boost",
+            "SDL2" => "SDL2",
+            "GTest" => "GoogleTest",
+            _ => return None,
+        };
+        Some(fw.to_string())
+    });
+
+    let entry_points = ["main.cpp", "src/main.cpp", "main.c", "src/main.c"]
+        .iter()
+        .filter(|f| path.join(f).exists())
+        .map(PathBuf::from)
+        .collect();
+
+    Some(DetectedProject {
+        project_type: ProjectType::Cpp,
+        name,
+        dependencies,
+        entry_points,
+        build_command: Some("cmake -B build && cmake --build build".to_string()),
+        test_command: Some("ctest --test-dir build".to_string()),
+        run_command: None,
+        framework,
+        subprojects: Vec::new(),
+    })
+}
+
+/// Detect PHP project (composer.json)
+fn detect_php_project(path: &Path) -> Option<DetectedProject> {
+    let composer = path.join("composer.json");
+    if !composer.exists() {
+        return None;
+    }
+
+    let content = fs::read_to_string(&composer).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+
+    let name = json
+        .get("name")
+        .and_then(|v| v.as_str())
+        .map(|n| n.rsplit('/').next().unwrap_or(n).to_string())
+        .unwrap_or_else(|| path.file_name().unwrap_or_default().to_string_lossy().to_string());
+
+    let mut dependencies = Vec::new();
+    let mut framework = None;
+    if let Some(deps) = json.get("require").and_then(|d| d.as_object()) {
+        for (dep, _) in deps {
+            match dep.as_str() {
+                "laravel/framework" => framework = Some("Laravel".to_string()),
+                "symfony/framework-bundle" => framework = Some("Symfony".to_string()),
+                "slim/slim" => framework = Some("Slim".to_string()),
+                _ => {}
+            }
+            if dep != "php" && !dep.starts_with("ext-") {
+                dependencies.push(dep.clone());
+            }
+        }
+    }
+
+    let mut entry_points = Vec::new();
+    for file in ["artisan", "public/index.php", "index.php"] {
+        if path.join(file).exists() {
+            entry_points.push(PathBuf::from(file));
+        }
+    }
+
+    let run_command = if framework.as_deref() == Some("Laravel") {
+        Some("php artisan serve".to_string())
+    } else {
+        None
+    };
+
+    Some(DetectedProject {
+        project_type: ProjectType::Php,
+        name,
+        dependencies,
+        entry_points,
+        build_command: Some("composer install".to_string()),
+        test_command: Some("vendor/bin/phpunit".to_string()),
+        run_command,
+        framework,
+        subprojects: Vec::new(),
+    })
+}
+
+/// Detect Ruby project (Gemfile)
+fn detect_ruby_project(path: &Path) -> Option<DetectedProject> {
+    let gemfile = path.join("Gemfile");
+    if !gemfile.exists() {
+        return None;
+    }
+
+    let content = fs::read_to_string(&gemfile).ok()?;
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let mut dependencies = Vec::new();
+    let mut framework = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("gem ") {
+            if let Some(dep) = rest.split(['"', '\'']).nth(1) {
+                match dep {
+                    "rails" => framework = Some("Rails".to_string()),
+                    "sinatra" if framework.is_none() => framework = Some("Sinatra".to_string()),
+                    "hanami" => framework = Some("Hanami".to_string()),
+                    _ => {}
+                }
+                dependencies.push(dep.to_string());
+            }
+        }
+    }
+
+    let is_rails = framework.as_deref() == Some("Rails");
+    let entry_points = if is_rails {
+        vec![PathBuf::from("config/routes.rb"), PathBuf::from("app/")]
+    } else {
+        ["main.rb", "app.rb", "lib/"]
+            .iter()
+            .filter(|f| path.join(f).exists())
+            .map(PathBuf::from)
+            .collect()
+    };
+
+    let test_command = if path.join("spec").exists() {
+        "bundle exec rspec"
+    } else if is_rails {
+        "bin/rails test"
+    } else {
+        "bundle exec rake test"
+    };
+
+    Some(DetectedProject {
+        project_type: ProjectType::Ruby,
+        name,
+        dependencies,
+        entry_points,
+        build_command: Some("bundle install".to_string()),
+        test_command: Some(test_command.to_string()),
+        run_command: is_rails.then(|| "bin/rails server".to_string()),
+        framework,
+        subprojects: Vec::new(),
+    })
+}
+
+/// Detect Elixir project (mix.exs)
+fn detect_elixir_project(path: &Path) -> Option<DetectedProject> {
+    let mix = path.join("mix.exs");
+    if !mix.exists() {
+        return None;
+    }
+
+    let content = fs::read_to_string(&mix).ok()?;
+    let mut name = path.file_name()?.to_string_lossy().to_string();
+    let mut dependencies = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("app: :") {
+            name = rest.trim_end_matches(',').trim().to_string();
+        } else if let Some(rest) = line.strip_prefix("{:") {
+            if let Some(dep) = rest.split(',').next() {
+                dependencies.push(dep.trim().to_string());
+            }
+        }
+    }
+
+    let framework = if dependencies.iter().any(|d| d == "phoenix") {
+        Some("Phoenix".to_string())
+    } else if dependencies.iter().any(|d| d == "nerves") {
+        Some("Nerves".to_string())
+    } else {
+        None
+    };
+
+    let run_command = if framework.as_deref() == Some("Phoenix") {
+        "mix phx.server"
+    } else {
+        "mix run"
+    };
+
+    let entry_points = if path.join("lib").exists() {
+        vec![PathBuf::from("lib/")]
+    } else {
+        Vec::new()
+    };
+
+    Some(DetectedProject {
+        project_type: ProjectType::Elixir,
+        name,
+        dependencies,
+        entry_points,
+        build_command: Some("mix compile".to_string()),
+        test_command: Some("mix test".to_string()),
+        run_command: Some(run_command.to_string()),
+        framework,
+        subprojects: Vec::new(),
     })
 }
 
@@ -374,7 +945,18 @@ pub fn generate_auto_manifest(project: &DetectedProject) -> String {
     output.push_str("# METADATA\n");
     output.push_str(&format!("name: {}\n", project.name));
     output.push_str(&format!("type: {}\n", project.project_type.as_str()));
-    output.push_str(&format!("language: {}\n", project.project_type.as_str()));
+    if project.subprojects.is_empty() {
+        output.push_str(&format!("language: {}\n", project.project_type.as_str()));
+    } else {
+        let mut languages: Vec<&str> = project
+            .subprojects
+            .iter()
+            .map(|sub| sub.project_type.as_str())
+            .collect();
+        languages.sort_unstable();
+        languages.dedup();
+        output.push_str(&format!("language: {}\n", languages.join(", ")));
+    }
     if let Some(ref fw) = project.framework {
         output.push_str(&format!("framework: {}\n", fw));
     }
@@ -401,6 +983,29 @@ pub fn generate_auto_manifest(project: &DetectedProject) -> String {
         output.push('\n');
     }
 
+    // Subprojects (monorepos)
+    if !project.subprojects.is_empty() {
+        output.push_str("# SUBPROJECTS\n");
+        for sub in &project.subprojects {
+            match sub.framework {
+                Some(ref fw) => output.push_str(&format!(
+                    "- {} ({}, {}): {}\n",
+                    sub.path.display(),
+                    sub.project_type.as_str(),
+                    fw,
+                    sub.name
+                )),
+                None => output.push_str(&format!(
+                    "- {} ({}): {}\n",
+                    sub.path.display(),
+                    sub.project_type.as_str(),
+                    sub.name
+                )),
+            }
+        }
+        output.push('\n');
+    }
+
     // Workflow commands
     output.push_str("# WORKFLOW\n");
     if let Some(ref cmd) = project.build_command {
@@ -413,9 +1018,161 @@ pub fn generate_auto_manifest(project: &DetectedProject) -> String {
         output.push_str(&format!("run: {}\n", cmd));
     }
 
+    output.push('\n');
+    output.push_str(MANIFEST_MARKER_AUTO_END);
+    output.push('\n');
+
     output
 }
 
+/// Regenerate the auto section of `dir/PROJECT.manifest`.
+///
+/// Content below `MANIFEST_MARKER_AUTO_END` is kept as-is. Manifests that were
+/// not generated by ARULA (or legacy fully AI-written ones without an auto
+/// section) are left untouched. Returns whether the file was written.
+pub fn refresh_manifest(dir: &Path, project: &DetectedProject) -> std::io::Result<bool> {
+    let manifest_path = dir.join("PROJECT.manifest");
+    let auto_section = generate_auto_manifest(project);
+
+    let updated = match fs::read_to_string(&manifest_path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => auto_section,
+        Err(e) => return Err(e),
+        Ok(existing) => {
+            if !existing.starts_with(MANIFEST_MARKER_AUTO) {
+                return Ok(false);
+            }
+            match existing.find(MANIFEST_MARKER_AUTO_END) {
+                Some(idx) => {
+                    let rest = &existing[idx + MANIFEST_MARKER_AUTO_END.len()..];
+                    let rest = rest.strip_prefix('\n').unwrap_or(rest);
+                    let updated = format!("{}{}", auto_section, rest);
+                    if updated == existing {
+                        return Ok(false);
+                    }
+                    updated
+                }
+                // Older auto manifests have no end marker and nothing to preserve
+                None => auto_section,
+            }
+        }
+    };
+
+    fs::write(&manifest_path, updated)?;
+    Ok(true)
+}
+
+/// Polls key project files and reports when the manifest should be refreshed.
+///
+/// Polling keeps this dependency-free. `is_due` is cheap enough for the UI
+/// tick loop; `check` stats every key file of the project and its
+/// subprojects, so UIs run it off their thread (the watcher is `Clone`, so a
+/// task can take it and hand it back).
+#[derive(Debug, Clone)]
+pub struct ManifestWatcher {
+    root: PathBuf,
+    interval: std::time::Duration,
+    last_check: std::time::Instant,
+    snapshot: Vec<(PathBuf, Option<std::time::SystemTime>)>,
+}
+
+impl ManifestWatcher {
+    /// Start watching the key files of `root` (and of detected subprojects)
+    pub fn new(root: &Path, interval: std::time::Duration) -> Self {
+        let mut watcher = Self {
+            root: root.to_path_buf(),
+            interval,
+            last_check: std::time::Instant::now(),
+            snapshot: Vec::new(),
+        };
+        watcher.snapshot = watcher.take_snapshot();
+        watcher
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn watched_files(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![self.root.clone()];
+        dirs.extend(
+            detect_subprojects(&self.root)
+                .into_iter()
+                .map(|sub| self.root.join(sub.path)),
+        );
+
+        let mut files = Vec::new();
+        for dir in dirs {
+            files.extend(KEY_PROJECT_FILES.iter().map(|f| dir.join(f)));
+            // .NET project files have per-project names
+            if let Ok(entries) = fs::read_dir(&dir) {
+                files.extend(entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| {
+                    p.extension()
+                        .map(|ext| ext == "csproj" || ext == "fsproj" || ext == "sln")
+                        .unwrap_or(false)
+                }));
+            }
+        }
+        files
+    }
+
+    fn take_snapshot(&self) -> Vec<(PathBuf, Option<std::time::SystemTime>)> {
+        self.watched_files()
+            .into_iter()
+            .map(|path| {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+                (path, modified)
+            })
+            .filter(|(_, modified)| modified.is_some())
+            .collect()
+    }
+
+    /// Whether `interval` has passed since the last check, restarting it if so
+    pub fn is_due(&mut self) -> bool {
+        if self.last_check.elapsed() < self.interval {
+            return false;
+        }
+        self.last_check = std::time::Instant::now();
+        true
+    }
+
+    /// Returns true when a key file was added, removed or modified since the last check
+    pub fn check(&mut self) -> bool {
+        let snapshot = self.take_snapshot();
+        let changed = snapshot != self.snapshot;
+        self.snapshot = snapshot;
+        changed
+    }
+
+    /// Returns true when a key file changed and `interval` has passed since the last poll
+    pub fn poll(&mut self) -> bool {
+        self.is_due() && self.check()
+    }
+
+    /// Checks and, on change, re-detects the project and refreshes the manifest.
+    ///
+    /// Returns the newly detected project when something changed.
+    pub fn check_and_refresh(&mut self) -> Option<DetectedProject> {
+        if !self.check() {
+            return None;
+        }
+        let project = detect_project(&self.root)?;
+        if let Err(e) = refresh_manifest(&self.root, &project) {
+            if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
+                eprintln!("⚠️ Failed to refresh PROJECT.manifest: {}", e);
+            }
+        }
+        Some(project)
+    }
+
+    /// `check_and_refresh` once `interval` has passed since the last poll
+    pub fn poll_and_refresh(&mut self) -> Option<DetectedProject> {
+        if !self.is_due() {
+            return None;
+        }
+        self.check_and_refresh()
+    }
+}
+
 /// Extract string value from TOML line like: name = "value"
 fn extract_toml_string(line: &str) -> Option<String> {
     let parts: Vec<&str> = line.splitn(2, '=').collect();
@@ -429,6 +1186,23 @@ fn extract_toml_string(line: &str) -> Option<String> {
     }
 }
 
+/// Extract the text of the first `<tag>...</tag>` in an XML document
+fn extract_xml_tag(content: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = content.find(&open)? + open.len();
+    let end = content[start..].find(&close)? + start;
+    Some(content[start..end].trim().to_string())
+}
+
+/// Extract an attribute value like `Include="Package"` from an XML element
+fn extract_xml_attr(line: &str, attr: &str) -> Option<String> {
+    let key = format!("{}=\"", attr);
+    let start = line.find(&key)? + key.len();
+    let end = line[start..].find('"')? + start;
+    Some(line[start..end].to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -443,5 +1217,56 @@ mod tests {
     fn test_project_type_as_str() {
         assert_eq!(ProjectType::Rust.as_str(), "Rust");
         assert_eq!(ProjectType::Node.as_str(), "Node.js");
+        assert_eq!(ProjectType::DotNet.as_str(), ".NET");
+    }
+
+    #[test]
+    fn test_extract_xml_helpers() {
+        let pom = "<project><artifactId>demo</artifactId></project>";
+        assert_eq!(extract_xml_tag(pom, "artifactId"), Some("demo".to_string()));
+        let reference = r#"<PackageReference Include="Serilog" Version="3.1.1" />"#;
+        assert_eq!(extract_xml_attr(reference, "Include"), Some("Serilog".to_string()));
+    }
+
+    #[test]
+    fn test_refresh_manifest_preserves_ai_section() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let mut project = DetectedProject {
+            name: "demo".to_string(),
+            ..Default::default()
+        };
+
+        assert!(refresh_manifest(root, &project).unwrap());
+        let manifest_path = root.join("PROJECT.manifest");
+        let ai_notes = format!("{}\nessence: keeps notes\n", MANIFEST_MARKER_AI);
+        let mut content = fs::read_to_string(&manifest_path).unwrap();
+        content.push_str(&ai_notes);
+        fs::write(&manifest_path, content).unwrap();
+        assert!(is_ai_enhanced(&manifest_path));
+
+        project.dependencies.push("serde".to_string());
+        assert!(refresh_manifest(root, &project).unwrap());
+        let content = fs::read_to_string(&manifest_path).unwrap();
+        assert!(content.contains("- serde"));
+        assert!(content.ends_with(&ai_notes));
+
+        // Unchanged project means no rewrite
+        assert!(!refresh_manifest(root, &project).unwrap());
+    }
+
+    #[test]
+    fn test_detect_monorepo() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("services/api")).unwrap();
+        fs::create_dir_all(root.join("web")).unwrap();
+        fs::write(root.join("services/api/go.mod"), "module example.com/api\n").unwrap();
+        fs::write(root.join("web/package.json"), r#"{"name": "web"}"#).unwrap();
+
+        let project = detect_project(root).unwrap();
+        assert_eq!(project.project_type, ProjectType::Monorepo);
+        assert_eq!(project.subprojects.len(), 2);
+        assert!(generate_auto_manifest(&project).contains("# SUBPROJECTS"));
     }
 }
@@ -0,0 +1,236 @@
+//! Prompt history shared by the frontends
+//!
+//! Every sent prompt is appended to `~/.arula/prompt_history.jsonl` as a JSON
+//! string, so multi-line prompts stay on one line. Up in an empty input walks
+//! back through the current session's prompts and then the global history,
+//! like a shell (`Recall`); Ctrl+R narrows it down with `search`.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Prompts kept; the file is cut back once it holds twice as many
+pub const MAX_ENTRIES: usize = 1000;
+
+/// Serializes appends from concurrent frontends in one process
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Where the global prompt history is kept
+pub fn history_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".arula")
+        .join("prompt_history.jsonl")
+}
+
+/// Global history, oldest first
+pub fn load() -> Vec<String> {
+    load_from(&history_path())
+}
+
+/// Prompts in `path`, oldest first, without repeats of the previous entry
+pub fn load_from(path: &Path) -> Vec<String> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<String> = Vec::new();
+    let mut lines = 0;
+    for line in text.lines() {
+        lines += 1;
+        let Ok(prompt) = serde_json::from_str::<String>(line) else {
+            continue;
+        };
+        if entries.last() != Some(&prompt) {
+            entries.push(prompt);
+        }
+    }
+    if entries.len() > MAX_ENTRIES {
+        entries.drain(..entries.len() - MAX_ENTRIES);
+    }
+    if lines > MAX_ENTRIES * 2 {
+        if let Err(e) = rewrite(path, &entries) {
+            tracing::warn!("Failed to trim prompt history: {}", e);
+        }
+    }
+    entries
+}
+
+fn rewrite(path: &Path, entries: &[String]) -> anyhow::Result<()> {
+    let mut text = String::new();
+    for entry in entries {
+        text.push_str(&serde_json::to_string(entry)?);
+        text.push('\n');
+    }
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+/// Add a sent prompt to the global history
+pub fn record(prompt: &str) {
+    if prompt.trim().is_empty() {
+        return;
+    }
+    if let Err(e) = append_to(&history_path(), prompt) {
+        tracing::warn!("Failed to record prompt history: {}", e);
+    }
+}
+
+fn append_to(path: &Path, prompt: &str) -> anyhow::Result<()> {
+    let line = serde_json::to_string(prompt)?;
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// Entries to recall, oldest first: the global history with the session's
+/// own prompts moved to the end, so Up reaches them first
+pub fn merged(global: &[String], session: &[String]) -> Vec<String> {
+    let mut entries: Vec<String> = Vec::with_capacity(global.len() + session.len());
+    for prompt in global.iter().chain(session) {
+        if prompt.trim().is_empty() {
+            continue;
+        }
+        entries.retain(|entry| entry != prompt);
+        entries.push(prompt.clone());
+    }
+    entries
+}
+
+/// Position while stepping through history entries with Up and Down
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recall {
+    /// How many entries back from the newest; 0 when not recalling
+    back: usize,
+    /// Text put into the input by the last step
+    shown: Option<String>,
+}
+
+impl Recall {
+    /// Step to an older or newer entry and return the text the input should
+    /// show; stepping past the newest entry empties the input again. `None`
+    /// when there is no history.
+    pub fn step(&mut self, entries: &[String], older: bool) -> Option<String> {
+        if entries.is_empty() {
+            return None;
+        }
+        self.back = if older {
+            (self.back + 1).min(entries.len())
+        } else {
+            self.back.saturating_sub(1)
+        };
+        self.shown = (self.back > 0).then(|| entries[entries.len() - self.back].clone());
+        Some(self.shown.clone().unwrap_or_default())
+    }
+
+    /// Whether the input still holds the recalled entry unchanged, so Up and
+    /// Down keep walking the history instead of moving the cursor
+    pub fn is_showing(&self, text: &str) -> bool {
+        self.shown.as_deref() == Some(text)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Entries matching `query` as a fuzzy subsequence, best match first and
+/// newest first among equal matches, without duplicates
+pub fn search<'a>(entries: &'a [String], query: &str) -> Vec<&'a str> {
+    let query = query.to_lowercase();
+    let mut matches: Vec<(usize, &str)> = Vec::new();
+    for entry in entries.iter().rev() {
+        if matches.iter().any(|(_, seen)| *seen == entry.as_str()) {
+            continue;
+        }
+        if let Some(score) = fuzzy_score(&entry.to_lowercase(), &query) {
+            matches.push((score, entry));
+        }
+    }
+    matches.sort_by_key(|(score, _)| *score);
+    matches.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Lower is better: 0 for a plain substring, otherwise one more than the
+/// characters skipped inside the tightest greedy match. `None` when the
+/// query's characters don't all appear in order.
+fn fuzzy_score(candidate: &str, query: &str) -> Option<usize> {
+    if candidate.contains(query) {
+        return Some(0);
+    }
+    let candidate: Vec<char> = candidate.chars().collect();
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let Some(&first) = query.first() else {
+        return Some(0);
+    };
+    let mut best: Option<usize> = None;
+    for (start, &c) in candidate.iter().enumerate() {
+        if c != first {
+            continue;
+        }
+        let mut at = start;
+        let mut matched = 0;
+        while at < candidate.len() && matched < query.len() {
+            if candidate[at] == query[matched] {
+                matched += 1;
+            }
+            at += 1;
+        }
+        if matched < query.len() {
+            break;
+        }
+        let gaps = at - start - query.len() + 1;
+        best = Some(best.map_or(gaps, |best| best.min(gaps)));
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_history_recall_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prompt_history.jsonl");
+        append_to(&path, "fix the build").unwrap();
+        append_to(&path, "explain\nthis diff").unwrap();
+        append_to(&path, "explain\nthis diff").unwrap();
+        let global = load_from(&path);
+        assert_eq!(global, strings(&["fix the build", "explain\nthis diff"]));
+
+        // The session's prompts come up first
+        let entries = merged(&global, &strings(&["run the tests", "fix the build"]));
+        assert_eq!(
+            entries,
+            strings(&["explain\nthis diff", "run the tests", "fix the build"])
+        );
+
+        let mut recall = Recall::default();
+        assert_eq!(recall.step(&entries, true).as_deref(), Some("fix the build"));
+        assert_eq!(recall.step(&entries, true).as_deref(), Some("run the tests"));
+        assert!(recall.is_showing("run the tests"));
+        assert!(!recall.is_showing("run the tests!"));
+        recall.step(&entries, true);
+        assert_eq!(recall.step(&entries, true).as_deref(), Some("explain\nthis diff"));
+        recall.step(&entries, false);
+        recall.step(&entries, false);
+        assert_eq!(recall.step(&entries, false).as_deref(), Some(""));
+        assert!(!recall.is_showing("fix the build"));
+        assert_eq!(Recall::default().step(&[], true), None);
+
+        assert_eq!(search(&entries, "tests"), vec!["run the tests"]);
+        assert_eq!(search(&entries, "ftb"), vec!["fix the build"]);
+        assert_eq!(search(&entries, "th"), vec!["fix the build", "run the tests", "explain\nthis diff"]);
+        assert!(search(&entries, "zzz").is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! Opt-in local cache of finished answers
+//!
+//! With `response_cache_enabled` set in `~/.arula/config.json`, a run whose
+//! model, system prompt, sampling settings, history and prompt all match an
+//! earlier run is answered from `~/.arula/cache/responses/<sha256>.json`
+//! without calling the provider. Meant for repeated deterministic queries such
+//! as doc generation pipelines; only runs that ended normally without calling
+//! tools are stored, since tool runs have side effects a cached answer would skip.
+//!
+//! The cache is consulted by runs that go through `Backend::stream_session`:
+//! the desktop app, `arula serve` and `arula acp`. The terminal chat and
+//! `arula -p` always call the provider.
+//!
+//! `arula cache list` shows the entries and `arula cache clear` removes them.
+
+use crate::api::api::ChatMessage;
+use crate::utils::config::{Config, SamplingParams};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Characters of the prompt kept for `arula cache list`
+const PROMPT_PREVIEW_CHARS: usize = 80;
+
+/// Enable or disable the cache from the config
+pub fn configure(config: &Config) {
+    ENABLED.store(config.get_response_cache_enabled(), Ordering::Relaxed);
+}
+
+/// Whether runs should consult and fill the cache
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Key for a run: hash of the model, system prompt, temperature, sampling
+/// parameters (seed included), history and prompt
+pub fn cache_key(
+    model: &str,
+    system_prompt: &str,
+    temperature: f32,
+    sampling: &SamplingParams,
+    history: Option<&[ChatMessage]>,
+    prompt: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    for part in [model, system_prompt] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(temperature.to_le_bytes());
+    hasher.update(serde_json::to_vec(sampling).unwrap_or_default());
+    hasher.update([0]);
+    for message in history.unwrap_or_default() {
+        hasher.update(serde_json::to_vec(message).unwrap_or_default());
+        hasher.update([0]);
+    }
+    hasher.update(prompt.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// One stored answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub key: String,
+    pub model: String,
+    /// Start of the prompt, for listing
+    pub prompt_preview: String,
+    pub created: DateTime<Utc>,
+    pub text: String,
+}
+
+impl CachedResponse {
+    pub fn new(key: String, model: &str, prompt: &str, text: String) -> Self {
+        Self {
+            key,
+            model: model.to_string(),
+            prompt_preview: prompt.chars().take(PROMPT_PREVIEW_CHARS).collect(),
+            created: Utc::now(),
+            text,
+        }
+    }
+}
+
+/// Directory of cached answers, one JSON file per key
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    root: PathBuf,
+}
+
+impl ResponseCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The cache in `~/.arula/cache/responses`
+    pub fn default_cache() -> Self {
+        Self::new(
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".arula")
+                .join("cache")
+                .join("responses"),
+        )
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.root.join(format!("{}.json", key))
+    }
+
+    /// The stored answer for `key`, if any
+    pub fn get(&self, key: &str) -> Option<CachedResponse> {
+        let json = fs::read_to_string(self.entry_path(key)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Store an answer, replacing any previous one for the same key
+    pub fn put(&self, entry: &CachedResponse) -> Result<()> {
+        fs::create_dir_all(&self.root)?;
+        fs::write(self.entry_path(&entry.key), serde_json::to_vec_pretty(entry)?)
+            .with_context(|| format!("Failed to write cache entry {}", entry.key))
+    }
+
+    /// All stored answers, newest first
+    pub fn entries(&self) -> Vec<CachedResponse> {
+        let Ok(dir) = fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<CachedResponse> = dir
+            .filter_map(|entry| fs::read_to_string(entry.ok()?.path()).ok())
+            .filter_map(|json| serde_json::from_str(&json).ok())
+            .collect();
+        entries.sort_by(|a, b| b.created.cmp(&a.created));
+        entries
+    }
+
+    /// Remove every stored answer; returns how many were removed
+    pub fn clear(&self) -> Result<usize> {
+        let Ok(dir) = fs::read_dir(&self.root) else {
+            return Ok(0);
+        };
+        let mut removed = 0;
+        for entry in dir.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::new(dir.path());
+
+        let sampling = SamplingParams::default();
+        let key = cache_key("gpt-4o", "You are Arula.", 0.0, &sampling, None, "Document lib.rs");
+        assert_ne!(key, cache_key("gpt-4o-mini", "You are Arula.", 0.0, &sampling, None, "Document lib.rs"));
+        assert_ne!(key, cache_key("gpt-4o", "You are Arula.", 0.7, &sampling, None, "Document lib.rs"));
+        let seeded = SamplingParams { seed: Some(7), ..Default::default() };
+        assert_ne!(key, cache_key("gpt-4o", "You are Arula.", 0.0, &seeded, None, "Document lib.rs"));
+        assert!(cache.get(&key).is_none());
+
+        let entry = CachedResponse::new(key.clone(), "gpt-4o", "Document lib.rs", "Docs".into());
+        cache.put(&entry).unwrap();
+        assert_eq!(cache.get(&key), Some(entry));
+        assert_eq!(cache.entries().len(), 1);
+
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(cache.get(&key).is_none());
+    }
+}
@@ -0,0 +1,232 @@
+//! Snapshots of files changed by the AI, for undo and `arula rollback`
+//!
+//! Before `write_file` or `edit_file` touches a file during a turn, its current
+//! content is copied into a content-addressed store under
+//! `~/.arula/snapshots/objects/<sha256>` (identical contents are stored once)
+//! and a line is appended to `~/.arula/snapshots/journal.jsonl` recording the
+//! turn, tool and path. Files that did not exist yet are journaled without a
+//! hash, so restoring them deletes the file again.
+//!
+//! Snapshots are only taken inside a turn started with `begin_turn`; calling
+//! the tools directly (tests, scripts) leaves the store alone.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+static CURRENT_TURN: Mutex<Option<String>> = Mutex::new(None);
+
+/// Serializes journal appends from concurrent tool calls
+static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+/// State of one file before a tool changed it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: String,
+    pub turn_id: String,
+    pub timestamp: DateTime<Utc>,
+    /// Tool that was about to change the file
+    pub tool: String,
+    /// Absolute path of the file
+    pub path: PathBuf,
+    /// Content hash, or `None` if the file did not exist
+    pub hash: Option<String>,
+}
+
+/// Content-addressed snapshot store with its journal
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    root: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The store in `~/.arula/snapshots`
+    pub fn default_store() -> Self {
+        Self::new(
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".arula")
+                .join("snapshots"),
+        )
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.root.join("journal.jsonl")
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.root.join("objects").join(hash)
+    }
+
+    /// Record the current state of `path` as part of `turn_id`
+    pub fn take(&self, turn_id: &str, tool: &str, path: &Path) -> Result<Snapshot> {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let hash = match fs::read(&path) {
+            Ok(bytes) => {
+                let hash = format!("{:x}", Sha256::digest(&bytes));
+                let object = self.object_path(&hash);
+                if !object.exists() {
+                    fs::create_dir_all(self.root.join("objects"))?;
+                    fs::write(&object, &bytes)
+                        .with_context(|| format!("Failed to store snapshot of {}", path.display()))?;
+                }
+                Some(hash)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+
+        let snapshot = Snapshot {
+            id: uuid::Uuid::new_v4().to_string(),
+            turn_id: turn_id.to_string(),
+            timestamp: Utc::now(),
+            tool: tool.to_string(),
+            path,
+            hash,
+        };
+
+        let line = serde_json::to_string(&snapshot)?;
+        let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        fs::create_dir_all(&self.root)?;
+        let mut journal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.journal_path())?;
+        writeln!(journal, "{}", line)?;
+        Ok(snapshot)
+    }
+
+    /// All journaled snapshots, oldest first
+    pub fn entries(&self) -> Vec<Snapshot> {
+        let Ok(file) = fs::File::open(self.journal_path()) else {
+            return Vec::new();
+        };
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect()
+    }
+
+    /// Look up a snapshot by id
+    pub fn find(&self, id: &str) -> Option<Snapshot> {
+        self.entries().into_iter().find(|s| s.id == id)
+    }
+
+    /// Id of the most recent turn that changed files
+    pub fn last_turn(&self) -> Option<String> {
+        self.entries().pop().map(|s| s.turn_id)
+    }
+
+    /// Put the file back the way it was when `snapshot` was taken
+    pub fn restore(&self, snapshot: &Snapshot) -> Result<()> {
+        match &snapshot.hash {
+            Some(hash) => {
+                let bytes = fs::read(self.object_path(hash))
+                    .with_context(|| format!("Snapshot {} is missing from the store", hash))?;
+                if let Some(parent) = snapshot.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&snapshot.path, bytes)
+                    .with_context(|| format!("Failed to restore {}", snapshot.path.display()))?;
+            }
+            None => match fs::remove_file(&snapshot.path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to remove {}", snapshot.path.display()));
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Undo every change made during `turn_id`, newest first, so each file ends
+    /// up as it was before the turn. Returns the restored snapshots.
+    pub fn rollback_turn(&self, turn_id: &str) -> Result<Vec<Snapshot>> {
+        let mut restored = Vec::new();
+        for snapshot in self.entries().iter().rev().filter(|s| s.turn_id == turn_id) {
+            self.restore(snapshot)?;
+            restored.push(snapshot.clone());
+        }
+        Ok(restored)
+    }
+}
+
+/// Start a new turn; snapshots taken until the next call belong to it
+pub fn begin_turn() -> String {
+    let turn_id = uuid::Uuid::new_v4().to_string();
+    if let Ok(mut current) = CURRENT_TURN.lock() {
+        *current = Some(turn_id.clone());
+    }
+    turn_id
+}
+
+/// Id of the turn in progress, if any
+pub fn current_turn() -> Option<String> {
+    CURRENT_TURN.lock().ok().and_then(|t| t.clone())
+}
+
+/// Snapshot `path` before `tool` changes it. Returns the snapshot id, or
+/// `None` outside a turn or when the snapshot could not be taken.
+pub fn snapshot_before_write(tool: &str, path: &str) -> Option<String> {
+    let turn_id = current_turn()?;
+    match SnapshotStore::default_store().take(&turn_id, tool, Path::new(path)) {
+        Ok(snapshot) => Some(snapshot.id),
+        Err(e) => {
+            tracing::warn!("Failed to snapshot {} before {}: {:#}", path, tool, e);
+            None
+        }
+    }
+}
+
+/// Restore the file recorded in snapshot `id`
+pub fn undo(id: &str) -> Result<Snapshot> {
+    let store = SnapshotStore::default_store();
+    let snapshot = store
+        .find(id)
+        .with_context(|| format!("Unknown snapshot {}", id))?;
+    store.restore(&snapshot)?;
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rollback_turn_restores_original_state() {
+        let dir = TempDir::new().unwrap();
+        let store = SnapshotStore::new(dir.path().join("store"));
+        let existing = dir.path().join("existing.txt");
+        let created = dir.path().join("created.txt");
+        fs::write(&existing, "original").unwrap();
+
+        store.take("turn-1", "edit_file", &existing).unwrap();
+        fs::write(&existing, "first edit").unwrap();
+        store.take("turn-1", "edit_file", &existing).unwrap();
+        fs::write(&existing, "second edit").unwrap();
+        let snapshot = store.take("turn-1", "write_file", &created).unwrap();
+        assert_eq!(snapshot.hash, None);
+        fs::write(&created, "new").unwrap();
+
+        assert_eq!(store.last_turn().as_deref(), Some("turn-1"));
+        let restored = store.rollback_turn("turn-1").unwrap();
+        assert_eq!(restored.len(), 3);
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "original");
+        assert!(!created.exists());
+    }
+}
@@ -0,0 +1,443 @@
+//! Spellchecking for the prompt input
+//!
+//! Reads Hunspell dictionaries (`<language>.dic` next to its `.aff`) from
+//! `~/.arula/dictionaries` or the system's hunspell and myspell directories.
+//! Of the affix file only what produces word forms is used: prefix and suffix
+//! rules (`PFX`/`SFX`) with their conditions and cross products, and the
+//! `FLAG` type. Compounding, replacement tables and the like are ignored, so
+//! a few valid words may still be flagged.
+//!
+//! Prompts are full of code, so words with digits, underscores, inner
+//! capitals (`camelCase`) or all capitals, and anything between backticks,
+//! are never checked.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Language used when none is configured
+pub const DEFAULT_LANGUAGE: &str = "en_US";
+
+/// Words further than this many edits away aren't suggested
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Where dictionaries are looked for, in order
+pub fn dictionary_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".arula").join("dictionaries"));
+        dirs.push(home.join("Library").join("Spelling"));
+    }
+    dirs.extend(
+        [
+            "/usr/share/hunspell",
+            "/usr/share/myspell",
+            "/usr/share/myspell/dicts",
+            "/usr/local/share/hunspell",
+            "/Library/Spelling",
+        ]
+        .iter()
+        .map(PathBuf::from),
+    );
+    dirs
+}
+
+/// The `.dic` file of `language`, if one is installed
+pub fn find(language: &str) -> Option<PathBuf> {
+    dictionary_dirs()
+        .into_iter()
+        .map(|dir| dir.join(format!("{}.dic", language)))
+        .find(|path| path.is_file())
+}
+
+/// How the affix file writes flags
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlagType {
+    /// One character per flag (the default)
+    Char,
+    /// Two characters per flag (`FLAG long`)
+    Long,
+    /// Comma-separated numbers (`FLAG num`)
+    Num,
+}
+
+impl FlagType {
+    fn parse(self, flags: &str) -> Vec<String> {
+        match self {
+            FlagType::Char => flags.chars().map(String::from).collect(),
+            FlagType::Long => flags
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|pair| pair.iter().collect())
+                .collect(),
+            FlagType::Num => flags.split(',').map(|f| f.trim().to_string()).collect(),
+        }
+    }
+}
+
+/// One character of an affix condition
+#[derive(Debug, Clone)]
+enum CondItem {
+    Any,
+    Char(char),
+    Set(Vec<char>, bool),
+}
+
+impl CondItem {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CondItem::Any => true,
+            CondItem::Char(expected) => *expected == c,
+            CondItem::Set(chars, negated) => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+fn parse_condition(condition: &str) -> Vec<CondItem> {
+    let mut items = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => items.push(CondItem::Any),
+            '[' => {
+                let mut set: Vec<char> = chars.by_ref().take_while(|&c| c != ']').collect();
+                let negated = set.first() == Some(&'^');
+                if negated {
+                    set.remove(0);
+                }
+                items.push(CondItem::Set(set, negated));
+            }
+            c => items.push(CondItem::Char(c)),
+        }
+    }
+    items
+}
+
+/// A prefix or suffix rule
+#[derive(Debug, Clone)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Vec<CondItem>,
+    cross: bool,
+}
+
+impl AffixRule {
+    fn apply_suffix(&self, word: &[char]) -> Option<String> {
+        let strip: Vec<char> = self.strip.chars().collect();
+        if !word.ends_with(&strip) || word.len() < self.condition.len() {
+            return None;
+        }
+        let tail = &word[word.len() - self.condition.len()..];
+        if !self.condition.iter().zip(tail).all(|(item, &c)| item.matches(c)) {
+            return None;
+        }
+        let stem: String = word[..word.len() - strip.len()].iter().collect();
+        Some(stem + &self.add)
+    }
+
+    fn apply_prefix(&self, word: &[char]) -> Option<String> {
+        let strip: Vec<char> = self.strip.chars().collect();
+        if !word.starts_with(&strip) || word.len() < self.condition.len() {
+            return None;
+        }
+        if !self.condition.iter().zip(word).all(|(item, &c)| item.matches(c)) {
+            return None;
+        }
+        let rest: String = word[strip.len()..].iter().collect();
+        Some(self.add.clone() + &rest)
+    }
+}
+
+/// Affix rules by flag
+#[derive(Debug, Default)]
+struct Affixes {
+    prefixes: HashMap<String, Vec<AffixRule>>,
+    suffixes: HashMap<String, Vec<AffixRule>>,
+}
+
+fn parse_affixes(aff: &str) -> (Affixes, FlagType) {
+    let mut flag_type = FlagType::Char;
+    let mut affixes = Affixes::default();
+    let mut cross: HashMap<(bool, String), bool> = HashMap::new();
+    for line in aff.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["FLAG", "long", ..] => flag_type = FlagType::Long,
+            ["FLAG", "num", ..] => flag_type = FlagType::Num,
+            // Header: PFX/SFX flag cross_product count
+            [kind @ ("PFX" | "SFX"), flag, product, count]
+                if count.parse::<usize>().is_ok() && matches!(*product, "Y" | "N") =>
+            {
+                cross.insert((*kind == "PFX", flag.to_string()), *product == "Y");
+            }
+            // Rule: PFX/SFX flag strip add condition
+            [kind @ ("PFX" | "SFX"), flag, strip, add, rest @ ..] => {
+                let prefix = *kind == "PFX";
+                let add = add.split('/').next().unwrap_or_default();
+                let rule = AffixRule {
+                    strip: if *strip == "0" { String::new() } else { strip.to_string() },
+                    add: if add == "0" { String::new() } else { add.to_string() },
+                    condition: parse_condition(rest.first().copied().unwrap_or(".")),
+                    cross: cross.get(&(prefix, flag.to_string())).copied().unwrap_or(false),
+                };
+                let rules = if prefix {
+                    &mut affixes.prefixes
+                } else {
+                    &mut affixes.suffixes
+                };
+                rules.entry(flag.to_string()).or_default().push(rule);
+            }
+            _ => {}
+        }
+    }
+    (affixes, flag_type)
+}
+
+/// Every word form a Hunspell dictionary accepts
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+    /// Words by lowercase first letter and length, so suggestions only
+    /// compare against words that could be close
+    by_shape: HashMap<(char, usize), Vec<String>>,
+}
+
+impl Dictionary {
+    /// Expand the stems of a `.dic` file with the rules of its `.aff` file
+    pub fn from_hunspell(dic: &str, aff: &str) -> Self {
+        let (affixes, flag_type) = parse_affixes(aff);
+        let mut words = HashSet::new();
+        // The first line holds the approximate word count
+        for line in dic.lines().skip(1) {
+            let entry = line.split(['\t', ' ']).next().unwrap_or_default();
+            let (stem, flags) = match entry.split_once('/') {
+                Some((stem, flags)) => (stem, flag_type.parse(flags)),
+                None => (entry, Vec::new()),
+            };
+            if stem.is_empty() {
+                continue;
+            }
+            let chars: Vec<char> = stem.chars().collect();
+            let mut suffixed: Vec<String> = Vec::new();
+            for flag in &flags {
+                for rule in affixes.suffixes.get(flag).into_iter().flatten() {
+                    if let Some(form) = rule.apply_suffix(&chars) {
+                        if rule.cross {
+                            suffixed.push(form.clone());
+                        }
+                        words.insert(form);
+                    }
+                }
+            }
+            for flag in &flags {
+                for rule in affixes.prefixes.get(flag).into_iter().flatten() {
+                    if let Some(form) = rule.apply_prefix(&chars) {
+                        words.insert(form);
+                    }
+                    if !rule.cross {
+                        continue;
+                    }
+                    for form in &suffixed {
+                        let form: Vec<char> = form.chars().collect();
+                        if let Some(form) = rule.apply_prefix(&form) {
+                            words.insert(form);
+                        }
+                    }
+                }
+            }
+            words.insert(stem.to_string());
+        }
+        let mut by_shape: HashMap<(char, usize), Vec<String>> = HashMap::new();
+        for word in &words {
+            if let Some(key) = shape(word) {
+                by_shape.entry(key).or_default().push(word.clone());
+            }
+        }
+        Self { words, by_shape }
+    }
+
+    /// Load `<language>.dic` and its `.aff` from the first directory that has them
+    pub fn load(language: &str) -> Option<Self> {
+        let dic_path = find(language)?;
+        let dic = std::fs::read_to_string(&dic_path).ok()?;
+        let aff = std::fs::read_to_string(dic_path.with_extension("aff")).unwrap_or_default();
+        Some(Self::from_hunspell(&dic, &aff))
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Whether `word` is spelled correctly; a capital first letter (start
+    /// of a sentence) and a possessive `'s` are allowed
+    pub fn check(&self, word: &str) -> bool {
+        let word = word
+            .strip_suffix("'s")
+            .or_else(|| word.strip_suffix("’s"))
+            .unwrap_or(word);
+        self.words.contains(word) || self.words.contains(&word.to_lowercase())
+    }
+
+    /// Dictionary words close to `word`, closest first, capitalized like it.
+    /// Only words sharing its first letter are considered
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<String> {
+        let lower = word.to_lowercase();
+        let target: Vec<char> = lower.chars().collect();
+        let Some((first, len)) = shape(&lower) else {
+            return Vec::new();
+        };
+        let lengths = len.saturating_sub(MAX_SUGGESTION_DISTANCE)..=len + MAX_SUGGESTION_DISTANCE;
+        let mut candidates: Vec<(usize, &String)> = lengths
+            .filter_map(|len| self.by_shape.get(&(first, len)))
+            .flatten()
+            .filter_map(|candidate| {
+                let chars: Vec<char> = candidate.to_lowercase().chars().collect();
+                let distance = edit_distance(&target, &chars);
+                (distance <= MAX_SUGGESTION_DISTANCE).then_some((distance, candidate))
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        let mut suggestions: Vec<String> = Vec::new();
+        for (_, candidate) in candidates {
+            let suggestion = if capitalized {
+                let mut chars = candidate.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            } else {
+                candidate.clone()
+            };
+            if !suggestions.contains(&suggestion) {
+                suggestions.push(suggestion);
+            }
+            if suggestions.len() == limit {
+                break;
+            }
+        }
+        suggestions
+    }
+
+    /// Byte ranges of the misspelled words in `text`
+    pub fn misspelled(&self, text: &str) -> Vec<Range<usize>> {
+        words(text)
+            .into_iter()
+            .filter(|range| !self.check(&text[range.clone()]))
+            .collect()
+    }
+}
+
+/// Damerau-Levenshtein distance (adjacent swaps count as one edit)
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// The lowercase first letter and length in chars a word is indexed by
+fn shape(word: &str) -> Option<(char, usize)> {
+    let first = word.chars().next()?.to_lowercase().next()?;
+    Some((first, word.chars().count()))
+}
+
+/// Byte ranges of the words in `text` worth checking: letters with inner
+/// apostrophes, outside backticks, that don't look like code or acronyms
+pub fn words(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut in_code = false;
+    let mut start: Option<usize> = None;
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_' || c == '\'' || c == '’';
+    for (at, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        if is_word_char(c) {
+            start.get_or_insert(at);
+            continue;
+        }
+        if let Some(from) = start.take() {
+            if !in_code {
+                let word = text[from..at].trim_end_matches(['\'', '’']);
+                let trimmed = word.trim_start_matches(['\'', '’']);
+                let from = from + word.len() - trimmed.len();
+                if is_checkable(trimmed) {
+                    words.push(from..from + trimmed.len());
+                }
+            }
+        }
+        if c == '`' {
+            in_code = !in_code;
+        }
+    }
+    words
+}
+
+fn is_checkable(word: &str) -> bool {
+    let mut chars = word.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    word.chars().count() > 1
+        && word.chars().all(|c| c.is_alphabetic() || c == '\'' || c == '’')
+        && first.is_alphabetic()
+        && !chars.any(char::is_uppercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8\n\
+        PFX A Y 1\n\
+        PFX A 0 re .\n\
+        SFX B Y 2\n\
+        SFX B 0 ed [^y]\n\
+        SFX B y ied y\n\
+        SFX S N 1\n\
+        SFX S 0 s .\n";
+
+    const DIC: &str = "4\nwork/AB\ncopy/B\nthe\nfile/S\n";
+
+    #[test]
+    fn test_dictionary_and_words() {
+        let dictionary = Dictionary::from_hunspell(DIC, AFF);
+        for word in ["work", "worked", "rework", "reworked", "copied", "files", "The", "file's"] {
+            assert!(dictionary.check(word), "{} should be accepted", word);
+        }
+        for word in ["copyed", "refile", "wrok"] {
+            assert!(!dictionary.check(word), "{} should be rejected", word);
+        }
+        assert_eq!(dictionary.suggest("wrok", 3)[0], "work");
+        assert_eq!(dictionary.suggest("Teh", 1), vec!["The"]);
+
+        let text = "Pls rework `fooBar` teh parseJSON HTTP file_2 files";
+        let found: Vec<&str> = dictionary
+            .misspelled(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect();
+        assert_eq!(found, vec!["Pls", "teh"]);
+    }
+}
@@ -0,0 +1,127 @@
+//! Conversation starters
+//!
+//! An empty conversation offers a few prompts to start from. The user's
+//! pinned starters always come first; `StarterMode` decides what fills the
+//! remaining slots: suggestions generated by the model (see
+//! `SessionManager::generate_conversation_starters`), fixed ones for the
+//! detected project type, or nothing.
+
+use crate::utils::config::{Config, StarterMode};
+use crate::utils::project_context::{detect_project, DetectedProject, ProjectType};
+use std::path::Path;
+
+/// Starters shown at once (the CLI binds them to Ctrl+1..3)
+pub const MAX_STARTERS: usize = 3;
+
+/// Fixed starters for a project type, or general ones when no project was found
+pub fn for_project(project: Option<&DetectedProject>) -> Vec<String> {
+    let starters: [&str; 3] = match project.map(|p| &p.project_type) {
+        Some(ProjectType::Rust) => [
+            "Review and improve code quality",
+            "Run tests and fix any issues",
+            "Add new feature with proper error handling",
+        ],
+        Some(ProjectType::Node) => [
+            "Review dependencies and update outdated packages",
+            "Add tests for critical functions",
+            "Improve error handling and logging",
+        ],
+        Some(ProjectType::Python) => [
+            "Review code for PEP 8 compliance",
+            "Add type hints to improve code clarity",
+            "Write unit tests for core functionality",
+        ],
+        Some(ProjectType::Go) => [
+            "Review code for idiomatic Go patterns",
+            "Add comprehensive error handling",
+            "Write benchmarks for performance",
+        ],
+        Some(ProjectType::Monorepo) => [
+            "Explain how the subprojects fit together",
+            "Find code duplicated across packages",
+            "Check that shared dependencies are in sync",
+        ],
+        Some(_) => [
+            "Explain the project structure",
+            "Suggest improvements to code organization",
+            "Add documentation for key components",
+        ],
+        None => [
+            "Start a new conversation",
+            "Ask about my capabilities",
+            "Get help with a task",
+        ],
+    };
+    starters.iter().map(|s| s.to_string()).collect()
+}
+
+/// Used when generating starters fails
+pub fn fallback() -> Vec<String> {
+    ["Review recent changes", "Run tests and fix issues", "Add new feature"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// `pinned` followed by `rest`, without repeats, capped at `MAX_STARTERS`
+pub fn with_pinned(pinned: &[String], rest: Vec<String>) -> Vec<String> {
+    let mut starters: Vec<String> = Vec::with_capacity(MAX_STARTERS);
+    for starter in pinned.iter().cloned().chain(rest) {
+        if starters.len() == MAX_STARTERS {
+            break;
+        }
+        if !starters.iter().any(|s| s.eq_ignore_ascii_case(&starter)) {
+            starters.push(starter);
+        }
+    }
+    starters
+}
+
+/// How many starters to ask the model for; 0 when the mode doesn't generate
+/// them or the pinned ones fill every slot
+pub fn to_generate(config: &Config) -> usize {
+    match config.get_starter_mode() {
+        StarterMode::Generated => MAX_STARTERS.saturating_sub(config.get_pinned_starters().len()),
+        StarterMode::Project | StarterMode::Off => 0,
+    }
+}
+
+/// Starters that need no model call: the pinned ones, followed by those for
+/// the project in `cwd` unless starters are off. Frontends without a model
+/// at hand use these in `Generated` mode too.
+pub fn offline(config: &Config, cwd: &Path) -> Vec<String> {
+    let rest = match config.get_starter_mode() {
+        StarterMode::Off => Vec::new(),
+        StarterMode::Generated | StarterMode::Project => for_project(detect_project(cwd).as_ref()),
+    };
+    with_pinned(&config.get_pinned_starters(), rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinned_starters_come_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.pinned_starters = vec!["Summarize open TODOs".to_string(), "  ".to_string()];
+        assert_eq!(to_generate(&config), 2);
+
+        config.starter_mode = Some(StarterMode::Project);
+        assert_eq!(to_generate(&config), 0);
+        let starters = offline(&config, dir.path());
+        assert_eq!(starters.len(), MAX_STARTERS);
+        assert_eq!(starters[0], "Summarize open TODOs");
+        assert_eq!(starters[1], "Start a new conversation");
+
+        config.starter_mode = Some(StarterMode::Off);
+        assert_eq!(offline(&config, dir.path()), vec!["Summarize open TODOs"]);
+
+        let pinned = vec!["Run tests and fix issues".to_string()];
+        assert_eq!(
+            with_pinned(&pinned, fallback()),
+            vec!["Run tests and fix issues", "Review recent changes", "Add new feature"]
+        );
+    }
+}
@@ -0,0 +1,573 @@
+//! Tree-sitter symbol index for project context
+//!
+//! Walks the current project (respecting .gitignore), parses supported source
+//! files with tree-sitter and records top-level symbols: functions, types,
+//! traits/interfaces, constants and methods. The index is built on a background
+//! thread and shared through a global so both the `code_outline` tool and the
+//! system prompt builder can use it without re-parsing.
+//!
+//! Supported languages: Rust, Python, JavaScript, TypeScript (incl. TSX), Go.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tree_sitter::{Language, Node, Parser};
+
+/// Files larger than this are skipped (generated code, bundles)
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+
+/// Upper bound on files parsed per index to keep indexing cheap on huge repos
+const MAX_FILES: usize = 5000;
+
+/// Maximum size of the outline appended to the system prompt
+const PROMPT_OUTLINE_MAX_CHARS: usize = 4000;
+
+/// Kind of an indexed symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Struct,
+    Class,
+    Enum,
+    Trait,
+    Interface,
+    Type,
+    Const,
+    Module,
+    Macro,
+}
+
+impl SymbolKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Function => "fn",
+            SymbolKind::Method => "method",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Class => "class",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Trait => "trait",
+            SymbolKind::Interface => "interface",
+            SymbolKind::Type => "type",
+            SymbolKind::Const => "const",
+            SymbolKind::Module => "mod",
+            SymbolKind::Macro => "macro",
+        }
+    }
+}
+
+/// A single symbol found in a source file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Symbol {
+    /// Symbol name; methods are qualified with their owner (`Type::method`)
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Path relative to the index root
+    pub file: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+    /// Whether the symbol is public/exported
+    pub exported: bool,
+}
+
+/// Symbols of a project, grouped by file in walk order
+#[derive(Debug, Clone, Default)]
+pub struct SymbolIndex {
+    pub root: PathBuf,
+    pub symbols: Vec<Symbol>,
+    pub files_indexed: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Lang {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl Lang {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Lang::Rust),
+            "py" => Some(Lang::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Lang::JavaScript),
+            "ts" | "mts" | "cts" => Some(Lang::TypeScript),
+            "tsx" => Some(Lang::Tsx),
+            "go" => Some(Lang::Go),
+            _ => None,
+        }
+    }
+
+    fn language(&self) -> Language {
+        match self {
+            Lang::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Lang::Python => tree_sitter_python::LANGUAGE.into(),
+            Lang::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Lang::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Lang::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Lang::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+}
+
+impl SymbolIndex {
+    /// Build an index for `root` synchronously
+    pub fn build(root: &Path) -> Self {
+        let mut index = SymbolIndex {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+        let mut parsers: Vec<(u8, Parser)> = Vec::new();
+
+        let walker = ignore::WalkBuilder::new(root)
+            .hidden(true)
+            .git_ignore(true)
+            .build();
+
+        for entry in walker.filter_map(|e| e.ok()) {
+            if index.files_indexed >= MAX_FILES {
+                break;
+            }
+            let path = entry.path();
+            let Some(lang) = Lang::from_path(path) else {
+                continue;
+            };
+            if entry.metadata().map(|m| m.len() > MAX_FILE_BYTES).unwrap_or(true) {
+                continue;
+            }
+            let Ok(source) = std::fs::read_to_string(path) else {
+                continue;
+            };
+
+            // One parser per language, created lazily
+            let key = lang as u8;
+            let parser = match parsers.iter().position(|(k, _)| *k == key) {
+                Some(pos) => &mut parsers[pos].1,
+                None => {
+                    let mut parser = Parser::new();
+                    if parser.set_language(&lang.language()).is_err() {
+                        continue;
+                    }
+                    parsers.push((key, parser));
+                    &mut parsers.last_mut().unwrap().1
+                }
+            };
+
+            let relative = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+            index
+                .symbols
+                .extend(extract_symbols(parser, lang, &source, &relative));
+            index.files_indexed += 1;
+        }
+
+        index
+    }
+
+    /// Symbols whose file lies under `path` (relative to the root or absolute)
+    pub fn symbols_under<'a>(&'a self, path: &Path) -> impl Iterator<Item = &'a Symbol> + 'a {
+        let relative = path
+            .strip_prefix(&self.root)
+            .unwrap_or(path)
+            .components()
+            .filter(|c| !matches!(c, std::path::Component::CurDir))
+            .collect::<PathBuf>();
+        self.symbols
+            .iter()
+            .filter(move |s| relative.as_os_str().is_empty() || s.file.starts_with(&relative))
+    }
+
+    /// Compact outline of exported symbols, grouped by file, for the system prompt
+    pub fn prompt_outline(&self) -> Option<String> {
+        let mut output = String::new();
+        let mut current_file: Option<&Path> = None;
+        let mut truncated = false;
+
+        for symbol in self.symbols.iter().filter(|s| s.exported) {
+            let mut chunk = String::new();
+            if current_file != Some(symbol.file.as_path()) {
+                chunk.push_str(&format!("{}\n", symbol.file.display()));
+                current_file = Some(symbol.file.as_path());
+            }
+            chunk.push_str(&format!("  {} {}\n", symbol.kind.as_str(), symbol.name));
+
+            if output.len() + chunk.len() > PROMPT_OUTLINE_MAX_CHARS {
+                truncated = true;
+                break;
+            }
+            output.push_str(&chunk);
+        }
+
+        if output.is_empty() {
+            return None;
+        }
+        if truncated {
+            output.push_str("  ... (use the code_outline tool for the full index)\n");
+        }
+        Some(output)
+    }
+}
+
+/// Parse one file and collect its symbols
+fn extract_symbols(parser: &mut Parser, lang: Lang, source: &str, file: &Path) -> Vec<Symbol> {
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+    let mut collector = Collector {
+        source: source.as_bytes(),
+        file,
+        symbols: Vec::new(),
+    };
+    let root = tree.root_node();
+    match lang {
+        Lang::Rust => collector.rust_items(root, None),
+        Lang::Python => collector.python_items(root, None),
+        Lang::JavaScript | Lang::TypeScript | Lang::Tsx => collector.js_items(root, false),
+        Lang::Go => collector.go_items(root),
+    }
+    collector.symbols
+}
+
+struct Collector<'a> {
+    source: &'a [u8],
+    file: &'a Path,
+    symbols: Vec<Symbol>,
+}
+
+impl<'a> Collector<'a> {
+    fn text(&self, node: Node) -> String {
+        node.utf8_text(self.source).unwrap_or_default().to_string()
+    }
+
+    fn field_text(&self, node: Node, field: &str) -> Option<String> {
+        node.child_by_field_name(field).map(|n| self.text(n))
+    }
+
+    fn push(&mut self, name: String, kind: SymbolKind, node: Node, exported: bool) {
+        if name.is_empty() {
+            return;
+        }
+        self.symbols.push(Symbol {
+            name,
+            kind,
+            file: self.file.to_path_buf(),
+            line: node.start_position().row + 1,
+            exported,
+        });
+    }
+
+    fn qualify(owner: Option<&str>, name: String) -> String {
+        match owner {
+            Some(owner) => format!("{}::{}", owner, name),
+            None => name,
+        }
+    }
+
+    fn rust_items(&mut self, node: Node, owner: Option<&str>) {
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            let exported = {
+                let mut c = child.walk();
+                child
+                    .named_children(&mut c)
+                    .any(|n| n.kind() == "visibility_modifier")
+            };
+            let kind = match child.kind() {
+                "function_item" | "function_signature_item" if owner.is_some() => SymbolKind::Method,
+                "function_item" => SymbolKind::Function,
+                "struct_item" | "union_item" => SymbolKind::Struct,
+                "enum_item" => SymbolKind::Enum,
+                "trait_item" => SymbolKind::Trait,
+                "type_item" => SymbolKind::Type,
+                "const_item" | "static_item" => SymbolKind::Const,
+                "macro_definition" => SymbolKind::Macro,
+                "mod_item" => SymbolKind::Module,
+                "impl_item" => {
+                    // Methods are listed under their implementing type
+                    if let (Some(ty), Some(body)) =
+                        (self.field_text(child, "type"), child.child_by_field_name("body"))
+                    {
+                        self.rust_items(body, Some(&ty));
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            let Some(name) = self.field_text(child, "name") else {
+                continue;
+            };
+            self.push(Self::qualify(owner, name.clone()), kind, child, exported);
+
+            if let Some(body) = child.child_by_field_name("body") {
+                match kind {
+                    SymbolKind::Module => self.rust_items(body, None),
+                    SymbolKind::Trait => {
+                        // Trait methods carry no `pub` but share the trait's visibility
+                        let first = self.symbols.len();
+                        self.rust_items(body, Some(&name));
+                        for method in &mut self.symbols[first..] {
+                            method.exported = exported;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn python_items(&mut self, node: Node, owner: Option<&str>) {
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            let definition = if child.kind() == "decorated_definition" {
+                match child.child_by_field_name("definition") {
+                    Some(def) => def,
+                    None => continue,
+                }
+            } else {
+                child
+            };
+            let kind = match definition.kind() {
+                "function_definition" if owner.is_some() => SymbolKind::Method,
+                "function_definition" => SymbolKind::Function,
+                "class_definition" => SymbolKind::Class,
+                _ => continue,
+            };
+            let Some(name) = self.field_text(definition, "name") else {
+                continue;
+            };
+            let exported = !name.starts_with('_') || (name.starts_with("__") && name.ends_with("__"));
+            self.push(Self::qualify(owner, name.clone()), kind, definition, exported);
+
+            if kind == SymbolKind::Class {
+                if let Some(body) = definition.child_by_field_name("body") {
+                    self.python_items(body, Some(&name));
+                }
+            }
+        }
+    }
+
+    fn js_items(&mut self, node: Node, exported: bool) {
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            self.js_item(child, exported);
+        }
+    }
+
+    fn js_item(&mut self, node: Node, exported: bool) {
+        let kind = match node.kind() {
+            "export_statement" => {
+                if let Some(decl) = node.child_by_field_name("declaration") {
+                    self.js_item(decl, true);
+                }
+                return;
+            }
+            "function_declaration" | "generator_function_declaration" => SymbolKind::Function,
+            "class_declaration" | "abstract_class_declaration" => SymbolKind::Class,
+            "interface_declaration" => SymbolKind::Interface,
+            "type_alias_declaration" => SymbolKind::Type,
+            "enum_declaration" => SymbolKind::Enum,
+            "internal_module" | "module" => SymbolKind::Module,
+            "lexical_declaration" | "variable_declaration" => {
+                // const foo = () => {} / const BAR = ...
+                let mut cursor = node.walk();
+                for declarator in node.named_children(&mut cursor) {
+                    if declarator.kind() != "variable_declarator" {
+                        continue;
+                    }
+                    let Some(name) = self.field_text(declarator, "name") else {
+                        continue;
+                    };
+                    let is_function = declarator
+                        .child_by_field_name("value")
+                        .map(|v| matches!(v.kind(), "arrow_function" | "function_expression" | "function"))
+                        .unwrap_or(false);
+                    let kind = if is_function { SymbolKind::Function } else { SymbolKind::Const };
+                    // Only keep plain variables when exported to avoid noise
+                    if is_function || exported {
+                        self.push(name, kind, declarator, exported);
+                    }
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        let Some(name) = self.field_text(node, "name") else {
+            return;
+        };
+        self.push(name.clone(), kind, node, exported);
+
+        if kind == SymbolKind::Class {
+            if let Some(body) = node.child_by_field_name("body") {
+                let mut cursor = body.walk();
+                for member in body.named_children(&mut cursor) {
+                    if member.kind() != "method_definition" {
+                        continue;
+                    }
+                    let Some(method) = self.field_text(member, "name") else {
+                        continue;
+                    };
+                    let is_private = method.starts_with('#') || {
+                        let mut c = member.walk();
+                        member.named_children(&mut c).any(|n| {
+                            n.kind() == "accessibility_modifier" && self.text(n) == "private"
+                        })
+                    };
+                    self.push(format!("{}.{}", name, method), SymbolKind::Method, member, exported && !is_private);
+                }
+            }
+        }
+    }
+
+    fn go_items(&mut self, node: Node) {
+        let is_exported = |name: &str| name.chars().next().map(|c| c.is_uppercase()).unwrap_or(false);
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            match child.kind() {
+                "function_declaration" => {
+                    if let Some(name) = self.field_text(child, "name") {
+                        let exported = is_exported(&name);
+                        self.push(name, SymbolKind::Function, child, exported);
+                    }
+                }
+                "method_declaration" => {
+                    let Some(name) = self.field_text(child, "name") else {
+                        continue;
+                    };
+                    // Receiver like `(s *Server)` -> `Server`
+                    let receiver = child
+                        .child_by_field_name("receiver")
+                        .map(|r| self.text(r))
+                        .and_then(|r| {
+                            r.trim_matches(|c| c == '(' || c == ')')
+                                .split_whitespace()
+                                .last()
+                                .map(|t| t.trim_start_matches('*').to_string())
+                        });
+                    let exported = is_exported(&name);
+                    self.push(Self::qualify(receiver.as_deref(), name), SymbolKind::Method, child, exported);
+                }
+                "type_declaration" => {
+                    let mut c = child.walk();
+                    for spec in child.named_children(&mut c) {
+                        if spec.kind() != "type_spec" && spec.kind() != "type_alias" {
+                            continue;
+                        }
+                        let Some(name) = self.field_text(spec, "name") else {
+                            continue;
+                        };
+                        let kind = match spec.child_by_field_name("type").map(|t| t.kind()) {
+                            Some("struct_type") => SymbolKind::Struct,
+                            Some("interface_type") => SymbolKind::Interface,
+                            _ => SymbolKind::Type,
+                        };
+                        let exported = is_exported(&name);
+                        self.push(name, kind, spec, exported);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// ==================== Background index ====================
+
+struct IndexState {
+    /// Root currently being indexed (or last indexed)
+    root: Option<PathBuf>,
+    index: Option<Arc<SymbolIndex>>,
+}
+
+lazy_static::lazy_static! {
+    static ref INDEX_STATE: Mutex<IndexState> = Mutex::new(IndexState { root: None, index: None });
+}
+
+/// Bumped every time a background index completes
+static INDEX_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Start indexing `root` on a background thread.
+///
+/// Does nothing if `root` is already indexed or being indexed.
+pub fn index_in_background(root: PathBuf) {
+    {
+        let Ok(mut state) = INDEX_STATE.lock() else {
+            return;
+        };
+        if state.root.as_ref() == Some(&root) {
+            return;
+        }
+        state.root = Some(root.clone());
+        state.index = None;
+    }
+
+    std::thread::spawn(move || {
+        let start = std::time::Instant::now();
+        let index = SymbolIndex::build(&root);
+        if crate::utils::debug::is_debug_enabled() {
+            eprintln!(
+                "🔎 Indexed {} symbols in {} files under {} ({:?})",
+                index.symbols.len(),
+                index.files_indexed,
+                root.display(),
+                start.elapsed()
+            );
+        }
+
+        if let Ok(mut state) = INDEX_STATE.lock() {
+            // The directory may have changed while we were indexing
+            if state.root.as_ref() == Some(&root) {
+                state.index = Some(Arc::new(index));
+                INDEX_GENERATION.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    });
+}
+
+/// Index of the current project, if one has finished building
+pub fn current_index() -> Option<Arc<SymbolIndex>> {
+    INDEX_STATE.lock().ok()?.index.clone()
+}
+
+/// Counter that changes whenever a new index becomes available
+pub fn index_generation() -> u64 {
+    INDEX_GENERATION.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_index_rust_and_python() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("lib.rs"),
+            "pub struct Foo;\nimpl Foo {\n    pub fn bar(&self) {}\n}\nfn private() {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("app.py"),
+            "class Service:\n    def run(self):\n        pass\n\ndef _helper():\n    pass\n",
+        )
+        .unwrap();
+
+        let index = SymbolIndex::build(temp_dir.path());
+        let names: Vec<&str> = index.symbols.iter().map(|s| s.name.as_str()).collect();
+        assert!(names.contains(&"Foo"));
+        assert!(names.contains(&"Foo::bar"));
+        assert!(names.contains(&"Service::run"));
+
+        let private = index.symbols.iter().find(|s| s.name == "private").unwrap();
+        assert!(!private.exported);
+
+        let outline = index.prompt_outline().unwrap();
+        assert!(outline.contains("struct Foo"));
+        assert!(!outline.contains("_helper"));
+    }
+}
@@ -0,0 +1,245 @@
+//! OpenTelemetry export for agent runs
+//!
+//! When `telemetry.enabled` is set in `~/.arula/config.json`, `init_logging`
+//! forwards `tracing` spans (sessions, tool calls) over OTLP/HTTP and a meter
+//! provider exports:
+//!
+//! - `arula.turn.duration` (s) - prompt to final token, per provider/model
+//! - `arula.tokens` - prompt/completion tokens reported by the provider
+//! - `arula.tool.duration` (s) - per tool and outcome
+//! - `arula.provider.errors` - failed turns per provider
+//!
+//! ```json
+//! "telemetry": { "enabled": true, "endpoint": "http://localhost:4318" }
+//! ```
+//!
+//! Export needs the `otel` cargo feature (on by default). Without it, or while
+//! telemetry is disabled, the `record_*` functions are no-ops.
+
+use crate::utils::config::TelemetryConfig;
+use std::time::Duration;
+
+/// Collector used when neither the config nor the environment names one
+pub const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4318";
+
+/// Default `service.name` resource attribute
+pub const DEFAULT_SERVICE_NAME: &str = "arula";
+
+/// How often metrics are pushed to the collector
+#[cfg(feature = "otel")]
+const METRIC_EXPORT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Collector base URL: config, then `OTEL_EXPORTER_OTLP_ENDPOINT`, then the default
+pub fn resolve_endpoint(config: &TelemetryConfig) -> String {
+    config
+        .endpoint
+        .clone()
+        .filter(|e| !e.trim().is_empty())
+        .or_else(|| std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok())
+        .unwrap_or_else(|| DEFAULT_OTLP_ENDPOINT.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use super::*;
+    use opentelemetry::metrics::{Counter, Histogram};
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use std::sync::OnceLock;
+    use tracing::Subscriber;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    /// Keeps the exporters running; flushes pending data when dropped
+    pub struct TelemetryGuard {
+        tracer_provider: SdkTracerProvider,
+        meter_provider: SdkMeterProvider,
+    }
+
+    impl TelemetryGuard {
+        /// `tracing` layer exporting spans through this guard's tracer
+        pub fn trace_layer<S>(&self) -> impl Layer<S> + use<S>
+        where
+            S: Subscriber + for<'a> LookupSpan<'a>,
+        {
+            tracing_opentelemetry::layer().with_tracer(self.tracer_provider.tracer("arula"))
+        }
+    }
+
+    impl Drop for TelemetryGuard {
+        fn drop(&mut self) {
+            let _ = self.tracer_provider.shutdown();
+            let _ = self.meter_provider.shutdown();
+        }
+    }
+
+    /// Start the OTLP trace and metric exporters
+    pub fn init(config: &TelemetryConfig) -> anyhow::Result<TelemetryGuard> {
+        let endpoint = resolve_endpoint(config);
+        let resource = Resource::builder()
+            .with_service_name(
+                config
+                    .service_name
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string()),
+            )
+            .build();
+
+        let span_exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", endpoint))
+            .build()?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(span_exporter)
+            .with_resource(resource.clone())
+            .build();
+
+        let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/metrics", endpoint))
+            .build()?;
+        let reader = PeriodicReader::builder(metric_exporter)
+            .with_interval(METRIC_EXPORT_INTERVAL)
+            .build();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(reader)
+            .with_resource(resource)
+            .build();
+        opentelemetry::global::set_meter_provider(meter_provider.clone());
+
+        Ok(TelemetryGuard {
+            tracer_provider,
+            meter_provider,
+        })
+    }
+
+    struct Instruments {
+        turn_duration: Histogram<f64>,
+        tokens: Counter<u64>,
+        tool_duration: Histogram<f64>,
+        provider_errors: Counter<u64>,
+    }
+
+    /// Instruments from the global meter; no-ops until `init` installs a provider
+    fn instruments() -> &'static Instruments {
+        static INSTRUMENTS: OnceLock<Instruments> = OnceLock::new();
+        INSTRUMENTS.get_or_init(|| {
+            let meter = opentelemetry::global::meter("arula");
+            Instruments {
+                turn_duration: meter
+                    .f64_histogram("arula.turn.duration")
+                    .with_unit("s")
+                    .with_description("Time from prompt to final token")
+                    .build(),
+                tokens: meter
+                    .u64_counter("arula.tokens")
+                    .with_description("Tokens reported by the provider")
+                    .build(),
+                tool_duration: meter
+                    .f64_histogram("arula.tool.duration")
+                    .with_unit("s")
+                    .with_description("Tool execution time")
+                    .build(),
+                provider_errors: meter
+                    .u64_counter("arula.provider.errors")
+                    .with_description("Turns that ended in a provider error")
+                    .build(),
+            }
+        })
+    }
+
+    pub fn record_turn(provider: &str, model: &str, duration: Duration) {
+        instruments().turn_duration.record(
+            duration.as_secs_f64(),
+            &[
+                KeyValue::new("provider", provider.to_string()),
+                KeyValue::new("model", model.to_string()),
+            ],
+        );
+    }
+
+    pub fn record_tokens(model: &str, prompt_tokens: u32, completion_tokens: u32) {
+        let tokens = &instruments().tokens;
+        for (kind, count) in [("prompt", prompt_tokens), ("completion", completion_tokens)] {
+            tokens.add(
+                u64::from(count),
+                &[
+                    KeyValue::new("model", model.to_string()),
+                    KeyValue::new("kind", kind),
+                ],
+            );
+        }
+    }
+
+    pub fn record_tool_call(name: &str, success: bool, duration: Duration) {
+        instruments().tool_duration.record(
+            duration.as_secs_f64(),
+            &[
+                KeyValue::new("tool", name.to_string()),
+                KeyValue::new("success", success),
+            ],
+        );
+    }
+
+    pub fn record_provider_error(provider: &str) {
+        instruments()
+            .provider_errors
+            .add(1, &[KeyValue::new("provider", provider.to_string())]);
+    }
+}
+
+#[cfg(feature = "otel")]
+pub use otel::{init, TelemetryGuard};
+
+/// Record the latency of a completed turn
+pub fn record_turn(provider: &str, model: &str, duration: Duration) {
+    #[cfg(feature = "otel")]
+    otel::record_turn(provider, model, duration);
+    #[cfg(not(feature = "otel"))]
+    let _ = (provider, model, duration);
+}
+
+/// Record token usage reported at the end of a provider response
+pub fn record_tokens(model: &str, prompt_tokens: u32, completion_tokens: u32) {
+    #[cfg(feature = "otel")]
+    otel::record_tokens(model, prompt_tokens, completion_tokens);
+    #[cfg(not(feature = "otel"))]
+    let _ = (model, prompt_tokens, completion_tokens);
+}
+
+/// Record how long a tool call took and whether it succeeded
+pub fn record_tool_call(name: &str, success: bool, duration: Duration) {
+    #[cfg(feature = "otel")]
+    otel::record_tool_call(name, success, duration);
+    #[cfg(not(feature = "otel"))]
+    let _ = (name, success, duration);
+}
+
+/// Record a turn that failed with a provider error
+pub fn record_provider_error(provider: &str) {
+    #[cfg(feature = "otel")]
+    otel::record_provider_error(provider);
+    #[cfg(not(feature = "otel"))]
+    let _ = provider;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_endpoint_prefers_config() {
+        let config = TelemetryConfig {
+            enabled: true,
+            endpoint: Some("http://collector:4318/".to_string()),
+            service_name: None,
+        };
+        assert_eq!(resolve_endpoint(&config), "http://collector:4318");
+    }
+}
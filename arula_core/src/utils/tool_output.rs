@@ -0,0 +1,143 @@
+//! Size limits for tool output entering the conversation history
+//!
+//! One `read_file` of a generated file or a noisy build log can add tens of
+//! thousands of tokens to every later request. Before a tool result is added to
+//! the history, `shrink_for_history` keeps the head and tail of anything larger
+//! than `tool_result_max_tokens` (default 4000) and says how much was cut. JSON
+//! results keep their structure; only the oversized string fields are cut.
+//!
+//! Only the history copy is shrunk. Frontends still receive the full result,
+//! so the tool bubble shows the complete output.
+
+use crate::utils::context_budget::estimate_tokens;
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Default limit for a single tool result in the history
+pub const DEFAULT_TOOL_RESULT_MAX_TOKENS: usize = 4_000;
+
+/// Share of the kept text taken from the start; the rest comes from the end
+const HEAD_SHARE: f32 = 0.6;
+
+/// Strings smaller than this are never cut inside JSON results
+const MIN_FIELD_TOKENS: usize = 64;
+
+/// Shrink `content` to roughly `max_tokens`, returning it unchanged when it fits
+pub fn shrink_for_history(content: &str, max_tokens: usize) -> Cow<'_, str> {
+    if estimate_tokens(content) <= max_tokens {
+        return Cow::Borrowed(content);
+    }
+
+    if let Ok(mut value) = serde_json::from_str::<Value>(content) {
+        if value.is_object() || value.is_array() {
+            shrink_json(&mut value, max_tokens);
+            let shrunk = value.to_string();
+            if estimate_tokens(&shrunk) <= max_tokens {
+                return Cow::Owned(shrunk);
+            }
+        }
+    }
+
+    Cow::Owned(head_tail(content, max_tokens))
+}
+
+/// Cut every large string in `value` so the strings together fit `max_tokens`
+fn shrink_json(value: &mut Value, max_tokens: usize) {
+    let mut large = Vec::new();
+    collect_large_strings(value, &mut large);
+    if large.is_empty() {
+        return;
+    }
+
+    // Leave room for keys, escaping and the rest of the structure
+    let share = (max_tokens * 4 / 5 / large.len()).max(MIN_FIELD_TOKENS);
+    shrink_strings(value, share);
+}
+
+fn collect_large_strings(value: &Value, out: &mut Vec<usize>) {
+    match value {
+        Value::String(s) => {
+            let tokens = estimate_tokens(s);
+            if tokens > MIN_FIELD_TOKENS {
+                out.push(tokens);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_large_strings(v, out)),
+        Value::Object(map) => map.values().for_each(|v| collect_large_strings(v, out)),
+        _ => {}
+    }
+}
+
+fn shrink_strings(value: &mut Value, max_tokens: usize) {
+    match value {
+        Value::String(s) => {
+            if estimate_tokens(s) > max_tokens {
+                *s = head_tail(s, max_tokens);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| shrink_strings(v, max_tokens)),
+        Value::Object(map) => map.values_mut().for_each(|v| shrink_strings(v, max_tokens)),
+        _ => {}
+    }
+}
+
+/// Keep the start and end of `text`, cut at line boundaries where possible
+fn head_tail(text: &str, max_tokens: usize) -> String {
+    let total_chars = text.chars().count();
+    let keep_chars = max_tokens * 4;
+    let head_chars = (keep_chars as f32 * HEAD_SHARE) as usize;
+    let tail_chars = keep_chars - head_chars;
+
+    let head_end = byte_offset(text, head_chars);
+    let head = match text[..head_end].rfind('\n') {
+        Some(idx) if idx > head_end / 2 => &text[..idx],
+        _ => &text[..head_end],
+    };
+
+    let tail_start = byte_offset(text, total_chars.saturating_sub(tail_chars));
+    let tail = match text[tail_start..].find('\n') {
+        Some(idx) if idx < (text.len() - tail_start) / 2 => &text[tail_start + idx + 1..],
+        _ => &text[tail_start..],
+    };
+
+    let omitted = &text[head.len()..text.len() - tail.len()];
+    format!(
+        "{}\n[... {} lines (~{} tokens) omitted; the full output is shown to the user ...]\n{}",
+        head,
+        omitted.lines().count(),
+        estimate_tokens(omitted),
+        tail
+    )
+}
+
+fn byte_offset(text: &str, chars: usize) -> usize {
+    text.char_indices()
+        .nth(chars)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_output_is_untouched() {
+        assert!(matches!(shrink_for_history("ok", 100), Cow::Borrowed("ok")));
+    }
+
+    #[test]
+    fn test_large_output_keeps_head_and_tail() {
+        let text: String = (0..2000).map(|i| format!("line {}\n", i)).collect();
+        let shrunk = shrink_for_history(&text, 500);
+        assert!(estimate_tokens(&shrunk) < 600);
+        assert!(shrunk.starts_with("line 0\n"));
+        assert!(shrunk.trim_end().ends_with("line 1999"));
+        assert!(shrunk.contains("omitted"));
+
+        let json = serde_json::json!({"content": text, "path": "big.txt"}).to_string();
+        let shrunk: Value = serde_json::from_str(&shrink_for_history(&json, 500)).unwrap();
+        assert_eq!(shrunk["path"], "big.txt");
+        assert!(shrunk["content"].as_str().unwrap().contains("omitted"));
+    }
+}
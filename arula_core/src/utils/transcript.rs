@@ -0,0 +1,205 @@
+//! Shareable transcripts of a conversation
+//!
+//! Renders a session's `UiEvent`s (as saved by `ConversationManager`) to
+//! Markdown for pasting into issues and chats, or to a single self-contained
+//! HTML page with inline styles for sharing as a file. Both are redacted, since
+//! tool output can carry secrets the user never looked at.
+
+use crate::session_manager::UiEvent;
+use crate::utils::redact;
+use std::fmt::Write as _;
+
+/// One rendered entry of the conversation
+enum Entry<'a> {
+    User(&'a str),
+    Assistant(&'a str),
+    Thinking(&'a str),
+    Tool {
+        summary: &'a str,
+        success: Option<bool>,
+    },
+}
+
+/// Title and entries of a conversation, in order
+fn entries(events: &[UiEvent]) -> (Option<&str>, Vec<Entry<'_>>) {
+    let mut title = None;
+    let mut entries = Vec::new();
+    for event in events {
+        match event {
+            UiEvent::ConversationTitle(t) => title = Some(t.as_str()),
+            UiEvent::UserMessage { content, .. } => entries.push(Entry::User(content)),
+            UiEvent::AiMessage { content, .. } => entries.push(Entry::Assistant(content)),
+            UiEvent::Thinking(_, text) => entries.push(Entry::Thinking(text)),
+            UiEvent::ToolCallStart(_, _, _, display) => entries.push(Entry::Tool {
+                summary: display,
+                success: None,
+            }),
+            UiEvent::ToolCallResult(_, _, ok, summary) => {
+                // Saved sessions pair each start with a result of the same text
+                match entries.last_mut() {
+                    Some(Entry::Tool {
+                        summary: s,
+                        success,
+                    }) if success.is_none() => {
+                        if !summary.is_empty() {
+                            *s = summary.as_str();
+                        }
+                        *success = Some(*ok);
+                    }
+                    _ => entries.push(Entry::Tool {
+                        summary,
+                        success: Some(*ok),
+                    }),
+                }
+            }
+            _ => {}
+        }
+    }
+    (title, entries)
+}
+
+fn tool_mark(success: Option<bool>) -> &'static str {
+    match success {
+        Some(true) => "✓",
+        Some(false) => "✗",
+        None => "…",
+    }
+}
+
+/// The conversation as Markdown
+pub fn to_markdown(events: &[UiEvent]) -> String {
+    let (title, entries) = entries(events);
+    let mut out = format!("# {}\n", title.unwrap_or("Arula conversation"));
+    for entry in entries {
+        match entry {
+            Entry::User(text) => {
+                let _ = write!(out, "\n## You\n\n{}\n", text.trim());
+            }
+            Entry::Assistant(text) => {
+                let _ = write!(out, "\n## Arula\n\n{}\n", text.trim());
+            }
+            Entry::Thinking(text) => {
+                let _ = write!(
+                    out,
+                    "\n<details><summary>Thinking</summary>\n\n{}\n\n</details>\n",
+                    text.trim()
+                );
+            }
+            Entry::Tool { summary, success } => {
+                let _ = write!(out, "\n> {} `{}`\n", tool_mark(success), summary.trim());
+            }
+        }
+    }
+    redact::redact(&out).into_owned()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Message text as HTML: fenced code blocks become `<pre>`, the rest keeps
+/// its line breaks
+fn text_to_html(text: &str) -> String {
+    let mut html = String::new();
+    for (i, part) in text.trim().split("```").enumerate() {
+        if i % 2 == 1 {
+            // Drop the language tag after the opening fence
+            let code = part.split_once('\n').map_or(part, |(_, code)| code);
+            let _ = write!(html, "<pre><code>{}</code></pre>", escape_html(code));
+        } else if !part.trim().is_empty() {
+            let _ = write!(html, "<p>{}</p>", escape_html(part.trim()));
+        }
+    }
+    html
+}
+
+const HTML_STYLE: &str = "body{font:15px/1.6 system-ui,sans-serif;max-width:820px;margin:2em auto;padding:0 1em;background:#1a1b26;color:#c0caf5}\
+h1{font-size:1.4em}.msg{border-radius:10px;padding:.6em 1em;margin:1em 0}.user{background:#2a2e45}.ai{background:#202233}\
+.role{font-size:.8em;opacity:.6;margin-bottom:.3em}p{white-space:pre-wrap;margin:.4em 0}\
+pre{background:#13141f;padding:.8em;border-radius:6px;overflow-x:auto}code{font-family:ui-monospace,monospace}\
+.tool{font-family:ui-monospace,monospace;font-size:.85em;opacity:.75;margin:.4em 1em}\
+details{opacity:.7;margin:.4em 1em}footer{opacity:.5;font-size:.8em;margin-top:3em}";
+
+/// The conversation as a standalone HTML page
+pub fn to_html(events: &[UiEvent]) -> String {
+    let (title, entries) = entries(events);
+    let title = escape_html(title.unwrap_or("Arula conversation"));
+    let mut body = String::new();
+    for entry in entries {
+        match entry {
+            Entry::User(text) => {
+                let _ = write!(
+                    body,
+                    "<div class=\"msg user\"><div class=\"role\">You</div>{}</div>\n",
+                    text_to_html(text)
+                );
+            }
+            Entry::Assistant(text) => {
+                let _ = write!(
+                    body,
+                    "<div class=\"msg ai\"><div class=\"role\">Arula</div>{}</div>\n",
+                    text_to_html(text)
+                );
+            }
+            Entry::Thinking(text) => {
+                let _ = write!(
+                    body,
+                    "<details><summary>Thinking</summary>{}</details>\n",
+                    text_to_html(text)
+                );
+            }
+            Entry::Tool { summary, success } => {
+                let _ = write!(
+                    body,
+                    "<div class=\"tool\">{} {}</div>\n",
+                    tool_mark(success),
+                    escape_html(summary.trim())
+                );
+            }
+        }
+    }
+    let page = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n\
+         {body}<footer>Exported from Arula</footer>\n</body>\n</html>\n"
+    );
+    redact::redact(&page).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_markdown_and_html() {
+        let id = Uuid::new_v4();
+        let events = vec![
+            UiEvent::ConversationTitle("Fix the build".into()),
+            UiEvent::UserMessage {
+                content: "Why does <main> fail?".into(),
+                timestamp: String::new(),
+            },
+            UiEvent::ToolCallStart(id, "call_1".into(), "read_file".into(), "📄 main.rs".into()),
+            UiEvent::ToolCallResult(id, "read_file".into(), true, String::new()),
+            UiEvent::AiMessage {
+                content: "Add this:\n```rust\nfn main() {}\n```".into(),
+                timestamp: String::new(),
+            },
+        ];
+
+        let markdown = to_markdown(&events);
+        assert!(markdown.starts_with("# Fix the build\n"));
+        assert!(markdown.contains("## You\n\nWhy does <main> fail?"));
+        assert!(markdown.contains("> ✓ `📄 main.rs`"));
+
+        let html = to_html(&events);
+        assert!(html.contains("Why does &lt;main&gt; fail?"));
+        assert!(html.contains("<pre><code>fn main() {}\n</code></pre>"));
+        assert_eq!(html.matches("class=\"tool\"").count(), 1);
+    }
+}
@@ -0,0 +1,390 @@
+//! Update check against GitHub releases
+//!
+//! Looks up the latest release of the repository, at most once a day (the
+//! answer is cached in `~/.arula/cache/update_check.json`), and reports it when
+//! it is newer than the running build. `self_update` downloads the release
+//! asset for this platform and replaces the running executable; it supports
+//! bare binaries and `.tar.gz`/`.zip` archives (unpacked with the system `tar`).
+//! The asset must be published with a `<asset>.sha256` checksum next to it;
+//! without one, or when the download doesn't match it, nothing is installed.
+
+use crate::api::http_client;
+use crate::utils::config::Config;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Repository the releases are published in
+pub const REPOSITORY: &str = "CriticalRange/arula-cli";
+
+/// Version of the running build
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How long a check result is reused
+const CHECK_INTERVAL_HOURS: i64 = 24;
+
+const DOWNLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// A downloadable file of a release
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub download_url: String,
+}
+
+/// A published release
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Release {
+    /// Version without the leading `v`
+    pub version: String,
+    /// Release page
+    pub url: String,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+impl Release {
+    /// The asset built for this OS and CPU, judged by its name
+    pub fn asset_for_platform(&self) -> Option<&Asset> {
+        let os: &[&str] = match std::env::consts::OS {
+            "linux" => &["linux"],
+            "macos" => &["macos", "darwin", "apple"],
+            "windows" => &["windows", "win64", ".exe"],
+            _ => return None,
+        };
+        let arch: &[&str] = match std::env::consts::ARCH {
+            "x86_64" => &["x86_64", "amd64", "x64"],
+            "aarch64" => &["aarch64", "arm64"],
+            _ => return None,
+        };
+        self.assets.iter().find(|asset| {
+            let name = asset.name.to_lowercase();
+            name.contains("arula")
+                && os.iter().any(|o| name.contains(o))
+                && arch.iter().any(|a| name.contains(a))
+                && !name.ends_with(".sha256")
+        })
+    }
+
+    /// The `<asset>.sha256` file published for `asset`
+    pub fn checksum_for(&self, asset: &Asset) -> Option<&Asset> {
+        let name = format!("{}.sha256", asset.name).to_lowercase();
+        self.assets.iter().find(|a| a.name.to_lowercase() == name)
+    }
+}
+
+/// Cached result of the last check
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckCache {
+    checked_at: DateTime<Utc>,
+    latest: Option<Release>,
+}
+
+fn cache_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".arula").join("cache").join("update_check.json"))
+}
+
+/// `1.2.3` from `v1.2.3` or `1.2.3-beta.1`; missing parts count as 0
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|p| p.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some((major, minor, patch))
+}
+
+/// Whether `latest` is a newer version than `current`
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// Release from GitHub's `releases/latest` response
+fn parse_release(json: &Value) -> Option<Release> {
+    let tag = json["tag_name"].as_str()?;
+    Some(Release {
+        version: tag.trim_start_matches('v').to_string(),
+        url: json["html_url"].as_str().unwrap_or_default().to_string(),
+        assets: json["assets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|asset| {
+                Some(Asset {
+                    name: asset["name"].as_str()?.to_string(),
+                    download_url: asset["browser_download_url"].as_str()?.to_string(),
+                })
+            })
+            .collect(),
+    })
+}
+
+/// Fetch the latest published release
+pub async fn latest_release() -> Result<Release> {
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/latest",
+        REPOSITORY
+    );
+    let response = http_client::get_general_client()
+        .get(&url)
+        .header("User-Agent", format!("arula/{}", CURRENT_VERSION))
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await
+        .context("Failed to reach GitHub")?;
+    if !response.status().is_success() {
+        bail!("GitHub answered with status {}", response.status());
+    }
+    let json: Value = response.json().await?;
+    parse_release(&json).ok_or_else(|| anyhow!("Unexpected release format from GitHub"))
+}
+
+/// The latest release if it is newer than this build. Skipped when update
+/// checks are turned off; a result less than a day old is reused.
+pub async fn check(config: &Config) -> Result<Option<Release>> {
+    if !config.get_update_check_enabled() {
+        return Ok(None);
+    }
+    let cached = cache_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str::<CheckCache>(&text).ok())
+        .filter(|cache| Utc::now() - cache.checked_at < Duration::hours(CHECK_INTERVAL_HOURS));
+    let latest = match cached {
+        Some(cache) => cache.latest,
+        None => {
+            let latest = latest_release().await?;
+            let cache = CheckCache {
+                checked_at: Utc::now(),
+                latest: Some(latest.clone()),
+            };
+            if let Some(path) = cache_path() {
+                let written = path
+                    .parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .and_then(|_| std::fs::write(&path, serde_json::to_string(&cache)?));
+                if let Err(e) = written {
+                    tracing::debug!("Failed to cache update check: {}", e);
+                }
+            }
+            Some(latest)
+        }
+    };
+    Ok(latest.filter(|release| is_newer(&release.version, CURRENT_VERSION)))
+}
+
+/// Replace the running executable with `release`'s build for this platform
+pub async fn self_update(release: &Release) -> Result<PathBuf> {
+    let asset = release.asset_for_platform().ok_or_else(|| {
+        anyhow!(
+            "Release {} has no build for {}-{}; download it from {}",
+            release.version,
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            release.url
+        )
+    })?;
+    let checksum = release.checksum_for(asset).ok_or_else(|| {
+        anyhow!(
+            "Release {} publishes no checksum for {}; not installing an unverified build",
+            release.version,
+            asset.name
+        )
+    })?;
+    let current = std::env::current_exe()?;
+    let dir = current
+        .parent()
+        .ok_or_else(|| anyhow!("Can't locate the install directory"))?;
+
+    let checksum = String::from_utf8_lossy(&download(checksum).await?).into_owned();
+    let expected = expected_sha256(&checksum, &asset.name)
+        .ok_or_else(|| anyhow!("{}.sha256 isn't a SHA-256 checksum file", asset.name))?;
+    let bytes = download(asset).await?;
+    verify_sha256(&bytes, &expected)
+        .with_context(|| format!("{} doesn't match its published checksum", asset.name))?;
+
+    // Unpack next to the executable so the final rename stays on one filesystem
+    let staging = tempfile::Builder::new()
+        .prefix(".arula-update")
+        .tempdir_in(dir)
+        .context("Can't write to the install directory")?;
+    let download = staging.path().join(&asset.name);
+    std::fs::write(&download, &bytes)?;
+    let binary = if is_archive(&asset.name) {
+        unpack(&download, staging.path(), &current)?
+    } else {
+        download
+    };
+    replace_executable(&binary, &current)?;
+    Ok(current)
+}
+
+async fn download(asset: &Asset) -> Result<Vec<u8>> {
+    let response = http_client::get_general_client()
+        .get(&asset.download_url)
+        .header("User-Agent", format!("arula/{}", CURRENT_VERSION))
+        .timeout(DOWNLOAD_TIMEOUT)
+        .send()
+        .await
+        .with_context(|| format!("Failed to download {}", asset.name))?;
+    if !response.status().is_success() {
+        bail!(
+            "Download of {} failed with status {}",
+            asset.name,
+            response.status()
+        );
+    }
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// The hex digest for `name` in a checksum file: either a bare digest or
+/// `sha256sum` output (`<digest>  <name>`, `*<name>` in binary mode)
+fn expected_sha256(text: &str, name: &str) -> Option<String> {
+    let is_digest = |s: &str| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit());
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let digest = lines.find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let digest = parts.next()?;
+        let file = parts.next().map(|f| f.trim_start_matches('*'));
+        let for_asset = file.is_none_or(|f| Path::new(f).file_name() == Some(name.as_ref()));
+        (is_digest(digest) && for_asset).then_some(digest)
+    })?;
+    Some(digest.to_lowercase())
+}
+
+fn verify_sha256(bytes: &[u8], expected: &str) -> Result<()> {
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if actual != expected {
+        bail!("expected SHA-256 {}, got {}", expected, actual);
+    }
+    Ok(())
+}
+
+fn is_archive(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.ends_with(".tar.gz") || name.ends_with(".tgz") || name.ends_with(".zip")
+}
+
+/// Extract `archive` into `dir` and find the file named like the executable
+fn unpack(archive: &Path, dir: &Path, current: &Path) -> Result<PathBuf> {
+    let status = std::process::Command::new("tar")
+        .arg("-xf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dir)
+        .status()
+        .context("Failed to run tar to unpack the update")?;
+    if !status.success() {
+        bail!("tar failed to unpack {}", archive.display());
+    }
+    let name = current
+        .file_name()
+        .ok_or_else(|| anyhow!("Executable has no file name"))?;
+    find_file(dir, name)
+        .ok_or_else(|| anyhow!("The archive doesn't contain {}", name.to_string_lossy()))
+}
+
+fn find_file(dir: &Path, name: &std::ffi::OsStr) -> Option<PathBuf> {
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if let Some(found) = find_file(&path, name) {
+                return Some(found);
+            }
+        } else if path.file_name() == Some(name) {
+            return Some(path);
+        }
+    }
+    None
+}
+
+fn replace_executable(new: &Path, current: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(new, std::fs::Permissions::from_mode(0o755))?;
+    }
+    // Windows can't overwrite a running executable, but it can rename it
+    #[cfg(windows)]
+    {
+        let old = current.with_extension("old.exe");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(current, &old).context("Failed to move the old executable aside")?;
+    }
+    let installed = std::fs::rename(new, current);
+    // Put the old executable back rather than leave none installed
+    #[cfg(windows)]
+    if installed.is_err() {
+        let _ = std::fs::rename(current.with_extension("old.exe"), current);
+    }
+    installed.context("Failed to install the new executable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_parsing_and_versions() {
+        let json = serde_json::json!({
+            "tag_name": "v0.3.0",
+            "html_url": "https://github.com/CriticalRange/arula-cli/releases/tag/v0.3.0",
+            "assets": [
+                { "name": "arula-x86_64-unknown-linux-gnu.tar.gz", "browser_download_url": "https://example.com/linux" },
+                { "name": "arula-aarch64-apple-darwin.tar.gz", "browser_download_url": "https://example.com/mac" },
+                { "name": "arula-x86_64-pc-windows-msvc.zip", "browser_download_url": "https://example.com/win" }
+            ]
+        });
+        let release = parse_release(&json).unwrap();
+        assert_eq!(release.version, "0.3.0");
+        assert_eq!(release.assets.len(), 3);
+        if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+            assert_eq!(
+                release
+                    .asset_for_platform()
+                    .map(|a| a.download_url.as_str()),
+                Some("https://example.com/linux")
+            );
+        }
+
+        assert!(is_newer("0.3.0", "0.1.0"));
+        assert!(is_newer("v1.0", "0.9.9"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0-beta.2", "0.1.0"));
+        assert!(!is_newer("nightly", "0.1.0"));
+    }
+
+    #[test]
+    fn test_checksum_verification() {
+        let release = Release {
+            version: "0.3.0".to_string(),
+            url: String::new(),
+            assets: ["arula-linux.tar.gz", "arula-linux.tar.gz.sha256"]
+                .iter()
+                .map(|name| Asset {
+                    name: name.to_string(),
+                    download_url: String::new(),
+                })
+                .collect(),
+        };
+        let checksum = release.checksum_for(&release.assets[0]).unwrap();
+        assert_eq!(checksum.name, "arula-linux.tar.gz.sha256");
+        assert!(release.checksum_for(checksum).is_none());
+
+        let bytes = b"new build";
+        let digest = format!("{:x}", Sha256::digest(bytes));
+        let listing = format!("{}  other.zip\n{} *dist/arula-linux.tar.gz\n", "0".repeat(64), digest);
+        assert_eq!(expected_sha256(&listing, "arula-linux.tar.gz"), Some(digest.clone()));
+        assert_eq!(expected_sha256(&digest.to_uppercase(), "arula-linux.tar.gz"), Some(digest.clone()));
+        assert_eq!(expected_sha256("not a checksum", "arula-linux.tar.gz"), None);
+
+        assert!(verify_sha256(bytes, &digest).is_ok());
+        assert!(verify_sha256(b"tampered", &digest).is_err());
+    }
+}
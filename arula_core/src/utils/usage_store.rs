@@ -0,0 +1,342 @@
+//! Local usage store backing the desktop "Stats" page
+//!
+//! Every provider response, finished turn and tool call is appended as one JSON
+//! line to `~/.arula/usage.jsonl`. Nothing leaves the machine; `summarize`
+//! aggregates the file into per-day tokens and cost, per-provider
+//! time-to-first-token and per-tool success rates.
+
+use crate::api::model_info;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Serializes appends from concurrent sessions
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// USD per million (input, output) tokens, matched against the model name.
+/// More specific prefixes come first. Unknown models have no cost estimate.
+const MODEL_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("o3-mini", 1.10, 4.40),
+    ("o4-mini", 1.10, 4.40),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-haiku", 1.00, 5.00),
+    ("claude-3-opus", 15.00, 75.00),
+    ("claude-opus", 15.00, 75.00),
+    ("claude-sonnet", 3.00, 15.00),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("glm-4.5-air", 0.20, 1.10),
+    ("glm-4", 0.60, 2.20),
+];
+
+/// One line of the usage file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: UsageKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UsageKind {
+    /// Token counts reported at the end of one provider response
+    Tokens {
+        model: String,
+        prompt_tokens: u32,
+        completion_tokens: u32,
+    },
+    /// A user turn, from prompt to the end of the stream
+    Turn {
+        provider: String,
+        model: String,
+        /// Milliseconds until the first text token, if any arrived
+        ttft_ms: Option<u64>,
+        duration_ms: u64,
+        success: bool,
+    },
+    /// A single tool execution
+    Tool {
+        name: String,
+        success: bool,
+        duration_ms: u64,
+    },
+}
+
+/// Tokens and estimated cost for one local calendar day
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Estimated USD; only counts models with a known price
+    pub cost: f64,
+}
+
+/// Latency figures for one provider
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProviderLatency {
+    pub provider: String,
+    pub turns: u64,
+    pub failed_turns: u64,
+    pub avg_ttft_ms: Option<u64>,
+}
+
+/// Outcome counts for one tool
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolStats {
+    pub name: String,
+    pub calls: u64,
+    pub successes: u64,
+    pub avg_duration_ms: u64,
+}
+
+impl ToolStats {
+    /// Fraction of calls that succeeded, 0.0 when never called
+    pub fn success_rate(&self) -> f32 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.successes as f32 / self.calls as f32
+        }
+    }
+}
+
+/// Aggregated view of the usage file
+#[derive(Debug, Clone, Default)]
+pub struct UsageSummary {
+    /// Most recent day first
+    pub days: Vec<DailyUsage>,
+    /// Sorted by provider name
+    pub providers: Vec<ProviderLatency>,
+    /// Most used first
+    pub tools: Vec<ToolStats>,
+}
+
+impl UsageSummary {
+    pub fn total_tokens(&self) -> u64 {
+        self.days
+            .iter()
+            .map(|d| d.prompt_tokens + d.completion_tokens)
+            .sum()
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.days.iter().map(|d| d.cost).sum()
+    }
+}
+
+/// Path of the usage file
+pub fn usage_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".arula")
+        .join("usage.jsonl")
+}
+
+/// Estimated USD cost of a response, if the model's price is known. Prices
+/// fetched from OpenRouter take precedence over the built-in table.
+pub fn estimate_cost(model: &str, prompt_tokens: u64, completion_tokens: u64) -> Option<f64> {
+    if let Some(info) = model_info::get(model) {
+        if let (Some(input), Some(output)) = (info.prompt_price, info.completion_price) {
+            return Some(
+                (prompt_tokens as f64 * input + completion_tokens as f64 * output) / 1_000_000.0,
+            );
+        }
+    }
+    let model = model.to_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    MODEL_PRICES
+        .iter()
+        .find(|(prefix, _, _)| name.starts_with(prefix))
+        .map(|(_, input, output)| {
+            (prompt_tokens as f64 * input + completion_tokens as f64 * output) / 1_000_000.0
+        })
+}
+
+/// Append a record to the usage file. Failures are logged, never surfaced.
+pub fn record(kind: UsageKind) {
+    if let Err(e) = append_to(&usage_path(), &UsageRecord { timestamp: Utc::now(), kind }) {
+        tracing::warn!("Failed to record usage: {}", e);
+    }
+}
+
+fn append_to(path: &Path, record: &UsageRecord) -> anyhow::Result<()> {
+    let line = serde_json::to_string(record)?;
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// Read all records from `path`, skipping lines that fail to parse
+pub fn load_records(path: &Path) -> Vec<UsageRecord> {
+    let Ok(file) = std::fs::File::open(path) else {
+        return Vec::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+/// Summarize the last `days` days of the usage file
+pub fn summarize(days: i64) -> UsageSummary {
+    summarize_records(&load_records(&usage_path()), days)
+}
+
+/// Aggregate records newer than `days` days
+pub fn summarize_records(records: &[UsageRecord], days: i64) -> UsageSummary {
+    let cutoff = Utc::now() - chrono::Duration::days(days);
+
+    let mut per_day: BTreeMap<NaiveDate, DailyUsage> = BTreeMap::new();
+    // provider -> (turns, failed, ttft sum, ttft samples)
+    let mut per_provider: HashMap<String, (u64, u64, u64, u64)> = HashMap::new();
+    // tool -> (calls, successes, total duration)
+    let mut per_tool: HashMap<String, (u64, u64, u64)> = HashMap::new();
+
+    for record in records.iter().filter(|r| r.timestamp >= cutoff) {
+        match &record.kind {
+            UsageKind::Tokens {
+                model,
+                prompt_tokens,
+                completion_tokens,
+            } => {
+                let date = record.timestamp.with_timezone(&Local).date_naive();
+                let day = per_day.entry(date).or_insert_with(|| DailyUsage {
+                    date,
+                    ..Default::default()
+                });
+                day.prompt_tokens += u64::from(*prompt_tokens);
+                day.completion_tokens += u64::from(*completion_tokens);
+                day.cost += estimate_cost(
+                    model,
+                    u64::from(*prompt_tokens),
+                    u64::from(*completion_tokens),
+                )
+                .unwrap_or(0.0);
+            }
+            UsageKind::Turn {
+                provider,
+                ttft_ms,
+                success,
+                ..
+            } => {
+                let entry = per_provider.entry(provider.clone()).or_default();
+                entry.0 += 1;
+                if !success {
+                    entry.1 += 1;
+                }
+                if let Some(ttft) = ttft_ms {
+                    entry.2 += ttft;
+                    entry.3 += 1;
+                }
+            }
+            UsageKind::Tool {
+                name,
+                success,
+                duration_ms,
+            } => {
+                let entry = per_tool.entry(name.clone()).or_default();
+                entry.0 += 1;
+                if *success {
+                    entry.1 += 1;
+                }
+                entry.2 += duration_ms;
+            }
+        }
+    }
+
+    let mut providers: Vec<ProviderLatency> = per_provider
+        .into_iter()
+        .map(|(provider, (turns, failed_turns, ttft_sum, samples))| ProviderLatency {
+            provider,
+            turns,
+            failed_turns,
+            avg_ttft_ms: (samples > 0).then(|| ttft_sum / samples),
+        })
+        .collect();
+    providers.sort_by(|a, b| a.provider.cmp(&b.provider));
+
+    let mut tools: Vec<ToolStats> = per_tool
+        .into_iter()
+        .map(|(name, (calls, successes, duration))| ToolStats {
+            name,
+            calls,
+            successes,
+            avg_duration_ms: duration / calls.max(1),
+        })
+        .collect();
+    tools.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.name.cmp(&b.name)));
+
+    UsageSummary {
+        days: per_day.into_values().rev().collect(),
+        providers,
+        tools,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_records() {
+        let now = Utc::now();
+        let records = vec![
+            UsageRecord {
+                timestamp: now,
+                kind: UsageKind::Tokens {
+                    model: "gpt-4o".to_string(),
+                    prompt_tokens: 1_000_000,
+                    completion_tokens: 0,
+                },
+            },
+            UsageRecord {
+                timestamp: now,
+                kind: UsageKind::Turn {
+                    provider: "openai".to_string(),
+                    model: "gpt-4o".to_string(),
+                    ttft_ms: Some(300),
+                    duration_ms: 1000,
+                    success: true,
+                },
+            },
+            UsageRecord {
+                timestamp: now,
+                kind: UsageKind::Tool {
+                    name: "read_file".to_string(),
+                    success: false,
+                    duration_ms: 10,
+                },
+            },
+            UsageRecord {
+                timestamp: now - chrono::Duration::days(40),
+                kind: UsageKind::Tool {
+                    name: "read_file".to_string(),
+                    success: true,
+                    duration_ms: 10,
+                },
+            },
+        ];
+
+        let summary = summarize_records(&records, 30);
+        assert_eq!(summary.total_tokens(), 1_000_000);
+        assert!((summary.total_cost() - 2.5).abs() < 1e-9);
+        assert_eq!(summary.providers[0].avg_ttft_ms, Some(300));
+        assert_eq!(summary.tools[0].calls, 1);
+        assert_eq!(summary.tools[0].success_rate(), 0.0);
+    }
+}
@@ -0,0 +1,168 @@
+//! Webhooks fired on session events
+//!
+//! Each entry in `webhooks` in `~/.arula/config.json` receives a JSON POST
+//! when a stream finishes, a tool call returns or a stream fails:
+//!
+//! ```json
+//! "webhooks": [
+//!   { "url": "https://hooks.slack.com/services/...", "events": ["stream_finished", "error"] }
+//! ]
+//! ```
+//!
+//! Payloads carry `event`, `session_id` and `timestamp`, the event's details,
+//! and a one-line `text` summary so Slack-style incoming webhooks display
+//! something useful without a transform. Answers and errors are redacted.
+//! Delivery is fire-and-forget; failures are logged and never retried.
+
+use crate::session_manager::UiEvent;
+use crate::utils::config::{Config, WebhookConfig, WebhookEvent};
+use crate::utils::redact;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Per-request timeout for webhook deliveries
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Answers longer than this are cut in the `text` summary (the full answer is
+/// still in `answer`)
+const SUMMARY_CHARS: usize = 500;
+
+static WEBHOOKS: RwLock<Vec<WebhookConfig>> = RwLock::new(Vec::new());
+
+/// Replace the configured webhooks
+pub fn configure(config: &Config) {
+    if let Ok(mut hooks) = WEBHOOKS.write() {
+        *hooks = config.webhooks.clone();
+    }
+}
+
+/// Build the payload for `event`. `answer` is the streamed text of a finished
+/// session. Returns `None` for events webhooks don't cover.
+pub fn payload(event: &UiEvent, answer: Option<&str>) -> Option<(WebhookEvent, Value)> {
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    match event {
+        UiEvent::StreamFinished(session_id) => {
+            let answer = redact::redact(answer.unwrap_or_default()).into_owned();
+            let summary: String = answer.chars().take(SUMMARY_CHARS).collect();
+            let ellipsis = if summary.len() < answer.len() { "…" } else { "" };
+            Some((
+                WebhookEvent::StreamFinished,
+                json!({
+                    "event": "stream_finished",
+                    "session_id": session_id,
+                    "timestamp": timestamp,
+                    "answer": answer,
+                    "text": format!("Arula finished: {}{}", summary, ellipsis),
+                }),
+            ))
+        }
+        UiEvent::ToolCallResult(session_id, tool, success, summary) => Some((
+            WebhookEvent::ToolCallResult,
+            json!({
+                "event": "tool_call_result",
+                "session_id": session_id,
+                "timestamp": timestamp,
+                "tool": tool,
+                "success": success,
+                "summary": summary,
+                "text": format!("{} {}: {}", if *success { "✓" } else { "✗" }, tool, summary),
+            }),
+        )),
+        UiEvent::StreamErrored(session_id, error) => {
+            let error = redact::redact(error).into_owned();
+            Some((
+                WebhookEvent::Error,
+                json!({
+                    "event": "error",
+                    "session_id": session_id,
+                    "timestamp": timestamp,
+                    "text": format!("Arula error: {}", error),
+                    "error": error,
+                }),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Deliver session events to the configured webhooks until the channel closes
+pub(crate) async fn run(mut events: broadcast::Receiver<UiEvent>) {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    // Streamed text per session, for the `stream_finished` payload
+    let mut answers: HashMap<Uuid, String> = HashMap::new();
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::warn!(missed, "Webhooks fell behind the event stream");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let answer = match &event {
+            UiEvent::Token(id, text, false) => {
+                answers.entry(*id).or_default().push_str(text);
+                continue;
+            }
+            UiEvent::StreamFinished(id) | UiEvent::StreamErrored(id, _) => answers.remove(id),
+            _ => None,
+        };
+
+        let hooks: Vec<WebhookConfig> = match WEBHOOKS.read() {
+            Ok(hooks) if !hooks.is_empty() => hooks.clone(),
+            _ => continue,
+        };
+        let Some((kind, body)) = payload(&event, answer.as_deref()) else {
+            continue;
+        };
+
+        for hook in hooks
+            .into_iter()
+            .filter(|h| h.events.is_empty() || h.events.contains(&kind))
+        {
+            let mut request = client.post(&hook.url).json(&body);
+            for (name, value) in &hook.headers {
+                request = request.header(name, value);
+            }
+            tokio::spawn(async move {
+                match request.send().await {
+                    Ok(response) if !response.status().is_success() => {
+                        tracing::warn!(url = %hook.url, status = %response.status(), "Webhook rejected");
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!(url = %hook.url, error = %e, "Webhook failed"),
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payloads() {
+        let id = Uuid::new_v4();
+        let (kind, body) = payload(&UiEvent::StreamFinished(id), Some("All done")).unwrap();
+        assert_eq!(kind, WebhookEvent::StreamFinished);
+        assert_eq!(body["session_id"], id.to_string());
+        assert_eq!(body["text"], "Arula finished: All done");
+
+        let event = UiEvent::ToolCallResult(id, "read_file".into(), false, "not found".into());
+        let (kind, body) = payload(&event, None).unwrap();
+        assert_eq!(kind, WebhookEvent::ToolCallResult);
+        assert_eq!(body["text"], "✗ read_file: not found");
+
+        assert!(payload(&UiEvent::StreamStarted(id), None).is_none());
+    }
+}
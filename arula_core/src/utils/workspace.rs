@@ -0,0 +1,132 @@
+//! Workspaces: a project directory with its own conversations
+//!
+//! A workspace bundles a root directory, its `PROJECT.manifest`, its
+//! `.arula/config.toml` overlay and a conversation list of its own, so one
+//! running app can switch between projects without mixing their histories.
+//! Conversations of a workspace live in
+//! `~/.arula/workspaces/<name>-<hash>/conversations`; the default workspace
+//! keeps using `~/.arula/conversations`. Knowledge base documents sit next to
+//! them in a `knowledge` directory.
+//!
+//! The list of workspace roots is kept in `~/.arula/config.json`.
+
+use crate::conversation_manager::ConversationManager;
+use crate::utils::project_config::ProjectConfig;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A project directory with its own conversation list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    pub name: String,
+    /// Project directory; `None` for the default workspace
+    pub root: Option<PathBuf>,
+}
+
+impl Workspace {
+    /// The workspace for conversations not tied to a project
+    pub fn default_workspace() -> Self {
+        Self {
+            name: "Default".to_string(),
+            root: None,
+        }
+    }
+
+    /// A workspace for the project in `root`, named after the directory
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        let name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| root.display().to_string());
+        Self {
+            name,
+            root: Some(root),
+        }
+    }
+
+    /// Directory name under `~/.arula/workspaces`: readable, but unique per root
+    pub fn storage_key(&self) -> Option<String> {
+        let root = self.root.as_ref()?;
+        let canonical = root.canonicalize().unwrap_or_else(|_| root.clone());
+        let hash = format!(
+            "{:x}",
+            Sha256::digest(canonical.to_string_lossy().as_bytes())
+        );
+        let slug: String = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        Some(format!("{}-{}", slug, &hash[..12]))
+    }
+
+    /// Where this workspace's conversations are stored, under `arula_dir`
+    pub fn conversations_dir_in(&self, arula_dir: &Path) -> PathBuf {
+        match self.storage_key() {
+            Some(key) => arula_dir.join("workspaces").join(key).join("conversations"),
+            None => arula_dir.join("conversations"),
+        }
+    }
+
+    /// Where this workspace's knowledge base documents are stored, under `arula_dir`
+    pub fn knowledge_dir_in(&self, arula_dir: &Path) -> PathBuf {
+        match self.storage_key() {
+            Some(key) => arula_dir.join("workspaces").join(key).join("knowledge"),
+            None => arula_dir.join("knowledge"),
+        }
+    }
+
+    /// Conversation manager for this workspace's conversations
+    pub fn conversation_manager(&self) -> Result<ConversationManager> {
+        let arula_dir = dirs::home_dir()
+            .context("Could not find home directory")?
+            .join(".arula");
+        ConversationManager::with_storage_dir(self.conversations_dir_in(&arula_dir))
+    }
+
+    /// The project's `.arula/config.toml` overlay, if any
+    pub fn project_config(&self) -> Option<ProjectConfig> {
+        ProjectConfig::load(self.root.as_deref()?)
+    }
+}
+
+impl fmt::Display for Workspace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversation_dirs() {
+        let arula_dir = Path::new("/home/me/.arula");
+        let default = Workspace::default_workspace();
+        assert_eq!(
+            default.conversations_dir_in(arula_dir),
+            arula_dir.join("conversations")
+        );
+        assert_eq!(default.knowledge_dir_in(arula_dir), arula_dir.join("knowledge"));
+
+        let api = Workspace::new("/work/My API");
+        let other = Workspace::new("/other/My API");
+        assert_eq!(api.name, "My API");
+        let key = api.storage_key().unwrap();
+        assert!(key.starts_with("my-api-"), "{key}");
+        assert_ne!(Some(key), other.storage_key());
+        assert!(api
+            .conversations_dir_in(arula_dir)
+            .starts_with(arula_dir.join("workspaces")));
+    }
+}
@@ -13,6 +13,8 @@ pub enum SettingsPage {
     Behavior,      // System prompt, temp, tokens, toggles
     Appearance,    // Living background, etc.
     ModelSelector, // Model list selector
+    Stats,         // Usage, cost and latency dashboard
+    Knowledge,     // Reference documents of the active workspace
 }
 
 impl SettingsPage {
@@ -25,6 +27,8 @@ impl SettingsPage {
             SettingsPage::Behavior => "Behavior",
             SettingsPage::Appearance => "Appearance",
             SettingsPage::ModelSelector => "Select Model",
+            SettingsPage::Stats => "Usage & Stats",
+            SettingsPage::Knowledge => "Knowledge",
         }
     }
 
@@ -37,6 +41,8 @@ impl SettingsPage {
             SettingsPage::Behavior => "Adjust AI behavior settings",
             SettingsPage::Appearance => "Customize visual settings",
             SettingsPage::ModelSelector => "Choose a model",
+            SettingsPage::Stats => "Tokens, cost and latency",
+            SettingsPage::Knowledge => "Reference documents for this workspace",
         }
     }
 }
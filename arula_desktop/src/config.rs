@@ -1,4 +1,6 @@
-use arula_core::utils::config::{AiConfig, Config, ZaiEndpoint};
+use arula_core::utils::config::{
+    AiConfig, Config, SamplingParams, StarterMode, ToolVerbosity, ZaiEndpoint,
+};
 use crate::theme::ThemeMode;
 
 /// Form state for the settings configuration panel.
@@ -13,16 +15,35 @@ pub struct ConfigForm {
     pub ollama_tools_enabled: bool,
     pub streaming_enabled: bool,
     pub living_background_enabled: bool,
+    /// Look for newer releases on GitHub once a day
+    pub update_check_enabled: bool,
+    /// Days before saved conversations are deleted, as typed; empty keeps them
+    pub conversation_retention_days: String,
+    /// How much of each tool call the chat shows
+    pub tool_verbosity: ToolVerbosity,
+    /// Mark misspelled words in the chat input
+    pub spellcheck_enabled: bool,
+    /// Where conversation starters come from after the pinned ones
+    pub starter_mode: StarterMode,
+    /// Semicolon-separated pinned starters
+    pub pinned_starters: String,
     pub system_prompt: String,
     pub temperature: f32,
     pub max_tokens: usize,
+    /// Sampling fields as typed; empty means the provider default
+    pub top_p: String,
+    pub frequency_penalty: String,
+    pub presence_penalty: String,
+    /// Comma-separated stop sequences
+    pub stop_sequences: String,
+    pub seed: String,
     pub provider_options: Vec<String>,
     pub status: Option<String>,
     /// Selected z.ai endpoint name (only used when provider is z.ai)
     pub endpoint_name: String,
     /// Available z.ai endpoint options
     pub endpoint_options: Vec<String>,
-    /// Selected theme mode (Light, Dark, Black)
+    /// Selected theme mode (Auto, Light, Dark, Black)
     pub theme_mode: ThemeMode,
     /// Available theme mode options
     pub theme_options: Vec<String>,
@@ -58,6 +79,8 @@ impl ConfigForm {
             .unwrap_or(false);
         let streaming_enabled = provider_config.and_then(|p| p.streaming).unwrap_or(true); // Default to true
         let living_background_enabled = config.get_living_background_enabled();
+        let sampling = config.get_sampling();
+        let optional = |value: Option<String>| value.unwrap_or_default();
 
         // Determine endpoint selection for z.ai provider
         let endpoint_options = ZaiEndpoint::names();
@@ -80,14 +103,31 @@ impl ConfigForm {
             ollama_tools_enabled,
             streaming_enabled,
             living_background_enabled,
+            update_check_enabled: config.get_update_check_enabled(),
+            conversation_retention_days: config
+                .get_conversation_retention_days()
+                .map(|days| days.to_string())
+                .unwrap_or_default(),
+            tool_verbosity: config.get_tool_verbosity(),
+            spellcheck_enabled: config.get_spellcheck_enabled(),
+            starter_mode: config.get_starter_mode(),
+            pinned_starters: config.get_pinned_starters().join("; "),
             system_prompt: "You are ARULA, an Autonomous AI Interface assistant. You help users with coding, shell commands, and general software development tasks. Be concise, helpful, and provide practical solutions.".to_string(),
             temperature: 0.7,
             max_tokens: 2048,
+            top_p: optional(sampling.top_p.map(|v| v.to_string())),
+            frequency_penalty: optional(sampling.frequency_penalty.map(|v| v.to_string())),
+            presence_penalty: optional(sampling.presence_penalty.map(|v| v.to_string())),
+            stop_sequences: sampling.stop.join(", "),
+            seed: optional(sampling.seed.map(|v| v.to_string())),
             provider_options,
             status: None,
             endpoint_name,
             endpoint_options,
-            theme_mode: ThemeMode::default(),
+            theme_mode: config
+                .get_theme()
+                .and_then(ThemeMode::from_name)
+                .unwrap_or_default(),
             theme_options: ThemeMode::all().iter().map(|s| s.to_string()).collect(),
         }
     }
@@ -98,6 +138,43 @@ impl ConfigForm {
         Self::with_provider_options(config, config.active_provider.clone(), provider_options)
     }
 
+    /// The pinned starters as a list, without blank entries.
+    pub fn pinned_starters(&self) -> Vec<String> {
+        self.pinned_starters
+            .split(';')
+            .map(|starter| starter.trim().to_string())
+            .filter(|starter| !starter.is_empty())
+            .collect()
+    }
+
+    /// Parses the sampling fields, naming the first one that isn't a valid number.
+    pub fn sampling(&self) -> Result<SamplingParams, String> {
+        fn parse<T: std::str::FromStr>(label: &str, value: &str) -> Result<Option<T>, String> {
+            let value = value.trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+            value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("{} must be a number", label))
+        }
+
+        Ok(SamplingParams {
+            top_p: parse("Top P", &self.top_p)?,
+            frequency_penalty: parse("Frequency penalty", &self.frequency_penalty)?,
+            presence_penalty: parse("Presence penalty", &self.presence_penalty)?,
+            stop: self
+                .stop_sequences
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+            seed: parse("Seed", &self.seed)?,
+        })
+    }
+
     /// Returns true if the API URL field should be editable.
     /// Now returns true for all providers to allow custom endpoint configuration.
     pub fn api_url_editable(&self) -> bool {
@@ -132,7 +209,11 @@ pub fn collect_provider_options(config: &Config) -> Vec<String> {
         "anthropic".to_string(),
         "z.ai coding plan".to_string(),
         "ollama".to_string(),
+        "lm studio".to_string(),
+        "llamafile".to_string(),
         "openrouter".to_string(),
+        "xai".to_string(),
+        "deepseek".to_string(),
     ];
 
     for name in config.get_provider_names() {
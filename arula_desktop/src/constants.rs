@@ -1,8 +1,81 @@
 // Animation timing
 pub const TICK_INTERVAL_MS: u64 = 16;
+// Tick rate while the window is unfocused or minimized
+pub const IDLE_TICK_INTERVAL_MS: u64 = 1000;
 pub const TICK_INCREMENT: f32 = 0.01;
 pub const HOVER_TICK_INCREMENT: f32 = 0.1;
 
+// Streaming markdown is re-rendered at most this often (~30Hz)
+pub const STREAM_RENDER_INTERVAL_MS: u64 = 33;
+
+// Chat input grows with its content up to this height, then scrolls
+pub const CHAT_INPUT_MAX_HEIGHT: f32 = 200.0;
+pub const CHAT_INPUT_LINE_HEIGHT: f32 = 21.0;
+
+// File preview pane: larger files are cut off at this many bytes
+pub const FILE_PREVIEW_MAX_BYTES: usize = 512 * 1024;
+
+// Chat view counts as scrolled to the bottom within this many pixels of it
+pub const CHAT_BOTTOM_SLACK: f32 = 24.0;
+
+// A streaming conversation is saved at most this often, so an answer
+// survives the app closing mid-stream
+pub const PARTIAL_SAVE_INTERVAL_MS: u64 = 2000;
+
+// The input is saved as a draft once typing pauses this long
+pub const DRAFT_SAVE_DELAY_MS: u64 = 500;
+
+// Runs of at least this many consecutive tool calls fold into one step
+pub const TOOL_GROUP_MIN: usize = 3;
+
+// Matches listed by the Ctrl+R prompt history search
+pub const PROMPT_SEARCH_RESULTS: usize = 8;
+
+// Corrections offered for a misspelled word in the input
+pub const SPELLING_SUGGESTIONS: usize = 4;
+
+// Persona picker entry that turns personas off
+pub const NO_PERSONA: &str = "No persona";
+
+// Messages skipped by PageUp/PageDown when moving the message focus
+pub const MESSAGE_FOCUS_PAGE: isize = 5;
+
+// Log viewer: records shown and refresh interval while open
+pub const LOG_VIEWER_LINES: usize = 500;
+pub const LOG_VIEWER_REFRESH_MS: u64 = 500;
+
+// Event inspector: refresh interval while open and body preview length
+pub const INSPECTOR_REFRESH_MS: u64 = 250;
+pub const INSPECTOR_PREVIEW_CHARS: usize = 160;
+
+// How often key project files are checked for PROJECT.manifest refresh
+pub const MANIFEST_POLL_INTERVAL_MS: u64 = 2000;
+
+// How often .git/HEAD and the index are checked for branch and status changes
+pub const GIT_POLL_INTERVAL_MS: u64 = 2000;
+
+// Quiet time after the last git change before the system prompt is rebuilt
+pub const GIT_REBUILD_DEBOUNCE_MS: u64 = 1500;
+
+// How often the OS light/dark preference is checked while the theme is Auto
+pub const SYSTEM_THEME_POLL_MS: u64 = 3000;
+
+// How often the active provider is pinged for the top bar health dot
+pub const PROVIDER_HEALTH_POLL_MS: u64 = 60_000;
+// ...and while it is unreachable, so queued prompts go out soon after it's back
+pub const PROVIDER_OFFLINE_POLL_MS: u64 = 5_000;
+
+// Times a prompt is resent after connectivity errors before the error is shown
+pub const OFFLINE_MAX_RETRIES: u32 = 3;
+// Wait before the first resend; it doubles with each further one
+pub const OFFLINE_RETRY_BASE_MS: u64 = 2_000;
+
+// Conversations loaded per sidebar page
+pub const CONVERSATION_PAGE_SIZE: usize = 50;
+
+// Days of usage history summarized on the Stats page
+pub const STATS_WINDOW_DAYS: i64 = 30;
+
 // Spring physics defaults
 pub const SPRING_STIFFNESS: f32 = 0.03;
 pub const SPRING_DAMPING: f32 = 0.80;
@@ -15,6 +88,7 @@ pub const PAGE_SLIDE_DISTANCE: f32 = 60.0;
 
 // UI Dimensions
 pub const SETTINGS_CARD_WIDTH: f32 = 300.0;
+pub const ONBOARDING_CARD_WIDTH: f32 = 460.0;
 pub const MESSAGE_MAX_WIDTH: f32 = 700.0;
 pub const MENU_BUTTON_SIZE: f32 = 48.0;
 pub const INPUT_BORDER_RADIUS: f32 = 24.0;
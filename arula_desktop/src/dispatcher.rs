@@ -4,9 +4,10 @@
 //! all backend logic to arula_core::SessionManager.
 
 use arula_core::api::api::ChatMessage;
-use arula_core::{SessionConfig, SessionManager, UiEvent};
+use arula_core::server::{RemoteCommand, WebSocketOptions};
+use arula_core::{PreparedBackend, SessionConfig, SessionManager, UiEvent};
 use iced::Subscription;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 use uuid::Uuid;
 use std::sync::Arc;
 
@@ -15,6 +16,8 @@ use std::sync::Arc;
 /// All backend logic lives in arula_core. This just provides Iced subscriptions.
 pub struct Dispatcher {
     manager: SessionManager,
+    /// Commands from WebSocket and D-Bus clients, one receiver per server
+    remote_commands: Vec<RemoteReceiverWrapper>,
 }
 
 impl Dispatcher {
@@ -22,6 +25,7 @@ impl Dispatcher {
     pub fn new(config: &arula_core::utils::config::Config) -> anyhow::Result<Self> {
         Ok(Self {
             manager: SessionManager::new(config)?,
+            remote_commands: Vec::new(),
         })
     }
 
@@ -33,6 +37,39 @@ impl Dispatcher {
         self.manager.update_backend(config)
     }
 
+    /// Builds a backend for the given configuration off the UI thread.
+    pub fn prepare_backend(
+        &self,
+        config: &arula_core::utils::config::Config,
+    ) -> impl std::future::Future<Output = anyhow::Result<PreparedBackend>> + Send + 'static {
+        self.manager.prepare_backend(config)
+    }
+
+    /// Swaps in a prepared backend unless a newer one was installed meanwhile.
+    pub fn install_backend(&mut self, prepared: PreparedBackend) -> bool {
+        self.manager.install_backend(prepared)
+    }
+
+    /// Streams prompts from a scripted backend instead of the provider.
+    pub fn set_mock_backend(&mut self, backend: Option<arula_core::MockBackend>) {
+        self.manager.set_mock_backend(backend);
+    }
+
+    /// Breakdown of the current system prompt under the context budget.
+    pub fn context_report(&self) -> &arula_core::utils::context_budget::ContextReport {
+        self.manager.context_report()
+    }
+
+    /// System prompt the backend sends ahead of every conversation.
+    pub fn system_prompt(&self) -> &str {
+        self.manager.system_prompt()
+    }
+
+    /// Searches `workspace`'s knowledge base for the following prompts.
+    pub fn set_workspace(&mut self, workspace: &arula_core::utils::workspace::Workspace) {
+        self.manager.set_workspace(workspace);
+    }
+
     /// Signals that streaming should stop for the given session.
     pub fn stop_stream(&self, session_id: Uuid) {
         self.manager.stop_stream(session_id);
@@ -40,7 +77,7 @@ impl Dispatcher {
 
     /// Starts a streaming session for the given prompt with conversation history.
     pub fn start_stream(
-        &self,
+        &mut self,
         session_id: Uuid,
         prompt: String,
         history: Option<Vec<ChatMessage>>,
@@ -61,6 +98,26 @@ impl Dispatcher {
         dispatcher_subscription(rx)
     }
 
+    /// Starts the WebSocket event API; client commands arrive through
+    /// `remote_subscription`.
+    pub fn serve_websocket(&mut self, options: WebSocketOptions) -> anyhow::Result<()> {
+        let rx = self.manager.serve_websocket(options)?;
+        self.remote_commands.push(RemoteReceiverWrapper(Arc::new(std::sync::Mutex::new(Some(rx)))));
+        Ok(())
+    }
+
+    /// Registers the D-Bus service; prompts arrive through `remote_subscription`.
+    #[cfg(target_os = "linux")]
+    pub fn serve_dbus(&mut self) {
+        let rx = self.manager.serve_dbus();
+        self.remote_commands.push(RemoteReceiverWrapper(Arc::new(std::sync::Mutex::new(Some(rx)))));
+    }
+
+    /// Creates an Iced subscription to receive WebSocket and D-Bus client commands.
+    pub fn remote_subscription(&self) -> Subscription<RemoteCommand> {
+        Subscription::batch(self.remote_commands.iter().cloned().map(remote_subscription))
+    }
+
     // ==================== Model Fetching Delegations ====================
 
     pub fn fetch_openai_models(&self) {
@@ -83,10 +140,46 @@ impl Dispatcher {
         self.manager.fetch_ollama_models();
     }
 
+    pub fn reload_ollama_models(&self) {
+        self.manager.reload_ollama_models();
+    }
+
     pub fn get_cached_ollama_models(&self) -> Option<Vec<String>> {
         self.manager.get_cached_ollama_models()
     }
 
+    pub fn fetch_xai_models(&self) {
+        self.manager.fetch_xai_models();
+    }
+
+    pub fn get_cached_xai_models(&self) -> Option<Vec<String>> {
+        self.manager.get_cached_xai_models()
+    }
+
+    pub fn fetch_deepseek_models(&self) {
+        self.manager.fetch_deepseek_models();
+    }
+
+    pub fn get_cached_deepseek_models(&self) -> Option<Vec<String>> {
+        self.manager.get_cached_deepseek_models()
+    }
+
+    pub fn fetch_lm_studio_models(&self) {
+        self.manager.fetch_lm_studio_models();
+    }
+
+    pub fn get_cached_lm_studio_models(&self) -> Option<Vec<String>> {
+        self.manager.get_cached_lm_studio_models()
+    }
+
+    pub fn fetch_llamafile_models(&self) {
+        self.manager.fetch_llamafile_models();
+    }
+
+    pub fn get_cached_llamafile_models(&self) -> Option<Vec<String>> {
+        self.manager.get_cached_llamafile_models()
+    }
+
     pub fn fetch_openrouter_models(&self) {
         self.manager.fetch_openrouter_models();
     }
@@ -152,3 +245,31 @@ pub fn dispatcher_subscription(
         })
     })
 }
+
+/// Receiver for WebSocket client commands, kept in the dispatcher so the
+/// subscription identity stays the same across `subscription()` calls
+#[derive(Clone)]
+struct RemoteReceiverWrapper(Arc<std::sync::Mutex<Option<mpsc::UnboundedReceiver<RemoteCommand>>>>);
+
+impl std::hash::Hash for RemoteReceiverWrapper {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state);
+    }
+}
+
+fn remote_subscription(wrapper: RemoteReceiverWrapper) -> Subscription<RemoteCommand> {
+    use iced::futures::channel::mpsc as iced_mpsc;
+    use iced::futures::SinkExt;
+    use iced::stream;
+
+    Subscription::run_with(wrapper, |wrapper: &RemoteReceiverWrapper| {
+        let rx = wrapper.0.lock().unwrap().take();
+        stream::channel(100, move |mut output: iced_mpsc::Sender<RemoteCommand>| async move {
+            if let Some(mut rx) = rx {
+                while let Some(command) = rx.recv().await {
+                    let _ = output.send(command).await;
+                }
+            }
+        })
+    })
+}
@@ -5,7 +5,9 @@ pub mod canvas;
 pub mod config;
 pub mod constants;
 pub mod dispatcher;
+pub mod onboarding;
 pub mod session;
+pub mod spelling;
 pub mod styles;
 pub mod theme;
 
@@ -16,6 +18,7 @@ pub use animation::{
 pub use config::{collect_provider_options, ConfigForm};
 pub use constants::*;
 pub use dispatcher::Dispatcher;
+pub use onboarding::{Onboarding, OnboardingStep};
 // Re-export UiEvent from core for convenience
 pub use arula_core::UiEvent;
 // Re-export project_context from core
@@ -23,10 +26,13 @@ pub use arula_core::detect_project;
 pub use arula_core::generate_auto_manifest;
 pub use arula_core::is_ai_enhanced;
 pub use arula_core::manifest_exists;
+pub use arula_core::refresh_manifest;
 pub use arula_core::DetectedProject;
+pub use arula_core::ManifestWatcher;
 pub use arula_core::ProjectType;
 pub use arula_core::MANIFEST_MARKER_AI;
 pub use arula_core::MANIFEST_MARKER_AUTO;
-pub use session::{MessageEntry, Session};
+pub use arula_core::MANIFEST_MARKER_AUTO_END;
+pub use session::{tool_run_label, MessageEntry, OutlineEntry, ResponseStats, Session, StreamStats};
 pub use styles::*;
-pub use theme::{app_theme, app_theme_with_mode, palette, palette_from_mode, PaletteColors, ThemeMode};
+pub use theme::{app_theme, app_theme_with_mode, palette, palette_from_mode, system_prefers_dark, PaletteColors, ThemeMode};
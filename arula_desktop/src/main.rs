@@ -1,25 +1,59 @@
 
-use arula_core::utils::config::Config;
+use arula_core::api::api::ChatMessage;
+use arula_core::api::bench::{self, Comparison};
+use arula_core::api::commit_message;
+use arula_core::api::context_limit::{self, Compaction, ContextFit, RequestSize};
+use arula_core::api::model_info;
+use arula_core::api::ollama::{self, LocalModel, PullEvent};
+use arula_core::api::provider_check::{self, Health, HealthReport};
+use arula_core::api::review::{self, ReviewReport, ReviewTarget, Severity};
+use arula_core::tools::builtin::screen_capture;
+use arula_core::tools::code_blocks::{self, ProposedEdit};
+use arula_core::utils::config::{Config, StarterMode, ToolVerbosity};
+use arula_core::utils::context_budget::{estimate_tokens, ContextStatus};
+use arula_core::utils::crash_report::{self, CrashReport};
+use arula_core::utils::drafts;
+use arula_core::utils::git_state::{GitInfo, GitWatcher};
+use arula_core::utils::inspector::{self, InspectorSnapshot, InspectorSource};
+use arula_core::utils::knowledge::{self, DocumentInfo, KnowledgeBase};
+use arula_core::utils::logging::{self, LogRecord, LOG_LEVELS};
+use arula_core::utils::personas;
+use arula_core::utils::project_config::ProjectConfig;
+use arula_core::utils::prompt_history::{self, Recall};
+use arula_core::utils::snapshots;
+use arula_core::utils::spellcheck::{self, Dictionary};
+use arula_core::utils::transcript;
+use arula_core::utils::updates::{self, Release};
+use arula_core::utils::usage_store::{self, UsageSummary};
+use arula_core::utils::workspace::Workspace;
 // Test edit - verifying edit tool functionality
+use arula_core::server::{RemoteCommand, WebSocketOptions};
 use arula_core::SessionConfig;
-use arula_core::{ConversationManager, ConversationMetadata};
+use arula_core::{ConversationList, ConversationManager, ConversationMetadata, PartialSaver, PreparedBackend};
 use arula_core::tools::QUESTION_HANDLER;
 use arula_desktop::animation::Spring;
+use arula_desktop::spelling::{self, SpellHighlighter, SpellSettings};
 use arula_desktop::canvas::{
     LiquidMenuBackground, LivingBackground, LoadingSpinner, SpinnerState, SpinnerType,
 };
 use arula_desktop::styles::{
     ai_bubble_style, chat_input_style,
-    input_style, primary_button_style,
+    input_style, primary_button_style, secondary_button_style,
     transparent_style, user_bubble_style,
 };
 use arula_desktop::{
     app_theme_with_mode, collect_provider_options, palette_from_mode, ConfigForm, Dispatcher,
     LiquidMenuState, LivingBackgroundState, MessageEntry, PaletteColors, Session, SettingsMenuState,
-    SettingsPage, TiltCardState, ThemeMode, UiEvent, MESSAGE_MAX_WIDTH, PAGE_SLIDE_DISTANCE,
-    SETTINGS_CARD_WIDTH, TICK_INTERVAL_MS, TILT_CARD_COUNT,
+    SettingsPage, TiltCardState, ThemeMode, UiEvent, CONVERSATION_PAGE_SIZE, IDLE_TICK_INTERVAL_MS,
+    INSPECTOR_PREVIEW_CHARS, INSPECTOR_REFRESH_MS, LOG_VIEWER_LINES, LOG_VIEWER_REFRESH_MS, MESSAGE_MAX_WIDTH, PAGE_SLIDE_DISTANCE,
+    CHAT_BOTTOM_SLACK, MESSAGE_FOCUS_PAGE, CHAT_INPUT_LINE_HEIGHT, CHAT_INPUT_MAX_HEIGHT, FILE_PREVIEW_MAX_BYTES, ONBOARDING_CARD_WIDTH, SETTINGS_CARD_WIDTH, STATS_WINDOW_DAYS, STREAM_RENDER_INTERVAL_MS, TICK_INTERVAL_MS,
+    TILT_CARD_COUNT, PARTIAL_SAVE_INTERVAL_MS, DRAFT_SAVE_DELAY_MS, TOOL_GROUP_MIN, PROMPT_SEARCH_RESULTS, SPELLING_SUGGESTIONS, NO_PERSONA, tool_run_label,
     // Project context
-    detect_project, generate_auto_manifest, is_ai_enhanced, DetectedProject,
+    detect_project, is_ai_enhanced, refresh_manifest, DetectedProject, ManifestWatcher,
+    MANIFEST_MARKER_AUTO_END, MANIFEST_POLL_INTERVAL_MS, GIT_POLL_INTERVAL_MS,
+    system_prefers_dark, SYSTEM_THEME_POLL_MS, PROVIDER_HEALTH_POLL_MS, PROVIDER_OFFLINE_POLL_MS,
+    OFFLINE_MAX_RETRIES, OFFLINE_RETRY_BASE_MS,
+    Onboarding, OnboardingStep,
 };
 use iced_fonts::bootstrap;
 
@@ -28,25 +62,33 @@ use iced::alignment::{Horizontal, Vertical};
 use iced::time::{self, Duration};
 use iced::widget::canvas::Canvas;
 use iced::widget::text_editor;
+use iced::window;
 use iced::widget::{
-    button, checkbox, column, container, markdown, pick_list, row, scrollable, stack, text, text_input, Space,
+    button, checkbox, column, container, markdown, pick_list, row, scrollable, stack, text, text_input,
+    tooltip, Space,
 };
 use iced::{Background, Border, Color, Element, Font, Length, Point, Subscription, Task};
 use rfd::FileDialog;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::time::Instant;
 
 /// Application state.
 struct App {
     dispatcher: Dispatcher,
     sessions: Vec<Session>,
     current: usize,
-    draft: String,
+    /// Chat input; Enter sends, Shift+Enter inserts a newline
+    draft: text_editor::Content,
     config: Config,
     config_form: ConfigForm,
     bg_state: LivingBackgroundState,
     /// Opacity for the living background (0.0 = disabled/gray, 1.0 = enabled)
     bg_opacity: f32,
+    /// Whether the window has focus; animations slow to an idle tick without it
+    window_focused: bool,
+    /// Whether the window is minimized
+    window_minimized: bool,
     menu_state: LiquidMenuState,
     /// Settings submenu navigation state
     settings_state: SettingsMenuState,
@@ -54,6 +96,8 @@ struct App {
     tilt_cards: Vec<TiltCardState>,
     /// Error message if initialization failed
     init_error: Option<String>,
+    /// First-launch setup wizard, shown instead of the chat until it's finished
+    onboarding: Option<Onboarding>,
     /// Editor contents for each message (keyed by session_index:message_index)
     message_editors: HashMap<String, text_editor::Content>,
     /// Cached model list for model selector
@@ -64,11 +108,30 @@ struct App {
     spinner_state: SpinnerState,
     /// Cached parsed markdown for AI messages (keyed by session_index:message_index)
     markdown_cache: HashMap<String, Vec<markdown::Item>>,
+    /// Incrementally parsed markdown of messages still streaming, with the parsed byte length
+    markdown_streams: HashMap<String, (markdown::Content, usize)>,
+    /// Revision of the background parse each key is waiting for
+    markdown_pending: HashMap<String, u64>,
+    /// Counter for background markdown parse revisions
+    markdown_revision: u64,
+    /// Streaming messages (session_index, message_index) with text not yet rendered
+    markdown_dirty: HashSet<(usize, usize)>,
+    /// When streaming markdown was last rendered
+    last_stream_render: Instant,
     /// Track tool display args from ToolCallStart to show in ToolCallResult (keyed by session_id)
     tool_args_cache: HashMap<uuid::Uuid, String>,
+    /// History messages of the raw transcript view, each with its
+    /// pretty-printed JSON, so only changed messages are printed again
+    raw_transcript_cache: Vec<(ChatMessage, String)>,
+    /// Session and message whose "Delete from here" waits for confirmation
+    delete_from_pending: Option<(uuid::Uuid, usize)>,
     /// Track expand/collapse animation state for tool messages (keyed by "session_index:message_index")
     /// Spring position: 0.0 = collapsed, 1.0 = expanded
     tool_animations: HashMap<String, Spring>,
+    /// Whether the chat view is following the latest message
+    chat_at_bottom: bool,
+    /// Message count of the current session when the user scrolled away from the bottom
+    chat_seen_messages: usize,
     /// Current stream error to display to the user
     stream_error: Option<String>,
     /// Whether the error toast is expanded to show full error
@@ -79,16 +142,120 @@ struct App {
     current_directory: PathBuf,
     /// Whether the directory popup is shown
     show_directory_popup: bool,
+    /// Whether the context budget panel is shown
+    show_context_panel: bool,
+    /// Whether the log viewer panel is shown
+    show_log_viewer: bool,
+    /// Whether the conversation outline panel is shown
+    show_outline: bool,
+    /// Whether the per-session temperature/max tokens popover is shown
+    show_session_tuning: bool,
+    /// File opened from a read_file bubble, shown in the right-hand preview pane
+    file_preview: Option<FilePreview>,
+    /// Generated commit message for the staged changes, shown under the git pill
+    commit_draft: Option<CommitDraft>,
+    /// Review of the working tree changes, shown as a findings report
+    review: Option<ReviewState>,
+    /// Two models answering one prompt side by side, while the compare view is open
+    compare: Option<CompareState>,
+    /// Code block from an AI message waiting for confirmation before it is written
+    pending_apply: Option<PendingApply>,
+    /// Screenshots attached to the next prompt
+    pending_images: Vec<PathBuf>,
+    /// Estimated size of the request the draft would send, for the session it
+    /// was computed for
+    context_estimate: Option<(uuid::Uuid, RequestSize)>,
+    /// Sessions whose history is being summarized or trimmed
+    compacting_history: HashSet<uuid::Uuid>,
+    /// Prompts sent while offline, in the order they were sent
+    queued_prompts: Vec<QueuedPrompt>,
+    /// Prompts resent in a row per session after connectivity errors
+    offline_retries: HashMap<uuid::Uuid, u32>,
+    /// Bumped for each connection check after saving settings; older results are dropped
+    provider_check_generation: u64,
+    /// Newer release found by the update check, shown as a chip in Settings
+    update_available: Option<Release>,
+    /// Crash reports from earlier runs the user hasn't dismissed yet
+    crash_reports: Vec<CrashReport>,
+    /// Message bubble focused with the keyboard, for the session it was
+    /// focused in
+    focused_message: Option<(uuid::Uuid, usize)>,
+    /// When each streaming session's conversation was last saved mid-answer
+    last_partial_save: HashMap<uuid::Uuid, Instant>,
+    /// Writes those saves off the UI thread
+    partial_saver: PartialSaver,
+    /// Folded runs of tool calls the user expanded, by session and first message
+    expanded_tool_groups: HashSet<(uuid::Uuid, usize)>,
+    /// Whether a screenshot is being taken
+    capturing_screen: bool,
+    /// Snapshot of recent log records for the log viewer
+    log_records: Vec<LogRecord>,
+    /// Least severe level shown in the log viewer
+    log_level_filter: String,
+    /// When the log viewer snapshot was last refreshed
+    last_log_refresh: Instant,
+    /// Whether the developer event inspector is shown (Ctrl+Shift+D)
+    show_inspector: bool,
+    /// Session and message of the last run stopped by a budget limit
+    budget_notice: Option<(uuid::Uuid, String)>,
+    /// Session whose next prompt runs without budget limits ("continue anyway")
+    budget_override: Option<uuid::Uuid>,
+    /// Session and message of a failed request that is being retried
+    retry_notice: Option<(uuid::Uuid, String)>,
+    /// Copy of the last turn's captured events
+    inspector_snapshot: InspectorSnapshot,
+    /// Source shown in the inspector ("all" or an `InspectorSource` label)
+    inspector_filter: String,
+    /// Inspector entries expanded to show their full body
+    inspector_expanded: HashSet<usize>,
+    /// When the inspector snapshot was last refreshed
+    last_inspector_refresh: Instant,
+    /// Usage summary for the Stats settings page, loaded each time it opens
+    usage_summary: Option<UsageSummary>,
+    /// Documents of the active workspace for the Knowledge page, loaded each time it opens
+    knowledge_documents: Option<Vec<DocumentInfo>>,
+    /// File name of the document being added to the knowledge base
+    knowledge_adding: Option<String>,
+    /// Last error from adding or removing a knowledge document
+    knowledge_error: Option<String>,
     /// Whether the custom directory input is shown
     show_directory_custom_input: bool,
     /// Draft value for the custom directory input
     directory_draft: String,
-    /// Recently used directories (most recent first)
+    /// Recently used directories (most recent first), persisted in the config
     recent_directories: Vec<PathBuf>,
-    /// Conversation manager for saving/loading conversations
+    /// Directories pinned in the directory popup, persisted in the config
+    bookmarked_directories: Vec<PathBuf>,
+    /// Default workspace followed by the ones added by the user
+    workspaces: Vec<Workspace>,
+    /// Index of the active workspace in `workspaces`
+    active_workspace: usize,
+    /// Open sessions and current index of inactive workspaces, keyed by root
+    parked_sessions: HashMap<Option<PathBuf>, (Vec<Session>, usize)>,
+    /// Conversation manager for the active workspace's conversations
     conversation_manager: ConversationManager,
-    /// List of saved conversations
+    /// Loaded pages of saved conversations
     saved_conversations: Vec<ConversationMetadata>,
+    /// Total number of saved conversations on disk
+    conversations_total: usize,
+    /// File offset of the next page of saved conversations
+    conversations_next_offset: usize,
+    /// Pinned conversations, listed above the others
+    pinned_conversations: Vec<ConversationMetadata>,
+    /// Archived conversations, loaded while the archive section is open
+    archived_conversations: Vec<ConversationMetadata>,
+    /// Number of archived conversations on disk
+    archived_total: usize,
+    /// Whether the archive section of the sidebar is open
+    show_archived: bool,
+    /// Whether the sidebar shows checkboxes for bulk actions
+    selecting_conversations: bool,
+    /// Conversations checked for a bulk action
+    selected_conversations: HashSet<uuid::Uuid>,
+    /// Conversations a bulk delete waits to remove until confirmed
+    delete_conversations_pending: Option<Vec<uuid::Uuid>>,
+    /// Tag typed in the bulk action bar
+    conversation_tag_draft: String,
     /// Whether the conversations sidebar is shown
     show_conversations: bool,
     /// Animation state for conversations sidebar visibility (0.0 = hidden, 1.0 = visible) - instant close
@@ -99,12 +266,37 @@ struct App {
     clipboard: Option<arboard::Clipboard>,
     /// Draft value for custom model input in model selector
     custom_model_draft: String,
+    /// Installed Ollama models shown on the Provider page, or why they couldn't be listed
+    ollama_models: Option<Result<Vec<LocalModel>, String>>,
+    /// Model name typed into the Ollama pull field
+    ollama_pull_draft: String,
+    /// Latest or running Ollama pull
+    ollama_pull: Option<OllamaPull>,
+    /// Ollama model whose delete button was pressed once, awaiting confirmation
+    ollama_delete_confirm: Option<String>,
     /// Current theme mode (Light, Dark, Black)
     theme_mode: ThemeMode,
+    /// Last detected OS preference, used while `theme_mode` is Auto
+    system_dark: bool,
+    /// Result of the last provider ping, shown as the top bar health dot
+    provider_health: Option<HealthReport>,
+    /// Most recent ping error and when it happened, kept after recovery
+    provider_last_error: Option<(String, chrono::DateTime<chrono::Local>)>,
     /// Detected project info for current directory (cached)
     detected_project: Option<DetectedProject>,
     /// Whether the current PROJECT.manifest was AI-enhanced
     manifest_is_ai_enhanced: bool,
+    /// Watches key project files to keep the auto section of PROJECT.manifest fresh
+    manifest_watcher: Option<ManifestWatcher>,
+    /// Branch and dirty state of the current directory's repository
+    git_info: Option<GitInfo>,
+    /// Watches HEAD and the index to keep `git_info` and the git prompt context fresh
+    git_watcher: Option<GitWatcher>,
+    /// When to rebuild the system prompt for git changes, pushed back by each
+    /// further change so a rebase or a burst of commits rebuilds once
+    git_rebuild_at: Option<Instant>,
+    /// Per-project overrides from .arula/config.toml in the current directory
+    project_config: Option<ProjectConfig>,
     /// Conversation starter suggestions (max 3)
     conversation_starters: Vec<String>,
     /// Pending question batches from ask_question tool that need user answers
@@ -113,6 +305,126 @@ struct App {
     input_bar_height_spring: Spring,
     /// Custom answer drafts per question: (batch_idx, question_idx) -> draft text
     question_answer_drafts: std::collections::HashMap<(usize, usize), String>,
+    /// Prompts sent from any session, oldest first, for Up and Ctrl+R
+    prompt_history: Vec<String>,
+    /// Where Up/Down in the input currently are in the prompt history
+    prompt_recall: Recall,
+    /// Query of the open Ctrl+R prompt search
+    prompt_search: Option<String>,
+    /// When the input was last edited without being saved as a draft
+    draft_edited: Option<Instant>,
+    /// Loaded dictionary while spellchecking is on
+    spellcheck: Option<std::sync::Arc<Dictionary>>,
+    /// Corrections for the last misspelled word in the input
+    spelling: Option<SpellingFix>,
+    /// Words the user chose to keep as typed
+    spelling_ignored: HashSet<String>,
+}
+
+/// A misspelled word of the input and what it could be replaced with
+struct SpellingFix {
+    word: String,
+    /// Byte range of the word in the input
+    range: std::ops::Range<usize>,
+    suggestions: Vec<String>,
+}
+
+/// A file shown in the preview pane
+struct FilePreview {
+    path: PathBuf,
+    /// Read-only editor holding the file, for highlighting and selection
+    content: text_editor::Content,
+    line_count: usize,
+    /// Whether the file was cut off at `FILE_PREVIEW_MAX_BYTES`
+    truncated: bool,
+    error: Option<String>,
+}
+
+impl FilePreview {
+    fn load(path: PathBuf) -> Self {
+        let (text, truncated, error) = match std::fs::read(&path) {
+            Ok(bytes) => {
+                let truncated = bytes.len() > FILE_PREVIEW_MAX_BYTES;
+                let bytes = &bytes[..bytes.len().min(FILE_PREVIEW_MAX_BYTES)];
+                (String::from_utf8_lossy(bytes).into_owned(), truncated, None)
+            }
+            Err(err) => (String::new(), false, Some(err.to_string())),
+        };
+        Self {
+            path,
+            line_count: text.lines().count().max(1),
+            content: text_editor::Content::with_text(&text),
+            truncated,
+            error,
+        }
+    }
+}
+
+/// A commit message being generated or reviewed before `git commit`
+struct CommitDraft {
+    /// Editable message, so it can be adjusted before committing
+    content: text_editor::Content,
+    generating: bool,
+    /// Generation or commit error, or git's summary after committing
+    status: Option<Result<String, String>>,
+}
+
+/// Progress of a code review of the working tree changes
+enum ReviewState {
+    Running,
+    Done(ReviewReport),
+    Failed(String),
+}
+
+/// The compare view: one prompt, two models, answers in adjacent columns
+struct CompareState {
+    prompt: String,
+    /// Provider and model of each column
+    targets: [(String, String); 2],
+    running: bool,
+    /// Answers of the last run, in column order
+    results: Vec<Comparison>,
+}
+
+/// An Ollama model download started from the Provider page
+struct OllamaPull {
+    model: String,
+    /// Ollama's latest status line
+    status: String,
+    completed: u64,
+    total: u64,
+    running: bool,
+    error: Option<String>,
+}
+
+/// A file-annotated code block previewed as an edit before it is applied
+struct PendingApply {
+    /// Path as written in the code fence
+    display_path: String,
+    edit: ProposedEdit,
+    applying: bool,
+    /// The tool's message once applied, or its error
+    status: Option<Result<String, String>>,
+}
+
+/// Prompt sent while the provider was unreachable, dispatched once it's back
+struct QueuedPrompt {
+    session_id: uuid::Uuid,
+    prompt: String,
+    images: Vec<PathBuf>,
+    /// A resent prompt waits out its backoff before going out again
+    not_before: Option<Instant>,
+}
+
+/// Path argument of a read_file call, from its display args
+/// (`path: "src/main.rs", ...`), resolved against `base`
+fn read_file_path(display_args: &str, base: &std::path::Path) -> Option<PathBuf> {
+    let start = display_args.find("path: ")? + "path: ".len();
+    let path = serde_json::Deserializer::from_str(&display_args[start..])
+        .into_iter::<String>()
+        .next()?
+        .ok()?;
+    Some(base.join(path))
 }
 
 /// A pending question batch from the AI's ask_question tool
@@ -143,11 +455,31 @@ struct PendingQuestionItem {
 #[derive(Debug, Clone)]
 #[allow(dead_code)] // Some variants reserved for future features
 enum Message {
-    DraftChanged(String),
+    DraftAction(text_editor::Action),
     SendPrompt,
     Received(UiEvent),
     NewTab,
+    /// Show the tab at this index
+    SwitchTab(usize),
+    /// Move through the tabs, wrapping around (Ctrl+Tab / Ctrl+Shift+Tab)
+    CycleTab(isize),
+    /// Close the tab at this index, stopping its answer if one is streaming
+    CloseTab(usize),
     ToggleSettings,
+    /// Reload the installed Ollama models
+    RefreshOllamaModels,
+    /// Installed Ollama models, or why they couldn't be listed
+    OllamaModelsLoaded(Result<Vec<LocalModel>, String>),
+    /// Model name typed into the Ollama pull field
+    OllamaPullDraftChanged(String),
+    /// Download the model named in the pull field
+    PullOllamaModel,
+    /// Progress of the running Ollama pull
+    OllamaPullProgress(PullEvent),
+    /// Delete an installed Ollama model (asks for confirmation first)
+    DeleteOllamaModel(String),
+    /// Result of deleting an Ollama model
+    OllamaModelDeleted(Result<(), String>),
     CloseSettings,
     Tick,
     ConfigProviderChanged(String),
@@ -158,6 +490,35 @@ enum Message {
     AddCustomModel,
     ConfigStreamingToggled(bool),
     ConfigLivingBackgroundToggled(bool),
+    ConfigSpellcheckToggled(bool),
+    /// The spellcheck dictionary finished loading, or wasn't found
+    DictionaryLoaded(Option<std::sync::Arc<Dictionary>>),
+    /// Replace the misspelled word with a suggestion
+    ApplySpelling(String),
+    /// Keep the misspelled word as typed
+    IgnoreSpelling,
+    ConfigUpdateCheckToggled(bool),
+    /// Conversation retention in days, as typed; empty keeps conversations
+    ConfigRetentionDaysChanged(String),
+    /// How much of each tool call the chat shows
+    ConfigToolVerbosityChanged(ToolVerbosity),
+    ConfigStarterModeChanged(StarterMode),
+    /// A persona (or `NO_PERSONA`) was picked next to the input
+    PersonaSelected(String),
+    ConfigPinnedStartersChanged(String),
+    UpdateChecked(Result<Option<Release>, String>),
+    /// Open the release page of the available update
+    OpenUpdate,
+    /// Open the latest crash report from an earlier run
+    OpenCrashReport,
+    /// Open a GitHub issue prefilled with the latest crash report
+    SubmitCrashReport,
+    /// Hide the crash notice and don't show these reports again
+    DismissCrashReports,
+    /// Ask the model to finish the answer cut off when the app closed
+    ContinueGeneration,
+    /// Hide the interrupted-answer notice of the current session
+    DismissInterrupted,
     ConfigApiUrlChanged(String),
     /// Handle z.ai endpoint selection change
     ConfigEndpointChanged(String),
@@ -168,7 +529,30 @@ enum Message {
     ConfigSystemPromptChanged(String),
     ConfigTemperatureChanged(f32),
     ConfigMaxTokensChanged(String),
+    /// Sampling fields on the Behavior page, kept as typed until saved
+    ConfigTopPChanged(String),
+    ConfigFrequencyPenaltyChanged(String),
+    ConfigPresencePenaltyChanged(String),
+    ConfigStopSequencesChanged(String),
+    ConfigSeedChanged(String),
     SaveConfig,
+    /// Result of the test request sent after saving provider settings, with
+    /// the check's generation
+    ProviderChecked(u64, Result<(), String>),
+    /// Ping the active provider for the health dot
+    PingProvider,
+    ProviderPinged(HealthReport),
+    /// Summarize or trim the current session's history so the next request fits
+    CompactHistory(Compaction),
+    /// Drop a prompt queued while offline
+    RemoveQueuedPrompt(usize),
+    /// Compacted history of a session, covering its first `covered` messages,
+    /// or why summarizing it failed
+    HistoryCompacted {
+        session_id: uuid::Uuid,
+        covered: usize,
+        history: Result<Vec<ChatMessage>, String>,
+    },
     CardHovered(usize, bool),
     CardMouseMoved(usize, Point),
     /// Handle text editor actions for message selection
@@ -181,22 +565,184 @@ enum Message {
     OpenModelSelector,
     /// Select a model from the list
     SelectModel(String),
+    /// Window focus/size changes, used to throttle animations
+    Window(window::Event),
     /// Handle markdown link clicks
     LinkClicked(markdown::Uri),
+    /// Background markdown parse finished for a message
+    MarkdownParsed {
+        key: String,
+        revision: u64,
+        items: Vec<markdown::Item>,
+    },
+    /// Expand or fold the run of tool calls starting at this message
+    ToggleToolGroup(usize),
     /// Stop the current streaming session
     StopStream,
+    /// Stop the current answer but keep it, marked as cut off, so the next
+    /// prompt can correct it (Esc in the input while streaming)
+    InterruptAndReply,
+    /// Put an older (`true`) or newer sent prompt into the empty input
+    RecallPrompt(bool),
+    /// Open or close the Ctrl+R prompt history search
+    TogglePromptSearch,
+    /// Query of the prompt history search changed
+    PromptSearchChanged(String),
+    /// Put a prompt picked from the history search into the input
+    PickHistoryPrompt(String),
     /// Toggle collapse state for a tool message bubble
     ToggleToolCollapse(String),
     /// Dismiss the error notification
     DismissError,
     /// Toggle error toast expand/collapse
     ToggleErrorExpand,
+    /// Restore the file a write/edit tool changed (snapshot id)
+    UndoEdit(String),
+    /// Result of restoring a snapshot: snapshot id and restored path or error
+    EditUndone(String, Result<String, String>),
     /// Copy message content to clipboard
     CopyToClipboard(String),
+    /// Ask to remove a message of the current session and everything after it
+    DeleteFromHere(usize),
+    /// Carry out the pending "Delete from here"
+    ConfirmDeleteFromHere,
+    /// Keep the messages after all
+    CancelDeleteFromHere,
+    /// Copy the current conversation to the clipboard as Markdown
+    CopyConversation,
+    /// Save the current conversation as a self-contained HTML page
+    ExportConversationHtml,
+    /// Switch the current session between the chat and the raw transcript
+    ToggleRawTranscript,
+    /// Result of writing an HTML export: saved path, or an error
+    ConversationExported(Option<Result<PathBuf, String>>),
     /// Clear the current chat session
     ClearChat,
     /// Toggle the directory popup visibility
     ToggleDirectoryPopup,
+    /// Toggle the context budget panel visibility
+    ToggleContextPanel,
+    /// Include git status and recent commits in the system prompt or not
+    ToggleGitContext,
+    /// Show/hide the log viewer
+    ToggleLogViewer,
+    /// Toggle the outline of the current conversation
+    ToggleOutline,
+    /// Open a file read by a tool call in the preview pane
+    OpenFilePreview(PathBuf),
+    /// Close the file preview pane
+    CloseFilePreview,
+    /// Selection and scrolling in the file preview
+    FilePreviewAction(text_editor::Action),
+    /// Generate a commit message for the staged changes
+    GenerateCommitMessage,
+    /// Generated commit message, or why it couldn't be generated
+    CommitMessageGenerated(Result<String, String>),
+    /// Edits to the commit message draft
+    CommitDraftAction(text_editor::Action),
+    /// Run `git commit` with the draft
+    CommitStaged,
+    /// `git commit` finished with its summary line or error
+    CommitFinished(Result<String, String>),
+    /// Dismiss the commit message draft
+    CloseCommitDraft,
+    /// Preview applying a code block (message index, block index) to its file
+    PreviewApply(usize, usize),
+    /// Apply the previewed code block
+    ConfirmApply,
+    /// The edit finished with the tool's message or error
+    ApplyFinished(Result<String, String>),
+    /// Discard the previewed code block
+    CancelApply,
+    /// Take a screenshot of a region the user selects and attach it to the next prompt
+    CaptureScreen,
+    /// Screenshot saved to the given path, or why it couldn't be taken
+    ScreenCaptured(Result<PathBuf, String>),
+    /// Ctrl+V in the chat input: attach a clipboard image, or paste its text
+    PasteIntoDraft,
+    /// Pasted clipboard image saved to the given path, or why it couldn't be
+    ImagePasted(Result<PathBuf, String>),
+    /// Remove an attached screenshot before sending
+    RemovePendingImage(usize),
+    /// Review the working tree changes
+    ReviewChanges,
+    /// Review report, or why the review couldn't run
+    ReviewFinished(Result<ReviewReport, String>),
+    /// Close the review report
+    CloseReview,
+    /// Open or close the model compare view
+    ToggleCompare,
+    /// Prompt typed into the compare view
+    ComparePromptChanged(String),
+    /// Provider of a compare column
+    CompareProviderChanged(usize, String),
+    /// Model of a compare column
+    CompareModelChanged(usize, String),
+    /// Send the compare prompt to both models
+    RunCompare,
+    /// Answers and measurements of both models
+    CompareFinished(Vec<Comparison>),
+    /// Make a compare column's model the default
+    UseComparedModel(usize),
+    /// Toggle the temperature/max tokens popover next to the model pill
+    ToggleSessionTuning,
+    /// Temperature for the current session only
+    SessionTemperatureChanged(f32),
+    /// Max tokens for the current session only
+    SessionMaxTokensChanged(u32),
+    /// Drop the current session's overrides and use the configured values
+    ResetSessionTuning,
+    /// Scroll the chat to a message picked in the outline
+    JumpToMessage(usize),
+    /// Change the least severe level shown in the log viewer
+    LogLevelFilterChanged(String),
+    /// Clear the log viewer buffer
+    ClearLogs,
+    /// Show/hide the developer event inspector
+    ToggleInspector,
+    /// Move keyboard focus to the next field (Tab)
+    FocusNext,
+    /// Move keyboard focus to the previous field (Shift+Tab)
+    FocusPrevious,
+    /// Move the message focus by this many bubbles (negative is up); starts
+    /// at the latest message
+    MoveMessageFocus(isize),
+    /// Expand or collapse the focused tool or thinking bubble
+    ToggleFocusedMessage,
+    /// Copy the focused message to the clipboard
+    CopyFocusedMessage,
+    /// Leave message navigation
+    ClearMessageFocus,
+    /// A navigation key pressed outside any widget, run only while a message
+    /// has the focus (Esc also closes the prompt search)
+    FocusedMessageKey(Box<Message>),
+    /// Change which event source the inspector shows
+    InspectorFilterChanged(String),
+    /// Expand or collapse an inspector entry's body
+    InspectorEntryToggled(usize),
+    /// Lift the budget for one run and resume the stopped session
+    ContinueAnyway,
+    /// Hide the budget limit notice
+    DismissBudgetNotice,
+    /// Prompt or stop request from a WebSocket or D-Bus client
+    Remote(RemoteCommand),
+    /// The manifest watcher finished checking the key project files off the
+    /// UI thread; it comes back with the re-detected project if they changed
+    ManifestChecked(Option<(ManifestWatcher, Option<DetectedProject>)>),
+    /// The system prompt was rebuilt off the UI thread after git changes
+    BackendPrepared(Result<PreparedBackend, String>),
+    /// Usage store summary finished loading for the Stats page
+    UsageSummaryLoaded(UsageSummary),
+    /// Documents of the active workspace finished loading for the Knowledge page
+    KnowledgeLoaded(Vec<DocumentInfo>),
+    /// Open a file picker to attach a document to the workspace
+    AddKnowledgeDocument,
+    /// Handle the result from the document picker
+    KnowledgeDocumentPicked(Option<PathBuf>),
+    /// A document was chunked and stored, or failed to
+    KnowledgeDocumentAdded(Result<DocumentInfo, String>),
+    /// Remove a document (by id) from the workspace
+    RemoveKnowledgeDocument(String),
     /// Open native file picker to select a directory
     OpenDirectoryPicker,
     /// Handle the result from the directory picker
@@ -209,6 +755,14 @@ enum Message {
     ChangeDirectory,
     /// Select a recent directory from the popup
     SelectRecentDirectory(PathBuf),
+    /// Pin or unpin a directory in the directory popup
+    ToggleDirectoryBookmark(PathBuf),
+    /// Switch to another workspace
+    SelectWorkspace(Workspace),
+    /// Pick a project directory to add as a workspace
+    AddWorkspace,
+    /// Directory picked for a new workspace
+    WorkspacePicked(Option<PathBuf>),
     /// Close the directory popup
     CloseDirectoryPopup,
     /// Toggle conversations sidebar
@@ -217,14 +771,72 @@ enum Message {
     LoadConversation(uuid::Uuid),
     /// Delete a conversation by ID
     DeleteConversation(uuid::Uuid),
+    /// Ask to delete several conversations at once
+    DeleteConversations(Vec<uuid::Uuid>),
+    /// Carry out the pending bulk delete
+    ConfirmDeleteConversations,
+    /// Keep the conversations after all
+    CancelDeleteConversations,
+    /// Pin (true) or unpin conversations
+    PinConversations(Vec<uuid::Uuid>, bool),
+    /// Archive (true) or restore conversations
+    ArchiveConversations(Vec<uuid::Uuid>, bool),
+    /// Open or close the archive section of the sidebar
+    ToggleArchivedConversations,
+    /// Enter or leave multi-select in the conversations sidebar
+    ToggleConversationSelection,
+    /// Check or uncheck a conversation for a bulk action
+    ConversationSelected(uuid::Uuid, bool),
+    /// Check every listed conversation
+    SelectAllConversations,
+    /// Tag draft in the bulk action bar changed
+    ConversationTagDraftChanged(String),
+    /// Add the drafted tag to the selected conversations
+    TagSelectedConversations,
+    /// Write the selected conversations as Markdown files into a folder
+    ExportSelectedConversations,
+    /// Result of a bulk export: folder and number of files written; `None` if cancelled
+    ConversationsExported(Option<Result<(PathBuf, usize), String>>),
     /// Refresh the conversations list
     RefreshConversations,
+    /// Load the next page of conversations
+    LoadMoreConversations,
+    /// Conversations sidebar scrolled; loads more near the bottom
+    ConversationsScrolled(scrollable::Viewport),
+    /// Chat message list scrolled
+    ChatScrolled(scrollable::Viewport),
+    /// Scroll the chat to the latest message and follow it again
+    JumpToLatest,
     /// Close conversations sidebar
     CloseConversations,
     /// Initialize project with AI (enhance PROJECT.manifest)
     InitializeProjectWithAI,
     /// Change theme mode (Light, Dark, Black)
     ThemeModeChanged(String),
+    /// Setup wizard: provider picked
+    OnboardingProviderSelected(String),
+    /// Setup wizard: API key edited
+    OnboardingApiKeyChanged(String),
+    /// Setup wizard: API URL edited
+    OnboardingApiUrlChanged(String),
+    /// Setup wizard: send a test request with the entered key
+    OnboardingTestConnection,
+    /// Setup wizard: test request answered, or why it failed
+    OnboardingTested(Result<String, String>),
+    /// Setup wizard: model picked or typed
+    OnboardingModelSelected(String),
+    /// Setup wizard: theme picked (applied right away as a preview)
+    OnboardingThemeSelected(ThemeMode),
+    /// Setup wizard: go to the previous step
+    OnboardingBack,
+    /// Setup wizard: go to the next step, or save on the last one
+    OnboardingNext,
+    /// Setup wizard: close it without saving
+    OnboardingSkip,
+    /// Poll the OS light/dark preference (theme mode Auto)
+    CheckSystemTheme,
+    /// The OS prefers a dark (true) or light color scheme
+    SystemThemeDetected(bool),
     /// Theme submenu selection (Dark/Black)
     ThemeSubmenuChanged(String),
     /// Click on a conversation starter to use it
@@ -244,6 +856,15 @@ fn input_id() -> iced::widget::Id {
     iced::widget::Id::new("chat-input")
 }
 
+/// Chat message list ID for scroll management
+fn prompt_search_id() -> iced::widget::Id {
+    iced::widget::Id::new("prompt-search")
+}
+
+fn chat_scroll_id() -> iced::widget::Id {
+    iced::widget::Id::new("chat-scroll")
+}
+
 /// Build enhanced system prompt
 /// Note: PROJECT.manifest context is handled by arula_core's build_system_prompt()
 fn build_enhanced_system_prompt(base_prompt: &str) -> String {
@@ -251,13 +872,132 @@ fn build_enhanced_system_prompt(base_prompt: &str) -> String {
     base_prompt.to_string()
 }
 
+/// Parses markdown on a blocking worker thread and reports back with `Message::MarkdownParsed`
+fn parse_markdown_task(key: String, revision: u64, content: String) -> Task<Message> {
+    Task::perform(
+        async move {
+            tokio::task::spawn_blocking(move || markdown::parse(&content).collect::<Vec<_>>())
+                .await
+                .unwrap_or_default()
+        },
+        move |items| Message::MarkdownParsed {
+            key: key.clone(),
+            revision,
+            items,
+        },
+    )
+}
+
+/// Global keyboard shortcuts
+fn shortcut_message(
+    event: iced::Event,
+    status: iced::event::Status,
+    _window: window::Id,
+) -> Option<Message> {
+    use iced::keyboard::{self, key::Named, Key};
+    match event {
+        // Ctrl+Tab switches tabs, even while the chat input has focus
+        iced::Event::Keyboard(keyboard::Event::KeyPressed {
+            key: Key::Named(Named::Tab),
+            modifiers,
+            ..
+        }) if modifiers.control() => Some(Message::CycleTab(if modifiers.shift() { -1 } else { 1 })),
+        // Tab order for keyboard users; only when no widget used the key
+        iced::Event::Keyboard(keyboard::Event::KeyPressed {
+            key: Key::Named(Named::Tab),
+            modifiers,
+            ..
+        }) if status == iced::event::Status::Ignored && !modifiers.command() => {
+            Some(if modifiers.shift() {
+                Message::FocusPrevious
+            } else {
+                Message::FocusNext
+            })
+        }
+        // Ctrl+R searches the prompt history; the chat input binds it itself
+        iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. })
+            if status == iced::event::Status::Ignored
+                && modifiers.command()
+                && !modifiers.shift()
+                && matches!(key.as_ref(), Key::Character(c) if c.eq_ignore_ascii_case("r")) =>
+        {
+            Some(Message::TogglePromptSearch)
+        }
+        iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. })
+            if modifiers.command()
+                && modifiers.shift()
+                && matches!(key.as_ref(), Key::Character(c) if c.eq_ignore_ascii_case("d")) =>
+        {
+            Some(Message::ToggleInspector)
+        }
+        // Bare keys nobody else used; they only act on a focused message
+        iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. })
+            if status == iced::event::Status::Ignored
+                && message_navigation(&key, modifiers).is_some() =>
+        {
+            message_navigation(&key, modifiers).map(|m| Message::FocusedMessageKey(Box::new(m)))
+        }
+        _ => None,
+    }
+}
+
+/// Keys that move between message bubbles and act on the focused one
+fn message_navigation(key: &iced::keyboard::Key, modifiers: iced::keyboard::Modifiers) -> Option<Message> {
+    use iced::keyboard::{key::Named, Key};
+    if modifiers.command() || modifiers.alt() {
+        return None;
+    }
+    match key.as_ref() {
+        Key::Named(Named::ArrowUp) => Some(Message::MoveMessageFocus(-1)),
+        Key::Named(Named::ArrowDown) => Some(Message::MoveMessageFocus(1)),
+        Key::Named(Named::PageUp) => Some(Message::MoveMessageFocus(-MESSAGE_FOCUS_PAGE)),
+        Key::Named(Named::PageDown) => Some(Message::MoveMessageFocus(MESSAGE_FOCUS_PAGE)),
+        Key::Named(Named::Enter) => Some(Message::ToggleFocusedMessage),
+        Key::Named(Named::Escape) => Some(Message::ClearMessageFocus),
+        Key::Character(c) if c.eq_ignore_ascii_case("c") => Some(Message::CopyFocusedMessage),
+        _ => None,
+    }
+}
+
+/// Deletes saved conversations older than the configured retention
+fn apply_conversation_retention(manager: &ConversationManager, config: &Config) {
+    let Some(days) = config.get_conversation_retention_days() else {
+        return;
+    };
+    match manager.delete_older_than(days) {
+        Ok(0) => {}
+        Ok(deleted) => tracing::info!("Deleted {} conversations older than {} days", deleted, days),
+        Err(err) => tracing::warn!("Failed to apply conversation retention: {}", err),
+    }
+}
+
+/// Model used for new streams: the active persona's, else the project
+/// override, else the global model
+fn effective_model(config: &Config, project: Option<&ProjectConfig>) -> String {
+    config
+        .get_active_persona()
+        .and_then(|p| p.model)
+        .filter(|m| !m.trim().is_empty())
+        .or_else(|| project.and_then(|p| p.model.clone()))
+        .filter(|m| !m.trim().is_empty())
+        .unwrap_or_else(|| config.get_model())
+}
+
+/// Temperature for new streams: the session's own, else the active persona's,
+/// else the one from the settings
+fn effective_temperature(session: Option<f32>, config: &Config, form: &ConfigForm) -> f32 {
+    session
+        .or_else(|| config.get_active_persona().and_then(|p| p.temperature))
+        .unwrap_or(form.temperature)
+}
+
 impl App {
     /// Initializes the application. Shows error dialog if initialization fails.
     fn init() -> (Self, Task<Message>) {
         match Self::try_init() {
             Ok(app) => Self::init_with_starters(app),
             Err(err) => {
-                eprintln!("Initialization error: {err}");
+                tracing::error!("Initialization error: {err}");
                 (Self::error_state(err.to_string()), Task::none())
             }
         }
@@ -269,7 +1009,14 @@ impl App {
         let _ = arula_core::utils::logger::init_global_logger();
 
         let config = Config::load_or_default()?;
-        let dispatcher = Dispatcher::new(&config)?;
+        let mut dispatcher = Dispatcher::new(&config)?;
+        if let Some(options) = WebSocketOptions::from_config(&config) {
+            if let Err(e) = dispatcher.serve_websocket(options) {
+                tracing::warn!("Failed to start WebSocket event API: {}", e);
+            }
+        }
+        #[cfg(target_os = "linux")]
+        dispatcher.serve_dbus();
         let config_form = ConfigForm::from_config(&config);
         let session = Session::new();
 
@@ -285,20 +1032,28 @@ impl App {
         };
 
         let theme_mode = config_form.theme_mode;
+        let recent_directories = config.get_recent_directories();
+        let workspaces: Vec<Workspace> = std::iter::once(Workspace::default_workspace())
+            .chain(config.get_workspaces())
+            .collect();
+        let bookmarked_directories = config.get_bookmarked_directories();
 
         Ok(Self {
             dispatcher,
             sessions: vec![session],
             current: 0,
-            draft: String::new(),
+            draft: text_editor::Content::new(),
             config,
             config_form,
             bg_state: LivingBackgroundState::default(),
             bg_opacity,
+            window_focused: true,
+            window_minimized: false,
             menu_state: LiquidMenuState::default(),
             settings_state: SettingsMenuState::default(),
             tilt_cards,
             init_error: None,
+            onboarding: (!Config::exists()).then(|| Onboarding::new(&config.active_provider)),
             message_editors: HashMap::new(),
             model_list: Vec::new(),
             models_loading: false,
@@ -310,38 +1065,103 @@ impl App {
                 accent_color: Color::from_rgba(0.6, 0.6, 0.6, 1.0),
             },
             markdown_cache: HashMap::new(),
+            markdown_streams: HashMap::new(),
+            markdown_pending: HashMap::new(),
+            markdown_revision: 0,
+            markdown_dirty: HashSet::new(),
+            last_stream_render: Instant::now(),
             tool_args_cache: HashMap::new(),
+            raw_transcript_cache: Vec::new(),
+            delete_from_pending: None,
             tool_animations: HashMap::new(),
+            chat_at_bottom: true,
+            chat_seen_messages: 0,
             stream_error: None,
             error_expanded: false,
             bash_output_lines: HashMap::new(),
             current_directory: std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/")),
             show_directory_popup: false,
+            show_context_panel: false,
+            show_log_viewer: false,
+            show_outline: false,
+            show_session_tuning: false,
+            file_preview: None,
+            commit_draft: None,
+            review: None,
+            compare: None,
+            pending_apply: None,
+            pending_images: Vec::new(),
+            context_estimate: None,
+            compacting_history: HashSet::new(),
+            queued_prompts: Vec::new(),
+            offline_retries: HashMap::new(),
+            provider_check_generation: 0,
+            update_available: None,
+            crash_reports: crash_report::unseen(),
+            focused_message: None,
+            last_partial_save: HashMap::new(),
+            expanded_tool_groups: HashSet::new(),
+            capturing_screen: false,
+            log_records: Vec::new(),
+            log_level_filter: "info".to_string(),
+            last_log_refresh: Instant::now(),
+            show_inspector: false,
+            budget_notice: None,
+            budget_override: None,
+            retry_notice: None,
+            inspector_snapshot: InspectorSnapshot::default(),
+            inspector_filter: "all".to_string(),
+            inspector_expanded: HashSet::new(),
+            last_inspector_refresh: Instant::now(),
+            usage_summary: None,
+            knowledge_documents: None,
+            knowledge_adding: None,
+            knowledge_error: None,
             show_directory_custom_input: false,
             directory_draft: String::new(),
-            recent_directories: Vec::new(),
-            conversation_manager: ConversationManager::new()?,
+            recent_directories,
+            bookmarked_directories,
+            workspaces,
+            active_workspace: 0,
+            parked_sessions: HashMap::new(),
+            conversation_manager: {
+                let manager = ConversationManager::new()?;
+                apply_conversation_retention(&manager, &config);
+                manager
+            },
+            partial_saver: PartialSaver::new(ConversationManager::new()?),
             saved_conversations: Vec::new(),
+            pinned_conversations: Vec::new(),
+            archived_conversations: Vec::new(),
+            archived_total: 0,
+            show_archived: false,
+            selecting_conversations: false,
+            selected_conversations: HashSet::new(),
+            delete_conversations_pending: None,
+            conversation_tag_draft: String::new(),
+            conversations_total: 0,
+            conversations_next_offset: 0,
             show_conversations: false,
             conversations_sidebar_animation: 0.0,
             conversations_layout_offset: 0.0,
             clipboard: arboard::Clipboard::new().ok(),
             custom_model_draft: String::new(),
+            ollama_models: None,
+            ollama_pull_draft: String::new(),
+            ollama_pull: None,
+            ollama_delete_confirm: None,
             theme_mode,
+            system_dark: system_prefers_dark(),
+            provider_health: None,
+            provider_last_error: None,
             detected_project: {
                 // Detect project on startup
                 let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
                 let detected = detect_project(&cwd);
                 
-                // Create manifest if it doesn't exist
-                if detected.is_some() {
-                    let manifest_path = cwd.join("PROJECT.manifest");
-                    if !manifest_path.exists() {
-                        if let Some(ref project) = detected {
-                            let content = generate_auto_manifest(project);
-                            let _ = std::fs::write(&manifest_path, content);
-                        }
-                    }
+                // Create the manifest or refresh its auto-generated section
+                if let Some(ref project) = detected {
+                    let _ = refresh_manifest(&cwd, project);
                 }
                 detected
             },
@@ -349,17 +1169,53 @@ impl App {
                 let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
                 is_ai_enhanced(&cwd.join("PROJECT.manifest"))
             },
+            manifest_watcher: std::env::current_dir().ok().map(|cwd| {
+                ManifestWatcher::new(&cwd, Duration::from_millis(MANIFEST_POLL_INTERVAL_MS))
+            }),
+            git_info: std::env::current_dir().ok().and_then(|cwd| GitInfo::read(&cwd)),
+            git_watcher: std::env::current_dir().ok().map(|cwd| {
+                GitWatcher::new(&cwd, Duration::from_millis(GIT_POLL_INTERVAL_MS))
+            }),
+            git_rebuild_at: None,
+            project_config: ProjectConfig::load_from_current_dir(),
             conversation_starters: Vec::new(),
             pending_question_batches: Vec::new(),
             input_bar_height_spring: Spring::default(),
             question_answer_drafts: std::collections::HashMap::new(),
+            prompt_history: prompt_history::load(),
+            prompt_recall: Recall::default(),
+            prompt_search: None,
+            draft_edited: None,
+            spellcheck: None,
+            spelling: None,
+            spelling_ignored: HashSet::new(),
         })
     }
 
     /// Post-initialization hook to start loading conversation starters
-    fn init_with_starters(app: Self) -> (Self, Task<Message>) {
-        // Trigger async fetch of conversation starters (don't show until received)
-        app.dispatcher.generate_conversation_starters();
+    fn init_with_starters(mut app: Self) -> (Self, Task<Message>) {
+        // Trigger async fetch of conversation starters (don't show until received);
+        // on first launch there is no provider to ask until setup is done
+        if app.onboarding.is_none() {
+            app.restore_interrupted_conversation();
+            app.restore_latest_draft();
+            app.dispatcher.generate_conversation_starters();
+            let spellcheck = app.load_dictionary();
+            let config = app.config.clone();
+            let update_check = Task::perform(
+                async move { updates::check(&config).await.map_err(|e| format!("{e:#}")) },
+                Message::UpdateChecked,
+            );
+            return (
+                app,
+                Task::batch([
+                    iced::widget::operation::focus(input_id()),
+                    Task::done(Message::PingProvider),
+                    update_check,
+                    spellcheck,
+                ]),
+            );
+        }
         (app, iced::widget::operation::focus(input_id()))
     }
 
@@ -368,17 +1224,20 @@ impl App {
             dispatcher: Dispatcher::new(&Config::default()).unwrap(),
             sessions: vec![Session::new()],
             current: 0,
-            draft: String::new(),
+            draft: text_editor::Content::new(),
             config: Config::default(),
             config_form: ConfigForm::from_config(&Config::default()),
             bg_state: LivingBackgroundState::default(),
             bg_opacity: 1.0,
+            window_focused: true,
+            window_minimized: false,
             menu_state: LiquidMenuState::default(),
             settings_state: SettingsMenuState::default(),
             tilt_cards: (0..TILT_CARD_COUNT)
                 .map(|_| TiltCardState::default())
                 .collect(),
             init_error: Some(error),
+            onboarding: None,
             message_editors: HashMap::new(),
             model_list: Vec::new(),
             models_loading: false,
@@ -390,102 +1249,411 @@ impl App {
                 accent_color: Color::from_rgba(0.6, 0.6, 0.6, 1.0),
             },
             markdown_cache: HashMap::new(),
+            markdown_streams: HashMap::new(),
+            markdown_pending: HashMap::new(),
+            markdown_revision: 0,
+            markdown_dirty: HashSet::new(),
+            last_stream_render: Instant::now(),
             tool_args_cache: HashMap::new(),
+            raw_transcript_cache: Vec::new(),
+            delete_from_pending: None,
             tool_animations: HashMap::new(),
+            chat_at_bottom: true,
+            chat_seen_messages: 0,
             stream_error: None,
             error_expanded: false,
             bash_output_lines: HashMap::new(),
             current_directory: std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/")),
             show_directory_popup: false,
+            show_context_panel: false,
+            show_log_viewer: false,
+            show_outline: false,
+            show_session_tuning: false,
+            file_preview: None,
+            commit_draft: None,
+            review: None,
+            compare: None,
+            pending_apply: None,
+            pending_images: Vec::new(),
+            context_estimate: None,
+            compacting_history: HashSet::new(),
+            queued_prompts: Vec::new(),
+            offline_retries: HashMap::new(),
+            provider_check_generation: 0,
+            update_available: None,
+            crash_reports: Vec::new(),
+            focused_message: None,
+            last_partial_save: HashMap::new(),
+            expanded_tool_groups: HashSet::new(),
+            capturing_screen: false,
+            log_records: Vec::new(),
+            log_level_filter: "info".to_string(),
+            last_log_refresh: Instant::now(),
+            show_inspector: false,
+            budget_notice: None,
+            budget_override: None,
+            retry_notice: None,
+            inspector_snapshot: InspectorSnapshot::default(),
+            inspector_filter: "all".to_string(),
+            inspector_expanded: HashSet::new(),
+            last_inspector_refresh: Instant::now(),
+            usage_summary: None,
+            knowledge_documents: None,
+            knowledge_adding: None,
+            knowledge_error: None,
             show_directory_custom_input: false,
             directory_draft: String::new(),
             recent_directories: Vec::new(),
+            bookmarked_directories: Vec::new(),
+            workspaces: vec![Workspace::default_workspace()],
+            active_workspace: 0,
+            parked_sessions: HashMap::new(),
             conversation_manager: ConversationManager::new().unwrap_or_else(|_| {
                 // If we can't create the conversation manager, just use a dummy one
                 // This shouldn't happen in normal circumstances
                 panic!("Failed to create conversation manager")
             }),
+            partial_saver: PartialSaver::new(
+                ConversationManager::new().expect("Failed to create conversation manager"),
+            ),
             saved_conversations: Vec::new(),
+            pinned_conversations: Vec::new(),
+            archived_conversations: Vec::new(),
+            archived_total: 0,
+            show_archived: false,
+            selecting_conversations: false,
+            selected_conversations: HashSet::new(),
+            delete_conversations_pending: None,
+            conversation_tag_draft: String::new(),
+            conversations_total: 0,
+            conversations_next_offset: 0,
             show_conversations: false,
             conversations_sidebar_animation: 0.0,
             conversations_layout_offset: 0.0,
             clipboard: arboard::Clipboard::new().ok(),
             custom_model_draft: String::new(),
+            ollama_models: None,
+            ollama_pull_draft: String::new(),
+            ollama_pull: None,
+            ollama_delete_confirm: None,
             theme_mode: ThemeMode::default(),
+            system_dark: true,
+            provider_health: None,
+            provider_last_error: None,
             detected_project: None,
             manifest_is_ai_enhanced: false,
+            manifest_watcher: None,
+            git_info: None,
+            git_watcher: None,
+            git_rebuild_at: None,
+            project_config: None,
             conversation_starters: Vec::new(),
             pending_question_batches: Vec::new(),
             input_bar_height_spring: Spring::default(),
             question_answer_drafts: std::collections::HashMap::new(),
+            prompt_history: Vec::new(),
+            prompt_recall: Recall::default(),
+            prompt_search: None,
+            draft_edited: None,
+            spellcheck: None,
+            spelling: None,
+            spelling_ignored: HashSet::new(),
         }
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
-        match message {
-            Message::DraftChanged(s) => self.draft = s,
-            Message::SendPrompt => {
-                if let Some(session) = self.sessions.get_mut(self.current) {
-                    if session.is_streaming {
-                        return Task::none();
-                    }
-                    let prompt = std::mem::take(&mut self.draft);
-                    if prompt.trim().is_empty() {
-                        return Task::none();
-                    }
-
-                    session.add_user_message(prompt.clone(), Utc::now().to_rfc3339());
-
-                    // Sync editor content for the new message
-                    let msg_idx = session.messages.len() - 1;
-                    let key = format!("{}:{}", self.current, msg_idx);
-                    self.message_editors.insert(
-                        key,
-                        text_editor::Content::with_text(&session.messages[msg_idx].content),
-                    );
+        let task = self.handle_message(message);
+        self.refresh_raw_transcript();
+        task
+    }
 
-                    session.set_streaming(true);
+    /// Once the poll interval has passed, hand the manifest watcher to a
+    /// blocking task: finding subprojects and statting their key files is too
+    /// slow for the tick
+    fn poll_manifest(&mut self) -> Task<Message> {
+        if !self.manifest_watcher.as_mut().is_some_and(|w| w.is_due()) {
+            return Task::none();
+        }
+        let Some(mut watcher) = self.manifest_watcher.take() else {
+            return Task::none();
+        };
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    let project = watcher.check_and_refresh();
+                    (watcher, project)
+                })
+                .await
+                .ok()
+            },
+            Message::ManifestChecked,
+        )
+    }
 
-                    let session_config = SessionConfig {
-                        system_prompt: build_enhanced_system_prompt(
-                            &self.config_form.system_prompt,
-                        ),
-                        model: self.config.get_model(),
-                        max_tokens: self.config_form.max_tokens as u32,
-                        temperature: self.config_form.temperature,
-                    };
+    /// Pretty-print the history messages of the raw transcript that changed
+    /// since the last update; the cache is dropped while the view is hidden
+    fn refresh_raw_transcript(&mut self) {
+        let Some(session) = self.sessions.get(self.current).filter(|s| s.show_raw) else {
+            self.raw_transcript_cache.clear();
+            return;
+        };
+        let history = session.get_chat_history();
+        self.raw_transcript_cache.truncate(history.len());
+        for (i, message) in history.into_iter().enumerate() {
+            if self.raw_transcript_cache.get(i).is_some_and(|(cached, _)| *cached == message) {
+                continue;
+            }
+            let body = serde_json::to_string_pretty(&message).unwrap_or_default();
+            if i < self.raw_transcript_cache.len() {
+                self.raw_transcript_cache[i] = (message, body);
+            } else {
+                self.raw_transcript_cache.push((message, body));
+            }
+        }
+    }
 
-                    // Get conversation history for context (excluding the current prompt which is included separately)
-                    let history = session.get_chat_history();
-                    let history_opt = if history.is_empty() {
-                        None
-                    } else {
-                        Some(history)
-                    };
+    fn handle_message(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::DraftAction(action) => {
+                let is_edit = action.is_edit();
+                self.draft.perform(action);
+                if is_edit {
+                    self.focused_message = None;
+                    self.draft_edited = Some(Instant::now());
+                    self.refresh_context_estimate();
+                    self.refresh_spelling();
+                }
+            }
+            Message::SendPrompt => {
+                let Some(session) = self.sessions.get(self.current) else {
+                    return Task::none();
+                };
+                if session.is_streaming {
+                    return Task::none();
+                }
+                let mut prompt = self.draft.text();
+                if prompt.trim().is_empty() && self.pending_images.is_empty() {
+                    return Task::none();
+                }
+                self.draft = text_editor::Content::new();
+                self.draft_edited = None;
+                drafts::save(session.id, "");
+                self.context_estimate = None;
+                self.spelling = None;
+                self.prompt_recall.reset();
+                if !prompt.trim().is_empty() {
+                    prompt_history::record(&prompt);
+                    self.prompt_history.push(prompt.clone());
+                }
+                let images = std::mem::take(&mut self.pending_images);
+                if prompt.trim().is_empty() {
+                    prompt = "What's in this screenshot?".to_string();
+                }
 
-                    if let Err(err) = self.dispatcher.start_stream(
-                        session.id,
+                // Offline, or behind earlier queued prompts of this session: wait in line
+                let session_id = session.id;
+                // A new prompt supersedes the offer to continue a cut-off answer
+                if let Some(session) = self.sessions.get_mut(self.current) {
+                    session.interrupted = false;
+                }
+                if self.is_offline() || self.queued_prompts.iter().any(|q| q.session_id == session_id) {
+                    self.queued_prompts.push(QueuedPrompt {
+                        session_id,
                         prompt,
-                        history_opt,
-                        session_config,
-                    ) {
-                        eprintln!("dispatch error: {err}");
-                        session.set_streaming(false);
-                    }
+                        images,
+                        not_before: None,
+                    });
+                    self.dispatch_queued_prompts();
+                    return iced::widget::operation::focus(input_id());
+                }
+                self.dispatch_prompt(self.current, prompt, images);
+                // Re-focus input after sending and follow the new answer
+                return Task::batch([iced::widget::operation::focus(input_id()), self.jump_to_latest()]);
+            }
+            Message::RecallPrompt(older) => {
+                let Some(session) = self.sessions.get(self.current) else {
+                    return Task::none();
+                };
+                let entries = prompt_history::merged(&self.prompt_history, &session.user_prompts());
+                if let Some(prompt) = self.prompt_recall.step(&entries, older) {
+                    self.set_draft(&prompt);
                 }
-                // Re-focus input after sending
+            }
+            Message::TogglePromptSearch => {
+                if self.prompt_search.take().is_some() {
+                    return iced::widget::operation::focus(input_id());
+                }
+                self.prompt_search = Some(String::new());
+                return iced::widget::operation::focus(prompt_search_id());
+            }
+            Message::PromptSearchChanged(query) => {
+                self.prompt_search = Some(query);
+            }
+            Message::PickHistoryPrompt(prompt) => {
+                self.prompt_search = None;
+                self.prompt_recall.reset();
+                self.set_draft(&prompt);
                 return iced::widget::operation::focus(input_id());
             }
+            Message::RemoveQueuedPrompt(idx) => {
+                if idx < self.queued_prompts.len() {
+                    self.queued_prompts.remove(idx);
+                }
+            }
             Message::Received(ev) => return self.handle_ui_event(ev),
             Message::NewTab => {
+                self.stash_draft();
                 self.sessions.push(Session::new());
                 self.current = self.sessions.len() - 1;
+                self.restore_draft();
                 // Fetch conversation starters for the new session
                 self.dispatcher.generate_conversation_starters();
             }
+            Message::SwitchTab(idx) => {
+                let Some(session) = self.sessions.get_mut(idx) else {
+                    return Task::none();
+                };
+                session.unread = false;
+                if let Some(err) = session.background_error.take() {
+                    self.stream_error = Some(err);
+                }
+                self.stash_draft();
+                self.current = idx;
+                self.restore_draft();
+                self.focused_message = None;
+                return Task::batch([iced::widget::operation::focus(input_id()), self.jump_to_latest()]);
+            }
+            Message::CycleTab(step) => {
+                let len = self.sessions.len() as isize;
+                let idx = (self.current as isize + step).rem_euclid(len);
+                return self.update(Message::SwitchTab(idx as usize));
+            }
+            Message::CloseTab(idx) => {
+                let Some(session) = self.sessions.get(idx) else {
+                    return Task::none();
+                };
+                let id = session.id;
+                if session.is_streaming {
+                    self.dispatcher.stop_stream(id);
+                }
+                // A draft of a chat that was never sent can't be reopened
+                let forget_draft = session.messages.is_empty();
+                let tool_call_ids: Vec<String> = session
+                    .messages
+                    .iter()
+                    .filter_map(|m| m.tool_call_id.clone())
+                    .collect();
+
+                // Message caches are keyed by session index, and every tab
+                // after the closed one moves down by one
+                self.drop_message_caches(idx, 0, &tool_call_ids);
+                for later in idx + 1..self.sessions.len() {
+                    self.drop_message_caches(later, 0, &[]);
+                }
+                self.tool_args_cache.remove(&id);
+                self.end_partial_saves(id, true);
+                self.queued_prompts.retain(|q| q.session_id != id);
+                self.offline_retries.remove(&id);
+                self.stash_draft();
+                if forget_draft {
+                    drafts::save(id, "");
+                }
+                self.sessions.remove(idx);
+                if self.sessions.is_empty() {
+                    self.sessions.push(Session::new());
+                }
+                if self.current > idx || self.current == self.sessions.len() {
+                    self.current -= 1;
+                }
+                self.restore_draft();
+                self.focused_message = None;
+
+                let reparse: Vec<Task<Message>> = (idx..self.sessions.len())
+                    .map(|later| self.parse_session_markdown(later))
+                    .collect();
+                return Task::batch(reparse);
+            }
             Message::ToggleSettings => {
                 self.menu_state.open();
                 self.config_form.clear_status();
+                return self.refresh_ollama_models();
+            }
+            Message::RefreshOllamaModels => {
+                return self.refresh_ollama_models();
+            }
+            Message::OllamaModelsLoaded(models) => {
+                self.ollama_models = Some(models);
+            }
+            Message::OllamaPullDraftChanged(draft) => {
+                self.ollama_pull_draft = draft;
+            }
+            Message::PullOllamaModel => {
+                let model = self.ollama_pull_draft.trim().to_string();
+                if model.is_empty() || self.ollama_pull.as_ref().is_some_and(|p| p.running) {
+                    return Task::none();
+                }
+                self.ollama_pull = Some(OllamaPull {
+                    model: model.clone(),
+                    status: "Starting…".to_string(),
+                    completed: 0,
+                    total: 0,
+                    running: true,
+                    error: None,
+                });
+                return Task::run(
+                    ollama::pull_model(self.ollama_base_url(), model),
+                    Message::OllamaPullProgress,
+                );
+            }
+            Message::OllamaPullProgress(event) => {
+                let Some(pull) = &mut self.ollama_pull else {
+                    return Task::none();
+                };
+                match event {
+                    PullEvent::Progress {
+                        status,
+                        completed,
+                        total,
+                    } => {
+                        pull.status = status;
+                        pull.completed = completed;
+                        pull.total = total;
+                    }
+                    PullEvent::Done => {
+                        self.ollama_pull = None;
+                        self.ollama_pull_draft.clear();
+                        // Keep the model selector's cached list in step
+                        self.dispatcher.reload_ollama_models();
+                        return self.refresh_ollama_models();
+                    }
+                    PullEvent::Failed(err) => {
+                        pull.running = false;
+                        pull.error = Some(err);
+                    }
+                }
+            }
+            Message::DeleteOllamaModel(model) => {
+                if self.ollama_delete_confirm.as_ref() != Some(&model) {
+                    self.ollama_delete_confirm = Some(model);
+                    return Task::none();
+                }
+                self.ollama_delete_confirm = None;
+                let base_url = self.ollama_base_url();
+                return Task::perform(
+                    async move {
+                        ollama::delete_model(&base_url, &model)
+                            .await
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::OllamaModelDeleted,
+                );
+            }
+            Message::OllamaModelDeleted(result) => {
+                if let Err(err) = result {
+                    self.config_form.set_error(&err);
+                }
+                self.dispatcher.reload_ollama_models();
+                return self.refresh_ollama_models();
             }
             Message::CloseSettings => {
                 self.menu_state.close();
@@ -500,6 +1668,17 @@ impl App {
                 // Update spinner animation
                 self.spinner_state.tick += 0.016; // ~60fps
 
+                // Save the input once typing pauses
+                if self
+                    .draft_edited
+                    .is_some_and(|at| at.elapsed() >= Duration::from_millis(DRAFT_SAVE_DELAY_MS))
+                {
+                    self.draft_edited = None;
+                    if let Some(session) = self.sessions.get(self.current) {
+                        drafts::save(session.id, &self.draft.text());
+                    }
+                }
+
                 // Animate background opacity based on config
                 // We use the *config* value (saved), not the form value, to drive the actual display
                 let target = if self.config.get_living_background_enabled() {
@@ -517,7 +1696,11 @@ impl App {
 
                 // Poll for cached models if loading
                 if self.models_loading {
-                    let provider = self.config_form.provider.to_lowercase();
+                    let provider = self
+                        .onboarding
+                        .as_ref()
+                        .map_or(&self.config_form.provider, |o| &o.provider)
+                        .to_lowercase();
                     let cached = match provider.as_str() {
                         "openai" => self.dispatcher.get_cached_openai_models(),
                         "anthropic" => self.dispatcher.get_cached_anthropic_models(),
@@ -526,6 +1709,10 @@ impl App {
                             self.dispatcher.get_cached_zai_models()
                         }
                         "openrouter" => self.dispatcher.get_cached_openrouter_models(),
+                        "xai" => self.dispatcher.get_cached_xai_models(),
+                        "deepseek" => self.dispatcher.get_cached_deepseek_models(),
+                        "lm studio" => self.dispatcher.get_cached_lm_studio_models(),
+                        "llamafile" => self.dispatcher.get_cached_llamafile_models(),
                         _ => None,
                     };
                     if let Some(models) = cached {
@@ -539,6 +1726,32 @@ impl App {
                     }
                 }
 
+                // Keep the log viewer current while it is open
+                if self.show_log_viewer
+                    && self.last_log_refresh.elapsed()
+                        >= Duration::from_millis(LOG_VIEWER_REFRESH_MS)
+                {
+                    self.log_records = logging::recent_logs(LOG_VIEWER_LINES);
+                    self.last_log_refresh = Instant::now();
+                }
+
+                if self.show_inspector
+                    && self.last_inspector_refresh.elapsed()
+                        >= Duration::from_millis(INSPECTOR_REFRESH_MS)
+                    && inspector::entry_count()
+                        != self.inspector_snapshot.entries.len() + self.inspector_snapshot.dropped
+                {
+                    self.refresh_inspector();
+                }
+
+                // Render streamed text that arrived since the last batch
+                if !self.markdown_dirty.is_empty()
+                    && self.last_stream_render.elapsed()
+                        >= Duration::from_millis(STREAM_RENDER_INTERVAL_MS)
+                {
+                    self.render_streaming_markdown();
+                }
+
                 // Note: This Tick also drives the message bubble fade-in animations
                 // Iced automatically redraws the view after handling a message
 
@@ -582,6 +1795,28 @@ impl App {
                 
                 // Update input bar height spring for question UI
                 self.input_bar_height_spring.update();
+
+                // Refresh PROJECT.manifest when key project files change
+                let manifest_check = self.poll_manifest();
+
+                // Follow branch switches, commits and staging in the top bar
+                // and the git context of the system prompt
+                if self.git_watcher.as_mut().is_some_and(|w| w.poll()) {
+                    self.git_info = GitInfo::read(&self.current_directory);
+                    if self.config.get_git_context_enabled() {
+                        self.git_rebuild_at =
+                            Some(Instant::now() + Duration::from_millis(GIT_REBUILD_DEBOUNCE_MS));
+                    }
+                }
+                let git_rebuild = match self.git_rebuild_at {
+                    Some(at) if Instant::now() >= at => {
+                        self.git_rebuild_at = None;
+                        Task::perform(self.dispatcher.prepare_backend(&self.config), |prepared| {
+                            Message::BackendPrepared(prepared.map_err(|e| e.to_string()))
+                        })
+                    }
+                    _ => Task::none(),
+                };
                 
                 // Poll for new question batches from QUESTION_HANDLER
                 let pending_batches = QUESTION_HANDLER.get_pending_questions();
@@ -591,9 +1826,9 @@ impl App {
                 
                 for (batch_id, questions) in pending_batches {
                     if !existing_batch_ids.contains(&batch_id) {
-                        eprintln!("🔔 New question batch detected: {} questions", questions.len());
+                        tracing::debug!("New question batch detected: {} questions", questions.len());
                         let items: Vec<PendingQuestionItem> = questions.into_iter().map(|q| {
-                            eprintln!("   - [{}] '{}' options: {:?}", q.id, q.question, q.options);
+                            tracing::debug!("  [{}] '{}' options: {:?}", q.id, q.question, q.options);
                             PendingQuestionItem {
                                 id: q.id,
                                 question: q.question,
@@ -611,17 +1846,46 @@ impl App {
                         self.input_bar_height_spring.set_target(1.0);
                     }
                 }
+                return Task::batch([manifest_check, git_rebuild]);
+            }
+            Message::BackendPrepared(prepared) => match prepared {
+                // Dropped if the backend was rebuilt (settings, directory) meanwhile
+                Ok(prepared) => {
+                    self.dispatcher.install_backend(prepared);
+                }
+                Err(err) => tracing::warn!("Failed to refresh git context: {}", err),
+            },
+            Message::ManifestChecked(checked) => {
+                let Some((watcher, project)) = checked else {
+                    return Task::none();
+                };
+                // A directory switch replaced the watcher while it was out
+                if self.manifest_watcher.is_some() || watcher.root() != self.current_directory {
+                    return Task::none();
+                }
+                self.manifest_watcher = Some(watcher);
+                if let Some(project) = project {
+                    let manifest_path = self.current_directory.join("PROJECT.manifest");
+                    self.manifest_is_ai_enhanced = is_ai_enhanced(&manifest_path);
+                    self.detected_project = Some(project);
+                    // Rebuild the system prompt with the updated manifest
+                    if let Err(err) = self.dispatcher.update_backend(&self.config) {
+                        tracing::warn!("Failed to reload project manifest: {}", err);
+                    }
+                }
             }
             Message::ConfigProviderChanged(provider) => {
                 // Use switch_provider to automatically set defaults (API URL, model)
                 if let Err(e) = self.config.switch_provider(&provider) {
-                    eprintln!("Provider switch error: {e}");
+                    tracing::error!("Provider switch error: {e}");
                 }
                 // Refresh form from updated config to show new defaults
                 let options = collect_provider_options(&self.config);
                 self.config_form = ConfigForm::from_config(&self.config);
                 self.config_form.provider = provider;
                 self.config_form.provider_options = options;
+                self.ollama_models = None;
+                return self.refresh_ollama_models();
             }
             Message::ConfigModelChanged(model) => {
                 self.config_form.model = model;
@@ -671,6 +1935,103 @@ impl App {
             Message::ConfigLivingBackgroundToggled(on) => {
                 self.config_form.living_background_enabled = on;
             }
+            Message::ConfigSpellcheckToggled(on) => {
+                self.config_form.spellcheck_enabled = on;
+            }
+            Message::DictionaryLoaded(dictionary) => {
+                if dictionary.is_none() {
+                    tracing::warn!(
+                        "No {} spellcheck dictionary found",
+                        self.config.get_spellcheck_language()
+                    );
+                }
+                self.spellcheck = dictionary.filter(|_| self.config.get_spellcheck_enabled());
+                self.refresh_spelling();
+            }
+            Message::ApplySpelling(replacement) => {
+                let Some(fix) = self.spelling.take() else {
+                    return Task::none();
+                };
+                let mut text = self.draft.text();
+                if text.get(fix.range.clone()) == Some(fix.word.as_str()) {
+                    text.replace_range(fix.range, &replacement);
+                    self.set_draft(&text);
+                    self.draft_edited = Some(Instant::now());
+                }
+                return iced::widget::operation::focus(input_id());
+            }
+            Message::IgnoreSpelling => {
+                if let Some(fix) = self.spelling.take() {
+                    self.spelling_ignored.insert(fix.word);
+                }
+            }
+            Message::ConfigUpdateCheckToggled(on) => {
+                self.config_form.update_check_enabled = on;
+            }
+            Message::ConfigRetentionDaysChanged(days) => {
+                self.config_form.conversation_retention_days = days;
+            }
+            Message::ConfigToolVerbosityChanged(verbosity) => {
+                self.config_form.tool_verbosity = verbosity;
+            }
+            Message::PersonaSelected(name) => {
+                let name = (name != NO_PERSONA).then_some(name);
+                if let Err(err) = self.config.set_active_persona(name.as_deref()) {
+                    tracing::warn!("Failed to save persona: {}", err);
+                }
+                if let Err(err) = self.dispatcher.update_backend(&self.config) {
+                    tracing::warn!("Failed to apply persona: {}", err);
+                }
+                self.refresh_context_estimate();
+            }
+            Message::ConfigStarterModeChanged(mode) => {
+                self.config_form.starter_mode = mode;
+            }
+            Message::ConfigPinnedStartersChanged(starters) => {
+                self.config_form.pinned_starters = starters;
+            }
+            Message::UpdateChecked(result) => match result {
+                Ok(release) => {
+                    if let Some(release) = &release {
+                        tracing::info!("Update available: {}", release.version);
+                    }
+                    self.update_available = release;
+                }
+                Err(err) => tracing::debug!("Update check failed: {}", err),
+            },
+            Message::OpenUpdate => {
+                if let Some(release) = &self.update_available {
+                    if let Err(e) = open::that(release.url.as_str()) {
+                        tracing::warn!("Failed to open URL: {}", e);
+                    }
+                }
+            }
+            Message::OpenCrashReport => {
+                if let Some(report) = self.crash_reports.last() {
+                    if let Err(e) = open::that(&report.path) {
+                        tracing::warn!("Failed to open crash report: {}", e);
+                    }
+                }
+            }
+            Message::SubmitCrashReport => {
+                if let Some(report) = self.crash_reports.last() {
+                    match std::fs::read_to_string(&report.path) {
+                        Ok(text) => {
+                            if let Err(e) = open::that(crash_report::issue_url(&text)) {
+                                tracing::warn!("Failed to open URL: {}", e);
+                            }
+                        }
+                        Err(e) => tracing::warn!("Failed to read crash report: {}", e),
+                    }
+                }
+            }
+            Message::DismissCrashReports => {
+                for report in self.crash_reports.drain(..) {
+                    if let Err(e) = report.mark_seen() {
+                        tracing::debug!("Failed to mark crash report as seen: {}", e);
+                    }
+                }
+            }
             Message::ConfigSystemPromptChanged(val) => {
                 self.config_form.system_prompt = val;
             }
@@ -682,37 +2043,180 @@ impl App {
                     self.config_form.max_tokens = n;
                 }
             }
+            Message::ConfigTopPChanged(val) => {
+                self.config_form.top_p = val;
+            }
+            Message::ConfigFrequencyPenaltyChanged(val) => {
+                self.config_form.frequency_penalty = val;
+            }
+            Message::ConfigPresencePenaltyChanged(val) => {
+                self.config_form.presence_penalty = val;
+            }
+            Message::ConfigStopSequencesChanged(val) => {
+                self.config_form.stop_sequences = val;
+            }
+            Message::ConfigSeedChanged(val) => {
+                self.config_form.seed = val;
+            }
             Message::SaveConfig => {
-                self.apply_config_changes();
+                return self.apply_config_changes();
+            }
+            Message::ProviderChecked(generation, result) => {
+                // Settings were saved again while this check ran
+                if generation != self.provider_check_generation {
+                    return Task::none();
+                }
+                match result {
+                    Ok(()) => self
+                        .config_form
+                        .set_success("Settings saved, connection verified"),
+                    Err(err) => self
+                        .config_form
+                        .set_error(&format!("Settings saved, but the test request failed: {err}")),
+                }
+                // The provider may have changed; refresh the health dot now
+                self.provider_health = None;
+                return Task::done(Message::PingProvider);
+            }
+            Message::PingProvider => {
+                if self.onboarding.is_some() {
+                    return Task::none();
+                }
+                let config = self.config.clone();
+                return Task::perform(
+                    async move { provider_check::ping(&config).await },
+                    Message::ProviderPinged,
+                );
+            }
+            Message::ProviderPinged(report) => {
+                if let Some(error) = &report.error {
+                    tracing::debug!("Provider health {:?}: {}", report.health, error);
+                    self.provider_last_error = Some((error.clone(), chrono::Local::now()));
+                }
+                self.provider_health = Some(report);
+                self.dispatch_queued_prompts();
             }
             Message::ThemeModeChanged(mode) => {
                 if let Some(theme_mode) = ThemeMode::from_name(&mode) {
-                    println!("Theme mode changed to: {:?}", theme_mode);
+                    tracing::debug!("Theme mode changed to: {:?}", theme_mode);
                     self.theme_mode = theme_mode;
                     self.config_form.theme_mode = theme_mode;
+                    self.save_theme();
+                    if theme_mode == ThemeMode::Auto {
+                        return Task::done(Message::CheckSystemTheme);
+                    }
                     return Task::none();
                 }
             }
-            Message::ThemeSubmenuChanged(submenu) => {
-                // Handle Dark/Black submenu selection
-                match submenu.as_str() {
-                    "Dark" => {
-                        println!("Theme submenu changed to: Dark");
-                        self.theme_mode = ThemeMode::Dark;
-                        self.config_form.theme_mode = ThemeMode::Dark;
-                    }
-                    "Black" => {
-                        println!("Theme submenu changed to: Black");
-                        self.theme_mode = ThemeMode::Black;
-                        self.config_form.theme_mode = ThemeMode::Black;
-                    }
-                    _ => {}
+            Message::OnboardingProviderSelected(provider) => {
+                if let Some(onboarding) = self.onboarding.as_mut() {
+                    onboarding.select_provider(&provider);
                 }
+            }
+            Message::OnboardingApiKeyChanged(key) => {
+                if let Some(onboarding) = self.onboarding.as_mut() {
+                    onboarding.api_key = key;
+                    onboarding.test_result = None;
+                }
+            }
+            Message::OnboardingApiUrlChanged(url) => {
+                if let Some(onboarding) = self.onboarding.as_mut() {
+                    onboarding.api_url = url;
+                    onboarding.test_result = None;
+                }
+            }
+            Message::OnboardingTestConnection => {
+                let Some(onboarding) = self.onboarding.as_mut() else {
+                    return Task::none();
+                };
+                let mut config = self.config.clone();
+                if let Err(e) = onboarding.apply_to(&mut config) {
+                    onboarding.test_result = Some(Err(e.to_string()));
+                    return Task::none();
+                }
+                onboarding.testing = true;
+                onboarding.test_result = None;
+                let provider = onboarding.provider.clone();
+                return Task::perform(
+                    async move {
+                        provider_check::check(&config)
+                            .await
+                            .map(|_| format!("Connected to {}", provider))
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::OnboardingTested,
+                );
+            }
+            Message::OnboardingTested(result) => {
+                if let Some(onboarding) = self.onboarding.as_mut() {
+                    onboarding.testing = false;
+                    onboarding.test_result = Some(result);
+                }
+            }
+            Message::OnboardingModelSelected(model) => {
+                if let Some(onboarding) = self.onboarding.as_mut() {
+                    onboarding.model = model;
+                }
+            }
+            Message::OnboardingThemeSelected(mode) => {
+                if let Some(onboarding) = self.onboarding.as_mut() {
+                    onboarding.theme_mode = mode;
+                }
+                self.theme_mode = mode;
+                self.config_form.theme_mode = mode;
+                if mode == ThemeMode::Auto {
+                    return Task::done(Message::CheckSystemTheme);
+                }
+            }
+            Message::OnboardingBack => {
+                if let Some(onboarding) = self.onboarding.as_mut() {
+                    if let Some(previous) = onboarding.step.previous() {
+                        onboarding.step = previous;
+                    }
+                }
+            }
+            Message::OnboardingNext => return self.advance_onboarding(),
+            Message::OnboardingSkip => {
+                self.onboarding = None;
+                return iced::widget::operation::focus(input_id());
+            }
+            Message::CheckSystemTheme => {
+                return Task::perform(
+                    async {
+                        tokio::task::spawn_blocking(system_prefers_dark)
+                            .await
+                            .unwrap_or(true)
+                    },
+                    Message::SystemThemeDetected,
+                );
+            }
+            Message::SystemThemeDetected(dark) => {
+                if dark != self.system_dark {
+                    tracing::debug!("System theme changed to: {}", if dark { "dark" } else { "light" });
+                    self.system_dark = dark;
+                }
+            }
+            Message::ThemeSubmenuChanged(submenu) => {
+                // Handle Dark/Black submenu selection
+                match submenu.as_str() {
+                    "Dark" => {
+                        tracing::debug!("Theme submenu changed to: Dark");
+                        self.theme_mode = ThemeMode::Dark;
+                        self.config_form.theme_mode = ThemeMode::Dark;
+                    }
+                    "Black" => {
+                        tracing::debug!("Theme submenu changed to: Black");
+                        self.theme_mode = ThemeMode::Black;
+                        self.config_form.theme_mode = ThemeMode::Black;
+                    }
+                    _ => {}
+                }
+                self.save_theme();
                 return Task::none();
             }
             Message::StarterClicked(starter) => {
                 // Set the draft to the starter and send it
-                self.draft = starter;
+                self.draft = text_editor::Content::with_text(&starter);
                 // Trigger send prompt
                 return Task::done(Message::SendPrompt);
             }
@@ -768,9 +2272,9 @@ impl App {
                     
                     if !answers.is_empty() {
                         if let Err(e) = QUESTION_HANDLER.answer(&batch.batch_id, answers.clone()) {
-                            eprintln!("❌ Failed to send answers for batch {}: {}", batch.batch_id, e);
+                            tracing::error!("Failed to send answers for batch {}: {}", batch.batch_id, e);
                         } else {
-                            eprintln!("✅ Sent {} answers for batch {}", answers.len(), batch.batch_id);
+                            tracing::debug!("Sent {} answers for batch {}", answers.len(), batch.batch_id);
                         }
                     }
                 }
@@ -804,6 +2308,77 @@ impl App {
             }
             Message::SettingsNavigate(page) => {
                 self.settings_state.navigate_to(page);
+                if page == SettingsPage::Stats {
+                    self.usage_summary = None;
+                    return Task::perform(
+                        async {
+                            tokio::task::spawn_blocking(|| usage_store::summarize(STATS_WINDOW_DAYS))
+                                .await
+                                .unwrap_or_default()
+                        },
+                        Message::UsageSummaryLoaded,
+                    );
+                }
+                if page == SettingsPage::Knowledge {
+                    return self.load_knowledge_documents();
+                }
+            }
+            Message::UsageSummaryLoaded(summary) => {
+                self.usage_summary = Some(summary);
+            }
+            Message::KnowledgeLoaded(documents) => {
+                self.knowledge_documents = Some(documents);
+            }
+            Message::AddKnowledgeDocument => {
+                return Task::future(async {
+                    let path = FileDialog::new()
+                        .add_filter("Documents", knowledge::SUPPORTED_EXTENSIONS)
+                        .pick_file();
+                    Message::KnowledgeDocumentPicked(path)
+                });
+            }
+            Message::KnowledgeDocumentPicked(Some(path)) => {
+                let Some(knowledge_base) = self.knowledge_base() else {
+                    return Task::none();
+                };
+                let config = self.config.clone();
+                self.knowledge_error = None;
+                self.knowledge_adding = Some(
+                    path.file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                );
+                return Task::future(async move {
+                    let result = knowledge_base
+                        .add(&path, &config)
+                        .await
+                        .map_err(|e| format!("{:#}", e));
+                    Message::KnowledgeDocumentAdded(result)
+                });
+            }
+            Message::KnowledgeDocumentPicked(None) => {}
+            Message::KnowledgeDocumentAdded(result) => {
+                self.knowledge_adding = None;
+                match result {
+                    Ok(document) => {
+                        self.knowledge_documents
+                            .get_or_insert_with(Vec::new)
+                            .push(document);
+                    }
+                    Err(e) => self.knowledge_error = Some(e),
+                }
+            }
+            Message::RemoveKnowledgeDocument(id) => {
+                if let Some(knowledge_base) = self.knowledge_base() {
+                    match knowledge_base.remove(&id) {
+                        Ok(()) => {
+                            if let Some(documents) = self.knowledge_documents.as_mut() {
+                                documents.retain(|d| d.id != id);
+                            }
+                        }
+                        Err(e) => self.knowledge_error = Some(format!("{:#}", e)),
+                    }
+                }
             }
             Message::SettingsBack => {
                 self.settings_state.navigate_back();
@@ -811,31 +2386,60 @@ impl App {
             Message::OpenModelSelector => {
                 // Navigate to model selector page and start fetching models
                 self.settings_state.navigate_to(SettingsPage::ModelSelector);
-                self.models_loading = true;
-                self.model_list.clear();
-                // Fetch models based on current provider
-                let provider = self.config_form.provider.to_lowercase();
-                match provider.as_str() {
-                    "openai" => self.dispatcher.fetch_openai_models(),
-                    "anthropic" => self.dispatcher.fetch_anthropic_models(),
-                    "ollama" => self.dispatcher.fetch_ollama_models(),
-                    "z.ai coding plan" | "z.ai" | "zai" => self.dispatcher.fetch_zai_models(),
-                    "openrouter" => self.dispatcher.fetch_openrouter_models(),
-                    _ => {
-                        self.models_loading = false;
-                    }
-                }
+                let provider = self.config_form.provider.clone();
+                self.fetch_models(&provider);
             }
             Message::SelectModel(model) => {
                 self.config_form.model = model;
                 // Go back to Provider page, not Main
                 self.settings_state.navigate_to(SettingsPage::Provider);
             }
+            Message::Window(event) => match event {
+                window::Event::Focused => {
+                    self.window_focused = true;
+                    // The answer shown in the current tab has now been seen
+                    if let Some(session) = self.sessions.get_mut(self.current) {
+                        session.unread = false;
+                    }
+                }
+                window::Event::Unfocused => self.window_focused = false,
+                // Minimizing reports a zero-sized window on most platforms
+                window::Event::Resized(size) => {
+                    self.window_minimized = size.width == 0.0 || size.height == 0.0;
+                }
+                _ => {}
+            },
+            Message::MarkdownParsed { key, revision, items } => {
+                // Ignore results superseded by a newer parse of the same message
+                if self.markdown_pending.get(&key) == Some(&revision) {
+                    self.markdown_pending.remove(&key);
+                    self.markdown_streams.remove(&key);
+                    self.markdown_cache.insert(key, items);
+                }
+            }
             Message::LinkClicked(url) => {
                 // Open the URL in the default browser
                 if let Err(e) = open::that(url.as_str()) {
-                    eprintln!("Failed to open URL: {}", e);
+                    tracing::warn!("Failed to open URL: {}", e);
+                }
+            }
+            Message::ToggleToolGroup(start) => {
+                if let Some(session) = self.sessions.get(self.current) {
+                    let key = (session.id, start);
+                    if !self.expanded_tool_groups.remove(&key) {
+                        self.expanded_tool_groups.insert(key);
+                    }
+                }
+            }
+            Message::InterruptAndReply => {
+                // Keep the partial answer, marked, and hand over to the input
+                if let Some(session) = self.sessions.get_mut(self.current) {
+                    if session.is_streaming {
+                        session.flush_ai_buffer(Utc::now().to_rfc3339());
+                        session.mark_answer_interrupted();
+                    }
                 }
+                return self.update(Message::StopStream);
             }
             Message::StopStream => {
                 // Stop the current streaming session
@@ -843,6 +2447,15 @@ impl App {
                     if session.is_streaming {
                         self.dispatcher.stop_stream(session.id);
                         session.set_streaming(false);
+                        // A deliberate stop isn't an interruption to offer continuing
+                        session.flush_ai_buffer(Utc::now().to_rfc3339());
+                        let events = session.to_ui_events();
+                        let model = effective_model(&self.config, self.project_config.as_ref());
+                        if let Err(err) =
+                            self.conversation_manager.save_conversation(session.id, &events, model)
+                        {
+                            tracing::warn!("Failed to save conversation: {}", err);
+                        }
                         // Re-focus the input after stopping
                         return iced::widget::operation::focus(input_id());
                     }
@@ -851,28 +2464,22 @@ impl App {
             Message::ToggleToolCollapse(key) => {
                 // Get or create animation spring for this tool
                 // Important: we need to know the DEFAULT state to create the spring correctly
-                // Thinking bubbles (finalized) default to collapsed, tools default to expanded
-                let is_thinking = key.contains(":")
-                    && self.sessions.iter().enumerate().any(|(sidx, session)| {
-                        session.messages.iter().enumerate().any(|(midx, msg)| {
-                            let msg_key = format!("{}:{}", sidx, midx);
-                            msg_key == key
-                                && msg.is_thinking()
-                                && msg.thinking_duration_secs.is_some()
-                        })
-                    });
+                // Finalized thinking defaults to collapsed, tools as the tool verbosity says
+                let message = key.split_once(':').and_then(|(s, m)| {
+                    let session = self.sessions.get(s.parse::<usize>().ok()?)?;
+                    session.messages.get(m.parse::<usize>().ok()?)
+                });
+                let expanded = match message {
+                    Some(msg) if msg.is_thinking() => msg.thinking_duration_secs.is_none(),
+                    Some(msg) => self.tool_expanded_by_default(msg),
+                    None => true,
+                };
 
                 let spring = self.tool_animations.entry(key).or_insert_with(|| {
                     let mut s = Spring::default();
-                    if is_thinking {
-                        // Finalized thinking defaults to collapsed
-                        s.position = 0.0;
-                        s.target = 0.0;
-                    } else {
-                        // Tools default to expanded
-                        s.position = 1.0;
-                        s.target = 1.0;
-                    }
+                    let position = if expanded { 1.0 } else { 0.0 };
+                    s.position = position;
+                    s.target = position;
                     s
                 });
 
@@ -887,12 +2494,127 @@ impl App {
             Message::ToggleErrorExpand => {
                 self.error_expanded = !self.error_expanded;
             }
+            Message::UndoEdit(snapshot_id) => {
+                let id = snapshot_id.clone();
+                return Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || {
+                            snapshots::undo(&id)
+                                .map(|s| s.path.display().to_string())
+                                .map_err(|e| format!("{:#}", e))
+                        })
+                        .await
+                        .unwrap_or_else(|e| Err(e.to_string()))
+                    },
+                    move |result| Message::EditUndone(snapshot_id.clone(), result),
+                );
+            }
+            Message::EditUndone(snapshot_id, result) => {
+                match result {
+                    Ok(path) => {
+                        tracing::info!("Restored {} from snapshot {}", path, snapshot_id);
+                        for message in self.sessions.iter_mut().flat_map(|s| s.messages.iter_mut()) {
+                            if message.snapshot_id.as_deref() == Some(snapshot_id.as_str()) {
+                                message.snapshot_id = None;
+                                message.content.push_str(" (undone)");
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        self.stream_error = Some(format!("Undo failed: {}", err));
+                    }
+                }
+            }
             Message::CopyToClipboard(text) => {
                 // Use persistent clipboard to prevent Wayland "dropped too quickly" issue
                 if let Some(ref mut clipboard) = self.clipboard {
                     let _ = clipboard.set_text(text);
                 }
             }
+            Message::DeleteFromHere(msg_idx) => {
+                if let Some(session) = self.sessions.get(self.current) {
+                    self.delete_from_pending = Some((session.id, msg_idx));
+                }
+            }
+            Message::CancelDeleteFromHere => {
+                self.delete_from_pending = None;
+            }
+            Message::ConfirmDeleteFromHere => {
+                let current = self.current;
+                let Some((session_id, msg_idx)) = self.delete_from_pending.take() else {
+                    return Task::none();
+                };
+                let Some(session) = self.sessions.get_mut(current) else {
+                    return Task::none();
+                };
+                if session.id != session_id || session.is_streaming {
+                    return Task::none();
+                }
+                let tool_call_ids = session.truncate_from(msg_idx);
+
+                // Persist the pruned thread; an emptied one is removed
+                let (id, events) = (session.id, session.to_ui_events());
+                let saved = if session.messages.is_empty() {
+                    self.conversation_manager.delete_conversation(id)
+                } else {
+                    self.conversation_manager.save_conversation(
+                        id,
+                        &events,
+                        effective_model(&self.config, self.project_config.as_ref()),
+                    )
+                };
+                if let Err(err) = saved {
+                    tracing::warn!("Failed to save truncated conversation: {}", err);
+                }
+
+                self.drop_message_caches(current, msg_idx, &tool_call_ids);
+                self.stream_error = None;
+                return self.parse_session_markdown(current);
+            }
+            Message::CopyConversation => {
+                let Some(session) = self.sessions.get(self.current) else {
+                    return Task::none();
+                };
+                let markdown = transcript::to_markdown(&session.to_ui_events());
+                if let Some(ref mut clipboard) = self.clipboard {
+                    let _ = clipboard.set_text(markdown);
+                }
+            }
+            Message::ToggleRawTranscript => {
+                if let Some(session) = self.sessions.get_mut(self.current) {
+                    session.show_raw = !session.show_raw;
+                }
+            }
+            Message::ExportConversationHtml => {
+                let Some(session) = self.sessions.get(self.current) else {
+                    return Task::none();
+                };
+                let html = transcript::to_html(&session.to_ui_events());
+                let file_name: String = session
+                    .get_title()
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+                    .collect();
+                let file_name = format!("{}.html", file_name);
+                return Task::future(async move {
+                    let Some(path) = FileDialog::new()
+                        .set_file_name(file_name)
+                        .add_filter("HTML", &["html"])
+                        .save_file()
+                    else {
+                        return Message::ConversationExported(None);
+                    };
+                    let result = std::fs::write(&path, html)
+                        .map(|_| path)
+                        .map_err(|e| e.to_string());
+                    Message::ConversationExported(Some(result))
+                });
+            }
+            Message::ConversationExported(result) => match result {
+                Some(Ok(path)) => tracing::info!("Exported conversation to {}", path.display()),
+                Some(Err(err)) => self.stream_error = Some(format!("Export failed: {}", err)),
+                None => {}
+            },
             Message::ClearChat => {
                 let session_id = self.sessions.get(self.current).map(|s| s.id);
                 let was_streaming = self
@@ -918,21 +2640,18 @@ impl App {
                 }
 
                 // Drop all cached UI state for this session so the next chat is pristine
-                let prefix = format!("{}:", self.current);
-                self.message_editors.retain(|k, _| !k.starts_with(&prefix));
-                self.markdown_cache.retain(|k, _| !k.starts_with(&prefix));
-                self.tool_animations.retain(|k, _| !k.starts_with(&prefix));
-
+                self.drop_message_caches(self.current, 0, &tool_call_ids);
                 if let Some(id) = session_id {
                     self.tool_args_cache.remove(&id);
                 }
-                for tool_call_id in tool_call_ids {
-                    self.bash_output_lines.remove(&tool_call_id);
-                }
 
                 self.stream_error = None;
                 self.error_expanded = false;
-                self.draft.clear();
+                self.draft = text_editor::Content::new();
+                self.draft_edited = None;
+                if let Some(id) = session_id {
+                    drafts::save(id, "");
+                }
 
                 if let Some(session) = self.sessions.get_mut(self.current) {
                     *session = Session::new();
@@ -950,1213 +2669,6029 @@ impl App {
                     self.directory_draft.clear();
                 }
             }
-            Message::CloseDirectoryPopup => {
-                self.show_directory_popup = false;
-                self.show_directory_custom_input = false;
-                self.directory_draft.clear();
+            Message::ToggleContextPanel => {
+                self.show_context_panel = !self.show_context_panel;
+                if self.show_context_panel {
+                    self.show_directory_popup = false;
+                    self.show_session_tuning = false;
+                }
             }
-            Message::ShowDirectoryCustomInput => {
-                self.show_directory_custom_input = true;
-                // Pre-fill with current directory
-                self.directory_draft = self.current_directory.display().to_string();
+            Message::ToggleGitContext => {
+                let enabled = !self.config.get_git_context_enabled();
+                if let Err(e) = self.config.set_git_context_enabled(enabled) {
+                    tracing::warn!("Failed to save git context setting: {}", e);
+                }
+                if let Err(err) = self.dispatcher.update_backend(&self.config) {
+                    tracing::warn!("Failed to apply git context setting: {}", err);
+                }
             }
-            Message::DirectoryDraftChanged(s) => {
-                self.directory_draft = s;
+            Message::ToggleLogViewer => {
+                self.show_log_viewer = !self.show_log_viewer;
+                if self.show_log_viewer {
+                    self.log_records = logging::recent_logs(LOG_VIEWER_LINES);
+                    self.last_log_refresh = Instant::now();
+                }
             }
-            Message::ChangeDirectory => {
-                let path = PathBuf::from(&self.directory_draft);
-                self.apply_directory_selection(path);
+            Message::ToggleSessionTuning => {
+                self.show_session_tuning = !self.show_session_tuning;
+                if self.show_session_tuning {
+                    self.show_context_panel = false;
+                    self.show_directory_popup = false;
+                }
             }
-            Message::OpenDirectoryPicker => {
-                let start_dir = self.current_directory.clone();
-                return Task::future(async move {
-                    let path = FileDialog::new().set_directory(start_dir).pick_folder();
-                    Message::DirectoryPickerResult(path)
+            Message::SessionTemperatureChanged(temperature) => {
+                if let Some(session) = self.sessions.get_mut(self.current) {
+                    session.temperature = Some(temperature);
+                }
+            }
+            Message::SessionMaxTokensChanged(max_tokens) => {
+                if let Some(session) = self.sessions.get_mut(self.current) {
+                    session.max_tokens = Some(max_tokens);
+                }
+            }
+            Message::ResetSessionTuning => {
+                if let Some(session) = self.sessions.get_mut(self.current) {
+                    session.temperature = None;
+                    session.max_tokens = None;
+                }
+            }
+            Message::OpenFilePreview(path) => {
+                self.file_preview = Some(FilePreview::load(path));
+            }
+            Message::CloseFilePreview => {
+                self.file_preview = None;
+            }
+            Message::FilePreviewAction(action) => {
+                // Read-only: selection and scrolling only
+                if let Some(preview) = &mut self.file_preview {
+                    if !action.is_edit() {
+                        preview.content.perform(action);
+                    }
+                }
+            }
+            Message::GenerateCommitMessage => {
+                self.commit_draft = Some(CommitDraft {
+                    content: text_editor::Content::new(),
+                    generating: true,
+                    status: None,
                 });
+                let config = self.config.clone();
+                let dir = self.current_directory.clone();
+                return Task::perform(
+                    async move {
+                        commit_message::generate(&config, &dir)
+                            .await
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::CommitMessageGenerated,
+                );
             }
-            Message::DirectoryPickerResult(path) => {
-                if let Some(path) = path {
-                    self.apply_directory_selection(path);
+            Message::CommitMessageGenerated(result) => {
+                // Ignore results for a draft that was closed meanwhile
+                if let Some(draft) = &mut self.commit_draft {
+                    draft.generating = false;
+                    match result {
+                        Ok(message) => draft.content = text_editor::Content::with_text(&message),
+                        Err(err) => draft.status = Some(Err(err)),
+                    }
                 }
             }
-            Message::SelectRecentDirectory(path) => {
-                self.apply_directory_selection(path);
+            Message::CommitDraftAction(action) => {
+                if let Some(draft) = &mut self.commit_draft {
+                    draft.content.perform(action);
+                }
             }
-            Message::ToggleConversations => {
-                self.show_conversations = !self.show_conversations;
-                if self.show_conversations {
-                    return Task::future(async move {
-                        Message::RefreshConversations
-                    });
+            Message::CommitStaged => {
+                let Some(draft) = &mut self.commit_draft else {
+                    return Task::none();
+                };
+                let message = draft.content.text().trim().to_string();
+                if message.is_empty() || draft.generating {
+                    return Task::none();
                 }
+                draft.generating = true;
+                let dir = self.current_directory.clone();
+                return Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || commit_message::commit(&dir, &message))
+                            .await
+                            .map_err(|e| e.to_string())
+                            .and_then(|r| r.map_err(|e| e.to_string()))
+                    },
+                    Message::CommitFinished,
+                );
             }
-            Message::RefreshConversations => {
-                if let Ok(conversations) = self.conversation_manager.list_conversations() {
-                    self.saved_conversations = conversations;
+            Message::CommitFinished(result) => {
+                if let Some(draft) = &mut self.commit_draft {
+                    draft.generating = false;
+                    draft.status = Some(result);
                 }
+                self.git_info = GitInfo::read(&self.current_directory);
             }
-            Message::LoadConversation(conversation_id) => {
-                if let Ok(conversation) = self.conversation_manager.load_conversation(conversation_id) {
-                    // Create a new session from the loaded events
-                    let new_session = Session::from_events(conversation_id, &conversation.events);
-                    
-                    // Add the new session
-                    self.sessions.push(new_session);
-                    self.current = self.sessions.len() - 1;
-                    
-                    // Close the conversations sidebar
-                    self.show_conversations = false;
-                    
-                    // Clear the draft
-                    self.draft.clear();
-                    
-                    // Focus the input
-                    return iced::widget::operation::focus(input_id());
+            Message::CloseCommitDraft => {
+                self.commit_draft = None;
+            }
+            Message::PreviewApply(msg_idx, block_idx) => {
+                let block = self
+                    .sessions
+                    .get(self.current)
+                    .and_then(|s| s.messages.get(msg_idx))
+                    .and_then(|m| code_blocks::file_blocks(&m.content).into_iter().nth(block_idx));
+                if let Some(block) = block {
+                    let path = self.current_directory.join(&block.path);
+                    match code_blocks::propose(&block, &path) {
+                        Ok(edit) => {
+                            self.pending_apply = Some(PendingApply {
+                                edit,
+                                display_path: block.path,
+                                applying: false,
+                                status: None,
+                            });
+                        }
+                        Err(err) => self.stream_error = Some(err),
+                    }
                 }
             }
-            Message::DeleteConversation(conversation_id) => {
-                if let Err(err) = self.conversation_manager.delete_conversation(conversation_id) {
-                    eprintln!("Failed to delete conversation: {}", err);
-                } else {
-                    // Refresh the list
-                    return Task::future(async move {
-                        Message::RefreshConversations
-                    });
+            Message::ConfirmApply => {
+                let Some(pending) = &mut self.pending_apply else {
+                    return Task::none();
+                };
+                if pending.applying {
+                    return Task::none();
                 }
+                pending.applying = true;
+                let edit = pending.edit.clone();
+                return Task::perform(
+                    async move { code_blocks::apply(&edit).await },
+                    Message::ApplyFinished,
+                );
             }
-            Message::CloseConversations => {
-                self.show_conversations = false;
+            Message::ApplyFinished(result) => {
+                if let Some(pending) = &mut self.pending_apply {
+                    pending.applying = false;
+                    pending.status = Some(result);
+                }
+                self.git_info = GitInfo::read(&self.current_directory);
             }
-            Message::InitializeProjectWithAI => {
-                // Build a prompt for AI to enhance the manifest
-                let project_name = self.detected_project
-                    .as_ref()
-                    .map(|p| p.name.clone())
-                    .unwrap_or_else(|| "this project".to_string());
-                
-                let prompt = format!(
-                    "Please analyze {} and enhance the PROJECT.manifest file.\n\n\
-                    First, read the existing PROJECT.manifest file to see what's already there.\n\
-                    Then explore the key files to understand the project architecture.\n\n\
-                    Update the manifest with:\n\
-                    1. A comprehensive 'essence' section describing what the project does\n\
-                    2. Key architecture patterns and design decisions\n\
-                    3. Important gotchas, pitfalls, and conventions\n\
-                    4. Common development tasks and how to approach them\n\n\
-                    CRITICAL: The FIRST LINE of the file MUST be exactly:\n\
-                    `# AI-ENHANCED by ARULA`\n\n\
-                    Then add a comment with today's date, and include all the enhanced content.\n\
-                    Keep the existing detected information but enrich it with your understanding.",
-                    project_name
+            Message::CancelApply => {
+                self.pending_apply = None;
+            }
+            Message::CaptureScreen => {
+                if self.capturing_screen {
+                    return Task::none();
+                }
+                self.capturing_screen = true;
+                return Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(|| screen_capture::capture(None, true))
+                            .await
+                            .map_err(|e| e.to_string())?
+                    },
+                    Message::ScreenCaptured,
                 );
-                
-                // Add as user message and trigger send
-                if let Some(session) = self.sessions.get_mut(self.current) {
-                    if !session.is_streaming {
-                        session.add_user_message(prompt.clone(), Utc::now().to_rfc3339());
-                        session.set_streaming(true);
-                        
-                        let session_config = SessionConfig {
-                            system_prompt: build_enhanced_system_prompt(&self.config_form.system_prompt),
-                            model: self.config.get_model(),
-                            max_tokens: self.config_form.max_tokens as u32,
-                            temperature: self.config_form.temperature,
-                        };
-                        
-                        let history = session.get_chat_history();
-                        let history_opt = if history.is_empty() { None } else { Some(history) };
-                        
-                        if let Err(err) = self.dispatcher.start_stream(
-                            session.id,
-                            prompt,
-                            history_opt,
-                            session_config,
-                        ) {
-                            eprintln!("dispatch error: {err}");
-                            session.set_streaming(false);
-                        }
-                    }
+            }
+            Message::ScreenCaptured(result) => {
+                self.capturing_screen = false;
+                match result {
+                    Ok(path) => self.pending_images.push(path),
+                    Err(err) => self.stream_error = Some(format!("Screenshot failed: {err}")),
                 }
                 return iced::widget::operation::focus(input_id());
             }
-        }
-        Task::none()
-    }
-
-    fn apply_directory_selection(&mut self, path: PathBuf) {
-        if !path.exists() || !path.is_dir() {
-            return;
-        }
-
-        if std::env::set_current_dir(&path).is_ok() {
-            self.recent_directories.retain(|p| p != &path);
-            self.recent_directories.insert(0, path.clone());
-            self.recent_directories.truncate(10);
-
-            self.current_directory = path.clone();
-            self.show_directory_popup = false;
-            self.show_directory_custom_input = false;
-            self.directory_draft.clear();
-            
-            // Auto-detect project and create manifest if needed
-            self.detected_project = detect_project(&path);
-            
-            let manifest_path = path.join("PROJECT.manifest");
-            if !manifest_path.exists() {
-                // Create auto-generated manifest if we detected a project
-                if let Some(ref project) = self.detected_project {
-                    let content = generate_auto_manifest(project);
-                    let _ = std::fs::write(&manifest_path, content);
+            Message::PasteIntoDraft => {
+                let Some(clipboard) = self.clipboard.as_mut() else {
+                    return Task::none();
+                };
+                if let Ok(image) = clipboard.get_image() {
+                    let (width, height) = (image.width as u32, image.height as u32);
+                    let rgba = image.bytes.into_owned();
+                    return Task::perform(
+                        async move {
+                            tokio::task::spawn_blocking(move || {
+                                arula_core::utils::images::save_pasted(width, height, rgba)
+                                    .map_err(|e| format!("{:#}", e))
+                            })
+                            .await
+                            .map_err(|e| e.to_string())?
+                        },
+                        Message::ImagePasted,
+                    );
+                }
+                if let Ok(text) = clipboard.get_text() {
+                    self.draft.perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                        std::sync::Arc::new(text),
+                    )));
+                    self.refresh_context_estimate();
                 }
             }
-            
-            // Check if manifest is AI-enhanced
-            self.manifest_is_ai_enhanced = is_ai_enhanced(&manifest_path);
-        }
-    }
-
-    /// Helper function to get tool icons
-    fn get_tool_icon(&self, name: &str) -> &'static str {
-        match name.to_lowercase().as_str() {
-            "execute_bash" => "○",
-            "read_file" => "○",
-            "write_file" | "edit_file" => "□",
-            "list_directory" => "◇",
-            "search_files" => "○",
-            "web_search" => "⭕",
-            "mcp_call" => "◊",
-            "visioneer" => "○",
-            "ask_question" => "❓",
-            _ => "□",
-        }
-    }
-
-    fn handle_ui_event(&mut self, ev: UiEvent) -> Task<Message> {
-        match ev {
-            UiEvent::ConversationStarters(starters) => {
-                self.conversation_starters = starters;
-                return Task::none();
+            Message::CompactHistory(compaction) => {
+                let Some(session) = self.sessions.get(self.current) else {
+                    return Task::none();
+                };
+                if session.is_streaming || self.compacting_history.contains(&session.id) {
+                    return Task::none();
+                }
+                let session_id = session.id;
+                self.compacting_history.insert(session_id);
+                let covered = session.messages.len();
+                let history = session.get_chat_history();
+                let mut config = self.config.clone();
+                config.set_model(&effective_model(&self.config, self.project_config.as_ref()));
+                return Task::perform(
+                    async move { context_limit::compact(history, compaction, &config).await },
+                    move |history| Message::HistoryCompacted {
+                        session_id,
+                        covered,
+                        history,
+                    },
+                );
             }
-            UiEvent::ConversationTitle(title) => {
-                // Update the current session's title
-                if let Some(s) = self.sessions.get_mut(self.current) {
-                    s.set_title(title);
+            Message::HistoryCompacted {
+                session_id,
+                covered,
+                history,
+            } => {
+                self.compacting_history.remove(&session_id);
+                let history = match history {
+                    Ok(history) => history,
+                    Err(err) => {
+                        // The history is left as it was
+                        self.report_stream_error(
+                            session_id,
+                            format!("Couldn't summarize the conversation: {err}"),
+                        );
+                        return Task::none();
+                    }
+                };
+                if let Some(session) = self.sessions.iter_mut().find(|s| s.id == session_id) {
+                    if session.messages.len() >= covered {
+                        session.set_compacted_history(covered, history);
+                    }
                 }
+                self.refresh_context_estimate();
             }
-            UiEvent::UserMessage { content: _, timestamp: _ } => {
-                // Clear starters when conversation starts
-                self.conversation_starters.clear();
-                // User messages are handled locally in the session, no action needed
+            Message::ImagePasted(result) => match result {
+                Ok(path) => self.pending_images.push(path),
+                Err(err) => self.stream_error = Some(format!("Couldn't attach pasted image: {err}")),
+            },
+            Message::RemovePendingImage(idx) => {
+                if idx < self.pending_images.len() {
+                    self.pending_images.remove(idx);
+                }
             }
-            UiEvent::AiMessage { content: _, timestamp: _ } => {
-                // AI messages are handled via Token events, no action needed
+            Message::ReviewChanges => {
+                self.review = Some(ReviewState::Running);
+                let config = self.config.clone();
+                let dir = self.current_directory.clone();
+                return Task::perform(
+                    async move {
+                        review::review(&config, &dir, &ReviewTarget::WorkingTree, |_, _| {})
+                            .await
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::ReviewFinished,
+                );
             }
-            UiEvent::StreamStarted(id) => {
-                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
-                    s.set_streaming(true);
+            Message::ReviewFinished(result) => {
+                // Ignore results for a report that was closed meanwhile
+                if self.review.is_some() {
+                    self.review = Some(match result {
+                        Ok(report) => ReviewState::Done(report),
+                        Err(err) => ReviewState::Failed(err),
+                    });
                 }
             }
-            UiEvent::Token(id, delta, is_final) => {
-                // Find session index for syncing editors
-                let session_idx = self.sessions.iter().position(|s| s.id == id);
-
-                if let Some(idx) = session_idx {
-                    let session = &mut self.sessions[idx];
-                    
-                    // Check if this is a non-streaming response (complete response at once)
-                    let is_non_streaming = !session.is_streaming() && is_final;
-                    
-                    if is_non_streaming {
-                        // For non-streaming responses, use add_ai_message to create separate bubbles
-                        session.add_ai_message(delta, Utc::now().to_rfc3339());
-                    } else {
-                        // For streaming responses, use append_ai_message
-                        session.append_ai_message(delta, Utc::now().to_rfc3339());
+            Message::CloseReview => {
+                self.review = None;
+            }
+            Message::ToggleCompare => {
+                self.compare = match self.compare {
+                    Some(_) => None,
+                    None => {
+                        let current = (self.config.active_provider.clone(), self.config.get_model());
+                        Some(CompareState {
+                            prompt: String::new(),
+                            targets: [current.clone(), current],
+                            running: false,
+                            results: Vec::new(),
+                        })
                     }
-
-                    // Get or create the text editor content for the AI message
-                    let msg_idx = session.messages.len() - 1;
-                    let key = format!("{}:{}", idx, msg_idx);
-
-                    // Handle message editor updates differently for streaming vs non-streaming
-                    if is_non_streaming {
-                        // For non-streaming responses, always update the editor content
-                        self.message_editors.insert(
-                            key.clone(),
-                            text_editor::Content::with_text(&session.messages[msg_idx].content),
-                        );
-                    } else {
-                        // For streaming responses, only update if it doesn't exist or if this is final
-                        if !self.message_editors.contains_key(&key) {
-                            self.message_editors.insert(
-                                key.clone(),
-                                text_editor::Content::with_text(&session.messages[msg_idx].content),
-                            );
-                        } else if is_final {
-                            // Only update on final token to avoid constant recreation
-                            if let Some(editor_content) = self.message_editors.get_mut(&key) {
-                                let text = &session.messages[msg_idx].content;
-                                *editor_content = text_editor::Content::with_text(text);
-                            }
-                        }
+                };
+            }
+            Message::ComparePromptChanged(prompt) => {
+                if let Some(compare) = &mut self.compare {
+                    compare.prompt = prompt;
+                }
+            }
+            Message::CompareProviderChanged(idx, provider) => {
+                if let Some(target) = self.compare.as_mut().and_then(|c| c.targets.get_mut(idx)) {
+                    // Start from the provider's configured model
+                    target.1 = self
+                        .config
+                        .providers
+                        .get(&provider)
+                        .map(|p| p.model.clone())
+                        .unwrap_or_default();
+                    target.0 = provider;
+                }
+            }
+            Message::CompareModelChanged(idx, model) => {
+                if let Some(target) = self.compare.as_mut().and_then(|c| c.targets.get_mut(idx)) {
+                    target.1 = model;
+                }
+            }
+            Message::RunCompare => {
+                let Some(compare) = &mut self.compare else {
+                    return Task::none();
+                };
+                if compare.running || compare.prompt.trim().is_empty() {
+                    return Task::none();
+                }
+                compare.running = true;
+                compare.results.clear();
+                let config = self.config.clone();
+                let prompt = compare.prompt.trim().to_string();
+                let targets = compare.targets.to_vec();
+                return Task::perform(
+                    async move { bench::compare_models(&config, &prompt, &targets).await },
+                    Message::CompareFinished,
+                );
+            }
+            Message::CompareFinished(results) => {
+                // Ignore results for a view that was closed meanwhile
+                if let Some(compare) = &mut self.compare {
+                    compare.running = false;
+                    compare.results = results;
+                }
+            }
+            Message::UseComparedModel(idx) => {
+                let Some((provider, model)) = self
+                    .compare
+                    .as_ref()
+                    .and_then(|c| c.targets.get(idx))
+                    .cloned()
+                else {
+                    return Task::none();
+                };
+                if self.config.active_provider != provider {
+                    if let Err(err) = self.config.switch_provider(&provider) {
+                        self.stream_error = Some(format!("Failed to switch provider: {err}"));
+                        return Task::none();
                     }
-
-                    // Update markdown cache for AI messages
-                    // Parse markdown on final token or periodically during streaming
-                    let should_update_md = is_final || !self.markdown_cache.contains_key(&key);
-                    if should_update_md && session.messages[msg_idx].is_ai() {
-                        let content = &session.messages[msg_idx].content;
-                        let items: Vec<markdown::Item> = markdown::parse(content).collect();
-                        self.markdown_cache.insert(key, items);
+                }
+                self.config.set_model(model.trim());
+                let saved = self
+                    .config
+                    .save()
+                    .and_then(|_| self.dispatcher.update_backend(&self.config));
+                if let Err(err) = saved {
+                    self.stream_error = Some(format!("Failed to change the default model: {err}"));
+                }
+                self.config_form = ConfigForm::from_config(&self.config);
+                self.compare = None;
+            }
+            Message::ToggleOutline => {
+                self.show_outline = !self.show_outline;
+            }
+            Message::JumpToMessage(index) => {
+                let Some(session) = self.sessions.get(self.current) else {
+                    return Task::none();
+                };
+                let fraction = session.scroll_fraction(index);
+                // Stop following the stream so the picked message stays in view
+                self.chat_at_bottom = false;
+                self.chat_seen_messages = session.messages.len();
+                return iced::widget::operation::snap_to(
+                    chat_scroll_id(),
+                    scrollable::RelativeOffset { x: 0.0, y: fraction },
+                );
+            }
+            Message::ContinueGeneration => {
+                if let Some(session) = self.sessions.get_mut(self.current) {
+                    session.interrupted = false;
+                    self.draft = text_editor::Content::with_text(
+                        "Your previous answer was cut off. Continue it from exactly where it stopped.",
+                    );
+                    return Task::done(Message::SendPrompt);
+                }
+            }
+            Message::DismissInterrupted => {
+                if let Some(session) = self.sessions.get_mut(self.current) {
+                    session.interrupted = false;
+                }
+            }
+            Message::ContinueAnyway => {
+                if let Some((id, _)) = self.budget_notice.take() {
+                    if let Some(idx) = self.sessions.iter().position(|s| s.id == id) {
+                        self.stash_draft();
+                        self.current = idx;
                     }
-
-                    // Handle final token differently for streaming vs non-streaming
-                    if is_final {
-                        if !is_non_streaming {
-                            // For streaming responses, flush any remaining buffer content
-                            session.flush_ai_buffer(Utc::now().to_rfc3339());
-                        }
-                        session.set_streaming(false);
-                        // Re-focus input when response completes
-                        return iced::widget::operation::focus(input_id());
+                    self.budget_override = Some(id);
+                    self.draft = text_editor::Content::with_text("Continue where you left off.");
+                    return Task::done(Message::SendPrompt);
+                }
+            }
+            Message::Remote(RemoteCommand::SendPrompt {
+                session_id, prompt, ..
+            }) => {
+                // Remote prompts take the normal send path so they show up in the UI
+                let idx = match self.sessions.iter().position(|s| s.id == session_id) {
+                    Some(idx) => idx,
+                    None => {
+                        let mut session = Session::new();
+                        session.id = session_id;
+                        self.sessions.push(session);
+                        self.sessions.len() - 1
                     }
+                };
+                if self.sessions[idx].is_streaming {
+                    tracing::warn!("Ignoring remote prompt for busy session {}", session_id);
+                    return Task::none();
                 }
+                self.stash_draft();
+                self.current = idx;
+                self.draft = text_editor::Content::with_text(&prompt);
+                let task = self.update(Message::SendPrompt);
+                self.restore_draft();
+                // Sending forgot the session's saved draft; keep what is shown
+                self.draft_edited = Some(Instant::now());
+                return task;
             }
-            UiEvent::StreamFinished(id) => {
-                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
-                    // Flush any remaining AI content from the buffer
-                    s.flush_ai_buffer(Utc::now().to_rfc3339());
-                    s.set_streaming(false);
-                    
-                    // Save the conversation
-                    let events = s.to_ui_events();
-                    if let Err(err) = self.conversation_manager.save_conversation(
-                        s.id,
-                        &events,
-                        self.config.get_model(),
-                    ) {
-                        eprintln!("Failed to save conversation: {}", err);
+            Message::Remote(RemoteCommand::StopStream { session_id }) => {
+                if let Some(session) = self.sessions.iter_mut().find(|s| s.id == session_id) {
+                    if session.is_streaming {
+                        self.dispatcher.stop_stream(session_id);
+                        session.set_streaming(false);
                     }
                 }
-                // Re-focus input when stream finishes
-                return iced::widget::operation::focus(input_id());
             }
-            UiEvent::StreamErrored(id, err) => {
-                eprintln!("stream error {id}: {err}");
-                // Store error for display to user
-                self.stream_error = Some(err);
-                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
-                    s.set_streaming(false);
+            Message::DismissBudgetNotice => {
+                self.budget_notice = None;
+            }
+            Message::FocusNext => {
+                return iced::widget::operation::focus_next();
+            }
+            Message::FocusPrevious => {
+                return iced::widget::operation::focus_previous();
+            }
+            Message::MoveMessageFocus(delta) => {
+                let Some(session) = self.sessions.get(self.current) else {
+                    return Task::none();
+                };
+                let Some(last) = session.messages.len().checked_sub(1) else {
+                    return Task::none();
+                };
+                let index = match self.focused_message_index() {
+                    Some(index) => index.saturating_add_signed(delta).min(last),
+                    None => last,
+                };
+                self.focused_message = Some((session.id, index));
+                return Task::done(Message::JumpToMessage(index));
+            }
+            Message::ToggleFocusedMessage => {
+                let Some(index) = self.focused_message_index() else {
+                    return Task::none();
+                };
+                let message = &self.sessions[self.current].messages[index];
+                if message.is_tool() || message.is_thinking() {
+                    return Task::done(Message::ToggleToolCollapse(format!("{}:{}", self.current, index)));
                 }
-                // Re-focus input on error
-                return iced::widget::operation::focus(input_id());
             }
-            UiEvent::Thinking(id, text) => {
-                // Create a thinking/reasoning bubble to show the AI's thought process
-                if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                    eprintln!(
-                        "🧠 UI: Received Thinking event for session {}: {:?}",
-                        id, text
-                    );
+            Message::CopyFocusedMessage => {
+                if let Some(index) = self.focused_message_index() {
+                    let content = self.sessions[self.current].messages[index].content.clone();
+                    return Task::done(Message::CopyToClipboard(content));
                 }
-                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
-                    if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                        eprintln!("🧠 UI: Calling append_thinking_message");
-                    }
-                    s.append_thinking_message(text, Utc::now().to_rfc3339());
-                } else if std::env::var("ARULA_DEBUG").unwrap_or_default() == "1" {
-                    eprintln!("🧠 UI: Session not found for id {}", id);
+            }
+            Message::ClearMessageFocus => {
+                self.focused_message = None;
+                self.prompt_search = None;
+            }
+            Message::FocusedMessageKey(message) => {
+                let closes_search =
+                    matches!(*message, Message::ClearMessageFocus) && self.prompt_search.is_some();
+                if self.focused_message_index().is_some() || closes_search {
+                    return Task::done(*message);
                 }
             }
-            UiEvent::ToolCallStart(id, tool_id, name, display_args) => {
-                let icon = self.get_tool_icon(&name);
-                // display_args already contains "{display_name} • {formatted_args}"
-                let content = format!("{} {}", icon, display_args);
-
-                // Cache the display_args for later use in ToolCallResult
-                self.tool_args_cache.insert(id, display_args);
-
-                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
-                    // Pass tool_id so we can look up streaming bash output lines
-                    s.add_tool_message(content, Utc::now().to_rfc3339(), Some(tool_id));
+            Message::ToggleInspector => {
+                self.show_inspector = !self.show_inspector;
+                inspector::set_enabled(self.show_inspector);
+                if self.show_inspector {
+                    self.refresh_inspector();
                 }
             }
-            UiEvent::ToolCallResult(id, name, success, result_summary) => {
-                let icon = self.get_tool_icon(&name);
-
-                // Get cached display_args if available (contains formatted args like filename)
-                let display_detail = self.tool_args_cache.remove(&id).unwrap_or_default();
-
-                let display_name = match name.to_lowercase().as_str() {
-                    "execute_bash" => "Shell",
-                    "read_file" => "Read",
-                    "write_file" => "Write",
-                    "edit_file" => "Edit",
-                    "list_directory" => "List",
-                    "search_files" => "Search",
-                    "web_search" => "Web",
-                    "mcp_call" => "MCP",
-                    "visioneer" => "Vision",
-                    "ask_question" => "Question",
-                    _ => &name,
-                };
-
-                let status = if success { "✓" } else { "✗" };
-
-                // If we have cached display args, extract just the args part (after the •)
-                let extra_info = if !display_detail.is_empty() {
-                    // display_detail is like "Read • path: \"arula_core/Cargo.toml\""
-                    if let Some(args_part) = display_detail.split(" • ").nth(1) {
-                        // Clean up JSON formatting: remove 'key: "' prefix and trailing '"'
-                        let cleaned = if let Some(colon_pos) = args_part.find(':') {
-                            // Extract value after the colon
-                            let value_part = args_part[colon_pos + 1..].trim();
-                            // Remove surrounding quotes if present
-                            value_part.trim_matches('"').to_string()
-                        } else {
-                            // No colon, just use as-is
-                            args_part.trim_matches('"').to_string()
-                        };
-                        format!(" {}", cleaned)
-                    } else {
-                        String::new()
+            Message::InspectorFilterChanged(filter) => {
+                self.inspector_filter = filter;
+            }
+            Message::InspectorEntryToggled(index) => {
+                if !self.inspector_expanded.remove(&index) {
+                    self.inspector_expanded.insert(index);
+                }
+            }
+            Message::LogLevelFilterChanged(level) => {
+                self.log_level_filter = level;
+            }
+            Message::ClearLogs => {
+                logging::clear_logs();
+                self.log_records.clear();
+            }
+            Message::CloseDirectoryPopup => {
+                self.show_directory_popup = false;
+                self.show_directory_custom_input = false;
+                self.directory_draft.clear();
+            }
+            Message::ShowDirectoryCustomInput => {
+                self.show_directory_custom_input = true;
+                // Pre-fill with current directory
+                self.directory_draft = self.current_directory.display().to_string();
+            }
+            Message::DirectoryDraftChanged(s) => {
+                self.directory_draft = s;
+            }
+            Message::ChangeDirectory => {
+                let path = PathBuf::from(&self.directory_draft);
+                self.apply_directory_selection(path);
+            }
+            Message::OpenDirectoryPicker => {
+                let start_dir = self.current_directory.clone();
+                return Task::future(async move {
+                    let path = FileDialog::new().set_directory(start_dir).pick_folder();
+                    Message::DirectoryPickerResult(path)
+                });
+            }
+            Message::DirectoryPickerResult(path) => {
+                if let Some(path) = path {
+                    self.apply_directory_selection(path);
+                }
+            }
+            Message::SelectRecentDirectory(path) => {
+                self.apply_directory_selection(path);
+            }
+            Message::ToggleDirectoryBookmark(path) => {
+                if self.bookmarked_directories.contains(&path) {
+                    self.bookmarked_directories.retain(|p| p != &path);
+                } else {
+                    self.bookmarked_directories.push(path);
+                }
+                if let Err(e) = self.config.set_bookmarked_directories(&self.bookmarked_directories) {
+                    tracing::warn!("Failed to save directory bookmarks: {}", e);
+                }
+            }
+            Message::ToggleConversations => {
+                self.show_conversations = !self.show_conversations;
+                if self.show_conversations {
+                    return Task::future(async move {
+                        Message::RefreshConversations
+                    });
+                }
+            }
+            Message::SelectWorkspace(workspace) => return self.switch_workspace(workspace),
+            Message::AddWorkspace => {
+                return Task::future(async {
+                    Message::WorkspacePicked(FileDialog::new().pick_folder())
+                });
+            }
+            Message::WorkspacePicked(Some(root)) => {
+                let workspace = Workspace::new(root);
+                if !self.workspaces.contains(&workspace) {
+                    self.workspaces.push(workspace.clone());
+                    if let Err(e) = self.config.set_workspaces(&self.workspaces) {
+                        tracing::warn!("Failed to save workspaces: {}", e);
+                    }
+                }
+                return self.switch_workspace(workspace);
+            }
+            Message::WorkspacePicked(None) => {}
+            Message::RefreshConversations => {
+                // Reload as many entries as are already shown so the list doesn't shrink
+                let limit = self.conversations_next_offset.max(CONVERSATION_PAGE_SIZE);
+                if let Ok(page) = self.conversation_manager.list_conversations_page(0, limit) {
+                    self.saved_conversations = page.conversations;
+                    self.conversations_total = page.total;
+                    self.conversations_next_offset = page.next_offset;
+                }
+                self.refresh_pinned_and_archived();
+            }
+            Message::LoadMoreConversations => {
+                let offset = self.conversations_next_offset;
+                if offset < self.conversations_total {
+                    if let Ok(page) = self
+                        .conversation_manager
+                        .list_conversations_page(offset, CONVERSATION_PAGE_SIZE)
+                    {
+                        self.saved_conversations.extend(page.conversations);
+                        self.conversations_total = page.total;
+                        self.conversations_next_offset = page.next_offset;
                     }
+                }
+            }
+            Message::ConversationsScrolled(viewport) => {
+                if viewport.relative_offset().y > 0.9
+                    && self.conversations_next_offset < self.conversations_total
+                {
+                    return Task::done(Message::LoadMoreConversations);
+                }
+            }
+            Message::ChatScrolled(viewport) => {
+                let at_bottom = viewport.content_bounds().height <= viewport.bounds().height
+                    || viewport.absolute_offset_reversed().y <= CHAT_BOTTOM_SLACK;
+                if at_bottom && !self.chat_at_bottom {
+                    // Back at the bottom: stick to the end again as content grows
+                    return self.jump_to_latest();
+                }
+                if !at_bottom && self.chat_at_bottom {
+                    self.chat_at_bottom = false;
+                    self.chat_seen_messages = self
+                        .sessions
+                        .get(self.current)
+                        .map_or(0, |s| s.messages.len());
+                }
+            }
+            Message::JumpToLatest => return self.jump_to_latest(),
+            Message::LoadConversation(conversation_id) => {
+                if let Ok(conversation) = self.conversation_manager.load_conversation(conversation_id) {
+                    // Create a new session from the loaded events
+                    let mut new_session = Session::from_events(conversation_id, &conversation.events);
+                    new_session.set_title(conversation.metadata.title.clone());
+                    new_session.interrupted = conversation.metadata.interrupted;
+                    new_session.draft = drafts::load()
+                        .remove(&conversation_id)
+                        .map(|draft| draft.text)
+                        .unwrap_or_default();
+                    
+                    // Add the new session
+                    self.stash_draft();
+                    self.sessions.push(new_session);
+                    self.current = self.sessions.len() - 1;
+                    
+                    // Close the conversations sidebar
+                    self.show_conversations = false;
+                    
+                    // Show the conversation's unsent draft, if any
+                    self.restore_draft();
+                    
+                    // Focus the input and show the end of the conversation
+                    return Task::batch([iced::widget::operation::focus(input_id()), self.jump_to_latest()]);
+                }
+            }
+            Message::DeleteConversation(conversation_id) => {
+                if let Err(err) = self.conversation_manager.delete_conversation(conversation_id) {
+                    tracing::warn!("Failed to delete conversation: {}", err);
                 } else {
-                    String::new()
+                    // Refresh the list
+                    return Task::future(async move {
+                        Message::RefreshConversations
+                    });
+                }
+            }
+            Message::DeleteConversations(ids) => {
+                self.delete_conversations_pending = (!ids.is_empty()).then_some(ids);
+            }
+            Message::CancelDeleteConversations => {
+                self.delete_conversations_pending = None;
+            }
+            Message::ConfirmDeleteConversations => {
+                let Some(ids) = self.delete_conversations_pending.take() else {
+                    return Task::none();
                 };
-
-                let content = format!(
-                    "{} {}{} {} {}",
-                    icon, display_name, extra_info, status, result_summary
-                );
-
-                // Find session index first, then update tool message
-                let session_idx = self.sessions.iter().position(|s| s.id == id);
-
-                if let Some(idx) = session_idx {
-                    let session = &mut self.sessions[idx];
-                    session.update_tool_message(content, Utc::now().to_rfc3339());
-
-                    // Auto-collapse the tool bubble when it completes
-                    // Find the last tool message index
-                    if let Some(msg_idx) = session.messages.iter().rposition(|m| m.is_tool()) {
-                        let key = format!("{}:{}", idx, msg_idx);
-                        let spring = self.tool_animations.entry(key).or_insert_with(|| {
-                            let mut s = Spring::default();
-                            s.position = 1.0; // Start expanded
-                            s.target = 1.0;
-                            s
-                        });
-                        // Set target to collapsed
-                        spring.set_target(0.0);
+                for id in ids {
+                    if let Err(err) = self.conversation_manager.delete_conversation(id) {
+                        tracing::warn!("Failed to delete conversation: {}", err);
                     }
+                    self.selected_conversations.remove(&id);
                 }
+                return Task::done(Message::RefreshConversations);
             }
-            UiEvent::BashOutputLine(_session_id, tool_call_id, line, is_stderr) => {
-                // Accumulate bash output lines for this tool call
-                self.bash_output_lines
-                    .entry(tool_call_id)
-                    .or_insert_with(Vec::new)
-                    .push((line, is_stderr));
+            Message::PinConversations(ids, pinned) => {
+                if let Err(err) = self.conversation_manager.set_pinned(&ids, pinned) {
+                    tracing::warn!("Failed to pin conversation: {}", err);
+                }
+                return Task::done(Message::RefreshConversations);
             }
-            UiEvent::AskQuestion { session_id: _, tool_call_id: _, question, options } => {
-                // Questions are now handled via polling from QUESTION_HANDLER
-                // This event is kept for backward compatibility but no action needed
-                eprintln!("🔔 AskQuestion event received (handled via polling): '{}' options={:?}", question, options);
+            Message::ArchiveConversations(ids, archived) => {
+                if let Err(err) = self.conversation_manager.set_archived(&ids, archived) {
+                    tracing::warn!("Failed to archive conversation: {}", err);
+                }
+                return Task::done(Message::RefreshConversations);
+            }
+            Message::ToggleArchivedConversations => {
+                self.show_archived = !self.show_archived;
+                self.refresh_pinned_and_archived();
+            }
+            Message::ToggleConversationSelection => {
+                self.selecting_conversations = !self.selecting_conversations;
+                self.selected_conversations.clear();
+            }
+            Message::ConversationSelected(id, selected) => {
+                if selected {
+                    self.selected_conversations.insert(id);
+                } else {
+                    self.selected_conversations.remove(&id);
+                }
+            }
+            Message::SelectAllConversations => {
+                self.selected_conversations = self
+                    .listed_conversations()
+                    .map(|conversation| conversation.id)
+                    .collect();
+            }
+            Message::ConversationTagDraftChanged(tag) => {
+                self.conversation_tag_draft = tag;
+            }
+            Message::TagSelectedConversations => {
+                let ids: Vec<uuid::Uuid> = self.selected_conversations.iter().copied().collect();
+                let tag = std::mem::take(&mut self.conversation_tag_draft);
+                if let Err(err) = self.conversation_manager.add_tag(&ids, &tag) {
+                    tracing::warn!("Failed to tag conversations: {}", err);
+                }
+                return Task::done(Message::RefreshConversations);
+            }
+            Message::ExportSelectedConversations => {
+                let mut files = Vec::new();
+                for conversation in self.listed_conversations() {
+                    if !self.selected_conversations.contains(&conversation.id) {
+                        continue;
+                    }
+                    match self.conversation_manager.load_conversation(conversation.id) {
+                        Ok(saved) => {
+                            let name: String = conversation
+                                .title
+                                .chars()
+                                .take(40)
+                                .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+                                .collect();
+                            let id = conversation.id.simple().to_string();
+                            files.push((
+                                format!("{}-{}.md", name, &id[..8]),
+                                transcript::to_markdown(&saved.events),
+                            ));
+                        }
+                        Err(err) => tracing::warn!("Failed to load conversation: {}", err),
+                    }
+                }
+                if files.is_empty() {
+                    return Task::none();
+                }
+                return Task::future(async move {
+                    let Some(dir) = FileDialog::new().pick_folder() else {
+                        return Message::ConversationsExported(None);
+                    };
+                    let count = files.len();
+                    let result = files
+                        .into_iter()
+                        .try_for_each(|(name, markdown)| std::fs::write(dir.join(name), markdown))
+                        .map(|_| (dir, count))
+                        .map_err(|e| e.to_string());
+                    Message::ConversationsExported(Some(result))
+                });
+            }
+            Message::ConversationsExported(result) => match result {
+                Some(Ok((dir, count))) => {
+                    tracing::info!("Exported {} conversations to {}", count, dir.display());
+                    self.selecting_conversations = false;
+                    self.selected_conversations.clear();
+                }
+                Some(Err(err)) => self.stream_error = Some(format!("Export failed: {}", err)),
+                None => {}
+            },
+            Message::CloseConversations => {
+                self.show_conversations = false;
+            }
+            Message::InitializeProjectWithAI => {
+                // Build a prompt for AI to enhance the manifest
+                let project_name = self.detected_project
+                    .as_ref()
+                    .map(|p| p.name.clone())
+                    .unwrap_or_else(|| "this project".to_string());
+                
+                let prompt = format!(
+                    "Please analyze {} and enhance the PROJECT.manifest file.\n\n\
+                    First, read the existing PROJECT.manifest file to see what's already there.\n\
+                    Then explore the key files to understand the project architecture.\n\n\
+                    Update the manifest with:\n\
+                    1. A comprehensive 'essence' section describing what the project does\n\
+                    2. Key architecture patterns and design decisions\n\
+                    3. Important gotchas, pitfalls, and conventions\n\
+                    4. Common development tasks and how to approach them\n\n\
+                    CRITICAL: Do NOT modify anything above the line `{}` - that section is \
+                    regenerated automatically when project files change.\n\
+                    Append your content BELOW that line. The first line of your content MUST be exactly:\n\
+                    `# AI-ENHANCED by ARULA`\n\n\
+                    Then add a comment with today's date, and include all the enhanced content.\n\
+                    Build on the detected information above but enrich it with your understanding.",
+                    project_name,
+                    MANIFEST_MARKER_AUTO_END
+                );
+                
+                // Add as user message and trigger send
+                if let Some(session) = self.sessions.get_mut(self.current) {
+                    if !session.is_streaming {
+                        session.add_user_message(prompt.clone(), Utc::now().to_rfc3339());
+                        session.set_streaming(true);
+                        
+                        let session_config = SessionConfig {
+                            system_prompt: build_enhanced_system_prompt(&self.config_form.system_prompt),
+                            model: effective_model(&self.config, self.project_config.as_ref()),
+                            max_tokens: session.max_tokens.unwrap_or(self.config_form.max_tokens as u32),
+                            temperature: effective_temperature(session.temperature, &self.config, &self.config_form),
+                            sampling: self.config.get_sampling(),
+                            ignore_budget: false,
+                            budget_usage: None,
+                        };
+                        
+                        let history = session.get_chat_history();
+                        let history_opt = if history.is_empty() { None } else { Some(history) };
+                        
+                        if let Err(err) = self.dispatcher.start_stream(
+                            session.id,
+                            prompt,
+                            history_opt,
+                            session_config,
+                        ) {
+                            tracing::error!("dispatch error: {err}");
+                            session.set_streaming(false);
+                        }
+                    }
+                }
+                return iced::widget::operation::focus(input_id());
             }
         }
         Task::none()
     }
 
-    fn apply_config_changes(&mut self) {
-        let selected_provider = self.config_form.provider.clone();
-        if self.config.active_provider != selected_provider {
-            if let Err(err) = self.config.switch_provider(&selected_provider) {
-                self.config_form
-                    .set_error(&format!("Failed to switch provider: {err}"));
-                return;
-            }
+    /// Scroll the chat to the end and keep following new content.
+    ///
+    /// A relative offset of 1.0 stays at the end as messages stream in, while
+    /// a manual scroll switches the list to an absolute offset that stays put.
+    fn jump_to_latest(&mut self) -> Task<Message> {
+        self.chat_at_bottom = true;
+        self.chat_seen_messages = 0;
+        iced::widget::operation::snap_to(chat_scroll_id(), scrollable::RelativeOffset::END)
+    }
+
+    /// Make `workspace` active: park the open sessions, restore the
+    /// workspace's own, swap the conversation list and move to its directory
+    /// so the manifest and project config feed the system prompt.
+    fn switch_workspace(&mut self, workspace: Workspace) -> Task<Message> {
+        let Some(index) = self.workspaces.iter().position(|w| w == &workspace) else {
+            return Task::none();
+        };
+        if index == self.active_workspace {
+            return Task::none();
+        }
+        // Stream events are routed to open sessions only
+        if self.sessions.iter().any(|s| s.is_streaming) {
+            self.stream_error =
+                Some("Wait for the current response to finish before switching workspaces".to_string());
+            return Task::none();
         }
+        let manager = match workspace.conversation_manager() {
+            Ok(manager) => manager,
+            Err(e) => {
+                self.stream_error = Some(format!("Couldn't open workspace {}: {}", workspace.name, e));
+                return Task::none();
+            }
+        };
 
-        self.config.set_model(&self.config_form.model);
-        self.config.set_api_url(&self.config_form.api_url);
-        self.config.set_api_key(&self.config_form.api_key);
+        // Message caches are keyed by session index, so they don't carry over
+        for session_idx in 0..self.sessions.len() {
+            self.drop_message_caches(session_idx, 0, &[]);
+        }
+        let previous_root = self.workspaces[self.active_workspace].root.clone();
+        let (sessions, current) = self
+            .parked_sessions
+            .remove(&workspace.root)
+            .unwrap_or_else(|| (vec![Session::new()], 0));
+        self.stash_draft();
+        let parked = std::mem::replace(&mut self.sessions, sessions);
+        self.parked_sessions.insert(previous_root, (parked, self.current));
+        self.current = current;
+        self.restore_draft();
+        self.active_workspace = index;
+
+        apply_conversation_retention(&manager, &self.config);
+        self.conversation_manager = manager;
+        self.saved_conversations.clear();
+        self.conversations_total = 0;
+        self.conversations_next_offset = 0;
+        if let Ok(page) = self
+            .conversation_manager
+            .list_conversations_page(0, CONVERSATION_PAGE_SIZE)
+        {
+            self.saved_conversations = page.conversations;
+            self.conversations_total = page.total;
+            self.conversations_next_offset = page.next_offset;
+        }
+        self.refresh_pinned_and_archived();
+        self.dispatcher.set_workspace(&workspace);
+        self.knowledge_documents = None;
 
-        if let Some(active) = self.config.get_active_provider_config_mut() {
-            active.thinking_enabled = Some(self.config_form.thinking_enabled);
-            active.web_search_enabled = Some(self.config_form.web_search_enabled);
-            active.tools_enabled = Some(self.config_form.ollama_tools_enabled);
-            active.streaming = Some(self.config_form.streaming_enabled);
+        if let Some(root) = workspace.root {
+            self.apply_directory_selection(root);
         }
 
-        // Save global settings
-        self.config.living_background_enabled = Some(self.config_form.living_background_enabled);
+        let parse_tasks: Vec<Task<Message>> = (0..self.sessions.len())
+            .map(|idx| self.parse_session_markdown(idx))
+            .collect();
+        Task::batch(parse_tasks).chain(self.jump_to_latest())
+    }
 
-        match self.config.save() {
-            Ok(_) => {
+    /// Knowledge base of the active workspace
+    fn knowledge_base(&mut self) -> Option<KnowledgeBase> {
+        match KnowledgeBase::for_workspace(&self.workspaces[self.active_workspace]) {
+            Ok(knowledge_base) => Some(knowledge_base),
+            Err(e) => {
+                self.knowledge_error = Some(format!("{:#}", e));
+                None
+            }
+        }
+    }
+
+    fn load_knowledge_documents(&mut self) -> Task<Message> {
+        self.knowledge_documents = None;
+        self.knowledge_error = None;
+        let Some(knowledge_base) = self.knowledge_base() else {
+            return Task::none();
+        };
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || knowledge_base.list())
+                    .await
+                    .unwrap_or_default()
+            },
+            Message::KnowledgeLoaded,
+        )
+    }
+
+    fn apply_directory_selection(&mut self, path: PathBuf) {
+        if !path.exists() || !path.is_dir() {
+            return;
+        }
+
+        if std::env::set_current_dir(&path).is_ok() {
+            self.recent_directories.retain(|p| p != &path);
+            self.recent_directories.insert(0, path.clone());
+            self.recent_directories.truncate(10);
+            if let Err(e) = self.config.set_recent_directories(&self.recent_directories) {
+                tracing::warn!("Failed to save recent directories: {}", e);
+            }
+
+            self.current_directory = path.clone();
+            self.show_directory_popup = false;
+            self.show_directory_custom_input = false;
+            self.directory_draft.clear();
+            
+            // Auto-detect project and create manifest if needed
+            self.detected_project = detect_project(&path);
+            
+            let manifest_path = path.join("PROJECT.manifest");
+            if let Some(ref project) = self.detected_project {
+                // Create the manifest or refresh its auto-generated section
+                let _ = refresh_manifest(&path, project);
+            }
+            self.manifest_watcher = Some(ManifestWatcher::new(
+                &path,
+                Duration::from_millis(MANIFEST_POLL_INTERVAL_MS),
+            ));
+            self.git_info = GitInfo::read(&path);
+            self.git_watcher = Some(GitWatcher::new(
+                &path,
+                Duration::from_millis(GIT_POLL_INTERVAL_MS),
+            ));
+            self.git_rebuild_at = None;
+            
+            // Check if manifest is AI-enhanced
+            self.manifest_is_ai_enhanced = is_ai_enhanced(&manifest_path);
+
+            // Re-evaluate the project overlay and rebuild the backend so the new
+            // directory's model, instructions and allowed tools take effect
+            self.project_config = ProjectConfig::load(&path);
+            if let Err(err) = self.dispatcher.update_backend(&self.config) {
+                tracing::warn!("Failed to apply project config: {}", err);
+            }
+        }
+    }
+
+    /// Helper function to get tool icons
+    fn get_tool_icon(&self, name: &str) -> &'static str {
+        match name.to_lowercase().as_str() {
+            "execute_bash" => "○",
+            "read_file" => "○",
+            "write_file" | "edit_file" => "□",
+            "list_directory" => "◇",
+            "search_files" => "○",
+            "web_search" => "⭕",
+            "fetch_url" => "⭕",
+            "mcp_call" => "◊",
+            "visioneer" => "○",
+            "ask_question" => "❓",
+            "code_outline" => "◇",
+            "capture_screen" => "▣",
+            "transcribe_audio" => "♪",
+            _ => "□",
+        }
+    }
+
+    /// Re-read the inspector capture, dropping expansions from a previous turn
+    fn refresh_inspector(&mut self) {
+        let snapshot = inspector::snapshot();
+        if snapshot.started_at != self.inspector_snapshot.started_at {
+            self.inspector_expanded.clear();
+        }
+        self.inspector_snapshot = snapshot;
+        self.last_inspector_refresh = Instant::now();
+    }
+
+    /// Feeds the text streamed since the last render into each dirty message's
+    /// incremental markdown, so only the trailing unparsed region is parsed.
+    fn render_streaming_markdown(&mut self) {
+        for (session_idx, msg_idx) in self.markdown_dirty.drain() {
+            let Some(msg) = self
+                .sessions
+                .get(session_idx)
+                .and_then(|s| s.messages.get(msg_idx))
+            else {
+                continue;
+            };
+            let key = format!("{}:{}", session_idx, msg_idx);
+            let (md_content, parsed_len) = self
+                .markdown_streams
+                .entry(key)
+                .or_insert_with(|| (markdown::Content::new(), 0));
+            if msg.content.len() > *parsed_len {
+                md_content.push_str(&msg.content[*parsed_len..]);
+                *parsed_len = msg.content.len();
+            }
+        }
+        self.last_stream_render = Instant::now();
+    }
+
+    /// Forget editor, markdown and animation state of a session's messages
+    /// from `from_msg` on, along with the bash output of their tool calls
+    fn drop_message_caches(&mut self, session_idx: usize, from_msg: usize, tool_call_ids: &[String]) {
+        let stale = |key: &String| {
+            key.split_once(':').is_some_and(|(s, m)| {
+                s.parse() == Ok(session_idx) && m.parse::<usize>().is_ok_and(|m| m >= from_msg)
+            })
+        };
+        self.message_editors.retain(|k, _| !stale(k));
+        self.markdown_cache.retain(|k, _| !stale(k));
+        self.markdown_streams.retain(|k, _| !stale(k));
+        self.markdown_pending.retain(|k, _| !stale(k));
+        self.tool_animations.retain(|k, _| !stale(k));
+        self.markdown_dirty
+            .retain(|(s, m)| *s != session_idx || *m < from_msg);
+        for tool_call_id in tool_call_ids {
+            self.bash_output_lines.remove(tool_call_id);
+        }
+    }
+
+    /// Starts background markdown parses for the AI messages of a session that
+    /// were streamed incrementally or have not been parsed yet.
+    fn parse_session_markdown(&mut self, session_idx: usize) -> Task<Message> {
+        let Some(session) = self.sessions.get(session_idx) else {
+            return Task::none();
+        };
+
+        let mut tasks = Vec::new();
+        for (msg_idx, msg) in session.messages.iter().enumerate() {
+            let key = format!("{}:{}", session_idx, msg_idx);
+            let is_parsed =
+                self.markdown_cache.contains_key(&key) && !self.markdown_streams.contains_key(&key);
+            if !msg.is_ai() || is_parsed {
+                continue;
+            }
+            self.markdown_revision += 1;
+            self.markdown_pending.insert(key.clone(), self.markdown_revision);
+            tasks.push(parse_markdown_task(key, self.markdown_revision, msg.content.clone()));
+        }
+        Task::batch(tasks)
+    }
+
+    fn handle_ui_event(&mut self, ev: UiEvent) -> Task<Message> {
+        inspector::record_event(InspectorSource::UiEvent, &ev);
+        // Any further event of the session means the retried request went through
+        if let Some((retry_id, _)) = &self.retry_notice {
+            if ev.session_id() == Some(*retry_id) && !matches!(ev, UiEvent::StreamRetrying(..)) {
+                self.retry_notice = None;
+            }
+        }
+        match ev {
+            UiEvent::ConversationStarters(starters) => {
+                self.conversation_starters = starters;
+                return Task::none();
+            }
+            UiEvent::ConversationTitle(title) => {
+                // Update the current session's title
+                if let Some(s) = self.sessions.get_mut(self.current) {
+                    s.set_title(title);
+                }
+            }
+            UiEvent::UserMessage { content: _, timestamp: _ } => {
+                // Clear starters when conversation starts
+                self.conversation_starters.clear();
+                // User messages are handled locally in the session, no action needed
+            }
+            UiEvent::AiMessage { content: _, timestamp: _ } => {
+                // AI messages are handled via Token events, no action needed
+            }
+            UiEvent::StreamStarted(id) => {
+                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
+                    s.set_streaming(true);
+                }
+            }
+            UiEvent::Token(id, delta, is_final) => {
+                // Find session index for syncing editors
+                let session_idx = self.sessions.iter().position(|s| s.id == id);
+                if let (Some(idx), false) = (session_idx, is_final) {
+                    self.save_partial_conversation(idx);
+                }
+
+                if let Some(idx) = session_idx {
+                    let session = &mut self.sessions[idx];
+                    
+                    // Check if this is a non-streaming response (complete response at once)
+                    let is_non_streaming = !session.is_streaming() && is_final;
+                    session.record_streamed_text(&delta);
+                    
+                    if is_non_streaming {
+                        // For non-streaming responses, use add_ai_message to create separate bubbles
+                        session.add_ai_message(delta, Utc::now().to_rfc3339());
+                    } else {
+                        // For streaming responses, use append_ai_message
+                        session.append_ai_message(delta, Utc::now().to_rfc3339());
+                    }
+
+                    // Get or create the text editor content for the AI message
+                    let msg_idx = session.messages.len() - 1;
+                    let key = format!("{}:{}", idx, msg_idx);
+
+                    // Handle message editor updates differently for streaming vs non-streaming
+                    if is_non_streaming {
+                        // For non-streaming responses, always update the editor content
+                        self.message_editors.insert(
+                            key.clone(),
+                            text_editor::Content::with_text(&session.messages[msg_idx].content),
+                        );
+                    } else {
+                        // For streaming responses, only update if it doesn't exist or if this is final
+                        if !self.message_editors.contains_key(&key) {
+                            self.message_editors.insert(
+                                key.clone(),
+                                text_editor::Content::with_text(&session.messages[msg_idx].content),
+                            );
+                        } else if is_final {
+                            // Only update on final token to avoid constant recreation
+                            if let Some(editor_content) = self.message_editors.get_mut(&key) {
+                                let text = &session.messages[msg_idx].content;
+                                *editor_content = text_editor::Content::with_text(text);
+                            }
+                        }
+                    }
+
+                    // Streaming markdown is rendered in batches from Tick; the
+                    // full parse runs in the background once the response is final
+                    if !is_non_streaming && session.messages[msg_idx].is_ai() {
+                        self.markdown_dirty.insert((idx, msg_idx));
+                    }
+
+                    // Handle final token differently for streaming vs non-streaming
+                    if is_final {
+                        if !is_non_streaming {
+                            // For streaming responses, flush any remaining buffer content
+                            session.flush_ai_buffer(Utc::now().to_rfc3339());
+                        }
+                        session.set_streaming(false);
+                        self.markdown_dirty.retain(|(s, _)| *s != idx);
+                        // Re-focus input when the shown response completes
+                        return Task::batch([
+                            self.parse_session_markdown(idx),
+                            self.focus_input_if_current(id),
+                        ]);
+                    }
+                }
+            }
+            UiEvent::StreamFinished(id) => {
+                self.offline_retries.remove(&id);
+                let session_idx = self.sessions.iter().position(|s| s.id == id);
+                let in_background = !self.window_focused || self.window_minimized;
+                if let Some(idx) = session_idx {
+                    self.markdown_dirty.retain(|(s, _)| *s != idx);
+                    // Flag answers that arrive in a background tab or while
+                    // the window is in the background
+                    if idx != self.current || in_background {
+                        self.sessions[idx].unread = true;
+                    }
+                }
+                // Flash the taskbar entry / bounce the dock icon
+                let attention = if in_background && session_idx.is_some() {
+                    window::latest().and_then(|id| {
+                        window::request_user_attention(id, Some(window::UserAttention::Informational))
+                    })
+                } else {
+                    Task::none()
+                };
+                let model = effective_model(&self.config, self.project_config.as_ref());
+                self.end_partial_saves(id, false);
+                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
+                    // Flush any remaining AI content from the buffer
+                    s.flush_ai_buffer(Utc::now().to_rfc3339());
+                    s.set_streaming(false);
+                    s.finish_stream_stats(&model);
+                    s.attach_sources();
+                    
+                    // Save the conversation
+                    let events = s.to_ui_events();
+                    if let Err(err) = self.conversation_manager.save_conversation(
+                        s.id,
+                        &events,
+                        model,
+                    ) {
+                        tracing::warn!("Failed to save conversation: {}", err);
+                    }
+                }
+                // Re-parse with any flushed content, then re-focus input when stream finishes
+                let parse_task = session_idx
+                    .map(|idx| self.parse_session_markdown(idx))
+                    .unwrap_or_else(Task::none);
+                self.dispatch_queued_prompts();
+                return Task::batch([parse_task, self.focus_input_if_current(id), attention]);
+            }
+            UiEvent::StreamErrored(id, err) => {
+                tracing::error!(session = %id, "stream error: {err}");
+                // The answer won't continue, whether or not the prompt is requeued
+                self.end_partial_saves(id, true);
+                if provider_check::is_connectivity_error(&err) {
+                    if let Some(task) = self.requeue_offline_prompt(id, &err) {
+                        return task;
+                    }
+                }
+                self.offline_retries.remove(&id);
+                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
+                    s.set_streaming(false);
+                }
+                // Store error for display to user
+                self.report_stream_error(id, err);
+                // Re-focus input on error
+                return self.focus_input_if_current(id);
+            }
+            UiEvent::Thinking(id, text) => {
+                // Create a thinking/reasoning bubble to show the AI's thought process
+                tracing::trace!(session = %id, "Received Thinking event: {:?}", text);
+                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
+                    s.record_streamed_text(&text);
+                    s.append_thinking_message(text, Utc::now().to_rfc3339());
+                } else {
+                    tracing::debug!(session = %id, "Thinking event for unknown session");
+                }
+            }
+            UiEvent::ToolCallStart(id, tool_id, name, display_args) => {
+                let icon = self.get_tool_icon(&name);
+                // display_args already contains "{display_name} • {formatted_args}"
+                let content = format!("{} {}", icon, display_args);
+                let preview_path = (name == "read_file")
+                    .then(|| read_file_path(&display_args, &self.current_directory))
+                    .flatten();
+
+                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
+                    s.record_tool_start(display_args.clone());
+                    // Pass tool_id so we can look up streaming bash output lines
+                    s.add_tool_message(content, Utc::now().to_rfc3339(), Some(tool_id));
+                    if let Some(path) = preview_path {
+                        s.set_tool_preview_path(path);
+                    }
+                }
+                // Cache the display_args for later use in ToolCallResult
+                self.tool_args_cache.insert(id, display_args);
+            }
+            UiEvent::ToolCallResult(id, name, success, result_summary) => {
+                let icon = self.get_tool_icon(&name);
+
+                // Get cached display_args if available (contains formatted args like filename)
+                let display_detail = self.tool_args_cache.remove(&id).unwrap_or_default();
+
+                let display_name = match name.to_lowercase().as_str() {
+                    "execute_bash" => "Shell",
+                    "read_file" => "Read",
+                    "write_file" => "Write",
+                    "edit_file" => "Edit",
+                    "list_directory" => "List",
+                    "search_files" => "Search",
+                    "web_search" => "Web",
+                    "fetch_url" => "Fetch",
+                    "mcp_call" => "MCP",
+                    "visioneer" => "Vision",
+                    "ask_question" => "Question",
+                    "code_outline" => "Outline",
+                    "capture_screen" => "Screenshot",
+                    "transcribe_audio" => "Transcribe",
+                    _ => &name,
+                };
+
+                let status = if success { "✓" } else { "✗" };
+
+                // If we have cached display args, extract just the args part (after the •)
+                let extra_info = if !display_detail.is_empty() {
+                    // display_detail is like "Read • path: \"arula_core/Cargo.toml\""
+                    if let Some(args_part) = display_detail.split(" • ").nth(1) {
+                        // Clean up JSON formatting: remove 'key: "' prefix and trailing '"'
+                        let cleaned = if let Some(colon_pos) = args_part.find(':') {
+                            // Extract value after the colon
+                            let value_part = args_part[colon_pos + 1..].trim();
+                            // Remove surrounding quotes if present
+                            value_part.trim_matches('"').to_string()
+                        } else {
+                            // No colon, just use as-is
+                            args_part.trim_matches('"').to_string()
+                        };
+                        format!(" {}", cleaned)
+                    } else {
+                        String::new()
+                    }
+                } else {
+                    String::new()
+                };
+
+                let content = format!(
+                    "{} {}{} {} {}",
+                    icon, display_name, extra_info, status, result_summary
+                );
+
+                // Find session index first, then update tool message
+                let session_idx = self.sessions.iter().position(|s| s.id == id);
+
+                if let Some(idx) = session_idx {
+                    let session = &mut self.sessions[idx];
+                    session.update_tool_message(content, Utc::now().to_rfc3339());
+                    session.record_tool_end();
+
+                    // Auto-collapse the tool bubble when it completes, unless
+                    // full tool output was asked for
+                    let keep_output = self.config.get_tool_verbosity() == ToolVerbosity::Full;
+                    if let Some(msg_idx) = session
+                        .messages
+                        .iter()
+                        .rposition(|m| m.is_tool())
+                        .filter(|_| !keep_output)
+                    {
+                        let key = format!("{}:{}", idx, msg_idx);
+                        let spring = self.tool_animations.entry(key).or_insert_with(|| {
+                            let mut s = Spring::default();
+                            s.position = 1.0; // Start expanded
+                            s.target = 1.0;
+                            s
+                        });
+                        // Set target to collapsed
+                        spring.set_target(0.0);
+                    }
+                }
+            }
+            UiEvent::BudgetExceeded(id, message) => {
+                tracing::warn!(session = %id, "{message}");
+                self.budget_notice = Some((id, message));
+            }
+            UiEvent::StreamRetrying(id, message) => {
+                self.retry_notice = Some((id, message));
+            }
+            UiEvent::ToolRound(id, round) => {
+                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
+                    s.record_tool_round(round);
+                }
+            }
+            UiEvent::FinishReason(id, reason) => {
+                // Cut-off or filtered answers would otherwise look complete
+                if let Some(notice) = reason.notice() {
+                    tracing::warn!(session = %id, "{notice}");
+                    self.report_stream_error(id, notice);
+                }
+            }
+            UiEvent::Usage(id, usage) => {
+                tracing::debug!(
+                    session = %id,
+                    prompt = usage.prompt_tokens,
+                    completion = usage.completion_tokens,
+                    cache_read = usage.cache_read_input_tokens,
+                    cache_creation = usage.cache_creation_input_tokens,
+                    "Request usage"
+                );
+                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
+                    s.record_completion_tokens(usage.completion_tokens);
+                }
+            }
+            UiEvent::FileSnapshot(id, snapshot_id) => {
+                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
+                    s.set_tool_snapshot(snapshot_id);
+                }
+            }
+            UiEvent::Sources(id, sources) => {
+                if let Some(s) = self.sessions.iter_mut().find(|s| s.id == id) {
+                    s.add_sources(sources);
+                }
+            }
+            UiEvent::BashOutputLine(_session_id, tool_call_id, line, is_stderr) => {
+                // Accumulate bash output lines for this tool call
+                self.bash_output_lines
+                    .entry(tool_call_id)
+                    .or_insert_with(Vec::new)
+                    .push((line, is_stderr));
+            }
+            UiEvent::AskQuestion { session_id: _, tool_call_id: _, question, options } => {
+                // Questions are now handled via polling from QUESTION_HANDLER
+                // This event is kept for backward compatibility but no action needed
+                tracing::debug!("AskQuestion event received (handled via polling): '{}' options={:?}", question, options);
+            }
+        }
+        Task::none()
+    }
+
+    /// Focuses the chat input if `session_id` is the shown session, so a
+    /// stream ending in a background tab doesn't take focus
+    fn focus_input_if_current(&self, session_id: uuid::Uuid) -> Task<Message> {
+        if self.sessions.get(self.current).is_some_and(|s| s.id == session_id) {
+            iced::widget::operation::focus(input_id())
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Shows a stream's error now if its session is shown, or keeps it on the
+    /// session until its tab is opened
+    fn report_stream_error(&mut self, session_id: uuid::Uuid, err: String) {
+        let current = self.current;
+        match self.sessions.iter().position(|s| s.id == session_id) {
+            Some(idx) if idx != current => {
+                let session = &mut self.sessions[idx];
+                session.background_error = Some(err);
+                session.unread = true;
+            }
+            _ => self.stream_error = Some(err),
+        }
+    }
+
+    /// Ollama's base URL from the endpoint in the settings form
+    fn ollama_base_url(&self) -> String {
+        let api_url = self.config_form.api_url.trim();
+        ollama::base_url((!api_url.is_empty()).then_some(api_url))
+    }
+
+    /// List the installed Ollama models when Ollama is the selected provider
+    fn refresh_ollama_models(&mut self) -> Task<Message> {
+        if !self.config_form.provider.eq_ignore_ascii_case("ollama") {
+            return Task::none();
+        }
+        let base_url = self.ollama_base_url();
+        Task::perform(
+            async move {
+                ollama::list_models(&base_url)
+                    .await
+                    .map_err(|e| format!("{e:#}"))
+            },
+            Message::OllamaModelsLoaded,
+        )
+    }
+
+    /// Saves the settings form, then checks the provider with a test request
+    /// so a wrong key, URL or model shows up here rather than on the next prompt.
+    /// The check only runs when the provider, URL, key or model changed.
+    fn apply_config_changes(&mut self) -> Task<Message> {
+        let connection = |config: &Config| {
+            (
+                config.active_provider.clone(),
+                config.get_api_url(),
+                config.get_api_key(),
+                config.get_model(),
+            )
+        };
+        let connection_before = connection(&self.config);
+        let sampling = match self.config_form.sampling() {
+            Ok(sampling) => sampling,
+            Err(err) => {
+                self.config_form.set_error(&err);
+                return Task::none();
+            }
+        };
+
+        let selected_provider = self.config_form.provider.clone();
+        if self.config.active_provider != selected_provider {
+            if let Err(err) = self.config.switch_provider(&selected_provider) {
+                self.config_form
+                    .set_error(&format!("Failed to switch provider: {err}"));
+                return Task::none();
+            }
+        }
+
+        self.config.set_model(&self.config_form.model);
+        self.config.set_api_url(&self.config_form.api_url);
+        self.config.set_api_key(&self.config_form.api_key);
+
+        if let Some(active) = self.config.get_active_provider_config_mut() {
+            active.thinking_enabled = Some(self.config_form.thinking_enabled);
+            active.web_search_enabled = Some(self.config_form.web_search_enabled);
+            active.tools_enabled = Some(self.config_form.ollama_tools_enabled);
+            active.streaming = Some(self.config_form.streaming_enabled);
+        }
+
+        // Save global settings
+        self.config.living_background_enabled = Some(self.config_form.living_background_enabled);
+        self.config.update_check_enabled = Some(self.config_form.update_check_enabled);
+        self.config.tool_verbosity = Some(self.config_form.tool_verbosity);
+        let pinned_starters = self.config_form.pinned_starters();
+        let starters_changed = self.config.get_starter_mode() != self.config_form.starter_mode
+            || self.config.get_pinned_starters() != pinned_starters;
+        self.config.starter_mode = Some(self.config_form.starter_mode);
+        self.config.pinned_starters = pinned_starters;
+        let language = self.config.get_spellcheck_language();
+        if self.config_form.spellcheck_enabled && spellcheck::find(&language).is_none() {
+            self.config_form.set_error(&format!(
+                "Spellcheck: no {language} dictionary found; put {language}.dic and {language}.aff in ~/.arula/dictionaries"
+            ));
+            return Task::none();
+        }
+        self.config.spellcheck_enabled = Some(self.config_form.spellcheck_enabled);
+        let retention = self.config_form.conversation_retention_days.trim();
+        self.config.conversation_retention_days = if retention.is_empty() {
+            None
+        } else {
+            match retention.parse::<u32>() {
+                Ok(days) => Some(days),
+                Err(_) => {
+                    self.config_form
+                        .set_error("Delete conversations after: enter a number of days, or leave it empty");
+                    return Task::none();
+                }
+            }
+        };
+        if !self.config_form.update_check_enabled {
+            self.update_available = None;
+        }
+        self.config.sampling = (sampling != Default::default()).then_some(sampling);
+
+        match self.config.save() {
+            Ok(_) => {
                 if let Err(err) = self.dispatcher.update_backend(&self.config) {
                     self.config_form
                         .set_error(&format!("Saved, but backend failed to refresh: {err}"));
-                    return;
+                    return Task::none();
+                }
+                if starters_changed && self.sessions[self.current].messages.is_empty() {
+                    self.conversation_starters.clear();
+                    self.dispatcher.generate_conversation_starters();
+                }
+                self.config_form = ConfigForm::from_config(&self.config);
+                if connection(&self.config) == connection_before {
+                    self.config_form.set_success("Settings saved");
+                    return self.load_dictionary();
+                }
+                self.config_form
+                    .set_success("Settings saved, checking the connection…");
+                self.provider_check_generation += 1;
+                let generation = self.provider_check_generation;
+                let config = self.config.clone();
+                Task::batch([
+                    Task::perform(
+                        async move {
+                            provider_check::check(&config)
+                                .await
+                                .map_err(|e| e.to_string())
+                        },
+                        move |result| Message::ProviderChecked(generation, result),
+                    ),
+                    self.load_dictionary(),
+                ])
+            }
+            Err(err) => {
+                self.config_form
+                    .set_error(&format!("Failed to save settings: {err}"));
+                Task::none()
+            }
+        }
+    }
+
+    /// Persists the theme picked in Settings → Appearance.
+    fn save_theme(&mut self) {
+        if let Err(err) = self.config.set_theme(&self.theme_mode.name().to_lowercase()) {
+            tracing::warn!("Failed to save theme: {}", err);
+        }
+    }
+
+    /// Starts fetching the model list of `provider` into `model_list`.
+    fn fetch_models(&mut self, provider: &str) {
+        self.models_loading = true;
+        self.model_list.clear();
+        match provider.to_lowercase().as_str() {
+            "openai" => self.dispatcher.fetch_openai_models(),
+            "anthropic" => self.dispatcher.fetch_anthropic_models(),
+            "ollama" => self.dispatcher.fetch_ollama_models(),
+            "z.ai coding plan" | "z.ai" | "zai" => self.dispatcher.fetch_zai_models(),
+            "openrouter" => self.dispatcher.fetch_openrouter_models(),
+            "xai" => self.dispatcher.fetch_xai_models(),
+            "deepseek" => self.dispatcher.fetch_deepseek_models(),
+            "lm studio" => self.dispatcher.fetch_lm_studio_models(),
+            "llamafile" => self.dispatcher.fetch_llamafile_models(),
+            _ => {
+                self.models_loading = false;
+            }
+        }
+    }
+
+    /// Moves the setup wizard one step forward; the last step saves the config.
+    fn advance_onboarding(&mut self) -> Task<Message> {
+        let Some(onboarding) = self.onboarding.as_mut() else {
+            return Task::none();
+        };
+        if !onboarding.can_continue() {
+            return Task::none();
+        }
+        if let Some(next) = onboarding.step.next() {
+            onboarding.step = next;
+            if next == OnboardingStep::Model {
+                // The model fetchers read the key and URL from the backend's config
+                let onboarding = onboarding.clone();
+                if let Err(err) = onboarding
+                    .apply_to(&mut self.config)
+                    .and_then(|_| self.dispatcher.update_backend(&self.config))
+                {
+                    tracing::warn!("Failed to apply setup choices: {}", err);
+                }
+                self.fetch_models(&onboarding.provider);
+            }
+            return Task::none();
+        }
+
+        let onboarding = onboarding.clone();
+        if let Err(err) = onboarding
+            .apply_to(&mut self.config)
+            .and_then(|_| self.config.save())
+            .and_then(|_| self.dispatcher.update_backend(&self.config))
+        {
+            if let Some(onboarding) = self.onboarding.as_mut() {
+                onboarding.test_result = Some(Err(format!("Failed to save settings: {err}")));
+            }
+            return Task::none();
+        }
+        self.onboarding = None;
+        self.config_form = ConfigForm::from_config(&self.config);
+        self.theme_mode = self.config_form.theme_mode;
+        self.dispatcher.generate_conversation_starters();
+        Task::batch([
+            iced::widget::operation::focus(input_id()),
+            Task::done(Message::PingProvider),
+        ])
+    }
+
+    /// Window title: "<conversation> — <project> — Arula". iced has no
+    /// taskbar or dock badge, so the badge goes in front of the title: "●"
+    /// while an answer streams, "(n)" for answers not looked at yet.
+    fn title(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(session) = self.sessions.get(self.current) {
+            let title = session.title.trim();
+            if !title.is_empty() {
+                parts.push(title.to_string());
+            }
+        }
+        let project = self
+            .detected_project
+            .as_ref()
+            .map(|p| p.name.clone())
+            .or_else(|| {
+                let workspace = self.workspaces.get(self.active_workspace)?;
+                workspace.root.is_some().then(|| workspace.name.clone())
+            });
+        parts.extend(project.filter(|name| !name.trim().is_empty()));
+        parts.push("Arula".to_string());
+        let title = parts.join(" — ");
+
+        let unread = self.sessions.iter().filter(|s| s.unread).count();
+        if self.sessions.iter().any(|s| s.is_streaming) {
+            format!("● {title}")
+        } else if unread > 0 {
+            format!("({unread}) {title}")
+        } else {
+            title
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let stream = self.dispatcher.subscription().map(Message::Received);
+        let remote = self.dispatcher.remote_subscription().map(Message::Remote);
+        // Drop to an idle tick rate when nobody is looking at the window
+        let tick_interval = if self.window_focused && !self.window_minimized {
+            TICK_INTERVAL_MS
+        } else {
+            IDLE_TICK_INTERVAL_MS
+        };
+        let ticks = time::every(Duration::from_millis(tick_interval)).map(|_| Message::Tick);
+        let window_events = window::events().map(|(_, event)| Message::Window(event));
+        let shortcuts = iced::event::listen_with(shortcut_message);
+        let mut subscriptions = vec![stream, remote, ticks, window_events, shortcuts];
+        if self.theme_mode == ThemeMode::Auto {
+            subscriptions.push(
+                time::every(Duration::from_millis(SYSTEM_THEME_POLL_MS))
+                    .map(|_| Message::CheckSystemTheme),
+            );
+        }
+        if self.onboarding.is_none() && !self.window_minimized {
+            let interval = if self.is_offline() {
+                PROVIDER_OFFLINE_POLL_MS
+            } else {
+                PROVIDER_HEALTH_POLL_MS
+            };
+            subscriptions.push(
+                time::every(Duration::from_millis(interval)).map(|_| Message::PingProvider),
+            );
+        }
+        Subscription::batch(subscriptions)
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let theme_mode = self.theme_mode.resolve(self.system_dark);
+        let pal = palette_from_mode(theme_mode);
+        
+        // Debug: print current theme mode
+        static LAST_THEME: std::sync::atomic::AtomicU8 = std::sync::atomic::AtomicU8::new(255);
+        let current_theme_id = match theme_mode {
+            ThemeMode::Light => 0,
+            ThemeMode::Dark => 1,
+            ThemeMode::Black => 2,
+            ThemeMode::Auto => 3,
+        };
+        if LAST_THEME.load(std::sync::atomic::Ordering::Relaxed) != current_theme_id {
+            tracing::debug!("View rendering with theme: {:?}, background: {:?}", theme_mode, pal.background);
+            LAST_THEME.store(current_theme_id, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        // Show error dialog if initialization failed
+        if let Some(ref error) = self.init_error {
+            return self.error_view(error, pal);
+        }
+        if let Some(onboarding) = &self.onboarding {
+            return self.onboarding_view(onboarding, pal);
+        }
+
+        let background = Canvas::new(LivingBackground::<Message>::new(
+            &self.bg_state,
+            pal,
+            self.bg_opacity,
+        ))
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+        // Get current session streaming state for typing indicator
+        let session = &self.sessions[self.current];
+        let is_streaming = session.is_streaming;
+
+        // Calculate width offset for top bar and input bar based on layout offset (smooth animation)
+        let sidebar_width = 340.0 * self.conversations_layout_offset;
+
+        // Build main layer with top bar, chat content, optional typing indicator, and input
+        let mut main_content: Vec<Element<'_, Message>> =
+            vec![self.top_bar(pal, sidebar_width), self.tab_strip(pal, sidebar_width)];
+        main_content.push(self.chat_panel(pal));
+
+        // Add typing indicator above input when streaming
+        if is_streaming {
+            main_content.push(self.typing_indicator(pal));
+        }
+
+        main_content.push(self.input_area(pal, sidebar_width));
+
+        let main_layer = column(main_content)
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+        let progress = self.menu_state.progress();
+        let overlay = if progress > 0.01 {
+            self.settings_overlay(pal).into()
+        } else {
+            Space::new().into()
+        };
+
+        // Error notification overlay
+        let error_overlay: Element<'_, Message> = if let Some(ref error) = self.stream_error {
+            // Extract a user-friendly error message for collapsed view
+            let short_error = if error.contains("502 Bad Gateway") {
+                "Server temporarily unavailable. Please try again.".to_string()
+            } else if error.contains("500") {
+                "Server error occurred. Please try again later.".to_string()
+            } else if error.contains("timeout") || error.contains("Timeout") {
+                "Request timed out. Please check your connection.".to_string()
+            } else if error.chars().count() > 60 && !self.error_expanded {
+                // Find safe character boundary
+                let truncate_at = error
+                    .char_indices()
+                    .take(60)
+                    .last()
+                    .map(|(i, c)| i + c.len_utf8())
+                    .unwrap_or(0);
+                format!("{}...", &error[..truncate_at])
+            } else {
+                error.clone()
+            };
+
+            let chevron = if self.error_expanded {
+                bootstrap::chevron_down()
+            } else {
+                bootstrap::chevron_right()
+            };
+
+            // Header row (always visible, clickable to expand)
+            let header_row = row![
+                text("!")
+                    .size(18)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(Color {
+                            r: 1.0,
+                            g: 0.8,
+                            b: 0.2,
+                            a: 1.0
+                        })
+                    }),
+                Space::new().width(Length::Fixed(8.0)),
+                chevron
+                    .size(12)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(Color { a: 0.7, ..pal.text })
+                    }),
+                Space::new().width(Length::Fixed(8.0)),
+                text(if self.error_expanded {
+                    "Error Details".to_string()
+                } else {
+                    short_error
+                })
+                .size(13)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.text)
+                }),
+                Space::new().width(Length::Fill),
+                button(
+                    text("×")
+                        .size(14)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.text)
+                        })
+                )
+                .on_press(Message::DismissError)
+                .padding([4, 8])
+                .style(move |_theme, _status| button::Style {
+                    background: Some(Background::Color(Color::TRANSPARENT)),
+                    border: Border::default(),
+                    text_color: pal.text,
+                    ..Default::default()
+                }),
+            ]
+            .align_y(iced::Alignment::Center);
+
+            // Make header clickable
+            let header_button = button(header_row)
+                .on_press(Message::ToggleErrorExpand)
+                .padding([12, 16])
+                .width(Length::Fill)
+                .style(move |_theme, _status| button::Style {
+                    background: Some(Background::Color(Color::TRANSPARENT)),
+                    border: Border::default(),
+                    text_color: pal.text,
+                    ..Default::default()
+                });
+
+            // Expanded content (full error details)
+            let expanded_content: Element<'_, Message> = if self.error_expanded {
+                container(
+                    scrollable(text(error.as_str()).size(12).font(Font::MONOSPACE).style(
+                        move |_| iced::widget::text::Style {
+                            color: Some(Color {
+                                r: 1.0,
+                                g: 0.7,
+                                b: 0.7,
+                                a: 1.0,
+                            }),
+                        },
+                    ))
+                    .height(Length::Fixed(120.0)),
+                )
+                .padding([6, 16])
+                .width(Length::Fill)
+                .into()
+            } else {
+                Space::new().into()
+            };
+
+            let error_container = container(column![header_button, expanded_content].spacing(0))
+                .max_width(600.0)
+                .style(move |_| container::Style {
+                    background: Some(Background::Color(Color {
+                        r: 0.2,
+                        g: 0.08,
+                        b: 0.08,
+                        a: 0.95,
+                    })),
+                    border: Border {
+                        radius: 8.0.into(),
+                        width: 1.0,
+                        color: Color {
+                            r: 0.8,
+                            g: 0.3,
+                            b: 0.3,
+                            a: 0.8,
+                        },
+                    },
+                    ..Default::default()
+                });
+
+            container(column![
+                Space::new().height(Length::Fill),
+                error_container,
+                Space::new().height(Length::Fixed(80.0)), // Space above input
+            ])
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(Horizontal::Center)
+            .into()
+        } else {
+            Space::new().into()
+        };
+
+        let budget_notice = self.budget_notice_banner(pal);
+        let retry_notice = self.retry_notice_banner(pal);
+        let directory_popup = self.directory_popup(pal);
+        let context_panel = self.context_panel(pal);
+        let outline_panel = self.outline_panel(pal);
+        let session_tuning = self.session_tuning_popover(pal);
+        let file_preview = self.file_preview_pane(pal);
+        let commit_panel = self.commit_panel(pal);
+        let review_panel = self.review_panel(pal);
+        let compare_panel = self.compare_panel(pal);
+        let apply_panel = self.apply_panel(pal);
+        let log_viewer = self.log_viewer(pal);
+        let event_inspector = self.event_inspector(pal);
+        let conversations_sidebar = self.conversations_sidebar(pal);
+
+        // Add backdrop overlay for conversations sidebar
+        let conversations_backdrop: Element<'_, Message> = if self.show_conversations {
+            button(Space::new().width(Length::Fill).height(Length::Fill))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(move |_theme, _status| button::Style {
+                    background: Some(Background::Color(Color {
+                        r: 0.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: self.conversations_sidebar_animation * 0.3, // Darken backdrop when sidebar is open
+                    })),
+                    ..Default::default()
+                })
+                .on_press(Message::CloseConversations) // Close when clicking backdrop
+                .into()
+        } else {
+            Space::new().into()
+        };
+
+        let content = stack(vec![
+            background.into(),
+            main_layer.into(),
+            overlay,
+            conversations_backdrop, // Add backdrop behind conversations sidebar
+            directory_popup,
+            context_panel,
+            session_tuning,
+            outline_panel,
+            file_preview,
+            commit_panel,
+            review_panel,
+            compare_panel,
+            apply_panel,
+            log_viewer,
+            event_inspector,
+            conversations_sidebar,
+            budget_notice,
+            retry_notice,
+            error_overlay,
+        ]);
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+
+    fn error_view(&self, error: &str, pal: PaletteColors) -> Element<'_, Message> {
+        let error_text = error.to_string();
+        container(
+            column![
+                text("Initialization Error")
+                    .size(32)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.danger)
+                    }),
+                text(error_text)
+                    .size(16)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.text)
+                    }),
+            ]
+            .spacing(16)
+            .align_x(iced::Alignment::Center),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Horizontal::Center)
+        .align_y(Vertical::Center)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(pal.background)),
+            ..Default::default()
+        })
+        .into()
+    }
+
+    /// First-launch setup wizard: provider, key test, model and theme.
+    fn onboarding_view<'a>(&'a self, onboarding: &'a Onboarding, pal: PaletteColors) -> Element<'a, Message> {
+        let step = onboarding.step;
+        let label = move |content: String, color: Color| {
+            text(content)
+                .size(12)
+                .style(move |_| iced::widget::text::Style { color: Some(color) })
+        };
+        // Full-width choice button, highlighted when selected
+        let choice = move |content: String, selected: bool, message: Message| -> Element<'a, Message> {
+            button(text(content).size(14))
+                .on_press(message)
+                .width(Length::Fill)
+                .padding([10, 14])
+                .style(move |_theme, status| {
+                    let hovered = matches!(status, button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if selected { 0.25 } else if hovered { 0.12 } else { 0.0 },
+                            ..pal.accent
+                        })),
+                        text_color: if selected { pal.accent } else { pal.text },
+                        border: Border {
+                            radius: 8.0.into(),
+                            width: 1.0,
+                            color: if selected { pal.accent } else { pal.border },
+                        },
+                        ..Default::default()
+                    }
+                })
+                .into()
+        };
+
+        let body: Element<'a, Message> = match step {
+            OnboardingStep::Provider => {
+                let providers = collect_provider_options(&self.config).into_iter().map(|provider| {
+                    let selected = provider.eq_ignore_ascii_case(&onboarding.provider);
+                    choice(
+                        provider.clone(),
+                        selected,
+                        Message::OnboardingProviderSelected(provider),
+                    )
+                });
+                scrollable(column(providers).spacing(6))
+                    .height(Length::Fixed(300.0))
+                    .into()
+            }
+            OnboardingStep::ApiKey => {
+                let key_placeholder = if onboarding.key_required() {
+                    "API key"
+                } else {
+                    "API key (not needed for local servers)"
+                };
+                let status: Element<'a, Message> = match &onboarding.test_result {
+                    _ if onboarding.testing => label("Sending a test request...".to_string(), pal.muted).into(),
+                    Some(Ok(message)) => label(format!("✓ {}", message), pal.success).into(),
+                    Some(Err(error)) => label(error.clone(), pal.danger).into(),
+                    None => label(
+                        "Send a short test request to check the key and URL".to_string(),
+                        pal.muted,
+                    )
+                    .into(),
+                };
+                column![
+                    label("API key".to_string(), pal.muted),
+                    text_input(key_placeholder, &onboarding.api_key)
+                        .on_input(Message::OnboardingApiKeyChanged)
+                        .on_submit(Message::OnboardingTestConnection)
+                        .secure(true)
+                        .padding([10, 12])
+                        .style(input_style(pal)),
+                    label("API URL".to_string(), pal.muted),
+                    text_input("https://...", &onboarding.api_url)
+                        .on_input(Message::OnboardingApiUrlChanged)
+                        .padding([10, 12])
+                        .style(input_style(pal)),
+                    Space::new().height(Length::Fixed(4.0)),
+                    row![
+                        button(text("Test connection").size(13))
+                            .on_press_maybe(
+                                (!onboarding.testing).then_some(Message::OnboardingTestConnection),
+                            )
+                            .padding([8, 14])
+                            .style(secondary_button_style(pal)),
+                        status,
+                    ]
+                    .spacing(12)
+                    .align_y(iced::Alignment::Center),
+                ]
+                .spacing(8)
+                .into()
+            }
+            OnboardingStep::Model => {
+                let models: Element<'a, Message> = if self.models_loading {
+                    label("Loading models...".to_string(), pal.muted).into()
+                } else if self.model_list.is_empty() {
+                    label("No model list available; type a model name below".to_string(), pal.muted).into()
+                } else {
+                    let rows = self.model_list.iter().map(|model| -> Element<'a, Message> {
+                        // Fetch errors come back as list entries
+                        if model.starts_with("⚠️") {
+                            return label(model.clone(), pal.danger).into();
+                        }
+                        choice(
+                            model.clone(),
+                            *model == onboarding.model,
+                            Message::OnboardingModelSelected(model.clone()),
+                        )
+                    });
+                    scrollable(column(rows).spacing(6))
+                        .height(Length::Fixed(240.0))
+                        .into()
+                };
+                column![
+                    models,
+                    label("Model".to_string(), pal.muted),
+                    text_input("Model name", &onboarding.model)
+                        .on_input(Message::OnboardingModelSelected)
+                        .padding([10, 12])
+                        .style(input_style(pal)),
+                ]
+                .spacing(8)
+                .into()
+            }
+            OnboardingStep::Theme => {
+                let themes = [ThemeMode::Auto, ThemeMode::Light, ThemeMode::Dark, ThemeMode::Black]
+                    .into_iter()
+                    .map(|mode| {
+                        choice(
+                            mode.name().to_string(),
+                            mode == onboarding.theme_mode,
+                            Message::OnboardingThemeSelected(mode),
+                        )
+                    });
+                column(themes).spacing(6).into()
+            }
+        };
+
+        let is_last = step.next().is_none();
+        let back: Element<'a, Message> = if step.previous().is_some() {
+            button(text("Back").size(13))
+                .on_press(Message::OnboardingBack)
+                .padding([8, 16])
+                .style(secondary_button_style(pal))
+                .into()
+        } else {
+            Space::new().into()
+        };
+        let footer = row![
+            button(text("Skip for now").size(12))
+                .on_press(Message::OnboardingSkip)
+                .padding([8, 4])
+                .style(move |_theme, status| button::Style {
+                    background: None,
+                    text_color: if matches!(status, button::Status::Hovered) {
+                        pal.text
+                    } else {
+                        pal.muted
+                    },
+                    ..Default::default()
+                }),
+            Space::new().width(Length::Fill),
+            back,
+            button(text(if is_last { "Finish" } else { "Continue" }).size(13))
+                .on_press_maybe(onboarding.can_continue().then_some(Message::OnboardingNext))
+                .padding([8, 16])
+                .style(primary_button_style(pal)),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let card = container(
+            column![
+                label(
+                    format!("Step {} of {}", step.index() + 1, OnboardingStep::ALL.len()),
+                    pal.accent,
+                ),
+                text(step.title())
+                    .size(24)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.text)
+                    }),
+                label(step.subtitle().to_string(), pal.muted),
+                Space::new().height(Length::Fixed(12.0)),
+                body,
+                Space::new().height(Length::Fixed(12.0)),
+                footer,
+            ]
+            .spacing(6),
+        )
+        .padding(24)
+        .width(Length::Fixed(ONBOARDING_CARD_WIDTH))
+        .style(move |_| container::Style {
+            background: Some(Background::Color(pal.surface)),
+            border: Border {
+                radius: 12.0.into(),
+                width: 1.0,
+                color: pal.border,
+            },
+            ..Default::default()
+        });
+
+        container(column![
+            text("Welcome to ARULA")
+                .size(32)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.accent)
+                }),
+            Space::new().height(Length::Fixed(16.0)),
+            card,
+        ]
+        .align_x(iced::Alignment::Center))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Horizontal::Center)
+        .align_y(Vertical::Center)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(pal.background)),
+            ..Default::default()
+        })
+        .into()
+    }
+
+    fn top_bar(&self, pal: PaletteColors, sidebar_width: f32) -> Element<'_, Message> {
+        // ─────────────────────────────────────────────────────────────────
+        // LEFT SIDE: Navigation buttons (icon-based for clean look)
+        // ─────────────────────────────────────────────────────────────────
+
+        // Conversations button (history icon)
+        let conversations_active = self.show_conversations;
+        let conversations_button = button(
+            container(
+                bootstrap::clock_history()
+                    .size(18)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(if conversations_active { pal.accent } else { pal.muted })
+                    })
+            )
+            .width(Length::Fixed(36.0))
+            .height(Length::Fixed(36.0))
+            .align_x(Horizontal::Center)
+            .align_y(Vertical::Center)
+        )
+        .on_press(Message::ToggleConversations)
+        .padding(0)
+        .style(move |_theme, status| {
+            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+            iced::widget::button::Style {
+                background: Some(Background::Color(Color {
+                    a: if conversations_active { 0.25 } else if is_hovered { 0.15 } else { 0.0 },
+                    ..pal.accent
+                })),
+                border: Border {
+                    radius: 10.0.into(),
+                    ..Default::default()
+                },
+                text_color: pal.muted,
+                ..Default::default()
+            }
+        });
+
+        // New Chat button (plus icon)
+        let new_chat_button = button(
+            container(
+                bootstrap::plus_lg()
+                    .size(18)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    })
+            )
+            .width(Length::Fixed(36.0))
+            .height(Length::Fixed(36.0))
+            .align_x(Horizontal::Center)
+            .align_y(Vertical::Center)
+        )
+        .on_press(Message::ClearChat)
+        .padding(0)
+        .style(move |_theme, status| {
+            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+            iced::widget::button::Style {
+                background: Some(Background::Color(Color {
+                    a: if is_hovered { 0.15 } else { 0.0 },
+                    ..pal.accent
+                })),
+                border: Border {
+                    radius: 10.0.into(),
+                    ..Default::default()
+                },
+                text_color: pal.muted,
+                ..Default::default()
+            }
+        });
+
+        // Copy-as-Markdown and HTML export, once there is something to share
+        let has_messages = self
+            .sessions
+            .get(self.current)
+            .is_some_and(|s| !s.messages.is_empty());
+        let show_raw = self.sessions.get(self.current).is_some_and(|s| s.show_raw);
+        let share_button = move |icon: iced::widget::Text<'static>, label: &'static str, message: Message| {
+            tooltip(
+                button(
+                    container(icon.size(16).style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted),
+                    }))
+                    .width(Length::Fixed(36.0))
+                    .height(Length::Fixed(36.0))
+                    .align_x(Horizontal::Center)
+                    .align_y(Vertical::Center),
+                )
+                .on_press_maybe(has_messages.then_some(message))
+                .padding(0)
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    iced::widget::button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.15 } else { 0.0 },
+                            ..pal.accent
+                        })),
+                        border: Border {
+                            radius: 10.0.into(),
+                            ..Default::default()
+                        },
+                        text_color: pal.muted,
+                        ..Default::default()
+                    }
+                }),
+                text(label).size(11),
+                tooltip::Position::Bottom,
+            )
+        };
+
+        let left_buttons = row![
+            conversations_button,
+            Space::new().width(Length::Fixed(4.0)),
+            new_chat_button,
+            Space::new().width(Length::Fixed(4.0)),
+            share_button(
+                bootstrap::clipboard(),
+                "Copy conversation as Markdown",
+                Message::CopyConversation
+            ),
+            share_button(
+                bootstrap::box_arrow_up(),
+                "Export as HTML",
+                Message::ExportConversationHtml
+            ),
+            if show_raw {
+                share_button(
+                    bootstrap::chat_text(),
+                    "Show formatted chat",
+                    Message::ToggleRawTranscript,
+                )
+            } else {
+                share_button(
+                    bootstrap::code_slash(),
+                    "Show raw transcript",
+                    Message::ToggleRawTranscript,
+                )
+            },
+        ]
+        .align_y(iced::Alignment::Center);
+
+        // ─────────────────────────────────────────────────────────────────
+        // CENTER: Directory selector (pill-shaped with folder icon)
+        // ─────────────────────────────────────────────────────────────────
+
+        let dir_name = self.current_directory
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_else(|| self.current_directory.to_str().unwrap_or("/"));
+        
+        let is_popup_open = self.show_directory_popup;
+        let directory_button = button(
+            row![
+                bootstrap::folder()
+                    .size(14)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(if is_popup_open { pal.accent } else { pal.muted })
+                    }),
+                Space::new().width(Length::Fixed(8.0)),
+                text(dir_name)
+                    .size(13)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.text)
+                    }),
+                Space::new().width(Length::Fixed(6.0)),
+                bootstrap::chevron_down()
+                    .size(10)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(Color { a: 0.5, ..pal.muted })
+                    }),
+            ]
+            .align_y(iced::Alignment::Center),
+        )
+        .on_press(Message::ToggleDirectoryPopup)
+        .padding([8, 14])
+        .style(move |_theme, status| {
+            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+            button::Style {
+                background: Some(Background::Color(Color {
+                    a: if is_popup_open { 0.2 } else if is_hovered { 0.1 } else { 0.0 },
+                    ..pal.accent
+                })),
+                border: Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: Color {
+                        a: if is_popup_open { 0.4 } else if is_hovered { 0.25 } else { 0.15 },
+                        ..pal.border
+                    },
+                },
+                text_color: pal.text,
+                ..Default::default()
+            }
+        });
+
+        // Branch pill: branch name and changed file count; clicking toggles
+        // whether git status and recent commits go into the system prompt
+        let git_pill = self.git_info.as_ref().map(|info| {
+            let git_context = self.config.get_git_context_enabled();
+            let dirty = info.is_dirty().then(|| {
+                text(format!("● {}", info.changed_files))
+                    .size(11)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.accent)
+                    })
+            });
+            button(
+                row![
+                    bootstrap::git()
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(if git_context { pal.accent } else { pal.muted })
+                        }),
+                    Space::new().width(Length::Fixed(6.0)),
+                    text(info.branch.clone())
+                        .size(12)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                ]
+                .push(dirty.map(|d| row![Space::new().width(Length::Fixed(6.0)), d]))
+                .align_y(iced::Alignment::Center),
+            )
+            .on_press(Message::ToggleGitContext)
+            .padding([8, 12])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.1 } else { 0.0 },
+                        ..pal.accent
+                    })),
+                    border: Border {
+                        radius: 12.0.into(),
+                        width: 1.0,
+                        color: Color {
+                            a: if is_hovered { 0.25 } else { 0.15 },
+                            ..pal.border
+                        },
+                    },
+                    text_color: pal.text,
+                    ..Default::default()
+                }
+            })
+        });
+
+        // Commit message generation, offered while the tree has changes
+        let is_commit_open = self.commit_draft.is_some();
+        let commit_button = self
+            .git_info
+            .as_ref()
+            .filter(|info| info.is_dirty() || is_commit_open)
+            .map(|_| {
+                button(
+                    bootstrap::stars()
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(if is_commit_open { pal.accent } else { pal.muted })
+                        }),
+                )
+                .on_press(if is_commit_open {
+                    Message::CloseCommitDraft
+                } else {
+                    Message::GenerateCommitMessage
+                })
+                .padding([8, 10])
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_commit_open { 0.2 } else if is_hovered { 0.1 } else { 0.0 },
+                            ..pal.accent
+                        })),
+                        border: Border {
+                            radius: 12.0.into(),
+                            width: 1.0,
+                            color: Color {
+                                a: if is_commit_open { 0.4 } else if is_hovered { 0.25 } else { 0.15 },
+                                ..pal.border
+                            },
+                        },
+                        text_color: pal.text,
+                        ..Default::default()
+                    }
+                })
+            });
+
+        // Code review of the working tree changes
+        let is_review_open = self.review.is_some();
+        let review_button = self
+            .git_info
+            .as_ref()
+            .filter(|info| info.is_dirty() || is_review_open)
+            .map(|_| {
+                button(
+                    bootstrap::file_earmark_diff()
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(if is_review_open { pal.accent } else { pal.muted })
+                        }),
+                )
+                .on_press(if is_review_open {
+                    Message::CloseReview
+                } else {
+                    Message::ReviewChanges
+                })
+                .padding([8, 10])
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_review_open { 0.2 } else if is_hovered { 0.1 } else { 0.0 },
+                            ..pal.accent
+                        })),
+                        border: Border {
+                            radius: 12.0.into(),
+                            width: 1.0,
+                            color: Color {
+                                a: if is_review_open { 0.4 } else if is_hovered { 0.25 } else { 0.15 },
+                                ..pal.border
+                            },
+                        },
+                        text_color: pal.text,
+                        ..Default::default()
+                    }
+                })
+            });
+
+        // ─────────────────────────────────────────────────────────────────
+        // RIGHT SIDE: Optional AI Initialize button
+        // ─────────────────────────────────────────────────────────────────
+
+        let report = self.dispatcher.context_report();
+        let is_context_open = self.show_context_panel;
+        let context_color = if report.trimmed_count() > 0 { pal.accent } else { pal.muted };
+        let context_button = button(
+            row![
+                bootstrap::layers()
+                    .size(14)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(if is_context_open { pal.accent } else { context_color })
+                    }),
+                Space::new().width(Length::Fixed(6.0)),
+                text(format!("{}k", report.used_tokens.div_ceil(1000)))
+                    .size(12)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    }),
+            ]
+            .align_y(iced::Alignment::Center),
+        )
+        .on_press(Message::ToggleContextPanel)
+        .padding([8, 12])
+        .style(move |_theme, status| {
+            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+            button::Style {
+                background: Some(Background::Color(Color {
+                    a: if is_context_open { 0.2 } else if is_hovered { 0.1 } else { 0.0 },
+                    ..pal.accent
+                })),
+                border: Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: Color {
+                        a: if is_context_open { 0.4 } else if is_hovered { 0.25 } else { 0.15 },
+                        ..pal.border
+                    },
+                },
+                text_color: pal.text,
+                ..Default::default()
+            }
+        });
+
+        // Model pill: opens the per-session temperature / max tokens popover
+        let is_tuning_open = self.show_session_tuning;
+        let is_tuned = self
+            .sessions
+            .get(self.current)
+            .is_some_and(|s| s.temperature.is_some() || s.max_tokens.is_some());
+        let model_pill = button(
+            row![
+                text(effective_model(&self.config, self.project_config.as_ref()))
+                    .size(12)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    }),
+                Space::new().width(Length::Fixed(6.0)),
+                bootstrap::sliders()
+                    .size(12)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(if is_tuning_open || is_tuned { pal.accent } else { pal.muted })
+                    }),
+            ]
+            .align_y(iced::Alignment::Center),
+        )
+        .on_press(Message::ToggleSessionTuning)
+        .padding([8, 12])
+        .style(move |_theme, status| {
+            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+            button::Style {
+                background: Some(Background::Color(Color {
+                    a: if is_tuning_open { 0.2 } else if is_hovered { 0.1 } else { 0.0 },
+                    ..pal.accent
+                })),
+                border: Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: Color {
+                        a: if is_tuning_open { 0.4 } else if is_hovered { 0.25 } else { 0.15 },
+                        ..pal.border
+                    },
+                },
+                text_color: pal.text,
+                ..Default::default()
+            }
+        });
+
+        let is_logs_open = self.show_log_viewer;
+        let logs_button = button(
+            bootstrap::journal_text()
+                .size(14)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(if is_logs_open { pal.accent } else { pal.muted })
+                }),
+        )
+        .on_press(Message::ToggleLogViewer)
+        .padding([8, 10])
+        .style(move |_theme, status| {
+            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+            button::Style {
+                background: Some(Background::Color(Color {
+                    a: if is_logs_open { 0.2 } else if is_hovered { 0.1 } else { 0.0 },
+                    ..pal.accent
+                })),
+                border: Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: Color {
+                        a: if is_logs_open { 0.4 } else if is_hovered { 0.25 } else { 0.15 },
+                        ..pal.border
+                    },
+                },
+                text_color: pal.text,
+                ..Default::default()
+            }
+        });
+
+        let is_outline_open = self.show_outline;
+        let outline_button = tooltip(
+            button(
+                bootstrap::list_ul()
+                    .size(14)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(if is_outline_open { pal.accent } else { pal.muted })
+                    }),
+            )
+            .on_press(Message::ToggleOutline)
+            .padding([8, 10])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_outline_open { 0.2 } else if is_hovered { 0.1 } else { 0.0 },
+                        ..pal.accent
+                    })),
+                    border: Border {
+                        radius: 12.0.into(),
+                        width: 1.0,
+                        color: Color {
+                            a: if is_outline_open { 0.4 } else if is_hovered { 0.25 } else { 0.15 },
+                            ..pal.border
+                        },
+                    },
+                    text_color: pal.text,
+                    ..Default::default()
+                }
+            }),
+            text("Outline").size(11),
+            tooltip::Position::Bottom,
+        );
+
+        let is_compare_open = self.compare.is_some();
+        let compare_button = tooltip(
+            button(
+                bootstrap::layout_split()
+                    .size(14)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(if is_compare_open { pal.accent } else { pal.muted })
+                    }),
+            )
+            .on_press(Message::ToggleCompare)
+            .padding([8, 10])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_compare_open { 0.2 } else if is_hovered { 0.1 } else { 0.0 },
+                        ..pal.accent
+                    })),
+                    border: Border {
+                        radius: 12.0.into(),
+                        width: 1.0,
+                        color: Color {
+                            a: if is_compare_open { 0.4 } else if is_hovered { 0.25 } else { 0.15 },
+                            ..pal.border
+                        },
+                    },
+                    text_color: pal.text,
+                    ..Default::default()
+                }
+            }),
+            text("Compare models").size(11),
+            tooltip::Position::Bottom,
+        );
+
+        let show_init_button = self.detected_project.is_some() && !self.manifest_is_ai_enhanced;
+        let init_ai_button: Option<Element<'_, Message>> = if show_init_button {
+            Some(
+                button(
+                    row![
+                        bootstrap::stars()
+                            .size(14)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.accent)
+                            }),
+                        Space::new().width(Length::Fixed(6.0)),
+                        text("AI Init")
+                            .size(12)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.text)
+                            }),
+                    ]
+                    .align_y(iced::Alignment::Center),
+                )
+                .on_press(Message::InitializeProjectWithAI)
+                .padding([8, 14])
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.3 } else { 0.2 },
+                            ..pal.accent
+                        })),
+                        border: Border {
+                            radius: 12.0.into(),
+                            width: 1.0,
+                            color: Color {
+                                a: if is_hovered { 0.7 } else { 0.5 },
+                                ..pal.accent
+                            },
+                        },
+                        text_color: pal.text,
+                        ..Default::default()
+                    }
+                })
+                .into()
+            )
+        } else {
+            None
+        };
+
+        // ─────────────────────────────────────────────────────────────────
+        // TOP BAR LAYOUT: Glassmorphism container
+        // ─────────────────────────────────────────────────────────────────
+
+        let mut top_row = row![
+            left_buttons,
+            Space::new().width(Length::Fixed(12.0)),
+            directory_button,
+            Space::new().width(Length::Fixed(6.0)),
+            context_button,
+            Space::new().width(Length::Fixed(6.0)),
+            model_pill,
+            Space::new().width(Length::Fixed(8.0)),
+            self.provider_health_dot(pal),
+        ]
+        .push(git_pill.map(|pill| row![Space::new().width(Length::Fixed(6.0)), pill]))
+        .push(commit_button.map(|b| row![Space::new().width(Length::Fixed(6.0)), b]))
+        .push(review_button.map(|b| row![Space::new().width(Length::Fixed(6.0)), b]))
+        .align_y(iced::Alignment::Center);
+        
+        // Push spacer, log viewer and optional AI button to right
+        top_row = top_row.push(Space::new().width(Length::Fill));
+        top_row = top_row.push(compare_button);
+        top_row = top_row.push(Space::new().width(Length::Fixed(6.0)));
+        top_row = top_row.push(outline_button);
+        top_row = top_row.push(Space::new().width(Length::Fixed(6.0)));
+        top_row = top_row.push(logs_button);
+
+        if let Some(ai_btn) = init_ai_button {
+            top_row = top_row.push(Space::new().width(Length::Fixed(6.0)));
+            top_row = top_row.push(ai_btn);
+        }
+
+        let top_bar_content = container(top_row)
+            .padding([6, 10])
+            .width(Length::Shrink);
+
+        let top_bar = container(top_bar_content)
+            .style(move |_| container::Style {
+                background: Some(Background::Color(Color {
+                    a: 0.5,
+                    ..pal.surface_raised
+                })),
+                border: Border {
+                    color: Color {
+                        a: 0.3,
+                        ..pal.border
+                    },
+                    width: 1.0,
+                    radius: 16.0.into(),
+                },
+                ..Default::default()
+            });
+
+        // Outer container with padding - adjust left padding based on sidebar width
+        let left_pad = if sidebar_width > 1.0 { sidebar_width } else { 0.0 };
+        container(top_bar)
+            .padding(iced::padding::Padding {
+                top: 12.0,
+                right: 16.0,
+                bottom: 12.0,
+                left: left_pad.max(0.0) + 16.0,
+            })
+            .width(Length::Fill)
+            .height(Length::Shrink)
+            .style(move |_| container::Style {
+                background: None,
+                ..Default::default()
+            })
+            .into()
+    }
+
+    /// Open sessions as tabs, each with its streaming or unread state and a
+    /// close button, followed by a button for a new tab
+    fn tab_strip(&self, pal: PaletteColors, sidebar_width: f32) -> Element<'_, Message> {
+        let mut tabs = row![].spacing(6).align_y(iced::Alignment::Center);
+        for (idx, session) in self.sessions.iter().enumerate() {
+            let is_current = idx == self.current;
+            // Streaming tabs spin, tabs with an unread answer show a dot
+            let indicator: Option<Element<'_, Message>> = if session.is_streaming {
+                Some(
+                    Canvas::new(LoadingSpinner::new(SpinnerState {
+                        tick: self.spinner_state.tick,
+                        spinner_type: SpinnerType::Orbital,
+                        size: 8.0,
+                        color: pal.accent,
+                        accent_color: pal.accent_soft,
+                    }))
+                    .width(Length::Fixed(14.0))
+                    .height(Length::Fixed(14.0))
+                    .into(),
+                )
+            } else if session.unread {
+                Some(
+                    text("●")
+                        .size(9)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.success),
+                        })
+                        .into(),
+                )
+            } else {
+                None
+            };
+            let label = button(
+                row![]
+                    .push(indicator)
+                    .push(text(session.tab_label()).size(12).style(move |_| {
+                        iced::widget::text::Style {
+                            color: Some(if is_current { pal.text } else { pal.muted }),
+                        }
+                    }))
+                    .spacing(6)
+                    .align_y(iced::Alignment::Center),
+            )
+            .on_press(Message::SwitchTab(idx))
+            .padding([5, 4])
+            .style(move |_theme, _status| button::Style {
+                background: None,
+                text_color: pal.text,
+                ..Default::default()
+            });
+            let close = button(bootstrap::x_lg().size(10))
+                .on_press(Message::CloseTab(idx))
+                .padding(2)
+                .style(move |_theme, status| button::Style {
+                    background: None,
+                    text_color: if matches!(status, button::Status::Hovered) {
+                        pal.danger
+                    } else {
+                        pal.muted
+                    },
+                    ..Default::default()
+                });
+            tabs = tabs.push(
+                container(row![label, close].spacing(2).align_y(iced::Alignment::Center))
+                    .padding([0, 8])
+                    .style(move |_| container::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_current { 0.6 } else { 0.25 },
+                            ..pal.surface_raised
+                        })),
+                        border: Border {
+                            radius: 10.0.into(),
+                            width: 1.0,
+                            color: if is_current {
+                                Color { a: 0.5, ..pal.accent }
+                            } else {
+                                Color { a: 0.2, ..pal.border }
+                            },
+                        },
+                        ..Default::default()
+                    }),
+            );
+        }
+        let new_tab = tooltip(
+            button(bootstrap::plus_lg().size(12))
+                .on_press(Message::NewTab)
+                .padding([5, 8])
+                .style(move |_theme, status| button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if matches!(status, button::Status::Hovered) { 0.15 } else { 0.0 },
+                        ..pal.accent
+                    })),
+                    border: Border {
+                        radius: 10.0.into(),
+                        ..Default::default()
+                    },
+                    text_color: pal.muted,
+                    ..Default::default()
+                }),
+            text("New tab").size(11),
+            tooltip::Position::Bottom,
+        );
+
+        let left_pad = if sidebar_width > 1.0 { sidebar_width } else { 0.0 };
+        container(
+            scrollable(tabs.push(new_tab))
+                .direction(scrollable::Direction::Horizontal(
+                    scrollable::Scrollbar::default().width(3).scroller_width(3),
+                ))
+                .width(Length::Fill),
+        )
+        .padding(iced::padding::Padding {
+            top: 0.0,
+            right: 16.0,
+            bottom: 6.0,
+            left: left_pad + 16.0,
+        })
+        .width(Length::Fill)
+        .into()
+    }
+
+    /// Adds `prompt` to the session at `index` and starts streaming the answer.
+    fn dispatch_prompt(&mut self, index: usize, prompt: String, images: Vec<PathBuf>) {
+        let Some(session) = self.sessions.get_mut(index) else {
+            return;
+        };
+        session.add_user_message_with_images(prompt.clone(), Utc::now().to_rfc3339(), images);
+
+        // Sync editor content for the new message
+        let msg_idx = session.messages.len() - 1;
+        let key = format!("{}:{}", index, msg_idx);
+        self.message_editors.insert(
+            key,
+            text_editor::Content::with_text(&session.messages[msg_idx].content),
+        );
+
+        session.set_streaming(true);
+
+        // Only this session's next prompt skips the budget
+        let ignore_budget = self.budget_override == Some(session.id);
+        if ignore_budget {
+            self.budget_override = None;
+        }
+        let session_config = SessionConfig {
+            system_prompt: build_enhanced_system_prompt(&self.config_form.system_prompt),
+            model: effective_model(&self.config, self.project_config.as_ref()),
+            max_tokens: session
+                .max_tokens
+                .unwrap_or(self.config_form.max_tokens as u32),
+            temperature: effective_temperature(session.temperature, &self.config, &self.config_form),
+            sampling: self.config.get_sampling(),
+            ignore_budget,
+            budget_usage: None,
+        };
+
+        // Get conversation history for context (excluding the current prompt which is included separately)
+        let history = session.get_chat_history();
+        let history_opt = if history.is_empty() {
+            None
+        } else {
+            Some(history)
+        };
+
+        if let Err(err) = self
+            .dispatcher
+            .start_stream(session.id, prompt, history_opt, session_config)
+        {
+            tracing::error!("dispatch error: {err}");
+            session.set_streaming(false);
+        }
+    }
+
+    /// Handles a stream that failed because the provider couldn't be reached:
+    /// marks it offline and, if no answer had started, puts the prompt back at
+    /// the front of the queue to be resent after a backoff instead of showing
+    /// an error. After `OFFLINE_MAX_RETRIES` resends in a row the error is shown.
+    fn requeue_offline_prompt(&mut self, session_id: uuid::Uuid, err: &str) -> Option<Task<Message>> {
+        self.provider_last_error = Some((err.to_string(), chrono::Local::now()));
+        self.provider_health = Some(HealthReport {
+            health: Health::Unreachable,
+            latency: None,
+            error: Some(err.to_string()),
+        });
+
+        let retries = self.offline_retries.entry(session_id).or_insert(0);
+        if *retries >= OFFLINE_MAX_RETRIES {
+            self.offline_retries.remove(&session_id);
+            return None;
+        }
+        *retries += 1;
+        let delay = Duration::from_millis(OFFLINE_RETRY_BASE_MS << (*retries - 1));
+
+        let index = self.sessions.iter().position(|s| s.id == session_id)?;
+        let session = &mut self.sessions[index];
+        session.set_streaming(false);
+        let last = session.messages.len().checked_sub(1)?;
+        if !session.messages[last].is_user() {
+            return None;
+        }
+        let message = session.messages[last].clone();
+        session.truncate_from(last);
+        self.message_editors.remove(&format!("{}:{}", index, last));
+        self.queued_prompts.insert(
+            0,
+            QueuedPrompt {
+                session_id,
+                prompt: message.content,
+                images: message.images,
+                not_before: Some(Instant::now() + delay),
+            },
+        );
+        Some(Task::batch([
+            Task::done(Message::PingProvider),
+            Task::perform(tokio::time::sleep(delay), |_| Message::PingProvider),
+        ]))
+    }
+
+    /// Whether the last ping found the provider unreachable
+    fn is_offline(&self) -> bool {
+        self.provider_health
+            .as_ref()
+            .is_some_and(|report| report.health == Health::Unreachable)
+    }
+
+    /// Sends the oldest queued prompt of every idle session, once online
+    fn dispatch_queued_prompts(&mut self) {
+        if self.is_offline() {
+            return;
+        }
+        let sessions = &self.sessions;
+        self.queued_prompts
+            .retain(|q| sessions.iter().any(|s| s.id == q.session_id));
+        let mut started = HashSet::new();
+        let mut idx = 0;
+        while idx < self.queued_prompts.len() {
+            let session_id = self.queued_prompts[idx].session_id;
+            let index = self.sessions.iter().position(|s| s.id == session_id);
+            match index {
+                Some(index)
+                    if !self.sessions[index].is_streaming
+                        && self.queued_prompts[idx].not_before.is_none_or(|t| t <= Instant::now())
+                        && started.insert(session_id) =>
+                {
+                    let queued = self.queued_prompts.remove(idx);
+                    tracing::info!(session = %session_id, "Sending prompt queued while offline");
+                    self.dispatch_prompt(index, queued.prompt, queued.images);
+                }
+                // Later prompts of the session wait for this answer
+                _ => {
+                    started.insert(session_id);
+                    idx += 1;
+                }
+            }
+        }
+    }
+
+    /// Index of the keyboard-focused message if it belongs to the current session
+    fn focused_message_index(&self) -> Option<usize> {
+        let (session_id, index) = self.focused_message?;
+        let session = self.sessions.get(self.current)?;
+        (session.id == session_id && index < session.messages.len()).then_some(index)
+    }
+
+    /// Saves the conversation of a streaming session as interrupted, at most
+    /// every `PARTIAL_SAVE_INTERVAL_MS`, on the `partial_saver` thread. Every
+    /// way the stream can end goes through `end_partial_saves`.
+    fn save_partial_conversation(&mut self, idx: usize) {
+        let Some(session) = self.sessions.get(idx) else {
+            return;
+        };
+        let now = Instant::now();
+        let interval = Duration::from_millis(PARTIAL_SAVE_INTERVAL_MS);
+        if self
+            .last_partial_save
+            .get(&session.id)
+            .is_some_and(|at| now.duration_since(*at) < interval)
+        {
+            return;
+        }
+        self.last_partial_save.insert(session.id, now);
+        let model = effective_model(&self.config, self.project_config.as_ref());
+        self.partial_saver.save(session.id, session.to_ui_events(), model);
+    }
+
+    /// Stops the interrupted saves of a stream that ended. `clear_mark` is for
+    /// endings without a regular save after them (an error, a closed tab),
+    /// which would otherwise leave the conversation marked as interrupted.
+    fn end_partial_saves(&mut self, id: uuid::Uuid, clear_mark: bool) {
+        self.last_partial_save.remove(&id);
+        if self.partial_saver.finish(id) && clear_mark {
+            if let Err(err) = self.conversation_manager.clear_interrupted(id) {
+                tracing::warn!("Failed to clear the interrupted mark: {}", err);
+            }
+        }
+    }
+
+    /// Opens the most recent conversation if the app closed while its answer
+    /// was streaming, so it can be continued
+    fn restore_interrupted_conversation(&mut self) {
+        let pinned = self
+            .conversation_manager
+            .list_page(ConversationList::Pinned, 0, usize::MAX)
+            .map(|page| page.conversations)
+            .unwrap_or_default();
+        let latest = self
+            .conversation_manager
+            .list_conversations_page(0, 1)
+            .map(|page| page.conversations)
+            .unwrap_or_default();
+        let Some(interrupted) = pinned
+            .into_iter()
+            .chain(latest)
+            .filter(|conversation| conversation.interrupted)
+            .max_by_key(|conversation| conversation.updated_at)
+        else {
+            return;
+        };
+        match self.conversation_manager.load_conversation(interrupted.id) {
+            Ok(conversation) => {
+                let mut session = Session::from_events(interrupted.id, &conversation.events);
+                session.interrupted = true;
+                self.sessions = vec![session];
+                self.current = 0;
+            }
+            Err(err) => tracing::warn!("Failed to restore interrupted conversation: {}", err),
+        }
+    }
+
+    /// Progress of a multi-step tool loop: the round out of the configured
+    /// limit, the tool being run, elapsed time and a cancel button
+    fn tool_progress(&self, pal: PaletteColors) -> Option<Element<'_, Message>> {
+        let session = self.sessions.get(self.current)?;
+        let stats = session.stream_stats.as_ref().filter(|_| session.is_streaming)?;
+        if stats.tool_rounds() == 0 {
+            return None;
+        }
+        let step = match self.config.get_budget().max_tool_iterations {
+            Some(max) => format!("Step {} of {}", stats.tool_rounds(), max),
+            None => format!("Step {}", stats.tool_rounds()),
+        };
+        let activity = stats
+            .current_tool()
+            .map_or_else(|| "Thinking…".to_string(), str::to_string);
+
+        Some(
+            container(
+                row![
+                    bootstrap::layers()
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.accent)
+                        }),
+                    text(step).size(12).font(Font::MONOSPACE),
+                    text(activity)
+                        .size(12)
+                        .width(Length::Fill)
+                        .wrapping(iced::widget::text::Wrapping::None)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                    text(format!("{:.1}s", stats.elapsed_secs()))
+                        .size(11)
+                        .font(Font::MONOSPACE)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(Color { a: 0.7, ..pal.muted })
+                        }),
+                    button(text("Cancel").size(12))
+                        .on_press(Message::StopStream)
+                        .padding([3, 10])
+                        .style(move |_theme, status| button::Style {
+                            background: matches!(status, button::Status::Hovered)
+                                .then_some(Background::Color(Color { a: 0.2, ..pal.danger })),
+                            text_color: pal.text,
+                            border: Border {
+                                radius: 8.0.into(),
+                                width: 1.0,
+                                color: Color { a: 0.5, ..pal.danger },
+                            },
+                            ..Default::default()
+                        }),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            )
+            .padding([6, 12])
+            .width(Length::Fill)
+            .style(move |_| container::Style {
+                background: Some(Background::Color(Color {
+                    a: 0.6,
+                    ..pal.surface_raised
+                })),
+                border: Border {
+                    radius: 10.0.into(),
+                    width: 1.0,
+                    color: Color { a: 0.3, ..pal.border },
+                },
+                text_color: Some(pal.text),
+                ..Default::default()
+            })
+            .into(),
+        )
+    }
+
+    /// Notice offering to finish an answer that was cut off when the app closed
+    /// Corrections for the last misspelled word of the input
+    fn spelling_suggestions(&self, pal: PaletteColors) -> Option<Element<'_, Message>> {
+        let fix = self.spelling.as_ref()?;
+        let chip = move |label: String, message: Message| {
+            button(text(label).size(12))
+                .on_press(message)
+                .padding([3, 10])
+                .style(move |_theme, status| button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if matches!(status, button::Status::Hovered) {
+                            0.3
+                        } else {
+                            0.15
+                        },
+                        ..pal.accent
+                    })),
+                    text_color: pal.text,
+                    border: Border {
+                        radius: 8.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+        };
+
+        let mut strip = row![text(format!("“{}”", fix.word))
+            .size(12)
+            .style(move |_| iced::widget::text::Style {
+                color: Some(pal.muted)
+            })]
+        .spacing(6)
+        .align_y(iced::Alignment::Center);
+        if fix.suggestions.is_empty() {
+            strip = strip.push(text("isn't in the dictionary").size(12));
+        } else {
+            strip = strip.push(text("Did you mean").size(12));
+            for suggestion in &fix.suggestions {
+                strip = strip.push(chip(
+                    suggestion.clone(),
+                    Message::ApplySpelling(suggestion.clone()),
+                ));
+            }
+        }
+        strip = strip
+            .push(Space::new().width(Length::Fill))
+            .push(chip("Keep as typed".to_string(), Message::IgnoreSpelling));
+
+        Some(
+            container(strip)
+                .padding([4, 12])
+                .width(Length::Fill)
+                .into(),
+        )
+    }
+
+    /// Ctrl+R search over sent prompts, best matches first
+    fn prompt_search_panel(&self, pal: PaletteColors) -> Option<Element<'_, Message>> {
+        let query = self.prompt_search.as_deref()?;
+        let session = self.sessions.get(self.current)?;
+        let entries = prompt_history::merged(&self.prompt_history, &session.user_prompts());
+        let matches: Vec<String> = prompt_history::search(&entries, query)
+            .into_iter()
+            .take(PROMPT_SEARCH_RESULTS)
+            .map(str::to_string)
+            .collect();
+
+        let mut search = text_input("Search sent prompts…", query)
+            .id(prompt_search_id())
+            .on_input(Message::PromptSearchChanged)
+            .size(13)
+            .padding([6, 10]);
+        if let Some(first) = matches.first() {
+            search = search.on_submit(Message::PickHistoryPrompt(first.clone()));
+        }
+
+        let results: Vec<Element<'_, Message>> = if matches.is_empty() {
+            vec![text("No matching prompts")
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                })
+                .into()]
+        } else {
+            matches
+                .into_iter()
+                .map(|prompt| {
+                    // Multi-line prompts show their first line
+                    let label = prompt.lines().next().unwrap_or_default().to_string();
+                    button(text(label).size(12).wrapping(iced::widget::text::Wrapping::None))
+                        .on_press(Message::PickHistoryPrompt(prompt))
+                        .padding([4, 8])
+                        .width(Length::Fill)
+                        .style(move |_theme, status| button::Style {
+                            background: matches!(status, button::Status::Hovered)
+                                .then_some(Background::Color(pal.surface_raised)),
+                            text_color: pal.text,
+                            border: Border {
+                                radius: 6.0.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                        .into()
+                })
+                .collect()
+        };
+
+        Some(
+            container(
+                column![
+                    row![
+                        bootstrap::clock_history()
+                            .size(13)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.accent)
+                            }),
+                        search,
+                        button(bootstrap::x_lg().size(12))
+                            .on_press(Message::TogglePromptSearch)
+                            .padding([4, 8])
+                            .style(move |_theme, status| button::Style {
+                                background: matches!(status, button::Status::Hovered)
+                                    .then_some(Background::Color(pal.surface_raised)),
+                                text_color: pal.muted,
+                                border: Border {
+                                    radius: 6.0.into(),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            }),
+                    ]
+                    .spacing(8)
+                    .align_y(iced::Alignment::Center),
+                    column(results).spacing(2),
+                ]
+                .spacing(6),
+            )
+            .padding([8, 12])
+            .width(Length::Fill)
+            .style(move |_| container::Style {
+                background: Some(Background::Color(Color {
+                    a: 0.6,
+                    ..pal.surface_raised
+                })),
+                border: Border {
+                    radius: 10.0.into(),
+                    width: 1.0,
+                    color: pal.border,
+                },
+                text_color: Some(pal.text),
+                ..Default::default()
+            })
+            .into(),
+        )
+    }
+
+    fn interrupted_notice(&self, pal: PaletteColors) -> Option<Element<'_, Message>> {
+        let session = self.sessions.get(self.current)?;
+        if !session.interrupted || session.is_streaming {
+            return None;
+        }
+        Some(
+            container(
+                row![
+                    bootstrap::exclamation_triangle()
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.accent)
+                        }),
+                    text("The app closed before this answer finished.")
+                        .size(12)
+                        .width(Length::Fill),
+                    button(text("Continue generation").size(12))
+                        .on_press(Message::ContinueGeneration)
+                        .padding([3, 10])
+                        .style(move |_theme, status| button::Style {
+                            background: Some(Background::Color(Color {
+                                a: if matches!(status, button::Status::Hovered) {
+                                    0.35
+                                } else {
+                                    0.2
+                                },
+                                ..pal.accent
+                            })),
+                            text_color: pal.text,
+                            border: Border {
+                                radius: 8.0.into(),
+                                width: 1.0,
+                                color: pal.accent,
+                            },
+                            ..Default::default()
+                        }),
+                    button(text("Dismiss").size(12))
+                        .on_press(Message::DismissInterrupted)
+                        .padding([3, 10])
+                        .style(move |_theme, status| button::Style {
+                            background: matches!(status, button::Status::Hovered)
+                                .then_some(Background::Color(pal.surface_raised)),
+                            text_color: pal.text,
+                            border: Border {
+                                radius: 8.0.into(),
+                                width: 1.0,
+                                color: pal.border,
+                            },
+                            ..Default::default()
+                        }),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            )
+            .padding([6, 12])
+            .width(Length::Fill)
+            .style(move |_| container::Style {
+                background: Some(Background::Color(Color {
+                    a: 0.6,
+                    ..pal.surface_raised
+                })),
+                border: Border {
+                    radius: 10.0.into(),
+                    width: 1.0,
+                    color: Color { a: 0.4, ..pal.accent },
+                },
+                text_color: Some(pal.text),
+                ..Default::default()
+            })
+            .into(),
+        )
+    }
+
+    /// Notice about crash reports left by earlier runs
+    fn crash_notice(&self, pal: PaletteColors) -> Option<Element<'_, Message>> {
+        let latest = self.crash_reports.last()?;
+        let heading = if self.crash_reports.len() == 1 {
+            format!(
+                "ARULA crashed last time ({}). A report was saved locally.",
+                latest.created.format("%b %-d, %H:%M")
+            )
+        } else {
+            format!(
+                "ARULA crashed {} times since you last looked. Reports were saved locally.",
+                self.crash_reports.len()
+            )
+        };
+        let action = |label: &'static str, message: Message| {
+            button(text(label).size(12))
+                .on_press(message)
+                .padding([3, 10])
+                .style(move |_theme, status| button::Style {
+                    background: matches!(status, button::Status::Hovered)
+                        .then_some(Background::Color(pal.surface_raised)),
+                    text_color: pal.text,
+                    border: Border {
+                        radius: 8.0.into(),
+                        width: 1.0,
+                        color: pal.border,
+                    },
+                    ..Default::default()
+                })
+        };
+
+        Some(
+            container(
+                row![
+                    bootstrap::exclamation_triangle()
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.danger)
+                        }),
+                    text(heading).size(12).width(Length::Fill),
+                    action("Open report", Message::OpenCrashReport),
+                    action("Report on GitHub", Message::SubmitCrashReport),
+                    action("Dismiss", Message::DismissCrashReports),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            )
+            .padding([6, 12])
+            .width(Length::Fill)
+            .style(move |_| container::Style {
+                background: Some(Background::Color(Color {
+                    a: 0.6,
+                    ..pal.surface_raised
+                })),
+                border: Border {
+                    radius: 10.0.into(),
+                    width: 1.0,
+                    color: Color { a: 0.4, ..pal.danger },
+                },
+                text_color: Some(pal.text),
+                ..Default::default()
+            })
+            .into(),
+        )
+    }
+
+    /// Banner shown while the provider is unreachable, listing queued prompts
+    fn offline_banner(&self, pal: PaletteColors) -> Option<Element<'_, Message>> {
+        if !self.is_offline() && self.queued_prompts.is_empty() {
+            return None;
+        }
+        let heading = if self.is_offline() {
+            format!(
+                "Offline: {} is unreachable. Prompts are queued and sent when it's back.",
+                self.config.active_provider
+            )
+        } else {
+            "Back online, sending queued prompts…".to_string()
+        };
+        let mut content = column![row![
+            bootstrap::wifi_off()
+                .size(13)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.danger)
+                }),
+            text(heading).size(12),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center)]
+        .spacing(4);
+
+        for (idx, queued) in self.queued_prompts.iter().enumerate() {
+            let first_line = queued.prompt.lines().next().unwrap_or_default();
+            let mut preview: String = first_line.chars().take(80).collect();
+            if preview.len() < queued.prompt.trim_end().len() {
+                preview.push('…');
+            }
+            content = content.push(
+                row![
+                    bootstrap::clock()
+                        .size(11)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                    text(preview)
+                        .size(12)
+                        .width(Length::Fill)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                    button(bootstrap::x_lg().size(10))
+                        .on_press(Message::RemoveQueuedPrompt(idx))
+                        .padding(2)
+                        .style(move |_theme, status| button::Style {
+                            background: None,
+                            text_color: if matches!(status, button::Status::Hovered) {
+                                pal.danger
+                            } else {
+                                pal.muted
+                            },
+                            ..Default::default()
+                        }),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            );
+        }
+
+        Some(
+            container(content)
+                .padding([6, 12])
+                .width(Length::Fill)
+                .style(move |_| container::Style {
+                    background: Some(Background::Color(Color {
+                        a: 0.6,
+                        ..pal.surface_raised
+                    })),
+                    border: Border {
+                        radius: 10.0.into(),
+                        width: 1.0,
+                        color: Color { a: 0.4, ..pal.danger },
+                    },
+                    text_color: Some(pal.text),
+                    ..Default::default()
+                })
+                .into(),
+        )
+    }
+
+    /// Re-estimates the request the draft would send against the model's
+    /// context window
+    /// Moves the input into the current session and saves it, before
+    /// another session is shown
+    fn stash_draft(&mut self) {
+        let text = self.draft.text();
+        self.draft_edited = None;
+        if let Some(session) = self.sessions.get_mut(self.current) {
+            if text != session.draft {
+                drafts::save(session.id, &text);
+            }
+            session.draft = text;
+        }
+    }
+
+    /// Puts the current session's draft into the input
+    fn restore_draft(&mut self) {
+        let text = self
+            .sessions
+            .get(self.current)
+            .map(|session| session.draft.clone())
+            .unwrap_or_default();
+        self.set_draft(&text);
+    }
+
+    /// Brings back the prompt that was being written when the app closed,
+    /// reopening its conversation if it had one
+    fn restore_latest_draft(&mut self) {
+        let saved = drafts::load();
+        let Some((id, draft)) = drafts::latest(&saved) else {
+            return;
+        };
+        if let Some(idx) = self.sessions.iter().position(|s| s.id == id) {
+            self.current = idx;
+        } else {
+            let session = match self.conversation_manager.load_conversation(id) {
+                Ok(conversation) => {
+                    let mut session = Session::from_events(id, &conversation.events);
+                    session.set_title(conversation.metadata.title.clone());
+                    session.interrupted = conversation.metadata.interrupted;
+                    session
+                }
+                Err(_) => {
+                    let mut session = Session::new();
+                    session.id = id;
+                    session
+                }
+            };
+            if self.sessions[self.current].messages.is_empty() {
+                self.sessions[self.current] = session;
+            } else {
+                self.sessions.push(session);
+                self.current = self.sessions.len() - 1;
+            }
+        }
+        self.sessions[self.current].draft = draft.text.clone();
+        self.restore_draft();
+    }
+
+    /// Replaces the input with `text`, cursor at the end
+    fn set_draft(&mut self, text: &str) {
+        self.draft = text_editor::Content::with_text(text);
+        self.draft
+            .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+        self.refresh_context_estimate();
+        self.refresh_spelling();
+    }
+
+    /// Loads the configured dictionary in the background once spellcheck is
+    /// turned on, and drops it when it is turned off
+    fn load_dictionary(&mut self) -> Task<Message> {
+        if !self.config.get_spellcheck_enabled() {
+            self.spellcheck = None;
+            self.spelling = None;
+            return Task::none();
+        }
+        if self.spellcheck.is_some() {
+            return Task::none();
+        }
+        let language = self.config.get_spellcheck_language();
+        Task::perform(
+            async move { Dictionary::load(&language).map(std::sync::Arc::new) },
+            Message::DictionaryLoaded,
+        )
+    }
+
+    /// Offers corrections for the last misspelled word of the input. The
+    /// word being typed isn't judged until something follows it.
+    fn refresh_spelling(&mut self) {
+        let Some(dictionary) = self.spellcheck.clone() else {
+            self.spelling = None;
+            return;
+        };
+        let text = self.draft.text();
+        let Some(range) = dictionary
+            .misspelled(&text)
+            .into_iter()
+            .rev()
+            .filter(|range| range.end < text.len())
+            .find(|range| !self.spelling_ignored.contains(&text[range.clone()]))
+        else {
+            self.spelling = None;
+            return;
+        };
+        let word = text[range.clone()].to_string();
+        if let Some(fix) = self.spelling.as_mut().filter(|fix| fix.word == word) {
+            fix.range = range;
+            return;
+        }
+        let suggestions = dictionary.suggest(&word, SPELLING_SUGGESTIONS);
+        self.spelling = Some(SpellingFix {
+            word,
+            range,
+            suggestions,
+        });
+    }
+
+    fn refresh_context_estimate(&mut self) {
+        let Some(session) = self.sessions.get(self.current) else {
+            self.context_estimate = None;
+            return;
+        };
+        let model = effective_model(&self.config, self.project_config.as_ref());
+        let system_tokens =
+            estimate_tokens(&build_enhanced_system_prompt(&self.config_form.system_prompt))
+                + self.dispatcher.context_report().used_tokens;
+        let max_tokens = session
+            .max_tokens
+            .unwrap_or(self.config_form.max_tokens as u32);
+        self.context_estimate = context_limit::estimate(
+            &model,
+            system_tokens,
+            &session.text_history(),
+            &self.config.get_history_strategy(&model),
+            &self.draft.text(),
+            max_tokens,
+        )
+        .map(|size| (session.id, size));
+    }
+
+    /// Warning above the input bar when the next request is close to or over
+    /// the model's context window, with one-click summarizing and trimming
+    fn context_warning(&self, pal: PaletteColors) -> Option<Element<'_, Message>> {
+        let session = self.sessions.get(self.current)?;
+        let size = self
+            .context_estimate
+            .filter(|(id, _)| *id == session.id)
+            .map(|(_, size)| size)?;
+        let fit = size.fit();
+        if fit == ContextFit::Fits {
+            return None;
+        }
+
+        let color = if fit == ContextFit::OverLimit {
+            pal.danger
+        } else {
+            Color::from_rgb(0.95, 0.68, 0.2)
+        };
+        let message = format!(
+            "≈{} of {} tokens: {}",
+            model_info::format_tokens(size.total() as u32),
+            model_info::format_tokens(size.limit as u32),
+            if fit == ContextFit::OverLimit {
+                "this request won't fit the model's context"
+            } else {
+                "close to the model's context limit"
+            }
+        );
+        let action = move |label: &'static str, message: Option<Message>| {
+            button(text(label).size(12))
+                .on_press_maybe(message)
+                .padding([4, 10])
+                .style(move |_theme, status| button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if matches!(status, button::Status::Hovered) { 0.25 } else { 0.12 },
+                        ..pal.accent
+                    })),
+                    border: Border {
+                        radius: 8.0.into(),
+                        ..Default::default()
+                    },
+                    text_color: pal.text,
+                    ..Default::default()
+                })
+        };
+        let compacting = self.compacting_history.contains(&session.id);
+        let idle = !compacting && !session.is_streaming;
+        let actions: Element<'_, Message> = if compacting {
+            text("Compacting history…").size(12).into()
+        } else {
+            row![
+                action(
+                    "Summarize older turns",
+                    idle.then_some(Message::CompactHistory(Compaction::Summarize)),
+                ),
+                action(
+                    "Trim oldest turns",
+                    idle.then_some(Message::CompactHistory(Compaction::Trim {
+                        max_tokens: size.trim_budget(),
+                    })),
+                ),
+            ]
+            .spacing(6)
+            .into()
+        };
+
+        Some(
+            container(
+                row![
+                    bootstrap::exclamation_triangle()
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style { color: Some(color) }),
+                    text(message).size(12).width(Length::Fill),
+                    actions,
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            )
+            .padding([6, 12])
+            .style(move |_| container::Style {
+                background: Some(Background::Color(Color {
+                    a: 0.6,
+                    ..pal.surface_raised
+                })),
+                border: Border {
+                    radius: 10.0.into(),
+                    width: 1.0,
+                    color: Color { a: 0.5, ..color },
+                },
+                text_color: Some(pal.text),
+                ..Default::default()
+            })
+            .into(),
+        )
+    }
+
+    /// Small ok/degraded/unreachable dot for the active provider; the tooltip
+    /// explains the state and repeats the last ping error
+    fn provider_health_dot(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let provider = &self.config.active_provider;
+        let (color, mut label) = match &self.provider_health {
+            None => (pal.muted, format!("Checking {}…", provider)),
+            Some(report) => {
+                let latency = report
+                    .latency
+                    .map(|l| format!(" · {} ms", l.as_millis()))
+                    .unwrap_or_default();
+                match report.health {
+                    Health::Ok => (pal.success, format!("{}: OK{}", provider, latency)),
+                    Health::Degraded => (
+                        Color::from_rgb(0.95, 0.68, 0.2),
+                        format!("{}: degraded{}", provider, latency),
+                    ),
+                    Health::Unreachable => (pal.danger, format!("{}: unreachable", provider)),
+                }
+            }
+        };
+        if let Some((error, at)) = &self.provider_last_error {
+            label.push_str(&format!("\nLast error ({}): {}", at.format("%H:%M"), error));
+        }
+
+        tooltip(
+            container(Space::new())
+                .width(Length::Fixed(8.0))
+                .height(Length::Fixed(8.0))
+                .style(move |_| container::Style {
+                    background: Some(Background::Color(color)),
+                    border: Border {
+                        radius: 4.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }),
+            container(text(label).size(11))
+                .padding([4, 8])
+                .max_width(320.0)
+                .style(move |_| container::Style {
+                    background: Some(Background::Color(pal.surface_raised)),
+                    border: Border {
+                        radius: 6.0.into(),
+                        width: 1.0,
+                        color: pal.border,
+                    },
+                    text_color: Some(pal.text),
+                    ..Default::default()
+                }),
+            tooltip::Position::Bottom,
+        )
+        .into()
+    }
+
+    /// Creates the directory popup overlay - improved UX
+    fn directory_popup(&self, pal: PaletteColors) -> Element<'_, Message> {
+        if !self.show_directory_popup {
+            return Space::new().into();
+        }
+
+        let mut popup_content: Vec<Element<'_, Message>> = Vec::new();
+
+        // ─────────────────────────────────────────────────────────────────
+        // HEADER
+        // ─────────────────────────────────────────────────────────────────
+        
+        popup_content.push(
+            row![
+                text("Select Directory")
+                    .size(14)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.text)
+                    }),
+                Space::new().width(Length::Fill),
+                button(
+                    bootstrap::x_lg()
+                        .size(14)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        })
+                )
+                .on_press(Message::ToggleDirectoryPopup)
+                .padding(4)
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.2 } else { 0.0 },
+                            ..pal.muted
+                        })),
+                        border: Border {
+                            radius: 4.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                }),
+            ]
+            .align_y(iced::Alignment::Center)
+            .into()
+        );
+
+        popup_content.push(Space::new().height(Length::Fixed(12.0)).into());
+
+        // ─────────────────────────────────────────────────────────────────
+        // PRIMARY ACTION: Browse button (large and prominent)
+        // ─────────────────────────────────────────────────────────────────
+
+        let browse_button = button(
+            row![
+                bootstrap::folder_plus()
+                    .size(20)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.text)
+                    }),
+                Space::new().width(Length::Fixed(12.0)),
+                column![
+                    text("Browse...")
+                        .size(14)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.text)
+                        }),
+                    text("Open native file picker")
+                        .size(11)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                ]
+                .spacing(2),
+            ]
+            .align_y(iced::Alignment::Center),
+        )
+        .on_press(Message::OpenDirectoryPicker)
+        .padding([14, 16])
+        .width(Length::Fill)
+        .style(move |_theme, status| {
+            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+            button::Style {
+                background: Some(Background::Color(Color {
+                    a: if is_hovered { 0.35 } else { 0.25 },
+                    ..pal.accent
+                })),
+                border: Border {
+                    radius: 10.0.into(),
+                    width: 1.0,
+                    color: Color {
+                        a: if is_hovered { 0.6 } else { 0.4 },
+                        ..pal.accent
+                    },
+                },
+                text_color: pal.text,
+                ..Default::default()
+            }
+        });
+
+        popup_content.push(browse_button.into());
+        popup_content.push(Space::new().height(Length::Fixed(16.0)).into());
+
+        // ─────────────────────────────────────────────────────────────────
+        // QUICK ACCESS: Home, Documents, Desktop
+        // ─────────────────────────────────────────────────────────────────
+
+        popup_content.push(
+            text("Quick Access")
+                .size(11)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                })
+                .into()
+        );
+        popup_content.push(Space::new().height(Length::Fixed(6.0)).into());
+
+        // Quick access buttons in a row
+        let home_dir = dirs::home_dir().unwrap_or_default();
+        let home_clone = home_dir.clone();
+        let docs_dir = dirs::document_dir().unwrap_or_else(|| home_dir.join("Documents"));
+        let docs_clone = docs_dir.clone();
+        let desktop_dir = dirs::desktop_dir().unwrap_or_else(|| home_dir.join("Desktop"));
+        let desktop_clone = desktop_dir.clone();
+
+        let quick_access_row = row![
+            // Home
+            button(
+                column![
+                    bootstrap::house()
+                        .size(18)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                    text("Home")
+                        .size(11)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                ]
+                .spacing(4)
+                .align_x(iced::Alignment::Center)
+            )
+            .on_press(Message::SelectRecentDirectory(home_clone))
+            .padding([10, 16])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.2 } else { 0.1 },
+                        ..pal.surface_raised
+                    })),
+                    border: Border {
+                        radius: 8.0.into(),
+                        width: 1.0,
+                        color: Color {
+                            a: if is_hovered { 0.3 } else { 0.15 },
+                            ..pal.border
+                        },
+                    },
+                    ..Default::default()
+                }
+            }),
+            Space::new().width(Length::Fixed(8.0)),
+            // Documents
+            button(
+                column![
+                    bootstrap::file_earmark_text()
+                        .size(18)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                    text("Docs")
+                        .size(11)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                ]
+                .spacing(4)
+                .align_x(iced::Alignment::Center)
+            )
+            .on_press(Message::SelectRecentDirectory(docs_clone))
+            .padding([10, 16])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.2 } else { 0.1 },
+                        ..pal.surface_raised
+                    })),
+                    border: Border {
+                        radius: 8.0.into(),
+                        width: 1.0,
+                        color: Color {
+                            a: if is_hovered { 0.3 } else { 0.15 },
+                            ..pal.border
+                        },
+                    },
+                    ..Default::default()
+                }
+            }),
+            Space::new().width(Length::Fixed(8.0)),
+            // Desktop
+            button(
+                column![
+                    bootstrap::display()
+                        .size(18)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                    text("Desktop")
+                        .size(11)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                ]
+                .spacing(4)
+                .align_x(iced::Alignment::Center)
+            )
+            .on_press(Message::SelectRecentDirectory(desktop_clone))
+            .padding([10, 16])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.2 } else { 0.1 },
+                        ..pal.surface_raised
+                    })),
+                    border: Border {
+                        radius: 8.0.into(),
+                        width: 1.0,
+                        color: Color {
+                            a: if is_hovered { 0.3 } else { 0.15 },
+                            ..pal.border
+                        },
+                    },
+                    ..Default::default()
+                }
+            }),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        popup_content.push(quick_access_row.into());
+
+        // ─────────────────────────────────────────────────────────────────
+        // BOOKMARKED AND RECENT DIRECTORIES (if any)
+        // ─────────────────────────────────────────────────────────────────
+
+        let directory_item = |dir: &PathBuf, pinned: bool| -> Element<'static, Message> {
+            let dir_name = dir
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_else(|| dir.to_str().unwrap_or("?"))
+                .to_string();
+
+            // Truncate path for display
+            let dir_path = dir.display().to_string();
+            let display_path = if dir_path.chars().count() > 35 {
+                let truncate_at = dir_path
+                    .char_indices()
+                    .take(32)
+                    .last()
+                    .map(|(i, c)| i + c.len_utf8())
+                    .unwrap_or(0);
+                format!("{}...", &dir_path[..truncate_at])
+            } else {
+                dir_path
+            };
+
+            let select_button = button(
+                row![
+                    (if pinned { bootstrap::folder_fill() } else { bootstrap::folder() })
+                        .size(14)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.accent)
+                        }),
+                    Space::new().width(Length::Fixed(10.0)),
+                    column![
+                        text(dir_name)
+                            .size(13)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.text)
+                            }),
+                        text(display_path)
+                            .size(10)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(Color { a: 0.6, ..pal.muted })
+                            }),
+                    ]
+                    .spacing(2),
+                ]
+                .align_y(iced::Alignment::Center),
+            )
+            .on_press(Message::SelectRecentDirectory(dir.clone()))
+            .padding([10, 12])
+            .width(Length::Fill)
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.15 } else { 0.05 },
+                        ..pal.surface_raised
+                    })),
+                    border: Border {
+                        radius: 8.0.into(),
+                        width: 0.0,
+                        color: Color::TRANSPARENT,
+                    },
+                    text_color: pal.text,
+                    ..Default::default()
+                }
+            });
+
+            let pin_button = tooltip(
+                button(
+                    (if pinned { bootstrap::pin_fill() } else { bootstrap::pin() })
+                        .size(12)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(if pinned { pal.accent } else { pal.muted })
+                        }),
+                )
+                .on_press(Message::ToggleDirectoryBookmark(dir.clone()))
+                .padding(8)
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.15 } else { 0.0 },
+                            ..pal.surface_raised
+                        })),
+                        border: Border {
+                            radius: 8.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                }),
+                text(if pinned { "Remove bookmark" } else { "Bookmark" }).size(11),
+                tooltip::Position::Left,
+            );
+
+            row![select_button, pin_button]
+                .spacing(4)
+                .align_y(iced::Alignment::Center)
+                .into()
+        };
+
+        let section_label = |label: &'static str| -> Element<'static, Message> {
+            text(label)
+                .size(11)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                })
+                .into()
+        };
+
+        if !self.bookmarked_directories.is_empty() {
+            popup_content.push(Space::new().height(Length::Fixed(16.0)).into());
+            popup_content.push(section_label("Bookmarks"));
+            popup_content.push(Space::new().height(Length::Fixed(6.0)).into());
+            for dir in &self.bookmarked_directories {
+                popup_content.push(directory_item(dir, true));
+            }
+        }
+
+        // Bookmarked directories are already listed above
+        let recent: Vec<&PathBuf> = self
+            .recent_directories
+            .iter()
+            .filter(|dir| !self.bookmarked_directories.contains(dir))
+            .take(4)
+            .collect();
+        if !recent.is_empty() {
+            popup_content.push(Space::new().height(Length::Fixed(16.0)).into());
+            popup_content.push(section_label("Recent"));
+            popup_content.push(Space::new().height(Length::Fixed(6.0)).into());
+            for dir in recent {
+                popup_content.push(directory_item(dir, false));
+            }
+        }
+
+        // ─────────────────────────────────────────────────────────────────
+        // MANUAL PATH INPUT (expandable)
+        // ─────────────────────────────────────────────────────────────────
+
+        popup_content.push(Space::new().height(Length::Fixed(12.0)).into());
+        
+        if self.show_directory_custom_input {
+            let dir_input = text_input("Enter path (e.g. /home/user/project)", &self.directory_draft)
+                .on_input(Message::DirectoryDraftChanged)
+                .on_submit(Message::ChangeDirectory)
+                .padding([10, 12])
+                .size(13)
+                .width(Length::Fill)
+                .style(move |_theme, status| {
+                    let is_focused = matches!(status, iced::widget::text_input::Status::Focused { .. });
+                    iced::widget::text_input::Style {
+                        background: Background::Color(Color {
+                            a: 0.15,
+                            ..pal.surface_raised
+                        }),
+                        border: Border {
+                            radius: 8.0.into(),
+                            width: 1.0,
+                            color: if is_focused { pal.accent } else { Color { a: 0.3, ..pal.border } },
+                        },
+                        icon: pal.muted,
+                        placeholder: pal.muted,
+                        value: pal.text,
+                        selection: Color { a: 0.3, ..pal.accent },
+                    }
+                });
+
+            popup_content.push(dir_input.into());
+            popup_content.push(Space::new().height(Length::Fixed(8.0)).into());
+            
+            let confirm_btn = button(
+                text("Open")
+                    .size(13)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.text)
+                    })
+            )
+            .on_press(Message::ChangeDirectory)
+            .padding([8, 20])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.4 } else { 0.3 },
+                        ..pal.success
+                    })),
+                    border: Border {
+                        radius: 8.0.into(),
+                        ..Default::default()
+                    },
+                    text_color: pal.text,
+                    ..Default::default()
+                }
+            });
+
+            popup_content.push(
+                row![
+                    Space::new().width(Length::Fill),
+                    confirm_btn,
+                ]
+                .into()
+            );
+        } else {
+            // Toggle to show manual input
+            let manual_button = button(
+                row![
+                    bootstrap::keyboard()
+                        .size(12)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                    Space::new().width(Length::Fixed(6.0)),
+                    text("Type path manually")
+                        .size(11)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        }),
+                ]
+                .align_y(iced::Alignment::Center),
+            )
+            .on_press(Message::ShowDirectoryCustomInput)
+            .padding([6, 10])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.1 } else { 0.0 },
+                        ..pal.surface_raised
+                    })),
+                    border: Border {
+                        radius: 6.0.into(),
+                        ..Default::default()
+                    },
+                    text_color: pal.muted,
+                    ..Default::default()
                 }
-                self.config_form = ConfigForm::from_config(&self.config);
-                self.config_form.set_success("Settings saved successfully!");
-            }
-            Err(err) => {
-                self.config_form
-                    .set_error(&format!("Failed to save settings: {err}"));
-            }
+            });
+
+            popup_content.push(manual_button.into());
         }
-    }
 
-    fn subscription(&self) -> Subscription<Message> {
-        let stream = self.dispatcher.subscription().map(Message::Received);
-        let ticks = time::every(Duration::from_millis(TICK_INTERVAL_MS)).map(|_| Message::Tick);
-        Subscription::batch(vec![stream, ticks])
-    }
+        // ─────────────────────────────────────────────────────────────────
+        // POPUP CONTAINER
+        // ─────────────────────────────────────────────────────────────────
 
-    fn view(&self) -> Element<'_, Message> {
-        let pal = palette_from_mode(self.theme_mode);
-        
-        // Debug: print current theme mode
-        static LAST_THEME: std::sync::atomic::AtomicU8 = std::sync::atomic::AtomicU8::new(255);
-        let current_theme_id = match self.theme_mode {
-            ThemeMode::Light => 0,
-            ThemeMode::Dark => 1,
-            ThemeMode::Black => 2,
-        };
-        if LAST_THEME.load(std::sync::atomic::Ordering::Relaxed) != current_theme_id {
-            println!("🎨 View rendering with theme: {:?}, background: {:?}", self.theme_mode, pal.background);
-            LAST_THEME.store(current_theme_id, std::sync::atomic::Ordering::Relaxed);
-        }
+        let popup = container(
+            column(popup_content)
+                .spacing(2)
+                .padding(16)
+        )
+        .width(Length::Fixed(340.0))
+        .style(move |_| container::Style {
+            background: Some(Background::Color(Color {
+                a: 0.95,
+                ..pal.background
+            })),
+            border: Border {
+                radius: 16.0.into(),
+                width: 1.0,
+                color: Color { a: 0.4, ..pal.border },
+            },
+            ..Default::default()
+        });
 
-        // Show error dialog if initialization failed
-        if let Some(ref error) = self.init_error {
-            return self.error_view(error, pal);
+        // Position the popup below the top bar
+        container(
+            column![
+                Space::new().height(Length::Fixed(70.0)), // Below top bar
+                row![
+                    Space::new().width(Length::Fixed(100.0)),
+                    popup,
+                ],
+            ]
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Creates the context budget panel: which prompt sources were included,
+    /// truncated or dropped to fit the token budget
+    fn context_panel(&self, pal: PaletteColors) -> Element<'_, Message> {
+        if !self.show_context_panel {
+            return Space::new().into();
         }
 
-        let background = Canvas::new(LivingBackground::<Message>::new(
-            &self.bg_state,
-            pal,
-            self.bg_opacity,
-        ))
-        .width(Length::Fill)
-        .height(Length::Fill);
+        let report = self.dispatcher.context_report();
+        let mut panel_content: Vec<Element<'_, Message>> = Vec::new();
 
-        // Get current session streaming state for typing indicator
-        let session = &self.sessions[self.current];
-        let is_streaming = session.is_streaming;
+        panel_content.push(
+            row![
+                text("Context")
+                    .size(14)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.text)
+                    }),
+                Space::new().width(Length::Fill),
+                text(format!("{} / {} tokens", report.used_tokens, report.budget_tokens))
+                    .size(11)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    }),
+                Space::new().width(Length::Fixed(8.0)),
+                button(
+                    bootstrap::x_lg()
+                        .size(14)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        })
+                )
+                .on_press(Message::ToggleContextPanel)
+                .padding(4)
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.2 } else { 0.0 },
+                            ..pal.muted
+                        })),
+                        border: Border {
+                            radius: 4.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                }),
+            ]
+            .align_y(iced::Alignment::Center)
+            .into()
+        );
 
-        // Calculate width offset for top bar and input bar based on layout offset (smooth animation)
-        let sidebar_width = 340.0 * self.conversations_layout_offset;
+        panel_content.push(Space::new().height(Length::Fixed(10.0)).into());
 
-        // Build main layer with top bar, chat content, optional typing indicator, and input
-        let mut main_content: Vec<Element<'_, Message>> = vec![self.top_bar(pal, sidebar_width)];
-        main_content.push(self.chat_panel(pal));
+        for entry in &report.entries {
+            let (status_label, status_color) = match entry.status {
+                ContextStatus::Included => ("Included".to_string(), pal.success),
+                ContextStatus::Truncated { kept_tokens } => {
+                    (format!("Truncated to {}", kept_tokens), pal.accent)
+                }
+                ContextStatus::Dropped => ("Dropped".to_string(), pal.danger),
+            };
 
-        // Add typing indicator above input when streaming
-        if is_streaming {
-            main_content.push(self.typing_indicator(pal));
+            panel_content.push(
+                row![
+                    column![
+                        text(entry.label.clone())
+                            .size(13)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.text)
+                            }),
+                        text(format!("{} · {} tokens", entry.priority.as_str(), entry.tokens))
+                            .size(10)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(Color { a: 0.6, ..pal.muted })
+                            }),
+                    ]
+                    .spacing(2),
+                    Space::new().width(Length::Fill),
+                    text(status_label)
+                        .size(11)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(status_color)
+                        }),
+                ]
+                .align_y(iced::Alignment::Center)
+                .padding([6, 4])
+                .into()
+            );
         }
 
-        main_content.push(self.input_area(pal, sidebar_width));
+        let panel = container(
+            column(panel_content)
+                .spacing(2)
+                .padding(16)
+        )
+        .width(Length::Fixed(340.0))
+        .style(move |_| container::Style {
+            background: Some(Background::Color(Color {
+                a: 0.95,
+                ..pal.background
+            })),
+            border: Border {
+                radius: 16.0.into(),
+                width: 1.0,
+                color: Color { a: 0.4, ..pal.border },
+            },
+            ..Default::default()
+        });
 
-        let main_layer = column(main_content)
-            .width(Length::Fill)
-            .height(Length::Fill);
+        // Position the panel below the top bar, next to the directory selector
+        container(
+            column![
+                Space::new().height(Length::Fixed(70.0)), // Below top bar
+                row![
+                    Space::new().width(Length::Fixed(200.0)),
+                    panel,
+                ],
+            ]
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
 
-        let progress = self.menu_state.progress();
-        let overlay = if progress > 0.01 {
-            self.settings_overlay(pal).into()
-        } else {
-            Space::new().into()
+    /// Creates the popover for the current session's temperature and max
+    /// tokens, which apply without touching the saved settings
+    fn session_tuning_popover(&self, pal: PaletteColors) -> Element<'_, Message> {
+        if !self.show_session_tuning {
+            return Space::new().into();
+        }
+        let Some(session) = self.sessions.get(self.current) else {
+            return Space::new().into();
         };
 
-        // Error notification overlay
-        let error_overlay: Element<'_, Message> = if let Some(ref error) = self.stream_error {
-            // Extract a user-friendly error message for collapsed view
-            let short_error = if error.contains("502 Bad Gateway") {
-                "Server temporarily unavailable. Please try again.".to_string()
-            } else if error.contains("500") {
-                "Server error occurred. Please try again later.".to_string()
-            } else if error.contains("timeout") || error.contains("Timeout") {
-                "Request timed out. Please check your connection.".to_string()
-            } else if error.chars().count() > 60 && !self.error_expanded {
-                // Find safe character boundary
-                let truncate_at = error
-                    .char_indices()
-                    .take(60)
-                    .last()
-                    .map(|(i, c)| i + c.len_utf8())
-                    .unwrap_or(0);
-                format!("{}...", &error[..truncate_at])
-            } else {
-                error.clone()
-            };
-
-            let chevron = if self.error_expanded {
-                bootstrap::chevron_down()
-            } else {
-                bootstrap::chevron_right()
-            };
-
-            // Header row (always visible, clickable to expand)
-            let header_row = row![
-                text("!")
-                    .size(18)
-                    .style(move |_| iced::widget::text::Style {
-                        color: Some(Color {
-                            r: 1.0,
-                            g: 0.8,
-                            b: 0.2,
-                            a: 1.0
-                        })
-                    }),
-                Space::new().width(Length::Fixed(8.0)),
-                chevron
+        let temperature = effective_temperature(session.temperature, &self.config, &self.config_form);
+        let max_tokens = session
+            .max_tokens
+            .unwrap_or(self.config_form.max_tokens as u32);
+        let source = |overridden: bool| if overridden { "this chat" } else { "default" };
+        let label = move |name: String, overridden: bool| {
+            row![
+                text(name)
                     .size(12)
                     .style(move |_| iced::widget::text::Style {
-                        color: Some(Color { a: 0.7, ..pal.text })
+                        color: Some(pal.text)
                     }),
-                Space::new().width(Length::Fixed(8.0)),
-                text(if self.error_expanded {
-                    "Error Details".to_string()
-                } else {
-                    short_error
-                })
-                .size(13)
-                .style(move |_| iced::widget::text::Style {
-                    color: Some(pal.text)
-                }),
                 Space::new().width(Length::Fill),
-                button(
-                    text("×")
-                        .size(14)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.text)
-                        })
-                )
-                .on_press(Message::DismissError)
-                .padding([4, 8])
-                .style(move |_theme, _status| button::Style {
-                    background: Some(Background::Color(Color::TRANSPARENT)),
-                    border: Border::default(),
+                text(source(overridden))
+                    .size(10)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(if overridden { pal.accent } else { pal.muted })
+                    }),
+            ]
+            .align_y(iced::Alignment::Center)
+        };
+
+        let reset_button = button(text("Use defaults").size(11))
+            .on_press_maybe(
+                (session.temperature.is_some() || session.max_tokens.is_some())
+                    .then_some(Message::ResetSessionTuning),
+            )
+            .padding([4, 10])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.2 } else { 0.1 },
+                        ..pal.accent
+                    })),
+                    border: Border {
+                        radius: 8.0.into(),
+                        ..Default::default()
+                    },
                     text_color: pal.text,
                     ..Default::default()
-                }),
+                }
+            });
+
+        let panel = container(
+            column![
+                label(
+                    format!("Temperature: {:.1}", temperature),
+                    session.temperature.is_some()
+                ),
+                iced::widget::slider(0.0..=2.0, temperature, Message::SessionTemperatureChanged)
+                    .step(0.1),
+                Space::new().height(Length::Fixed(10.0)),
+                label(format!("Max tokens: {}", max_tokens), session.max_tokens.is_some()),
+                iced::widget::slider(256..=32768, max_tokens, Message::SessionMaxTokensChanged)
+                    .step(256u32),
+                Space::new().height(Length::Fixed(10.0)),
+                row![
+                    text("Applies to this chat only")
+                        .size(10)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(Color { a: 0.6, ..pal.muted })
+                        }),
+                    Space::new().width(Length::Fill),
+                    reset_button,
+                ]
+                .align_y(iced::Alignment::Center),
             ]
-            .align_y(iced::Alignment::Center);
+            .spacing(6)
+            .padding(16),
+        )
+        .width(Length::Fixed(280.0))
+        .style(move |_| container::Style {
+            background: Some(Background::Color(Color {
+                a: 0.95,
+                ..pal.background
+            })),
+            border: Border {
+                radius: 16.0.into(),
+                width: 1.0,
+                color: Color { a: 0.4, ..pal.border },
+            },
+            ..Default::default()
+        });
 
-            // Make header clickable
-            let header_button = button(header_row)
-                .on_press(Message::ToggleErrorExpand)
-                .padding([12, 16])
-                .width(Length::Fill)
-                .style(move |_theme, _status| button::Style {
-                    background: Some(Background::Color(Color::TRANSPARENT)),
-                    border: Border::default(),
-                    text_color: pal.text,
-                    ..Default::default()
-                });
+        // Below the top bar, under the model pill
+        container(
+            column![
+                Space::new().height(Length::Fixed(70.0)), // Below top bar
+                row![
+                    Space::new().width(Length::Fixed(280.0)),
+                    panel,
+                ],
+            ]
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
 
-            // Expanded content (full error details)
-            let expanded_content: Element<'_, Message> = if self.error_expanded {
-                container(
-                    scrollable(text(error.as_str()).size(12).font(Font::MONOSPACE).style(
-                        move |_| iced::widget::text::Style {
-                            color: Some(Color {
-                                r: 1.0,
-                                g: 0.7,
-                                b: 0.7,
-                                a: 1.0,
-                            }),
+    /// Creates the panel previewing a generated commit message, with actions
+    /// to regenerate, copy or commit it
+    fn commit_panel(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let Some(draft) = &self.commit_draft else {
+            return Space::new().into();
+        };
+
+        let action_button = |label: &'static str, message: Option<Message>| {
+            button(text(label).size(11))
+                .on_press_maybe(message)
+                .padding([4, 10])
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.2 } else { 0.1 },
+                            ..pal.accent
+                        })),
+                        border: Border {
+                            radius: 8.0.into(),
+                            ..Default::default()
                         },
-                    ))
-                    .height(Length::Fixed(120.0)),
-                )
-                .padding([6, 16])
-                .width(Length::Fill)
-                .into()
-            } else {
-                Space::new().into()
-            };
+                        text_color: pal.text,
+                        ..Default::default()
+                    }
+                })
+        };
 
-            let error_container = container(column![header_button, expanded_content].spacing(0))
-                .max_width(600.0)
-                .style(move |_| container::Style {
-                    background: Some(Background::Color(Color {
-                        r: 0.2,
-                        g: 0.08,
-                        b: 0.08,
-                        a: 0.95,
-                    })),
+        let message = draft.content.text();
+        let has_message = !message.trim().is_empty();
+        let committed = matches!(draft.status, Some(Ok(_)));
+        let idle = !draft.generating && !committed;
+
+        let body: Element<'_, Message> = if draft.generating && !has_message {
+            text("Summarizing staged changes…")
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                })
+                .into()
+        } else {
+            text_editor(&draft.content)
+                .on_action(Message::CommitDraftAction)
+                .font(Font::MONOSPACE)
+                .size(12)
+                .height(Length::Fixed(160.0))
+                .style(move |_theme, _status| text_editor::Style {
+                    background: Background::Color(Color { a: 0.3, ..pal.surface }),
                     border: Border {
                         radius: 8.0.into(),
                         width: 1.0,
-                        color: Color {
-                            r: 0.8,
-                            g: 0.3,
-                            b: 0.3,
-                            a: 0.8,
-                        },
+                        color: Color { a: 0.3, ..pal.border },
                     },
-                    ..Default::default()
-                });
+                    placeholder: pal.muted,
+                    value: pal.text,
+                    selection: Color { a: 0.3, ..pal.accent },
+                })
+                .into()
+        };
 
-            container(column![
-                Space::new().height(Length::Fill),
-                error_container,
-                Space::new().height(Length::Fixed(80.0)), // Space above input
-            ])
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .align_x(Horizontal::Center)
-            .into()
-        } else {
-            Space::new().into()
+        let status: Element<'_, Message> = match &draft.status {
+            Some(Ok(summary)) => text(format!("✓ {}", summary))
+                .size(11)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.success)
+                })
+                .into(),
+            Some(Err(err)) => text(err.clone())
+                .size(11)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.danger)
+                })
+                .into(),
+            None => Space::new().into(),
         };
 
-        let directory_popup = self.directory_popup(pal);
-        let conversations_sidebar = self.conversations_sidebar(pal);
+        let panel = container(
+            column![
+                row![
+                    text("Commit message")
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.text)
+                        }),
+                    Space::new().width(Length::Fill),
+                    text("Conventional Commits · staged changes")
+                        .size(10)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(Color { a: 0.6, ..pal.muted })
+                        }),
+                ]
+                .align_y(iced::Alignment::Center),
+                body,
+                status,
+                row![
+                    action_button(
+                        "Regenerate",
+                        (!draft.generating).then_some(Message::GenerateCommitMessage)
+                    ),
+                    action_button(
+                        "Copy",
+                        has_message.then(|| Message::CopyToClipboard(message.trim().to_string()))
+                    ),
+                    Space::new().width(Length::Fill),
+                    action_button("Close", Some(Message::CloseCommitDraft)),
+                    action_button(
+                        "Commit",
+                        (idle && has_message).then_some(Message::CommitStaged)
+                    ),
+                ]
+                .spacing(6)
+                .align_y(iced::Alignment::Center),
+            ]
+            .spacing(10)
+            .padding(16),
+        )
+        .width(Length::Fixed(460.0))
+        .style(move |_| container::Style {
+            background: Some(Background::Color(Color {
+                a: 0.95,
+                ..pal.background
+            })),
+            border: Border {
+                radius: 16.0.into(),
+                width: 1.0,
+                color: Color { a: 0.4, ..pal.border },
+            },
+            ..Default::default()
+        });
 
-        // Add backdrop overlay for conversations sidebar
-        let conversations_backdrop: Element<'_, Message> = if self.show_conversations {
-            button(Space::new().width(Length::Fill).height(Length::Fill))
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .style(move |_theme, _status| button::Style {
-                    background: Some(Background::Color(Color {
-                        r: 0.0,
-                        g: 0.0,
-                        b: 0.0,
-                        a: self.conversations_sidebar_animation * 0.3, // Darken backdrop when sidebar is open
-                    })),
-                    ..Default::default()
+        // Below the top bar, under the git pill
+        container(
+            column![
+                Space::new().height(Length::Fixed(70.0)),
+                row![Space::new().width(Length::Fixed(360.0)), panel],
+            ]
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Creates the confirmation dialog for applying a code block, showing the
+    /// edit as a diff
+    fn apply_panel(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let Some(pending) = &self.pending_apply else {
+            return Space::new().into();
+        };
+        let edit = &pending.edit;
+
+        let action_button = |label: &'static str, message: Option<Message>, color: Color| {
+            button(text(label).size(12))
+                .on_press_maybe(message)
+                .padding([6, 14])
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.4 } else { 0.25 },
+                            ..color
+                        })),
+                        border: Border {
+                            radius: 8.0.into(),
+                            ..Default::default()
+                        },
+                        text_color: pal.text,
+                        ..Default::default()
+                    }
+                })
+        };
+
+        let diff_lines = edit.diff.lines().map(|line| {
+            let color = match line.chars().next() {
+                Some('+') => pal.success,
+                Some('-') => pal.danger,
+                _ => pal.muted,
+            };
+            text(line.to_string())
+                .size(12)
+                .font(Font::MONOSPACE)
+                .style(move |_| iced::widget::text::Style { color: Some(color) })
+                .into()
+        });
+        let diff: Element<'_, Message> = if edit.diff.is_empty() {
+            text("The file already has this content")
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
                 })
-                .on_press(Message::CloseConversations) // Close when clicking backdrop
                 .into()
         } else {
-            Space::new().into()
+            scrollable(column(diff_lines).padding([4, 0]))
+                .direction(scrollable::Direction::Both {
+                    vertical: scrollable::Scrollbar::default(),
+                    horizontal: scrollable::Scrollbar::default(),
+                })
+                .height(Length::Fixed(360.0))
+                .into()
+        };
+
+        let status: Element<'_, Message> = match &pending.status {
+            Some(Ok(message)) => text(format!("✓ {}", message))
+                .size(11)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.success)
+                })
+                .into(),
+            Some(Err(err)) => text(err.clone())
+                .size(11)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.danger)
+                })
+                .into(),
+            None => Space::new().into(),
         };
+        let applied = matches!(pending.status, Some(Ok(_)));
+        let can_apply = !pending.applying && !applied && !edit.diff.is_empty();
+
+        let panel = container(
+            column![
+                row![
+                    bootstrap::file_earmark_diff()
+                        .size(14)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.accent)
+                        }),
+                    Space::new().width(Length::Fixed(8.0)),
+                    text(pending.display_path.clone())
+                        .size(14)
+                        .font(Font::MONOSPACE)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.text)
+                        }),
+                    Space::new().width(Length::Fill),
+                    text(format!(
+                        "{} · +{} −{}",
+                        edit.tool, edit.lines_added, edit.lines_removed
+                    ))
+                    .size(11)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    }),
+                ]
+                .align_y(iced::Alignment::Center),
+                diff,
+                status,
+                row![
+                    Space::new().width(Length::Fill),
+                    action_button(
+                        if applied { "Close" } else { "Cancel" },
+                        Some(Message::CancelApply),
+                        pal.muted
+                    ),
+                    action_button("Apply", can_apply.then_some(Message::ConfirmApply), pal.success),
+                ]
+                .spacing(8),
+            ]
+            .spacing(10)
+            .padding(16),
+        )
+        .width(Length::Fixed(640.0))
+        .style(move |_| container::Style {
+            background: Some(Background::Color(Color {
+                a: 0.97,
+                ..pal.background
+            })),
+            border: Border {
+                radius: 16.0.into(),
+                width: 1.0,
+                color: Color { a: 0.4, ..pal.border },
+            },
+            ..Default::default()
+        });
 
-        let content = stack(vec![
-            background.into(),
-            main_layer.into(),
-            overlay,
-            conversations_backdrop, // Add backdrop behind conversations sidebar
-            directory_popup,
-            conversations_sidebar,
-            error_overlay,
-        ]);
-        container(content)
+        container(panel)
             .width(Length::Fill)
             .height(Length::Fill)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
             .into()
     }
 
-    fn error_view(&self, error: &str, pal: PaletteColors) -> Element<'_, Message> {
-        let error_text = error.to_string();
-        container(
-            column![
-                text("Initialization Error")
-                    .size(32)
+    /// Creates the review report: findings grouped by severity, each opening
+    /// its file in the preview pane
+    fn review_panel(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let Some(state) = &self.review else {
+            return Space::new().into();
+        };
+
+        let muted_text = move |label: String| {
+            text(label)
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                })
+        };
+        let (summary, body): (String, Element<'_, Message>) = match state {
+            ReviewState::Running => (
+                "Reviewing working tree changes…".to_string(),
+                Space::new().into(),
+            ),
+            ReviewState::Failed(err) => (
+                "Review failed".to_string(),
+                text(err.clone())
+                    .size(12)
                     .style(move |_| iced::widget::text::Style {
                         color: Some(pal.danger)
-                    }),
-                text(error_text)
-                    .size(16)
-                    .style(move |_| iced::widget::text::Style {
-                        color: Some(pal.text)
-                    }),
+                    })
+                    .into(),
+            ),
+            ReviewState::Done(report) => {
+                let summary = format!(
+                    "{} error(s) · {} warning(s) · {} info",
+                    report.count(Severity::Error),
+                    report.count(Severity::Warning),
+                    report.count(Severity::Info)
+                );
+                let mut list = column![].spacing(6);
+                if report.findings.is_empty() {
+                    list = list.push(muted_text("Nothing to flag".to_string()));
+                }
+                for finding in &report.findings {
+                    let color = match finding.severity {
+                        Severity::Error => pal.danger,
+                        Severity::Warning => pal.accent,
+                        Severity::Info => pal.muted,
+                    };
+                    let location = match finding.line {
+                        Some(line) => format!("{}:{}", finding.file, line),
+                        None => finding.file.clone(),
+                    };
+                    let mut entry = column![
+                        row![
+                            text(finding.severity.as_str())
+                                .size(10)
+                                .style(move |_| iced::widget::text::Style {
+                                    color: Some(color)
+                                }),
+                            Space::new().width(Length::Fixed(8.0)),
+                            text(location)
+                                .size(11)
+                                .font(Font::MONOSPACE)
+                                .style(move |_| iced::widget::text::Style {
+                                    color: Some(pal.muted)
+                                }),
+                        ]
+                        .align_y(iced::Alignment::Center),
+                        text(finding.message.clone())
+                            .size(12)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.text)
+                            }),
+                    ]
+                    .spacing(2);
+                    if let Some(suggestion) = &finding.suggestion {
+                        entry = entry.push(muted_text(format!("→ {}", suggestion)));
+                    }
+                    let path = self.current_directory.join(&finding.file);
+                    list = list.push(
+                        button(entry)
+                            .on_press_maybe(
+                                (!finding.file.is_empty()).then(|| Message::OpenFilePreview(path)),
+                            )
+                            .width(Length::Fill)
+                            .padding([6, 8])
+                            .style(move |_theme, status| {
+                                let is_hovered = matches!(status, button::Status::Hovered);
+                                button::Style {
+                                    background: Some(Background::Color(Color {
+                                        a: if is_hovered { 0.1 } else { 0.0 },
+                                        ..pal.accent
+                                    })),
+                                    border: Border {
+                                        radius: 8.0.into(),
+                                        ..Default::default()
+                                    },
+                                    text_color: pal.text,
+                                    ..Default::default()
+                                }
+                            }),
+                    );
+                }
+                for failed in &report.failed_chunks {
+                    list = list.push(muted_text(format!("Not reviewed: {}", failed)));
+                }
+                (summary, scrollable(list).height(Length::Shrink).into())
+            }
+        };
+
+        let panel = container(
+            column![
+                row![
+                    text("Review")
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.text)
+                        }),
+                    Space::new().width(Length::Fill),
+                    muted_text(summary).size(10),
+                ]
+                .align_y(iced::Alignment::Center),
+                body,
             ]
-            .spacing(16)
-            .align_x(iced::Alignment::Center),
+            .spacing(10)
+            .padding(16),
         )
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .align_x(Horizontal::Center)
-        .align_y(Vertical::Center)
+        .width(Length::Fixed(440.0))
+        .max_height(560.0)
         .style(move |_| container::Style {
-            background: Some(Background::Color(pal.background)),
+            background: Some(Background::Color(Color {
+                a: 0.95,
+                ..pal.background
+            })),
+            border: Border {
+                radius: 16.0.into(),
+                width: 1.0,
+                color: Color { a: 0.4, ..pal.border },
+            },
             ..Default::default()
-        })
+        });
+
+        container(
+            column![
+                Space::new().height(Length::Fixed(70.0)), // Below top bar
+                row![Space::new().width(Length::Fixed(80.0)), panel],
+            ]
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
         .into()
     }
 
-    fn top_bar(&self, pal: PaletteColors, sidebar_width: f32) -> Element<'_, Message> {
-        // ─────────────────────────────────────────────────────────────────
-        // LEFT SIDE: Navigation buttons (icon-based for clean look)
-        // ─────────────────────────────────────────────────────────────────
+    /// Creates the compare view: a prompt, then each model's answer in its
+    /// own column with latency, speed, token and cost stats
+    fn compare_panel(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let Some(compare) = &self.compare else {
+            return Space::new().into();
+        };
 
-        // Conversations button (history icon)
-        let conversations_active = self.show_conversations;
-        let conversations_button = button(
-            container(
-                bootstrap::clock_history()
-                    .size(18)
-                    .style(move |_| iced::widget::text::Style {
-                        color: Some(if conversations_active { pal.accent } else { pal.muted })
-                    })
-            )
-            .width(Length::Fixed(36.0))
-            .height(Length::Fixed(36.0))
-            .align_x(Horizontal::Center)
-            .align_y(Vertical::Center)
-        )
-        .on_press(Message::ToggleConversations)
-        .padding(0)
-        .style(move |_theme, status| {
-            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
-            iced::widget::button::Style {
+        let muted_text = move |label: String| {
+            text(label)
+                .size(11)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                })
+        };
+        let input_style = move |_theme: &iced::Theme, status: iced::widget::text_input::Status| {
+            let is_focused = matches!(status, iced::widget::text_input::Status::Focused { .. });
+            iced::widget::text_input::Style {
+                background: Background::Color(Color {
+                    a: 0.15,
+                    ..pal.surface_raised
+                }),
+                border: Border {
+                    radius: 8.0.into(),
+                    width: 1.0,
+                    color: if is_focused { pal.accent } else { Color { a: 0.3, ..pal.border } },
+                },
+                icon: pal.muted,
+                placeholder: pal.muted,
+                value: pal.text,
+                selection: Color { a: 0.3, ..pal.accent },
+            }
+        };
+        let action_style = move |_theme: &iced::Theme, status: button::Status| {
+            let is_hovered = matches!(status, button::Status::Hovered);
+            button::Style {
                 background: Some(Background::Color(Color {
-                    a: if conversations_active { 0.25 } else if is_hovered { 0.15 } else { 0.0 },
+                    a: if is_hovered { 0.3 } else { 0.2 },
                     ..pal.accent
                 })),
                 border: Border {
-                    radius: 10.0.into(),
-                    ..Default::default()
+                    radius: 8.0.into(),
+                    width: 1.0,
+                    color: Color { a: 0.5, ..pal.accent },
                 },
-                text_color: pal.muted,
+                text_color: pal.text,
                 ..Default::default()
             }
-        });
+        };
 
-        // New Chat button (plus icon)
-        let new_chat_button = button(
-            container(
-                bootstrap::plus_lg()
-                    .size(18)
+        let header = row![
+            text("Compare models")
+                .size(14)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.text)
+                }),
+            Space::new().width(Length::Fill),
+            button(
+                bootstrap::x_lg()
+                    .size(14)
                     .style(move |_| iced::widget::text::Style {
                         color: Some(pal.muted)
                     })
             )
-            .width(Length::Fixed(36.0))
-            .height(Length::Fixed(36.0))
-            .align_x(Horizontal::Center)
-            .align_y(Vertical::Center)
-        )
-        .on_press(Message::ClearChat)
-        .padding(0)
-        .style(move |_theme, status| {
-            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
-            iced::widget::button::Style {
+            .on_press(Message::ToggleCompare)
+            .padding(4)
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.2 } else { 0.0 },
+                        ..pal.muted
+                    })),
+                    border: Border {
+                        radius: 4.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }
+            }),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let can_run = !compare.running && !compare.prompt.trim().is_empty();
+        let prompt_row = row![
+            text_input("Prompt to send to both models...", &compare.prompt)
+                .on_input(Message::ComparePromptChanged)
+                .on_submit(Message::RunCompare)
+                .padding([10, 12])
+                .size(13)
+                .width(Length::Fill)
+                .style(input_style),
+            Space::new().width(Length::Fixed(8.0)),
+            button(text(if compare.running { "Running…" } else { "Compare" }).size(13))
+                .on_press_maybe(can_run.then_some(Message::RunCompare))
+                .padding([9, 16])
+                .style(action_style),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let providers = self.config.get_provider_names();
+        let mut columns = row![].spacing(12);
+        for (idx, (provider, model)) in compare.targets.iter().enumerate() {
+            let selector = row![
+                pick_list(
+                    providers.clone(),
+                    Some(provider.clone()),
+                    move |provider| Message::CompareProviderChanged(idx, provider),
+                )
+                .text_size(12),
+                Space::new().width(Length::Fixed(6.0)),
+                text_input("Model", model)
+                    .on_input(move |model| Message::CompareModelChanged(idx, model))
+                    .on_submit(Message::RunCompare)
+                    .padding([7, 10])
+                    .size(12)
+                    .width(Length::Fill)
+                    .style(input_style),
+            ]
+            .align_y(iced::Alignment::Center);
+
+            let body: Element<'_, Message> = match compare.results.get(idx) {
+                _ if compare.running => muted_text("Waiting for the answer…".to_string()).into(),
+                None => muted_text("Answers appear here".to_string()).into(),
+                Some(Comparison { result, .. }) if result.error.is_some() => {
+                    text(result.error.clone().unwrap_or_default())
+                        .size(12)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.danger)
+                        })
+                        .into()
+                }
+                Some(Comparison { result, answer }) => {
+                    let mut stats = vec![format!("{:.1}s", result.total_ms as f64 / 1000.0)];
+                    if let Some(ttft) = result.ttft_ms {
+                        stats.insert(0, format!("first token {}ms", ttft));
+                    }
+                    if let Some(speed) = result.tokens_per_sec {
+                        stats.push(format!("{:.0} tok/s", speed));
+                    }
+                    stats.push(format!("{} tokens", result.completion_tokens));
+                    if let Some(cost) = result.cost {
+                        stats.push(format!("${:.4}", cost));
+                    }
+                    column![
+                        row![
+                            muted_text(stats.join(" · ")),
+                            Space::new().width(Length::Fill),
+                            button(text("Use as default").size(11))
+                                .on_press(Message::UseComparedModel(idx))
+                                .padding([4, 10])
+                                .style(action_style),
+                        ]
+                        .align_y(iced::Alignment::Center),
+                        scrollable(
+                            text(answer.clone())
+                                .size(13)
+                                .style(move |_| iced::widget::text::Style {
+                                    color: Some(pal.text)
+                                })
+                        )
+                        .height(Length::Fill),
+                    ]
+                    .spacing(8)
+                    .into()
+                }
+            };
+
+            columns = columns.push(
+                container(column![selector, body].spacing(10))
+                    .padding(12)
+                    .width(Length::FillPortion(1))
+                    .height(Length::Fill)
+                    .style(move |_| container::Style {
+                        background: Some(Background::Color(Color {
+                            a: 0.3,
+                            ..pal.surface_raised
+                        })),
+                        border: Border {
+                            radius: 12.0.into(),
+                            width: 1.0,
+                            color: Color { a: 0.3, ..pal.border },
+                        },
+                        ..Default::default()
+                    }),
+            );
+        }
+
+        let panel = container(column![header, prompt_row, columns].spacing(12).padding(16))
+            .width(Length::Fixed(960.0))
+            .height(Length::Fixed(620.0))
+            .style(move |_| container::Style {
                 background: Some(Background::Color(Color {
-                    a: if is_hovered { 0.15 } else { 0.0 },
-                    ..pal.accent
+                    a: 0.97,
+                    ..pal.background
                 })),
                 border: Border {
-                    radius: 10.0.into(),
-                    ..Default::default()
+                    radius: 16.0.into(),
+                    width: 1.0,
+                    color: Color { a: 0.4, ..pal.border },
                 },
-                text_color: pal.muted,
                 ..Default::default()
-            }
-        });
+            });
 
-        let left_buttons = row![
-            conversations_button,
-            Space::new().width(Length::Fixed(4.0)),
-            new_chat_button,
-        ]
-        .align_y(iced::Alignment::Center);
+        container(panel)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .into()
+    }
 
-        // ─────────────────────────────────────────────────────────────────
-        // CENTER: Directory selector (pill-shaped with folder icon)
-        // ─────────────────────────────────────────────────────────────────
+    /// Creates the right-hand pane showing a file opened from a read_file
+    /// bubble, with line numbers and syntax highlighting
+    fn file_preview_pane(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let Some(preview) = &self.file_preview else {
+            return Space::new().into();
+        };
 
-        let dir_name = self.current_directory
+        let file_name = preview
+            .path
             .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or_else(|| self.current_directory.to_str().unwrap_or("/"));
-        
-        let is_popup_open = self.show_directory_popup;
-        let directory_button = button(
-            row![
-                bootstrap::folder()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| preview.path.display().to_string());
+        let header = row![
+            bootstrap::file_earmark_text()
+                .size(14)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.accent)
+                }),
+            Space::new().width(Length::Fixed(8.0)),
+            column![
+                text(file_name)
                     .size(14)
-                    .style(move |_| iced::widget::text::Style {
-                        color: Some(if is_popup_open { pal.accent } else { pal.muted })
-                    }),
-                Space::new().width(Length::Fixed(8.0)),
-                text(dir_name)
-                    .size(13)
                     .style(move |_| iced::widget::text::Style {
                         color: Some(pal.text)
                     }),
-                Space::new().width(Length::Fixed(6.0)),
-                bootstrap::chevron_down()
+                text(preview.path.display().to_string())
                     .size(10)
+                    .font(Font::MONOSPACE)
                     .style(move |_| iced::widget::text::Style {
-                        color: Some(Color { a: 0.5, ..pal.muted })
+                        color: Some(Color { a: 0.6, ..pal.muted })
                     }),
             ]
-            .align_y(iced::Alignment::Center),
-        )
-        .on_press(Message::ToggleDirectoryPopup)
-        .padding([8, 14])
-        .style(move |_theme, status| {
-            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
-            button::Style {
+            .spacing(2),
+            Space::new().width(Length::Fill),
+            button(
+                bootstrap::x_lg()
+                    .size(14)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    })
+            )
+            .on_press(Message::CloseFilePreview)
+            .padding(4)
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.2 } else { 0.0 },
+                        ..pal.muted
+                    })),
+                    border: Border {
+                        radius: 4.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }
+            }),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let body: Element<'_, Message> = if let Some(error) = &preview.error {
+            text(format!("Couldn't open file: {}", error))
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.danger)
+                })
+                .into()
+        } else {
+            let line_numbers = (1..=preview.line_count)
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let extension = preview
+                .path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("txt")
+                .to_string();
+            let editor = text_editor(&preview.content)
+                .on_action(Message::FilePreviewAction)
+                .font(Font::MONOSPACE)
+                .size(12)
+                .padding(0)
+                .wrapping(iced::widget::text::Wrapping::None)
+                .height(Length::Shrink)
+                .highlight(&extension, iced::highlighter::Theme::Base16Ocean)
+                .style(move |_theme, _status| text_editor::Style {
+                    background: Background::Color(Color::TRANSPARENT),
+                    border: Border::default(),
+                    placeholder: pal.muted,
+                    value: pal.text,
+                    selection: Color {
+                        a: 0.3,
+                        ..pal.accent
+                    },
+                });
+            scrollable(
+                row![
+                    text(line_numbers)
+                        .size(12)
+                        .font(Font::MONOSPACE)
+                        .align_x(Horizontal::Right)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(Color { a: 0.5, ..pal.muted })
+                        }),
+                    Space::new().width(Length::Fixed(12.0)),
+                    editor,
+                ]
+                .padding([4, 0]),
+            )
+            .direction(scrollable::Direction::Both {
+                vertical: scrollable::Scrollbar::default(),
+                horizontal: scrollable::Scrollbar::default(),
+            })
+            .height(Length::Fill)
+            .into()
+        };
+
+        let mut content = column![header, Space::new().height(Length::Fixed(10.0))];
+        if preview.truncated {
+            content = content.push(
+                text(format!(
+                    "Showing the first {} KB",
+                    FILE_PREVIEW_MAX_BYTES / 1024
+                ))
+                .size(10)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.accent)
+                }),
+            );
+        }
+        content = content.push(body);
+
+        let pane = container(content.spacing(4).padding(16))
+            .width(Length::Fixed(520.0))
+            .height(Length::Fill)
+            .style(move |_| container::Style {
                 background: Some(Background::Color(Color {
-                    a: if is_popup_open { 0.2 } else if is_hovered { 0.1 } else { 0.0 },
-                    ..pal.accent
+                    a: 0.97,
+                    ..pal.background
                 })),
                 border: Border {
-                    radius: 12.0.into(),
+                    radius: 16.0.into(),
                     width: 1.0,
-                    color: Color {
-                        a: if is_popup_open { 0.4 } else if is_hovered { 0.25 } else { 0.15 },
-                        ..pal.border
-                    },
+                    color: Color { a: 0.4, ..pal.border },
                 },
-                text_color: pal.text,
                 ..Default::default()
-            }
-        });
+            });
 
-        // ─────────────────────────────────────────────────────────────────
-        // RIGHT SIDE: Optional AI Initialize button
-        // ─────────────────────────────────────────────────────────────────
+        // Docked to the right, below the top bar
+        container(pane)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(Horizontal::Right)
+            .padding(iced::Padding {
+                top: 70.0,
+                right: 16.0,
+                bottom: 90.0,
+                left: 0.0,
+            })
+            .into()
+    }
 
-        let show_init_button = self.detected_project.is_some() && !self.manifest_is_ai_enhanced;
-        let init_ai_button: Option<Element<'_, Message>> = if show_init_button {
-            Some(
+    /// Creates the outline panel: user turns and tool groups of the current
+    /// session, each scrolling the chat to that message when clicked
+    fn outline_panel(&self, pal: PaletteColors) -> Element<'_, Message> {
+        if !self.show_outline {
+            return Space::new().into();
+        }
+
+        let outline = self.sessions[self.current].outline();
+        let header = row![
+            text("Outline")
+                .size(14)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.text)
+                }),
+            Space::new().width(Length::Fill),
+            button(
+                bootstrap::x_lg()
+                    .size(14)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    })
+            )
+            .on_press(Message::ToggleOutline)
+            .padding(4)
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.2 } else { 0.0 },
+                        ..pal.muted
+                    })),
+                    border: Border {
+                        radius: 4.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }
+            }),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let entries: Vec<Element<'_, Message>> = outline
+            .into_iter()
+            .map(|entry| {
+                let is_tools = entry.tool_count.is_some();
+                let label = match entry.tool_count {
+                    Some(1) => entry.label,
+                    Some(count) => format!("{} (+{} more)", entry.label, count - 1),
+                    None => entry.label,
+                };
+                let icon = if is_tools { bootstrap::terminal() } else { bootstrap::chat_left_text() };
                 button(
                     row![
-                        bootstrap::stars()
-                            .size(14)
-                            .style(move |_| iced::widget::text::Style {
-                                color: Some(pal.accent)
-                            }),
-                        Space::new().width(Length::Fixed(6.0)),
-                        text("AI Init")
-                            .size(12)
+                        icon.size(12).style(move |_| iced::widget::text::Style {
+                            color: Some(if is_tools { pal.muted } else { pal.accent })
+                        }),
+                        Space::new().width(Length::Fixed(8.0)),
+                        text(label)
+                            .size(if is_tools { 11 } else { 12 })
                             .style(move |_| iced::widget::text::Style {
-                                color: Some(pal.text)
+                                color: Some(if is_tools { pal.muted } else { pal.text })
                             }),
                     ]
                     .align_y(iced::Alignment::Center),
                 )
-                .on_press(Message::InitializeProjectWithAI)
-                .padding([8, 14])
+                .on_press(Message::JumpToMessage(entry.message_index))
+                .width(Length::Fill)
+                .padding(if is_tools { [4, 20] } else { [6, 8] })
                 .style(move |_theme, status| {
-                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    let is_hovered = matches!(status, button::Status::Hovered);
                     button::Style {
                         background: Some(Background::Color(Color {
-                            a: if is_hovered { 0.3 } else { 0.2 },
+                            a: if is_hovered { 0.12 } else { 0.0 },
                             ..pal.accent
                         })),
                         border: Border {
-                            radius: 12.0.into(),
-                            width: 1.0,
-                            color: Color {
-                                a: if is_hovered { 0.7 } else { 0.5 },
-                                ..pal.accent
-                            },
+                            radius: 6.0.into(),
+                            ..Default::default()
                         },
                         text_color: pal.text,
                         ..Default::default()
                     }
                 })
-                .into()
-            )
-        } else {
-            None
-        };
-
-        // ─────────────────────────────────────────────────────────────────
-        // TOP BAR LAYOUT: Glassmorphism container
-        // ─────────────────────────────────────────────────────────────────
-
-        let mut top_row = row![
-            left_buttons,
-            Space::new().width(Length::Fixed(12.0)),
-            directory_button,
-        ]
-        .align_y(iced::Alignment::Center);
-        
-        // Push spacer and optional AI button to right
-        top_row = top_row.push(Space::new().width(Length::Fill));
-        
-        if let Some(ai_btn) = init_ai_button {
-            top_row = top_row.push(ai_btn);
-        }
-
-        let top_bar_content = container(top_row)
-            .padding([6, 10])
-            .width(Length::Shrink);
-
-        let top_bar = container(top_bar_content)
-            .style(move |_| container::Style {
-                background: Some(Background::Color(Color {
-                    a: 0.5,
-                    ..pal.surface_raised
-                })),
-                border: Border {
-                    color: Color {
-                        a: 0.3,
-                        ..pal.border
-                    },
-                    width: 1.0,
-                    radius: 16.0.into(),
-                },
-                ..Default::default()
-            });
-
-        // Outer container with padding - adjust left padding based on sidebar width
-        let left_pad = if sidebar_width > 1.0 { sidebar_width } else { 0.0 };
-        container(top_bar)
-            .padding(iced::padding::Padding {
-                top: 12.0,
-                right: 16.0,
-                bottom: 12.0,
-                left: left_pad.max(0.0) + 16.0,
-            })
-            .width(Length::Fill)
-            .height(Length::Shrink)
-            .style(move |_| container::Style {
-                background: None,
-                ..Default::default()
+                .into()
             })
-            .into()
+            .collect();
+
+        let body: Element<'_, Message> = if entries.is_empty() {
+            text("Nothing to outline yet")
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                })
+                .into()
+        } else {
+            scrollable(column(entries).spacing(2))
+                .height(Length::Shrink)
+                .into()
+        };
+
+        let panel = container(
+            column![header, Space::new().height(Length::Fixed(10.0)), body]
+                .padding(16),
+        )
+        .width(Length::Fixed(300.0))
+        .max_height(520.0)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(Color {
+                a: 0.95,
+                ..pal.background
+            })),
+            border: Border {
+                radius: 16.0.into(),
+                width: 1.0,
+                color: Color { a: 0.4, ..pal.border },
+            },
+            ..Default::default()
+        });
+
+        // Right-aligned below the top bar, under the outline button
+        container(
+            column![
+                Space::new().height(Length::Fixed(70.0)), // Below top bar
+                panel,
+            ]
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Horizontal::Right)
+        .padding([0, 16])
+        .into()
     }
 
-    /// Creates the directory popup overlay - improved UX
-    fn directory_popup(&self, pal: PaletteColors) -> Element<'_, Message> {
-        if !self.show_directory_popup {
+    /// Transient status while a failed request of the current session is retried
+    fn retry_notice_banner(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let Some((id, message)) = &self.retry_notice else {
+            return Space::new().into();
+        };
+        if self.sessions.get(self.current).is_none_or(|s| s.id != *id) {
             return Space::new().into();
         }
 
-        let mut popup_content: Vec<Element<'_, Message>> = Vec::new();
-
-        // ─────────────────────────────────────────────────────────────────
-        // HEADER
-        // ─────────────────────────────────────────────────────────────────
-        
-        popup_content.push(
+        let banner = container(
             row![
-                text("Select Directory")
+                bootstrap::arrow_clockwise()
                     .size(14)
                     .style(move |_| iced::widget::text::Style {
-                        color: Some(pal.text)
+                        color: Some(pal.accent)
                     }),
-                Space::new().width(Length::Fill),
-                button(
-                    bootstrap::x_lg()
-                        .size(14)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.muted)
-                        })
-                )
-                .on_press(Message::ToggleDirectoryPopup)
-                .padding(4)
+                text(message.as_str()).size(12).style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                }),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+        )
+        .padding([6, 14])
+        .style(move |_| container::Style {
+            background: Some(Background::Color(pal.surface)),
+            border: Border {
+                radius: 14.0.into(),
+                width: 1.0,
+                color: pal.border,
+            },
+            ..Default::default()
+        });
+
+        container(column![
+            Space::new().height(Length::Fill),
+            banner,
+            Space::new().height(Length::Fixed(80.0)), // Space above input
+        ])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Horizontal::Center)
+        .into()
+    }
+
+    /// Notice shown when a budget limit stopped a run, with "Continue anyway"
+    fn budget_notice_banner(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let Some((_, message)) = &self.budget_notice else {
+            return Space::new().into();
+        };
+
+        let action_button = |label: &'static str, msg: Message, primary: bool| {
+            button(text(label).size(12))
+                .on_press(msg)
+                .padding([6, 12])
                 .style(move |_theme, status| {
-                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    let hovered = matches!(status, button::Status::Hovered);
                     button::Style {
-                        background: Some(Background::Color(Color {
-                            a: if is_hovered { 0.2 } else { 0.0 },
-                            ..pal.muted
+                        background: Some(Background::Color(if primary {
+                            Color {
+                                a: if hovered { 1.0 } else { 0.85 },
+                                ..pal.accent
+                            }
+                        } else if hovered {
+                            pal.surface_raised
+                        } else {
+                            Color::TRANSPARENT
                         })),
+                        text_color: if primary { pal.background } else { pal.text },
                         border: Border {
-                            radius: 4.0.into(),
-                            ..Default::default()
+                            radius: 6.0.into(),
+                            width: if primary { 0.0 } else { 1.0 },
+                            color: pal.border,
                         },
                         ..Default::default()
                     }
-                }),
-            ]
-            .align_y(iced::Alignment::Center)
-            .into()
-        );
-
-        popup_content.push(Space::new().height(Length::Fixed(12.0)).into());
-
-        // ─────────────────────────────────────────────────────────────────
-        // PRIMARY ACTION: Browse button (large and prominent)
-        // ─────────────────────────────────────────────────────────────────
+                })
+        };
 
-        let browse_button = button(
+        let banner = container(
             row![
-                bootstrap::folder_plus()
-                    .size(20)
+                bootstrap::exclamation_triangle_fill()
+                    .size(16)
                     .style(move |_| iced::widget::text::Style {
-                        color: Some(pal.text)
+                        color: Some(pal.accent)
                     }),
-                Space::new().width(Length::Fixed(12.0)),
-                column![
-                    text("Browse...")
-                        .size(14)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.text)
-                        }),
-                    text("Open native file picker")
-                        .size(11)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.muted)
-                        }),
-                ]
-                .spacing(2),
+                text(message.as_str()).size(13).style(move |_| iced::widget::text::Style {
+                    color: Some(pal.text)
+                }),
+                Space::new().width(Length::Fill),
+                action_button("Dismiss", Message::DismissBudgetNotice, false),
+                action_button("Continue anyway", Message::ContinueAnyway, true),
             ]
+            .spacing(10)
             .align_y(iced::Alignment::Center),
         )
-        .on_press(Message::OpenDirectoryPicker)
-        .padding([14, 16])
-        .width(Length::Fill)
-        .style(move |_theme, status| {
-            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
-            button::Style {
-                background: Some(Background::Color(Color {
-                    a: if is_hovered { 0.35 } else { 0.25 },
-                    ..pal.accent
-                })),
-                border: Border {
-                    radius: 10.0.into(),
-                    width: 1.0,
-                    color: Color {
-                        a: if is_hovered { 0.6 } else { 0.4 },
-                        ..pal.accent
-                    },
-                },
-                text_color: pal.text,
-                ..Default::default()
-            }
+        .padding([10, 16])
+        .max_width(600.0)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(pal.surface)),
+            border: Border {
+                radius: 8.0.into(),
+                width: 1.0,
+                color: pal.accent_soft,
+            },
+            ..Default::default()
         });
 
-        popup_content.push(browse_button.into());
-        popup_content.push(Space::new().height(Length::Fixed(16.0)).into());
-
-        // ─────────────────────────────────────────────────────────────────
-        // QUICK ACCESS: Home, Documents, Desktop
-        // ─────────────────────────────────────────────────────────────────
+        container(column![
+            Space::new().height(Length::Fill),
+            banner,
+            Space::new().height(Length::Fixed(80.0)), // Space above input
+        ])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(Horizontal::Center)
+        .into()
+    }
 
-        popup_content.push(
-            text("Quick Access")
-                .size(11)
-                .style(move |_| iced::widget::text::Style {
-                    color: Some(pal.muted)
-                })
-                .into()
-        );
-        popup_content.push(Space::new().height(Length::Fixed(6.0)).into());
+    /// Creates the log viewer: recent `tracing` records from this run, newest last
+    fn log_viewer(&self, pal: PaletteColors) -> Element<'_, Message> {
+        if !self.show_log_viewer {
+            return Space::new().into();
+        }
 
-        // Quick access buttons in a row
-        let home_dir = dirs::home_dir().unwrap_or_default();
-        let home_clone = home_dir.clone();
-        let docs_dir = dirs::document_dir().unwrap_or_else(|| home_dir.join("Documents"));
-        let docs_clone = docs_dir.clone();
-        let desktop_dir = dirs::desktop_dir().unwrap_or_else(|| home_dir.join("Desktop"));
-        let desktop_clone = desktop_dir.clone();
+        let max_severity = LOG_LEVELS
+            .iter()
+            .position(|l| *l == self.log_level_filter)
+            .unwrap_or(2);
+        let level_options: Vec<String> = LOG_LEVELS.iter().map(|l| l.to_string()).collect();
 
-        let quick_access_row = row![
-            // Home
-            button(
-                column![
-                    bootstrap::house()
-                        .size(18)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.muted)
-                        }),
-                    text("Home")
-                        .size(11)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.muted)
-                        }),
-                ]
-                .spacing(4)
-                .align_x(iced::Alignment::Center)
+        let header = row![
+            text("Logs")
+                .size(14)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.text)
+                }),
+            Space::new().width(Length::Fill),
+            pick_list(
+                level_options,
+                Some(self.log_level_filter.clone()),
+                Message::LogLevelFilterChanged,
             )
-            .on_press(Message::SelectRecentDirectory(home_clone))
-            .padding([10, 16])
-            .style(move |_theme, status| {
-                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
-                button::Style {
-                    background: Some(Background::Color(Color {
-                        a: if is_hovered { 0.2 } else { 0.1 },
-                        ..pal.surface_raised
-                    })),
-                    border: Border {
-                        radius: 8.0.into(),
-                        width: 1.0,
-                        color: Color {
-                            a: if is_hovered { 0.3 } else { 0.15 },
-                            ..pal.border
-                        },
-                    },
-                    ..Default::default()
-                }
-            }),
+            .text_size(12),
             Space::new().width(Length::Fixed(8.0)),
-            // Documents
-            button(
-                column![
-                    bootstrap::file_earmark_text()
-                        .size(18)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.muted)
-                        }),
-                    text("Docs")
-                        .size(11)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.muted)
-                        }),
-                ]
-                .spacing(4)
-                .align_x(iced::Alignment::Center)
+            button(text("Clear").size(12))
+                .on_press(Message::ClearLogs)
+                .padding([4, 10])
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.2 } else { 0.1 },
+                            ..pal.muted
+                        })),
+                        border: Border {
+                            radius: 6.0.into(),
+                            ..Default::default()
+                        },
+                        text_color: pal.text,
+                        ..Default::default()
+                    }
+                }),
+            Space::new().width(Length::Fixed(8.0)),
+            button(
+                bootstrap::x_lg()
+                    .size(14)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    })
             )
-            .on_press(Message::SelectRecentDirectory(docs_clone))
-            .padding([10, 16])
+            .on_press(Message::ToggleLogViewer)
+            .padding(4)
             .style(move |_theme, status| {
                 let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
                 button::Style {
                     background: Some(Background::Color(Color {
-                        a: if is_hovered { 0.2 } else { 0.1 },
-                        ..pal.surface_raised
+                        a: if is_hovered { 0.2 } else { 0.0 },
+                        ..pal.muted
                     })),
                     border: Border {
-                        radius: 8.0.into(),
-                        width: 1.0,
-                        color: Color {
-                            a: if is_hovered { 0.3 } else { 0.15 },
-                            ..pal.border
-                        },
+                        radius: 4.0.into(),
+                        ..Default::default()
                     },
                     ..Default::default()
                 }
             }),
-            Space::new().width(Length::Fixed(8.0)),
-            // Desktop
-            button(
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let lines: Vec<Element<'_, Message>> = self
+            .log_records
+            .iter()
+            .filter(|record| {
+                LOG_LEVELS
+                    .iter()
+                    .position(|l| l.eq_ignore_ascii_case(&record.level))
+                    .is_some_and(|severity| severity <= max_severity)
+            })
+            .map(|record| {
+                let level_color = match record.level.as_str() {
+                    "ERROR" => pal.danger,
+                    "WARN" => pal.accent,
+                    "INFO" => pal.success,
+                    _ => pal.muted,
+                };
+                let context = if record.spans.is_empty() {
+                    record.target.clone()
+                } else {
+                    format!("{} {}", record.target, record.spans)
+                };
                 column![
-                    bootstrap::display()
-                        .size(18)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.muted)
-                        }),
-                    text("Desktop")
-                        .size(11)
+                    row![
+                        text(record.timestamp.format("%H:%M:%S%.3f").to_string())
+                            .size(11)
+                            .font(Font::MONOSPACE)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(Color { a: 0.6, ..pal.muted })
+                            }),
+                        Space::new().width(Length::Fixed(8.0)),
+                        text(format!("{:<5}", record.level))
+                            .size(11)
+                            .font(Font::MONOSPACE)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(level_color)
+                            }),
+                        Space::new().width(Length::Fixed(8.0)),
+                        text(context)
+                            .size(11)
+                            .font(Font::MONOSPACE)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(Color { a: 0.6, ..pal.muted })
+                            }),
+                    ],
+                    text(record.message.clone())
+                        .size(12)
+                        .font(Font::MONOSPACE)
                         .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.muted)
+                            color: Some(pal.text)
                         }),
                 ]
-                .spacing(4)
-                .align_x(iced::Alignment::Center)
+                .spacing(2)
+                .padding([4, 0])
+                .into()
+            })
+            .collect();
+
+        let body: Element<'_, Message> = if lines.is_empty() {
+            text("No log records at this level yet")
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                })
+                .into()
+        } else {
+            scrollable(column(lines).spacing(2))
+                .anchor_bottom()
+                .height(Length::Fixed(380.0))
+                .into()
+        };
+
+        let panel = container(
+            column![
+                header,
+                Space::new().height(Length::Fixed(10.0)),
+                body,
+                Space::new().height(Length::Fixed(8.0)),
+                text(format!("Written to {}", logging::log_dir().display()))
+                    .size(10)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(Color { a: 0.6, ..pal.muted })
+                    }),
+            ]
+            .padding(16)
+        )
+        .width(Length::Fixed(560.0))
+        .style(move |_| container::Style {
+            background: Some(Background::Color(Color {
+                a: 0.95,
+                ..pal.background
+            })),
+            border: Border {
+                radius: 16.0.into(),
+                width: 1.0,
+                color: Color { a: 0.4, ..pal.border },
+            },
+            ..Default::default()
+        });
+
+        // Position the panel below the top bar, aligned to the right edge
+        container(
+            column![
+                Space::new().height(Length::Fixed(70.0)), // Below top bar
+                row![
+                    Space::new().width(Length::Fill),
+                    panel,
+                    Space::new().width(Length::Fixed(20.0)),
+                ],
+            ]
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Creates the developer event inspector: the last turn's raw requests,
+    /// responses, chunks and events with timing
+    fn event_inspector(&self, pal: PaletteColors) -> Element<'_, Message> {
+        if !self.show_inspector {
+            return Space::new().into();
+        }
+
+        let snapshot = &self.inspector_snapshot;
+        let filter_options: Vec<String> = std::iter::once("all")
+            .chain(
+                [
+                    InspectorSource::Request,
+                    InspectorSource::Response,
+                    InspectorSource::Chunk,
+                    InspectorSource::StreamEvent,
+                    InspectorSource::UiEvent,
+                ]
+                .iter()
+                .map(|s| s.label()),
             )
-            .on_press(Message::SelectRecentDirectory(desktop_clone))
-            .padding([10, 16])
+            .map(str::to_string)
+            .collect();
+
+        let header = row![
+            text("Event inspector")
+                .size(14)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.text)
+                }),
+            Space::new().width(Length::Fill),
+            pick_list(
+                filter_options,
+                Some(self.inspector_filter.clone()),
+                Message::InspectorFilterChanged,
+            )
+            .text_size(12),
+            Space::new().width(Length::Fixed(8.0)),
+            button(
+                bootstrap::x_lg()
+                    .size(14)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    })
+            )
+            .on_press(Message::ToggleInspector)
+            .padding(4)
             .style(move |_theme, status| {
                 let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
                 button::Style {
                     background: Some(Background::Color(Color {
-                        a: if is_hovered { 0.2 } else { 0.1 },
-                        ..pal.surface_raised
+                        a: if is_hovered { 0.2 } else { 0.0 },
+                        ..pal.muted
                     })),
                     border: Border {
-                        radius: 8.0.into(),
-                        width: 1.0,
-                        color: Color {
-                            a: if is_hovered { 0.3 } else { 0.15 },
-                            ..pal.border
-                        },
+                        radius: 4.0.into(),
+                        ..Default::default()
                     },
                     ..Default::default()
                 }
@@ -2164,143 +8699,302 @@ impl App {
         ]
         .align_y(iced::Alignment::Center);
 
-        popup_content.push(quick_access_row.into());
+        let timing = match snapshot.started_at {
+            Some(started_at) => {
+                let first_chunk = snapshot
+                    .first_chunk_ms()
+                    .map(|ms| format!("first chunk {} ms", ms))
+                    .unwrap_or_else(|| "no chunks yet".to_string());
+                let dropped = if snapshot.dropped > 0 {
+                    format!(" ({} dropped)", snapshot.dropped)
+                } else {
+                    String::new()
+                };
+                format!(
+                    "Turn at {} · {} · {} ms · {} events{}",
+                    started_at
+                        .with_timezone(&chrono::Local)
+                        .format("%H:%M:%S"),
+                    first_chunk,
+                    snapshot.duration_ms(),
+                    snapshot.entries.len(),
+                    dropped
+                )
+            }
+            None => "No turn captured yet".to_string(),
+        };
 
-        // ─────────────────────────────────────────────────────────────────
-        // RECENT DIRECTORIES (if any)
-        // ─────────────────────────────────────────────────────────────────
+        let entries: Vec<Element<'_, Message>> = snapshot
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                self.inspector_filter == "all" || entry.source.label() == self.inspector_filter
+            })
+            .map(|(index, entry)| {
+                let source_color = match entry.source {
+                    InspectorSource::Request => pal.accent,
+                    InspectorSource::Response => pal.success,
+                    InspectorSource::Chunk => pal.muted,
+                    InspectorSource::StreamEvent | InspectorSource::UiEvent => pal.text,
+                };
+                let expanded = self.inspector_expanded.contains(&index);
+                let body = if expanded {
+                    entry.body.clone()
+                } else {
+                    let first_line = entry.body.lines().next().unwrap_or_default();
+                    let mut preview: String =
+                        first_line.chars().take(INSPECTOR_PREVIEW_CHARS).collect();
+                    if preview.len() < entry.body.len() {
+                        preview.push('…');
+                    }
+                    preview
+                };
 
-        if !self.recent_directories.is_empty() {
-            popup_content.push(Space::new().height(Length::Fixed(16.0)).into());
-            popup_content.push(
-                text("Recent")
+                button(
+                    column![
+                        row![
+                            text(format!("+{} ms", entry.elapsed_ms))
+                                .size(11)
+                                .font(Font::MONOSPACE)
+                                .style(move |_| iced::widget::text::Style {
+                                    color: Some(Color { a: 0.6, ..pal.muted })
+                                }),
+                            Space::new().width(Length::Fixed(8.0)),
+                            text(format!("{:<8}", entry.source.label()))
+                                .size(11)
+                                .font(Font::MONOSPACE)
+                                .style(move |_| iced::widget::text::Style {
+                                    color: Some(source_color)
+                                }),
+                            Space::new().width(Length::Fixed(8.0)),
+                            text(entry.title.clone())
+                                .size(11)
+                                .font(Font::MONOSPACE)
+                                .style(move |_| iced::widget::text::Style {
+                                    color: Some(pal.text)
+                                }),
+                        ],
+                        text(body)
+                            .size(11)
+                            .font(Font::MONOSPACE)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(Color { a: 0.8, ..pal.text })
+                            }),
+                    ]
+                    .spacing(2),
+                )
+                .on_press(Message::InspectorEntryToggled(index))
+                .padding([4, 6])
+                .width(Length::Fill)
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if expanded { 0.12 } else if is_hovered { 0.06 } else { 0.0 },
+                            ..pal.accent
+                        })),
+                        border: Border {
+                            radius: 6.0.into(),
+                            ..Default::default()
+                        },
+                        text_color: pal.text,
+                        ..Default::default()
+                    }
+                })
+                .into()
+            })
+            .collect();
+
+        let body: Element<'_, Message> = if entries.is_empty() {
+            text("Nothing captured for this filter. Send a message to record a turn.")
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                })
+                .into()
+        } else {
+            scrollable(column(entries).spacing(2))
+                .anchor_bottom()
+                .height(Length::Fixed(460.0))
+                .into()
+        };
+
+        let panel = container(
+            column![
+                header,
+                Space::new().height(Length::Fixed(4.0)),
+                text(timing)
                     .size(11)
                     .style(move |_| iced::widget::text::Style {
                         color: Some(pal.muted)
-                    })
-                    .into()
-            );
-            popup_content.push(Space::new().height(Length::Fixed(6.0)).into());
+                    }),
+                Space::new().height(Length::Fixed(10.0)),
+                body,
+                Space::new().height(Length::Fixed(8.0)),
+                text("Ctrl+Shift+D to toggle · click an entry to expand")
+                    .size(10)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(Color { a: 0.6, ..pal.muted })
+                    }),
+            ]
+            .padding(16)
+        )
+        .width(Length::Fixed(640.0))
+        .style(move |_| container::Style {
+            background: Some(Background::Color(Color {
+                a: 0.95,
+                ..pal.background
+            })),
+            border: Border {
+                radius: 16.0.into(),
+                width: 1.0,
+                color: Color { a: 0.4, ..pal.border },
+            },
+            ..Default::default()
+        });
+
+        // Left-aligned so it can sit beside the log viewer
+        container(
+            column![
+                Space::new().height(Length::Fixed(70.0)), // Below top bar
+                row![
+                    Space::new().width(Length::Fixed(20.0)),
+                    panel,
+                    Space::new().width(Length::Fill),
+                ],
+            ]
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Creates the conversations sidebar - modern relaxing design
+    /// Animations: Staggered Cascade (opacity), Content Parallax (timing), Glow Reveal
+    /// Uses SLIDE ANIMATION - sidebar stays full width, slides from off-screen (no squishing!)
+    /// Reloads the pinned conversations, and the archived ones while their
+    /// section is open (otherwise only their number)
+    fn refresh_pinned_and_archived(&mut self) {
+        match self
+            .conversation_manager
+            .list_page(ConversationList::Pinned, 0, usize::MAX)
+        {
+            Ok(page) => self.pinned_conversations = page.conversations,
+            Err(err) => tracing::warn!("Failed to list pinned conversations: {}", err),
+        }
+        let limit = if self.show_archived { usize::MAX } else { 0 };
+        match self
+            .conversation_manager
+            .list_page(ConversationList::Archived, 0, limit)
+        {
+            Ok(page) => {
+                self.archived_conversations = page.conversations;
+                self.archived_total = page.total;
+            }
+            Err(err) => tracing::warn!("Failed to list archived conversations: {}", err),
+        }
+    }
+
+    /// Conversations currently listed in the sidebar, in display order
+    fn listed_conversations(&self) -> impl Iterator<Item = &ConversationMetadata> {
+        let archived: &[ConversationMetadata] = if self.show_archived {
+            &self.archived_conversations
+        } else {
+            &[]
+        };
+        self.pinned_conversations
+            .iter()
+            .chain(&self.saved_conversations)
+            .chain(archived)
+    }
 
-            for dir in self.recent_directories.iter().take(4) {
-                let dir_clone = dir.clone();
-                let dir_name = dir
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or_else(|| dir.to_str().unwrap_or("?"));
-                
-                // Truncate path for display
-                let dir_path = dir.display().to_string();
-                let display_path = if dir_path.chars().count() > 35 {
-                    let truncate_at = dir_path
-                        .char_indices()
-                        .take(32)
-                        .last()
-                        .map(|(i, c)| i + c.len_utf8())
-                        .unwrap_or(0);
-                    format!("{}...", &dir_path[..truncate_at])
-                } else {
-                    dir_path
-                };
-                
-                let recent_item = button(
-                    row![
-                        bootstrap::folder()
-                            .size(14)
-                            .style(move |_| iced::widget::text::Style {
-                                color: Some(pal.accent)
-                            }),
-                        Space::new().width(Length::Fixed(10.0)),
-                        column![
-                            text(dir_name)
-                                .size(13)
-                                .style(move |_| iced::widget::text::Style {
-                                    color: Some(pal.text)
-                                }),
-                            text(display_path)
-                                .size(10)
-                                .style(move |_| iced::widget::text::Style {
-                                    color: Some(Color { a: 0.6, ..pal.muted })
-                                }),
-                        ]
-                        .spacing(2),
-                    ]
-                    .align_y(iced::Alignment::Center),
-                )
-                .on_press(Message::SelectRecentDirectory(dir_clone))
-                .padding([10, 12])
-                .width(Length::Fill)
+    /// Bar with actions for the checked conversations, shown in multi-select
+    fn conversation_bulk_bar(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let ids: Vec<uuid::Uuid> = self.selected_conversations.iter().copied().collect();
+        let any = !ids.is_empty();
+        let action = |label: &'static str, message: Message, danger: bool| {
+            button(text(label).size(12))
+                .on_press_maybe(any.then_some(message))
+                .padding([4, 10])
                 .style(move |_theme, status| {
                     let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    let color = if danger { pal.danger } else { pal.accent };
                     button::Style {
                         background: Some(Background::Color(Color {
-                            a: if is_hovered { 0.15 } else { 0.05 },
-                            ..pal.surface_raised
+                            a: if is_hovered { 0.2 } else { 0.08 },
+                            ..color
                         })),
                         border: Border {
                             radius: 8.0.into(),
-                            width: 0.0,
-                            color: Color::TRANSPARENT,
+                            ..Default::default()
+                        },
+                        text_color: if matches!(status, iced::widget::button::Status::Disabled) {
+                            pal.muted
+                        } else {
+                            pal.text
                         },
-                        text_color: pal.text,
                         ..Default::default()
                     }
-                });
-                
-                popup_content.push(recent_item.into());
-            }
-        }
-
-        // ─────────────────────────────────────────────────────────────────
-        // MANUAL PATH INPUT (expandable)
-        // ─────────────────────────────────────────────────────────────────
+                })
+        };
 
-        popup_content.push(Space::new().height(Length::Fixed(12.0)).into());
-        
-        if self.show_directory_custom_input {
-            let dir_input = text_input("Enter path (e.g. /home/user/project)", &self.directory_draft)
-                .on_input(Message::DirectoryDraftChanged)
-                .on_submit(Message::ChangeDirectory)
-                .padding([10, 12])
-                .size(13)
-                .width(Length::Fill)
-                .style(move |_theme, status| {
-                    let is_focused = matches!(status, iced::widget::text_input::Status::Focused { .. });
-                    iced::widget::text_input::Style {
-                        background: Background::Color(Color {
-                            a: 0.15,
-                            ..pal.surface_raised
-                        }),
-                        border: Border {
-                            radius: 8.0.into(),
-                            width: 1.0,
-                            color: if is_focused { pal.accent } else { Color { a: 0.3, ..pal.border } },
-                        },
-                        icon: pal.muted,
-                        placeholder: pal.muted,
-                        value: pal.text,
-                        selection: Color { a: 0.3, ..pal.accent },
+        column![
+            row![
+                text(format!("{} selected", ids.len())).size(12).style(move |_| {
+                    iced::widget::text::Style {
+                        color: Some(pal.muted),
                     }
-                });
+                }),
+                Space::new().width(Length::Fill),
+                button(text("Select all").size(12))
+                    .on_press(Message::SelectAllConversations)
+                    .padding([2, 8])
+                    .style(move |_theme, _status| button::Style {
+                        background: None,
+                        text_color: pal.accent,
+                        ..Default::default()
+                    }),
+            ]
+            .align_y(iced::Alignment::Center),
+            row![
+                action("Pin", Message::PinConversations(ids.clone(), true), false),
+                action("Archive", Message::ArchiveConversations(ids.clone(), true), false),
+                action("Export", Message::ExportSelectedConversations, false),
+                action("Delete", Message::DeleteConversations(ids), true),
+            ]
+            .spacing(6),
+            row![
+                text_input("Tag", &self.conversation_tag_draft)
+                    .on_input(Message::ConversationTagDraftChanged)
+                    .on_submit(Message::TagSelectedConversations)
+                    .size(12)
+                    .padding([4, 8]),
+                action("Add tag", Message::TagSelectedConversations, false),
+            ]
+            .spacing(6)
+            .align_y(iced::Alignment::Center),
+        ]
+        .spacing(8)
+        .padding([4, 16])
+        .into()
+    }
 
-            popup_content.push(dir_input.into());
-            popup_content.push(Space::new().height(Length::Fixed(8.0)).into());
-            
-            let confirm_btn = button(
-                text("Open")
-                    .size(13)
-                    .style(move |_| iced::widget::text::Style {
-                        color: Some(pal.text)
-                    })
-            )
-            .on_press(Message::ChangeDirectory)
-            .padding([8, 20])
-            .style(move |_theme, status| {
-                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
-                button::Style {
+    /// Confirmation shown before a bulk delete removes `count` conversations
+    fn conversation_delete_confirm(&self, count: usize, pal: PaletteColors) -> Element<'_, Message> {
+        let question = if count == 1 {
+            "Delete 1 conversation? This can't be undone.".to_string()
+        } else {
+            format!("Delete {} conversations? This can't be undone.", count)
+        };
+        let choice = |label: &'static str, message: Message, color: Color| {
+            button(text(label).size(12))
+                .on_press(message)
+                .padding([4, 10])
+                .style(move |_theme, status| button::Style {
                     background: Some(Background::Color(Color {
-                        a: if is_hovered { 0.4 } else { 0.3 },
-                        ..pal.success
+                        a: if matches!(status, button::Status::Hovered) { 0.2 } else { 0.08 },
+                        ..color
                     })),
                     border: Border {
                         radius: 8.0.into(),
@@ -2308,96 +9002,266 @@ impl App {
                     },
                     text_color: pal.text,
                     ..Default::default()
-                }
-            });
+                })
+        };
 
-            popup_content.push(
-                row![
-                    Space::new().width(Length::Fill),
-                    confirm_btn,
-                ]
-                .into()
-            );
+        column![
+            text(question).size(12).style(move |_| iced::widget::text::Style {
+                color: Some(pal.danger),
+            }),
+            row![
+                choice("Delete", Message::ConfirmDeleteConversations, pal.danger),
+                choice("Cancel", Message::CancelDeleteConversations, pal.accent),
+            ]
+            .spacing(6),
+        ]
+        .spacing(8)
+        .padding([4, 16])
+        .into()
+    }
+
+    /// Card for a saved conversation in the sidebar, with pin and archive actions
+    fn conversation_card<'a>(
+        &'a self,
+        index: usize,
+        conversation: &'a ConversationMetadata,
+        pal: PaletteColors,
+    ) -> Element<'a, Message> {
+        let t = self.conversations_sidebar_animation;
+        let conv_id = conversation.id;
+        let selected = self.selected_conversations.contains(&conv_id);
+
+        // Truncate title safely
+        let title = if conversation.title.chars().count() > 30 {
+            let truncate_at = conversation.title
+                .char_indices()
+                .take(27)
+                .last()
+                .map(|(i, c)| i + c.len_utf8())
+                .unwrap_or(0);
+            format!("{}...", conversation.title[..truncate_at].trim())
         } else {
-            // Toggle to show manual input
-            let manual_button = button(
-                row![
-                    bootstrap::keyboard()
-                        .size(12)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.muted)
-                        }),
-                    Space::new().width(Length::Fixed(6.0)),
-                    text("Type path manually")
-                        .size(11)
-                        .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.muted)
-                        }),
-                ]
-                .align_y(iced::Alignment::Center),
-            )
-            .on_press(Message::ShowDirectoryCustomInput)
-            .padding([6, 10])
-            .style(move |_theme, status| {
-                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
-                button::Style {
+            conversation.title.clone()
+        };
+
+        // Staggered Cascade: Each item fades in with increasing delay
+        // Cap the stagger so entries from later pages still appear
+        let item_delay = 0.1 + (index.min(12) as f32 * 0.06); // Base delay + stagger
+        let item_progress = ((t - item_delay) / (1.0 - item_delay)).clamp(0.0, 1.0);
+        let item_opacity = 1.0 - (1.0 - item_progress).powi(2); // Ease out
+
+        let card = button(
+            row![
+                // Icon
+                container(
+                    if conversation.pinned {
+                        bootstrap::pin_fill()
+                    } else {
+                        bootstrap::chat()
+                    }
+                    .size(16)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.accent)
+                    })
+                )
+                .width(Length::Fixed(32.0))
+                .height(Length::Fixed(32.0))
+                .align_x(Horizontal::Center)
+                .align_y(Vertical::Center)
+                .style(move |_| container::Style {
                     background: Some(Background::Color(Color {
-                        a: if is_hovered { 0.1 } else { 0.0 },
-                        ..pal.surface_raised
+                        a: 0.15,
+                        ..pal.accent
                     })),
                     border: Border {
-                        radius: 6.0.into(),
+                        radius: 8.0.into(),
                         ..Default::default()
                     },
-                    text_color: pal.muted,
                     ..Default::default()
-                }
-            });
-
-            popup_content.push(manual_button.into());
-        }
+                }),
 
-        // ─────────────────────────────────────────────────────────────────
-        // POPUP CONTAINER
-        // ─────────────────────────────────────────────────────────────────
+                Space::new().width(Length::Fixed(12.0)),
 
-        let popup = container(
-            column(popup_content)
-                .spacing(2)
-                .padding(16)
+                // Content
+                column![
+                    text(title)
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.text)
+                        }),
+                    row![
+                        text(format!("{} msgs", conversation.message_count))
+                            .size(11)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(Color { a: 0.6, ..pal.muted })
+                            }),
+                        Space::new().width(Length::Fixed(8.0)),
+                        text("•")
+                            .size(11)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(Color { a: 0.4, ..pal.muted })
+                            }),
+                        Space::new().width(Length::Fixed(8.0)),
+                        text(conversation.relative_time())
+                            .size(11)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(Color { a: 0.6, ..pal.muted })
+                            }),
+                        Space::new().width(Length::Fixed(8.0)),
+                        text(
+                            conversation
+                                .tags
+                                .iter()
+                                .map(|tag| format!("#{}", tag))
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        )
+                        .size(11)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(Color { a: 0.8, ..pal.accent })
+                        }),
+                    ]
+                    .align_y(iced::Alignment::Center),
+                ]
+                .spacing(4),
+            ]
+            .align_y(iced::Alignment::Center)
         )
-        .width(Length::Fixed(340.0))
-        .style(move |_| container::Style {
-            background: Some(Background::Color(Color {
-                a: 0.95,
-                ..pal.background
-            })),
-            border: Border {
-                radius: 16.0.into(),
-                width: 1.0,
-                color: Color { a: 0.4, ..pal.border },
-            },
-            ..Default::default()
+        // In multi-select a click checks the conversation instead of opening it
+        .on_press(if self.selecting_conversations {
+            Message::ConversationSelected(conv_id, !selected)
+        } else {
+            Message::LoadConversation(conv_id)
+        })
+        .padding([12, 14])
+        .width(Length::Fill)
+        .style(move |_theme, status| {
+            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+            button::Style {
+                background: Some(Background::Color(Color {
+                    a: if is_hovered { 0.12 } else { 0.05 },
+                    ..pal.surface_raised
+                })),
+                border: Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: Color {
+                        a: if is_hovered { 0.25 } else { 0.1 },
+                        ..pal.border
+                    },
+                },
+                text_color: pal.text,
+                ..Default::default()
+            }
+        });
+        let action = |icon: iced::widget::Text<'a>, label: &'static str, message: Message| {
+            tooltip(
+                button(icon.size(13).style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted),
+                }))
+                .on_press(message)
+                .padding(6)
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.15 } else { 0.0 },
+                            ..pal.accent
+                        })),
+                        border: Border {
+                            radius: 8.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                }),
+                text(label).size(11),
+                tooltip::Position::Top,
+            )
+        };
+        let pin = if conversation.pinned {
+            action(bootstrap::pin_fill(), "Unpin", Message::PinConversations(vec![conv_id], false))
+        } else {
+            action(bootstrap::pin(), "Pin", Message::PinConversations(vec![conv_id], true))
+        };
+        let archive = if conversation.archived {
+            action(
+                bootstrap::box_arrow_up(),
+                "Restore",
+                Message::ArchiveConversations(vec![conv_id], false),
+            )
+        } else {
+            action(
+                bootstrap::archive(),
+                "Archive",
+                Message::ArchiveConversations(vec![conv_id], true),
+            )
+        };
+
+        let select = self.selecting_conversations.then(|| {
+            checkbox(selected)
+                .on_toggle(move |on| Message::ConversationSelected(conv_id, on))
+                .size(14)
         });
 
-        // Position the popup below the top bar
+        // Wrap card with staggered opacity
         container(
-            column![
-                Space::new().height(Length::Fixed(70.0)), // Below top bar
-                row![
-                    Space::new().width(Length::Fixed(100.0)),
-                    popup,
-                ],
-            ]
+            row![]
+                .push(select)
+                .push(card)
+                .push(pin)
+                .push(archive)
+                .spacing(2)
+                .align_y(iced::Alignment::Center),
         )
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .into()
+            .width(Length::Fill)
+            .style(move |_| container::Style {
+                text_color: Some(Color {
+                    a: item_opacity,
+                    ..pal.text
+                }),
+                ..Default::default()
+            })
+            .into()
+    }
+
+    /// Heading of a sidebar section, with actions for all of its conversations
+    fn conversation_section_header<'a>(
+        &'a self,
+        icon: iced::widget::Text<'a>,
+        title: String,
+        actions: Vec<(&'static str, Message)>,
+        pal: PaletteColors,
+    ) -> Element<'a, Message> {
+        let mut header = row![
+            icon.size(11).style(move |_| iced::widget::text::Style {
+                color: Some(pal.muted)
+            }),
+            text(title).size(11).style(move |_| iced::widget::text::Style {
+                color: Some(pal.muted)
+            }),
+            Space::new().width(Length::Fill),
+        ]
+        .spacing(6)
+        .align_y(iced::Alignment::Center);
+        for (label, message) in actions {
+            header = header.push(
+                button(text(label).size(11))
+                    .on_press(message)
+                    .padding([2, 8])
+                    .style(move |_theme, status| {
+                        let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                        button::Style {
+                            background: None,
+                            text_color: if is_hovered { pal.accent } else { pal.muted },
+                            ..Default::default()
+                        }
+                    }),
+            );
+        }
+        container(header).padding([6, 4]).into()
     }
 
-    /// Creates the conversations sidebar - modern relaxing design
-    /// Animations: Staggered Cascade (opacity), Content Parallax (timing), Glow Reveal
-    /// Uses SLIDE ANIMATION - sidebar stays full width, slides from off-screen (no squishing!)
     fn conversations_sidebar(&self, pal: PaletteColors) -> Element<'_, Message> {
         // ─────────────────────────────────────────────────────────────────
         // ANIMATION CALCULATIONS
@@ -2421,6 +9285,7 @@ impl App {
         let header_opacity = 1.0 - (1.0 - header_progress).powi(2); // Faster fade-in
 
         let mut sidebar_content: Vec<Element<'_, Message>> = Vec::new();
+        let selecting = self.selecting_conversations;
 
         // ─────────────────────────────────────────────────────────────────
         // HEADER: Parallax effect (slides in faster)
@@ -2446,6 +9311,37 @@ impl App {
                 
                 Space::new().width(Length::Fill),
                 
+                // Multi-select toggle (icon only)
+                tooltip(
+                    button(
+                        bootstrap::check_lg()
+                            .size(16)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(if selecting { pal.accent } else { pal.muted })
+                            })
+                    )
+                    .on_press(Message::ToggleConversationSelection)
+                    .padding(8)
+                    .style(move |_theme, status| {
+                        let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                        button::Style {
+                            background: Some(Background::Color(Color {
+                                a: if is_hovered { 0.15 } else { 0.0 },
+                                ..pal.accent
+                            })),
+                            border: Border {
+                                radius: 8.0.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        }
+                    }),
+                    text(if selecting { "Done selecting" } else { "Select" }).size(11),
+                    tooltip::Position::Bottom,
+                ),
+
+                Space::new().width(Length::Fixed(4.0)),
+
                 // Refresh button (icon only)
                 button(
                     bootstrap::arrow_clockwise()
@@ -2515,13 +9411,68 @@ impl App {
             });
 
         sidebar_content.push(header_with_parallax.into());
+
+        // Workspace switcher: each workspace has its own conversation list
+        let workspace_row = row![
+            pick_list(
+                self.workspaces.as_slice(),
+                self.workspaces.get(self.active_workspace),
+                Message::SelectWorkspace,
+            )
+            .text_size(13)
+            .padding([6, 10])
+            .width(Length::Fill),
+            Space::new().width(Length::Fixed(6.0)),
+            tooltip(
+                button(
+                    bootstrap::folder_plus()
+                        .size(14)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.muted)
+                        })
+                )
+                .on_press(Message::AddWorkspace)
+                .padding(8)
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.15 } else { 0.0 },
+                            ..pal.accent
+                        })),
+                        border: Border {
+                            radius: 8.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                }),
+                text("Add workspace").size(11),
+                tooltip::Position::Bottom,
+            ),
+        ]
+        .align_y(iced::Alignment::Center)
+        .padding([0, 16]);
+
+        sidebar_content.push(workspace_row.into());
         sidebar_content.push(Space::new().height(Length::Fixed(8.0)).into());
+        if let Some(ids) = &self.delete_conversations_pending {
+            sidebar_content.push(self.conversation_delete_confirm(ids.len(), pal));
+            sidebar_content.push(Space::new().height(Length::Fixed(8.0)).into());
+        }
+        if self.selecting_conversations {
+            sidebar_content.push(self.conversation_bulk_bar(pal));
+            sidebar_content.push(Space::new().height(Length::Fixed(8.0)).into());
+        }
 
         // ─────────────────────────────────────────────────────────────────
         // CONVERSATION LIST
         // ─────────────────────────────────────────────────────────────────
 
-        if self.saved_conversations.is_empty() {
+        if self.saved_conversations.is_empty()
+            && self.pinned_conversations.is_empty()
+            && self.archived_total == 0
+        {
             // Empty state - calm and inviting
             let empty_state = container(
                 column![
@@ -2554,131 +9505,64 @@ impl App {
             sidebar_content.push(empty_state.into());
         } else {
             // Conversation cards with Staggered Cascade animation
-            let cards = column(
-                    self.saved_conversations
-                        .iter()
-                        .enumerate()
-                        .map(|(index, conversation)| {
-                            let conv_id = conversation.id;
-                            
-                            // Truncate title safely
-                            let title = if conversation.title.chars().count() > 30 {
-                                let truncate_at = conversation.title
-                                    .char_indices()
-                                    .take(27)
-                                    .last()
-                                    .map(|(i, c)| i + c.len_utf8())
-                                    .unwrap_or(0);
-                                format!("{}...", conversation.title[..truncate_at].trim())
-                            } else {
-                                conversation.title.clone()
-                            };
-
-                            // Staggered Cascade: Each item fades in with increasing delay
-                            let item_delay = 0.1 + (index as f32 * 0.06); // Base delay + stagger
-                            let item_progress = ((t - item_delay) / (1.0 - item_delay)).clamp(0.0, 1.0);
-                            let item_opacity = 1.0 - (1.0 - item_progress).powi(2); // Ease out
-
-                            let card = button(
-                                row![
-                                    // Icon
-                                    container(
-                                        bootstrap::chat()
-                                            .size(16)
-                                            .style(move |_| iced::widget::text::Style {
-                                                color: Some(pal.accent)
-                                            })
-                                    )
-                                    .width(Length::Fixed(32.0))
-                                    .height(Length::Fixed(32.0))
-                                    .align_x(Horizontal::Center)
-                                    .align_y(Vertical::Center)
-                                    .style(move |_| container::Style {
-                                        background: Some(Background::Color(Color {
-                                            a: 0.15,
-                                            ..pal.accent
-                                        })),
-                                        border: Border {
-                                            radius: 8.0.into(),
-                                            ..Default::default()
-                                        },
-                                        ..Default::default()
-                                    }),
-                                    
-                                    Space::new().width(Length::Fixed(12.0)),
-                                    
-                                    // Content
-                                    column![
-                                        text(title)
-                                            .size(13)
-                                            .style(move |_| iced::widget::text::Style {
-                                                color: Some(pal.text)
-                                            }),
-                                        row![
-                                            text(format!("{} msgs", conversation.message_count))
-                                                .size(11)
-                                                .style(move |_| iced::widget::text::Style {
-                                                    color: Some(Color { a: 0.6, ..pal.muted })
-                                                }),
-                                            Space::new().width(Length::Fixed(8.0)),
-                                            text("•")
-                                                .size(11)
-                                                .style(move |_| iced::widget::text::Style {
-                                                    color: Some(Color { a: 0.4, ..pal.muted })
-                                                }),
-                                            Space::new().width(Length::Fixed(8.0)),
-                                            text(conversation.relative_time())
-                                                .size(11)
-                                                .style(move |_| iced::widget::text::Style {
-                                                    color: Some(Color { a: 0.6, ..pal.muted })
-                                                }),
-                                        ]
-                                        .align_y(iced::Alignment::Center),
-                                    ]
-                                    .spacing(4),
-                                ]
-                                .align_y(iced::Alignment::Center)
-                            )
-                            .on_press(Message::LoadConversation(conv_id))
-                            .padding([12, 14])
-                            .width(Length::Fill)
-                            .style(move |_theme, status| {
-                                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
-                                button::Style {
-                                    background: Some(Background::Color(Color {
-                                        a: if is_hovered { 0.12 } else { 0.05 },
-                                        ..pal.surface_raised
-                                    })),
-                                    border: Border {
-                                        radius: 12.0.into(),
-                                        width: 1.0,
-                                        color: Color {
-                                            a: if is_hovered { 0.25 } else { 0.1 },
-                                            ..pal.border
-                                        },
-                                    },
-                                    text_color: pal.text,
-                                    ..Default::default()
-                                }
-                            });
-                            // Wrap card with staggered opacity
-                            container(card)
-                                .width(Length::Fill)
-                                .style(move |_| container::Style {
-                                    text_color: Some(Color {
-                                        a: item_opacity,
-                                        ..pal.text
-                                    }),
-                                    ..Default::default()
-                                })
-                                .into()
-                        })
-                        .collect::<Vec<Element<'_, Message>>>()
-                )
+            // Pinned first, then recent, then the archive when it is opened
+            let mut sections = column![]
                 .spacing(6)
-                .width(Length::Fixed(sidebar_width - 24.0));  // Account for padding (12*2)
-            
-            let conversations_container = container(cards)
+                .width(Length::Fixed(sidebar_width - 24.0)); // Account for padding (12*2)
+            let mut index = 0;
+            if !self.pinned_conversations.is_empty() {
+                let ids: Vec<uuid::Uuid> = self.pinned_conversations.iter().map(|c| c.id).collect();
+                sections = sections.push(self.conversation_section_header(
+                    bootstrap::pin_fill(),
+                    "Pinned".to_string(),
+                    vec![("Unpin all", Message::PinConversations(ids, false))],
+                    pal,
+                ));
+                for conversation in &self.pinned_conversations {
+                    sections = sections.push(self.conversation_card(index, conversation, pal));
+                    index += 1;
+                }
+            }
+            if !self.saved_conversations.is_empty() {
+                if !self.pinned_conversations.is_empty() {
+                    sections = sections.push(self.conversation_section_header(
+                        bootstrap::clock_history(),
+                        "Recent".to_string(),
+                        Vec::new(),
+                        pal,
+                    ));
+                }
+                for conversation in &self.saved_conversations {
+                    sections = sections.push(self.conversation_card(index, conversation, pal));
+                    index += 1;
+                }
+            }
+            if self.archived_total > 0 {
+                let mut actions = vec![(
+                    if self.show_archived { "Hide" } else { "Show" },
+                    Message::ToggleArchivedConversations,
+                )];
+                if self.show_archived {
+                    let ids: Vec<uuid::Uuid> =
+                        self.archived_conversations.iter().map(|c| c.id).collect();
+                    actions.push(("Restore all", Message::ArchiveConversations(ids.clone(), false)));
+                    actions.push(("Delete all", Message::DeleteConversations(ids)));
+                }
+                sections = sections.push(self.conversation_section_header(
+                    bootstrap::archive(),
+                    format!("Archived ({})", self.archived_total),
+                    actions,
+                    pal,
+                ));
+                if self.show_archived {
+                    for conversation in &self.archived_conversations {
+                        sections = sections.push(self.conversation_card(index, conversation, pal));
+                        index += 1;
+                    }
+                }
+            }
+
+            let conversations_container = container(sections)
                 .padding([0, 12])
                 .width(Length::Fill);  // This can fill since parent is fixed
 
@@ -2691,6 +9575,7 @@ impl App {
 
         // Scrollable content with FIXED width - never changes size!
         let scroll_content = scrollable(column(sidebar_content))
+            .on_scroll(Message::ConversationsScrolled)
             .width(Length::Fixed(sidebar_width))
             .height(Length::Fill);
 
@@ -2834,24 +9719,265 @@ impl App {
             .into();
         }
 
-        // Build message list
-        let messages: Vec<Element<'_, Message>> = session
-            .messages
-            .iter()
-            .enumerate()
-            .map(|(idx, msg)| self.message_bubble(idx, msg, pal))
-            .collect();
+        if session.show_raw {
+            return self.raw_transcript(pal);
+        }
+
+        // Build message list; the keyboard-focused bubble gets a focus ring
+        let focused = self.focused_message_index();
+        let bubble = |idx: usize| -> Element<'_, Message> {
+            let bubble = self.message_bubble(idx, &session.messages[idx], pal);
+            if focused != Some(idx) {
+                return bubble;
+            }
+            container(bubble)
+                .padding(3)
+                .style(move |_| container::Style {
+                    border: Border {
+                        radius: 18.0.into(),
+                        width: 2.0,
+                        color: pal.accent,
+                    },
+                    ..Default::default()
+                })
+                .into()
+        };
+        // Long runs of tool calls fold into one step that expands on click;
+        // with hidden tool verbosity they aren't shown at all
+        let hide_tools = self.config.get_tool_verbosity() == ToolVerbosity::Hidden;
+        let mut messages: Vec<Element<'_, Message>> = Vec::new();
+        let mut idx = 0;
+        while idx < session.messages.len() {
+            if hide_tools && session.messages[idx].is_tool() {
+                idx += 1;
+                continue;
+            }
+            let run = session.messages[idx..]
+                .iter()
+                .take_while(|m| m.is_tool())
+                .count();
+            if run < TOOL_GROUP_MIN {
+                messages.push(bubble(idx));
+                idx += 1;
+                continue;
+            }
+            let expanded = self.expanded_tool_groups.contains(&(session.id, idx));
+            messages.push(self.tool_group_header(
+                idx,
+                &session.messages[idx..idx + run],
+                expanded,
+                pal,
+            ));
+            if expanded {
+                messages.extend((idx..idx + run).map(&bubble));
+            }
+            idx += run;
+        }
+
+        // Only follow new content while the user is at the bottom (see
+        // `jump_to_latest`), so reading earlier messages isn't interrupted
+        let list = scrollable(
+            column(messages)
+                .spacing(16) // Tighter spacing between messages
+                .padding(24),
+        )
+        .id(chat_scroll_id())
+        .on_scroll(Message::ChatScrolled)
+        .height(Length::Fill)
+        .width(Length::Fill);
+
+        // Always a stack, so showing the pill doesn't rebuild the list and
+        // lose its scroll position
+        if self.chat_at_bottom {
+            return stack(vec![list.into(), Space::new().into()]).into();
+        }
+
+        let unseen = session.messages.len().saturating_sub(self.chat_seen_messages);
+        let label = if unseen > 0 {
+            format!("Jump to latest ↓ ({} new)", unseen)
+        } else {
+            "Jump to latest ↓".to_string()
+        };
+        let pill = button(text(label).size(12))
+            .on_press(Message::JumpToLatest)
+            .padding([6, 14])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                iced::widget::button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 1.0 } else { 0.9 },
+                        ..pal.accent
+                    })),
+                    border: Border {
+                        radius: 16.0.into(),
+                        ..Default::default()
+                    },
+                    shadow: iced::Shadow {
+                        color: Color::BLACK,
+                        offset: iced::Vector::new(0.0, 2.0),
+                        blur_radius: 6.0,
+                    },
+                    text_color: pal.background,
+                    ..Default::default()
+                }
+            });
+
+        stack(vec![
+            list.into(),
+            container(pill)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(Horizontal::Center)
+                .align_y(Vertical::Bottom)
+                .padding(16)
+                .into(),
+        ])
+        .into()
+    }
+
+    /// The system prompt, then every history message as the JSON sent to
+    /// the model, compaction and interruption notes included. Tool schemas
+    /// and per-request context are added when the request is built and are
+    /// not shown here.
+    fn raw_transcript(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let mut sections = vec![("system prompt".to_string(), self.dispatcher.system_prompt())];
+        sections.extend(
+            self.raw_transcript_cache
+                .iter()
+                .enumerate()
+                .map(|(i, (message, body))| (format!("#{} {}", i + 1, message.role), body.as_str())),
+        );
+        let plain = sections
+            .iter()
+            .map(|(label, body)| format!("── {} ──\n{}", label, body))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let header = row![
+            text("Raw transcript: the system prompt and history sent to the model")
+                .size(12)
+                .width(Length::Fill)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                }),
+            button(text("Copy").size(12))
+                .on_press(Message::CopyToClipboard(plain))
+                .padding([4, 10])
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    iced::widget::button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.2 } else { 0.1 },
+                            ..pal.accent
+                        })),
+                        border: Border {
+                            radius: 8.0.into(),
+                            ..Default::default()
+                        },
+                        text_color: pal.text,
+                        ..Default::default()
+                    }
+                }),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let mut blocks: Vec<Element<'_, Message>> = vec![header.into()];
+        blocks.extend(sections.into_iter().map(|(label, body)| {
+            column![
+                text(label)
+                    .size(11)
+                    .font(Font::MONOSPACE)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.accent)
+                    }),
+                container(text(body).size(12).font(Font::MONOSPACE))
+                    .padding(10)
+                    .width(Length::Fill)
+                    .style(move |_| container::Style {
+                        background: Some(Background::Color(Color {
+                            a: 0.5,
+                            ..pal.surface
+                        })),
+                        border: Border {
+                            radius: 8.0.into(),
+                            width: 1.0,
+                            color: pal.border,
+                        },
+                        text_color: Some(pal.text),
+                        ..Default::default()
+                    }),
+            ]
+            .spacing(4)
+            .into()
+        }));
+
+        scrollable(column(blocks).spacing(12).padding(24))
+        .height(Length::Fill)
+        .width(Length::Fill)
+        .into()
+    }
+
+    /// Whether a tool bubble shows its output before the user toggles it:
+    /// always with full verbosity, otherwise only while the call runs
+    fn tool_expanded_by_default(&self, message: &MessageEntry) -> bool {
+        match self.config.get_tool_verbosity() {
+            ToolVerbosity::Full => true,
+            ToolVerbosity::Summary | ToolVerbosity::Hidden => {
+                !(message.content.contains('✓') || message.content.contains('✗'))
+            }
+        }
+    }
 
-        // Create scrollable - always anchor to bottom to prevent scroll jumping
-        // when markdown rerenders or streaming ends
-        scrollable(
-            column(messages)
-                .spacing(16) // Tighter spacing between messages
-                .padding(24),
+    /// Clickable summary standing in for a folded run of tool calls
+    fn tool_group_header(
+        &self,
+        start: usize,
+        run: &[MessageEntry],
+        expanded: bool,
+        pal: PaletteColors,
+    ) -> Element<'_, Message> {
+        let chevron = if expanded {
+            bootstrap::chevron_down()
+        } else {
+            bootstrap::chevron_right()
+        };
+        button(
+            row![
+                chevron.size(11).style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                }),
+                bootstrap::layers()
+                    .size(13)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.accent)
+                    }),
+                text(tool_run_label(run))
+                    .size(13)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    }),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
         )
-        .height(Length::Fill)
-        .width(Length::Fill)
-        .anchor_bottom() // Always anchor to bottom like a chat app
+        .on_press(Message::ToggleToolGroup(start))
+        .padding([8, 14])
+        .style(move |_theme, status| {
+            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+            button::Style {
+                background: Some(Background::Color(Color {
+                    a: if is_hovered { 0.12 } else { 0.06 },
+                    ..pal.surface_raised
+                })),
+                border: Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: Color { a: 0.2, ..pal.border },
+                },
+                text_color: pal.text,
+                ..Default::default()
+            }
+        })
         .into()
     }
 
@@ -2873,6 +9999,16 @@ impl App {
         .width(Length::Fixed(24.0))
         .height(Length::Fixed(24.0));
 
+        // Elapsed time and speed of the response so far
+        let stats = self.sessions[self.current]
+            .stream_stats
+            .as_ref()
+            .map(|stats| match stats.tokens_per_sec() {
+                Some(speed) => format!("{:.1}s · {:.0} tok/s", stats.elapsed_secs(), speed),
+                None => format!("{:.1}s", stats.elapsed_secs()),
+            })
+            .unwrap_or_default();
+
         // Wrap in a bubble-like container
         let indicator_content = container(
             row![
@@ -2883,6 +10019,13 @@ impl App {
                     .style(move |_| iced::widget::text::Style {
                         color: Some(pal.muted)
                     }),
+                Space::new().width(Length::Fixed(10.0)),
+                text(stats)
+                    .size(11)
+                    .font(Font::MONOSPACE)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(Color { a: 0.7, ..pal.muted })
+                    }),
             ]
             .align_y(iced::Alignment::Center),
         )
@@ -2951,10 +10094,14 @@ impl App {
         let content_widget: Element<'_, Message> = if is_ai_message {
             // Use markdown rendering for AI messages
             // Get cached markdown items or parse fresh
-            let md_items = self.markdown_cache.get(&key);
+            let md_items = self
+                .markdown_cache
+                .get(&key)
+                .map(|items| items.as_slice())
+                .or_else(|| self.markdown_streams.get(&key).map(|(content, _)| content.items()));
 
             if let Some(items) = md_items {
-                // Render cached markdown
+                // Render cached (or incrementally parsed) markdown
                 markdown::view(
                     items,
                     markdown::Settings::with_style(markdown::Style::from_palette(iced::Theme::TokyoNightStorm.palette())),
@@ -3087,11 +10234,222 @@ impl App {
             }
         });
 
-        // Bottom row with timestamp and copy button
-        let bottom_row = row![timestamp, Space::new().width(Length::Fill), copy_button]
-            .align_y(iced::Alignment::Center);
+        // Tokens, duration and model of a finished response
+        let stats_footer: Element<'_, Message> = match &message.response_stats {
+            _ if message.interrupted => text(" · interrupted")
+                .size(10)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(Color {
+                        a: fade_opacity * 0.6,
+                        ..pal.muted
+                    }),
+                })
+                .into(),
+            Some(stats) => text(format!(" · {}", stats.footer()))
+                .size(10)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(Color {
+                        a: fade_opacity * 0.6,
+                        ..pal.muted
+                    }),
+                })
+                .into(),
+            None => Space::new().into(),
+        };
+
+        // Bottom row with timestamp, stats, copy and delete buttons
+        let mut bottom_row = row![
+            timestamp,
+            stats_footer,
+            Space::new().width(Length::Fill),
+            copy_button
+        ]
+        .spacing(2)
+        .align_y(iced::Alignment::Center);
+
+        // Prune the thread from this message on (not while it's still streaming)
+        if !session.is_streaming {
+            let delete_button = button(
+                bootstrap::trash()
+                    .size(12)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(Color {
+                            a: fade_opacity * 0.6,
+                            ..pal.muted
+                        }),
+                    }),
+            )
+            .on_press(Message::DeleteFromHere(msg_idx))
+            .padding([2, 4])
+            .style(move |_theme, status| {
+                let hovered = matches!(status, button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color::TRANSPARENT)),
+                    border: Border::default(),
+                    text_color: Color {
+                        a: fade_opacity * if hovered { 1.0 } else { 0.6 },
+                        ..if hovered { pal.danger } else { pal.muted }
+                    },
+                    ..Default::default()
+                }
+            });
+            bottom_row = bottom_row.push(tooltip(
+                delete_button,
+                text("Delete from here").size(11),
+                tooltip::Position::Top,
+            ));
+
+            if self.delete_from_pending == Some((session.id, msg_idx)) {
+                let count = session.messages.len() - msg_idx;
+                let question = if msg_idx == 0 {
+                    "Delete the whole conversation?".to_string()
+                } else if count == 1 {
+                    "Delete this message?".to_string()
+                } else {
+                    format!("Delete this and the {} messages after it?", count - 1)
+                };
+                let choice = |label: &'static str, message: Message, color: Color| {
+                    button(text(label).size(11))
+                        .on_press(message)
+                        .padding([2, 8])
+                        .style(move |_theme, status| button::Style {
+                            background: Some(Background::Color(Color {
+                                a: if matches!(status, button::Status::Hovered) { 0.25 } else { 0.12 },
+                                ..color
+                            })),
+                            border: Border {
+                                radius: 6.0.into(),
+                                ..Default::default()
+                            },
+                            text_color: pal.text,
+                            ..Default::default()
+                        })
+                };
+                bottom_row = bottom_row
+                    .push(text(question).size(11).style(move |_| iced::widget::text::Style {
+                        color: Some(pal.danger),
+                    }))
+                    .push(choice("Delete", Message::ConfirmDeleteFromHere, pal.danger))
+                    .push(choice("Cancel", Message::CancelDeleteFromHere, pal.muted));
+            }
+        }
+
+        // "Apply to file" for code blocks whose fence names a file
+        let mut apply_row = row![].spacing(6);
+        if is_ai_message && !is_streaming {
+            for (block_idx, block) in code_blocks::file_blocks(&message.content).iter().enumerate() {
+                apply_row = apply_row.push(
+                    button(
+                        row![
+                            bootstrap::file_earmark_diff()
+                                .size(11)
+                                .style(move |_| iced::widget::text::Style {
+                                    color: Some(pal.accent)
+                                }),
+                            text(format!("Apply to {}", block.path)).size(11),
+                        ]
+                        .spacing(6)
+                        .align_y(iced::Alignment::Center),
+                    )
+                    .on_press(Message::PreviewApply(msg_idx, block_idx))
+                    .padding([4, 10])
+                    .style(move |_theme, status| {
+                        let is_hovered = matches!(status, button::Status::Hovered);
+                        button::Style {
+                            background: Some(Background::Color(Color {
+                                a: if is_hovered { 0.2 } else { 0.1 },
+                                ..pal.accent
+                            })),
+                            border: Border {
+                                radius: 8.0.into(),
+                                ..Default::default()
+                            },
+                            text_color: pal.text,
+                            ..Default::default()
+                        }
+                    }),
+                );
+            }
+        }
+
+        // Screenshots sent with a user message
+        let mut image_row = row![].spacing(6);
+        for path in &message.images {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            image_row = image_row.push(
+                row![
+                    bootstrap::image()
+                        .size(11)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.accent)
+                        }),
+                    text(name).size(11).style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    }),
+                ]
+                .spacing(4)
+                .align_y(iced::Alignment::Center),
+            );
+        }
+
+        // Numbered citations for the pages web_search and fetch_url returned
+        let mut sources_row = row![].spacing(6);
+        for (n, source) in message.sources.iter().enumerate() {
+            let host = source
+                .url
+                .split("://")
+                .nth(1)
+                .and_then(|rest| rest.split('/').next())
+                .unwrap_or(&source.url)
+                .trim_start_matches("www.")
+                .to_string();
+            let chip = button(
+                row![
+                    text(format!("{}", n + 1)).size(10).style(move |_| iced::widget::text::Style {
+                        color: Some(pal.accent)
+                    }),
+                    text(host).size(11),
+                ]
+                .spacing(6)
+                .align_y(iced::Alignment::Center),
+            )
+            .on_press(Message::LinkClicked(source.url.clone()))
+            .padding([3, 10])
+            .style(move |_theme, status| {
+                let is_hovered = matches!(status, button::Status::Hovered);
+                button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if is_hovered { 0.2 } else { 0.08 },
+                        ..pal.accent
+                    })),
+                    border: Border {
+                        radius: 10.0.into(),
+                        ..Default::default()
+                    },
+                    text_color: pal.muted,
+                    ..Default::default()
+                }
+            });
+            sources_row = sources_row.push(tooltip(
+                chip,
+                text(source.title.clone()).size(11),
+                tooltip::Position::Top,
+            ));
+        }
 
-        let bubble = container(column![content_widget, bottom_row].spacing(6))
+        let bubble = container(
+            column![
+                content_widget,
+                image_row.wrap(),
+                sources_row.wrap(),
+                apply_row.wrap(),
+                bottom_row
+            ]
+            .spacing(6),
+        )
             .padding(16)
             .max_width(MESSAGE_MAX_WIDTH);
 
@@ -3145,10 +10503,13 @@ impl App {
         pal: PaletteColors,
         fade_opacity: f32,
     ) -> Element<'a, Message> {
-        // Get animation state: default to expanded (position=1.0)
+        // Get animation state; without one the tool verbosity decides
         let spring = self.tool_animations.get(key);
-        let expand_progress = spring.map(|s| s.position).unwrap_or(1.0);
-        let is_collapsed = spring.map(|s| s.target < 0.5).unwrap_or(false);
+        let expanded_by_default = self.tool_expanded_by_default(message);
+        let expand_progress = spring
+            .map(|s| s.position)
+            .unwrap_or(if expanded_by_default { 1.0 } else { 0.0 });
+        let is_collapsed = spring.map_or(!expanded_by_default, |s| s.target < 0.5);
         let key_owned = key.to_string();
 
         // Parse tool content - format varies:
@@ -3454,6 +10815,7 @@ impl App {
         let command_display = {
             let tool_names = [
                 "Shell", "Read", "Write", "Edit", "List", "Search", "Web", "MCP", "Vision",
+                "Outline",
             ];
             let mut after_tool = "";
 
@@ -3867,6 +11229,41 @@ impl App {
             header_row = header_row.push(Space::new().width(Length::Fixed(6.0)));
         }
         
+        // Undo button for writes/edits that were snapshotted
+        if let Some(snapshot_id) = message.snapshot_id.clone().filter(|_| has_checkmark) {
+            header_row = header_row.push(
+                button(
+                    row![
+                        bootstrap::arrow_counterclockwise().size(11),
+                        text("Undo edit").size(11),
+                    ]
+                    .spacing(4)
+                    .align_y(iced::Alignment::Center),
+                )
+                .padding([2, 8])
+                .on_press(Message::UndoEdit(snapshot_id))
+                .style(move |_theme, status| {
+                    let hovered = matches!(status, button::Status::Hovered);
+                    button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if hovered { 0.25 } else { 0.12 } * fade_opacity,
+                            ..pal.accent
+                        })),
+                        text_color: Color {
+                            a: fade_opacity * 0.85,
+                            ..pal.text
+                        },
+                        border: Border {
+                            radius: 6.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                }),
+            );
+            header_row = header_row.push(Space::new().width(Length::Fixed(6.0)));
+        }
+
         header_row = header_row.push(
             status_icon
                 .size(14)
@@ -3893,8 +11290,9 @@ impl App {
 
         // Header button - only clickable for non-read tools
         let header = if is_read {
-            // Read file: non-expandable, just a container with full rounded corners
+            // Read file: non-expandable; opens the file in the preview pane
             button(header_row)
+                .on_press_maybe(message.preview_path.clone().map(Message::OpenFilePreview))
                 .padding([8, 12])
                 .style(move |_theme, _status| button::Style {
                     background: Some(Background::Color(header_bg_color)),
@@ -4647,10 +12045,88 @@ impl App {
         });
         // .on_press(Message::ToggleMicrophone)  // TODO: Implement later
 
+        // Screenshot button: select a region and attach it to the next prompt
+        let capturing = self.capturing_screen;
+        let camera_button = button(
+            container(
+                bootstrap::camera()
+                    .size(16)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(if capturing { pal.accent } else { pal.muted })
+                    })
+            )
+            .width(Length::Fixed(36.0))
+            .height(Length::Fixed(36.0))
+            .align_x(Horizontal::Center)
+            .align_y(Vertical::Center)
+        )
+        .on_press_maybe((!capturing).then_some(Message::CaptureScreen))
+        .padding(0)
+        .style(move |_theme, status| {
+            let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+            iced::widget::button::Style {
+                background: Some(Background::Color(Color {
+                    a: if is_hovered { 0.2 } else { 0.0 },
+                    ..pal.accent
+                })),
+                border: Border {
+                    radius: 8.0.into(),
+                    ..Default::default()
+                },
+                text_color: pal.muted,
+                ..Default::default()
+            }
+        });
+        let camera_button = tooltip(
+            camera_button,
+            text("Attach a screenshot").size(11),
+            tooltip::Position::Top,
+        );
+
+        // Persona picker: system prompt, temperature and model presets
+        let mut persona_names = vec![NO_PERSONA.to_string()];
+        persona_names.extend(personas::all(&self.config).into_iter().map(|p| p.name));
+        let active_persona = self.config.get_active_persona();
+        let has_persona = active_persona.is_some();
+        let persona_picker = tooltip(
+            pick_list(
+                persona_names,
+                Some(
+                    active_persona
+                        .map(|p| p.name)
+                        .unwrap_or_else(|| NO_PERSONA.to_string()),
+                ),
+                Message::PersonaSelected,
+            )
+            .text_size(12)
+            .padding([6, 8])
+            .style(move |_theme, status| iced::widget::pick_list::Style {
+                background: Background::Color(Color {
+                    a: if matches!(status, iced::widget::pick_list::Status::Hovered) {
+                        0.2
+                    } else {
+                        0.0
+                    },
+                    ..pal.accent
+                }),
+                text_color: if has_persona { pal.accent } else { pal.muted },
+                placeholder_color: pal.muted,
+                border: Border {
+                    radius: 8.0.into(),
+                    ..Default::default()
+                },
+                handle_color: pal.muted,
+            }),
+            text("Persona").size(11),
+            tooltip::Position::Top,
+        );
+
         let left_buttons = row![
             attach_button,
             image_button,
+            camera_button,
             mic_button,
+            persona_picker,
         ]
         .spacing(2)
         .align_y(iced::Alignment::Center);
@@ -4659,11 +12135,70 @@ impl App {
         // CENTER: Text input field
         // ─────────────────────────────────────────────────────────────────
         
-        let input_field = text_input("Message ARULA...", &self.draft)
+        let navigating_messages = self.focused_message_index().is_some();
+        // Up in an empty input, and Up/Down while it still holds a recalled
+        // prompt, walk the prompt history
+        let draft_text = self.draft.text();
+        let recalling = self.prompt_recall.is_showing(&draft_text);
+        let draft_empty = draft_text.trim().is_empty();
+        let input_field = text_editor(&self.draft)
+            .highlight_with::<SpellHighlighter>(
+                SpellSettings {
+                    dictionary: self.spellcheck.clone(),
+                },
+                spelling::misspelled_format,
+            )
             .id(input_id())
-            .on_input(Message::DraftChanged)
-            .on_submit(Message::SendPrompt)
+            .placeholder("Message ARULA...")
+            .on_action(Message::DraftAction)
+            .key_binding(move |key_press| {
+                use iced::keyboard::{key::Named, Key};
+                // While a message is focused the navigation keys act on it
+                if navigating_messages {
+                    if let Some(message) = message_navigation(&key_press.key, key_press.modifiers) {
+                        return Some(text_editor::Binding::Custom(message));
+                    }
+                }
+                match key_press.key.as_ref() {
+                    // Shift+Enter falls through to the default newline
+                    Key::Named(Named::Enter) if !key_press.modifiers.shift() => {
+                        Some(text_editor::Binding::Custom(Message::SendPrompt))
+                    }
+                    // Tab moves focus on instead of typing a tab character
+                    Key::Named(Named::Tab) => None,
+                    // Escape cuts the running answer off to reply to it
+                    Key::Named(Named::Escape) if is_streaming => {
+                        Some(text_editor::Binding::Custom(Message::InterruptAndReply))
+                    }
+                    // Alt+Up steps from the input into the messages
+                    Key::Named(Named::ArrowUp) if key_press.modifiers.alt() => {
+                        Some(text_editor::Binding::Custom(Message::MoveMessageFocus(-1)))
+                    }
+                    Key::Named(Named::ArrowUp)
+                        if (draft_empty || recalling) && key_press.modifiers.is_empty() =>
+                    {
+                        Some(text_editor::Binding::Custom(Message::RecallPrompt(true)))
+                    }
+                    Key::Named(Named::ArrowDown) if recalling && key_press.modifiers.is_empty() => {
+                        Some(text_editor::Binding::Custom(Message::RecallPrompt(false)))
+                    }
+                    Key::Character(c)
+                        if key_press.modifiers.command()
+                            && !key_press.modifiers.shift()
+                            && c.eq_ignore_ascii_case("r") =>
+                    {
+                        Some(text_editor::Binding::Custom(Message::TogglePromptSearch))
+                    }
+                    // Images on the clipboard become attachments
+                    Key::Character(c) if key_press.modifiers.command() && c.eq_ignore_ascii_case("v") => {
+                        Some(text_editor::Binding::Custom(Message::PasteIntoDraft))
+                    }
+                    _ => text_editor::Binding::from_key_press(key_press),
+                }
+            })
             .padding([12, 8])
+            .height(Length::Shrink)
+            .max_height(CHAT_INPUT_MAX_HEIGHT)
             .style(chat_input_style(pal))
             .width(Length::Fill);
 
@@ -4707,8 +12242,43 @@ impl App {
 
         // Send/Stop button with modern pill shape
         let action_button: Element<'_, Message> = if is_streaming {
+            // Interrupt & reply: stop, keep the partial answer and type a correction
+            let interrupt_button = tooltip(
+                button(
+                    container(
+                        bootstrap::pencil()
+                            .size(16)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.text)
+                            })
+                    )
+                    .width(Length::Fixed(36.0))
+                    .height(Length::Fixed(36.0))
+                    .align_x(Horizontal::Center)
+                    .align_y(Vertical::Center)
+                )
+                .on_press(Message::InterruptAndReply)
+                .padding(0)
+                .style(move |_theme, status| {
+                    let is_hovered = matches!(status, iced::widget::button::Status::Hovered);
+                    iced::widget::button::Style {
+                        background: Some(Background::Color(Color {
+                            a: if is_hovered { 0.25 } else { 0.12 },
+                            ..pal.accent
+                        })),
+                        border: Border {
+                            radius: 10.0.into(),
+                            ..Default::default()
+                        },
+                        text_color: pal.text,
+                        ..Default::default()
+                    }
+                }),
+                text("Interrupt & reply (Esc)").size(11),
+                tooltip::Position::Top,
+            );
             // Stop button - red with square icon
-            button(
+            let stop_button = button(
                 container(
                     bootstrap::stop_fill()
                         .size(18)
@@ -4738,11 +12308,14 @@ impl App {
                     text_color: pal.text,
                     ..Default::default()
                 }
-            })
-            .into()
+            });
+            row![interrupt_button, stop_button]
+                .spacing(6)
+                .align_y(iced::Alignment::Center)
+                .into()
         } else {
             // Send button - accent colored with arrow icon
-            let has_content = !self.draft.trim().is_empty();
+            let has_content = !self.draft.text().trim().is_empty();
             button(
                 container(
                     bootstrap::arrow_up()
@@ -4809,8 +12382,12 @@ impl App {
         let _normal_opacity = 1.0 - question_progress;
         let question_opacity = question_progress;
         
+        // Grow with the draft's lines until the editor reaches its max height and scrolls
+        let draft_lines = self.draft.line_count().max(1) as f32;
+        let base_height =
+            (60.0 + (draft_lines - 1.0) * CHAT_INPUT_LINE_HEIGHT).min(CHAT_INPUT_MAX_HEIGHT + 24.0);
+
         // Calculate dynamic height for question mode - reasonable expansion
-        let base_height = 60.0;
         let question_height = if self.pending_question_batches.is_empty() {
             base_height
         } else {
@@ -5094,6 +12671,65 @@ impl App {
                 ..Default::default()
             });
 
+        // Attached screenshots and pasted images, shown above the bar until the prompt is sent
+        let mut attachments = row![].spacing(6);
+        for (idx, path) in self.pending_images.iter().enumerate() {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            attachments = attachments.push(
+                container(
+                    row![
+                        bootstrap::image()
+                            .size(12)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.accent)
+                            }),
+                        text(name).size(12),
+                        button(bootstrap::x_lg().size(10))
+                            .on_press(Message::RemovePendingImage(idx))
+                            .padding(2)
+                            .style(move |_theme, status| button::Style {
+                                background: None,
+                                text_color: if matches!(status, button::Status::Hovered) {
+                                    pal.danger
+                                } else {
+                                    pal.muted
+                                },
+                                ..Default::default()
+                            }),
+                    ]
+                    .spacing(6)
+                    .align_y(iced::Alignment::Center),
+                )
+                .padding([4, 10])
+                .style(move |_| container::Style {
+                    background: Some(Background::Color(Color {
+                        a: 0.6,
+                        ..pal.surface_raised
+                    })),
+                    border: Border {
+                        radius: 10.0.into(),
+                        ..Default::default()
+                    },
+                    text_color: Some(pal.text),
+                    ..Default::default()
+                }),
+            );
+        }
+        let input_bar = column![]
+            .push(self.crash_notice(pal))
+            .push(self.interrupted_notice(pal))
+            .push(self.prompt_search_panel(pal))
+            .push(self.spelling_suggestions(pal))
+            .push(self.tool_progress(pal))
+            .push(self.offline_banner(pal))
+            .push(self.context_warning(pal))
+            .push(attachments.wrap())
+            .push(input_bar)
+            .spacing(6);
+
         // Outer container with padding - adjust left padding based on sidebar width
         let left_pad = if sidebar_width > 1.0 { sidebar_width } else { 0.0 };
         container(input_bar)
@@ -5140,6 +12776,8 @@ impl App {
                     SettingsPage::Behavior => self.settings_behavior_page(pal, form),
                     SettingsPage::Appearance => self.settings_appearance_page(pal, form),
                     SettingsPage::ModelSelector => self.settings_model_selector_page(pal),
+                    SettingsPage::Stats => self.settings_stats_page(pal),
+                    SettingsPage::Knowledge => self.settings_knowledge_page(pal),
                 })
             } else {
                 None
@@ -5297,6 +12935,41 @@ impl App {
         ]
         .spacing(2);
 
+        // Small chip linking to the release page when a newer version exists
+        let update_chip = self.update_available.as_ref().map(|release| {
+            tooltip(
+                button(
+                    row![
+                        bootstrap::arrow_up_circle()
+                            .size(12)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.accent)
+                            }),
+                        text(format!("Update available: {}", release.version)).size(12),
+                    ]
+                    .spacing(6)
+                    .align_y(iced::Alignment::Center),
+                )
+                .on_press(Message::OpenUpdate)
+                .padding([4, 10])
+                .style(move |_theme, status| button::Style {
+                    background: Some(Background::Color(Color {
+                        a: if matches!(status, button::Status::Hovered) { 0.25 } else { 0.12 },
+                        ..pal.accent
+                    })),
+                    border: Border {
+                        radius: 10.0.into(),
+                        ..Default::default()
+                    },
+                    text_color: pal.text,
+                    ..Default::default()
+                }),
+                text("Open the release page, or run `arula self-update`").size(11),
+                tooltip::Position::Bottom,
+            )
+        });
+        let header = column![header].push(update_chip).spacing(8);
+
         // Category buttons with icons
         let provider_btn = self.category_button(
             bootstrap::cpu(),
@@ -5322,6 +12995,22 @@ impl App {
             pal,
         );
 
+        let stats_btn = self.category_button(
+            bootstrap::bar_chart(),
+            "Usage & Stats",
+            "Tokens, cost and latency",
+            Message::SettingsNavigate(SettingsPage::Stats),
+            pal,
+        );
+
+        let knowledge_btn = self.category_button(
+            bootstrap::journal_text(),
+            "Knowledge",
+            "Reference documents for this workspace",
+            Message::SettingsNavigate(SettingsPage::Knowledge),
+            pal,
+        );
+
         // Dim the menu slightly when a submenu is open to show focus shift
         let menu_opacity = if is_on_submenu { 0.6 } else { 1.0 };
 
@@ -5332,6 +13021,8 @@ impl App {
                 provider_btn,
                 behavior_btn,
                 appearance_btn,
+                stats_btn,
+                knowledge_btn,
             ]
             .spacing(6)
             .width(Length::Fixed(SETTINGS_CARD_WIDTH)),
@@ -5420,6 +13111,171 @@ impl App {
     }
 
     /// Renders the Provider & Model settings page.
+    /// Installed Ollama models with pull and delete, shown on the Provider page
+    fn ollama_models_section(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let muted_text = move |label: String| {
+            text(label)
+                .size(11)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                })
+        };
+        let icon_style = move |_theme: &iced::Theme, status: button::Status| {
+            let is_hovered = matches!(status, button::Status::Hovered);
+            button::Style {
+                background: Some(Background::Color(Color {
+                    a: if is_hovered { 0.2 } else { 0.0 },
+                    ..pal.muted
+                })),
+                border: Border {
+                    radius: 4.0.into(),
+                    ..Default::default()
+                },
+                text_color: pal.muted,
+                ..Default::default()
+            }
+        };
+
+        let header = row![
+            text("Installed models")
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted)
+                }),
+            Space::new().width(Length::Fill),
+            button(bootstrap::arrow_clockwise().size(12))
+                .on_press(Message::RefreshOllamaModels)
+                .padding(4)
+                .style(icon_style),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let list: Element<'_, Message> = match &self.ollama_models {
+            None => muted_text("Loading…".to_string()).into(),
+            Some(Err(err)) => text(err.clone())
+                .size(11)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.danger)
+                })
+                .into(),
+            Some(Ok(models)) if models.is_empty() => {
+                muted_text("No models installed yet".to_string()).into()
+            }
+            Some(Ok(models)) => {
+                let mut list = column![].spacing(4);
+                for model in models {
+                    let details = [
+                        model.details.parameter_size.clone(),
+                        model.details.quantization_level.clone(),
+                        Some(ollama::format_size(model.size)),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" · ");
+                    let confirming = self.ollama_delete_confirm.as_ref() == Some(&model.name);
+                    let delete: Element<'_, Message> = if confirming {
+                        text("Delete?")
+                            .size(11)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.danger)
+                            })
+                            .into()
+                    } else {
+                        bootstrap::trash().size(12).into()
+                    };
+                    list = list.push(
+                        row![
+                            text(&model.name)
+                                .size(12)
+                                .font(Font::MONOSPACE)
+                                .style(move |_| iced::widget::text::Style {
+                                    color: Some(pal.text)
+                                }),
+                            Space::new().width(Length::Fill),
+                            muted_text(details),
+                            Space::new().width(Length::Fixed(8.0)),
+                            button(delete)
+                                .on_press(Message::DeleteOllamaModel(model.name.clone()))
+                                .padding(4)
+                                .style(icon_style),
+                        ]
+                        .align_y(iced::Alignment::Center),
+                    );
+                }
+                list.into()
+            }
+        };
+
+        let pulling = self.ollama_pull.as_ref().is_some_and(|p| p.running);
+        let pull_row = row![
+            text_input("Model to pull, e.g. llama3.2:3b", &self.ollama_pull_draft)
+                .on_input(Message::OllamaPullDraftChanged)
+                .on_submit(Message::PullOllamaModel)
+                .padding(8)
+                .style(input_style(pal)),
+            Space::new().width(Length::Fixed(8.0)),
+            button(text("Pull").size(13))
+                .on_press_maybe(
+                    (!pulling && !self.ollama_pull_draft.trim().is_empty())
+                        .then_some(Message::PullOllamaModel)
+                )
+                .padding([8, 16])
+                .style(primary_button_style(pal)),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let mut section = column![header, list, Space::new().height(Length::Fixed(8.0)), pull_row]
+            .spacing(6)
+            .width(Length::Fill);
+
+        if let Some(pull) = &self.ollama_pull {
+            let progress = if pull.total > 0 {
+                pull.completed as f32 / pull.total as f32
+            } else {
+                0.0
+            };
+            let status = if pull.total > 0 {
+                format!(
+                    "{}: {} ({} / {})",
+                    pull.model,
+                    pull.status,
+                    ollama::format_size(pull.completed),
+                    ollama::format_size(pull.total)
+                )
+            } else {
+                format!("{}: {}", pull.model, pull.status)
+            };
+            section = section
+                .push(muted_text(status))
+                .push(
+                    iced::widget::progress_bar(0.0..=1.0, progress)
+                        .style(move |_theme| iced::widget::progress_bar::Style {
+                            background: Background::Color(Color {
+                                a: 0.15,
+                                ..pal.accent
+                            }),
+                            bar: Background::Color(pal.accent),
+                            border: Border {
+                                radius: 3.0.into(),
+                                ..Default::default()
+                            },
+                        }),
+                );
+            if let Some(err) = &pull.error {
+                section = section.push(
+                    text(err.clone())
+                        .size(11)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.danger)
+                        }),
+                );
+            }
+        }
+
+        section.into()
+    }
+
     fn settings_provider_page<'a>(
         &'a self,
         pal: PaletteColors,
@@ -5639,6 +13495,13 @@ impl App {
         ]
         .spacing(0)
         .width(Length::Fill);
+        let base_content = if form.provider.eq_ignore_ascii_case("ollama") {
+            base_content
+                .push(Space::new().height(Length::Fixed(4.0)))
+                .push(self.ollama_models_section(pal))
+        } else {
+            base_content
+        };
 
         let content = container(base_content)
         .padding(16)
@@ -5697,6 +13560,25 @@ impl App {
                 color: Some(pal.text),
             });
 
+        // Empty fields leave the parameter to the provider
+        let sampling_field = |label: &'static str,
+                              placeholder: &'static str,
+                              value: &'a str,
+                              on_input: fn(String) -> Message| {
+            column![
+                text(label)
+                    .size(12)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    }),
+                text_input(placeholder, value)
+                    .on_input(on_input)
+                    .padding(4)
+                    .style(input_style(pal))
+            ]
+            .width(Length::Fill)
+        };
+
         let content = container(
             column![
                 text("System Prompt")
@@ -5737,21 +13619,172 @@ impl App {
                     ]
                     .width(Length::Fixed(80.0))
                 ]
-                .spacing(16),
-                Space::new().height(Length::Fixed(12.0)),
-                row![
-                    text("Enable Streaming")
-                        .size(14)
+                .spacing(16),
+                Space::new().height(Length::Fixed(12.0)),
+                row![
+                    sampling_field("Top P", "1.0", &form.top_p, Message::ConfigTopPChanged),
+                    sampling_field(
+                        "Frequency penalty",
+                        "0.0",
+                        &form.frequency_penalty,
+                        Message::ConfigFrequencyPenaltyChanged
+                    ),
+                    sampling_field(
+                        "Presence penalty",
+                        "0.0",
+                        &form.presence_penalty,
+                        Message::ConfigPresencePenaltyChanged
+                    ),
+                    sampling_field("Seed", "random", &form.seed, Message::ConfigSeedChanged),
+                ]
+                .spacing(16),
+                text("Stop Sequences")
+                    .size(12)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    }),
+                text_input("Comma-separated, e.g. ###, END", &form.stop_sequences)
+                    .on_input(Message::ConfigStopSequencesChanged)
+                    .padding(8)
+                    .style(input_style(pal)),
+                Space::new().height(Length::Fixed(12.0)),
+                row![
+                    text("Enable Streaming")
+                        .size(14)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.text)
+                        }),
+                    Space::new().width(Length::Fill),
+                    iced::widget::toggler(form.streaming_enabled)
+                        .on_toggle(Message::ConfigStreamingToggled)
+                        .width(Length::Shrink)
+                ]
+                .spacing(12)
+                .align_y(iced::Alignment::Center),
+                row![
+                    column![
+                        text("Check for Updates")
+                            .size(14)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.text)
+                            }),
+                        text(format!("Look for a newer release on GitHub daily (running {})", updates::CURRENT_VERSION))
+                            .size(12)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.muted)
+                            }),
+                    ],
+                    Space::new().width(Length::Fill),
+                    iced::widget::toggler(form.update_check_enabled)
+                        .on_toggle(Message::ConfigUpdateCheckToggled)
+                        .width(Length::Shrink)
+                ]
+                .spacing(12)
+                .align_y(iced::Alignment::Center),
+                row![
+                    column![
+                        text("Delete Old Conversations")
+                            .size(14)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.text)
+                            }),
+                        text("Days without activity before a saved conversation is deleted; pinned ones are kept. Empty keeps them forever")
+                            .size(12)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.muted)
+                            }),
+                    ]
+                    .width(Length::Fill),
+                    text_input("Never", &form.conversation_retention_days)
+                        .on_input(Message::ConfigRetentionDaysChanged)
+                        .size(13)
+                        .padding([6, 10])
+                        .width(Length::Fixed(80.0)),
+                ]
+                .spacing(12)
+                .align_y(iced::Alignment::Center),
+                row![
+                    column![
+                        text("Tool Calls")
+                            .size(14)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.text)
+                            }),
+                        text("Show each tool call's full output, a one-line summary, or only the final answer")
+                            .size(12)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.muted)
+                            }),
+                    ]
+                    .width(Length::Fill),
+                    pick_list(
+                        ToolVerbosity::ALL,
+                        Some(form.tool_verbosity),
+                        Message::ConfigToolVerbosityChanged,
+                    )
+                    .text_size(13)
+                    .padding([6, 10])
+                    .style(move |_theme, _status| iced::widget::pick_list::Style {
+                        background: Background::Color(pal.surface),
+                        text_color: pal.text,
+                        placeholder_color: pal.muted,
+                        border: Border {
+                            radius: 8.0.into(),
+                            width: 1.0,
+                            color: pal.border,
+                        },
+                        handle_color: pal.accent,
+                    }),
+                ]
+                .spacing(12)
+                .align_y(iced::Alignment::Center),
+                row![
+                    column![
+                        text("Conversation Starters")
+                            .size(14)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.text)
+                            }),
+                        text("Suggestions in an empty chat: generated by the model, picked for the project type, or only your pinned ones")
+                            .size(12)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.muted)
+                            }),
+                    ]
+                    .width(Length::Fill),
+                    pick_list(
+                        StarterMode::ALL,
+                        Some(form.starter_mode),
+                        Message::ConfigStarterModeChanged,
+                    )
+                    .text_size(13)
+                    .padding([6, 10])
+                    .style(move |_theme, _status| iced::widget::pick_list::Style {
+                        background: Background::Color(pal.surface),
+                        text_color: pal.text,
+                        placeholder_color: pal.muted,
+                        border: Border {
+                            radius: 8.0.into(),
+                            width: 1.0,
+                            color: pal.border,
+                        },
+                        handle_color: pal.accent,
+                    }),
+                ]
+                .spacing(12)
+                .align_y(iced::Alignment::Center),
+                column![
+                    text("Pinned Starters")
+                        .size(12)
                         .style(move |_| iced::widget::text::Style {
-                            color: Some(pal.text)
+                            color: Some(pal.muted)
                         }),
-                    Space::new().width(Length::Fill),
-                    iced::widget::toggler(form.streaming_enabled)
-                        .on_toggle(Message::ConfigStreamingToggled)
-                        .width(Length::Shrink)
+                    text_input("Separated by semicolons, e.g. Review my diff; Run the tests", &form.pinned_starters)
+                        .on_input(Message::ConfigPinnedStartersChanged)
+                        .padding(4)
+                        .style(input_style(pal)),
                 ]
-                .spacing(12)
-                .align_y(iced::Alignment::Center),
+                .spacing(4),
                 Space::new().height(Length::Fill),
             ]
             .spacing(8)
@@ -5814,7 +13847,7 @@ impl App {
             });
 
         // Theme mode selection (Light vs Dark)
-        let light_dark_options = vec!["Light".to_string(), "Dark".to_string()];
+        let light_dark_options = vec!["Auto".to_string(), "Light".to_string(), "Dark".to_string()];
         let theme_selector = row![
             column![
                 text("Theme Mode").size(14).style(move |_| {
@@ -5822,7 +13855,7 @@ impl App {
                         color: Some(pal.text),
                     }
                 }),
-                text("Choose your preferred color scheme")
+                text("Choose your preferred color scheme, or Auto to follow the system")
                     .size(12)
                     .style(move |_| iced::widget::text::Style {
                         color: Some(pal.muted)
@@ -5832,6 +13865,7 @@ impl App {
             pick_list(
                 light_dark_options,
                 Some(match self.theme_mode {
+                    ThemeMode::Auto => "Auto".to_string(),
                     ThemeMode::Light => "Light".to_string(),
                     _ => "Dark".to_string(),
                 }),
@@ -5921,6 +13955,28 @@ impl App {
         .spacing(12)
         .align_y(iced::Alignment::Center);
 
+        // Spellcheck toggle
+        let spellcheck_toggle = row![
+            column![
+                text("Spellcheck").size(14).style(move |_| {
+                    iced::widget::text::Style {
+                        color: Some(pal.text),
+                    }
+                }),
+                text("Mark misspelled words in the message input and suggest corrections")
+                    .size(12)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    }),
+            ],
+            Space::new().width(Length::Fill),
+            iced::widget::toggler(form.spellcheck_enabled)
+                .on_toggle(Message::ConfigSpellcheckToggled)
+                .width(Length::Shrink)
+        ]
+        .spacing(12)
+        .align_y(iced::Alignment::Center);
+
         // Build the content column
         let mut content_col = column![
             text("Visual Settings")
@@ -5941,6 +13997,8 @@ impl App {
         // Add living background toggle
         content_col = content_col.push(Space::new().height(Length::Fixed(16.0)));
         content_col = content_col.push(living_bg_toggle);
+        content_col = content_col.push(Space::new().height(Length::Fixed(16.0)));
+        content_col = content_col.push(spellcheck_toggle);
         content_col = content_col.push(Space::new().height(Length::Fill));
 
         let content = container(content_col)
@@ -5988,6 +14046,354 @@ impl App {
         .into()
     }
 
+    /// Renders the Stats page: daily tokens and cost, time-to-first-token per
+    /// provider and tool success rates from the local usage store.
+    fn settings_stats_page(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let header = text("Usage & Stats")
+            .size(18)
+            .style(move |_| iced::widget::text::Style {
+                color: Some(pal.text),
+            });
+
+        let Some(summary) = self.usage_summary.as_ref() else {
+            return column![
+                header,
+                Space::new().height(Length::Fixed(12.0)),
+                text("Loading usage...")
+                    .size(12)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.muted)
+                    }),
+            ]
+            .width(Length::Fill)
+            .into();
+        };
+
+        let section = move |title: &'static str| {
+            text(title)
+                .size(14)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(pal.muted),
+                })
+        };
+        // Label on the left, value on the right
+        let stat_row = move |label: String, value: String, color: Color| -> Element<'static, Message> {
+            row![
+                text(label).size(12).style(move |_| iced::widget::text::Style {
+                    color: Some(pal.text)
+                }),
+                Space::new().width(Length::Fill),
+                text(value)
+                    .size(12)
+                    .font(Font::MONOSPACE)
+                    .style(move |_| iced::widget::text::Style { color: Some(color) }),
+            ]
+            .align_y(iced::Alignment::Center)
+            .into()
+        };
+        let empty = move |message: &'static str| -> Element<'static, Message> {
+            text(message)
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(Color { a: 0.6, ..pal.muted }),
+                })
+                .into()
+        };
+
+        let totals = stat_row(
+            format!("Last {} days", STATS_WINDOW_DAYS),
+            format!(
+                "{} tokens · ~${:.2}",
+                summary.total_tokens(),
+                summary.total_cost()
+            ),
+            pal.accent,
+        );
+
+        let days: Vec<Element<'_, Message>> = if summary.days.is_empty() {
+            vec![empty("No token usage recorded yet")]
+        } else {
+            summary
+                .days
+                .iter()
+                .map(|day| {
+                    stat_row(
+                        day.date.format("%a %b %-d").to_string(),
+                        format!(
+                            "{} in / {} out · ${:.2}",
+                            day.prompt_tokens, day.completion_tokens, day.cost
+                        ),
+                        pal.text,
+                    )
+                })
+                .collect()
+        };
+
+        let providers: Vec<Element<'_, Message>> = if summary.providers.is_empty() {
+            vec![empty("No turns recorded yet")]
+        } else {
+            summary
+                .providers
+                .iter()
+                .map(|p| {
+                    let ttft = p
+                        .avg_ttft_ms
+                        .map(|ms| format!("{} ms", ms))
+                        .unwrap_or_else(|| "n/a".to_string());
+                    let color = if p.failed_turns > 0 { pal.danger } else { pal.text };
+                    stat_row(
+                        p.provider.clone(),
+                        format!("{} · {} turns, {} failed", ttft, p.turns, p.failed_turns),
+                        color,
+                    )
+                })
+                .collect()
+        };
+
+        let tools: Vec<Element<'_, Message>> = if summary.tools.is_empty() {
+            vec![empty("No tool calls recorded yet")]
+        } else {
+            summary
+                .tools
+                .iter()
+                .map(|t| {
+                    let rate = t.success_rate();
+                    let color = if rate >= 0.9 {
+                        pal.success
+                    } else if rate >= 0.5 {
+                        pal.accent
+                    } else {
+                        pal.danger
+                    };
+                    stat_row(
+                        t.name.clone(),
+                        format!(
+                            "{:.0}% of {} · {} ms avg",
+                            rate * 100.0,
+                            t.calls,
+                            t.avg_duration_ms
+                        ),
+                        color,
+                    )
+                })
+                .collect()
+        };
+
+        let content_col = column![
+            totals,
+            Space::new().height(Length::Fixed(16.0)),
+            section("Tokens per day"),
+            column(days).spacing(4),
+            Space::new().height(Length::Fixed(16.0)),
+            section("Average time to first token"),
+            column(providers).spacing(4),
+            Space::new().height(Length::Fixed(16.0)),
+            section("Tool success rate"),
+            column(tools).spacing(4),
+        ]
+        .spacing(6);
+
+        let content = container(scrollable(content_col).height(Length::Fill))
+            .padding(16)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(move |_| container::Style {
+                background: Some(Background::Color(Color {
+                    a: 0.08,
+                    ..pal.accent
+                })),
+                border: Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: Color {
+                        a: 0.15,
+                        ..pal.accent
+                    },
+                },
+                ..Default::default()
+            });
+
+        column![
+            header,
+            Space::new().height(Length::Fixed(12.0)),
+            content,
+            Space::new().height(Length::Fixed(8.0)),
+            text(format!(
+                "Costs are estimates for known models. Recorded locally in {}",
+                usage_store::usage_path().display()
+            ))
+            .size(10)
+            .style(move |_| iced::widget::text::Style {
+                color: Some(Color { a: 0.6, ..pal.muted })
+            }),
+        ]
+        .spacing(4)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Renders the Knowledge page: documents attached to the active workspace.
+    fn settings_knowledge_page(&self, pal: PaletteColors) -> Element<'_, Message> {
+        let workspace = &self.workspaces[self.active_workspace];
+        let header = text(format!("Knowledge · {}", workspace.name))
+            .size(18)
+            .style(move |_| iced::widget::text::Style {
+                color: Some(pal.text),
+            });
+
+        let muted = move |message: String| -> Element<'static, Message> {
+            text(message)
+                .size(12)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(Color { a: 0.6, ..pal.muted }),
+                })
+                .into()
+        };
+
+        let mut rows: Vec<Element<'_, Message>> = match &self.knowledge_documents {
+            None => vec![muted("Loading documents...".to_string())],
+            Some(documents) if documents.is_empty() && self.knowledge_adding.is_none() => {
+                vec![muted(
+                    "No documents yet. Added documents are searched for every prompt in this workspace."
+                        .to_string(),
+                )]
+            }
+            Some(documents) => documents
+                .iter()
+                .map(|doc| {
+                    let details = format!(
+                        "{} chunks · ~{} tokens · {} · added {}",
+                        doc.chunks,
+                        doc.tokens,
+                        doc.embedding_model.as_deref().unwrap_or("keyword search"),
+                        doc.added_at.with_timezone(&chrono::Local).format("%b %-d")
+                    );
+                    row![
+                        bootstrap::file_earmark_text()
+                            .size(16)
+                            .style(move |_| iced::widget::text::Style {
+                                color: Some(pal.accent)
+                            }),
+                        column![
+                            text(doc.name.clone()).size(13).style(move |_| {
+                                iced::widget::text::Style {
+                                    color: Some(pal.text),
+                                }
+                            }),
+                            text(details)
+                                .size(11)
+                                .style(move |_| iced::widget::text::Style {
+                                    color: Some(pal.muted)
+                                }),
+                        ]
+                        .spacing(2),
+                        Space::new().width(Length::Fill),
+                        button(bootstrap::trash().size(14))
+                            .on_press(Message::RemoveKnowledgeDocument(doc.id.clone()))
+                            .padding([4, 8])
+                            .style(move |_theme, status| button::Style {
+                                background: matches!(status, button::Status::Hovered)
+                                    .then_some(Background::Color(pal.surface_raised)),
+                                text_color: pal.danger,
+                                border: Border {
+                                    radius: 6.0.into(),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            }),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center)
+                    .into()
+                })
+                .collect(),
+        };
+        if let Some(name) = &self.knowledge_adding {
+            rows.push(muted(format!("Adding {}...", name)));
+        }
+        if let Some(error) = &self.knowledge_error {
+            rows.push(
+                text(error.clone())
+                    .size(12)
+                    .style(move |_| iced::widget::text::Style {
+                        color: Some(pal.danger),
+                    })
+                    .into(),
+            );
+        }
+
+        let add_button = button(
+            row![bootstrap::file_earmark_plus().size(14), text("Add document").size(12)]
+                .spacing(6)
+                .align_y(iced::Alignment::Center),
+        )
+        .on_press_maybe(
+            self.knowledge_adding
+                .is_none()
+                .then_some(Message::AddKnowledgeDocument),
+        )
+        .padding([6, 12])
+        .style(move |_theme, status| {
+            let hovered = matches!(status, button::Status::Hovered);
+            button::Style {
+                background: Some(Background::Color(Color {
+                    a: if hovered { 1.0 } else { 0.85 },
+                    ..pal.accent
+                })),
+                text_color: pal.background,
+                border: Border {
+                    radius: 6.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        });
+
+        let content = container(scrollable(column(rows).spacing(10)).height(Length::Fill))
+            .padding(16)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(move |_| container::Style {
+                background: Some(Background::Color(Color {
+                    a: 0.08,
+                    ..pal.accent
+                })),
+                border: Border {
+                    radius: 12.0.into(),
+                    width: 1.0,
+                    color: Color {
+                        a: 0.15,
+                        ..pal.accent
+                    },
+                },
+                ..Default::default()
+            });
+
+        let retrieval = match self.config.get_knowledge().embedding_model {
+            Some(model) => format!("Excerpts are matched to prompts with {}.", model),
+            None => "Excerpts are matched to prompts by keywords; set knowledge.embedding_model for semantic search."
+                .to_string(),
+        };
+
+        column![
+            row![header, Space::new().width(Length::Fill), add_button]
+                .align_y(iced::Alignment::Center),
+            Space::new().height(Length::Fixed(12.0)),
+            content,
+            Space::new().height(Length::Fixed(8.0)),
+            text(format!("PDF, Markdown and text files. {}", retrieval))
+                .size(10)
+                .style(move |_| iced::widget::text::Style {
+                    color: Some(Color { a: 0.6, ..pal.muted })
+                }),
+        ]
+        .spacing(4)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
     /// Renders the Model Selector page with loading state and model list.
     fn settings_model_selector_page(&self, pal: PaletteColors) -> Element<'_, Message> {
         let header = text("Select Model")
@@ -6206,6 +14612,44 @@ impl App {
                     let model_name = model.clone();
                     let model_display = model.clone();
                     let is_selected = model == &self.config_form.model;
+                    // OpenRouter reports context length, pricing and modalities
+                    let info = model_info::get(model);
+                    let mut name_col = column![text(model_display)
+                        .size(13)
+                        .style(move |_| iced::widget::text::Style {
+                            color: Some(pal.text)
+                        })]
+                    .spacing(2)
+                    .width(Length::Fill);
+                    let mut badges = row![].spacing(4);
+                    if let Some(info) = &info {
+                        name_col = name_col.push(text(info.summary()).size(11).style(move |_| {
+                            iced::widget::text::Style {
+                                color: Some(pal.muted),
+                            }
+                        }));
+                        for badge in info.badges() {
+                            badges = badges.push(
+                                container(text(badge.to_string()).size(10).style(move |_| {
+                                    iced::widget::text::Style {
+                                        color: Some(pal.accent),
+                                    }
+                                }))
+                                .padding([1, 6])
+                                .style(move |_| container::Style {
+                                    background: Some(Background::Color(Color {
+                                        a: 0.12,
+                                        ..pal.accent
+                                    })),
+                                    border: Border {
+                                        radius: 6.0.into(),
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                }),
+                            );
+                        }
+                    }
                     let model_btn = button(
                         row![
                             bootstrap::check_lg()
@@ -6218,11 +14662,8 @@ impl App {
                                     })
                                 }),
                             Space::new().width(Length::Fixed(8.0)),
-                            text(model_display)
-                                .size(13)
-                                .style(move |_| iced::widget::text::Style {
-                                    color: Some(pal.text)
-                                }),
+                            name_col,
+                            badges,
                         ]
                         .align_y(iced::Alignment::Center),
                     )
@@ -6308,12 +14749,37 @@ impl App {
 }
 
 fn main() -> iced::Result {
+    // Structured logs go to ~/.arula/logs and the in-app log viewer
+    let log_level = logging::log_level_from_args(std::env::args().skip(1));
+    let telemetry = Config::load_or_default()
+        .ok()
+        .and_then(|config| config.telemetry);
+    let _log_guard = match logging::init_logging(log_level.as_deref(), telemetry.as_ref()) {
+        Ok(guard) => Some(guard),
+        Err(e) => {
+            eprintln!("Failed to initialize logging: {e}");
+            None
+        }
+    };
+
+    // Panics leave a report in ~/.arula/crashes, offered on the next launch
+    crash_report::install("arula-desktop");
+
+    // File tools outside the working directory ask through the question dialog
+    arula_core::tools::sandbox::set_interactive(true);
+    // Screenshots are uploaded without asking, so only when the config opts in
+    arula_core::tools::builtin::screen_capture::set_enabled(
+        arula_core::utils::config::Config::load_or_default()
+            .map(|config| config.get_screen_capture())
+            .unwrap_or(false),
+    );
+
     fn get_theme(app: &App) -> iced::Theme {
-        app_theme_with_mode(app.theme_mode)
+        app_theme_with_mode(app.theme_mode.resolve(app.system_dark))
     }
     
     iced::application(App::init, App::update, App::view)
-        .title("Arula Desktop")
+        .title(App::title)
         .subscription(App::subscription)
         .theme(get_theme)
         .font(iced_fonts::BOOTSTRAP_FONT_BYTES)
@@ -0,0 +1,129 @@
+//! First-launch setup wizard.
+//!
+//! Shown instead of the chat when no config file exists yet: the user picks a
+//! provider, enters a key (checked with a test request), picks a model and a
+//! theme, and the result is saved as the first config.
+
+use crate::theme::ThemeMode;
+use arula_core::utils::config::{AiConfig, Config};
+
+/// Steps of the setup wizard, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnboardingStep {
+    Provider,
+    ApiKey,
+    Model,
+    Theme,
+}
+
+impl OnboardingStep {
+    pub const ALL: [OnboardingStep; 4] = [
+        OnboardingStep::Provider,
+        OnboardingStep::ApiKey,
+        OnboardingStep::Model,
+        OnboardingStep::Theme,
+    ];
+
+    /// Returns the heading for the step.
+    pub fn title(&self) -> &'static str {
+        match self {
+            OnboardingStep::Provider => "Choose a provider",
+            OnboardingStep::ApiKey => "Connect",
+            OnboardingStep::Model => "Pick a model",
+            OnboardingStep::Theme => "Pick a theme",
+        }
+    }
+
+    /// Returns the line shown under the heading.
+    pub fn subtitle(&self) -> &'static str {
+        match self {
+            OnboardingStep::Provider => "Where ARULA sends your prompts",
+            OnboardingStep::ApiKey => "Enter your API key and test the connection",
+            OnboardingStep::Model => "The model answering your prompts; change it any time in Settings",
+            OnboardingStep::Theme => "You can switch later under Settings → Appearance",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        Self::ALL.iter().position(|s| s == self).unwrap_or(0)
+    }
+
+    pub fn next(&self) -> Option<Self> {
+        Self::ALL.get(self.index() + 1).copied()
+    }
+
+    pub fn previous(&self) -> Option<Self> {
+        self.index().checked_sub(1).map(|i| Self::ALL[i])
+    }
+}
+
+/// State of the setup wizard.
+#[derive(Debug, Clone)]
+pub struct Onboarding {
+    pub step: OnboardingStep,
+    pub provider: String,
+    pub api_url: String,
+    pub api_key: String,
+    pub model: String,
+    pub theme_mode: ThemeMode,
+    /// Whether the test request is in flight
+    pub testing: bool,
+    /// Outcome of the last test request: a confirmation or the error
+    pub test_result: Option<Result<String, String>>,
+}
+
+impl Onboarding {
+    /// Starts the wizard with `provider` preselected.
+    pub fn new(provider: &str) -> Self {
+        let mut onboarding = Self {
+            step: OnboardingStep::Provider,
+            provider: String::new(),
+            api_url: String::new(),
+            api_key: String::new(),
+            model: String::new(),
+            theme_mode: ThemeMode::Auto,
+            testing: false,
+            test_result: None,
+        };
+        onboarding.select_provider(provider);
+        onboarding
+    }
+
+    /// Switches provider, resetting URL, model and key to its defaults.
+    pub fn select_provider(&mut self, provider: &str) {
+        let defaults = AiConfig::get_provider_defaults(provider);
+        self.provider = provider.to_string();
+        self.api_url = defaults.api_url;
+        self.api_key = defaults.api_key;
+        self.model = defaults.model;
+        self.test_result = None;
+    }
+
+    /// Local servers (Ollama, LM Studio, llamafile) work without a key.
+    pub fn key_required(&self) -> bool {
+        !matches!(
+            self.provider.to_lowercase().as_str(),
+            "ollama" | "lm studio" | "lmstudio" | "lm-studio" | "llamafile"
+        )
+    }
+
+    /// Whether the current step is complete enough to move on.
+    pub fn can_continue(&self) -> bool {
+        match self.step {
+            OnboardingStep::Provider => !self.provider.is_empty(),
+            OnboardingStep::ApiKey => matches!(self.test_result, Some(Ok(_))),
+            OnboardingStep::Model => !self.model.trim().is_empty(),
+            OnboardingStep::Theme => true,
+        }
+    }
+
+    /// Writes the wizard's choices into `config` (without saving it).
+    pub fn apply_to(&self, config: &mut Config) -> anyhow::Result<()> {
+        config.switch_provider(&self.provider)?;
+        config.set_api_url(self.api_url.trim());
+        config.set_api_key(self.api_key.trim());
+        config.set_model(self.model.trim());
+        config.theme = Some(self.theme_mode.name().to_lowercase());
+        Ok(())
+    }
+}
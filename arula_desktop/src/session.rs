@@ -1,4 +1,8 @@
+use arula_core::api::api::ChatMessage;
+use arula_core::session_manager::Source;
+use arula_core::utils::images;
 use chrono::{DateTime, Utc};
+use std::path::PathBuf;
 use std::time::Instant;
 use uuid::Uuid;
 
@@ -14,6 +18,90 @@ pub struct MessageEntry {
     pub tool_call_id: Option<String>,
     /// Duration in seconds the AI spent thinking (only set for completed thinking messages)
     pub thinking_duration_secs: Option<f32>,
+    /// Snapshot taken before a write/edit tool changed a file, for "Undo edit"
+    pub snapshot_id: Option<String>,
+    /// File a read_file call read, for opening it in the preview pane
+    pub preview_path: Option<PathBuf>,
+    /// Final stats of the response this AI message ended (only set for the last AI message of a turn)
+    pub response_stats: Option<ResponseStats>,
+    /// Screenshots attached to a user message, sent as vision input
+    pub images: Vec<PathBuf>,
+    /// Web pages cited under an AI answer (only set for the last AI message of a turn)
+    pub sources: Vec<Source>,
+    /// The user stopped this answer part-way to reply (only set for AI messages)
+    pub interrupted: bool,
+}
+
+/// Stats shown in the footer of a finished AI response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseStats {
+    pub tokens: u32,
+    pub duration_secs: f32,
+    pub model: String,
+}
+
+impl ResponseStats {
+    /// Footer text, e.g. "412 tokens · 6.3s · gpt-4o".
+    pub fn footer(&self) -> String {
+        format!(
+            "{} tokens · {:.1}s · {}",
+            self.tokens, self.duration_secs, self.model
+        )
+    }
+}
+
+/// Live measurements of the response being streamed.
+#[derive(Debug, Clone)]
+pub struct StreamStats {
+    started: Instant,
+    /// Characters of text and reasoning received so far
+    streamed_chars: usize,
+    /// Completion tokens reported by the provider, summed over the turn's requests
+    reported_tokens: Option<u32>,
+    /// Round of tool calls the agent loop is on, as reported by the backend
+    tool_rounds: u32,
+    /// Tool call that hasn't returned yet, as shown in its bubble
+    current_tool: Option<String>,
+}
+
+impl StreamStats {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            streamed_chars: 0,
+            reported_tokens: None,
+            tool_rounds: 0,
+            current_tool: None,
+        }
+    }
+
+    /// Rounds of tool calls the turn has run so far.
+    pub fn tool_rounds(&self) -> u32 {
+        self.tool_rounds
+    }
+
+    /// The tool call being run, if any.
+    pub fn current_tool(&self) -> Option<&str> {
+        self.current_tool.as_deref()
+    }
+
+    /// Seconds since the response started.
+    pub fn elapsed_secs(&self) -> f32 {
+        self.started.elapsed().as_secs_f32()
+    }
+
+    /// Tokens generated so far: reported usage, or ~4 characters per token
+    /// until the provider sends it.
+    pub fn tokens(&self) -> u32 {
+        self.reported_tokens
+            .unwrap_or((self.streamed_chars / 4) as u32)
+    }
+
+    /// Generation speed, once there's enough to measure.
+    pub fn tokens_per_sec(&self) -> Option<f32> {
+        let elapsed = self.elapsed_secs();
+        (elapsed >= 0.5 && self.tokens() > 0).then(|| self.tokens() as f32 / elapsed)
+    }
 }
 
 impl MessageEntry {
@@ -31,6 +119,12 @@ impl MessageEntry {
             parsed_timestamp,
             tool_call_id: None,
             thinking_duration_secs: None,
+            snapshot_id: None,
+            preview_path: None,
+            response_stats: None,
+            images: Vec::new(),
+            sources: Vec::new(),
+            interrupted: false,
         }
     }
 
@@ -48,6 +142,12 @@ impl MessageEntry {
             parsed_timestamp,
             tool_call_id: None,
             thinking_duration_secs: None,
+            snapshot_id: None,
+            preview_path: None,
+            response_stats: None,
+            images: Vec::new(),
+            sources: Vec::new(),
+            interrupted: false,
         }
     }
 
@@ -85,6 +185,12 @@ impl MessageEntry {
             parsed_timestamp,
             tool_call_id,
             thinking_duration_secs: None,
+            snapshot_id: None,
+            preview_path: None,
+            response_stats: None,
+            images: Vec::new(),
+            sources: Vec::new(),
+            interrupted: false,
         }
     }
 
@@ -102,6 +208,12 @@ impl MessageEntry {
             parsed_timestamp,
             tool_call_id: None,
             thinking_duration_secs: None,
+            snapshot_id: None,
+            preview_path: None,
+            response_stats: None,
+            images: Vec::new(),
+            sources: Vec::new(),
+            interrupted: false,
         }
     }
 
@@ -123,6 +235,52 @@ impl MessageEntry {
     }
 }
 
+/// Summary of a run of consecutive tool calls, e.g. "Explored 15 files" for
+/// reads and searches, or "Ran 6 tool calls (Read 3, Edit 2, Shell 1)".
+pub fn tool_run_label(run: &[MessageEntry]) -> String {
+    // Tool bubbles read "<icon> <Tool name> ..."
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for message in run {
+        let name = message.content.split_whitespace().nth(1).unwrap_or("Tool");
+        match counts.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+    let exploring = ["Read", "List", "Search", "Outline"];
+    let mut label = if counts.iter().all(|(name, _)| exploring.contains(name)) {
+        format!("Explored {} files", run.len())
+    } else {
+        counts.sort_by(|a, b| b.1.cmp(&a.1));
+        let breakdown: Vec<String> = counts
+            .iter()
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect();
+        format!("Ran {} tool calls ({})", run.len(), breakdown.join(", "))
+    };
+    let failed = run.iter().filter(|m| m.content.contains(" ✗ ")).count();
+    if failed > 0 {
+        label.push_str(&format!(" · {} failed", failed));
+    }
+    label
+}
+
+/// One entry of a session's outline: a user turn or a run of tool calls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineEntry {
+    /// Index of the first message the entry covers
+    pub message_index: usize,
+    pub label: String,
+    /// Number of tool calls, for a tool group
+    pub tool_count: Option<usize>,
+}
+
+/// Characters of a user turn shown in the outline
+const OUTLINE_LABEL_CHARS: usize = 60;
+
+/// Characters of the title shown on a tab
+const TAB_LABEL_CHARS: usize = 24;
+
 /// A chat session with message history.
 #[derive(Debug, Clone)]
 pub struct Session {
@@ -133,6 +291,29 @@ pub struct Session {
     ai_buffer: String,
     /// Title for the conversation
     pub title: String,
+    /// Measurements of the response being streamed
+    pub stream_stats: Option<StreamStats>,
+    /// Temperature for this session only, overriding the configured one
+    pub temperature: Option<f32>,
+    /// Max tokens for this session only, overriding the configured value
+    pub max_tokens: Option<u32>,
+    /// Web pages the running turn's tool calls returned
+    turn_sources: Vec<Source>,
+    /// History sent in place of the first N messages after the conversation
+    /// was summarized or trimmed; the transcript itself is left as it was
+    compacted_history: Option<(usize, Vec<ChatMessage>)>,
+    /// The app closed while the last answer was streaming; cleared once the
+    /// user continues or sends something else
+    pub interrupted: bool,
+    /// An answer finished while another tab was shown
+    pub unread: bool,
+    /// Error of a stream that failed while another tab was shown, reported
+    /// when this tab is shown again
+    pub background_error: Option<String>,
+    /// Show the messages as sent to the model instead of the rendered chat
+    pub show_raw: bool,
+    /// Unsent input, kept here while another tab is shown
+    pub draft: String,
 }
 
 impl Session {
@@ -144,6 +325,16 @@ impl Session {
             is_streaming: false,
             ai_buffer: String::new(),
             title: "New Chat".to_string(),
+            stream_stats: None,
+            temperature: None,
+            max_tokens: None,
+            turn_sources: Vec::new(),
+            compacted_history: None,
+            interrupted: false,
+            unread: false,
+            background_error: None,
+            show_raw: false,
+            draft: String::new(),
         }
     }
 
@@ -155,6 +346,16 @@ impl Session {
             is_streaming: false,
             ai_buffer: String::new(),
             title: "New Chat".to_string(),
+            stream_stats: None,
+            temperature: None,
+            max_tokens: None,
+            turn_sources: Vec::new(),
+            compacted_history: None,
+            interrupted: false,
+            unread: false,
+            background_error: None,
+            show_raw: false,
+            draft: String::new(),
         };
 
         for event in events {
@@ -179,6 +380,12 @@ impl Session {
                     // For simplicity, we'll just mark the tool as complete
                     // The actual display is handled by the update_tool_message
                 }
+                arula_core::session_manager::UiEvent::Sources(_, sources) => {
+                    // Saved right after the answer they belong to
+                    if let Some(last) = session.messages.last_mut().filter(|m| m.is_ai()) {
+                        last.sources = sources.clone();
+                    }
+                }
                 _ => {}
             }
         }
@@ -229,6 +436,12 @@ impl Session {
                         content: msg.content.clone(),
                         timestamp: msg.timestamp.clone(),
                     });
+                    if !msg.sources.is_empty() {
+                        events.push(arula_core::session_manager::UiEvent::Sources(
+                            self.id,
+                            msg.sources.clone(),
+                        ));
+                    }
                 }
                 "Thinking" => {
                     events.push(arula_core::session_manager::UiEvent::Thinking(self.id, msg.content.clone()));
@@ -270,8 +483,20 @@ impl Session {
 
     /// Adds a user message to the session.
     pub fn add_user_message(&mut self, content: String, timestamp: String) {
+        self.add_user_message_with_images(content, timestamp, Vec::new());
+    }
+
+    /// Adds a user message with attached images to the session.
+    pub fn add_user_message_with_images(
+        &mut self,
+        content: String,
+        timestamp: String,
+        images: Vec<PathBuf>,
+    ) {
         self.finalize_thinking_messages();
-        self.messages.push(MessageEntry::user(content, timestamp));
+        let mut entry = MessageEntry::user(content, timestamp);
+        entry.images = images;
+        self.messages.push(entry);
     }
 
     /// Adds or appends to an AI message using buffered approach.
@@ -371,6 +596,20 @@ impl Session {
         self.add_tool_message(content, timestamp, None);
     }
 
+    /// Attaches a file snapshot to the last tool message.
+    pub fn set_tool_snapshot(&mut self, snapshot_id: String) {
+        if let Some(last) = self.messages.iter_mut().rev().find(|m| m.is_tool()) {
+            last.snapshot_id = Some(snapshot_id);
+        }
+    }
+
+    /// Attaches the file a read_file call read to the last tool message.
+    pub fn set_tool_preview_path(&mut self, path: PathBuf) {
+        if let Some(last) = self.messages.iter_mut().rev().find(|m| m.is_tool()) {
+            last.preview_path = Some(path);
+        }
+    }
+
     /// Adds or appends to a Thinking message.
     pub fn append_thinking_message(&mut self, content: String, timestamp: String) {
         if let Some(last) = self.messages.last_mut() {
@@ -387,51 +626,204 @@ impl Session {
         }
     }
 
+    /// Prompts the user sent in this session, oldest first.
+    pub fn user_prompts(&self) -> Vec<String> {
+        self.messages
+            .iter()
+            .filter(|m| m.is_user())
+            .map(|m| m.content.clone())
+            .collect()
+    }
+
     /// Converts session messages to ChatMessage format for API calls.
     /// Includes user, AI, and tool messages for full conversation context.
     /// Excludes thinking messages as they're internal reasoning.
-    pub fn get_chat_history(&self) -> Vec<arula_core::api::api::ChatMessage> {
-        self.messages
-            .iter()
-            .filter(|msg| msg.is_user() || msg.is_ai() || msg.is_tool())
-            .map(|msg| {
-                if msg.is_tool() {
-                    // Tool messages contain the result of tool execution
-                    // Pass through tool_call_id for provider correlation
-                    arula_core::api::api::ChatMessage {
-                        role: "tool".to_string(),
-                        content: Some(msg.content.clone()),
-                        tool_calls: None,
-                        tool_call_id: msg.tool_call_id.clone(), // Pass through the ID
-                        tool_name: Some("tool_result".to_string()), // Generic name for Ollama compatibility
-                    }
-                } else {
-                    arula_core::api::api::ChatMessage {
-                        role: if msg.is_user() {
-                            "user".to_string()
+    pub fn get_chat_history(&self) -> Vec<ChatMessage> {
+        self.chat_history(true)
+    }
+
+    /// Chat history without image attachments, for token estimates
+    pub fn text_history(&self) -> Vec<ChatMessage> {
+        self.chat_history(false)
+    }
+
+    fn chat_history(&self, with_images: bool) -> Vec<ChatMessage> {
+        let (skip, mut history) = match &self.compacted_history {
+            Some((covered, history)) => (*covered, history.clone()),
+            None => (0, Vec::new()),
+        };
+        history.extend(
+            self.messages
+                .iter()
+                .skip(skip)
+                .filter(|msg| msg.is_user() || msg.is_ai() || msg.is_tool())
+                .map(|msg| {
+                    if msg.is_tool() {
+                        // Tool messages contain the result of tool execution
+                        // Pass through tool_call_id for provider correlation
+                        ChatMessage {
+                            role: "tool".to_string(),
+                            content: Some(msg.content.clone()),
+                            tool_calls: None,
+                            tool_call_id: msg.tool_call_id.clone(), // Pass through the ID
+                            tool_name: Some("tool_result".to_string()), // Generic name for Ollama compatibility
+                            images: Vec::new(),
+                        }
+                    } else {
+                        // Tell the model where the user cut its answer off
+                        let content = if msg.interrupted {
+                            format!("{}\n\n[Interrupted by the user]", msg.content)
                         } else {
-                            "assistant".to_string()
-                        },
-                        content: Some(msg.content.clone()),
-                        tool_calls: None,
-                        tool_call_id: None,
-                        tool_name: None,
+                            msg.content.clone()
+                        };
+                        ChatMessage {
+                            role: if msg.is_user() {
+                                "user".to_string()
+                            } else {
+                                "assistant".to_string()
+                            },
+                            content: Some(content),
+                            tool_calls: None,
+                            tool_call_id: None,
+                            tool_name: None,
+                            images: msg
+                                .images
+                                .iter()
+                                .filter(|_| with_images)
+                                .filter_map(|path| match images::data_url(path) {
+                                    Ok(url) => Some(url),
+                                    Err(err) => {
+                                        tracing::warn!("Dropping attachment: {err}");
+                                        None
+                                    }
+                                })
+                                .collect(),
+                        }
                     }
-                }
-            })
-            .collect()
+                }),
+        );
+        history
     }
 
-    /// Sets the streaming state.
+    /// Sends `history` instead of the first `covered` messages from now on.
+    pub fn set_compacted_history(&mut self, covered: usize, history: Vec<ChatMessage>) {
+        self.compacted_history = Some((covered, history));
+    }
+
+    /// Sets the streaming state, starting fresh stream stats when a response begins.
     pub fn set_streaming(&mut self, streaming: bool) {
+        if streaming && !self.is_streaming {
+            self.stream_stats = Some(StreamStats::new());
+            self.turn_sources.clear();
+        }
         self.is_streaming = streaming;
     }
 
+    /// Counts streamed text or reasoning towards the live stats.
+    pub fn record_streamed_text(&mut self, text: &str) {
+        if let Some(stats) = &mut self.stream_stats {
+            stats.streamed_chars += text.chars().count();
+        }
+    }
+
+    /// Shows a tool call as running.
+    pub fn record_tool_start(&mut self, label: String) {
+        if let Some(stats) = &mut self.stream_stats {
+            stats.current_tool = Some(label);
+        }
+    }
+
+    /// Records the round of tool calls the agent loop started.
+    pub fn record_tool_round(&mut self, round: u32) {
+        if let Some(stats) = &mut self.stream_stats {
+            stats.tool_rounds = round;
+        }
+    }
+
+    /// Clears the running tool once its result is in.
+    pub fn record_tool_end(&mut self) {
+        if let Some(stats) = &mut self.stream_stats {
+            stats.current_tool = None;
+        }
+    }
+
+    /// Adds provider-reported completion tokens; a turn with tool calls spans several requests.
+    pub fn record_completion_tokens(&mut self, tokens: u32) {
+        if let Some(stats) = &mut self.stream_stats {
+            *stats.reported_tokens.get_or_insert(0) += tokens;
+        }
+    }
+
+    /// Ends the live stats and attaches them to the turn's last AI message.
+    pub fn finish_stream_stats(&mut self, model: &str) {
+        let Some(stats) = self.stream_stats.take() else {
+            return;
+        };
+        // Only this turn's answer: stop at the user message that started it
+        let mut this_turn = self.messages.iter_mut().rev().take_while(|m| !m.is_user());
+        if let Some(message) = this_turn.find(|m| m.is_ai()) {
+            message.response_stats = Some(ResponseStats {
+                tokens: stats.tokens(),
+                duration_secs: stats.elapsed_secs(),
+                model: model.to_string(),
+            });
+        }
+    }
+
+    /// Collects web pages a tool call of the running turn returned, once per URL.
+    pub fn add_sources(&mut self, sources: Vec<Source>) {
+        for source in sources {
+            if !self.turn_sources.iter().any(|s| s.url == source.url) {
+                self.turn_sources.push(source);
+            }
+        }
+    }
+
+    /// Attaches the turn's web pages to its answer for numbered citations.
+    pub fn attach_sources(&mut self) {
+        if self.turn_sources.is_empty() {
+            return;
+        }
+        let sources = std::mem::take(&mut self.turn_sources);
+        let mut this_turn = self.messages.iter_mut().rev().take_while(|m| !m.is_user());
+        if let Some(message) = this_turn.find(|m| m.is_ai()) {
+            message.sources = sources;
+        }
+    }
+
+    /// Marks the answer of the running turn as cut off by the user, so the
+    /// next prompt is sent knowing it was incomplete
+    pub fn mark_answer_interrupted(&mut self) {
+        let mut this_turn = self.messages.iter_mut().rev().take_while(|m| !m.is_user());
+        if let Some(message) = this_turn.find(|m| m.is_ai()) {
+            message.interrupted = true;
+        }
+    }
+
     /// Sets the conversation title.
     pub fn set_title(&mut self, title: String) {
         self.title = title;
     }
 
+    /// Short title for the tab strip: the conversation title once one is
+    /// set, otherwise the first prompt
+    pub fn tab_label(&self) -> String {
+        let title = if self.title != "New Chat" {
+            self.title.trim()
+        } else {
+            self.messages
+                .iter()
+                .find(|m| m.is_user())
+                .and_then(|m| m.content.lines().find(|l| !l.trim().is_empty()))
+                .map_or("New Chat", str::trim)
+        };
+        let mut label: String = title.chars().take(TAB_LABEL_CHARS).collect();
+        if title.chars().count() > TAB_LABEL_CHARS {
+            label.push('…');
+        }
+        label
+    }
+
     /// Gets the conversation title.
     pub fn get_title(&self) -> &str {
         &self.title
@@ -442,10 +834,91 @@ impl Session {
         self.is_streaming
     }
 
+    /// Removes the message at `index` and everything after it, returning the
+    /// tool call ids of the removed tool messages.
+    pub fn truncate_from(&mut self, index: usize) -> Vec<String> {
+        if index >= self.messages.len() {
+            return Vec::new();
+        }
+        self.ai_buffer.clear();
+        if self
+            .compacted_history
+            .as_ref()
+            .is_some_and(|(covered, _)| index < *covered)
+        {
+            self.compacted_history = None;
+        }
+        self.messages
+            .drain(index..)
+            .filter_map(|m| m.tool_call_id)
+            .collect()
+    }
+
+    /// User turns and groups of consecutive tool calls, for quick navigation.
+    pub fn outline(&self) -> Vec<OutlineEntry> {
+        let mut outline: Vec<OutlineEntry> = Vec::new();
+        let mut previous_was_tool = false;
+        for (index, message) in self.messages.iter().enumerate() {
+            if message.is_user() {
+                let first_line = message.content.lines().find(|l| !l.trim().is_empty());
+                let first_line = first_line.unwrap_or_default().trim();
+                let mut label: String = first_line.chars().take(OUTLINE_LABEL_CHARS).collect();
+                if first_line.chars().count() > OUTLINE_LABEL_CHARS {
+                    label.push('…');
+                }
+                outline.push(OutlineEntry {
+                    message_index: index,
+                    label,
+                    tool_count: None,
+                });
+            } else if message.is_tool() {
+                match outline.last_mut() {
+                    Some(OutlineEntry {
+                        tool_count: Some(count),
+                        ..
+                    }) if previous_was_tool => *count += 1,
+                    _ => outline.push(OutlineEntry {
+                        message_index: index,
+                        label: message.content.lines().next().unwrap_or_default().to_string(),
+                        tool_count: Some(1),
+                    }),
+                }
+            }
+            // Thinking between tool calls doesn't split a group
+            previous_was_tool = message.is_tool() || (previous_was_tool && message.is_thinking());
+        }
+        outline
+    }
+
+    /// Approximate position of a message in the scrolled chat, from 0.0 (top)
+    /// to 1.0 (bottom), estimated from message lengths since the real layout
+    /// isn't known outside the view.
+    pub fn scroll_fraction(&self, index: usize) -> f32 {
+        let heights: Vec<f32> = self
+            .messages
+            .iter()
+            .map(|m| {
+                if m.is_tool() || m.is_thinking() {
+                    // Collapsed by default
+                    return 48.0;
+                }
+                let lines: usize = m.content.lines().map(|l| 1 + l.len() / 90).sum();
+                40.0 + 20.0 * lines.max(1) as f32
+            })
+            .collect();
+        let total: f32 = heights.iter().sum();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let before: f32 = heights.iter().take(index).sum();
+        (before / total).clamp(0.0, 1.0)
+    }
+
       /// Clears all messages from the session.
     pub fn clear_messages(&mut self) {
         self.messages.clear();
         self.ai_buffer.clear();
+        self.compacted_history = None;
         self.is_streaming = false;
     }
 }
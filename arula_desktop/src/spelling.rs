@@ -0,0 +1,102 @@
+//! Misspelled-word highlighting for the chat input
+//!
+//! The input's `text_editor` runs this highlighter over each line and colors
+//! the words the dictionary rejects. iced can't underline editor text, so
+//! misspellings are drawn in the theme's danger color instead. Lines of
+//! ```` ``` ```` fenced code are left alone; whether each line ends inside a
+//! fence is remembered, so an edit re-highlights from the changed line.
+
+use arula_core::utils::spellcheck::Dictionary;
+use iced::advanced::text::highlighter::{self, Highlighter};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Dictionary the highlighter checks against; `None` turns it off
+#[derive(Debug, Clone, Default)]
+pub struct SpellSettings {
+    pub dictionary: Option<Arc<Dictionary>>,
+}
+
+impl PartialEq for SpellSettings {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.dictionary, &other.dictionary) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A word the dictionary doesn't know
+#[derive(Debug, Clone, Copy)]
+pub struct Misspelled;
+
+pub struct SpellHighlighter {
+    dictionary: Option<Arc<Dictionary>>,
+    current_line: usize,
+    /// Whether each line highlighted so far ends inside a code fence
+    in_fence: Vec<bool>,
+}
+
+impl Highlighter for SpellHighlighter {
+    type Settings = SpellSettings;
+    type Highlight = Misspelled;
+    type Iterator<'a> = std::vec::IntoIter<(Range<usize>, Misspelled)>;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Self {
+            dictionary: settings.dictionary.clone(),
+            current_line: 0,
+            in_fence: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        self.dictionary = new_settings.dictionary.clone();
+        self.current_line = 0;
+        self.in_fence.clear();
+    }
+
+    fn change_line(&mut self, line: usize) {
+        // Fence state up to the changed line still holds
+        self.current_line = line.min(self.in_fence.len());
+        self.in_fence.truncate(self.current_line);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let starts_in_fence = self
+            .current_line
+            .checked_sub(1)
+            .and_then(|previous| self.in_fence.get(previous))
+            .copied()
+            .unwrap_or(false);
+        let is_fence = line.trim_start().starts_with("```");
+        self.in_fence.truncate(self.current_line);
+        self.in_fence.push(starts_in_fence != is_fence);
+        self.current_line += 1;
+
+        if starts_in_fence || is_fence {
+            return Vec::new().into_iter();
+        }
+        self.dictionary
+            .as_ref()
+            .map(|dictionary| dictionary.misspelled(line))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|range| (range, Misspelled))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.current_line
+    }
+}
+
+/// How a misspelled word is drawn
+pub fn misspelled_format(_: &Misspelled, theme: &iced::Theme) -> highlighter::Format<iced::Font> {
+    highlighter::Format {
+        color: Some(theme.palette().danger),
+        font: None,
+    }
+}
@@ -1,5 +1,5 @@
 use crate::theme::PaletteColors;
-use iced::widget::text_input;
+use iced::widget::{text_editor, text_input};
 use iced::{Background, Border, Color, Theme};
 
 /// Creates a styled text input with accent border on focus.
@@ -34,18 +34,17 @@ pub fn input_style(
     }
 }
 
-/// Creates a transparent input style for the chat input area.
+/// Creates a transparent editor style for the chat input area.
 pub fn chat_input_style(
     palette: PaletteColors,
-) -> impl Fn(&Theme, text_input::Status) -> text_input::Style + Clone {
-    move |_, _status| text_input::Style {
+) -> impl Fn(&Theme, text_editor::Status) -> text_editor::Style + Clone {
+    move |_, _status| text_editor::Style {
         background: Background::Color(Color::TRANSPARENT),
         border: Border {
             color: Color::TRANSPARENT,
             width: 0.0,
             radius: 0.0.into(),
         },
-        icon: palette.muted,
         placeholder: palette.muted,
         value: palette.text,
         selection: palette.accent,
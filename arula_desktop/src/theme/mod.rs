@@ -2,4 +2,4 @@ mod app_theme;
 mod palette;
 
 pub use app_theme::{app_theme, app_theme_with_mode, app_theme_with_palette};
-pub use palette::{palette, palette_from_mode, PaletteColors, ThemeMode};
+pub use palette::{palette, palette_from_mode, system_prefers_dark, PaletteColors, ThemeMode};
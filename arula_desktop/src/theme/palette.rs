@@ -3,6 +3,8 @@ use iced::Color;
 /// Theme mode enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ThemeMode {
+    /// Follows the OS light/dark preference
+    Auto,
     Light,
     #[default]
     Dark,
@@ -12,6 +14,7 @@ pub enum ThemeMode {
 impl ThemeMode {
     pub fn name(&self) -> &'static str {
         match self {
+            ThemeMode::Auto => "Auto",
             ThemeMode::Light => "Light",
             ThemeMode::Dark => "Dark",
             ThemeMode::Black => "Black",
@@ -20,6 +23,7 @@ impl ThemeMode {
 
     pub fn from_name(name: &str) -> Option<Self> {
         match name.to_lowercase().as_str() {
+            "auto" => Some(ThemeMode::Auto),
             "light" => Some(ThemeMode::Light),
             "dark" => Some(ThemeMode::Dark),
             "black" => Some(ThemeMode::Black),
@@ -28,8 +32,22 @@ impl ThemeMode {
     }
 
     pub fn all() -> Vec<&'static str> {
-        vec!["Light", "Dark", "Black"]
+        vec!["Auto", "Light", "Dark", "Black"]
     }
+
+    /// The mode to draw with: `Auto` becomes Light or Dark per the OS preference
+    pub fn resolve(self, system_dark: bool) -> Self {
+        match self {
+            ThemeMode::Auto if system_dark => ThemeMode::Dark,
+            ThemeMode::Auto => ThemeMode::Light,
+            mode => mode,
+        }
+    }
+}
+
+/// Whether the OS prefers a dark color scheme; dark when it can't be detected
+pub fn system_prefers_dark() -> bool {
+    !matches!(dark_light::detect(), Ok(dark_light::Mode::Light))
 }
 
 /// Core color palette for the Arula Neon theme.
@@ -110,7 +128,8 @@ impl PaletteColors {
     pub fn from_theme_mode(mode: ThemeMode) -> Self {
         match mode {
             ThemeMode::Light => Self::light(),
-            ThemeMode::Dark => Self::dark(),
+            // Callers resolve Auto first; dark is the fallback
+            ThemeMode::Auto | ThemeMode::Dark => Self::dark(),
             ThemeMode::Black => Self::black(),
         }
     }